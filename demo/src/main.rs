@@ -8,7 +8,8 @@ use log::info;
 
 use fidget::{
     context::Context,
-    eval::{BulkEvaluator, MathShape},
+    eval::{BulkEvaluator, IntervalMode, MathShape, ShapeVars, Vars},
+    render::ColorMode,
 };
 
 /// Simple test program
@@ -49,6 +50,10 @@ enum Command {
         /// Render using an isometric perspective
         #[clap(long)]
         isometric: bool,
+
+        /// Color by curvature instead of surface normals (implies `--color`)
+        #[clap(long, value_enum)]
+        curvature: Option<Curvature>,
     },
     Mesh {
         #[clap(flatten)]
@@ -64,6 +69,12 @@ enum EvalMode {
     Jit,
 }
 
+#[derive(ValueEnum, Clone, Copy)]
+enum Curvature {
+    Mean,
+    Gaussian,
+}
+
 #[derive(Parser)]
 struct ImageSettings {
     /// Name of a `.png` file to write
@@ -85,6 +96,14 @@ struct ImageSettings {
     /// Image size
     #[clap(short, long, default_value_t = 128)]
     size: u32,
+
+    /// Use affine arithmetic (instead of interval arithmetic) to find bounds
+    #[clap(long)]
+    affine: bool,
+
+    /// Variable binding, as `name=value` (may be repeated)
+    #[clap(long = "var", value_parser = parse_var)]
+    vars: Vec<(String, f32)>,
 }
 
 #[derive(Parser)]
@@ -97,7 +116,7 @@ struct MeshSettings {
     #[clap(long)]
     max_depth: Option<u8>,
 
-    /// Name of a `.stl` file to write
+    /// Name of a `.stl`, `.obj`, or `.ply` file to write
     #[clap(short, long)]
     out: Option<PathBuf>,
 
@@ -112,25 +131,66 @@ struct MeshSettings {
     /// Number of times to render (for benchmarking)
     #[clap(short = 'N', default_value_t = 1)]
     n: usize,
+
+    /// Use affine arithmetic (instead of interval arithmetic) to find bounds
+    #[clap(long)]
+    affine: bool,
+
+    /// Variable binding, as `name=value` (may be repeated)
+    #[clap(long = "var", value_parser = parse_var)]
+    vars: Vec<(String, f32)>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////
-fn run3d<S: fidget::eval::Shape>(
+
+fn parse_var(s: &str) -> Result<(String, f32), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected `name=value`, got `{s}`"))?;
+    let value = value.parse().map_err(|e| format!("invalid value: {e}"))?;
+    Ok((name.to_owned(), value))
+}
+
+/// Binds variables by name, returning values in the shape's variable order
+fn bind_vars<S: ShapeVars>(shape: &S, vars: &[(String, f32)]) -> Vec<f32> {
+    let mut out = Vars::new(shape.vars());
+    out.bind(vars.iter().map(|(k, v)| (k.as_str(), *v)))
+        .to_vec()
+}
+
+fn interval_mode(affine: bool) -> IntervalMode {
+    if affine {
+        IntervalMode::Affine
+    } else {
+        IntervalMode::Interval
+    }
+}
+
+fn run3d<S: fidget::eval::Shape + ShapeVars>(
     shape: S,
     settings: &ImageSettings,
     isometric: bool,
     mode_color: bool,
+    curvature: Option<Curvature>,
 ) -> Vec<u8> {
     let mut mat = nalgebra::Transform3::identity();
     if !isometric {
         *mat.matrix_mut().get_mut((3, 2)).unwrap() = 0.3;
     }
+    let vars = bind_vars(&shape, &settings.vars);
     let cfg = fidget::render::RenderConfig {
         image_size: settings.size as usize,
         tile_sizes: S::tile_sizes_3d().to_vec(),
         threads: settings.threads,
 
         mat,
+        interval_mode: interval_mode(settings.affine),
+        color_mode: match curvature {
+            None => ColorMode::Normals,
+            Some(Curvature::Mean) => ColorMode::MeanCurvature,
+            Some(Curvature::Gaussian) => ColorMode::GaussianCurvature,
+        },
+        vars: &vars,
     };
 
     let mut depth = vec![];
@@ -139,7 +199,7 @@ fn run3d<S: fidget::eval::Shape>(
         (depth, color) = fidget::render::render3d(shape.clone(), &cfg);
     }
 
-    let out = if mode_color {
+    let out = if mode_color || curvature.is_some() {
         depth
             .into_iter()
             .zip(color)
@@ -171,12 +231,13 @@ fn run3d<S: fidget::eval::Shape>(
 
 ////////////////////////////////////////////////////////////////////////////////
 
-fn run2d<S: fidget::eval::Shape>(
+fn run2d<S: fidget::eval::Shape + ShapeVars>(
     shape: S,
     settings: &ImageSettings,
     brute: bool,
     sdf: bool,
 ) -> Vec<u8> {
+    let vars = bind_vars(&shape, &settings.vars);
     if brute {
         let tape = shape.float_slice_tape(Default::default());
         let mut eval = S::new_float_slice_eval();
@@ -194,8 +255,8 @@ fn run2d<S: fidget::eval::Shape>(
                 }
             }
             let zs = vec![0.0; xs.len()];
-            let values = eval.eval(&tape, &xs, &ys, &zs, &[]).unwrap();
-            out = values.iter().map(|v| *v <= 0.0).collect();
+            let values = eval.eval(&tape, &xs, &ys, &zs, &vars).unwrap();
+            out = values[0].iter().map(|v| *v <= 0.0).collect();
         }
         // Convert from Vec<bool> to an image
         out.into_iter()
@@ -209,6 +270,9 @@ fn run2d<S: fidget::eval::Shape>(
             threads: settings.threads,
 
             mat: nalgebra::Transform2::identity(),
+            interval_mode: interval_mode(settings.affine),
+            color_mode: ColorMode::default(),
+            vars: &vars,
         };
         if sdf {
             let mut image = vec![];
@@ -242,22 +306,26 @@ fn run2d<S: fidget::eval::Shape>(
 
 ////////////////////////////////////////////////////////////////////////////////
 
-fn run_mesh<S: fidget::eval::Shape>(
+fn run_mesh<S: fidget::eval::Shape + ShapeVars>(
     shape: S,
     settings: &MeshSettings,
-) -> fidget::mesh::Mesh {
+) -> Result<fidget::mesh::Mesh> {
     let mut mesh = fidget::mesh::Mesh::new();
+    let vars = bind_vars(&shape, &settings.vars);
 
     for _ in 0..settings.n {
         let settings = fidget::mesh::Settings {
             threads: settings.threads,
             min_depth: settings.depth,
             max_depth: settings.max_depth.unwrap_or(settings.depth),
+            bounds: Default::default(),
+            interval_mode: interval_mode(settings.affine),
+            vars: &vars,
         };
-        let octree = fidget::mesh::Octree::build(&shape, settings);
+        let octree = fidget::mesh::Octree::build(&shape, settings)?;
         mesh = octree.walk_dual(settings);
     }
-    mesh
+    Ok(mesh)
 }
 
 fn main() -> Result<()> {
@@ -312,6 +380,7 @@ fn main() -> Result<()> {
             settings,
             color,
             isometric,
+            curvature,
         } => {
             let start = Instant::now();
             let buffer = match settings.eval {
@@ -319,12 +388,12 @@ fn main() -> Result<()> {
                 EvalMode::Jit => {
                     let shape = fidget::jit::JitShape::new(&ctx, root)?;
                     info!("Built shape in {:?}", start.elapsed());
-                    run3d(shape, &settings, isometric, color)
+                    run3d(shape, &settings, isometric, color, curvature)
                 }
                 EvalMode::Vm => {
                     let shape = fidget::vm::VmShape::new(&ctx, root)?;
                     info!("Built shape in {:?}", start.elapsed());
-                    run3d(shape, &settings, isometric, color)
+                    run3d(shape, &settings, isometric, color, curvature)
                 }
             };
             info!(
@@ -353,12 +422,12 @@ fn main() -> Result<()> {
                 EvalMode::Jit => {
                     let shape = fidget::jit::JitShape::new(&ctx, root)?;
                     info!("Built shape in {:?}", start.elapsed());
-                    run_mesh(shape, &settings)
+                    run_mesh(shape, &settings)?
                 }
                 EvalMode::Vm => {
                     let shape = fidget::vm::VmShape::new(&ctx, root)?;
                     info!("Built shape in {:?}", start.elapsed());
-                    run_mesh(shape, &settings)
+                    run_mesh(shape, &settings)?
                 }
             };
             info!(
@@ -369,8 +438,13 @@ fn main() -> Result<()> {
                     / (settings.n as f64)
             );
             if let Some(out) = settings.out {
-                info!("Writing STL to {out:?}");
-                mesh.write_stl(&mut std::fs::File::create(out)?)?;
+                info!("Writing mesh to {out:?}");
+                let mut f = std::fs::File::create(&out)?;
+                match out.extension().and_then(|e| e.to_str()) {
+                    Some("obj") => mesh.write_obj(&mut f)?,
+                    Some("ply") => mesh.write_ply(&mut f)?,
+                    _ => mesh.write_stl(&mut f)?,
+                }
             }
         }
     }
@@ -18,18 +18,21 @@ pub fn colonnade_octree_thread_sweep(c: &mut Criterion) {
             min_depth: 6,
             max_depth: 6,
             threads,
+            bounds: Default::default(),
+            interval_mode: Default::default(),
+            vars: &[],
         };
         #[cfg(feature = "jit")]
         group.bench_function(BenchmarkId::new("jit", threads), move |b| {
             b.iter(|| {
                 let cfg = *cfg;
-                black_box(fidget::mesh::Octree::build(shape_jit, cfg))
+                black_box(fidget::mesh::Octree::build(shape_jit, cfg).unwrap())
             })
         });
         group.bench_function(BenchmarkId::new("vm", threads), move |b| {
             b.iter(|| {
                 let cfg = *cfg;
-                black_box(fidget::mesh::Octree::build(shape_vm, cfg))
+                black_box(fidget::mesh::Octree::build(shape_vm, cfg).unwrap())
             })
         });
     }
@@ -42,8 +45,11 @@ pub fn colonnade_mesh(c: &mut Criterion) {
         min_depth: 8,
         max_depth: 8,
         threads: 8,
+        bounds: Default::default(),
+        interval_mode: Default::default(),
+        vars: &[],
     };
-    let octree = &fidget::mesh::Octree::build(shape_vm, cfg);
+    let octree = &fidget::mesh::Octree::build(shape_vm, cfg).unwrap();
 
     let mut group =
         c.benchmark_group("speed vs threads (colonnade, meshing) (depth 8)");
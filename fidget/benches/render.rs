@@ -21,6 +21,9 @@ pub fn prospero_size_sweep(c: &mut Criterion) {
             tile_sizes: fidget::vm::VmShape::tile_sizes_2d().to_vec(),
             threads: 8,
             mat: nalgebra::Transform2::identity(),
+            interval_mode: Default::default(),
+            color_mode: Default::default(),
+            vars: &[],
         };
         group.bench_function(BenchmarkId::new("vm", size), move |b| {
             b.iter(|| {
@@ -40,6 +43,9 @@ pub fn prospero_size_sweep(c: &mut Criterion) {
                 tile_sizes: fidget::jit::JitShape::tile_sizes_2d().to_vec(),
                 threads: 8,
                 mat: nalgebra::Transform2::identity(),
+                interval_mode: Default::default(),
+                color_mode: Default::default(),
+                vars: &[],
             };
             group.bench_function(BenchmarkId::new("jit", size), move |b| {
                 b.iter(|| {
@@ -70,6 +76,9 @@ pub fn prospero_thread_sweep(c: &mut Criterion) {
             tile_sizes: fidget::vm::VmShape::tile_sizes_2d().to_vec(),
             threads,
             mat: nalgebra::Transform2::identity(),
+            interval_mode: Default::default(),
+            color_mode: Default::default(),
+            vars: &[],
         };
         group.bench_function(BenchmarkId::new("vm", threads), move |b| {
             b.iter(|| {
@@ -88,6 +97,9 @@ pub fn prospero_thread_sweep(c: &mut Criterion) {
                 tile_sizes: fidget::jit::JitShape::tile_sizes_2d().to_vec(),
                 threads,
                 mat: nalgebra::Transform2::identity(),
+                interval_mode: Default::default(),
+                color_mode: Default::default(),
+                vars: &[],
             };
             group.bench_function(BenchmarkId::new("jit", threads), move |b| {
                 b.iter(|| {
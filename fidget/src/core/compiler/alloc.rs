@@ -1,4 +1,7 @@
-use crate::compiler::{Lru, RegOp, RegTape, SsaOp};
+use crate::{
+    compiler::{Lru, RegOp, RegTape, SsaOp},
+    context::Node,
+};
 
 #[derive(Copy, Clone, Debug)]
 enum Allocation {
@@ -48,9 +51,13 @@ pub struct RegisterAllocator<const N: usize> {
 impl<const N: usize> RegisterAllocator<N> {
     /// Builds a new `RegisterAllocator`.
     ///
-    /// Upon construction, SSA register 0 is bound to local register 0; you
-    /// would be well advised to use it as the output of your function.
-    pub fn new(size: usize) -> Self {
+    /// Upon construction, SSA registers `0..outputs` are bound to local
+    /// registers `0..outputs`; you would be well advised to use them as the
+    /// outputs of your function.
+    ///
+    /// # Panics
+    /// If `outputs` is 0 or greater than `N`
+    pub fn new(size: usize, outputs: usize) -> Self {
         assert!(N <= u8::MAX as usize);
         let mut out = Self {
             allocations: vec![UNASSIGNED; size],
@@ -63,7 +70,7 @@ impl<const N: usize> RegisterAllocator<N> {
 
             out: RegTape::empty(),
         };
-        out.bind_register(0, 0);
+        out.bind_outputs(outputs);
         out
     }
 
@@ -83,7 +90,10 @@ impl<const N: usize> RegisterAllocator<N> {
     }
 
     /// Resets internal state, reusing allocations and the provided tape
-    pub fn reset(&mut self, size: usize, tape: RegTape) {
+    ///
+    /// As in [`RegisterAllocator::new`], SSA registers `0..outputs` are bound
+    /// to local registers `0..outputs`.
+    pub fn reset(&mut self, size: usize, outputs: usize, tape: RegTape) {
         assert!(self.out.is_empty());
         self.allocations.fill(UNASSIGNED);
         self.allocations.resize(size, UNASSIGNED);
@@ -93,7 +103,16 @@ impl<const N: usize> RegisterAllocator<N> {
         self.spare_memory.clear();
         self.out = tape;
         self.out.reset();
-        self.bind_register(0, 0);
+        self.bind_outputs(outputs);
+    }
+
+    /// Binds the first `outputs` SSA registers to the matching local registers
+    fn bind_outputs(&mut self, outputs: usize) {
+        assert!(outputs > 0 && outputs <= N);
+        for i in 0..outputs {
+            self.bind_register(i as u32, i as u8);
+        }
+        self.out.slot_count = outputs as u32;
     }
 
     /// Claims the internal `Vec<RegOp>`, leaving it empty
@@ -251,6 +270,10 @@ impl<const N: usize> RegisterAllocator<N> {
             SsaOp::RecipReg(out, arg) => (out, arg, RegOp::RecipReg),
             SsaOp::SqrtReg(out, arg) => (out, arg, RegOp::SqrtReg),
             SsaOp::SquareReg(out, arg) => (out, arg, RegOp::SquareReg),
+            SsaOp::FloorReg(out, arg) => (out, arg, RegOp::FloorReg),
+            SsaOp::CeilReg(out, arg) => (out, arg, RegOp::CeilReg),
+            SsaOp::RoundReg(out, arg) => (out, arg, RegOp::RoundReg),
+            SsaOp::NotReg(out, arg) => (out, arg, RegOp::NotReg),
             SsaOp::SinReg(out, arg) => (out, arg, RegOp::SinReg),
             SsaOp::CosReg(out, arg) => (out, arg, RegOp::CosReg),
             SsaOp::TanReg(out, arg) => (out, arg, RegOp::TanReg),
@@ -278,6 +301,10 @@ impl<const N: usize> RegisterAllocator<N> {
             | SsaOp::RecipReg(..)
             | SsaOp::SqrtReg(..)
             | SsaOp::SquareReg(..)
+            | SsaOp::FloorReg(..)
+            | SsaOp::CeilReg(..)
+            | SsaOp::RoundReg(..)
+            | SsaOp::NotReg(..)
             | SsaOp::CopyReg(..)
             | SsaOp::SinReg(..)
             | SsaOp::CosReg(..)
@@ -295,17 +322,41 @@ impl<const N: usize> RegisterAllocator<N> {
             | SsaOp::DivRegImm(..)
             | SsaOp::DivImmReg(..)
             | SsaOp::MinRegImm(..)
-            | SsaOp::MaxRegImm(..) => self.op_reg_imm(op),
+            | SsaOp::MaxRegImm(..)
+            | SsaOp::AtanRegImm(..)
+            | SsaOp::AtanImmReg(..)
+            | SsaOp::ModRegImm(..)
+            | SsaOp::ModImmReg(..)
+            | SsaOp::CompareRegImm(..)
+            | SsaOp::CompareImmReg(..)
+            | SsaOp::AndRegImm(..)
+            | SsaOp::OrRegImm(..) => self.op_reg_imm(op),
 
             SsaOp::AddRegReg(..)
             | SsaOp::SubRegReg(..)
             | SsaOp::MulRegReg(..)
             | SsaOp::DivRegReg(..)
             | SsaOp::MinRegReg(..)
-            | SsaOp::MaxRegReg(..) => self.op_reg_reg(op),
+            | SsaOp::MaxRegReg(..)
+            | SsaOp::AtanRegReg(..)
+            | SsaOp::ModRegReg(..)
+            | SsaOp::CompareRegReg(..)
+            | SsaOp::AndRegReg(..)
+            | SsaOp::OrRegReg(..) => self.op_reg_reg(op),
         }
     }
 
+    /// Allocates the next operation in the tape, recording `node` as the
+    /// source of every [`RegOp`] that it generates
+    ///
+    /// This builds a [source map](RegTape::source_map) for the output tape;
+    /// if it's used, it must be used for every operation.
+    #[inline]
+    pub fn op_with_source(&mut self, op: SsaOp, node: Node) {
+        self.op(op);
+        self.out.attribute(node);
+    }
+
     fn push_store(&mut self, reg: u8, mem: u32) {
         self.out.push(RegOp::Store(reg, mem));
         self.release_mem(mem);
@@ -485,6 +536,19 @@ impl<const N: usize> RegisterAllocator<N> {
             SsaOp::MaxRegReg(out, lhs, rhs) => {
                 (out, lhs, rhs, RegOp::MaxRegReg)
             }
+            SsaOp::AtanRegReg(out, lhs, rhs) => {
+                (out, lhs, rhs, RegOp::AtanRegReg)
+            }
+            SsaOp::ModRegReg(out, lhs, rhs) => {
+                (out, lhs, rhs, RegOp::ModRegReg)
+            }
+            SsaOp::CompareRegReg(out, lhs, rhs) => {
+                (out, lhs, rhs, RegOp::CompareRegReg)
+            }
+            SsaOp::AndRegReg(out, lhs, rhs) => {
+                (out, lhs, rhs, RegOp::AndRegReg)
+            }
+            SsaOp::OrRegReg(out, lhs, rhs) => (out, lhs, rhs, RegOp::OrRegReg),
             _ => panic!("Bad opcode: {op:?}"),
         };
         let r_x = self.get_out_reg(out);
@@ -571,7 +635,7 @@ impl<const N: usize> RegisterAllocator<N> {
     /// [`RegOp`], pushing it to the internal tape.
     #[inline(always)]
     fn op_reg_imm(&mut self, op: SsaOp) {
-        let (out, arg, imm, op): (_, _, _, fn(u8, u8, f32) -> RegOp) = match op
+        let (out, arg, imm, op): (_, _, _, fn(u8, u8, f64) -> RegOp) = match op
         {
             SsaOp::AddRegImm(out, arg, imm) => {
                 (out, arg, imm, RegOp::AddRegImm)
@@ -597,6 +661,28 @@ impl<const N: usize> RegisterAllocator<N> {
             SsaOp::MaxRegImm(out, arg, imm) => {
                 (out, arg, imm, RegOp::MaxRegImm)
             }
+            SsaOp::AtanRegImm(out, arg, imm) => {
+                (out, arg, imm, RegOp::AtanRegImm)
+            }
+            SsaOp::AtanImmReg(out, arg, imm) => {
+                (out, arg, imm, RegOp::AtanImmReg)
+            }
+            SsaOp::ModRegImm(out, arg, imm) => {
+                (out, arg, imm, RegOp::ModRegImm)
+            }
+            SsaOp::ModImmReg(out, arg, imm) => {
+                (out, arg, imm, RegOp::ModImmReg)
+            }
+            SsaOp::CompareRegImm(out, arg, imm) => {
+                (out, arg, imm, RegOp::CompareRegImm)
+            }
+            SsaOp::CompareImmReg(out, arg, imm) => {
+                (out, arg, imm, RegOp::CompareImmReg)
+            }
+            SsaOp::AndRegImm(out, arg, imm) => {
+                (out, arg, imm, RegOp::AndRegImm)
+            }
+            SsaOp::OrRegImm(out, arg, imm) => (out, arg, imm, RegOp::OrRegImm),
             _ => panic!("Bad opcode: {op:?}"),
         };
         self.op_reg_fn(out, arg, |out, arg| op(out, arg, imm));
@@ -612,7 +698,7 @@ impl<const N: usize> RegisterAllocator<N> {
     /// Pushes a [`CopyImm`](crate::compiler::RegOp::CopyImm) operation to the
     /// tape
     #[inline(always)]
-    fn op_copy_imm(&mut self, out: u32, imm: f32) {
+    fn op_copy_imm(&mut self, out: u32, imm: f64) {
         self.op_out_only(out, |out| RegOp::CopyImm(out, imm));
     }
 
@@ -0,0 +1,868 @@
+//! Source code generation from an [`SsaTape`]
+use crate::{
+    compiler::{SsaOp, SsaTape},
+    context::{BinaryOpcode, UnaryOpcode},
+    Error,
+};
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// Target language for [`SsaTape::to_source`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Language {
+    /// C99, using single-precision functions from `math.h`
+    C,
+    /// GLSL (version 3.30 and later, or GLSL ES 3.00 and later)
+    Glsl,
+    /// WebGPU Shading Language
+    Wgsl,
+}
+
+/// Argument to an operation, which is either a temporary or an immediate
+#[derive(Copy, Clone)]
+enum Arg {
+    Reg(u32),
+    Imm(f64),
+}
+
+/// Right-hand side of an assignment in generated code
+enum Expr {
+    Input(u32),
+    Var(u32),
+    Copy(Arg),
+    Unary(UnaryOpcode, Arg),
+    Binary(BinaryOpcode, Arg, Arg),
+}
+
+/// Helper functions which are emitted (once) before the main function
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+enum Helper {
+    Min,
+    Max,
+    Compare,
+    Mod,
+    Round,
+    /// Runtime `NaN` (WGSL only)
+    Nan,
+    /// Runtime infinity (WGSL only)
+    Inf,
+}
+
+impl SsaTape {
+    /// Generates a self-contained function which evaluates this tape
+    ///
+    /// The function takes `x`, `y`, and `z` as single-precision floats, along
+    /// with a `vars` array if the tape uses any variables (in the order given
+    /// by [`SsaTape::vars`], which is also listed in a comment with quoted
+    /// variable names).  Each SSA
+    /// slot becomes a temporary, so shared subexpressions are only evaluated
+    /// once.  Helper functions are prefixed with `name`, so the output of
+    /// multiple calls can be concatenated into a single file.
+    ///
+    /// Results match the VM's point evaluator, including its handling of `NaN`
+    /// in `min`, `max`, and `compare`.  Shader languages don't guarantee
+    /// IEEE-compliant `NaN` handling, and their transcendental functions may
+    /// be less precise.
+    ///
+    /// Returns `Error::TooManyOutputs` for multi-output tapes, or
+    /// `Error::BadIdentifier` if `name` isn't a valid identifier.
+    ///
+    /// ```
+    /// use fidget::{compiler::{Language, SsaTape}, Context};
+    ///
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let r = ctx.max(x, y)?;
+    /// let tape = SsaTape::new(&ctx, r)?;
+    ///
+    /// let src = tape.to_source(Language::C, "shape")?;
+    /// assert!(src.contains("float shape(float x, float y, float z)"));
+    /// let src = tape.to_source(Language::Wgsl, "shape")?;
+    /// assert!(src.contains("fn shape(x: f32, y: f32, z: f32) -> f32"));
+    /// # Ok::<(), fidget::Error>(())
+    /// ```
+    pub fn to_source(
+        &self,
+        lang: Language,
+        name: &str,
+    ) -> Result<String, Error> {
+        let mut chars = name.chars();
+        if !chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(Error::BadIdentifier(name.to_owned()));
+        }
+        match self.output_count {
+            0 => return Err(Error::NoOutputs),
+            1 => (),
+            n => return Err(Error::TooManyOutputs(n, 1)),
+        }
+
+        let mut helpers = BTreeSet::new();
+        let mut body = String::new();
+        for op in self.tape.iter().rev() {
+            let (out, expr) = decode(op);
+            let expr = lang.expr(expr, name, &mut helpers);
+            match lang {
+                Language::C | Language::Glsl => {
+                    writeln!(body, "    float v{out} = {expr};").unwrap()
+                }
+                Language::Wgsl => {
+                    writeln!(body, "    let v{out}: f32 = {expr};").unwrap()
+                }
+            }
+        }
+
+        let mut out = String::new();
+        if lang == Language::C {
+            out += "#include <math.h>\n\n";
+        }
+        for h in helpers {
+            out += &lang.helper(h, name);
+            out += "\n";
+        }
+
+        let mut vars = self.vars.iter().collect::<Vec<_>>();
+        vars.sort_by_key(|(_, i)| **i);
+        for (v, i) in &vars {
+            // Names are quoted and escaped, so they can't end the comment
+            writeln!(out, "// vars[{i}] = {v:?}").unwrap();
+        }
+        let n = vars.len();
+        match lang {
+            Language::C => {
+                write!(out, "float {name}(float x, float y, float z").unwrap();
+                if n > 0 {
+                    out += ", const float* vars";
+                }
+                out += ") {\n";
+            }
+            Language::Glsl => {
+                write!(out, "float {name}(float x, float y, float z").unwrap();
+                if n > 0 {
+                    write!(out, ", float vars[{n}]").unwrap();
+                }
+                out += ") {\n";
+            }
+            Language::Wgsl => {
+                write!(out, "fn {name}(x: f32, y: f32, z: f32").unwrap();
+                if n > 0 {
+                    write!(out, ", vars: array<f32, {n}>").unwrap();
+                }
+                out += ") -> f32 {\n";
+            }
+        }
+        out += &body;
+        out += "    return v0;\n}\n";
+        Ok(out)
+    }
+}
+
+/// Splits an [`SsaOp`] into its output slot and expression
+fn decode(op: &SsaOp) -> (u32, Expr) {
+    use Arg::{Imm, Reg};
+    use BinaryOpcode as B;
+    use UnaryOpcode as U;
+    match *op {
+        SsaOp::Input(out, i) => (out, Expr::Input(i)),
+        SsaOp::Var(out, i) => (out, Expr::Var(i)),
+        SsaOp::CopyImm(out, imm) => (out, Expr::Copy(Imm(imm))),
+        SsaOp::CopyReg(out, a) => (out, Expr::Copy(Reg(a))),
+
+        SsaOp::NegReg(out, a) => (out, Expr::Unary(U::Neg, Reg(a))),
+        SsaOp::AbsReg(out, a) => (out, Expr::Unary(U::Abs, Reg(a))),
+        SsaOp::RecipReg(out, a) => (out, Expr::Unary(U::Recip, Reg(a))),
+        SsaOp::SqrtReg(out, a) => (out, Expr::Unary(U::Sqrt, Reg(a))),
+        SsaOp::SquareReg(out, a) => (out, Expr::Unary(U::Square, Reg(a))),
+        SsaOp::FloorReg(out, a) => (out, Expr::Unary(U::Floor, Reg(a))),
+        SsaOp::CeilReg(out, a) => (out, Expr::Unary(U::Ceil, Reg(a))),
+        SsaOp::RoundReg(out, a) => (out, Expr::Unary(U::Round, Reg(a))),
+        SsaOp::NotReg(out, a) => (out, Expr::Unary(U::Not, Reg(a))),
+        SsaOp::SinReg(out, a) => (out, Expr::Unary(U::Sin, Reg(a))),
+        SsaOp::CosReg(out, a) => (out, Expr::Unary(U::Cos, Reg(a))),
+        SsaOp::TanReg(out, a) => (out, Expr::Unary(U::Tan, Reg(a))),
+        SsaOp::AsinReg(out, a) => (out, Expr::Unary(U::Asin, Reg(a))),
+        SsaOp::AcosReg(out, a) => (out, Expr::Unary(U::Acos, Reg(a))),
+        SsaOp::AtanReg(out, a) => (out, Expr::Unary(U::Atan, Reg(a))),
+        SsaOp::ExpReg(out, a) => (out, Expr::Unary(U::Exp, Reg(a))),
+        SsaOp::LnReg(out, a) => (out, Expr::Unary(U::Ln, Reg(a))),
+
+        SsaOp::AddRegImm(out, a, i) => {
+            (out, Expr::Binary(B::Add, Reg(a), Imm(i)))
+        }
+        SsaOp::MulRegImm(out, a, i) => {
+            (out, Expr::Binary(B::Mul, Reg(a), Imm(i)))
+        }
+        SsaOp::DivRegImm(out, a, i) => {
+            (out, Expr::Binary(B::Div, Reg(a), Imm(i)))
+        }
+        SsaOp::DivImmReg(out, a, i) => {
+            (out, Expr::Binary(B::Div, Imm(i), Reg(a)))
+        }
+        SsaOp::SubRegImm(out, a, i) => {
+            (out, Expr::Binary(B::Sub, Reg(a), Imm(i)))
+        }
+        SsaOp::SubImmReg(out, a, i) => {
+            (out, Expr::Binary(B::Sub, Imm(i), Reg(a)))
+        }
+        SsaOp::MinRegImm(out, a, i) => {
+            (out, Expr::Binary(B::Min, Reg(a), Imm(i)))
+        }
+        SsaOp::MaxRegImm(out, a, i) => {
+            (out, Expr::Binary(B::Max, Reg(a), Imm(i)))
+        }
+        SsaOp::AtanRegImm(out, a, i) => {
+            (out, Expr::Binary(B::Atan, Reg(a), Imm(i)))
+        }
+        SsaOp::AtanImmReg(out, a, i) => {
+            (out, Expr::Binary(B::Atan, Imm(i), Reg(a)))
+        }
+        SsaOp::ModRegImm(out, a, i) => {
+            (out, Expr::Binary(B::Mod, Reg(a), Imm(i)))
+        }
+        SsaOp::ModImmReg(out, a, i) => {
+            (out, Expr::Binary(B::Mod, Imm(i), Reg(a)))
+        }
+        SsaOp::CompareRegImm(out, a, i) => {
+            (out, Expr::Binary(B::Compare, Reg(a), Imm(i)))
+        }
+        SsaOp::CompareImmReg(out, a, i) => {
+            (out, Expr::Binary(B::Compare, Imm(i), Reg(a)))
+        }
+        SsaOp::AndRegImm(out, a, i) => {
+            (out, Expr::Binary(B::And, Reg(a), Imm(i)))
+        }
+        SsaOp::OrRegImm(out, a, i) => {
+            (out, Expr::Binary(B::Or, Reg(a), Imm(i)))
+        }
+
+        SsaOp::AddRegReg(out, a, b) => {
+            (out, Expr::Binary(B::Add, Reg(a), Reg(b)))
+        }
+        SsaOp::MulRegReg(out, a, b) => {
+            (out, Expr::Binary(B::Mul, Reg(a), Reg(b)))
+        }
+        SsaOp::DivRegReg(out, a, b) => {
+            (out, Expr::Binary(B::Div, Reg(a), Reg(b)))
+        }
+        SsaOp::SubRegReg(out, a, b) => {
+            (out, Expr::Binary(B::Sub, Reg(a), Reg(b)))
+        }
+        SsaOp::MinRegReg(out, a, b) => {
+            (out, Expr::Binary(B::Min, Reg(a), Reg(b)))
+        }
+        SsaOp::MaxRegReg(out, a, b) => {
+            (out, Expr::Binary(B::Max, Reg(a), Reg(b)))
+        }
+        SsaOp::AtanRegReg(out, a, b) => {
+            (out, Expr::Binary(B::Atan, Reg(a), Reg(b)))
+        }
+        SsaOp::ModRegReg(out, a, b) => {
+            (out, Expr::Binary(B::Mod, Reg(a), Reg(b)))
+        }
+        SsaOp::CompareRegReg(out, a, b) => {
+            (out, Expr::Binary(B::Compare, Reg(a), Reg(b)))
+        }
+        SsaOp::AndRegReg(out, a, b) => {
+            (out, Expr::Binary(B::And, Reg(a), Reg(b)))
+        }
+        SsaOp::OrRegReg(out, a, b) => {
+            (out, Expr::Binary(B::Or, Reg(a), Reg(b)))
+        }
+    }
+}
+
+impl Language {
+    /// Returns a single-precision literal
+    ///
+    /// In WGSL, a constant expression which evaluates to `NaN` or infinity is
+    /// a shader-creation error, so those values are returned by helper
+    /// functions instead (which must be recorded by the caller).
+    fn literal(self, v: f64, prefix: &str) -> String {
+        let f = v as f32;
+        let s = if f.is_nan() {
+            match self {
+                Language::C => "NAN".to_owned(),
+                Language::Glsl => "uintBitsToFloat(0x7fc00000u)".to_owned(),
+                Language::Wgsl => format!("{prefix}_nan()"),
+            }
+        } else if f.is_infinite() {
+            let sign = if f < 0.0 { "-" } else { "" };
+            match self {
+                Language::C => format!("{sign}INFINITY"),
+                Language::Glsl => format!("{sign}uintBitsToFloat(0x7f800000u)"),
+                Language::Wgsl => format!("{sign}{prefix}_inf()"),
+            }
+        } else if self == Language::C {
+            format!("{f:?}f")
+        } else {
+            format!("{f:?}")
+        };
+        if s.starts_with('-') {
+            format!("({s})")
+        } else {
+            s
+        }
+    }
+
+    fn arg(self, a: Arg, prefix: &str) -> String {
+        match a {
+            Arg::Reg(i) => format!("v{i}"),
+            Arg::Imm(v) => self.literal(v, prefix),
+        }
+    }
+
+    /// Returns the name of a standard math function
+    fn function(self, op: UnaryOpcode) -> &'static str {
+        use UnaryOpcode as U;
+        match (self, op) {
+            (Language::C, U::Abs) => "fabsf",
+            (Language::C, U::Sqrt) => "sqrtf",
+            (Language::C, U::Floor) => "floorf",
+            (Language::C, U::Ceil) => "ceilf",
+            (Language::C, U::Round) => "roundf",
+            (Language::C, U::Sin) => "sinf",
+            (Language::C, U::Cos) => "cosf",
+            (Language::C, U::Tan) => "tanf",
+            (Language::C, U::Asin) => "asinf",
+            (Language::C, U::Acos) => "acosf",
+            (Language::C, U::Atan) => "atanf",
+            (Language::C, U::Exp) => "expf",
+            (Language::C, U::Ln) => "logf",
+            (_, U::Ln) => "log",
+            (_, op) => op.name(),
+        }
+    }
+
+    /// Builds an expression, recording any helper functions that it uses
+    fn expr(
+        self,
+        expr: Expr,
+        prefix: &str,
+        helpers: &mut BTreeSet<Helper>,
+    ) -> String {
+        if self == Language::Wgsl {
+            let imms = match expr {
+                Expr::Copy(a) | Expr::Unary(_, a) => [Some(a), None],
+                Expr::Binary(_, a, b) => [Some(a), Some(b)],
+                Expr::Input(..) | Expr::Var(..) => [None, None],
+            };
+            for a in imms.into_iter().flatten() {
+                if let Arg::Imm(v) = a {
+                    let f = v as f32;
+                    if f.is_nan() {
+                        helpers.insert(Helper::Nan);
+                    } else if f.is_infinite() {
+                        helpers.insert(Helper::Inf);
+                    }
+                }
+            }
+        }
+        let zero = self.literal(0.0, prefix);
+        let one = self.literal(1.0, prefix);
+        let mut call = |h: Helper, args: &[String]| {
+            helpers.insert(h);
+            let name = format!("{h:?}").to_lowercase();
+            format!("{prefix}_{name}({})", args.join(", "))
+        };
+        match expr {
+            Expr::Input(i) => ["x", "y", "z"][i as usize].to_owned(),
+            Expr::Var(i) => format!("vars[{i}]"),
+            Expr::Copy(a) => self.arg(a, prefix),
+            Expr::Unary(op, a) => {
+                let a = self.arg(a, prefix);
+                match op {
+                    UnaryOpcode::Neg => format!("-{a}"),
+                    UnaryOpcode::Recip => format!("{one} / {a}"),
+                    UnaryOpcode::Square => format!("{a} * {a}"),
+                    UnaryOpcode::Not => match self {
+                        Language::C | Language::Glsl => {
+                            format!("({a} == {zero} ? {one} : {zero})")
+                        }
+                        Language::Wgsl => {
+                            format!("select({zero}, {one}, {a} == {zero})")
+                        }
+                    },
+                    UnaryOpcode::Round if self != Language::C => {
+                        call(Helper::Round, &[a])
+                    }
+                    op => format!("{}({a})", self.function(op)),
+                }
+            }
+            Expr::Binary(op, a, b) => {
+                let a = self.arg(a, prefix);
+                let b = self.arg(b, prefix);
+                match op {
+                    BinaryOpcode::Add => format!("{a} + {b}"),
+                    BinaryOpcode::Sub => format!("{a} - {b}"),
+                    BinaryOpcode::Mul => format!("{a} * {b}"),
+                    BinaryOpcode::Div => format!("{a} / {b}"),
+                    BinaryOpcode::Atan => match self {
+                        Language::C => format!("atan2f({a}, {b})"),
+                        Language::Glsl => format!("atan({a}, {b})"),
+                        Language::Wgsl => format!("atan2({a}, {b})"),
+                    },
+                    BinaryOpcode::Min => call(Helper::Min, &[a, b]),
+                    BinaryOpcode::Max => call(Helper::Max, &[a, b]),
+                    BinaryOpcode::Mod => call(Helper::Mod, &[a, b]),
+                    BinaryOpcode::Compare => call(Helper::Compare, &[a, b]),
+                    BinaryOpcode::And | BinaryOpcode::Or => {
+                        let cmp =
+                            if op == BinaryOpcode::And { "==" } else { "!=" };
+                        match self {
+                            Language::C | Language::Glsl => {
+                                format!("({a} {cmp} {zero} ? {a} : {b})")
+                            }
+                            Language::Wgsl => {
+                                format!("select({b}, {a}, {a} {cmp} {zero})")
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the definition of a helper function
+    fn helper(self, h: Helper, prefix: &str) -> String {
+        let name = format!("{prefix}_{}", format!("{h:?}").to_lowercase());
+        let (args, body) = match (self, h) {
+            (Language::C, Helper::Min | Helper::Max) => {
+                let cmp = if h == Helper::Min { "<" } else { ">" };
+                (
+                    2,
+                    format!(
+                        "return a {cmp} b ? a : b {cmp} a ? b\n        \
+                         : (isnan(a) || isnan(b)) ? NAN : b;"
+                    ),
+                )
+            }
+            (Language::C, Helper::Compare) => (
+                2,
+                "return a < b ? -1.0f : a > b ? 1.0f : a == b ? 0.0f : NAN;"
+                    .to_owned(),
+            ),
+            (Language::C, Helper::Mod) => (
+                2,
+                "float r = fmodf(a, b);\n    \
+                 return r < 0.0f ? r + fabsf(b) : r;"
+                    .to_owned(),
+            ),
+            (Language::Glsl, Helper::Min | Helper::Max) => {
+                let cmp = if h == Helper::Min { "<" } else { ">" };
+                (
+                    2,
+                    format!(
+                        "return a {cmp} b ? a : b {cmp} a ? b\n        \
+                         : (isnan(a) || isnan(b)) ? a + b : b;"
+                    ),
+                )
+            }
+            (Language::Glsl, Helper::Compare) => (
+                2,
+                "return a < b ? -1.0 : a > b ? 1.0 : a == b ? 0.0 : a + b;"
+                    .to_owned(),
+            ),
+            (Language::Glsl, Helper::Mod) => (
+                2,
+                "float r = a - b * trunc(a / b);\n    \
+                 return r < 0.0 ? r + abs(b) : r;"
+                    .to_owned(),
+            ),
+            (Language::Glsl, Helper::Round) => (
+                1,
+                "float t = trunc(a);\n    \
+                 return abs(a - t) >= 0.5 ? t + sign(a) : t;"
+                    .to_owned(),
+            ),
+            (Language::Wgsl, Helper::Min | Helper::Max) => {
+                let cmp = if h == Helper::Min { "<" } else { ">" };
+                (
+                    2,
+                    format!(
+                        "if (a {cmp} b) {{ return a; }}\n    \
+                         if (b {cmp} a) {{ return b; }}\n    \
+                         if (a != a || b != b) {{ return a + b; }}\n    \
+                         return b;"
+                    ),
+                )
+            }
+            (Language::Wgsl, Helper::Compare) => (
+                2,
+                "if (a < b) { return -1.0; }\n    \
+                 if (a > b) { return 1.0; }\n    \
+                 if (a == b) { return 0.0; }\n    \
+                 return a + b;"
+                    .to_owned(),
+            ),
+            (Language::Wgsl, Helper::Mod) => (
+                2,
+                "let r = a - b * trunc(a / b);\n    \
+                 return select(r, r + abs(b), r < 0.0);"
+                    .to_owned(),
+            ),
+            (Language::Wgsl, Helper::Round) => (
+                1,
+                "let t = trunc(a);\n    \
+                 return select(t, t + sign(a), abs(a - t) >= 0.5);"
+                    .to_owned(),
+            ),
+            (Language::Wgsl, Helper::Nan | Helper::Inf) => {
+                let bits = if h == Helper::Nan {
+                    "0x7fc00000u"
+                } else {
+                    "0x7f800000u"
+                };
+                (
+                    0,
+                    format!(
+                        "// Loading from a `var` keeps this out of constant \
+                         evaluation\n    \
+                         var bits = {bits};\n    \
+                         return bitcast<f32>(bits);"
+                    ),
+                )
+            }
+            (Language::C, Helper::Round) => {
+                unreachable!("C uses `roundf` instead")
+            }
+            (Language::C | Language::Glsl, Helper::Nan | Helper::Inf) => {
+                unreachable!("only WGSL uses helpers for non-finite values")
+            }
+        };
+        let params = ["a", "b"][..args]
+            .iter()
+            .map(|p| match self {
+                Language::C => format!("float {p}"),
+                Language::Glsl => format!("float {p}"),
+                Language::Wgsl => format!("{p}: f32"),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        match self {
+            Language::C => format!(
+                "static inline float {name}({params}) {{\n    {body}\n}}\n"
+            ),
+            Language::Glsl => {
+                format!("float {name}({params}) {{\n    {body}\n}}\n")
+            }
+            Language::Wgsl => {
+                format!("fn {name}({params}) -> f32 {{\n    {body}\n}}\n")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{Context, Node},
+        eval::{EzShape, MathShape, Shape, TracingEvaluator},
+        vm::VmShape,
+    };
+
+    #[test]
+    fn test_codegen_errors() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let tape = SsaTape::new(&ctx, x).unwrap();
+        for name in ["", "1abc", "a-b", "a b"] {
+            assert!(matches!(
+                tape.to_source(Language::C, name),
+                Err(Error::BadIdentifier(..))
+            ));
+        }
+        assert!(tape.to_source(Language::C, "_a1").is_ok());
+
+        let y = ctx.y();
+        let tape = SsaTape::new_multi(&ctx, &[x, y]).unwrap();
+        assert!(matches!(
+            tape.to_source(Language::Glsl, "f"),
+            Err(Error::TooManyOutputs(2, 1))
+        ));
+    }
+
+    #[test]
+    fn test_codegen_shaders() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let a = ctx.var("a").unwrap();
+        let m = ctx.min(x, y).unwrap();
+        let r = ctx.round(m).unwrap();
+        let out = ctx.sub(r, a).unwrap();
+        let out = ctx.add(out, -1.5).unwrap();
+        let tape = SsaTape::new(&ctx, out).unwrap();
+
+        let glsl = tape.to_source(Language::Glsl, "shape").unwrap();
+        assert!(glsl.contains("// vars[0] = \"a\"\n"));
+        assert!(glsl
+            .contains("float shape(float x, float y, float z, float vars[1])"));
+        assert!(glsl.contains("float shape_min(float a, float b)"));
+        assert!(glsl.contains("float shape_round(float a)"));
+        assert!(glsl.contains(" = vars[0];\n"));
+        assert!(glsl.contains(" + (-1.5);\n"));
+        assert!(glsl.ends_with("    return v0;\n}\n"));
+
+        let wgsl = tape.to_source(Language::Wgsl, "shape").unwrap();
+        assert!(wgsl.contains(
+            "fn shape(x: f32, y: f32, z: f32, vars: array<f32, 1>) -> f32"
+        ));
+        assert!(wgsl.contains("fn shape_min(a: f32, b: f32) -> f32"));
+        assert!(wgsl.contains("fn shape_round(a: f32) -> f32"));
+        assert!(wgsl.contains("    let v0: f32 = "));
+
+        let c = tape.to_source(Language::C, "shape").unwrap();
+        assert!(c.starts_with("#include <math.h>\n"));
+        assert!(!c.contains("shape_round"));
+        assert!(c.contains(" = roundf("));
+        assert!(c.contains(" + (-1.5f);\n"));
+    }
+
+    #[test]
+    fn test_codegen_var_names() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let a = ctx.var("a\nfloat evil = 1.0; \\").unwrap();
+        let b = ctx.var("b\r\u{2028}*/").unwrap();
+        let out = ctx.add(x, a).unwrap();
+        let out = ctx.add(out, b).unwrap();
+        let tape = SsaTape::new(&ctx, out).unwrap();
+
+        for lang in [Language::C, Language::Glsl, Language::Wgsl] {
+            let src = tape.to_source(lang, "shape").unwrap();
+            assert!(!src.contains("\nfloat evil"));
+            assert!(!src.contains('\r'));
+            assert!(!src.contains('\u{2028}'));
+            assert!(
+                src.contains("// vars[0] = \"a\\nfloat evil = 1.0; \\\\\"\n")
+            );
+            assert!(src.contains("// vars[1] = \"b\\r\\u{2028}*/\"\n"));
+        }
+    }
+
+    /// Builds shaders for every opcode (plus non-finite constants), then checks
+    /// them with `naga`'s parser and validator
+    #[test]
+    fn test_codegen_validate_shaders() {
+        let mut ctx = Context::new();
+        let mut nodes = all_ops(&mut ctx);
+        let x = ctx.x();
+        for f in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            nodes.push(ctx.constant(f));
+            nodes.push(ctx.add(x, f).unwrap());
+            nodes.push(ctx.min(f, x).unwrap());
+        }
+
+        let mut wgsl = String::new();
+        let mut glsl = "#version 450\n".to_owned();
+        let mut calls = String::new();
+        for (i, n) in nodes.iter().enumerate() {
+            let tape = SsaTape::new(&ctx, *n).unwrap();
+            let name = format!("shape{i}");
+            wgsl += &tape.to_source(Language::Wgsl, &name).unwrap();
+            glsl += &tape.to_source(Language::Glsl, &name).unwrap();
+            let vars = if tape_vars(&ctx, *n) { ", vars" } else { "" };
+            writeln!(calls, "    s += {name}(0.5, 1.0, 2.0{vars});").unwrap();
+        }
+        assert!(wgsl.contains("_nan() -> f32"));
+        assert!(wgsl.contains("_inf() -> f32"));
+        assert!(!wgsl.contains("bitcast<f32>(0x"));
+
+        // GLSL requires an entry point, which calls every function
+        glsl += "layout(location = 0) out vec4 color;\n";
+        glsl += "void main() {\n";
+        glsl += "    float vars[1] = float[1](0.75);\n";
+        glsl += "    float s = 0.0;\n";
+        glsl += &calls;
+        glsl += "    color = vec4(s);\n}\n";
+
+        let validate = |module: naga::Module, src: &str| {
+            let r = naga::valid::Validator::new(
+                naga::valid::ValidationFlags::all(),
+                naga::valid::Capabilities::all(),
+            )
+            .validate(&module);
+            if let Err(e) = r {
+                panic!("{}", e.emit_to_string(src));
+            }
+        };
+        let module = naga::front::wgsl::parse_str(&wgsl)
+            .unwrap_or_else(|e| panic!("{}", e.emit_to_string(&wgsl)));
+        validate(module, &wgsl);
+        let module = naga::front::glsl::Frontend::default()
+            .parse(
+                &naga::front::glsl::Options::from(naga::ShaderStage::Fragment),
+                &glsl,
+            )
+            .unwrap_or_else(|e| panic!("{}", e.emit_to_string(&glsl)));
+        validate(module, &glsl);
+    }
+
+    /// Builds a set of nodes which (between them) use every opcode, with both
+    /// register and immediate arguments, along with a variable `v`
+    fn all_ops(ctx: &mut Context) -> Vec<Node> {
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let v = ctx.var("v").unwrap();
+
+        // a = x / 2 + y - v, b = y * z
+        let a = ctx.mul(x, 0.5).unwrap();
+        let a = ctx.add(a, y).unwrap();
+        let a = ctx.sub(a, v).unwrap();
+        let b = ctx.mul(y, z).unwrap();
+        let k1 = ctx.constant(1.5);
+        let k2 = ctx.constant(-2.5);
+
+        let mut nodes = vec![];
+        for f in [
+            Context::neg::<Node>,
+            Context::abs,
+            Context::recip,
+            Context::sqrt,
+            Context::square,
+            Context::floor,
+            Context::ceil,
+            Context::round,
+            Context::not,
+            Context::sin,
+            Context::cos,
+            Context::tan,
+            Context::asin,
+            Context::acos,
+            Context::atan,
+            Context::exp,
+            Context::ln,
+        ] {
+            nodes.push(f(ctx, a).unwrap());
+        }
+        for f in [
+            Context::add::<Node, Node>,
+            Context::sub,
+            Context::mul,
+            Context::div,
+            Context::atan2,
+            Context::min,
+            Context::max,
+            Context::modulo,
+            Context::compare,
+            Context::and,
+            Context::or,
+        ] {
+            nodes.push(f(ctx, a, b).unwrap());
+            nodes.push(f(ctx, a, k1).unwrap());
+            nodes.push(f(ctx, k2, b).unwrap());
+        }
+        let c = ctx.constant(3.0);
+        nodes.push(c);
+        let s = ctx.sin(b).unwrap();
+        let s = ctx.mul(s, a).unwrap();
+        nodes.push(ctx.max(s, a).unwrap());
+        nodes
+    }
+
+    /// Compiles C functions with the system compiler, then compares the
+    /// results to the VM's point evaluator.
+    #[test]
+    fn test_codegen_c() {
+        let mut ctx = Context::new();
+        let nodes = all_ops(&mut ctx);
+
+        let vals = [-2.0, -0.5, 0.0, 0.25, 1.0, 3.0];
+        let mut points = vec![];
+        for x in vals {
+            for y in vals {
+                for z in [-1.0, 0.0, 2.0] {
+                    points.push([x, y, z]);
+                }
+            }
+        }
+        let var_value = 0.75;
+
+        let mut src = "#include <stdio.h>\n\n".to_owned();
+        for (i, n) in nodes.iter().enumerate() {
+            let tape = SsaTape::new(&ctx, *n).unwrap();
+            src += &tape.to_source(Language::C, &format!("f{i}")).unwrap();
+            src += "\n";
+        }
+        src += "int main(void) {\n";
+        writeln!(src, "    const float vars[1] = {{ {var_value:?}f }};")
+            .unwrap();
+        for [x, y, z] in &points {
+            for (i, n) in nodes.iter().enumerate() {
+                let vars = if tape_vars(&ctx, *n) { ", vars" } else { "" };
+                writeln!(
+                    src,
+                    "    printf(\"%.9g\\n\", f{i}({x:?}f, {y:?}f, {z:?}f{vars}));"
+                )
+                .unwrap();
+            }
+        }
+        src += "    return 0;\n}\n";
+
+        let dir = std::env::temp_dir()
+            .join(format!("fidget-codegen-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let c_file = dir.join("test.c");
+        let exe = dir.join("test");
+        std::fs::write(&c_file, &src).unwrap();
+        let Ok(status) = std::process::Command::new("cc")
+            .arg("-std=c99")
+            .arg(&c_file)
+            .arg("-o")
+            .arg(&exe)
+            .arg("-lm")
+            .status()
+        else {
+            eprintln!("skipping C codegen test: no C compiler found");
+            return;
+        };
+        assert!(status.success(), "failed to compile generated code");
+        let output = std::process::Command::new(&exe).output().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let mut lines = stdout.lines();
+
+        let mut eval = VmShape::new_point_eval();
+        for [x, y, z] in &points {
+            for (i, n) in nodes.iter().enumerate() {
+                let shape = VmShape::new(&ctx, *n).unwrap();
+                let tape = shape.ez_point_tape();
+                let vars = if tape_vars(&ctx, *n) {
+                    vec![var_value]
+                } else {
+                    vec![]
+                };
+                let (expected, _) =
+                    eval.eval(&tape, *x, *y, *z, &vars).unwrap();
+                let line = lines.next().unwrap();
+                let actual: f32 = if line.contains("nan") {
+                    f32::NAN
+                } else {
+                    line.parse().unwrap()
+                };
+                assert!(
+                    actual == expected
+                        || (actual.is_nan() && expected.is_nan())
+                        || (actual - expected).abs()
+                            <= 1e-6 * expected.abs().max(1.0),
+                    "mismatch in f{i} at ({x}, {y}, {z}): \
+                     {actual} != {expected}"
+                );
+            }
+        }
+        assert!(lines.next().is_none());
+    }
+
+    /// Checks whether the given node uses any variables
+    fn tape_vars(ctx: &Context, n: Node) -> bool {
+        !SsaTape::new(ctx, n).unwrap().vars.is_empty()
+    }
+}
@@ -6,10 +6,15 @@
 //!   set of operations in single-static assignment form.
 //! - The [`SsaTape`] goes through [register allocation](RegisterAllocator) and
 //!   becomes a [`RegTape`], planned with some number of registers.
+//! - Alternatively, an [`SsaTape`] can be [converted into source
+//!   code](SsaTape::to_source) in one of several [`Language`]s.
 
 mod alloc;
 pub use alloc::RegisterAllocator;
 
+mod codegen;
+pub use codegen::Language;
+
 mod op;
 
 mod lru;
@@ -27,7 +32,7 @@ mod test {
     use super::*;
     #[test]
     fn test_vm_op_size() {
-        assert_eq!(std::mem::size_of::<RegOp>(), 8);
-        assert_eq!(std::mem::size_of::<SsaOp>(), 16);
+        assert_eq!(std::mem::size_of::<RegOp>(), 16);
+        assert_eq!(std::mem::size_of::<SsaOp>(), 24);
     }
 }
@@ -42,6 +42,18 @@ macro_rules! opcodes {
             #[doc = "Square the given register"]
             SquareReg($t, $t),
 
+            #[doc = "Rounds the given register down to the nearest integer"]
+            FloorReg($t, $t),
+
+            #[doc = "Rounds the given register up to the nearest integer"]
+            CeilReg($t, $t),
+
+            #[doc = "Rounds the given register to the nearest integer"]
+            RoundReg($t, $t),
+
+            #[doc = "Logical not: returns 1 if the register is 0, and 0 otherwise"]
+            NotReg($t, $t),
+
             #[doc = "Computes the sine of the given register (in radians)"]
             SinReg($t, $t),
 
@@ -70,21 +82,37 @@ macro_rules! opcodes {
             CopyReg($t, $t),
 
             #[doc = "Add a register and an immediate"]
-            AddRegImm($t, $t, f32),
+            AddRegImm($t, $t, f64),
             #[doc = "Multiply a register and an immediate"]
-            MulRegImm($t, $t, f32),
+            MulRegImm($t, $t, f64),
             #[doc = "Divides a register and an immediate"]
-            DivRegImm($t, $t, f32),
+            DivRegImm($t, $t, f64),
             #[doc = "Divides an immediate by a register"]
-            DivImmReg($t, $t, f32),
+            DivImmReg($t, $t, f64),
             #[doc = "Subtract a register from an immediate"]
-            SubImmReg($t, $t, f32),
+            SubImmReg($t, $t, f64),
             #[doc = "Subtract an immediate from a register"]
-            SubRegImm($t, $t, f32),
+            SubRegImm($t, $t, f64),
             #[doc = "Compute the minimum of a register and an immediate"]
-            MinRegImm($t, $t, f32),
+            MinRegImm($t, $t, f64),
             #[doc = "Compute the maximum of a register and an immediate"]
-            MaxRegImm($t, $t, f32),
+            MaxRegImm($t, $t, f64),
+            #[doc = "Compute atan2 of a register (y) and an immediate (x)"]
+            AtanRegImm($t, $t, f64),
+            #[doc = "Compute atan2 of an immediate (y) and a register (x)"]
+            AtanImmReg($t, $t, f64),
+            #[doc = "Compute the Euclidean remainder of a register and an immediate"]
+            ModRegImm($t, $t, f64),
+            #[doc = "Compute the Euclidean remainder of an immediate and a register"]
+            ModImmReg($t, $t, f64),
+            #[doc = "Compares a register with an immediate (-1, 0, or 1)"]
+            CompareRegImm($t, $t, f64),
+            #[doc = "Compares an immediate with a register (-1, 0, or 1)"]
+            CompareImmReg($t, $t, f64),
+            #[doc = "Logical and of a register and an immediate"]
+            AndRegImm($t, $t, f64),
+            #[doc = "Logical or of a register and an immediate"]
+            OrRegImm($t, $t, f64),
 
             #[doc = "Add two registers"]
             AddRegReg($t, $t, $t),
@@ -98,9 +126,19 @@ macro_rules! opcodes {
             MinRegReg($t, $t, $t),
             #[doc = "Take the maximum of two registers"]
             MaxRegReg($t, $t, $t),
+            #[doc = "Compute atan2 of two registers (y, x)"]
+            AtanRegReg($t, $t, $t),
+            #[doc = "Compute the Euclidean remainder of two registers"]
+            ModRegReg($t, $t, $t),
+            #[doc = "Compares two registers (-1, 0, or 1)"]
+            CompareRegReg($t, $t, $t),
+            #[doc = "Logical and of two registers"]
+            AndRegReg($t, $t, $t),
+            #[doc = "Logical or of two registers"]
+            OrRegReg($t, $t, $t),
 
             #[doc = "Copy an immediate to a register"]
-            CopyImm($t, f32),
+            CopyImm($t, f64),
 
             $(
                 $(#[$($a)*])*
@@ -137,6 +175,10 @@ impl SsaOp {
             | SsaOp::RecipReg(out, ..)
             | SsaOp::SqrtReg(out, ..)
             | SsaOp::SquareReg(out, ..)
+            | SsaOp::FloorReg(out, ..)
+            | SsaOp::CeilReg(out, ..)
+            | SsaOp::RoundReg(out, ..)
+            | SsaOp::NotReg(out, ..)
             | SsaOp::CopyReg(out, ..)
             | SsaOp::SinReg(out, ..)
             | SsaOp::CosReg(out, ..)
@@ -159,7 +201,20 @@ impl SsaOp {
             | SsaOp::MinRegImm(out, ..)
             | SsaOp::MaxRegImm(out, ..)
             | SsaOp::MinRegReg(out, ..)
-            | SsaOp::MaxRegReg(out, ..) => *out,
+            | SsaOp::MaxRegReg(out, ..)
+            | SsaOp::AtanRegImm(out, ..)
+            | SsaOp::AtanImmReg(out, ..)
+            | SsaOp::AtanRegReg(out, ..)
+            | SsaOp::ModRegImm(out, ..)
+            | SsaOp::ModImmReg(out, ..)
+            | SsaOp::ModRegReg(out, ..)
+            | SsaOp::CompareRegImm(out, ..)
+            | SsaOp::CompareImmReg(out, ..)
+            | SsaOp::CompareRegReg(out, ..)
+            | SsaOp::AndRegImm(out, ..)
+            | SsaOp::AndRegReg(out, ..)
+            | SsaOp::OrRegImm(out, ..)
+            | SsaOp::OrRegReg(out, ..) => *out,
         }
     }
     /// Returns true if the given opcode is associated with a choice
@@ -173,6 +228,10 @@ impl SsaOp {
             | SsaOp::RecipReg(..)
             | SsaOp::SqrtReg(..)
             | SsaOp::SquareReg(..)
+            | SsaOp::FloorReg(..)
+            | SsaOp::CeilReg(..)
+            | SsaOp::RoundReg(..)
+            | SsaOp::NotReg(..)
             | SsaOp::CopyReg(..)
             | SsaOp::SinReg(..)
             | SsaOp::CosReg(..)
@@ -191,11 +250,24 @@ impl SsaOp {
             | SsaOp::SubRegReg(..)
             | SsaOp::DivRegReg(..)
             | SsaOp::DivRegImm(..)
-            | SsaOp::DivImmReg(..) => false,
+            | SsaOp::DivImmReg(..)
+            | SsaOp::AtanRegImm(..)
+            | SsaOp::AtanImmReg(..)
+            | SsaOp::AtanRegReg(..)
+            | SsaOp::ModRegImm(..)
+            | SsaOp::ModImmReg(..)
+            | SsaOp::ModRegReg(..)
+            | SsaOp::CompareRegImm(..)
+            | SsaOp::CompareImmReg(..)
+            | SsaOp::CompareRegReg(..) => false,
             SsaOp::MinRegImm(..)
             | SsaOp::MaxRegImm(..)
             | SsaOp::MinRegReg(..)
-            | SsaOp::MaxRegReg(..) => true,
+            | SsaOp::MaxRegReg(..)
+            | SsaOp::AndRegImm(..)
+            | SsaOp::AndRegReg(..)
+            | SsaOp::OrRegImm(..)
+            | SsaOp::OrRegReg(..) => true,
         }
     }
 }
@@ -220,3 +292,61 @@ opcodes!(
         Store(u8, u32),
     }
 );
+
+impl RegOp {
+    /// Returns the output register, or `None` for a [`Store`](RegOp::Store)
+    pub(crate) fn output(&self) -> Option<u8> {
+        match self {
+            RegOp::Input(out, ..)
+            | RegOp::Var(out, ..)
+            | RegOp::CopyImm(out, ..)
+            | RegOp::NegReg(out, ..)
+            | RegOp::AbsReg(out, ..)
+            | RegOp::RecipReg(out, ..)
+            | RegOp::SqrtReg(out, ..)
+            | RegOp::SquareReg(out, ..)
+            | RegOp::FloorReg(out, ..)
+            | RegOp::CeilReg(out, ..)
+            | RegOp::RoundReg(out, ..)
+            | RegOp::NotReg(out, ..)
+            | RegOp::CopyReg(out, ..)
+            | RegOp::SinReg(out, ..)
+            | RegOp::CosReg(out, ..)
+            | RegOp::TanReg(out, ..)
+            | RegOp::AsinReg(out, ..)
+            | RegOp::AcosReg(out, ..)
+            | RegOp::AtanReg(out, ..)
+            | RegOp::ExpReg(out, ..)
+            | RegOp::LnReg(out, ..)
+            | RegOp::AddRegImm(out, ..)
+            | RegOp::MulRegImm(out, ..)
+            | RegOp::DivRegImm(out, ..)
+            | RegOp::DivImmReg(out, ..)
+            | RegOp::SubImmReg(out, ..)
+            | RegOp::SubRegImm(out, ..)
+            | RegOp::AddRegReg(out, ..)
+            | RegOp::MulRegReg(out, ..)
+            | RegOp::DivRegReg(out, ..)
+            | RegOp::SubRegReg(out, ..)
+            | RegOp::MinRegImm(out, ..)
+            | RegOp::MaxRegImm(out, ..)
+            | RegOp::MinRegReg(out, ..)
+            | RegOp::MaxRegReg(out, ..)
+            | RegOp::AtanRegImm(out, ..)
+            | RegOp::AtanImmReg(out, ..)
+            | RegOp::AtanRegReg(out, ..)
+            | RegOp::ModRegImm(out, ..)
+            | RegOp::ModImmReg(out, ..)
+            | RegOp::ModRegReg(out, ..)
+            | RegOp::CompareRegImm(out, ..)
+            | RegOp::CompareImmReg(out, ..)
+            | RegOp::CompareRegReg(out, ..)
+            | RegOp::AndRegImm(out, ..)
+            | RegOp::AndRegReg(out, ..)
+            | RegOp::OrRegImm(out, ..)
+            | RegOp::OrRegReg(out, ..)
+            | RegOp::Load(out, ..) => Some(*out),
+            RegOp::Store(..) => None,
+        }
+    }
+}
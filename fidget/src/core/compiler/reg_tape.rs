@@ -1,5 +1,8 @@
 //! Tape used for evaluation
-use crate::compiler::{RegOp, RegisterAllocator, SsaTape};
+use crate::{
+    compiler::{RegOp, RegisterAllocator, SsaTape},
+    context::Node,
+};
 
 /// Low-level tape for use with the Fidget virtual machine (or to be lowered
 /// further into machine instructions).
@@ -9,6 +12,11 @@ pub struct RegTape {
 
     /// Total allocated slots
     pub(super) slot_count: u32,
+
+    /// Source map from each operation in `tape` to its originating [`Node`]
+    ///
+    /// This is empty if the tape was built without a source map.
+    source: Vec<Node>,
 }
 
 impl RegTape {
@@ -18,10 +26,23 @@ impl RegTape {
     /// to use [`VmData::simplify`](crate::vm::VmData::simplify), which
     /// simultaneously simplifies **and** performs register allocation in a
     /// single pass.
+    ///
+    /// If the SSA tape has a [source map](SsaTape::source_map), then it is
+    /// carried through to this tape.
     pub fn new<const N: usize>(ssa: &SsaTape) -> Self {
-        let mut alloc = RegisterAllocator::<N>::new(ssa.len());
-        for &op in ssa.iter() {
-            alloc.op(op)
+        let mut alloc =
+            RegisterAllocator::<N>::new(ssa.len(), ssa.output_count);
+        match &ssa.source_map {
+            Some(source) => {
+                for (&op, &node) in ssa.iter().zip(source) {
+                    alloc.op_with_source(op, node)
+                }
+            }
+            None => {
+                for &op in ssa.iter() {
+                    alloc.op(op)
+                }
+            }
         }
         alloc.finalize()
     }
@@ -31,6 +52,7 @@ impl RegTape {
         Self {
             tape: vec![],
             slot_count: 1,
+            source: vec![],
         }
     }
 
@@ -38,6 +60,7 @@ impl RegTape {
     pub fn reset(&mut self) {
         self.tape.clear();
         self.slot_count = 1;
+        self.source.clear();
     }
     /// Returns the number of unique register and memory locations that are used
     /// by this tape.
@@ -63,10 +86,29 @@ impl RegTape {
     pub fn iter(&self) -> std::slice::Iter<'_, RegOp> {
         self.into_iter()
     }
+    /// Returns the originating [`Node`] for each operation, in the same order
+    /// as [`RegTape::iter`]
+    ///
+    /// Register allocation may add `Load` and `Store` operations, which are
+    /// attributed to the same node as the operation that required them.
+    ///
+    /// Returns `None` if the tape was built without a source map.
+    pub fn source_map(&self) -> Option<&[Node]> {
+        if self.source.is_empty() {
+            None
+        } else {
+            Some(&self.source)
+        }
+    }
     #[inline]
     pub(crate) fn push(&mut self, op: RegOp) {
         self.tape.push(op)
     }
+    /// Attributes every operation that doesn't yet have a source to `node`
+    #[inline]
+    pub(crate) fn attribute(&mut self, node: Node) {
+        self.source.resize(self.tape.len(), node)
+    }
 }
 
 impl<'a> IntoIterator for &'a RegTape {
@@ -16,7 +16,7 @@ use std::{
 /// - 4-byte opcode (required)
 /// - 4-byte output register (required)
 /// - 4-byte LHS register
-/// - 4-byte RHS register (or immediate `f32`)
+/// - 4-byte RHS register (or 8-byte immediate `f64`)
 ///
 /// All register addressing is absolute.
 #[derive(Clone, Debug, Default)]
@@ -28,12 +28,24 @@ pub struct SsaTape {
     /// Number of choice operations in the tape
     pub choice_count: usize,
 
+    /// Number of outputs in the tape
+    ///
+    /// Output `i` is always written to SSA slot `i`.
+    pub output_count: usize,
+
     /// Mapping from variable names (in the original [`Context`]) to indexes in
     /// the variable array used during evaluation.
     ///
     /// This is an `Arc` so it can be trivially shared by all of the tape's
     /// descendents, since the variable array order does not change.
     pub vars: Arc<HashMap<String, u32>>,
+
+    /// Optional source map, storing the [`Node`] (in the original
+    /// [`Context`]) that generated each operation in `tape`
+    ///
+    /// This is only populated for tapes built with
+    /// [`SsaTape::new_with_source_map`] (and tapes simplified from them).
+    pub source_map: Option<Vec<Node>>,
 }
 
 impl SsaTape {
@@ -42,55 +54,119 @@ impl SsaTape {
     /// This should always succeed unless the `root` is from a different
     /// `Context`, in which case `Error::BadNode` will be returned.
     pub fn new(ctx: &Context, root: Node) -> Result<Self, Error> {
+        Self::new_multi(ctx, &[root])
+    }
+
+    /// Flattens multiple subtrees of the graph into a single tape
+    ///
+    /// Each root becomes one output of the tape, in the order given; output
+    /// `i` is written to SSA slot `i`.  Subexpressions which are shared between
+    /// roots are only evaluated once.
+    ///
+    /// Returns `Error::NoOutputs` if `roots` is empty, or `Error::BadNode` if
+    /// any root is from a different `Context`.
+    pub fn new_multi(ctx: &Context, roots: &[Node]) -> Result<Self, Error> {
+        Self::build(ctx, roots, false)
+    }
+
+    /// Flattens multiple subtrees of the graph into a single tape, recording
+    /// the originating [`Node`] for each operation in
+    /// [`source_map`](SsaTape::source_map)
+    ///
+    /// Copies into output slots are attributed to the corresponding root.
+    /// Otherwise, this is identical to [`SsaTape::new_multi`].
+    pub fn new_with_source_map(
+        ctx: &Context,
+        roots: &[Node],
+    ) -> Result<Self, Error> {
+        Self::build(ctx, roots, true)
+    }
+
+    fn build(
+        ctx: &Context,
+        roots: &[Node],
+        track: bool,
+    ) -> Result<Self, Error> {
+        if roots.is_empty() {
+            return Err(Error::NoOutputs);
+        }
         let mut mapping = HashMap::new();
         let mut parent_count: HashMap<Node, usize> = HashMap::new();
         let mut var_names = HashMap::new();
-        let mut slot_count = 0;
 
         // Get either a node or constant index
         #[derive(Copy, Clone)]
         enum Slot {
             Reg(u32),
-            Immediate(f32),
+            Immediate(f64),
+        }
+
+        // The first slots are reserved for outputs.  If a root is a constant
+        // or is used by an earlier output, then its output slot is populated
+        // with a copy instead.
+        let mut copies = vec![];
+        for (i, &root) in roots.iter().enumerate() {
+            let i = i as u32;
+            let op = ctx.get_op(root).ok_or(Error::BadNode)?;
+            if matches!(op, Op::Const(..)) || mapping.contains_key(&root) {
+                copies.push((i, root));
+            } else {
+                mapping.insert(root, Slot::Reg(i));
+            }
         }
+        let mut slot_count = roots.len() as u32;
 
         // Accumulate parent counts and declare all nodes
         let mut seen = HashSet::new();
-        let mut todo = vec![root];
+        let mut todo = roots.to_vec();
         while let Some(node) = todo.pop() {
             if !seen.insert(node) {
                 continue;
             }
             let op = ctx.get_op(node).ok_or(Error::BadNode)?;
-            let prev = match op {
+            if matches!(op, Op::Var(..)) {
+                let next_var = var_names.len().try_into().unwrap();
+                var_names.insert(
+                    ctx.var_name(node).unwrap().unwrap().to_string(),
+                    next_var,
+                );
+            }
+            match op {
                 Op::Const(c) => {
-                    mapping.insert(node, Slot::Immediate(c.0 as f32))
+                    mapping.insert(node, Slot::Immediate(c.0));
                 }
                 _ => {
-                    let i = slot_count;
-                    slot_count += 1;
-                    if matches!(op, Op::Var(..)) {
-                        let next_var = var_names.len().try_into().unwrap();
-                        var_names.insert(
-                            ctx.var_name(node).unwrap().unwrap().to_string(),
-                            next_var,
-                        );
-                    }
-                    mapping.insert(node, Slot::Reg(i))
+                    mapping.entry(node).or_insert_with(|| {
+                        let i = slot_count;
+                        slot_count += 1;
+                        Slot::Reg(i)
+                    });
                 }
-            };
-            assert!(prev.is_none());
+            }
             for child in op.iter_children() {
                 *parent_count.entry(child).or_default() += 1;
                 todo.push(child);
             }
         }
 
+        // Copies into output slots are evaluated last, so they go at the
+        // beginning of the (reversed) tape
+        let mut tape = vec![];
+        let mut source = track.then(Vec::new);
+        for (i, root) in copies {
+            tape.push(match mapping[&root] {
+                Slot::Reg(src) => SsaOp::CopyReg(i, src),
+                Slot::Immediate(imm) => SsaOp::CopyImm(i, imm),
+            });
+            if let Some(s) = &mut source {
+                s.push(root);
+            }
+        }
+
         // Now that we've populated our parents, flatten the graph
         let mut seen = HashSet::new();
-        let mut todo = vec![root];
+        let mut todo = roots.to_vec();
         let mut choice_count = 0;
-        let mut tape = vec![];
         while let Some(node) = todo.pop() {
             if *parent_count.get(&node).unwrap_or(&0) > 0 || !seen.insert(node)
             {
@@ -129,7 +205,7 @@ impl SsaTape {
                     let rhs = mapping[rhs];
 
                     type RegFn = fn(u32, u32, u32) -> SsaOp;
-                    type ImmFn = fn(u32, u32, f32) -> SsaOp;
+                    type ImmFn = fn(u32, u32, f64) -> SsaOp;
                     let f: (RegFn, ImmFn, ImmFn) = match op {
                         BinaryOpcode::Add => (
                             SsaOp::AddRegReg,
@@ -151,6 +227,11 @@ impl SsaTape {
                             SsaOp::DivRegImm,
                             SsaOp::DivImmReg,
                         ),
+                        BinaryOpcode::Atan => (
+                            SsaOp::AtanRegReg,
+                            SsaOp::AtanRegImm,
+                            SsaOp::AtanImmReg,
+                        ),
                         BinaryOpcode::Min => (
                             SsaOp::MinRegReg,
                             SsaOp::MinRegImm,
@@ -161,13 +242,44 @@ impl SsaTape {
                             SsaOp::MaxRegImm,
                             SsaOp::MaxRegImm,
                         ),
+                        BinaryOpcode::Mod => (
+                            SsaOp::ModRegReg,
+                            SsaOp::ModRegImm,
+                            SsaOp::ModImmReg,
+                        ),
+                        BinaryOpcode::Compare => (
+                            SsaOp::CompareRegReg,
+                            SsaOp::CompareRegImm,
+                            SsaOp::CompareImmReg,
+                        ),
+                        // `Context::and` and `Context::or` fold constant
+                        // left-hand arguments, but we handle them here anyways
+                        // by picking the appropriate branch.
+                        BinaryOpcode::And => (
+                            SsaOp::AndRegReg,
+                            SsaOp::AndRegImm,
+                            |out, arg, imm| {
+                                if imm == 0.0 {
+                                    SsaOp::CopyImm(out, imm)
+                                } else {
+                                    SsaOp::CopyReg(out, arg)
+                                }
+                            },
+                        ),
+                        BinaryOpcode::Or => (
+                            SsaOp::OrRegReg,
+                            SsaOp::OrRegImm,
+                            |out, arg, imm| {
+                                if imm != 0.0 {
+                                    SsaOp::CopyImm(out, imm)
+                                } else {
+                                    SsaOp::CopyReg(out, arg)
+                                }
+                            },
+                        ),
                     };
 
-                    if matches!(op, BinaryOpcode::Min | BinaryOpcode::Max) {
-                        choice_count += 1;
-                    }
-
-                    match (lhs, rhs) {
+                    let op = match (lhs, rhs) {
                         (Slot::Reg(lhs), Slot::Reg(rhs)) => f.0(i, lhs, rhs),
                         (Slot::Reg(arg), Slot::Immediate(imm)) => {
                             f.1(i, arg, imm)
@@ -178,7 +290,11 @@ impl SsaTape {
                         (Slot::Immediate(..), Slot::Immediate(..)) => {
                             panic!("Cannot handle f(imm, imm)")
                         }
+                    };
+                    if op.has_choice() {
+                        choice_count += 1;
                     }
+                    op
                 }
                 Op::Unary(op, lhs) => {
                     let lhs = match mapping[lhs] {
@@ -193,6 +309,10 @@ impl SsaTape {
                         UnaryOpcode::Recip => SsaOp::RecipReg,
                         UnaryOpcode::Sqrt => SsaOp::SqrtReg,
                         UnaryOpcode::Square => SsaOp::SquareReg,
+                        UnaryOpcode::Floor => SsaOp::FloorReg,
+                        UnaryOpcode::Ceil => SsaOp::CeilReg,
+                        UnaryOpcode::Round => SsaOp::RoundReg,
+                        UnaryOpcode::Not => SsaOp::NotReg,
                         UnaryOpcode::Sin => SsaOp::SinReg,
                         UnaryOpcode::Cos => SsaOp::CosReg,
                         UnaryOpcode::Tan => SsaOp::TanReg,
@@ -206,19 +326,17 @@ impl SsaTape {
                 }
             };
             tape.push(op);
-        }
-
-        // Special case if the Node is a single constant, which isn't usually
-        // recorded in the tape
-        if tape.is_empty() {
-            let c = ctx.const_value(root).unwrap().unwrap() as f32;
-            tape.push(SsaOp::CopyImm(0, c));
+            if let Some(s) = &mut source {
+                s.push(node);
+            }
         }
 
         Ok(SsaTape {
             tape,
             choice_count,
+            output_count: roots.len(),
             vars: Arc::new(var_names),
+            source_map: source,
         })
     }
 
@@ -243,6 +361,10 @@ impl SsaTape {
     pub fn reset(&mut self) {
         self.tape.clear();
         self.choice_count = 0;
+        self.output_count = 0;
+        if let Some(s) = &mut self.source_map {
+            s.clear();
+        }
     }
     /// Pretty-prints the given tape to `stdout`
     pub fn pretty_print(&self) {
@@ -260,6 +382,10 @@ impl SsaTape {
                 | SsaOp::SqrtReg(out, arg)
                 | SsaOp::CopyReg(out, arg)
                 | SsaOp::SquareReg(out, arg)
+                | SsaOp::FloorReg(out, arg)
+                | SsaOp::CeilReg(out, arg)
+                | SsaOp::RoundReg(out, arg)
+                | SsaOp::NotReg(out, arg)
                 | SsaOp::SinReg(out, arg)
                 | SsaOp::CosReg(out, arg)
                 | SsaOp::TanReg(out, arg)
@@ -274,6 +400,10 @@ impl SsaTape {
                         SsaOp::RecipReg(..) => "RECIP",
                         SsaOp::SqrtReg(..) => "SQRT",
                         SsaOp::SquareReg(..) => "SQUARE",
+                        SsaOp::FloorReg(..) => "FLOOR",
+                        SsaOp::CeilReg(..) => "CEIL",
+                        SsaOp::RoundReg(..) => "ROUND",
+                        SsaOp::NotReg(..) => "NOT",
                         SsaOp::SinReg(..) => "SIN",
                         SsaOp::CosReg(..) => "COS",
                         SsaOp::TanReg(..) => "TAN",
@@ -293,7 +423,12 @@ impl SsaTape {
                 | SsaOp::DivRegReg(out, lhs, rhs)
                 | SsaOp::SubRegReg(out, lhs, rhs)
                 | SsaOp::MinRegReg(out, lhs, rhs)
-                | SsaOp::MaxRegReg(out, lhs, rhs) => {
+                | SsaOp::MaxRegReg(out, lhs, rhs)
+                | SsaOp::AtanRegReg(out, lhs, rhs)
+                | SsaOp::ModRegReg(out, lhs, rhs)
+                | SsaOp::CompareRegReg(out, lhs, rhs)
+                | SsaOp::AndRegReg(out, lhs, rhs)
+                | SsaOp::OrRegReg(out, lhs, rhs) => {
                     let op = match op {
                         SsaOp::AddRegReg(..) => "ADD",
                         SsaOp::MulRegReg(..) => "MUL",
@@ -301,6 +436,11 @@ impl SsaTape {
                         SsaOp::SubRegReg(..) => "SUB",
                         SsaOp::MinRegReg(..) => "MIN",
                         SsaOp::MaxRegReg(..) => "MAX",
+                        SsaOp::AtanRegReg(..) => "ATAN2",
+                        SsaOp::ModRegReg(..) => "MOD",
+                        SsaOp::CompareRegReg(..) => "COMPARE",
+                        SsaOp::AndRegReg(..) => "AND",
+                        SsaOp::OrRegReg(..) => "OR",
                         _ => unreachable!(),
                     };
                     println!("${out} = {op} ${lhs} ${rhs}");
@@ -313,7 +453,15 @@ impl SsaTape {
                 | SsaOp::SubImmReg(out, arg, imm)
                 | SsaOp::SubRegImm(out, arg, imm)
                 | SsaOp::MinRegImm(out, arg, imm)
-                | SsaOp::MaxRegImm(out, arg, imm) => {
+                | SsaOp::MaxRegImm(out, arg, imm)
+                | SsaOp::AtanRegImm(out, arg, imm)
+                | SsaOp::AtanImmReg(out, arg, imm)
+                | SsaOp::ModRegImm(out, arg, imm)
+                | SsaOp::ModImmReg(out, arg, imm)
+                | SsaOp::CompareRegImm(out, arg, imm)
+                | SsaOp::CompareImmReg(out, arg, imm)
+                | SsaOp::AndRegImm(out, arg, imm)
+                | SsaOp::OrRegImm(out, arg, imm) => {
                     let (op, swap) = match op {
                         SsaOp::AddRegImm(..) => ("ADD", false),
                         SsaOp::MulRegImm(..) => ("MUL", false),
@@ -323,6 +471,14 @@ impl SsaTape {
                         SsaOp::SubRegImm(..) => ("SUB", false),
                         SsaOp::MinRegImm(..) => ("MIN", false),
                         SsaOp::MaxRegImm(..) => ("MAX", false),
+                        SsaOp::AtanRegImm(..) => ("ATAN2", false),
+                        SsaOp::AtanImmReg(..) => ("ATAN2", true),
+                        SsaOp::ModRegImm(..) => ("MOD", false),
+                        SsaOp::ModImmReg(..) => ("MOD", true),
+                        SsaOp::CompareRegImm(..) => ("COMPARE", false),
+                        SsaOp::CompareImmReg(..) => ("COMPARE", true),
+                        SsaOp::AndRegImm(..) => ("AND", false),
+                        SsaOp::OrRegImm(..) => ("OR", false),
                         _ => unreachable!(),
                     };
                     if swap {
@@ -370,4 +526,28 @@ mod test {
         let tape = SsaTape::new(&ctx, x_squared).unwrap();
         assert_eq!(tape.len(), 2);
     }
+
+    #[test]
+    fn test_multi() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let sum = ctx.add(x, y).unwrap();
+        let scaled = ctx.mul(sum, 2.0).unwrap();
+        let one = ctx.constant(1.0);
+
+        let tape = SsaTape::new_multi(&ctx, &[sum, scaled, x, one, x]).unwrap();
+        assert_eq!(tape.output_count, 5);
+        // X, Y, sum, and scaled are only evaluated once, plus one copy for
+        // the constant output and one for the duplicate X output
+        assert_eq!(tape.len(), 6);
+        let mut outputs: Vec<u32> = tape.iter().map(|op| op.output()).collect();
+        outputs.sort();
+        assert_eq!(outputs, [0, 1, 2, 3, 4, 5]);
+
+        assert!(matches!(
+            SsaTape::new_multi(&ctx, &[]),
+            Err(Error::NoOutputs)
+        ));
+    }
 }
@@ -5,66 +5,95 @@ use crate::{
 };
 use std::{cell::RefCell, rc::Rc};
 
-/// Shareable context used in a [`BoundNode`]
+/// Shareable context used to build [`BoundNode`] expressions
 ///
-/// This is only available for unit testing, because it is less efficient than
-/// manually managing a [`Context`].
-#[derive(Clone, Debug)]
+/// This allows math expressions to be written inline, using operator
+/// overloading:
+/// ```
+/// use fidget::{context::BoundContext, vm::VmShape};
+///
+/// let ctx = BoundContext::new();
+/// let (x, y, _z) = ctx.axes();
+/// let circle = (x.square() + y.square()).sqrt() - 1.0;
+/// assert_eq!(circle.eval(2.0, 0.0, 0.0)?, 1.0);
+///
+/// let shape: VmShape = circle.convert()?;
+/// # Ok::<(), fidget::Error>(())
+/// ```
+///
+/// It's less efficient than manually managing a [`Context`]: each
+/// [`BoundNode`] stores a reference-counted pointer to its context, and every
+/// operation incurs a `RefCell` dynamic borrow.  The inner `Context` is never
+/// exposed, so these borrows are always released before returning to the
+/// caller (and cannot panic).
+#[derive(Clone, Debug, Default)]
 pub struct BoundContext(Rc<RefCell<Context>>);
 
-impl std::ops::Deref for BoundContext {
-    type Target = Rc<RefCell<Context>>;
-    fn deref(&self) -> &Self::Target {
-        &self.0
+impl BoundContext {
+    /// Creates a new, empty `BoundContext`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn bind(&self, node: Node) -> BoundNode {
+        BoundNode {
+            node,
+            ctx: self.clone(),
+        }
     }
-}
 
-impl std::ops::DerefMut for BoundContext {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+    /// Returns a node representing the X axis
+    pub fn x(&self) -> BoundNode {
+        let node = self.0.borrow_mut().x();
+        self.bind(node)
     }
-}
 
-impl BoundContext {
-    /// Creates a new, empty `BoundContext`
-    pub fn new() -> Self {
-        BoundContext(Rc::new(RefCell::new(Context::new())))
+    /// Returns a node representing the Y axis
+    pub fn y(&self) -> BoundNode {
+        let node = self.0.borrow_mut().y();
+        self.bind(node)
+    }
+
+    /// Returns a node representing the Z axis
+    pub fn z(&self) -> BoundNode {
+        let node = self.0.borrow_mut().z();
+        self.bind(node)
     }
+
     /// Returns `(x, y, z)` axes from this context
     pub fn axes(&self) -> (BoundNode, BoundNode, BoundNode) {
-        let x = self.borrow_mut().x();
-        let y = self.borrow_mut().y();
-        let z = self.borrow_mut().z();
-        let x = BoundNode {
-            node: x,
-            ctx: self.clone(),
-        };
-        let y = BoundNode {
-            node: y,
-            ctx: self.clone(),
-        };
-        let z = BoundNode {
-            node: z,
-            ctx: self.clone(),
-        };
-        (x, y, z)
+        (self.x(), self.y(), self.z())
     }
+
+    /// Returns a constant node
     pub fn constant(&self, f: f64) -> BoundNode {
-        let node = self.borrow_mut().constant(f);
-        BoundNode {
-            node,
-            ctx: self.clone(),
-        }
+        let node = self.0.borrow_mut().constant(f);
+        self.bind(node)
+    }
+
+    /// Returns a named variable
+    ///
+    /// See [`Context::var`] for details and possible errors.
+    pub fn var(&self, name: &str) -> Result<BoundNode, Error> {
+        let node = self.0.borrow_mut().var(name)?;
+        Ok(self.bind(node))
     }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
-/// A node bound to a specific context
+/// A node bound to a specific [`BoundContext`]
+///
+/// `BoundNode` implements the arithmetic operators from [`std::ops`] (`+`,
+/// `-`, `*`, `/`, their assigning forms, and unary `-`), and [`std::ops::Not`]
+/// for logical `not` (see [`Context::not`]).  The other operand may be another
+/// `BoundNode` (by value or reference) or an `f32` / `f64` constant; constants
+/// on the left-hand side must be `f64`, so that float literals are
+/// unambiguous.  Every other operation is available as a method.
 ///
-/// This allows us to write inline math expressions, for ease of testing.
-/// However, it's less efficient: each `BoundNode` doubles in size, and every
-/// operation encurs a `RefCell` dynamic borrow.
+/// Operands may be bound to different contexts.  The result is bound to the
+/// context of `self` (or of the `BoundNode`, for a constant on the left-hand
+/// side), and other operands are copied into it with [`Context::import`].
 #[derive(Clone, Debug)]
 pub struct BoundNode {
     node: Node,
@@ -73,114 +102,273 @@ pub struct BoundNode {
 
 impl std::cmp::PartialEq for BoundNode {
     fn eq(&self, other: &Self) -> bool {
-        self.node == other.node && self.ctx.as_ptr() == other.ctx.as_ptr()
+        self.node == other.node && Rc::ptr_eq(&self.ctx.0, &other.ctx.0)
     }
 }
 
 impl IntoNode for BoundNode {
     fn into_node(self, ctx: &mut Context) -> Result<Node, Error> {
-        assert_eq!(self.ctx.as_ptr(), ctx as *mut Context);
-        Ok(self.node)
+        (&self).into_node(ctx)
+    }
+}
+
+impl IntoNode for &BoundNode {
+    /// Returns the inner node
+    ///
+    /// If `ctx` is not this node's context, then the node's subgraph is
+    /// imported into `ctx` (see [`Context::import`]).
+    fn into_node(self, ctx: &mut Context) -> Result<Node, Error> {
+        if std::ptr::eq(self.ctx.0.as_ptr(), ctx) {
+            Ok(self.node)
+        } else {
+            ctx.import(&self.ctx.0.borrow(), self.node)
+        }
     }
 }
 
 impl BoundNode {
-    pub fn convert<T: MathShape>(&self) -> T {
-        let ctx = self.ctx.borrow();
-        let Ok(out) = T::new(&ctx, self.node) else {
-            unreachable!("invalid node for our own context?")
-        };
-        out
+    /// Returns the underlying [`Node`]
+    pub fn node(&self) -> Node {
+        self.node
     }
 
-    fn op_bin<A: IntoNode>(
-        self,
-        other: A,
-        f: fn(&mut Context, Node, Node) -> Result<Node, Error>,
-    ) -> Self {
-        let node = {
-            let mut ctx = self.ctx.borrow_mut();
-            let other = other.into_node(&mut ctx).unwrap();
-            f(&mut ctx, self.node, other).unwrap()
-        };
-        BoundNode {
-            node,
-            ctx: self.ctx,
-        }
+    /// Returns the context to which this node is bound
+    pub fn context(&self) -> &BoundContext {
+        &self.ctx
+    }
+
+    /// Converts this node into a shape
+    pub fn convert<T: MathShape>(&self) -> Result<T, Error> {
+        T::new(&self.ctx.0.borrow(), self.node)
+    }
+
+    /// Evaluates the node at a particular point
+    ///
+    /// This is very inefficient compared to constructing a proper evaluator!
+    /// It returns an error if the expression uses any variables.
+    pub fn eval(&self, x: f64, y: f64, z: f64) -> Result<f64, Error> {
+        self.ctx.0.borrow().eval_xyz(self.node, x, y, z)
+    }
+
+    /// Builds a new node in our context, then binds it
+    ///
+    /// Operands are either our own node (which is always valid in our context)
+    /// or converted with [`IntoNode`], which imports nodes from other contexts,
+    /// so building cannot fail.
+    fn build(
+        &self,
+        f: impl FnOnce(&mut Context) -> Result<Node, Error>,
+    ) -> BoundNode {
+        let node = f(&mut self.ctx.0.borrow_mut())
+            .expect("operands must be valid in the bound context");
+        self.ctx.bind(node)
     }
 
     fn op_unary(
-        self,
+        &self,
         f: fn(&mut Context, Node) -> Result<Node, Error>,
-    ) -> Self {
-        let node = {
-            let mut ctx = self.ctx.borrow_mut();
-            f(&mut ctx, self.node).unwrap()
-        };
-        BoundNode {
-            node,
-            ctx: self.ctx,
-        }
+    ) -> BoundNode {
+        self.build(|ctx| f(ctx, self.node))
+    }
+
+    fn op_binary<A: IntoNode>(
+        &self,
+        other: A,
+        f: fn(&mut Context, Node, Node) -> Result<Node, Error>,
+    ) -> BoundNode {
+        self.build(|ctx| {
+            let other = other.into_node(ctx)?;
+            f(ctx, self.node, other)
+        })
+    }
+
+    /// Builds an absolute value operation
+    pub fn abs(&self) -> BoundNode {
+        self.op_unary(Context::abs)
+    }
+
+    /// Builds a reciprocal operation
+    pub fn recip(&self) -> BoundNode {
+        self.op_unary(Context::recip)
+    }
+
+    /// Builds a square root operation
+    pub fn sqrt(&self) -> BoundNode {
+        self.op_unary(Context::sqrt)
+    }
+
+    /// Builds a `square` operation
+    pub fn square(&self) -> BoundNode {
+        self.op_unary(Context::square)
+    }
+
+    /// Builds a `floor` operation
+    pub fn floor(&self) -> BoundNode {
+        self.op_unary(Context::floor)
+    }
+
+    /// Builds a `ceil` operation
+    pub fn ceil(&self) -> BoundNode {
+        self.op_unary(Context::ceil)
+    }
+
+    /// Builds a `round` operation
+    pub fn round(&self) -> BoundNode {
+        self.op_unary(Context::round)
+    }
+
+    /// Builds a `sin` operation
+    pub fn sin(&self) -> BoundNode {
+        self.op_unary(Context::sin)
+    }
+
+    /// Builds a `cos` operation
+    pub fn cos(&self) -> BoundNode {
+        self.op_unary(Context::cos)
+    }
+
+    /// Builds a `tan` operation
+    pub fn tan(&self) -> BoundNode {
+        self.op_unary(Context::tan)
+    }
+
+    /// Builds an `asin` operation
+    pub fn asin(&self) -> BoundNode {
+        self.op_unary(Context::asin)
+    }
+
+    /// Builds an `acos` operation
+    pub fn acos(&self) -> BoundNode {
+        self.op_unary(Context::acos)
+    }
+
+    /// Builds an `atan` operation
+    pub fn atan(&self) -> BoundNode {
+        self.op_unary(Context::atan)
+    }
+
+    /// Builds an `exp` operation
+    pub fn exp(&self) -> BoundNode {
+        self.op_unary(Context::exp)
+    }
+
+    /// Builds a natural log operation
+    pub fn ln(&self) -> BoundNode {
+        self.op_unary(Context::ln)
     }
 
     /// Builds a `min` operation
-    ///
-    /// # Panics
-    /// If the nodes are invalid or are not from the same [`Context`]
-    pub fn min<A: IntoNode>(self, other: A) -> Self {
-        self.op_bin(other, Context::min)
+    pub fn min<A: IntoNode>(&self, other: A) -> BoundNode {
+        self.op_binary(other, Context::min)
     }
 
     /// Builds a `max` operation
+    pub fn max<A: IntoNode>(&self, other: A) -> BoundNode {
+        self.op_binary(other, Context::max)
+    }
+
+    /// Builds a two-argument arctangent operation, i.e. `atan2(self, other)`
+    pub fn atan2<A: IntoNode>(&self, other: A) -> BoundNode {
+        self.op_binary(other, Context::atan2)
+    }
+
+    /// Builds a Euclidean remainder operation
     ///
-    /// # Panics
-    /// If the nodes are invalid or are not from the same [`Context`]
-    pub fn max<A: IntoNode>(self, other: A) -> Self {
-        self.op_bin(other, Context::max)
+    /// Unlike Rust's `%` operator, the result is never negative; see
+    /// [`Context::modulo`].
+    pub fn modulo<A: IntoNode>(&self, other: A) -> BoundNode {
+        self.op_binary(other, Context::modulo)
     }
 
-    /// Builds a `square` operation
-    pub fn square(self) -> Self {
-        self.op_unary(Context::square)
+    /// Builds a comparison operation
+    ///
+    /// See [`Context::compare`] for details.
+    pub fn compare<A: IntoNode>(&self, other: A) -> BoundNode {
+        self.op_binary(other, Context::compare)
     }
 
-    /// Builds a square root operation
-    pub fn sqrt(self) -> Self {
-        self.op_unary(Context::sqrt)
+    /// Builds a logical `and` operation
+    ///
+    /// See [`Context::and`] for details.
+    pub fn and<A: IntoNode>(&self, other: A) -> BoundNode {
+        self.op_binary(other, Context::and)
     }
 
-    /// Evaluates the node at a particular point
+    /// Builds a logical `or` operation
     ///
-    /// This is very inefficient compared to constructing a proper evaluator!
-    pub fn eval(&self, x: f64, y: f64, z: f64) -> f64 {
-        self.ctx.0.borrow().eval_xyz(self.node, x, y, z).unwrap()
+    /// See [`Context::or`] for details.
+    pub fn or<A: IntoNode>(&self, other: A) -> BoundNode {
+        self.op_binary(other, Context::or)
+    }
+
+    /// Selects `a` if this node is nonzero, and `b` otherwise
+    ///
+    /// See [`Context::if_nonzero_else`] for details.
+    pub fn if_nonzero_else<A: IntoNode, B: IntoNode>(
+        &self,
+        a: A,
+        b: B,
+    ) -> BoundNode {
+        self.build(|ctx| {
+            let a = a.into_node(ctx)?;
+            let b = b.into_node(ctx)?;
+            ctx.if_nonzero_else(self.node, a, b)
+        })
     }
 }
 
+macro_rules! impl_unary {
+    ($op:ident, $base_fn:ident, $ctx_fn:ident) => {
+        impl std::ops::$op for BoundNode {
+            type Output = BoundNode;
+            fn $base_fn(self) -> BoundNode {
+                self.op_unary(Context::$ctx_fn)
+            }
+        }
+        impl std::ops::$op for &BoundNode {
+            type Output = BoundNode;
+            fn $base_fn(self) -> BoundNode {
+                self.op_unary(Context::$ctx_fn)
+            }
+        }
+    };
+}
+
+impl_unary!(Neg, neg, neg);
+impl_unary!(Not, not, not);
+
 macro_rules! impl_binary {
-    ($op:ident, $assign: ident, $base_fn:ident, $assign_fn:ident) => {
+    ($op:ident, $assign:ident, $base_fn:ident, $assign_fn:ident) => {
         impl<A: IntoNode> std::ops::$op<A> for BoundNode {
-            type Output = Self;
-
-            fn $base_fn(self, other: A) -> Self {
-                self.op_bin(other, Context::$base_fn)
+            type Output = BoundNode;
+            fn $base_fn(self, other: A) -> BoundNode {
+                self.op_binary(other, Context::$base_fn)
             }
         }
-        impl std::ops::$op<BoundNode> for f32 {
+        impl<A: IntoNode> std::ops::$op<A> for &BoundNode {
             type Output = BoundNode;
-
-            fn $base_fn(self, other: BoundNode) -> Self::Output {
-                let lhs = BoundNode {
-                    node: self.into_node(&mut other.ctx.borrow_mut()).unwrap(),
-                    ctx: other.ctx.clone(),
-                };
-                lhs.op_bin(other, Context::$base_fn)
+            fn $base_fn(self, other: A) -> BoundNode {
+                self.op_binary(other, Context::$base_fn)
             }
         }
         impl<A: IntoNode> std::ops::$assign<A> for BoundNode {
             fn $assign_fn(&mut self, other: A) {
-                let lhs = self.clone();
-                self.node = lhs.op_bin(other, Context::$base_fn).node;
+                *self = self.op_binary(other, Context::$base_fn);
+            }
+        }
+        impl_binary!(@scalar f64, $op, $base_fn);
+    };
+    (@scalar $t:ty, $op:ident, $base_fn:ident) => {
+        impl std::ops::$op<BoundNode> for $t {
+            type Output = BoundNode;
+            fn $base_fn(self, other: BoundNode) -> BoundNode {
+                std::ops::$op::$base_fn(self, &other)
+            }
+        }
+        impl std::ops::$op<&BoundNode> for $t {
+            type Output = BoundNode;
+            fn $base_fn(self, other: &BoundNode) -> BoundNode {
+                other.build(|ctx| Context::$base_fn(ctx, self, other.node))
             }
         }
     };
@@ -196,6 +384,7 @@ impl_binary!(Div, DivAssign, div, div_assign);
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::vm::VmShape;
 
     #[test]
     fn test_bound_node() {
@@ -206,4 +395,141 @@ mod test {
         let n = x + y + z + 1.0;
         assert_eq!(n.node.0, 6);
     }
+
+    #[test]
+    fn test_bound_ops() {
+        let ctx = BoundContext::new();
+        let (x, y, _z) = ctx.axes();
+
+        let a = &x * 2.0 - &y;
+        assert_eq!(a.eval(3.0, 1.0, 0.0).unwrap(), 5.0);
+        let b = 1.0 - &x / 4.0f32;
+        assert_eq!(b.eval(2.0, 0.0, 0.0).unwrap(), 0.5);
+        let c = 6.0 / -(&x);
+        assert_eq!(c.eval(2.0, 0.0, 0.0).unwrap(), -3.0);
+        let d = 2.0 * x.clone() + 0.5f32;
+        assert_eq!(d.eval(1.0, 0.0, 0.0).unwrap(), 2.5);
+
+        let mut e = x.clone();
+        e += &y;
+        e *= 3.0;
+        e -= 1.0f32;
+        e /= &y;
+        assert_eq!(e.eval(1.0, 2.0, 0.0).unwrap(), 4.0);
+
+        let n = !x.compare(&y);
+        assert_eq!(n.eval(1.0, 1.0, 0.0).unwrap(), 1.0);
+        assert_eq!(n.eval(1.0, 2.0, 0.0).unwrap(), 0.0);
+
+        // Building the same expression twice produces the same node
+        assert_eq!(&x + &y, x.clone() + y.clone());
+        assert_ne!(&x + &y, &x - &y);
+    }
+
+    #[test]
+    fn test_bound_methods() {
+        let ctx = BoundContext::new();
+        let (x, y, _z) = ctx.axes();
+        let p = [(-0.25, 0.5), (0.75, -1.5), (2.0, 3.0)];
+        type Case = (BoundNode, fn(f64, f64) -> f64);
+        let cases: [Case; 15] = [
+            (x.abs(), |x, _| x.abs()),
+            (x.recip(), |x, _| 1.0 / x),
+            (y.abs().sqrt(), |_, y| y.abs().sqrt()),
+            (x.square(), |x, _| x * x),
+            (x.floor(), |x, _| x.floor()),
+            (y.ceil(), |_, y| y.ceil()),
+            (x.round(), |x, _| x.round()),
+            (x.sin() + y.cos(), |x, y| x.sin() + y.cos()),
+            (x.tan(), |x, _| x.tan()),
+            (x.asin() + x.acos(), |x, _| x.asin() + x.acos()),
+            (y.atan(), |_, y| y.atan()),
+            (x.exp() + y.abs().ln(), |x, y| x.exp() + y.abs().ln()),
+            (x.min(&y) + x.max(&y), |x, y| x.min(y) + x.max(y)),
+            (y.atan2(&x), |x, y| y.atan2(x)),
+            (x.modulo(1.0), |x, _| x.rem_euclid(1.0)),
+        ];
+        for (node, f) in cases {
+            for (px, py) in p {
+                let v = node.eval(px, py, 0.0).unwrap();
+                let e = f(px, py);
+                assert!(
+                    v == e || (v.is_nan() && e.is_nan()),
+                    "mismatch at ({px}, {py}): {v} != {e}"
+                );
+            }
+        }
+
+        let c = x.compare(0.0);
+        let s = c.if_nonzero_else(&y, 5.0);
+        assert_eq!(s.eval(1.0, 2.0, 0.0).unwrap(), 2.0);
+        assert_eq!(s.eval(0.0, 2.0, 0.0).unwrap(), 5.0);
+        assert_eq!(x.and(&y).eval(0.0, 2.0, 0.0).unwrap(), 0.0);
+        assert_eq!(x.or(&y).eval(0.0, 2.0, 0.0).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn test_bound_convert() {
+        let ctx = BoundContext::new();
+        let (x, y, _z) = ctx.axes();
+        let a = ctx.var("a").unwrap();
+        let shape: VmShape = (x * &a + y).convert().unwrap();
+        assert_eq!(shape.var_count(), 1);
+        assert!(a.eval(0.0, 0.0, 0.0).is_err());
+        assert!(ctx.var("X").is_err());
+    }
+
+    #[test]
+    fn test_bound_mixed_contexts() {
+        let a = BoundContext::new().x();
+        let b = BoundContext::new().x();
+        assert_ne!(a, b);
+
+        // Nodes from other contexts are imported into the left-hand context
+        let c = &a + &b;
+        assert_eq!(c.context().0.borrow().len(), 3); // x, 2, x * 2
+        assert!(Rc::ptr_eq(&c.context().0, &a.context().0));
+        assert_eq!(c.eval(3.0, 0.0, 0.0).unwrap(), 6.0);
+        assert_eq!(b.context().0.borrow().len(), 1);
+
+        let d = 1.0 - &b;
+        assert!(Rc::ptr_eq(&d.context().0, &b.context().0));
+        let e = d.min(a.square()).if_nonzero_else(&c, b.sqrt());
+        assert!(Rc::ptr_eq(&e.context().0, &b.context().0));
+        assert_eq!(e.eval(4.0, 0.0, 0.0).unwrap(), 8.0);
+
+        // Variables are matched by name
+        let ctx_a = BoundContext::new();
+        let ctx_b = BoundContext::new();
+        let va = ctx_a.var("v").unwrap();
+        let vb = ctx_b.var("v").unwrap();
+        let s = &va - &vb;
+        assert_eq!(s.node(), (&va - &va).node());
+
+        // A plain `Context` imports nodes as well
+        let mut ctx = Context::new();
+        let n = ctx.add(&a, 1.0).unwrap();
+        assert_eq!(ctx.eval_xyz(n, 2.0, 0.0, 0.0).unwrap(), 3.0);
+    }
+
+    #[test]
+    fn test_bound_mixed_contexts_dedup() {
+        // Build the same expression in two contexts with different node order
+        let ctx_a = BoundContext::new();
+        let (x, y, _z) = ctx_a.axes();
+        let a = (&x * &y).min(&x + &y);
+
+        let ctx_b = BoundContext::new();
+        let y = ctx_b.y();
+        let x = ctx_b.x();
+        let b = (&x + &y).min(&y * &x);
+        assert_eq!(ctx_b.0.borrow().len(), 5);
+
+        // Combining them reuses the existing nodes in the left-hand context
+        assert_eq!(b.max(&a), b);
+        assert_eq!(&b + &a, &b * 2.0);
+        assert_eq!(ctx_b.0.borrow().len(), 7); // 2.0 and b * 2.0
+        assert_eq!(a.min(&b), a);
+        assert_eq!(ctx_a.0.borrow().len(), 6);
+    }
 }
@@ -52,6 +52,10 @@ where
     pub fn get_by_index(&self, i: I) -> Option<&V> {
         self.data.get(i.get())
     }
+    /// Looks up the handle for the given value, if it's in the map
+    pub fn get_by_value(&self, v: &V) -> Option<I> {
+        self.map.get(v).copied()
+    }
     /// Insert the given value into the map, returning a handle.
     ///
     /// If the value is already in the map, the handle will be to the existing
@@ -1,12 +1,15 @@
 //! Infrastructure for representing math expressions as graphs
+mod bound;
 mod indexed;
 mod op;
+mod rebalance;
+mod serialize;
+mod stats;
 
-#[cfg(test)]
-pub(crate) mod bound;
-
+pub use bound::{BoundContext, BoundNode};
 use indexed::{define_index, Index, IndexMap, IndexVec};
 pub use op::{BinaryOpcode, Op, UnaryOpcode};
+pub use stats::GraphStats;
 
 use crate::Error;
 
@@ -23,7 +26,8 @@ define_index!(VarNode, "An index in the `Context::vars` map");
 /// operations.
 ///
 /// It should be used like an arena allocator: it grows over time, then frees
-/// all of its contents when dropped.
+/// all of its contents when dropped.  Unreachable nodes can be removed with
+/// [`Context::gc`].
 #[derive(Debug, Default)]
 pub struct Context {
     ops: IndexMap<Op, Node>,
@@ -78,6 +82,41 @@ impl Context {
         self.get_op(node).ok_or(Error::BadNode).map(|_| ())
     }
 
+    /// Returns every node reachable from `root`, with children before their
+    /// parents and each node listed once
+    ///
+    /// Nodes for which `skip` returns `true` are left out, along with any
+    /// children which are only reachable through them.  The graph is walked
+    /// on the heap, to protect against stack overflows.
+    fn post_order(&self, root: Node, skip: impl Fn(Node) -> bool) -> Vec<Node> {
+        enum Action {
+            Down,
+            Up,
+        }
+
+        let mut order = vec![];
+        let mut todo = vec![(Action::Down, root)];
+        let mut seen = BTreeSet::new();
+        while let Some((action, node)) = todo.pop() {
+            match action {
+                Action::Down => {
+                    if skip(node) || !seen.insert(node) {
+                        continue;
+                    }
+                    todo.push((Action::Up, node));
+                    todo.extend(
+                        self.get_op(node)
+                            .unwrap()
+                            .iter_children()
+                            .map(|c| (Action::Down, c)),
+                    );
+                }
+                Action::Up => order.push(node),
+            }
+        }
+        order
+    }
+
     /// Erases the most recently added node from the tree.
     ///
     /// A few caveats apply, so this must be used with caution:
@@ -447,6 +486,151 @@ impl Context {
         self.op_unary(a, UnaryOpcode::Ln)
     }
 
+    /// Builds a node which rounds its input down to the nearest integer
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.floor(x).unwrap();
+    /// let v = ctx.eval_xyz(op, 1.2, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.0);
+    /// let v = ctx.eval_xyz(op, -1.2, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, -2.0);
+    /// ```
+    pub fn floor<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        self.op_unary(a, UnaryOpcode::Floor)
+    }
+
+    /// Builds a node which rounds its input up to the nearest integer
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.ceil(x).unwrap();
+    /// let v = ctx.eval_xyz(op, 1.2, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 2.0);
+    /// let v = ctx.eval_xyz(op, -1.2, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, -1.0);
+    /// ```
+    pub fn ceil<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        self.op_unary(a, UnaryOpcode::Ceil)
+    }
+
+    /// Builds a node which rounds its input to the nearest integer
+    ///
+    /// Half-way cases are rounded away from zero, matching [`f64::round`].
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.round(x).unwrap();
+    /// let v = ctx.eval_xyz(op, 1.2, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.0);
+    /// let v = ctx.eval_xyz(op, -1.5, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, -2.0);
+    /// ```
+    pub fn round<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        self.op_unary(a, UnaryOpcode::Round)
+    }
+
+    /// Builds a node which compares two values
+    ///
+    /// The result is -1 if `a < b`, 0 if `a == b`, and 1 if `a > b`.  If
+    /// either input is `NaN`, the result is also `NaN`.
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.compare(x, 1.0).unwrap();
+    /// let v = ctx.eval_xyz(op, 0.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, -1.0);
+    /// let v = ctx.eval_xyz(op, 1.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 0.0);
+    /// let v = ctx.eval_xyz(op, 2.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.0);
+    /// ```
+    pub fn compare<A: IntoNode, B: IntoNode>(
+        &mut self,
+        a: A,
+        b: B,
+    ) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let b = b.into_node(self)?;
+        self.op_binary(a, b, BinaryOpcode::Compare)
+    }
+
+    /// Builds a node which is 1 if its input is zero, and 0 otherwise
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.not(x).unwrap();
+    /// let v = ctx.eval_xyz(op, 0.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.0);
+    /// let v = ctx.eval_xyz(op, 2.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 0.0);
+    /// ```
+    pub fn not<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        self.op_unary(a, UnaryOpcode::Not)
+    }
+
+    /// Builds a logical `and` node
+    ///
+    /// If `a` is zero, returns `a`; otherwise, returns `b`.  Like `min` and
+    /// `max`, this operation records a [`Choice`](crate::vm::Choice) during
+    /// tracing evaluation, so the untaken branch can be simplified away.
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let op = ctx.and(x, y).unwrap();
+    /// let v = ctx.eval_xyz(op, 0.0, 2.0, 0.0).unwrap();
+    /// assert_eq!(v, 0.0);
+    /// let v = ctx.eval_xyz(op, 1.0, 2.0, 0.0).unwrap();
+    /// assert_eq!(v, 2.0);
+    /// ```
+    pub fn and<A: IntoNode, B: IntoNode>(
+        &mut self,
+        a: A,
+        b: B,
+    ) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let b = b.into_node(self)?;
+        match self.const_value(a)? {
+            Some(0.0) => Ok(a),
+            Some(_) => Ok(b),
+            None => self.op_binary(a, b, BinaryOpcode::And),
+        }
+    }
+
+    /// Builds a logical `or` node
+    ///
+    /// If `a` is nonzero, returns `a`; otherwise, returns `b`.  Like `min` and
+    /// `max`, this operation records a [`Choice`](crate::vm::Choice) during
+    /// tracing evaluation, so the untaken branch can be simplified away.
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let op = ctx.or(x, y).unwrap();
+    /// let v = ctx.eval_xyz(op, 0.0, 2.0, 0.0).unwrap();
+    /// assert_eq!(v, 2.0);
+    /// let v = ctx.eval_xyz(op, 1.0, 2.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.0);
+    /// ```
+    pub fn or<A: IntoNode, B: IntoNode>(
+        &mut self,
+        a: A,
+        b: B,
+    ) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let b = b.into_node(self)?;
+        match self.const_value(a)? {
+            Some(0.0) => Ok(b),
+            Some(_) => Ok(a),
+            None => self.op_binary(a, b, BinaryOpcode::Or),
+        }
+    }
+
     ////////////////////////////////////////////////////////////////////////////
     // Derived functions
     /// Builds a node which squares its input
@@ -511,6 +695,79 @@ impl Context {
         }
     }
 
+    /// Builds a node which computes `atan2(y, x)`
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let op = ctx.atan2(y, x).unwrap();
+    /// let v = ctx.eval_xyz(op, 0.0, 1.0, 0.0).unwrap();
+    /// assert_eq!(v, std::f64::consts::FRAC_PI_2);
+    /// ```
+    pub fn atan2<A: IntoNode, B: IntoNode>(
+        &mut self,
+        y: A,
+        x: B,
+    ) -> Result<Node, Error> {
+        let y = y.into_node(self)?;
+        let x = x.into_node(self)?;
+        self.op_binary(y, x, BinaryOpcode::Atan)
+    }
+
+    /// Builds a node which computes the least non-negative remainder of
+    /// `a / b`, i.e. [`f64::rem_euclid`]
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.modulo(x, 2.0).unwrap();
+    /// let v = ctx.eval_xyz(op, 3.5, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.5);
+    /// let v = ctx.eval_xyz(op, -0.5, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.5);
+    /// ```
+    pub fn modulo<A: IntoNode, B: IntoNode>(
+        &mut self,
+        a: A,
+        b: B,
+    ) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let b = b.into_node(self)?;
+        self.op_binary(a, b, BinaryOpcode::Mod)
+    }
+
+    /// Builds a node which selects between `a` and `b` based on a condition
+    ///
+    /// If `condition` is nonzero, returns `a`; otherwise, returns `b`.  This
+    /// is built from [`and`](Context::and), [`or`](Context::or), and
+    /// [`not`](Context::not) nodes, so either branch may be pruned during
+    /// simplification.
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let z = ctx.z();
+    /// let c = ctx.compare(x, 0.0).unwrap();
+    /// let op = ctx.if_nonzero_else(c, y, z).unwrap();
+    /// let v = ctx.eval_xyz(op, 1.0, 2.0, 3.0).unwrap();
+    /// assert_eq!(v, 2.0);
+    /// let v = ctx.eval_xyz(op, 0.0, 2.0, 3.0).unwrap();
+    /// assert_eq!(v, 3.0);
+    /// ```
+    pub fn if_nonzero_else<C: IntoNode, A: IntoNode, B: IntoNode>(
+        &mut self,
+        condition: C,
+        a: A,
+        b: B,
+    ) -> Result<Node, Error> {
+        let condition = condition.into_node(self)?;
+        let a = a.into_node(self)?;
+        let b = b.into_node(self)?;
+        let lhs = self.and(condition, a)?;
+        let n_condition = self.not(condition)?;
+        let rhs = self.and(n_condition, b)?;
+        self.or(lhs, rhs)
+    }
+
     ////////////////////////////////////////////////////////////////////////////
 
     /// Remaps the X, Y, Z nodes to the given values
@@ -570,6 +827,544 @@ impl Context {
         Ok(*done.get(&root).unwrap())
     }
 
+    /// Replaces nodes in the expression rooted at `root`, using `map` to look
+    /// up replacements
+    ///
+    /// This is a generalization of [`Context::remap_xyz`]: keys are typically
+    /// variables (from [`Context::var`]) or inputs, but any node may be
+    /// replaced.  The graph above each replacement is rebuilt through the
+    /// usual builders, so substituting constants will fold operations which
+    /// become constant.
+    ///
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let a = ctx.var("a").unwrap();
+    /// let op = ctx.mul(x, a).unwrap();
+    /// let map = [(a, y)].into_iter().collect();
+    /// let op = ctx.substitute(op, &map).unwrap();
+    /// let v = ctx.eval_xyz(op, 2.0, 3.0, 0.0).unwrap();
+    /// assert_eq!(v, 6.0);
+    /// ```
+    pub fn substitute(
+        &mut self,
+        root: Node,
+        map: &BTreeMap<Node, Node>,
+    ) -> Result<Node, Error> {
+        self.check_node(root)?;
+        map.iter().try_for_each(|(k, v)| {
+            self.check_node(*k)?;
+            self.check_node(*v)
+        })?;
+
+        // Replaced nodes are leaves, so we don't walk below them
+        let mut done = map.clone();
+        for node in self.post_order(root, |n| map.contains_key(&n)) {
+            let r = match *self.get_op(node).unwrap() {
+                Op::Input(..) | Op::Var(..) | Op::Const(..) => node,
+                Op::Unary(op, a) => {
+                    let a = *done.get(&a).unwrap();
+                    self.op_unary(a, op)?
+                }
+                Op::Binary(op, a, b) => {
+                    let a = *done.get(&a).unwrap();
+                    let b = *done.get(&b).unwrap();
+                    self.build_binary(op, a, b)?
+                }
+            };
+            done.insert(node, r);
+        }
+        Ok(*done.get(&root).unwrap())
+    }
+
+    /// Partially evaluates the expression rooted at `root`, replacing bound
+    /// variables with constants and folding the result
+    ///
+    /// Variables are looked up by name, as in [`Context::eval`] (so `"X"`,
+    /// `"Y"`, and `"Z"` bind the inputs).  Names which are not present in the
+    /// context are ignored, and variables without a binding are left in the
+    /// expression.
+    ///
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let r = ctx.var("r").unwrap();
+    /// let x2 = ctx.square(x).unwrap();
+    /// let r2 = ctx.square(r).unwrap();
+    /// let op = ctx.sub(x2, r2).unwrap();
+    ///
+    /// let vars = BTreeMap::from([("r".to_owned(), 2.0)]);
+    /// let op = ctx.specialize(op, &vars).unwrap();
+    /// assert_eq!(ctx.eval_xyz(op, 3.0, 0.0, 0.0).unwrap(), 5.0);
+    ///
+    /// let vars = BTreeMap::from([("X".to_owned(), 1.0)]);
+    /// let op = ctx.specialize(op, &vars).unwrap();
+    /// assert_eq!(ctx.const_value(op).unwrap(), Some(-3.0));
+    /// ```
+    pub fn specialize(
+        &mut self,
+        root: Node,
+        vars: &BTreeMap<String, f64>,
+    ) -> Result<Node, Error> {
+        self.check_node(root)?;
+        let mut map = BTreeMap::new();
+        for (name, value) in vars {
+            let Some(v) = self.vars.get_by_value(name) else {
+                continue;
+            };
+            let op = if matches!(name.as_str(), "X" | "Y" | "Z") {
+                Op::Input(v)
+            } else {
+                Op::Var(v)
+            };
+            if let Some(n) = self.ops.get_by_value(&op) {
+                let c = self.constant(*value);
+                map.insert(n, c);
+            }
+        }
+        self.substitute(root, &map)
+    }
+
+    /// Removes every operation and variable which is not reachable from one of
+    /// the given roots, compacting the context.
+    ///
+    /// Returns a map from old to new handles for every surviving node; all
+    /// other [`Node`] and [`VarNode`] handles from this context are
+    /// invalidated.  If any root is invalid, returns an error and leaves the
+    /// context unchanged.
+    ///
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let tmp = ctx.mul(x, y).unwrap();
+    /// let sum = ctx.add(x, 1.0).unwrap();
+    /// assert_eq!(ctx.len(), 5);
+    ///
+    /// let remap = ctx.gc(&[sum]).unwrap();
+    /// assert_eq!(ctx.len(), 3);
+    /// assert!(!remap.contains_key(&tmp));
+    /// let v = ctx.eval_xyz(remap[&sum], 2.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 3.0);
+    /// ```
+    pub fn gc(
+        &mut self,
+        roots: &[Node],
+    ) -> Result<BTreeMap<Node, Node>, Error> {
+        roots.iter().try_for_each(|r| self.check_node(*r))?;
+
+        let old = std::mem::take(self);
+        let mut done = BTreeMap::new();
+        for r in roots {
+            self.import_with(&old, *r, &mut done);
+        }
+        Ok(done)
+    }
+
+    /// Copies the subgraph rooted at `node` from another context into this one,
+    /// returning its handle in this context.
+    ///
+    /// Operations and variables which already exist in this context (including
+    /// those shared with previously imported subgraphs) are reused rather than
+    /// duplicated.
+    ///
+    /// ```
+    /// # use fidget::context::Context;
+    /// let mut a = Context::new();
+    /// let x = a.x();
+    /// let shape = a.add(x, 1.0).unwrap();
+    ///
+    /// let mut b = Context::new();
+    /// let y = b.y();
+    /// let node = b.import(&a, shape).unwrap();
+    /// assert_eq!(b.len(), 4); // y, x, 1.0, and the addition
+    /// assert_eq!(b.eval_xyz(node, 2.0, 0.0, 0.0).unwrap(), 3.0);
+    /// ```
+    pub fn import(
+        &mut self,
+        other: &Context,
+        node: Node,
+    ) -> Result<Node, Error> {
+        other.check_node(node)?;
+        Ok(self.import_with(other, node, &mut BTreeMap::new()))
+    }
+
+    /// Copies a subgraph from another context, using (and updating) `done` as
+    /// a map from nodes in `other` to nodes in `self`.
+    ///
+    /// Operations are copied without constant folding, so the imported
+    /// subgraph has the same shape as the original.  The only normalization
+    /// is re-sorting the arguments of commutative operations, because
+    /// renumbering nodes may break the order which
+    /// [`op_binary_commutative`](Self::op_binary_commutative) relies on for
+    /// deduplication.  `root` must be valid in `other`.
+    fn import_with(
+        &mut self,
+        other: &Context,
+        root: Node,
+        done: &mut BTreeMap<Node, Node>,
+    ) -> Node {
+        for node in other.post_order(root, |n| done.contains_key(&n)) {
+            let op = match *other.get_op(node).unwrap() {
+                Op::Binary(op, lhs, rhs) => {
+                    let (a, b) = (done[&lhs], done[&rhs]);
+                    if op.is_commutative() {
+                        Op::Binary(op, a.min(b), a.max(b))
+                    } else {
+                        Op::Binary(op, a, b)
+                    }
+                }
+                Op::Unary(op, arg) => Op::Unary(op, done[&arg]),
+                Op::Const(c) => Op::Const(c),
+                Op::Var(v) => {
+                    let name = other.get_var_by_index(v).unwrap();
+                    Op::Var(self.vars.insert(name.to_owned()))
+                }
+                Op::Input(v) => {
+                    let name = other.get_var_by_index(v).unwrap();
+                    Op::Input(self.vars.insert(name.to_owned()))
+                }
+            };
+            done.insert(node, self.ops.insert(op));
+        }
+        done[&root]
+    }
+
+    /// Builds a node representing the partial derivative of `root` with
+    /// respect to `v`, which must be an input (e.g. [`Context::x`]) or a
+    /// variable (from [`Context::var`]).
+    ///
+    /// Non-differentiable operations use the same subgradients as
+    /// [`Grad`](crate::eval::types::Grad): for example, `min(a, b)` takes the
+    /// derivative of `b` when `a == b`, and `abs(a)` has a derivative of 1 at
+    /// `a == 0`.  Rounding, comparison, and `not` have a derivative of zero.
+    ///
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let x2 = ctx.square(x).unwrap();
+    /// let op = ctx.mul(x2, y).unwrap();
+    /// let dx = ctx.deriv(op, x).unwrap();
+    /// let v = ctx.eval_xyz(dx, 3.0, 5.0, 0.0).unwrap();
+    /// assert_eq!(v, 30.0); // 2 * x * y
+    /// let z = ctx.z();
+    /// let dz = ctx.deriv(op, z).unwrap();
+    /// assert_eq!(ctx.const_value(dz).unwrap(), Some(0.0));
+    /// ```
+    pub fn deriv(&mut self, root: Node, v: Node) -> Result<Node, Error> {
+        self.check_node(root)?;
+        if !matches!(self.get_op(v), Some(Op::Input(..) | Op::Var(..))) {
+            self.check_node(v)?;
+            return Err(Error::NotAVariable);
+        }
+
+        let zero = self.constant(0.0);
+        let one = self.constant(1.0);
+        let mut done = BTreeMap::new();
+
+        for node in self.post_order(root, |_| false) {
+            let r = match *self.get_op(node).unwrap() {
+                Op::Input(..) | Op::Var(..) => {
+                    if node == v {
+                        one
+                    } else {
+                        zero
+                    }
+                }
+                Op::Const(..) => zero,
+                Op::Unary(op, a) => {
+                    let da = *done.get(&a).unwrap();
+                    self.deriv_unary(op, node, a, da)?
+                }
+                Op::Binary(op, a, b) => {
+                    let da = *done.get(&a).unwrap();
+                    let db = *done.get(&b).unwrap();
+                    self.deriv_binary(op, node, a, b, da, db)?
+                }
+            };
+            done.insert(node, r);
+        }
+        Ok(*done.get(&root).unwrap())
+    }
+
+    /// Derivative of `out = op(a)`, given the derivative `da`
+    fn deriv_unary(
+        &mut self,
+        op: UnaryOpcode,
+        out: Node,
+        a: Node,
+        da: Node,
+    ) -> Result<Node, Error> {
+        // Every rule is proportional to `da`, so skip the work if it's zero
+        if self.const_value(da)? == Some(0.0) {
+            return Ok(da);
+        }
+        match op {
+            UnaryOpcode::Neg => self.neg(da),
+            UnaryOpcode::Abs => {
+                // Pick `-da` if `a < 0`, matching `Grad::abs`
+                let c = self.compare(0.0, a)?;
+                let c = self.max(c, 0.0)?;
+                let n = self.neg(da)?;
+                self.deriv_select(c, n, da)
+            }
+            UnaryOpcode::Recip => {
+                let a2 = self.square(a)?;
+                let d = self.div(da, a2)?;
+                self.neg(d)
+            }
+            UnaryOpcode::Sqrt => {
+                let d = self.mul(out, 2.0)?;
+                self.div(da, d)
+            }
+            UnaryOpcode::Square => {
+                let d = self.mul(a, 2.0)?;
+                self.mul(d, da)
+            }
+            UnaryOpcode::Floor
+            | UnaryOpcode::Ceil
+            | UnaryOpcode::Round
+            | UnaryOpcode::Not => Ok(self.constant(0.0)),
+            UnaryOpcode::Sin => {
+                let c = self.cos(a)?;
+                self.mul(c, da)
+            }
+            UnaryOpcode::Cos => {
+                let s = self.sin(a)?;
+                let s = self.neg(s)?;
+                self.mul(s, da)
+            }
+            UnaryOpcode::Tan => {
+                let c = self.cos(a)?;
+                let c2 = self.square(c)?;
+                self.div(da, c2)
+            }
+            UnaryOpcode::Asin | UnaryOpcode::Acos => {
+                let a2 = self.square(a)?;
+                let r = self.sub(1.0, a2)?;
+                let r = self.sqrt(r)?;
+                let d = self.div(da, r)?;
+                if op == UnaryOpcode::Acos {
+                    self.neg(d)
+                } else {
+                    Ok(d)
+                }
+            }
+            UnaryOpcode::Atan => {
+                let a2 = self.square(a)?;
+                let r = self.add(a2, 1.0)?;
+                self.div(da, r)
+            }
+            UnaryOpcode::Exp => self.mul(out, da),
+            UnaryOpcode::Ln => self.div(da, a),
+        }
+    }
+
+    /// Derivative of `out = op(a, b)`, given the derivatives `da` and `db`
+    fn deriv_binary(
+        &mut self,
+        op: BinaryOpcode,
+        out: Node,
+        a: Node,
+        b: Node,
+        da: Node,
+        db: Node,
+    ) -> Result<Node, Error> {
+        match op {
+            BinaryOpcode::Add => self.add(da, db),
+            BinaryOpcode::Sub => self.sub(da, db),
+            BinaryOpcode::Mul => {
+                let lhs = self.mul(a, db)?;
+                let rhs = self.mul(b, da)?;
+                self.add(lhs, rhs)
+            }
+            BinaryOpcode::Div => {
+                // (da * b - a * db) / b²
+                let lhs = self.mul(da, b)?;
+                let rhs = self.mul(a, db)?;
+                let num = self.sub(lhs, rhs)?;
+                let b2 = self.square(b)?;
+                self.div(num, b2)
+            }
+            BinaryOpcode::Atan => {
+                // (x * dy - y * dx) / (x² + y²), where `a` is y and `b` is x
+                let lhs = self.mul(b, da)?;
+                let rhs = self.mul(a, db)?;
+                let num = self.sub(lhs, rhs)?;
+                let a2 = self.square(a)?;
+                let b2 = self.square(b)?;
+                let d = self.add(a2, b2)?;
+                self.div(num, d)
+            }
+            BinaryOpcode::Min => {
+                // Pick `da` if `a < b`, matching `Grad::min`
+                let c = self.compare(b, a)?;
+                let c = self.max(c, 0.0)?;
+                self.deriv_select(c, da, db)
+            }
+            BinaryOpcode::Max => {
+                // Pick `da` if `a > b`, matching `Grad::max`
+                let c = self.compare(a, b)?;
+                let c = self.max(c, 0.0)?;
+                self.deriv_select(c, da, db)
+            }
+            BinaryOpcode::Mod => {
+                // The Euclidean quotient is locally constant, so this is
+                // da - q * db (where q = (a - out) / b)
+                let q = self.sub(a, out)?;
+                let q = self.div(q, b)?;
+                let d = self.mul(q, db)?;
+                self.sub(da, d)
+            }
+            BinaryOpcode::Compare => Ok(self.constant(0.0)),
+            BinaryOpcode::And => self.deriv_select(a, db, da),
+            BinaryOpcode::Or => self.deriv_select(a, da, db),
+        }
+    }
+
+    /// Selects between two derivatives, skipping the selection if they match
+    fn deriv_select(
+        &mut self,
+        cond: Node,
+        a: Node,
+        b: Node,
+    ) -> Result<Node, Error> {
+        if a == b {
+            Ok(a)
+        } else {
+            self.if_nonzero_else(cond, a, b)
+        }
+    }
+
+    ////////////////////////////////////////////////////////////////////////////
+
+    /// Rebuilds the expression rooted at `root`, applying algebraic rewrites
+    ///
+    /// The graph is rebuilt bottom-up through the usual builders (so constants
+    /// are folded and commutative operands are sorted for deduplication), and
+    /// the following rewrites are applied:
+    ///
+    /// - `-(-a) → a`
+    /// - `abs(-a) → abs(a)`, `abs(abs(a)) → abs(a)`, `abs(a²) → a²`
+    /// - `(-a)² → a²`, `abs(a)² → a²`
+    /// - Nested rounding (e.g. `floor(round(a))`) keeps only the inner
+    ///   operation
+    /// - `not(not(not(a))) → not(a)`
+    /// - `a + (-b) → a - b`, `(-a) + b → b - a`, `a - (-b) → a + b`
+    /// - `(-a) * (-b) → a * b`, `a * a → a²`
+    /// - `min(a, a)`, `max(a, a)`, `and(a, a)`, and `or(a, a)` become `a`
+    ///
+    /// These rewrites give the same result for every input (including
+    /// infinities and `NaN`).  Rewrites that are only valid for finite values,
+    /// e.g. `a - a → 0`, or that change where single-precision evaluators
+    /// overflow or underflow, e.g. `sqrt(a²) → abs(a)`, are not applied.
+    ///
+    /// This is opt-in, because it may add new nodes to the context; the
+    /// original nodes are left unchanged.
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let n = ctx.neg(x).unwrap();
+    /// let n = ctx.neg(n).unwrap();
+    /// let op = ctx.min(n, x).unwrap();
+    /// assert_eq!(ctx.optimize(op).unwrap(), x);
+    /// ```
+    pub fn optimize(&mut self, root: Node) -> Result<Node, Error> {
+        self.check_node(root)?;
+
+        let mut done = BTreeMap::new();
+        for node in self.post_order(root, |_| false) {
+            let r = match *self.get_op(node).unwrap() {
+                Op::Input(..) | Op::Var(..) | Op::Const(..) => node,
+                Op::Unary(op, a) => {
+                    let a = *done.get(&a).unwrap();
+                    self.optimize_unary(op, a)?
+                }
+                Op::Binary(op, a, b) => {
+                    let a = *done.get(&a).unwrap();
+                    let b = *done.get(&b).unwrap();
+                    self.optimize_binary(op, a, b)?
+                }
+            };
+            done.insert(node, r);
+        }
+        Ok(*done.get(&root).unwrap())
+    }
+
+    /// Builds `op(a)`, where `a` has already been optimized
+    fn optimize_unary(
+        &mut self,
+        op: UnaryOpcode,
+        a: Node,
+    ) -> Result<Node, Error> {
+        use UnaryOpcode as U;
+        match (op, *self.get_op(a).unwrap()) {
+            (U::Neg, Op::Unary(U::Neg, b)) => Ok(b),
+            (U::Abs | U::Square, Op::Unary(U::Neg | U::Abs, b)) => {
+                self.op_unary(b, op)
+            }
+            (U::Abs, Op::Unary(U::Square, _)) => Ok(a),
+            (
+                U::Floor | U::Ceil | U::Round,
+                Op::Unary(U::Floor | U::Ceil | U::Round, _),
+            ) => Ok(a),
+            (U::Not, Op::Unary(U::Not, b))
+                if matches!(self.get_op(b), Some(Op::Unary(U::Not, _))) =>
+            {
+                Ok(b)
+            }
+            _ => self.op_unary(a, op),
+        }
+    }
+
+    /// Builds `op(a, b)`, where `a` and `b` have already been optimized
+    fn optimize_binary(
+        &mut self,
+        op: BinaryOpcode,
+        a: Node,
+        b: Node,
+    ) -> Result<Node, Error> {
+        use BinaryOpcode as B;
+        let neg = |n: Node| match self.get_op(n) {
+            Some(Op::Unary(UnaryOpcode::Neg, v)) => Some(*v),
+            _ => None,
+        };
+        match (op, neg(a), neg(b)) {
+            (B::Add, _, Some(nb)) => self.sub(a, nb),
+            (B::Add, Some(na), None) => self.sub(b, na),
+            (B::Sub, _, Some(nb)) => self.add(a, nb),
+            (B::Mul, Some(na), Some(nb)) => self.mul(na, nb),
+            (B::And | B::Or, ..) if a == b => Ok(a),
+            _ => self.build_binary(op, a, b),
+        }
+    }
+
+    /// Builds `op(a, b)` through the matching builder (e.g. [`Context::add`]),
+    /// so that constants are folded and trivial operations are skipped
+    fn build_binary(
+        &mut self,
+        op: BinaryOpcode,
+        a: Node,
+        b: Node,
+    ) -> Result<Node, Error> {
+        match op {
+            BinaryOpcode::Add => self.add(a, b),
+            BinaryOpcode::Sub => self.sub(a, b),
+            BinaryOpcode::Mul => self.mul(a, b),
+            BinaryOpcode::Div => self.div(a, b),
+            BinaryOpcode::Atan => self.atan2(a, b),
+            BinaryOpcode::Min => self.min(a, b),
+            BinaryOpcode::Max => self.max(a, b),
+            BinaryOpcode::Mod => self.modulo(a, b),
+            BinaryOpcode::Compare => self.compare(a, b),
+            BinaryOpcode::And => self.and(a, b),
+            BinaryOpcode::Or => self.or(a, b),
+        }
+    }
+
     ////////////////////////////////////////////////////////////////////////////
     /// Evaluates the given node with the provided values for X, Y, and Z.
     ///
@@ -604,6 +1399,9 @@ impl Context {
     ///
     /// This is extremely inefficient; consider converting the node into a
     /// [`Shape`](crate::eval::Shape) and using its evaluators instead.
+    ///
+    /// Returns [`Error::UnknownVariable`] if a variable in the expression is
+    /// missing from `vars`.
     pub fn eval(
         &self,
         root: Node,
@@ -629,7 +1427,9 @@ impl Context {
         let v = match self.get_op(node).ok_or(Error::BadNode)? {
             Op::Var(v) | Op::Input(v) => {
                 let var_name = self.vars.get_by_index(*v).unwrap();
-                *vars.get(var_name).unwrap()
+                *vars
+                    .get(var_name)
+                    .ok_or_else(|| Error::UnknownVariable(var_name.clone()))?
             }
             Op::Const(c) => c.0,
 
@@ -641,8 +1441,28 @@ impl Context {
                     BinaryOpcode::Sub => a - b,
                     BinaryOpcode::Mul => a * b,
                     BinaryOpcode::Div => a / b,
+                    BinaryOpcode::Atan => a.atan2(b),
                     BinaryOpcode::Min => a.min(b),
                     BinaryOpcode::Max => a.max(b),
+                    BinaryOpcode::Mod => a.rem_euclid(b),
+                    BinaryOpcode::Compare => a
+                        .partial_cmp(&b)
+                        .map(|c| c as i8 as f64)
+                        .unwrap_or(f64::NAN),
+                    BinaryOpcode::And => {
+                        if a == 0.0 {
+                            a
+                        } else {
+                            b
+                        }
+                    }
+                    BinaryOpcode::Or => {
+                        if a != 0.0 {
+                            a
+                        } else {
+                            b
+                        }
+                    }
                 }
             }
 
@@ -655,6 +1475,10 @@ impl Context {
                     UnaryOpcode::Recip => 1.0 / a,
                     UnaryOpcode::Sqrt => a.sqrt(),
                     UnaryOpcode::Square => a * a,
+                    UnaryOpcode::Floor => a.floor(),
+                    UnaryOpcode::Ceil => a.ceil(),
+                    UnaryOpcode::Round => a.round(),
+                    UnaryOpcode::Not => (a == 0.0).into(),
                     UnaryOpcode::Sin => a.sin(),
                     UnaryOpcode::Cos => a.cos(),
                     UnaryOpcode::Tan => a.tan(),
@@ -689,46 +1513,80 @@ impl Context {
     /// assert_eq!(ctx.len(), 7);
     /// ```
     ///
+    /// Each line defines a node as `<name> <opcode> <args...>`, where the
+    /// arguments are the names of previously defined nodes.  Supported opcodes
+    /// are
+    /// - `const <value>` for a constant
+    /// - `var-x`, `var-y`, `var-z` for the input coordinates
+    /// - `var-<name>` for any other named variable
+    /// - the [name](UnaryOpcode::name) of any unary or binary opcode, e.g.
+    ///   `sin`, `recip`, `atan2`, or `compare`
+    ///
+    /// Empty lines and lines beginning with `#` are ignored, and the last node
+    /// is returned as the root.  Malformed lines (including lines with extra
+    /// fields) return an error with their (1-indexed) line number.
+    ///
     /// This representation is loosely defined and only intended for use in
-    /// quick experiments.
+    /// quick experiments; see [`Context::read_text`] for a stable format.
     pub fn from_text<R: Read>(r: R) -> Result<(Self, Node), Error> {
         let reader = BufReader::new(r);
         let mut ctx = Self::new();
         let mut seen = BTreeMap::new();
         let mut last = None;
 
-        for line in reader.lines().map(|line| line.unwrap()) {
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line_num = i + 1;
+            let line = line.trim();
             if line.is_empty() || line.starts_with('#') {
                 continue;
             }
             let mut iter = line.split_whitespace();
-            let i: String = iter.next().unwrap().to_owned();
-            let opcode = iter.next().unwrap();
+            let name = iter.next().unwrap(); // the line is not empty
+            let opcode = iter.next().ok_or(Error::MissingField(line_num))?;
 
+            let mut next = || iter.next().ok_or(Error::MissingField(line_num));
             let mut pop = || {
-                let txt = iter.next().unwrap();
-                seen.get(txt)
-                    .cloned()
-                    .ok_or_else(|| Error::UnknownVariable(txt.to_string()))
+                let txt = next()?;
+                seen.get(txt).cloned().ok_or_else(|| {
+                    Error::UndefinedNode(line_num, txt.to_owned())
+                })
             };
             let node = match opcode {
-                "const" => ctx.constant(iter.next().unwrap().parse().unwrap()),
+                "const" => {
+                    let txt = next()?;
+                    let v = txt.parse().map_err(|_| {
+                        Error::BadConstant(line_num, txt.to_owned())
+                    })?;
+                    ctx.constant(v)
+                }
                 "var-x" => ctx.x(),
                 "var-y" => ctx.y(),
                 "var-z" => ctx.z(),
-                "abs" => ctx.abs(pop()?)?,
-                "neg" => ctx.neg(pop()?)?,
-                "sqrt" => ctx.sqrt(pop()?)?,
-                "square" => ctx.square(pop()?)?,
-                "add" => ctx.add(pop()?, pop()?)?,
-                "mul" => ctx.mul(pop()?, pop()?)?,
-                "min" => ctx.min(pop()?, pop()?)?,
-                "max" => ctx.max(pop()?, pop()?)?,
-                "div" => ctx.div(pop()?, pop()?)?,
-                "sub" => ctx.sub(pop()?, pop()?)?,
-                op => return Err(Error::UnknownOpcode(op.to_owned())),
+                op => {
+                    if let Some(v) = op.strip_prefix("var-") {
+                        ctx.var(v).map_err(|_| {
+                            Error::ReservedVarName(line_num, v.to_owned())
+                        })?
+                    } else if let Some(op) = UnaryOpcode::from_name(op) {
+                        let a = pop()?;
+                        ctx.op_unary(a, op)?
+                    } else if let Some(op) = BinaryOpcode::from_name(op) {
+                        let a = pop()?;
+                        let b = pop()?;
+                        ctx.build_binary(op, a, b)?
+                    } else {
+                        return Err(Error::UnknownOpcode(
+                            line_num,
+                            op.to_owned(),
+                        ));
+                    }
+                }
             };
-            seen.insert(i, node);
+            if iter.next().is_some() {
+                return Err(Error::ExtraField(line_num));
+            }
+            seen.insert(name.to_owned(), node);
             last = Some(node);
         }
         match last {
@@ -738,6 +1596,9 @@ impl Context {
     }
 
     /// Converts the entire context into a GraphViz drawing
+    ///
+    /// See [`Context::dot_subgraph`] and [`Context::dot_annotated`] to draw
+    /// only the nodes used by a particular root.
     pub fn dot(&self) -> String {
         let mut out = "digraph mygraph{\n".to_owned();
         for node in self.ops.keys() {
@@ -762,29 +1623,8 @@ impl Context {
                 let v = self.vars.get_by_index(*v).unwrap();
                 out += v;
             }
-            Op::Binary(op, ..) => match op {
-                BinaryOpcode::Add => out += "add",
-                BinaryOpcode::Sub => out += "sub",
-                BinaryOpcode::Mul => out += "mul",
-                BinaryOpcode::Div => out += "div",
-                BinaryOpcode::Min => out += "min",
-                BinaryOpcode::Max => out += "max",
-            },
-            Op::Unary(op, ..) => match op {
-                UnaryOpcode::Neg => out += "neg",
-                UnaryOpcode::Abs => out += "abs",
-                UnaryOpcode::Recip => out += "recip",
-                UnaryOpcode::Sqrt => out += "sqrt",
-                UnaryOpcode::Square => out += "square",
-                UnaryOpcode::Sin => out += "sin",
-                UnaryOpcode::Cos => out += "cos",
-                UnaryOpcode::Tan => out += "tan",
-                UnaryOpcode::Asin => out += "asin",
-                UnaryOpcode::Acos => out += "acos",
-                UnaryOpcode::Atan => out += "atan",
-                UnaryOpcode::Exp => out += "exp",
-                UnaryOpcode::Ln => out += "ln",
-            },
+            Op::Binary(op, ..) => out += op.name(),
+            Op::Unary(op, ..) => out += op.name(),
         };
         write!(
             out,
@@ -913,4 +1753,744 @@ mod test {
         let v = ctx.remap_xyz(s, [one, y, z]).unwrap();
         assert_eq!(ctx.eval_xyz(v, 0.0, 1.0, 0.0).unwrap(), 4.0);
     }
+
+    #[test]
+    fn test_deriv() {
+        use crate::{
+            eval::{BulkEvaluator, EzShape, MathShape, Shape},
+            vm::VmShape,
+        };
+
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+
+        // a = x / 2 + y / 4, b = y - z * 0.65
+        let a = ctx.mul(x, 0.5).unwrap();
+        let ya = ctx.mul(y, 0.25).unwrap();
+        let a = ctx.add(a, ya).unwrap();
+        let zb = ctx.mul(z, 0.65).unwrap();
+        let b = ctx.sub(y, zb).unwrap();
+
+        let mut nodes = vec![];
+        for f in [
+            Context::neg::<Node>,
+            Context::abs,
+            Context::recip,
+            Context::sqrt,
+            Context::square,
+            Context::floor,
+            Context::ceil,
+            Context::round,
+            Context::not,
+            Context::sin,
+            Context::cos,
+            Context::tan,
+            Context::asin,
+            Context::acos,
+            Context::atan,
+            Context::exp,
+            Context::ln,
+        ] {
+            nodes.push(f(&mut ctx, a).unwrap());
+        }
+        for f in [
+            Context::add::<Node, Node>,
+            Context::sub,
+            Context::mul,
+            Context::div,
+            Context::atan2,
+            Context::min,
+            Context::max,
+            Context::modulo,
+            Context::compare,
+            Context::and,
+            Context::or,
+        ] {
+            nodes.push(f(&mut ctx, a, b).unwrap());
+        }
+        let c = ctx.compare(a, b).unwrap();
+        let ax = ctx.mul(a, x).unwrap();
+        nodes.push(ctx.if_nonzero_else(c, ax, b).unwrap());
+
+        let vs = [-1.3, -0.7, -0.2, 0.3, 0.9, 1.4];
+        let mut xs = vec![];
+        let mut ys = vec![];
+        let mut zs = vec![];
+        for (i, &vx) in vs.iter().enumerate() {
+            for (j, &vy) in vs.iter().enumerate() {
+                xs.push(vx);
+                ys.push(vy);
+                zs.push(vs[(i + j) % vs.len()]);
+            }
+        }
+
+        let mut eval = VmShape::new_grad_slice_eval();
+        for node in nodes {
+            let shape = VmShape::new(&ctx, node).unwrap();
+            let tape = shape.ez_grad_slice_tape();
+            let out = eval.eval(&tape, &xs, &ys, &zs, &[]).unwrap().output(0);
+            for (i, v) in [x, y, z].into_iter().enumerate() {
+                let d = ctx.deriv(node, v).unwrap();
+                for (j, g) in out.iter().enumerate() {
+                    let g = g.d(i) as f64;
+                    let e = ctx
+                        .eval_xyz(d, xs[j] as f64, ys[j] as f64, zs[j] as f64)
+                        .unwrap();
+                    if !e.is_finite() || !g.is_finite() {
+                        continue;
+                    }
+                    assert!(
+                        (e - g).abs() <= 1e-4 * e.abs().max(1.0),
+                        "derivative mismatch for {:?} at {j}: {e} != {g}",
+                        ctx.get_op(node).unwrap()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_deriv_folding() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let s = ctx.sin(x).unwrap();
+        let m = ctx.mul(s, 3.0).unwrap();
+
+        let dy = ctx.deriv(m, y).unwrap();
+        assert_eq!(ctx.const_value(dy).unwrap(), Some(0.0));
+
+        let a = ctx.var("a").unwrap();
+        let ma = ctx.mul(m, a).unwrap();
+        let da = ctx.deriv(ma, a).unwrap();
+        assert_eq!(da, m);
+
+        assert!(matches!(ctx.deriv(m, s), Err(Error::NotAVariable)));
+        let mut other = Context::new();
+        for i in 0..100 {
+            other.constant(i as f64);
+        }
+        let bad = other.constant(100.0);
+        assert!(matches!(ctx.deriv(m, bad), Err(Error::BadNode)));
+    }
+
+    /// Writes every node in the context in the format used by `from_text`
+    fn to_vm(ctx: &Context) -> String {
+        let mut out = String::new();
+        for node in ctx.ops.keys() {
+            write!(out, "_{} ", node.get()).unwrap();
+            match ctx.get_op(node).unwrap() {
+                Op::Input(v) => {
+                    let name = ctx.get_var_by_index(*v).unwrap();
+                    writeln!(out, "var-{}", name.to_lowercase()).unwrap()
+                }
+                Op::Var(v) => {
+                    let name = ctx.get_var_by_index(*v).unwrap();
+                    writeln!(out, "var-{name}").unwrap()
+                }
+                Op::Const(c) => writeln!(out, "const {c}").unwrap(),
+                Op::Unary(op, a) => {
+                    writeln!(out, "{} _{}", op.name(), a.get()).unwrap()
+                }
+                Op::Binary(op, a, b) => {
+                    let (a, b) = (a.get(), b.get());
+                    writeln!(out, "{} _{a} _{b}", op.name()).unwrap()
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_optimize() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+
+        let nx = ctx.neg(x).unwrap();
+        let ny = ctx.neg(y).unwrap();
+        let nnx = ctx.neg(nx).unwrap();
+        assert_eq!(ctx.optimize(nnx).unwrap(), x);
+
+        let ax = ctx.abs(x).unwrap();
+        let sx = ctx.square(x).unwrap();
+        for (a, op) in [(nx, UnaryOpcode::Abs), (ax, UnaryOpcode::Abs)] {
+            let n = ctx.op_unary(a, op).unwrap();
+            assert_eq!(ctx.optimize(n).unwrap(), ax);
+        }
+        for a in [nx, ax] {
+            let n = ctx.square(a).unwrap();
+            assert_eq!(ctx.optimize(n).unwrap(), sx);
+        }
+        let n = ctx.abs(sx).unwrap();
+        assert_eq!(ctx.optimize(n).unwrap(), sx);
+        let n = ctx.sqrt(sx).unwrap();
+        assert_eq!(ctx.optimize(n).unwrap(), n);
+
+        let r = ctx.round(x).unwrap();
+        let n = ctx.floor(r).unwrap();
+        let n = ctx.ceil(n).unwrap();
+        assert_eq!(ctx.optimize(n).unwrap(), r);
+
+        let n1 = ctx.not(x).unwrap();
+        let n2 = ctx.not(n1).unwrap();
+        let n3 = ctx.not(n2).unwrap();
+        assert_eq!(ctx.optimize(n2).unwrap(), n2);
+        assert_eq!(ctx.optimize(n3).unwrap(), n1);
+
+        let sub = ctx.sub(x, y).unwrap();
+        let n = ctx.add(x, ny).unwrap();
+        assert_eq!(ctx.optimize(n).unwrap(), sub);
+        let n = ctx.add(ny, x).unwrap();
+        assert_eq!(ctx.optimize(n).unwrap(), sub);
+        let add = ctx.add(x, y).unwrap();
+        let n = ctx.sub(x, ny).unwrap();
+        assert_eq!(ctx.optimize(n).unwrap(), add);
+        let mul = ctx.mul(x, y).unwrap();
+        let n = ctx.mul(nx, ny).unwrap();
+        assert_eq!(ctx.optimize(n).unwrap(), mul);
+
+        // Children which become equal after rewriting are merged
+        let n = ctx.min(nnx, x).unwrap();
+        assert_eq!(ctx.optimize(n).unwrap(), x);
+        let n = ctx.mul(nnx, x).unwrap();
+        assert_eq!(ctx.optimize(n).unwrap(), sx);
+        for op in [BinaryOpcode::Max, BinaryOpcode::And, BinaryOpcode::Or] {
+            let n = ctx.op_binary(nnx, x, op).unwrap();
+            assert_eq!(ctx.optimize(n).unwrap(), x);
+        }
+
+        // Commutative operands are sorted
+        let n = ctx.ops.insert(Op::Binary(BinaryOpcode::Add, y, x));
+        assert_ne!(n, add);
+        assert_eq!(ctx.optimize(n).unwrap(), add);
+
+        // This isn't valid for infinite or NaN values
+        let n = ctx.sub(x, x).unwrap();
+        assert_eq!(ctx.optimize(n).unwrap(), n);
+
+        let mut other = Context::new();
+        for _ in 0..10 {
+            other.z();
+        }
+        assert!(matches!(other.optimize(n), Err(Error::BadNode)));
+    }
+
+    #[test]
+    fn test_optimize_values() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let a = ctx.mul(x, 0.5).unwrap();
+        let a = ctx.add(a, y).unwrap();
+        let b = ctx.sub(y, z).unwrap();
+
+        let na = ctx.neg(a).unwrap();
+        let nb = ctx.neg(b).unwrap();
+        let nna = ctx.neg(na).unwrap();
+        let mut nodes = vec![nna];
+        for v in [a, na, nb] {
+            for f in [Context::abs::<Node>, Context::square, Context::round] {
+                let n = f(&mut ctx, v).unwrap();
+                nodes.push(n);
+                nodes.push(ctx.abs(n).unwrap());
+                nodes.push(ctx.square(n).unwrap());
+                nodes.push(ctx.sqrt(n).unwrap());
+                nodes.push(ctx.floor(n).unwrap());
+            }
+            let n = ctx.not(v).unwrap();
+            let n = ctx.not(n).unwrap();
+            nodes.push(ctx.not(n).unwrap());
+        }
+        for (lhs, rhs) in [(a, nb), (na, b), (na, nb), (nna, a), (b, nna)] {
+            for f in [
+                Context::add::<Node, Node>,
+                Context::sub,
+                Context::mul,
+                Context::div,
+                Context::min,
+                Context::max,
+                Context::and,
+                Context::or,
+                Context::atan2,
+                Context::modulo,
+                Context::compare,
+            ] {
+                nodes.push(f(&mut ctx, lhs, rhs).unwrap());
+            }
+        }
+        let sum = nodes
+            .iter()
+            .try_fold(nb, |acc, n| ctx.add(acc, *n))
+            .unwrap();
+        nodes.push(sum);
+
+        let opt = nodes
+            .iter()
+            .map(|n| ctx.optimize(*n).unwrap())
+            .collect::<Vec<_>>();
+        assert!(nodes.iter().zip(&opt).any(|(a, b)| a != b));
+
+        let values = [
+            -2.5,
+            -1.0,
+            -0.0,
+            0.0,
+            0.25,
+            3.0,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NAN,
+        ];
+        for (&n, &o) in nodes.iter().zip(&opt) {
+            for &vx in &values {
+                for &vy in &values {
+                    for &vz in &values {
+                        let a = ctx.eval_xyz(n, vx, vy, vz).unwrap();
+                        let b = ctx.eval_xyz(o, vx, vy, vz).unwrap();
+                        assert!(
+                            a == b || (a.is_nan() && b.is_nan()),
+                            "mismatch at ({vx}, {vy}, {vz}): {a} != {b}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_optimize_f32_range() {
+        use crate::{
+            eval::{BulkEvaluator, EzShape, MathShape, Shape},
+            vm::VmShape,
+        };
+
+        // Squares of these values overflow or underflow in single precision
+        let xs = [1e20, -1e20, 1e30, 1e-30, -1e-30, 3.0];
+        let zeros = [0.0; 6];
+
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let nx = ctx.neg(x).unwrap();
+        let ax = ctx.abs(x).unwrap();
+        let sx = ctx.square(x).unwrap();
+        let snx = ctx.square(nx).unwrap();
+        let sax = ctx.square(ax).unwrap();
+        let mut nodes = vec![];
+        for n in [sx, snx, sax] {
+            nodes.push(ctx.sqrt(n).unwrap());
+            nodes.push(ctx.abs(n).unwrap());
+        }
+
+        let mut eval = VmShape::new_float_slice_eval();
+        let mut run = |ctx: &Context, n| {
+            let shape = VmShape::new(ctx, n).unwrap();
+            let tape = shape.ez_float_slice_tape();
+            eval.eval(&tape, &xs, &zeros, &zeros, &[])
+                .unwrap()
+                .output(0)
+                .to_vec()
+        };
+        for n in nodes {
+            let o = ctx.optimize(n).unwrap();
+            let a = run(&ctx, n);
+            let b = run(&ctx, o);
+            assert_eq!(a, b, "mismatch for {:?}", ctx.get_op(n).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_substitute() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let a = ctx.var("a").unwrap();
+        let b = ctx.var("b").unwrap();
+        let xa = ctx.mul(x, a).unwrap();
+        let yb = ctx.mul(y, b).unwrap();
+        let op = ctx.add(xa, yb).unwrap();
+
+        // Swapping variables rebuilds the graph with the new nodes
+        let map = BTreeMap::from([(a, b), (b, a)]);
+        let swapped = ctx.substitute(op, &map).unwrap();
+        let vars = BTreeMap::from([
+            ("X".to_owned(), 2.0),
+            ("Y".to_owned(), 3.0),
+            ("a".to_owned(), 5.0),
+            ("b".to_owned(), 7.0),
+        ]);
+        assert_eq!(ctx.eval(op, &vars).unwrap(), 2.0 * 5.0 + 3.0 * 7.0);
+        assert_eq!(ctx.eval(swapped, &vars).unwrap(), 2.0 * 7.0 + 3.0 * 5.0);
+        assert_eq!(ctx.substitute(swapped, &map).unwrap(), op);
+
+        // Replacing with constants runs the builders' simplifications
+        let zero = ctx.constant(0.0);
+        let one = ctx.constant(1.0);
+        let map = BTreeMap::from([(a, one), (b, zero)]);
+        assert_eq!(ctx.substitute(op, &map).unwrap(), x);
+
+        // Non-variable nodes can also be replaced
+        let map = BTreeMap::from([(xa, y)]);
+        let r = ctx.substitute(op, &map).unwrap();
+        assert_eq!(ctx.eval(r, &vars).unwrap(), 3.0 + 3.0 * 7.0);
+
+        assert_eq!(ctx.substitute(op, &BTreeMap::new()).unwrap(), op);
+        assert!(matches!(
+            ctx.substitute(Node(100), &BTreeMap::new()),
+            Err(Error::BadNode)
+        ));
+        let map = BTreeMap::from([(a, Node(100))]);
+        assert!(matches!(ctx.substitute(op, &map), Err(Error::BadNode)));
+    }
+
+    #[test]
+    fn test_specialize() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let r = ctx.var("r").unwrap();
+        let mode = ctx.var("mode").unwrap();
+
+        // Either a circle of radius `r` or a square of half-width `r`
+        let x2 = ctx.square(x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let sum = ctx.add(x2, y2).unwrap();
+        let dist = ctx.sqrt(sum).unwrap();
+        let circle = ctx.sub(dist, r).unwrap();
+        let ax = ctx.abs(x).unwrap();
+        let ay = ctx.abs(y).unwrap();
+        let m = ctx.max(ax, ay).unwrap();
+        let square = ctx.sub(m, r).unwrap();
+        let c = ctx.compare(mode, 0.0).unwrap();
+        let shape = ctx.if_nonzero_else(c, square, circle).unwrap();
+
+        let bind = |r: f64, mode: f64| {
+            BTreeMap::from([("r".to_owned(), r), ("mode".to_owned(), mode)])
+        };
+        let s = ctx.specialize(shape, &bind(1.5, 0.0)).unwrap();
+        let expected = ctx.sub(dist, 1.5).unwrap();
+        assert_eq!(s, expected);
+        // `or(a, 0)` isn't folded by the builder, so check values instead;
+        // evaluating without `r` or `mode` checks that they were removed.
+        let s = ctx.specialize(shape, &bind(0.5, -1.0)).unwrap();
+        let pts = [(0.0, 0.0), (1.0, 2.0), (-3.0, 0.25), (0.5, -0.5)];
+        for (px, py) in pts {
+            let xy = [("X".to_owned(), px), ("Y".to_owned(), py)];
+            let mut vars = bind(0.5, -1.0);
+            vars.extend(xy.clone());
+            assert_eq!(
+                ctx.eval(s, &BTreeMap::from(xy)).unwrap(),
+                ctx.eval(shape, &vars).unwrap()
+            );
+        }
+
+        // Partial bindings leave the other variables alone
+        let vars = BTreeMap::from([("r".to_owned(), 2.0)]);
+        let s = ctx.specialize(shape, &vars).unwrap();
+        for ((px, py), mode) in pts.into_iter().zip([0.0, 1.0, 0.0, -2.0]) {
+            let xy = [("X".to_owned(), px), ("Y".to_owned(), py)];
+            let mut vars = BTreeMap::from([("mode".to_owned(), mode)]);
+            vars.extend(xy.clone());
+            assert!(ctx.eval(s, &BTreeMap::from(xy)).is_err());
+            let mut all = vars.clone();
+            all.insert("r".to_owned(), 2.0);
+            let expected = ctx.eval(shape, &all).unwrap();
+            assert_eq!(ctx.eval(s, &vars).unwrap(), expected);
+        }
+
+        // Unknown names are ignored
+        let vars = BTreeMap::from([("q".to_owned(), 1.0)]);
+        assert_eq!(ctx.specialize(shape, &vars).unwrap(), shape);
+
+        // Binding inputs folds the whole expression
+        let mut vars = bind(1.0, 0.0);
+        vars.insert("X".to_owned(), 3.0);
+        vars.insert("Y".to_owned(), 4.0);
+        let s = ctx.specialize(shape, &vars).unwrap();
+        assert_eq!(ctx.const_value(s).unwrap(), Some(4.0));
+    }
+
+    #[test]
+    fn test_gc() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let a = ctx.var("a").unwrap();
+        let b = ctx.var("b").unwrap();
+
+        let xa = ctx.mul(x, a).unwrap();
+        let s1 = ctx.add(xa, y).unwrap();
+        let s2 = ctx.sub(xa, 2.0).unwrap();
+        let dead = ctx.div(z, b).unwrap();
+        let dead = ctx.sqrt(dead).unwrap();
+        assert_eq!(ctx.len(), 11);
+        assert_eq!(ctx.vars.len(), 5);
+
+        let vars = BTreeMap::from([
+            ("X".to_owned(), 1.5),
+            ("Y".to_owned(), -0.5),
+            ("a".to_owned(), 3.0),
+        ]);
+        let v1 = ctx.eval(s1, &vars).unwrap();
+        let v2 = ctx.eval(s2, &vars).unwrap();
+
+        let remap = ctx.gc(&[s1, s2]).unwrap();
+        assert_eq!(ctx.len(), 7); // x, a, x * a, y, x * a + y, 2, x * a - 2
+        assert_eq!(ctx.vars.len(), 3); // X, Y, a
+        assert_eq!(remap.len(), 7);
+        assert!(!remap.contains_key(&dead));
+        assert!(!remap.contains_key(&z));
+        assert!(!remap.contains_key(&b));
+        assert_eq!(ctx.eval(remap[&s1], &vars).unwrap(), v1);
+        assert_eq!(ctx.eval(remap[&s2], &vars).unwrap(), v2);
+
+        // Surviving nodes are deduplicated against new nodes as usual
+        assert_eq!(ctx.x(), remap[&x]);
+        assert_eq!(ctx.var("a").unwrap(), remap[&a]);
+        assert_eq!(ctx.len(), 7);
+
+        // Collecting with no roots empties the context
+        let remap = ctx.gc(&[]).unwrap();
+        assert!(remap.is_empty());
+        assert!(ctx.is_empty());
+        assert_eq!(ctx.vars.len(), 0);
+    }
+
+    #[test]
+    fn test_gc_bad_root() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let sum = ctx.add(x, y).unwrap();
+        assert!(matches!(ctx.gc(&[sum, Node(100)]), Err(Error::BadNode)));
+        assert_eq!(ctx.len(), 3);
+        assert_eq!(ctx.eval_xyz(sum, 1.0, 2.0, 0.0).unwrap(), 3.0);
+    }
+
+    #[test]
+    fn test_import() {
+        let mut a = Context::new();
+        let x = a.x();
+        let r = a.var("r").unwrap();
+        let x2 = a.square(x).unwrap();
+        let sum = a.add(x2, r).unwrap();
+        let shape = a.min(sum, x2).unwrap();
+
+        let mut b = Context::new();
+        let y = b.y();
+        let bx = b.x();
+        let bx2 = b.square(bx).unwrap();
+        let len = b.len();
+
+        let out = b.import(&a, shape).unwrap();
+        assert_eq!(b.len(), len + 3); // r, x^2 + r, and min
+        let br = b.var("r").unwrap();
+        assert_eq!(b.var_name(br).unwrap(), Some("r"));
+        assert_eq!(b.len(), len + 3);
+        let Some(Op::Binary(BinaryOpcode::Min, lhs, rhs)) = b.get_op(out)
+        else {
+            panic!("expected min");
+        };
+        assert_eq!(*lhs, bx2);
+        assert!(matches!(
+            b.get_op(*rhs),
+            Some(Op::Binary(BinaryOpcode::Add, n, m)) if *n == bx2 && *m == br
+        ));
+
+        for v in [-1.5, 0.0, 0.5, 3.0] {
+            let vars = BTreeMap::from([
+                ("X".to_owned(), v),
+                ("Y".to_owned(), 0.0),
+                ("r".to_owned(), -2.0),
+            ]);
+            assert_eq!(
+                a.eval(shape, &vars).unwrap(),
+                b.eval(out, &vars).unwrap()
+            );
+        }
+
+        // Importing again is a no-op
+        assert_eq!(b.import(&a, shape).unwrap(), out);
+        assert_eq!(b.import(&a, x).unwrap(), bx);
+        assert_eq!(b.len(), len + 3);
+        assert_ne!(b.import(&a, x).unwrap(), y);
+
+        assert!(matches!(b.import(&a, Node(100)), Err(Error::BadNode)));
+    }
+
+    #[test]
+    fn test_gc_import_commutative() {
+        for f in [
+            Context::add::<Node, Node>,
+            Context::mul,
+            Context::min,
+            Context::max,
+        ] {
+            let mut ctx = Context::new();
+            let x = ctx.x();
+            let y = ctx.y();
+            let n = f(&mut ctx, x, y).unwrap();
+
+            // Rebuilding the expression after renumbering finds the same node
+            let remap = ctx.gc(&[n]).unwrap();
+            let (x, y, n) = (remap[&x], remap[&y], remap[&n]);
+            assert_eq!(f(&mut ctx, x, y).unwrap(), n);
+            assert_eq!(f(&mut ctx, y, x).unwrap(), n);
+            assert_eq!(ctx.len(), 3);
+
+            // Same thing when importing into a context with a different order
+            let mut b = Context::new();
+            b.z();
+            let by = b.y();
+            let bx = b.x();
+            let m = b.import(&ctx, n).unwrap();
+            assert_eq!(f(&mut b, bx, by).unwrap(), m);
+            assert_eq!(f(&mut b, by, bx).unwrap(), m);
+            assert_eq!(b.len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_from_text_models() {
+        for model in [
+            include_str!("../../../../models/colonnade.vm"),
+            include_str!("../../../../models/hi.vm"),
+            include_str!("../../../../models/prospero.vm"),
+            include_str!("../../../../models/quarter.vm"),
+        ] {
+            let (ctx, root) = Context::from_text(model.as_bytes()).unwrap();
+            let txt = to_vm(&ctx);
+            let (new_ctx, new_root) =
+                Context::from_text(txt.as_bytes()).unwrap();
+            assert_eq!(new_ctx.len(), ctx.len());
+            assert_eq!(new_root, root);
+            assert_eq!(to_vm(&new_ctx), txt);
+            for (x, y, z) in
+                [(0.0, 0.0, 0.0), (0.5, -0.25, 0.1), (-0.7, 0.8, 1.0)]
+            {
+                let a = ctx.eval_xyz(root, x, y, z).unwrap();
+                let b = new_ctx.eval_xyz(new_root, x, y, z).unwrap();
+                assert_eq!(a.to_bits(), b.to_bits());
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_text_opcodes() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let a = ctx.var("a").unwrap();
+        let mut prev = ctx.add(x, a).unwrap();
+        for op in [
+            UnaryOpcode::Neg,
+            UnaryOpcode::Abs,
+            UnaryOpcode::Recip,
+            UnaryOpcode::Sqrt,
+            UnaryOpcode::Square,
+            UnaryOpcode::Floor,
+            UnaryOpcode::Ceil,
+            UnaryOpcode::Round,
+            UnaryOpcode::Not,
+            UnaryOpcode::Sin,
+            UnaryOpcode::Cos,
+            UnaryOpcode::Tan,
+            UnaryOpcode::Asin,
+            UnaryOpcode::Acos,
+            UnaryOpcode::Atan,
+            UnaryOpcode::Exp,
+            UnaryOpcode::Ln,
+        ] {
+            prev = ctx.op_unary(prev, op).unwrap();
+        }
+        for (i, op) in [
+            BinaryOpcode::Add,
+            BinaryOpcode::Sub,
+            BinaryOpcode::Mul,
+            BinaryOpcode::Div,
+            BinaryOpcode::Atan,
+            BinaryOpcode::Min,
+            BinaryOpcode::Max,
+            BinaryOpcode::Mod,
+            BinaryOpcode::Compare,
+            BinaryOpcode::And,
+            BinaryOpcode::Or,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let other = [x, y, z][i % 3];
+            prev = ctx.op_binary(prev, other, op).unwrap();
+        }
+        let root = prev;
+
+        let txt = to_vm(&ctx);
+        for op in ["recip", "sin", "atan2", "mod", "compare", "var-a"] {
+            assert!(txt.contains(op), "missing {op}");
+        }
+        let (new_ctx, new_root) = Context::from_text(txt.as_bytes()).unwrap();
+        assert_eq!(new_ctx.len(), ctx.len());
+        let vars: BTreeMap<String, f64> =
+            [("X", 0.25), ("Y", -0.5), ("Z", 0.75), ("a", 0.125)]
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v))
+                .collect();
+        let v1 = ctx.eval(root, &vars).unwrap();
+        let v2 = new_ctx.eval(new_root, &vars).unwrap();
+        assert_eq!(v1.to_bits(), v2.to_bits());
+    }
+
+    #[test]
+    fn test_from_text_errors() {
+        let read = |s: &str| Context::from_text(s.as_bytes());
+        assert!(matches!(read(""), Err(Error::EmptyFile)));
+        assert!(matches!(read("# hello\n\n  \n"), Err(Error::EmptyFile)));
+        assert!(matches!(read("a var-x\nb"), Err(Error::MissingField(2))));
+        assert!(matches!(
+            read("# comment\na var-x\nb add a"),
+            Err(Error::MissingField(3))
+        ));
+        assert!(matches!(read("a const"), Err(Error::MissingField(1))));
+        assert!(matches!(
+            read("\na const 1.x"),
+            Err(Error::BadConstant(2, s)) if s == "1.x"
+        ));
+        assert!(matches!(
+            read("a var-x\nb sin c"),
+            Err(Error::UndefinedNode(2, s)) if s == "c"
+        ));
+        assert!(matches!(
+            read("a var-x\nb frob a"),
+            Err(Error::UnknownOpcode(2, s)) if s == "frob"
+        ));
+        assert!(matches!(
+            read("a var-x\nb var-X"),
+            Err(Error::ReservedVarName(2, s)) if s == "X"
+        ));
+        assert!(matches!(read("a var-x b"), Err(Error::ExtraField(1))));
+        assert!(matches!(
+            read("a var-x\nb const 1 2"),
+            Err(Error::ExtraField(2))
+        ));
+        assert!(matches!(
+            read("a var-x\nb neg a a"),
+            Err(Error::ExtraField(2))
+        ));
+        assert!(matches!(
+            Context::from_text(&[b'a', b' ', 0xff][..]),
+            Err(Error::IoError(..))
+        ));
+
+        let (ctx, root) = read("a var-x\nb var-radius\nc sub a b").unwrap();
+        assert_eq!(ctx.len(), 3);
+        assert!(matches!(
+            ctx.get_op(root),
+            Some(Op::Binary(BinaryOpcode::Sub, ..))
+        ));
+    }
 }
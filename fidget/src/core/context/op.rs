@@ -10,6 +10,10 @@ pub enum UnaryOpcode {
     Recip,
     Sqrt,
     Square,
+    Floor,
+    Ceil,
+    Round,
+    Not,
     Sin,
     Cos,
     Tan,
@@ -28,8 +32,113 @@ pub enum BinaryOpcode {
     Sub,
     Mul,
     Div,
+    Atan,
     Min,
     Max,
+    Mod,
+    Compare,
+    And,
+    Or,
+}
+
+impl UnaryOpcode {
+    /// Returns the lowercase name of this opcode, e.g. `"neg"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            UnaryOpcode::Neg => "neg",
+            UnaryOpcode::Abs => "abs",
+            UnaryOpcode::Recip => "recip",
+            UnaryOpcode::Sqrt => "sqrt",
+            UnaryOpcode::Square => "square",
+            UnaryOpcode::Floor => "floor",
+            UnaryOpcode::Ceil => "ceil",
+            UnaryOpcode::Round => "round",
+            UnaryOpcode::Not => "not",
+            UnaryOpcode::Sin => "sin",
+            UnaryOpcode::Cos => "cos",
+            UnaryOpcode::Tan => "tan",
+            UnaryOpcode::Asin => "asin",
+            UnaryOpcode::Acos => "acos",
+            UnaryOpcode::Atan => "atan",
+            UnaryOpcode::Exp => "exp",
+            UnaryOpcode::Ln => "ln",
+        }
+    }
+
+    /// Looks up a one-argument opcode by its name, as returned by [`UnaryOpcode::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let out = match name {
+            "neg" => UnaryOpcode::Neg,
+            "abs" => UnaryOpcode::Abs,
+            "recip" => UnaryOpcode::Recip,
+            "sqrt" => UnaryOpcode::Sqrt,
+            "square" => UnaryOpcode::Square,
+            "floor" => UnaryOpcode::Floor,
+            "ceil" => UnaryOpcode::Ceil,
+            "round" => UnaryOpcode::Round,
+            "not" => UnaryOpcode::Not,
+            "sin" => UnaryOpcode::Sin,
+            "cos" => UnaryOpcode::Cos,
+            "tan" => UnaryOpcode::Tan,
+            "asin" => UnaryOpcode::Asin,
+            "acos" => UnaryOpcode::Acos,
+            "atan" => UnaryOpcode::Atan,
+            "exp" => UnaryOpcode::Exp,
+            "ln" => UnaryOpcode::Ln,
+            _ => return None,
+        };
+        Some(out)
+    }
+}
+
+impl BinaryOpcode {
+    /// Returns the lowercase name of this opcode, e.g. `"add"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            BinaryOpcode::Add => "add",
+            BinaryOpcode::Sub => "sub",
+            BinaryOpcode::Mul => "mul",
+            BinaryOpcode::Div => "div",
+            BinaryOpcode::Atan => "atan2",
+            BinaryOpcode::Min => "min",
+            BinaryOpcode::Max => "max",
+            BinaryOpcode::Mod => "mod",
+            BinaryOpcode::Compare => "compare",
+            BinaryOpcode::And => "and",
+            BinaryOpcode::Or => "or",
+        }
+    }
+
+    /// Looks up a two-argument opcode by its name, as returned by [`BinaryOpcode::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let out = match name {
+            "add" => BinaryOpcode::Add,
+            "sub" => BinaryOpcode::Sub,
+            "mul" => BinaryOpcode::Mul,
+            "div" => BinaryOpcode::Div,
+            "atan2" => BinaryOpcode::Atan,
+            "min" => BinaryOpcode::Min,
+            "max" => BinaryOpcode::Max,
+            "mod" => BinaryOpcode::Mod,
+            "compare" => BinaryOpcode::Compare,
+            "and" => BinaryOpcode::And,
+            "or" => BinaryOpcode::Or,
+            _ => return None,
+        };
+        Some(out)
+    }
+
+    /// Checks whether [`Context`](crate::context::Context) sorts the
+    /// arguments of this opcode when building it
+    pub(crate) fn is_commutative(&self) -> bool {
+        matches!(
+            self,
+            BinaryOpcode::Add
+                | BinaryOpcode::Mul
+                | BinaryOpcode::Min
+                | BinaryOpcode::Max
+        )
+    }
 }
 
 /// An operation in a math expression.
@@ -67,9 +176,13 @@ impl Op {
         match self {
             Op::Const(..) => "green",
             Op::Var(..) | Op::Input(..) => "red",
-            Op::Binary(BinaryOpcode::Min | BinaryOpcode::Max, ..) => {
-                "dodgerblue"
-            }
+            Op::Binary(
+                BinaryOpcode::Min
+                | BinaryOpcode::Max
+                | BinaryOpcode::And
+                | BinaryOpcode::Or,
+                ..,
+            ) => "dodgerblue",
             Op::Binary(..) | Op::Unary(..) => "goldenrod",
         }
     }
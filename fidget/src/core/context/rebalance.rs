@@ -0,0 +1,401 @@
+//! Reassociation of long `min`, `max`, and `add` chains into balanced trees
+use super::{BinaryOpcode, Context, Node, Op};
+use crate::{
+    eval::{types::Interval, EzShape, MathShape, Shape, TracingEvaluator},
+    vm::VmShape,
+    Error,
+};
+
+use std::collections::BTreeMap;
+
+/// Number of times that cells are subdivided when locating operands in
+/// [`Context::rebalance_spatial`]
+const MAX_DEPTH: usize = 4;
+
+impl Context {
+    /// Rebuilds `root` with chains of `min`, `max`, and `add` reassociated
+    /// into balanced trees
+    ///
+    /// Scenes made of many primitives are often built as left-leaning chains
+    /// (`min(min(min(a, b), c), d)...`), where the depth of the tree is
+    /// proportional to the number of primitives.  Rebalancing reduces the
+    /// depth to `O(log n)`, which reduces register pressure during evaluation.
+    ///
+    /// A node is only merged into a chain if its single parent uses the same
+    /// opcode, so shared subexpressions are never duplicated.  Operands keep
+    /// their original (left-to-right) order.
+    ///
+    /// Reassociating `min` and `max` doesn't change results, but reassociating
+    /// `add` may change floating-point rounding.
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let mut op = ctx.sub(x, 0.0).unwrap();
+    /// for i in 1..8 {
+    ///     let c = ctx.sub(x, i as f64).unwrap();
+    ///     op = ctx.max(op, c).unwrap();
+    /// }
+    /// let balanced = ctx.rebalance(op).unwrap();
+    /// assert_ne!(balanced, op);
+    /// assert_eq!(ctx.eval_xyz(balanced, 5.0, 0.0, 0.0).unwrap(), 5.0);
+    /// ```
+    pub fn rebalance(&mut self, root: Node) -> Result<Node, Error> {
+        self.rebalance_with(root, None)
+    }
+
+    /// Rebalances chains like [`rebalance`](Context::rebalance), grouping
+    /// `min` and `max` operands spatially
+    ///
+    /// Each operand is evaluated with interval arithmetic over `region`, which
+    /// is recursively subdivided to find the cells that its surface may pass
+    /// through.
+    /// Operands are then recursively split about their median position along
+    /// the widest axis, so that nearby operands end up in the same subtree and
+    /// can be pruned together by [`Shape::simplify`].
+    ///
+    /// This works best for scenes made of many small, separated primitives.
+    /// Operands which can't be located (e.g. because they use variables, or
+    /// are nowhere near zero in `region`) are placed at the region's center.
+    /// `add` chains are balanced without reordering.
+    pub fn rebalance_spatial(
+        &mut self,
+        root: Node,
+        region: [Interval; 3],
+    ) -> Result<Node, Error> {
+        self.rebalance_with(root, Some(region))
+    }
+
+    fn rebalance_with(
+        &mut self,
+        root: Node,
+        region: Option<[Interval; 3]>,
+    ) -> Result<Node, Error> {
+        self.check_node(root)?;
+
+        // Find every node's parents (within this subgraph)
+        let order = self.post_order(root, |_| false);
+        let mut parents: BTreeMap<Node, Vec<Node>> = BTreeMap::new();
+        for &node in &order {
+            for c in self.get_op(node).unwrap().iter_children() {
+                parents.entry(c).or_default().push(node);
+            }
+        }
+
+        // A node is absorbed into its parent's chain if it's used exactly
+        // once, by a node with the same (associative) opcode
+        let absorbed = |ctx: &Context, n: Node| match (
+            chain_opcode(ctx, n),
+            parents.get(&n).map(|p| p.as_slice()),
+        ) {
+            (Some(op), Some([p])) => chain_opcode(ctx, *p) == Some(op),
+            _ => false,
+        };
+
+        let mut done = BTreeMap::new();
+        for node in order {
+            if absorbed(self, node) {
+                continue;
+            }
+            let r = match *self.get_op(node).unwrap() {
+                Op::Input(..) | Op::Var(..) | Op::Const(..) => node,
+                Op::Unary(op, a) => {
+                    let a = *done.get(&a).unwrap();
+                    self.op_unary(a, op)?
+                }
+                Op::Binary(op, a, b) if chain_opcode(self, node).is_none() => {
+                    let a = *done.get(&a).unwrap();
+                    let b = *done.get(&b).unwrap();
+                    self.op_binary(a, b, op)?
+                }
+                Op::Binary(op, ..) => {
+                    // Collect operands from left to right
+                    let mut operands = vec![];
+                    let mut todo = vec![node];
+                    while let Some(n) = todo.pop() {
+                        if n == node || absorbed(self, n) {
+                            let Some(Op::Binary(_, a, b)) = self.get_op(n)
+                            else {
+                                unreachable!("chain nodes must be binary");
+                            };
+                            todo.push(*b);
+                            todo.push(*a);
+                        } else {
+                            operands.push(*done.get(&n).unwrap());
+                        }
+                    }
+                    match region {
+                        Some(region) if op != BinaryOpcode::Add => {
+                            let mut items = operands
+                                .into_iter()
+                                .map(|n| Ok((self.locate(n, region)?, n)))
+                                .collect::<Result<Vec<_>, Error>>()?;
+                            self.build_spatial(op, &mut items)?
+                        }
+                        _ => self.build_balanced(op, &operands)?,
+                    }
+                }
+            };
+            done.insert(node, r);
+        }
+        Ok(*done.get(&root).unwrap())
+    }
+
+    /// Builds a single node in a chain
+    fn build_chain_op(
+        &mut self,
+        op: BinaryOpcode,
+        a: Node,
+        b: Node,
+    ) -> Result<Node, Error> {
+        match op {
+            BinaryOpcode::Min => self.min(a, b),
+            BinaryOpcode::Max => self.max(a, b),
+            BinaryOpcode::Add => self.add(a, b),
+            _ => unreachable!("invalid chain opcode {op:?}"),
+        }
+    }
+
+    /// Builds a balanced tree, preserving operand order
+    fn build_balanced(
+        &mut self,
+        op: BinaryOpcode,
+        nodes: &[Node],
+    ) -> Result<Node, Error> {
+        if let [n] = nodes {
+            return Ok(*n);
+        }
+        let (a, b) = nodes.split_at(nodes.len() / 2);
+        let a = self.build_balanced(op, a)?;
+        let b = self.build_balanced(op, b)?;
+        self.build_chain_op(op, a, b)
+    }
+
+    /// Builds a balanced tree, recursively splitting along the widest axis
+    fn build_spatial(
+        &mut self,
+        op: BinaryOpcode,
+        items: &mut [([f32; 3], Node)],
+    ) -> Result<Node, Error> {
+        if let [(_, n)] = items {
+            return Ok(*n);
+        }
+        let spread = |i: usize| {
+            let (lo, hi) = items.iter().fold(
+                (f32::INFINITY, f32::NEG_INFINITY),
+                |(lo, hi), (p, _)| (lo.min(p[i]), hi.max(p[i])),
+            );
+            hi - lo
+        };
+        let axis = (0..3)
+            .max_by(|a, b| spread(*a).total_cmp(&spread(*b)))
+            .unwrap();
+        items.sort_by(|a, b| a.0[axis].total_cmp(&b.0[axis]));
+
+        let (a, b) = items.split_at_mut(items.len() / 2);
+        let a = self.build_spatial(op, a)?;
+        let b = self.build_spatial(op, b)?;
+        self.build_chain_op(op, a, b)
+    }
+
+    /// Finds the center of the cells which `node`'s surface may pass through
+    fn locate(
+        &self,
+        node: Node,
+        region: [Interval; 3],
+    ) -> Result<[f32; 3], Error> {
+        let center = region.map(|r| r.midpoint());
+        let shape = VmShape::new(self, node)?;
+        let tape = shape.ez_interval_tape();
+        let mut eval = VmShape::new_interval_eval();
+
+        // Recursively subdivide cells which the surface may pass through,
+        // then take the bounding box of the smallest such cells
+        let mut lo = [f32::INFINITY; 3];
+        let mut hi = [f32::NEG_INFINITY; 3];
+        let mut todo = vec![(region, 0)];
+        while let Some((cell @ [x, y, z], depth)) = todo.pop() {
+            let Ok((v, _)) = eval.eval(&tape, x, y, z, &[]) else {
+                return Ok(center);
+            };
+            if !(v.has_nan() || (v.lower() <= 0.0 && v.upper() >= 0.0)) {
+                continue;
+            } else if depth == MAX_DEPTH {
+                for (i, r) in cell.into_iter().enumerate() {
+                    lo[i] = lo[i].min(r.lower());
+                    hi[i] = hi[i].max(r.upper());
+                }
+            } else {
+                let [x, y, z] = cell.map(|r| {
+                    let (a, b) = r.split();
+                    [a, b]
+                });
+                for x in x {
+                    for y in y {
+                        for z in z {
+                            todo.push(([x, y, z], depth + 1));
+                        }
+                    }
+                }
+            }
+        }
+        if lo[0] > hi[0] {
+            Ok(center)
+        } else {
+            Ok(std::array::from_fn(|i| (lo[i] + hi[i]) / 2.0))
+        }
+    }
+}
+
+/// Returns the opcode of `n` if it's an associative operation
+fn chain_opcode(ctx: &Context, n: Node) -> Option<BinaryOpcode> {
+    match ctx.get_op(n) {
+        Some(Op::Binary(
+            op @ (BinaryOpcode::Min | BinaryOpcode::Max | BinaryOpcode::Add),
+            ..,
+        )) => Some(*op),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns the depth of the tree rooted at `n`
+    fn depth(ctx: &Context, n: Node) -> usize {
+        1 + ctx
+            .get_op(n)
+            .unwrap()
+            .iter_children()
+            .map(|c| depth(ctx, c))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Builds a left-leaning chain of circles with the given centers
+    fn circles(ctx: &mut Context, centers: &[[f64; 2]]) -> Node {
+        let x = ctx.x();
+        let y = ctx.y();
+        let mut out = None;
+        for &[cx, cy] in centers {
+            let dx = ctx.sub(x, cx).unwrap();
+            let dx = ctx.square(dx).unwrap();
+            let dy = ctx.sub(y, cy).unwrap();
+            let dy = ctx.square(dy).unwrap();
+            let r = ctx.add(dx, dy).unwrap();
+            let r = ctx.sqrt(r).unwrap();
+            let c = ctx.sub(r, 0.1).unwrap();
+            out = Some(match out {
+                Some(prev) => ctx.min(prev, c).unwrap(),
+                None => c,
+            });
+        }
+        out.unwrap()
+    }
+
+    #[test]
+    fn test_rebalance() {
+        let mut ctx = Context::new();
+        // Shuffled circles in an 8x8 grid
+        let centers = (0..64)
+            .map(|i| {
+                let j = i * 37 % 64;
+                [(j % 8) as f64 / 4.0 - 0.875, (j / 8) as f64 / 4.0 - 0.875]
+            })
+            .collect::<Vec<_>>();
+        let root = circles(&mut ctx, &centers);
+        assert_eq!(depth(&ctx, root), 69);
+
+        let balanced = ctx.rebalance(root).unwrap();
+        assert_eq!(depth(&ctx, balanced), 12);
+        for i in 0..100 {
+            let x = i as f64 / 50.0 - 1.0;
+            for y in [-0.5, 0.0, 0.05, 0.3] {
+                assert_eq!(
+                    ctx.eval_xyz(root, x, y, 0.0).unwrap(),
+                    ctx.eval_xyz(balanced, x, y, 0.0).unwrap()
+                );
+            }
+        }
+
+        // Rebalancing is idempotent
+        assert_eq!(ctx.rebalance(balanced).unwrap(), balanced);
+
+        let spatial = ctx
+            .rebalance_spatial(root, [Interval::new(-1.0, 1.0); 3])
+            .unwrap();
+        assert_eq!(depth(&ctx, spatial), 12);
+        for i in 0..100 {
+            let x = i as f64 / 50.0 - 1.0;
+            for y in [-0.5, 0.0, 0.05, 0.3] {
+                assert_eq!(
+                    ctx.eval_xyz(root, x, y, 0.0).unwrap(),
+                    ctx.eval_xyz(spatial, x, y, 0.0).unwrap()
+                );
+            }
+        }
+
+        // Grouping nearby circles means that more of the tape is pruned when
+        // evaluating over a grid of cells
+        let simplified_size = |node: Node| {
+            let shape = VmShape::new(&ctx, node).unwrap();
+            let tape = shape.ez_interval_tape();
+            let mut eval = VmShape::new_interval_eval();
+            let cell = |i: usize| {
+                Interval::new(i as f32 / 8.0 - 1.0, (i + 1) as f32 / 8.0 - 1.0)
+            };
+            let mut total = 0;
+            for i in 0..16 {
+                for j in 0..16 {
+                    let z = Interval::new(-1.0, 1.0);
+                    let (_, trace) =
+                        eval.eval(&tape, cell(i), cell(j), z, &[]).unwrap();
+                    total += shape.ez_simplify(trace.unwrap()).unwrap().size();
+                }
+            }
+            total
+        };
+        let a = simplified_size(balanced);
+        let b = simplified_size(spatial);
+        assert!(b < a, "spatial rebalancing didn't help ({b} >= {a})");
+    }
+
+    #[test]
+    fn test_rebalance_shared() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let a = ctx.var("a").unwrap();
+
+        // `xy` is used twice, so it must not be merged into either chain
+        let xy = ctx.min(x, y).unwrap();
+        let lhs = ctx.min(xy, z).unwrap();
+        let rhs = ctx.min(xy, a).unwrap();
+        let root = ctx.max(lhs, rhs).unwrap();
+        assert_eq!(ctx.rebalance(root).unwrap(), root);
+
+        // Different opcodes aren't merged, but their children are rebalanced
+        let mut chain = x;
+        for n in [y, z, a, x] {
+            let s = ctx.sin(n).unwrap();
+            chain = ctx.add(chain, s).unwrap();
+        }
+        let root = ctx.mul(chain, chain).unwrap();
+        let out = ctx.rebalance(root).unwrap();
+        assert_eq!(depth(&ctx, root), 7);
+        assert_eq!(depth(&ctx, out), 6);
+
+        // Variables are allowed when grouping spatially
+        let out = ctx
+            .rebalance_spatial(root, [Interval::new(-1.0, 1.0); 3])
+            .unwrap();
+        assert_eq!(depth(&ctx, out), 6);
+
+        let mut other = Context::new();
+        for _ in 0..10 {
+            other.z();
+        }
+        assert!(matches!(other.rebalance(root), Err(Error::BadNode)));
+    }
+}
@@ -0,0 +1,693 @@
+//! Stable text and binary serialization of [`Context`] graphs
+use super::{BinaryOpcode, Context, Node, Op, UnaryOpcode};
+use crate::Error;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// Current version of both serialization formats
+const VERSION: u32 = 1;
+
+/// Header line for the text format (followed by the version number)
+const TEXT_HEADER: &str = "fidget-context v";
+
+/// Magic bytes at the start of the binary format
+const BINARY_MAGIC: &[u8; 4] = b"FDGT";
+
+/// Stable opcodes for the binary format
+///
+/// These values are part of the format and must never be reassigned.
+mod code {
+    pub const CONST: u8 = 0x00;
+    pub const X: u8 = 0x01;
+    pub const Y: u8 = 0x02;
+    pub const Z: u8 = 0x03;
+    pub const VAR: u8 = 0x04;
+}
+
+const UNARY_CODES: [(UnaryOpcode, u8); 17] = [
+    (UnaryOpcode::Neg, 0x10),
+    (UnaryOpcode::Abs, 0x11),
+    (UnaryOpcode::Recip, 0x12),
+    (UnaryOpcode::Sqrt, 0x13),
+    (UnaryOpcode::Square, 0x14),
+    (UnaryOpcode::Floor, 0x15),
+    (UnaryOpcode::Ceil, 0x16),
+    (UnaryOpcode::Round, 0x17),
+    (UnaryOpcode::Not, 0x18),
+    (UnaryOpcode::Sin, 0x19),
+    (UnaryOpcode::Cos, 0x1a),
+    (UnaryOpcode::Tan, 0x1b),
+    (UnaryOpcode::Asin, 0x1c),
+    (UnaryOpcode::Acos, 0x1d),
+    (UnaryOpcode::Atan, 0x1e),
+    (UnaryOpcode::Exp, 0x1f),
+    (UnaryOpcode::Ln, 0x20),
+];
+
+const BINARY_CODES: [(BinaryOpcode, u8); 11] = [
+    (BinaryOpcode::Add, 0x40),
+    (BinaryOpcode::Sub, 0x41),
+    (BinaryOpcode::Mul, 0x42),
+    (BinaryOpcode::Div, 0x43),
+    (BinaryOpcode::Atan, 0x44),
+    (BinaryOpcode::Min, 0x45),
+    (BinaryOpcode::Max, 0x46),
+    (BinaryOpcode::Mod, 0x47),
+    (BinaryOpcode::Compare, 0x48),
+    (BinaryOpcode::And, 0x49),
+    (BinaryOpcode::Or, 0x4a),
+];
+
+/// A single serialized node, which refers to children by their position in
+/// the serialized node list
+enum RawOp {
+    X,
+    Y,
+    Z,
+    Var(String),
+    Const(f64),
+    Unary(UnaryOpcode, usize),
+    Binary(BinaryOpcode, usize, usize),
+}
+
+impl Context {
+    /// Writes the subgraph rooted at `root` in a human-readable text format
+    ///
+    /// The format is line-based.  The first line is the header
+    /// `fidget-context v1`; each following line defines one node as
+    /// `<index> <opcode> <args...>`.  Node indices start at 0 and increase
+    /// by one on every line, and arguments refer to previously defined nodes
+    /// by index.  The last node is the root.
+    ///
+    /// Opcodes are
+    /// - `x`, `y`, `z` for the input coordinates
+    /// - `var <name>` for a named variable, where the name is the rest of the
+    ///   line, with `\\`, `\n`, and `\r` used as escapes
+    /// - `const <value>` for a constant, which is written with enough digits
+    ///   to round-trip exactly
+    /// - the [name](UnaryOpcode::name) of any unary or binary opcode, followed
+    ///   by one or two argument indices
+    ///
+    /// Empty lines and lines beginning with `#` are ignored when reading.
+    ///
+    /// ```
+    /// # use fidget::context::Context;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let r = ctx.var("r").unwrap();
+    /// let x2 = ctx.square(x).unwrap();
+    /// let root = ctx.sub(x2, r).unwrap();
+    ///
+    /// let mut out = vec![];
+    /// ctx.write_text(root, &mut out).unwrap();
+    /// assert_eq!(
+    ///     std::str::from_utf8(&out).unwrap(),
+    ///     "fidget-context v1\n0 x\n1 var r\n2 square 0\n3 sub 2 1\n"
+    /// );
+    /// ```
+    pub fn write_text<W: Write>(
+        &self,
+        root: Node,
+        mut w: W,
+    ) -> Result<(), Error> {
+        writeln!(w, "{TEXT_HEADER}{VERSION}")?;
+        for (i, op) in self.raw_ops(root)?.into_iter().enumerate() {
+            write!(w, "{i} ")?;
+            match op {
+                RawOp::X => writeln!(w, "x")?,
+                RawOp::Y => writeln!(w, "y")?,
+                RawOp::Z => writeln!(w, "z")?,
+                RawOp::Var(name) => writeln!(w, "var {}", escape(&name))?,
+                RawOp::Const(c) => writeln!(w, "const {c}")?,
+                RawOp::Unary(op, a) => writeln!(w, "{} {a}", op.name())?,
+                RawOp::Binary(op, a, b) => {
+                    writeln!(w, "{} {a} {b}", op.name())?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a graph in the format written by [`Context::write_text`]
+    ///
+    /// Returns a new context and its root node.  Nodes are inserted exactly
+    /// as written (without constant folding), so writing the result produces
+    /// the same text.
+    pub fn read_text<R: Read>(r: R) -> Result<(Self, Node), Error> {
+        let reader = BufReader::new(r);
+        let mut lines =
+            reader.lines().enumerate().filter(|(_, line)| match line {
+                Ok(line) => !line.is_empty() && !line.starts_with('#'),
+                Err(..) => true,
+            });
+
+        let header = lines.next().ok_or(Error::EmptyFile)?.1?;
+        let version = header
+            .strip_prefix(TEXT_HEADER)
+            .and_then(|v| v.parse().ok())
+            .ok_or(Error::BadHeader)?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let mut loader = Loader::default();
+        for (i, line) in lines {
+            let line = line?;
+            let bad = || Error::MalformedNode(line.clone());
+            let (index, rest) = line.split_once(' ').ok_or_else(bad)?;
+            if index.parse() != Ok(loader.nodes.len()) {
+                return Err(bad());
+            }
+            let (opcode, args) = rest.split_once(' ').unwrap_or((rest, ""));
+            if opcode == "var" {
+                let name = unescape(args).ok_or_else(bad)?;
+                loader.push(RawOp::Var(name))?;
+                continue;
+            }
+
+            let mut args = args.split_whitespace();
+            let mut arg = || -> Result<usize, Error> {
+                args.next().and_then(|a| a.parse().ok()).ok_or_else(bad)
+            };
+            let op = match opcode {
+                "x" => RawOp::X,
+                "y" => RawOp::Y,
+                "z" => RawOp::Z,
+                "const" => {
+                    let v = args.next().and_then(|a| a.parse().ok());
+                    RawOp::Const(v.ok_or_else(bad)?)
+                }
+                op => {
+                    if let Some(op) = UnaryOpcode::from_name(op) {
+                        RawOp::Unary(op, arg()?)
+                    } else if let Some(op) = BinaryOpcode::from_name(op) {
+                        RawOp::Binary(op, arg()?, arg()?)
+                    } else {
+                        return Err(Error::UnknownOpcode(i + 1, op.to_owned()));
+                    }
+                }
+            };
+            if args.next().is_some() {
+                return Err(bad());
+            }
+            loader.push(op)?;
+        }
+        loader.finish()
+    }
+
+    /// Writes the subgraph rooted at `root` in a compact binary format
+    ///
+    /// The format begins with the magic bytes `FDGT`, then a `u32` version
+    /// number (currently 1) and a `u32` node count.  Each node is a one-byte
+    /// opcode followed by its payload:
+    /// - Constants store their `f64` bit pattern
+    /// - Variables store a `u32` byte length, then the UTF-8 name
+    /// - Unary and binary operations store one or two `u32` indices of
+    ///   previously defined nodes
+    /// - Inputs have no payload
+    ///
+    /// All integers and floats are little-endian.  As in the text format, the
+    /// last node is the root.
+    ///
+    /// ```
+    /// # use fidget::context::Context;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let root = ctx.sin(x).unwrap();
+    ///
+    /// let mut out = vec![];
+    /// ctx.write_binary(root, &mut out).unwrap();
+    /// let (ctx, root) = Context::read_binary(out.as_slice()).unwrap();
+    /// assert_eq!(ctx.eval_xyz(root, 0.0, 0.0, 0.0).unwrap(), 0.0);
+    /// ```
+    pub fn write_binary<W: Write>(
+        &self,
+        root: Node,
+        mut w: W,
+    ) -> Result<(), Error> {
+        let ops = self.raw_ops(root)?;
+        w.write_all(BINARY_MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        write_u32(&mut w, ops.len())?;
+        for op in ops {
+            match op {
+                RawOp::X => w.write_all(&[code::X])?,
+                RawOp::Y => w.write_all(&[code::Y])?,
+                RawOp::Z => w.write_all(&[code::Z])?,
+                RawOp::Var(name) => {
+                    w.write_all(&[code::VAR])?;
+                    write_u32(&mut w, name.len())?;
+                    w.write_all(name.as_bytes())?;
+                }
+                RawOp::Const(c) => {
+                    w.write_all(&[code::CONST])?;
+                    w.write_all(&c.to_le_bytes())?;
+                }
+                RawOp::Unary(op, a) => {
+                    let (_, c) =
+                        UNARY_CODES.iter().find(|(u, _)| *u == op).unwrap();
+                    w.write_all(&[*c])?;
+                    write_u32(&mut w, a)?;
+                }
+                RawOp::Binary(op, a, b) => {
+                    let (_, c) =
+                        BINARY_CODES.iter().find(|(u, _)| *u == op).unwrap();
+                    w.write_all(&[*c])?;
+                    write_u32(&mut w, a)?;
+                    write_u32(&mut w, b)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a graph in the format written by [`Context::write_binary`]
+    ///
+    /// Returns a new context and its root node.  Nodes are inserted exactly
+    /// as written (without constant folding), so writing the result produces
+    /// the same bytes.
+    pub fn read_binary<R: Read>(mut r: R) -> Result<(Self, Node), Error> {
+        let mut magic = [0u8; 4];
+        read_exact(&mut r, &mut magic)?;
+        if &magic != BINARY_MAGIC {
+            return Err(Error::BadHeader);
+        }
+        let version = read_u32(&mut r)?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let count = read_u32(&mut r)?;
+        let mut loader = Loader::default();
+        for _ in 0..count {
+            let mut opcode = [0u8];
+            read_exact(&mut r, &mut opcode)?;
+            let op = match opcode[0] {
+                code::X => RawOp::X,
+                code::Y => RawOp::Y,
+                code::Z => RawOp::Z,
+                code::VAR => {
+                    let len = read_u32(&mut r)?;
+                    let mut name = vec![];
+                    (&mut r).take(len as u64).read_to_end(&mut name)?;
+                    if name.len() != len as usize {
+                        return Err(Error::UnexpectedEof);
+                    }
+                    let name = String::from_utf8(name).map_err(|_| {
+                        Error::MalformedNode("invalid variable name".to_owned())
+                    })?;
+                    RawOp::Var(name)
+                }
+                code::CONST => {
+                    let mut v = [0u8; 8];
+                    read_exact(&mut r, &mut v)?;
+                    RawOp::Const(f64::from_le_bytes(v))
+                }
+                c => {
+                    if let Some((op, _)) =
+                        UNARY_CODES.iter().find(|(_, u)| *u == c)
+                    {
+                        RawOp::Unary(*op, read_u32(&mut r)? as usize)
+                    } else if let Some((op, _)) =
+                        BINARY_CODES.iter().find(|(_, u)| *u == c)
+                    {
+                        let a = read_u32(&mut r)? as usize;
+                        let b = read_u32(&mut r)? as usize;
+                        RawOp::Binary(*op, a, b)
+                    } else {
+                        return Err(Error::MalformedNode(format!(
+                            "unknown opcode {c:#04x}"
+                        )));
+                    }
+                }
+            };
+            loader.push(op)?;
+        }
+        if r.read(&mut [0u8])? != 0 {
+            return Err(Error::TrailingData);
+        }
+        loader.finish()
+    }
+
+    /// Returns the subgraph rooted at `root` as a list of [`RawOp`]s, with
+    /// children before their parents and `root` as the last item.
+    fn raw_ops(&self, root: Node) -> Result<Vec<RawOp>, Error> {
+        self.check_node(root)?;
+
+        let mut todo = vec![root];
+        let mut seen = BTreeSet::new();
+        while let Some(node) = todo.pop() {
+            if seen.insert(node) {
+                todo.extend(self.get_op(node).unwrap().iter_children());
+            }
+        }
+
+        // Children are always inserted into the context before their parents,
+        // so sorting by node index gives us a topological ordering.
+        let indices: BTreeMap<Node, usize> =
+            seen.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+        let index = |n: &Node| indices[n];
+        let mut out = Vec::with_capacity(seen.len());
+        for &node in &seen {
+            let op = match self.get_op(node).unwrap() {
+                Op::Input(v) => match self.get_var_by_index(*v)? {
+                    "X" => RawOp::X,
+                    "Y" => RawOp::Y,
+                    "Z" => RawOp::Z,
+                    name => {
+                        return Err(Error::UnknownVariable(name.to_owned()))
+                    }
+                },
+                Op::Var(v) => RawOp::Var(self.get_var_by_index(*v)?.to_owned()),
+                Op::Const(c) => RawOp::Const(c.0),
+                Op::Unary(op, a) => RawOp::Unary(*op, index(a)),
+                Op::Binary(op, a, b) => RawOp::Binary(*op, index(a), index(b)),
+            };
+            out.push(op);
+        }
+        Ok(out)
+    }
+}
+
+/// Helper struct to rebuild a [`Context`] from a list of [`RawOp`]s
+#[derive(Default)]
+struct Loader {
+    ctx: Context,
+    /// Maps from serialized index to node in `ctx`
+    nodes: Vec<Node>,
+}
+
+impl Loader {
+    fn push(&mut self, op: RawOp) -> Result<(), Error> {
+        let i = self.nodes.len();
+        let get = |j: usize| {
+            if j < i {
+                Ok(self.nodes[j])
+            } else {
+                Err(Error::BadReference(i, j))
+            }
+        };
+        let node = match op {
+            RawOp::X => self.ctx.x(),
+            RawOp::Y => self.ctx.y(),
+            RawOp::Z => self.ctx.z(),
+            RawOp::Var(name) => self.ctx.var(&name)?,
+            RawOp::Const(c) => self.ctx.constant(c),
+            RawOp::Unary(op, a) => self.ctx.ops.insert(Op::Unary(op, get(a)?)),
+            RawOp::Binary(op, a, b) => {
+                self.ctx.ops.insert(Op::Binary(op, get(a)?, get(b)?))
+            }
+        };
+        self.nodes.push(node);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(Context, Node), Error> {
+        match self.nodes.last() {
+            Some(node) => Ok((self.ctx, *node)),
+            None => Err(Error::EmptyFile),
+        }
+    }
+}
+
+/// Escapes a variable name so that it fits on a single line
+fn escape(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '\r' => out += "\\r",
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Reverses [`escape`], returning `None` if the input has an invalid escape
+fn unescape(name: &str) -> Option<String> {
+    let mut out = String::with_capacity(name.len());
+    let mut iter = name.chars();
+    while let Some(c) = iter.next() {
+        if c == '\\' {
+            match iter.next()? {
+                '\\' => out.push('\\'),
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                _ => return None,
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    Some(out)
+}
+
+fn write_u32<W: Write>(w: &mut W, v: usize) -> Result<(), Error> {
+    let v = u32::try_from(v).map_err(|_| {
+        Error::MalformedNode(format!("{v} is too large to serialize"))
+    })?;
+    w.write_all(&v.to_le_bytes())?;
+    Ok(())
+}
+
+fn read_u32<R: Read>(r: &mut R) -> Result<u32, Error> {
+    let mut v = [0u8; 4];
+    read_exact(r, &mut v)?;
+    Ok(u32::from_le_bytes(v))
+}
+
+/// Wrapper around [`Read::read_exact`] which converts an early EOF into
+/// [`Error::UnexpectedEof`]
+fn read_exact<R: Read>(r: &mut R, buf: &mut [u8]) -> Result<(), Error> {
+    r.read_exact(buf).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => Error::UnexpectedEof,
+        _ => e.into(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(ctx: &Context, root: Node) -> (Context, Node) {
+        let mut text = vec![];
+        ctx.write_text(root, &mut text).unwrap();
+        let (ctx_t, root_t) = Context::read_text(text.as_slice()).unwrap();
+        let mut text2 = vec![];
+        ctx_t.write_text(root_t, &mut text2).unwrap();
+        assert_eq!(text, text2);
+
+        let mut bin = vec![];
+        ctx.write_binary(root, &mut bin).unwrap();
+        let (ctx_b, root_b) = Context::read_binary(bin.as_slice()).unwrap();
+        let mut bin2 = vec![];
+        ctx_b.write_binary(root_b, &mut bin2).unwrap();
+        assert_eq!(bin, bin2);
+
+        // Both formats should produce the same graph
+        let mut bin3 = vec![];
+        ctx_t.write_binary(root_t, &mut bin3).unwrap();
+        assert_eq!(bin, bin3);
+
+        (ctx_b, root_b)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let a = ctx.var("a").unwrap();
+        let b = ctx.var("weird name\\\n\r#").unwrap();
+
+        let mut nodes = vec![x, y, z, a, b];
+        for op in UNARY_CODES.iter().map(|(op, _)| *op) {
+            let prev = *nodes.last().unwrap();
+            nodes.push(ctx.op_unary(prev, op).unwrap());
+        }
+        for op in BINARY_CODES.iter().map(|(op, _)| *op) {
+            let n = nodes.len();
+            nodes.push(ctx.op_binary(nodes[n - 1], nodes[n % 5], op).unwrap());
+        }
+        let c = ctx.constant(0.1);
+        let root = ctx.add(*nodes.last().unwrap(), c).unwrap();
+        let root = ctx.mul(root, -2.5).unwrap();
+        let root = ctx.max(root, f64::INFINITY).unwrap();
+
+        let (new_ctx, new_root) = round_trip(&ctx, root);
+        assert_eq!(new_ctx.len(), ctx.len());
+        assert_eq!(new_ctx.var_name(new_root).unwrap(), None);
+
+        let vars: BTreeMap<String, f64> =
+            [("a", 0.5), ("weird name\\\n\r#", 1.25)]
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v))
+                .collect();
+        for xyz in [(0.1, 0.2, 0.3), (-1.0, 2.0, 0.5)] {
+            let mut vars = vars.clone();
+            vars.insert("X".to_owned(), xyz.0);
+            vars.insert("Y".to_owned(), xyz.1);
+            vars.insert("Z".to_owned(), xyz.2);
+            let v1 = ctx.eval(root, &vars).unwrap();
+            let v2 = new_ctx.eval(new_root, &vars).unwrap();
+            assert_eq!(v1.to_bits(), v2.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let _unused = ctx.add(x, y).unwrap();
+        let root = ctx.sqrt(y).unwrap();
+        let (new_ctx, _) = round_trip(&ctx, root);
+        assert_eq!(new_ctx.len(), 2);
+
+        let mut text = vec![];
+        ctx.write_text(x, &mut text).unwrap();
+        assert_eq!(text, b"fidget-context v1\n0 x\n");
+    }
+
+    #[test]
+    fn test_model() {
+        const PROSPERO: &str = include_str!("../../../../models/prospero.vm");
+        let (ctx, root) = Context::from_text(PROSPERO.as_bytes()).unwrap();
+        let (new_ctx, new_root) = round_trip(&ctx, root);
+        assert_eq!(new_ctx.len(), ctx.len());
+        for (x, y) in [(0.0, 0.0), (0.5, -0.25), (-0.75, 0.8)] {
+            let a = ctx.eval_xyz(root, x, y, 0.0).unwrap();
+            let b = new_ctx.eval_xyz(new_root, x, y, 0.0).unwrap();
+            assert_eq!(a.to_bits(), b.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_constants() {
+        for v in [
+            0.1,
+            -0.0,
+            1e-310,
+            f64::MAX,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ] {
+            let mut ctx = Context::new();
+            let c = ctx.constant(v);
+            let (new_ctx, root) = round_trip(&ctx, c);
+            let out = new_ctx.const_value(root).unwrap().unwrap();
+            assert_eq!(out.to_bits(), v.to_bits());
+        }
+        let mut ctx = Context::new();
+        let c = ctx.constant(f64::NAN);
+        let (new_ctx, root) = round_trip(&ctx, c);
+        assert!(new_ctx.const_value(root).unwrap().unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_text_errors() {
+        let read = |s: &str| Context::read_text(s.as_bytes());
+        assert!(matches!(read(""), Err(Error::EmptyFile)));
+        assert!(matches!(read("# hi\n"), Err(Error::EmptyFile)));
+        assert!(matches!(read("0 x\n"), Err(Error::BadHeader)));
+        assert!(matches!(
+            read("fidget-context v2\n0 x\n"),
+            Err(Error::UnsupportedVersion(2))
+        ));
+        assert!(matches!(read("fidget-context v1\n"), Err(Error::EmptyFile)));
+
+        let body = |s: &str| read(&format!("fidget-context v1\n{s}"));
+        assert!(body("0 x\n\n# comment\n1 sin 0\n").is_ok());
+        assert!(matches!(body("1 x\n"), Err(Error::MalformedNode(..))));
+        assert!(matches!(body("x\n"), Err(Error::MalformedNode(..))));
+        assert!(matches!(body("0 x 1\n"), Err(Error::MalformedNode(..))));
+        assert!(matches!(body("0 const\n"), Err(Error::MalformedNode(..))));
+        assert!(matches!(body("0 const q\n"), Err(Error::MalformedNode(..))));
+        assert!(matches!(
+            body("0 var a\\q\n"),
+            Err(Error::MalformedNode(..))
+        ));
+        assert!(matches!(body("0 var X\n"), Err(Error::ReservedName)));
+        assert!(matches!(
+            body("0 x\n1 add 0\n"),
+            Err(Error::MalformedNode(..))
+        ));
+        assert!(matches!(
+            body("0 x\n1 neg x\n"),
+            Err(Error::MalformedNode(..))
+        ));
+        assert!(matches!(
+            body("0 x\n1 neg 1\n"),
+            Err(Error::BadReference(1, 1))
+        ));
+        assert!(matches!(
+            body("0 x\n1 frob 0\n"),
+            Err(Error::UnknownOpcode(3, s)) if s == "frob"
+        ));
+        assert!(matches!(
+            Context::read_text(&[0xff, 0xfe][..]),
+            Err(Error::IoError(..))
+        ));
+    }
+
+    #[test]
+    fn test_binary_errors() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let a = ctx.var("abc").unwrap();
+        let s = ctx.sub(x, a).unwrap();
+        let root = ctx.mul(s, 2.5).unwrap();
+        let mut good = vec![];
+        ctx.write_binary(root, &mut good).unwrap();
+
+        let read = |s: &[u8]| Context::read_binary(s);
+        assert!(read(&good).is_ok());
+
+        // Every truncation should fail cleanly
+        for i in 0..good.len() {
+            assert!(matches!(read(&good[..i]), Err(Error::UnexpectedEof)));
+        }
+
+        let mut bad = good.clone();
+        bad.push(0);
+        assert!(matches!(read(&bad), Err(Error::TrailingData)));
+
+        let mut bad = good.clone();
+        bad[0] = b'X';
+        assert!(matches!(read(&bad), Err(Error::BadHeader)));
+
+        let mut bad = good.clone();
+        bad[4] = 3;
+        assert!(matches!(read(&bad), Err(Error::UnsupportedVersion(3))));
+
+        let mut bad = good.clone();
+        bad[8..12].copy_from_slice(&0u32.to_le_bytes());
+        assert!(matches!(read(&bad[..12]), Err(Error::EmptyFile)));
+
+        // Node 0 is `x`; make it an unknown opcode
+        let mut bad = good.clone();
+        bad[12] = 0xff;
+        assert!(matches!(
+            read(&bad),
+            Err(Error::MalformedNode(s)) if s == "unknown opcode 0xff"
+        ));
+
+        // Node 1 is `var abc`; make the name invalid UTF-8
+        let mut bad = good.clone();
+        bad[18] = 0xff;
+        assert!(matches!(read(&bad), Err(Error::MalformedNode(..))));
+
+        // Node 1 is `var abc`; give it an enormous length
+        let mut bad = good.clone();
+        bad[14..18].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(read(&bad), Err(Error::UnexpectedEof)));
+
+        // Node 2 is `sub 0 1`; make it refer to itself
+        let mut bad = good.clone();
+        bad[26..30].copy_from_slice(&2u32.to_le_bytes());
+        assert!(matches!(read(&bad), Err(Error::BadReference(2, 2))));
+    }
+}
@@ -0,0 +1,322 @@
+//! Statistics and GraphViz drawings for the subgraph below a single node
+use super::{BinaryOpcode, Context, Node, Op};
+use crate::{context::indexed::Index, Error};
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Summary of the subgraph reachable from a single node, built by
+/// [`Context::stats`]
+///
+/// All counts only consider nodes which are reachable from the root.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GraphStats {
+    /// Number of unique nodes
+    pub nodes: usize,
+    /// Number of nodes of each kind
+    ///
+    /// Operations are keyed by their opcode name (e.g. `"add"`); leaves are
+    /// keyed by `"const"`, `"var"`, or `"input"` (for X, Y, and Z).
+    pub op_counts: BTreeMap<&'static str, usize>,
+    /// Length of the longest path from the root to a leaf, in edges
+    pub depth: usize,
+    /// Largest number of incoming edges to any node
+    pub max_fan_out: usize,
+    /// Number of `min`, `max`, `and`, and `or` nodes, which record choices
+    /// during tracing evaluation
+    pub choices: usize,
+    /// Number of nodes with more than one incoming edge
+    pub shared: usize,
+    /// Sorted list of variable names (excluding X, Y, and Z)
+    pub vars: Vec<String>,
+}
+
+impl GraphStats {
+    /// Returns the fraction of nodes which are shared subexpressions, i.e.
+    /// which have more than one incoming edge
+    pub fn shared_ratio(&self) -> f64 {
+        if self.nodes == 0 {
+            0.0
+        } else {
+            self.shared as f64 / self.nodes as f64
+        }
+    }
+}
+
+impl std::fmt::Display for GraphStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "nodes: {}", self.nodes)?;
+        writeln!(f, "depth: {}", self.depth)?;
+        writeln!(f, "max fan-out: {}", self.max_fan_out)?;
+        writeln!(f, "choices: {}", self.choices)?;
+        writeln!(
+            f,
+            "shared: {} ({:.1}%)",
+            self.shared,
+            self.shared_ratio() * 100.0
+        )?;
+        write!(f, "ops:")?;
+        for (name, count) in &self.op_counts {
+            write!(f, " {name}={count}")?;
+        }
+        writeln!(f)?;
+        write!(f, "vars: [{}]", self.vars.join(", "))
+    }
+}
+
+/// Per-node data collected by [`Context::analyze`]
+struct Analysis {
+    /// Reachable nodes, in order of increasing index
+    nodes: Vec<Node>,
+    /// Depth of the subgraph below each node
+    depth: BTreeMap<Node, usize>,
+    /// Number of incoming edges for each node
+    fan_out: BTreeMap<Node, usize>,
+}
+
+impl Context {
+    /// Computes statistics about the subgraph reachable from `root`
+    ///
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let r = ctx.var("r").unwrap();
+    /// let x2 = ctx.square(x).unwrap();
+    /// let y2 = ctx.square(y).unwrap();
+    /// let sum = ctx.add(x2, y2).unwrap();
+    /// let circle = ctx.sub(sum, r).unwrap();
+    /// let op = ctx.min(circle, x2).unwrap();
+    ///
+    /// let stats = ctx.stats(op).unwrap();
+    /// assert_eq!(stats.nodes, 8);
+    /// assert_eq!(stats.depth, 4);
+    /// assert_eq!(stats.op_counts["square"], 2);
+    /// assert_eq!(stats.op_counts["input"], 2);
+    /// assert_eq!(stats.max_fan_out, 2); // x2 is used twice
+    /// assert_eq!(stats.choices, 1);
+    /// assert_eq!(stats.vars, vec!["r"]);
+    /// ```
+    pub fn stats(&self, root: Node) -> Result<GraphStats, Error> {
+        let a = self.analyze(root)?;
+        self.stats_from(root, &a)
+    }
+
+    /// Builds a [`GraphStats`] from an existing analysis of `root`
+    fn stats_from(
+        &self,
+        root: Node,
+        a: &Analysis,
+    ) -> Result<GraphStats, Error> {
+        let mut out = GraphStats {
+            nodes: a.nodes.len(),
+            depth: a.depth[&root],
+            max_fan_out: a.fan_out.values().copied().max().unwrap_or(0),
+            shared: a.fan_out.values().filter(|f| **f > 1).count(),
+            ..GraphStats::default()
+        };
+        for n in &a.nodes {
+            let op = self.get_op(*n).unwrap();
+            let name = match op {
+                Op::Const(..) => "const",
+                Op::Input(..) => "input",
+                Op::Var(v) => {
+                    out.vars.push(self.get_var_by_index(*v)?.to_owned());
+                    "var"
+                }
+                Op::Unary(op, ..) => op.name(),
+                Op::Binary(op, ..) => op.name(),
+            };
+            *out.op_counts.entry(name).or_default() += 1;
+            if matches!(
+                op,
+                Op::Binary(
+                    BinaryOpcode::Min
+                        | BinaryOpcode::Max
+                        | BinaryOpcode::And
+                        | BinaryOpcode::Or,
+                    ..
+                )
+            ) {
+                out.choices += 1;
+            }
+        }
+        out.vars.sort();
+        Ok(out)
+    }
+
+    /// Converts the subgraph reachable from `root` into a GraphViz drawing
+    ///
+    /// Unlike [`Context::dot`], nodes which aren't used by `root` are omitted.
+    pub fn dot_subgraph(&self, root: Node) -> Result<String, Error> {
+        let a = self.analyze(root)?;
+        Ok(self.dot_nodes(&a.nodes, |_| String::new()))
+    }
+
+    /// Converts the subgraph reachable from `root` into a GraphViz drawing,
+    /// annotated with its [statistics](Context::stats)
+    ///
+    /// The drawing is labelled with the summary from [`GraphStats`], and each
+    /// node is given an external label with its depth and fan-out.
+    pub fn dot_annotated(&self, root: Node) -> Result<String, Error> {
+        let a = self.analyze(root)?;
+        let stats = self.stats_from(root, &a)?;
+        let mut out = self.dot_nodes(&a.nodes, |n| {
+            format!(
+                r#"n{} [xlabel = "d={} f={}"]"#,
+                n.get(),
+                a.depth[&n],
+                a.fan_out[&n]
+            )
+        });
+        out.truncate(out.len() - 2); // remove the closing "}\n"
+        let label = stats.to_string().replace('\n', r"\l") + r"\l";
+        writeln!(out, r#"label = "{label}""#).unwrap();
+        writeln!(out, "labeljust = l").unwrap();
+        out += "}\n";
+        Ok(out)
+    }
+
+    /// Draws the given nodes, with an extra (optional) line for each node
+    fn dot_nodes<F: Fn(Node) -> String>(&self, nodes: &[Node], f: F) -> String {
+        let mut out = "digraph mygraph{\n".to_owned();
+        for node in nodes {
+            let op = self.get_op(*node).unwrap();
+            out += &self.dot_node(*node);
+            out += "\n";
+            let extra = f(*node);
+            if !extra.is_empty() {
+                out += &extra;
+                out += "\n";
+            }
+            out += &op.dot_edges(*node);
+        }
+        out += "}\n";
+        out
+    }
+
+    /// Collects the reachable nodes below `root`, along with their depths and
+    /// fan-outs
+    fn analyze(&self, root: Node) -> Result<Analysis, Error> {
+        self.check_node(root)?;
+
+        let mut nodes = self.post_order(root, |_| false);
+        let mut depth = BTreeMap::new();
+        let mut fan_out = BTreeMap::new();
+        fan_out.insert(root, 0);
+        for &node in &nodes {
+            let mut d = 0;
+            for c in self.get_op(node).unwrap().iter_children() {
+                *fan_out.entry(c).or_default() += 1;
+                d = d.max(depth[&c] + 1);
+            }
+            depth.insert(node, d);
+        }
+        nodes.sort();
+
+        Ok(Analysis {
+            nodes,
+            depth,
+            fan_out,
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let a = ctx.var("a").unwrap();
+        let b = ctx.var("b").unwrap();
+        let _unused = ctx.var("unused").unwrap();
+
+        let xa = ctx.mul(x, a).unwrap();
+        let s = ctx.add(xa, y).unwrap();
+        let t = ctx.sub(xa, b).unwrap();
+        let u = ctx.max(s, t).unwrap();
+        let v = ctx.neg(xa).unwrap();
+        let w = ctx.min(u, v).unwrap();
+        let root = ctx.and(w, 2.0).unwrap();
+        let _unused = ctx.add(root, z).unwrap();
+
+        let stats = ctx.stats(root).unwrap();
+        assert_eq!(stats.nodes, 12);
+        assert_eq!(stats.depth, 5); // and → min → max → add → mul → x
+        assert_eq!(stats.max_fan_out, 3); // xa
+        assert_eq!(stats.shared, 1);
+        assert_eq!(stats.shared_ratio(), 1.0 / 12.0);
+        assert_eq!(stats.choices, 3);
+        assert_eq!(stats.vars, vec!["a", "b"]);
+        let counts: BTreeMap<_, _> = [
+            ("input", 2),
+            ("var", 2),
+            ("const", 1),
+            ("mul", 1),
+            ("add", 1),
+            ("sub", 1),
+            ("neg", 1),
+            ("min", 1),
+            ("max", 1),
+            ("and", 1),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(stats.op_counts, counts);
+
+        let stats = ctx.stats(x).unwrap();
+        assert_eq!(stats.nodes, 1);
+        assert_eq!(stats.depth, 0);
+        assert_eq!(stats.max_fan_out, 0);
+        assert_eq!(stats.shared_ratio(), 0.0);
+        assert!(stats.vars.is_empty());
+
+        assert!(matches!(ctx.stats(Node(100)), Err(Error::BadNode)));
+    }
+
+    #[test]
+    fn test_dot_subgraph() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let r = ctx.var("r").unwrap();
+        let x2 = ctx.square(x).unwrap();
+        let s = ctx.sub(x2, r).unwrap();
+        let unused = ctx.mul(y, 3.0).unwrap();
+
+        let full = ctx.dot();
+        let sub = ctx.dot_subgraph(s).unwrap();
+        assert!(sub.starts_with("digraph mygraph{\n"));
+        assert!(sub.ends_with("}\n"));
+        for n in [x, r, x2, s] {
+            assert!(sub.contains(&format!("n{} [label", n.get())));
+        }
+        for n in [y, unused] {
+            assert!(full.contains(&format!("n{} [label", n.get())));
+            assert!(!sub.contains(&format!("n{} [label", n.get())));
+        }
+        assert!(!sub.contains("xlabel"));
+
+        let annotated = ctx.dot_annotated(s).unwrap();
+        assert!(annotated.ends_with("}\n"));
+        assert!(
+            annotated.contains(&format!("n{} [xlabel = \"d=2 f=0\"]", s.get()))
+        );
+        assert!(
+            annotated.contains(&format!("n{} [xlabel = \"d=0 f=1\"]", x.get()))
+        );
+        assert!(annotated.contains(r#"label = "nodes: 4\ldepth: 2\l"#));
+        assert!(annotated.contains(r"vars: [r]\l"));
+        assert!(!annotated.contains(&format!("n{} [label", y.get())));
+
+        assert!(matches!(ctx.dot_subgraph(Node(100)), Err(Error::BadNode)));
+        assert!(matches!(ctx.dot_annotated(Node(100)), Err(Error::BadNode)));
+    }
+}
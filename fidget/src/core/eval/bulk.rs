@@ -4,7 +4,8 @@
 //! can take advantage of SIMD.
 //!
 //! A bulk evaluator expects to be given **many single points**, i.e. the X, Y,
-//! Z inputs are always `&[f32]`.  The output may be of a different type, e.g.
+//! Z inputs are always slices of scalars (`&[f32]` by default, or `&[f64]` for
+//! double-precision evaluators).  The output may be of a different type, e.g.
 //! partial derivatives with respect to X/Y/Z
 //! in [`Shape::GradSliceEval`](crate::eval::Shape::GradSliceEval).
 //!
@@ -15,10 +16,13 @@ use crate::{eval::Tape, Error};
 
 /// Trait for bulk evaluation returning the given type `T`
 ///
+/// Inputs and variables are slices of the scalar type `F`, which is `f32` for
+/// all but the double-precision evaluators.
+///
 /// It's uncommon to use this trait outside the library itself; it's an
 /// abstraction to reduce code duplication, and is public because it's used as a
 /// constraint on other public APIs.
-pub trait BulkEvaluator: Default {
+pub trait BulkEvaluator<F = f32>: Default {
     /// Data type used during evaluation
     type Data: From<f32> + Copy + Clone;
 
@@ -35,6 +39,9 @@ pub trait BulkEvaluator: Default {
 
     /// Evaluates many points using the given instruction tape
     ///
+    /// Returns one slice per output of the tape; each slice has the same length
+    /// as the input slices.  Only VM tapes may have more than one output.
+    ///
     /// # Panics
     /// This function may assume that the `x`, `y`, `z`, and `out` slices are of
     /// equal length and panic otherwise; higher-level calls should maintain
@@ -45,11 +52,11 @@ pub trait BulkEvaluator: Default {
     fn eval(
         &mut self,
         tape: &Self::Tape,
-        x: &[f32],
-        y: &[f32],
-        z: &[f32],
-        vars: &[f32],
-    ) -> Result<&[Self::Data], Error>;
+        x: &[F],
+        y: &[F],
+        z: &[F],
+        vars: &[F],
+    ) -> Result<BulkOutput<'_, Self::Data>, Error>;
 
     /// Build a new empty evaluator
     fn new() -> Self {
@@ -59,10 +66,10 @@ pub trait BulkEvaluator: Default {
     /// Helper function to return an error if the inputs are invalid
     fn check_arguments(
         &self,
-        xs: &[f32],
-        ys: &[f32],
-        zs: &[f32],
-        vars: &[f32],
+        xs: &[F],
+        ys: &[F],
+        zs: &[F],
+        vars: &[F],
         tape_var_count: usize, // TODO: maybe a trait instead?
     ) -> Result<(), Error> {
         if xs.len() != ys.len() || ys.len() != zs.len() {
@@ -74,3 +81,78 @@ pub trait BulkEvaluator: Default {
         }
     }
 }
+
+/// Results from a [`BulkEvaluator`], with one slice for each output
+///
+/// Indexing returns the slice of values for a particular output:
+/// ```
+/// use fidget::{context::Context, eval::{BulkEvaluator, Shape}, vm::VmShape};
+///
+/// let mut ctx = Context::new();
+/// let x = ctx.x();
+/// let y = ctx.y();
+/// let shape = VmShape::new_multi(&ctx, &[x, y])?;
+///
+/// let mut eval = VmShape::new_float_slice_eval();
+/// let tape = shape.float_slice_tape(Default::default());
+/// let out = eval.eval(&tape, &[1.0, 2.0], &[3.0, 4.0], &[0.0, 0.0], &[])?;
+/// assert_eq!(out.len(), 2);
+/// assert_eq!(out[0], [1.0, 2.0]);
+/// assert_eq!(out[1], [3.0, 4.0]);
+/// # Ok::<(), fidget::Error>(())
+/// ```
+#[derive(Copy, Clone)]
+pub struct BulkOutput<'a, T> {
+    data: &'a [Vec<T>],
+    len: usize,
+}
+
+impl<'a, T> BulkOutput<'a, T> {
+    /// Builds a new output, using the first `len` items from each array
+    ///
+    /// # Panics
+    /// If any of the arrays is shorter than `len`
+    pub fn new(data: &'a [Vec<T>], len: usize) -> Self {
+        assert!(data.iter().all(|d| d.len() >= len));
+        Self { data, len }
+    }
+
+    /// Returns the number of outputs
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks whether there are no outputs
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the slice of values for the given output
+    ///
+    /// Unlike indexing, the returned slice borrows from the evaluator (rather
+    /// than from this object).
+    ///
+    /// # Panics
+    /// If the output index is out of range
+    pub fn output(&self, i: usize) -> &'a [T] {
+        &self.data[i][0..self.len]
+    }
+
+    /// Iterates over per-output slices
+    pub fn iter(&self) -> impl Iterator<Item = &'a [T]> + '_ {
+        (0..self.len()).map(|i| self.output(i))
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for BulkOutput<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> std::ops::Index<usize> for BulkOutput<'_, T> {
+    type Output = [T];
+    fn index(&self, i: usize) -> &Self::Output {
+        self.output(i)
+    }
+}
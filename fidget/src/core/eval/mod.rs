@@ -34,11 +34,13 @@ pub mod types;
 mod vars;
 
 // Re-export a few things
-pub use bulk::BulkEvaluator;
+pub use bulk::{BulkEvaluator, BulkOutput};
 pub use tracing::TracingEvaluator;
 pub use vars::Vars;
 
-use types::{Grad, Interval};
+use types::{
+    Affine, Grad, Hessian, Interval, Interval64, IntervalGrad, MultiGrad,
+};
 
 /// A shape represents an implicit surface
 ///
@@ -89,6 +91,56 @@ pub trait Shape: Send + Sync + Clone {
         Self::IntervalEval::new()
     }
 
+    /// Associated type for interval tracing evaluation with outward rounding
+    ///
+    /// Unlike [`Shape::IntervalEval`], this evaluator guarantees that its
+    /// result contains the exact value over the input intervals, at the cost
+    /// of slightly wider bounds and slower evaluation.
+    type RobustIntervalEval: TracingEvaluator<
+            Data = Interval,
+            Trace = Self::Trace,
+            TapeStorage = Self::TapeStorage,
+        > + Send
+        + Sync;
+
+    /// Builds a new interval evaluator with outward rounding
+    fn new_robust_interval_eval() -> Self::RobustIntervalEval {
+        Self::RobustIntervalEval::new()
+    }
+
+    /// Associated type for affine arithmetic tracing evaluation
+    ///
+    /// This is an alternative to [`Shape::IntervalEval`] which tracks linear
+    /// dependencies on the inputs, producing tighter bounds at the cost of
+    /// slower evaluation.
+    type AffineEval: TracingEvaluator<
+            Data = Affine,
+            Trace = Self::Trace,
+            TapeStorage = Self::TapeStorage,
+        > + Send
+        + Sync;
+
+    /// Builds a new affine arithmetic evaluator
+    fn new_affine_eval() -> Self::AffineEval {
+        Self::AffineEval::new()
+    }
+
+    /// Associated type for interval tracing evaluation with derivatives
+    ///
+    /// In addition to bounds on the value, this evaluator returns bounds on
+    /// the partial derivatives over the region (see [`IntervalGrad`]).
+    type IntervalGradEval: TracingEvaluator<
+            Data = IntervalGrad,
+            Trace = Self::Trace,
+            TapeStorage = Self::TapeStorage,
+        > + Send
+        + Sync;
+
+    /// Builds a new interval evaluator with derivatives
+    fn new_interval_grad_eval() -> Self::IntervalGradEval {
+        Self::IntervalGradEval::new()
+    }
+
     /// Associated type for evaluating many points in one call
     type FloatSliceEval: BulkEvaluator<Data = f32, TapeStorage = Self::TapeStorage>
         + Send
@@ -109,6 +161,66 @@ pub trait Shape: Send + Sync + Clone {
         Self::GradSliceEval::new()
     }
 
+    /// Associated type for evaluating many values with first and second
+    /// derivatives in one call (see [`Hessian`])
+    type HessianSliceEval: BulkEvaluator<Data = Hessian, TapeStorage = Self::TapeStorage>
+        + Send
+        + Sync;
+
+    /// Builds a new Hessian slice evaluator
+    fn new_hessian_slice_eval() -> Self::HessianSliceEval {
+        Self::HessianSliceEval::new()
+    }
+
+    /// Associated type for evaluating many gradients with respect to
+    /// configurable targets in one call
+    type MultiGradSliceEval: BulkEvaluator<Data = MultiGrad, TapeStorage = Self::TapeStorage>
+        + Send
+        + Sync;
+
+    /// Builds a new multi-target gradient slice evaluator
+    fn new_multi_grad_slice_eval() -> Self::MultiGradSliceEval {
+        Self::MultiGradSliceEval::new()
+    }
+
+    /// Associated type for double-precision single-point tracing evaluation
+    type PointEval64: TracingEvaluator<
+            f64,
+            Data = f64,
+            Trace = Self::Trace,
+            TapeStorage = Self::TapeStorage,
+        > + Send
+        + Sync;
+
+    /// Builds a new double-precision point evaluator
+    fn new_point_eval64() -> Self::PointEval64 {
+        Self::PointEval64::new()
+    }
+
+    /// Associated type for double-precision interval tracing evaluation
+    type IntervalEval64: TracingEvaluator<
+            f64,
+            Data = Interval64,
+            Trace = Self::Trace,
+            TapeStorage = Self::TapeStorage,
+        > + Send
+        + Sync;
+
+    /// Builds a new double-precision interval evaluator
+    fn new_interval_eval64() -> Self::IntervalEval64 {
+        Self::IntervalEval64::new()
+    }
+
+    /// Associated type for evaluating many double-precision points in one call
+    type FloatSliceEval64: BulkEvaluator<f64, Data = f64, TapeStorage = Self::TapeStorage>
+        + Send
+        + Sync;
+
+    /// Builds a new double-precision float slice evaluator
+    fn new_float_slice_eval64() -> Self::FloatSliceEval64 {
+        Self::FloatSliceEval64::new()
+    }
+
     /// Returns an evaluation tape for a point evaluator
     fn point_tape(
         &self,
@@ -121,6 +233,25 @@ pub trait Shape: Send + Sync + Clone {
         storage: Self::TapeStorage,
     ) -> <Self::IntervalEval as TracingEvaluator>::Tape;
 
+    /// Returns an evaluation tape for an interval evaluator with outward
+    /// rounding
+    fn robust_interval_tape(
+        &self,
+        storage: Self::TapeStorage,
+    ) -> <Self::RobustIntervalEval as TracingEvaluator>::Tape;
+
+    /// Returns an evaluation tape for an affine arithmetic evaluator
+    fn affine_tape(
+        &self,
+        storage: Self::TapeStorage,
+    ) -> <Self::AffineEval as TracingEvaluator>::Tape;
+
+    /// Returns an evaluation tape for an interval evaluator with derivatives
+    fn interval_grad_tape(
+        &self,
+        storage: Self::TapeStorage,
+    ) -> <Self::IntervalGradEval as TracingEvaluator>::Tape;
+
     /// Returns an evaluation tape for a float slice evaluator
     fn float_slice_tape(
         &self,
@@ -133,6 +264,41 @@ pub trait Shape: Send + Sync + Clone {
         storage: Self::TapeStorage,
     ) -> <Self::GradSliceEval as BulkEvaluator>::Tape;
 
+    /// Returns an evaluation tape for a Hessian slice evaluator
+    fn hessian_slice_tape(
+        &self,
+        storage: Self::TapeStorage,
+    ) -> <Self::HessianSliceEval as BulkEvaluator>::Tape;
+
+    /// Returns an evaluation tape for a multi-target gradient slice evaluator
+    ///
+    /// Derivative `i` of each [`MultiGrad`] is taken with respect to
+    /// `targets[i]`.  Returns an error if there are more than [`MultiGrad::N`]
+    /// targets, or if a target names a variable that isn't in the shape.
+    fn multi_grad_slice_tape(
+        &self,
+        targets: &[GradTarget],
+        storage: Self::TapeStorage,
+    ) -> Result<<Self::MultiGradSliceEval as BulkEvaluator>::Tape, Error>;
+
+    /// Returns an evaluation tape for a double-precision point evaluator
+    fn point_tape64(
+        &self,
+        storage: Self::TapeStorage,
+    ) -> <Self::PointEval64 as TracingEvaluator<f64>>::Tape;
+
+    /// Returns an evaluation tape for a double-precision interval evaluator
+    fn interval_tape64(
+        &self,
+        storage: Self::TapeStorage,
+    ) -> <Self::IntervalEval64 as TracingEvaluator<f64>>::Tape;
+
+    /// Returns an evaluation tape for a double-precision float slice evaluator
+    fn float_slice_tape64(
+        &self,
+        storage: Self::TapeStorage,
+    ) -> <Self::FloatSliceEval64 as BulkEvaluator<f64>>::Tape;
+
     /// Computes a simplified tape using the given trace, and reusing storage
     fn simplify(
         &self,
@@ -155,6 +321,11 @@ pub trait Shape: Send + Sync + Clone {
     /// shapes, it's typically the length of the tape,
     fn size(&self) -> usize;
 
+    /// Returns the number of variables in this shape
+    ///
+    /// Variable slices passed to evaluators must have exactly this length.
+    fn var_count(&self) -> usize;
+
     /// Recommended tile sizes for 3D rendering
     fn tile_sizes_3d() -> &'static [usize];
 
@@ -190,6 +361,20 @@ pub trait EzShape: Shape {
         &self,
     ) -> <Self::IntervalEval as TracingEvaluator>::Tape;
 
+    /// Returns an evaluation tape for an interval evaluator with outward
+    /// rounding
+    fn ez_robust_interval_tape(
+        &self,
+    ) -> <Self::RobustIntervalEval as TracingEvaluator>::Tape;
+
+    /// Returns an evaluation tape for an affine arithmetic evaluator
+    fn ez_affine_tape(&self) -> <Self::AffineEval as TracingEvaluator>::Tape;
+
+    /// Returns an evaluation tape for an interval evaluator with derivatives
+    fn ez_interval_grad_tape(
+        &self,
+    ) -> <Self::IntervalGradEval as TracingEvaluator>::Tape;
+
     /// Returns an evaluation tape for a float slice evaluator
     fn ez_float_slice_tape(
         &self,
@@ -200,6 +385,32 @@ pub trait EzShape: Shape {
         &self,
     ) -> <Self::GradSliceEval as BulkEvaluator>::Tape;
 
+    /// Returns an evaluation tape for a Hessian slice evaluator
+    fn ez_hessian_slice_tape(
+        &self,
+    ) -> <Self::HessianSliceEval as BulkEvaluator>::Tape;
+
+    /// Returns an evaluation tape for a multi-target gradient slice evaluator
+    fn ez_multi_grad_slice_tape(
+        &self,
+        targets: &[GradTarget],
+    ) -> Result<<Self::MultiGradSliceEval as BulkEvaluator>::Tape, Error>;
+
+    /// Returns an evaluation tape for a double-precision point evaluator
+    fn ez_point_tape64(
+        &self,
+    ) -> <Self::PointEval64 as TracingEvaluator<f64>>::Tape;
+
+    /// Returns an evaluation tape for a double-precision interval evaluator
+    fn ez_interval_tape64(
+        &self,
+    ) -> <Self::IntervalEval64 as TracingEvaluator<f64>>::Tape;
+
+    /// Returns an evaluation tape for a double-precision float slice evaluator
+    fn ez_float_slice_tape64(
+        &self,
+    ) -> <Self::FloatSliceEval64 as BulkEvaluator<f64>>::Tape;
+
     /// Computes a simplified tape using the given trace
     fn ez_simplify(&self, trace: &Self::Trace) -> Result<Self, Error>
     where
@@ -217,6 +428,22 @@ impl<S: Shape> EzShape for S {
         self.interval_tape(Default::default())
     }
 
+    fn ez_robust_interval_tape(
+        &self,
+    ) -> <Self::RobustIntervalEval as TracingEvaluator>::Tape {
+        self.robust_interval_tape(Default::default())
+    }
+
+    fn ez_affine_tape(&self) -> <Self::AffineEval as TracingEvaluator>::Tape {
+        self.affine_tape(Default::default())
+    }
+
+    fn ez_interval_grad_tape(
+        &self,
+    ) -> <Self::IntervalGradEval as TracingEvaluator>::Tape {
+        self.interval_grad_tape(Default::default())
+    }
+
     fn ez_float_slice_tape(
         &self,
     ) -> <Self::FloatSliceEval as BulkEvaluator>::Tape {
@@ -229,18 +456,127 @@ impl<S: Shape> EzShape for S {
         self.grad_slice_tape(Default::default())
     }
 
+    fn ez_hessian_slice_tape(
+        &self,
+    ) -> <Self::HessianSliceEval as BulkEvaluator>::Tape {
+        self.hessian_slice_tape(Default::default())
+    }
+
+    fn ez_multi_grad_slice_tape(
+        &self,
+        targets: &[GradTarget],
+    ) -> Result<<Self::MultiGradSliceEval as BulkEvaluator>::Tape, Error> {
+        self.multi_grad_slice_tape(targets, Default::default())
+    }
+
+    fn ez_point_tape64(
+        &self,
+    ) -> <Self::PointEval64 as TracingEvaluator<f64>>::Tape {
+        self.point_tape64(Default::default())
+    }
+
+    fn ez_interval_tape64(
+        &self,
+    ) -> <Self::IntervalEval64 as TracingEvaluator<f64>>::Tape {
+        self.interval_tape64(Default::default())
+    }
+
+    fn ez_float_slice_tape64(
+        &self,
+    ) -> <Self::FloatSliceEval64 as BulkEvaluator<f64>>::Tape {
+        self.float_slice_tape64(Default::default())
+    }
+
     fn ez_simplify(&self, trace: &Self::Trace) -> Result<Self, Error> {
         let mut workspace = Default::default();
         self.simplify(trace, Default::default(), &mut workspace)
     }
 }
 
+/// Selects the evaluator used to find bounds over a region
+///
+/// Renderers and the mesher evaluate shapes over regions of space, then skip
+/// or subdivide each region based on the result.  Tighter bounds mean fewer
+/// subdivisions, at the cost of more expensive evaluation.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum IntervalMode {
+    /// Use [`Shape::IntervalEval`]
+    #[default]
+    Interval,
+    /// Use [`Shape::AffineEval`], which is slower but produces tighter bounds
+    Affine,
+}
+
 /// A [`Shape`] which contains named variables
 pub trait ShapeVars {
     /// Returns the variable map for ease of binding
     fn vars(&self) -> &HashMap<String, u32>;
 }
 
+/// Differentiation target for a [`MultiGrad`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GradTarget {
+    /// Partial derivative with respect to the `x` input
+    X,
+    /// Partial derivative with respect to the `y` input
+    Y,
+    /// Partial derivative with respect to the `z` input
+    Z,
+    /// Partial derivative with respect to the named variable
+    Var(String),
+}
+
+/// Derivative seeds for each input and variable in a tape
+///
+/// These are built from a list of [`GradTarget`] values, and used by
+/// multi-target gradient evaluators when loading inputs and variables.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GradSeeds {
+    inputs: [[f32; MultiGrad::N]; 3],
+    vars: Vec<[f32; MultiGrad::N]>,
+}
+
+impl GradSeeds {
+    /// Resolves targets against a map of variable names to indices
+    pub fn new(
+        targets: &[GradTarget],
+        vars: &HashMap<String, u32>,
+    ) -> Result<Self, Error> {
+        if targets.len() > MultiGrad::N {
+            return Err(Error::TooManyGradTargets(targets.len(), MultiGrad::N));
+        }
+        let mut out = Self {
+            inputs: Default::default(),
+            vars: vec![[0.0; MultiGrad::N]; vars.len()],
+        };
+        for (i, t) in targets.iter().enumerate() {
+            let seed = match t {
+                GradTarget::X => &mut out.inputs[0],
+                GradTarget::Y => &mut out.inputs[1],
+                GradTarget::Z => &mut out.inputs[2],
+                GradTarget::Var(name) => {
+                    let Some(v) = vars.get(name) else {
+                        return Err(Error::UnknownVariable(name.clone()));
+                    };
+                    &mut out.vars[*v as usize]
+                }
+            };
+            seed[i] = 1.0;
+        }
+        Ok(out)
+    }
+
+    /// Returns the derivative seeds for the given input (0-2)
+    pub fn input(&self, i: u8) -> &[f32; MultiGrad::N] {
+        &self.inputs[i as usize]
+    }
+
+    /// Returns the derivative seeds for the given variable index
+    pub fn var(&self, i: u32) -> &[f32; MultiGrad::N] {
+        &self.vars[i as usize]
+    }
+}
+
 /// A [`Shape`] which can be built from a math expression
 pub trait MathShape {
     /// Builds a new shape from the given context and node
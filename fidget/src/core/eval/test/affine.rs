@@ -0,0 +1,421 @@
+//! Tests for affine arithmetic evaluators
+//!
+//! If the `eval-tests` feature is set, then this exposes a standard test suite
+//! for affine arithmetic evaluators; otherwise, the module has no public
+//! exports.
+//!
+//! Results are checked against the point and interval evaluators: the affine
+//! range should contain every point result, and should be no wider than the
+//! interval result.
+use super::{
+    build_stress_fn, test_args_n, CanonicalBinaryOp, CanonicalUnaryOp,
+};
+use crate::{
+    context::{Context, Node},
+    eval::{
+        types::{Affine, Interval},
+        EzShape, MathShape, Shape, TracingEvaluator,
+    },
+    vm::Choice,
+};
+
+/// Helper struct to put constrains on our `Shape` object
+pub struct TestAffine<S>(std::marker::PhantomData<*const S>);
+
+impl<S> TestAffine<S>
+where
+    S: Shape + MathShape,
+    <S as Shape>::Trace: AsRef<[Choice]>,
+{
+    /// Intervals used as inputs
+    fn affine_test_args() -> Vec<Interval> {
+        let mut args = test_args_n(4);
+        args.retain(|a| !a.is_nan());
+        let mut out = vec![];
+        for &lower in &args {
+            out.push(Interval::from(lower));
+            for size in [0.1, 1.0, 2.5] {
+                out.push(Interval::new(lower, lower + size));
+            }
+        }
+        out
+    }
+
+    /// Checks that the interval is equal to `expected`, within rounding error
+    fn assert_close(out: Interval, expected: [f32; 2]) {
+        let eps = 1e-5;
+        assert!(
+            (out.lower() - expected[0]).abs() < eps
+                && (out.upper() - expected[1]).abs() < eps,
+            "{out} is not close to {expected:?}"
+        );
+    }
+
+    /// Returns a set of sample points within the interval
+    fn samples(i: Interval) -> Vec<f32> {
+        let mut out = vec![i.lower(), i.upper()];
+        for j in 1..8 {
+            let pos = j as f32 / 8.0;
+            out.push(i.lerp(pos).clamp(i.lower(), i.upper()));
+        }
+        out
+    }
+
+    /// Evaluates `root` with the affine evaluator, checking it against both
+    /// point samples and the interval evaluator
+    fn check_bounds(ctx: &Context, root: Node, x: Interval, y: Interval) {
+        let shape = S::new(ctx, root).unwrap();
+        let tape = shape.ez_affine_tape();
+        let mut eval = S::new_affine_eval();
+        let (out, _trace) = eval.eval(&tape, x, y, 0.0.into(), &[]).unwrap();
+        let out = out.interval();
+
+        let tape = shape.ez_interval_tape();
+        let mut eval = S::new_interval_eval();
+        let (i, _trace) = eval.eval(&tape, x, y, 0.0.into(), &[]).unwrap();
+        // A NaN result is always conservative (and evaluators disagree on
+        // edge cases like `sqrt([-1, 0])`), so there's nothing to check
+        if i.has_nan() || out.has_nan() {
+            return;
+        }
+        assert!(
+            out.lower() >= i.lower() && out.upper() <= i.upper(),
+            "affine result {out} is wider than interval result {i} \
+             (inputs {x}, {y})"
+        );
+
+        let tape = shape.ez_point_tape();
+        let mut eval = S::new_point_eval();
+        for &px in &Self::samples(x) {
+            for &py in &Self::samples(y) {
+                let (v, _trace) = eval.eval(&tape, px, py, 0.0, &[]).unwrap();
+                let eps = 1e-5 * v.abs().max(1.0);
+                assert!(
+                    v.is_nan()
+                        || (v >= out.lower() - eps && v <= out.upper() + eps),
+                    "f({px}, {py}) = {v} is not in {out} (inputs {x}, {y})"
+                );
+            }
+        }
+    }
+
+    pub fn test_a_dependency() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+
+        // x - x is exactly zero
+        let sub = ctx.sub(x, x).unwrap();
+        let shape = S::new(&ctx, sub).unwrap();
+        let tape = shape.ez_affine_tape();
+        let mut eval = S::new_affine_eval();
+        let (out, _) = eval
+            .eval(&tape, [0.0, 1.0], [0.0, 1.0], [0.0, 1.0], &[])
+            .unwrap();
+        assert_eq!(out.interval(), 0.0.into());
+
+        // (x + y) - x only depends on y
+        let sum = ctx.add(x, y).unwrap();
+        let root = ctx.sub(sum, x).unwrap();
+        let shape = S::new(&ctx, root).unwrap();
+        let tape = shape.ez_affine_tape();
+        let (out, _) = eval
+            .eval(&tape, [0.0, 1.0], [2.0, 3.0], [0.0; 2], &[])
+            .unwrap();
+        Self::assert_close(out.interval(), [2.0, 3.0]);
+
+        // x * (1 - x) has a maximum of 0.25 at x = 0.5; interval arithmetic
+        // gives [0, 1], while affine arithmetic does better.
+        let one_minus = ctx.sub(1.0, x).unwrap();
+        let root = ctx.mul(x, one_minus).unwrap();
+        let shape = S::new(&ctx, root).unwrap();
+        let tape = shape.ez_affine_tape();
+        let (out, _) = eval
+            .eval(&tape, [0.0, 1.0], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        Self::assert_close(out.interval(), [0.0, 0.5]);
+
+        // Inputs are independent
+        let root = ctx.sub(x, y).unwrap();
+        let shape = S::new(&ctx, root).unwrap();
+        let tape = shape.ez_affine_tape();
+        let (out, _) = eval
+            .eval(&tape, [0.0, 1.0], [0.0, 1.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.interval(), [-1.0, 1.0].into());
+    }
+
+    pub fn test_a_distance() {
+        // Distance to a circle, in coordinates which are rotated (so that X
+        // and Y each appear in both terms)
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let u = {
+            let a = ctx.mul(x, 0.6).unwrap();
+            let b = ctx.mul(y, 0.8).unwrap();
+            ctx.add(a, b).unwrap()
+        };
+        let v = {
+            let a = ctx.mul(x, 0.8).unwrap();
+            let b = ctx.mul(y, 0.6).unwrap();
+            ctx.sub(a, b).unwrap()
+        };
+        let u2 = ctx.square(u).unwrap();
+        let v2 = ctx.square(v).unwrap();
+        let r2 = ctx.add(u2, v2).unwrap();
+        let r = ctx.sqrt(r2).unwrap();
+        let root = ctx.sub(r, 0.5).unwrap();
+
+        let shape = S::new(&ctx, root).unwrap();
+        let tape = shape.ez_affine_tape();
+        let mut eval = S::new_affine_eval();
+        let (a, _) = eval
+            .eval(&tape, [1.5, 2.0], [1.5, 2.0], [0.0; 2], &[])
+            .unwrap();
+
+        let tape = shape.ez_interval_tape();
+        let mut eval = S::new_interval_eval();
+        let (i, _) = eval
+            .eval(&tape, [1.5, 2.0], [1.5, 2.0], [0.0; 2], &[])
+            .unwrap();
+        let exact = Interval::new(
+            1.5 * std::f32::consts::SQRT_2 - 0.5,
+            2.0 * std::f32::consts::SQRT_2 - 0.5,
+        );
+        let a = a.interval();
+        assert!(a.lower() <= exact.lower() && a.upper() >= exact.upper());
+        assert!(
+            a.width() < i.width(),
+            "affine result {a} should be tighter than {i}"
+        );
+
+        for x in Self::affine_test_args().into_iter().step_by(3) {
+            for y in Self::affine_test_args().into_iter().step_by(5) {
+                Self::check_bounds(&ctx, root, x, y);
+            }
+        }
+    }
+
+    pub fn test_a_choice() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+
+        // The ranges of x and x + 1 overlap, but x is always smaller
+        let x1 = ctx.add(x, 1.0).unwrap();
+        let min = ctx.min(x, x1).unwrap();
+        let shape = S::new(&ctx, min).unwrap();
+
+        let tape = shape.ez_interval_tape();
+        let mut eval = S::new_interval_eval();
+        let (_, trace) = eval
+            .eval(&tape, [0.0, 2.0], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        assert!(trace.is_none());
+
+        let tape = shape.ez_affine_tape();
+        let mut eval = S::new_affine_eval();
+        let (out, trace) = eval
+            .eval(&tape, [0.0, 2.0], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.interval(), [0.0, 2.0].into());
+        let trace = trace.unwrap();
+        assert_eq!(trace.as_ref(), &[Choice::Left]);
+
+        let next = shape.ez_simplify(trace).unwrap();
+        assert!(next.size() < shape.size());
+
+        // max(x + 1, x) picks x + 1 (the order of arguments may be changed
+        // when building the tape, so we don't check the choice itself)
+        let max = ctx.max(x1, x).unwrap();
+        let shape = S::new(&ctx, max).unwrap();
+        let tape = shape.ez_affine_tape();
+        let (out, trace) = eval
+            .eval(&tape, [0.0, 2.0], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        Self::assert_close(out.interval(), [1.0, 3.0]);
+        let next = shape.ez_simplify(trace.unwrap()).unwrap();
+        assert!(next.size() < shape.size());
+
+        // min(x, y) can't be decided
+        let min = ctx.min(x, y).unwrap();
+        let shape = S::new(&ctx, min).unwrap();
+        let tape = shape.ez_affine_tape();
+        let (out, trace) = eval
+            .eval(&tape, [0.0, 2.0], [1.0, 3.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.interval(), [0.0, 2.0].into());
+        assert!(trace.is_none());
+
+        // compare(x, x + 1) is always -1
+        let cmp = ctx.compare(x, x1).unwrap();
+        let shape = S::new(&ctx, cmp).unwrap();
+        let tape = shape.ez_affine_tape();
+        let (out, _) = eval
+            .eval(&tape, [0.0, 2.0], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.interval(), (-1.0).into());
+    }
+
+    pub fn test_a_nan() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let root = ctx.recip(x).unwrap();
+        let shape = S::new(&ctx, root).unwrap();
+        let tape = shape.ez_affine_tape();
+        let mut eval = S::new_affine_eval();
+        let (out, _) = eval
+            .eval(&tape, [-1.0, 1.0], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        assert!(out.has_nan());
+
+        let (out, _) = eval
+            .eval(&tape, [f32::NAN; 2], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        assert!(out.has_nan());
+
+        // Infinite inputs fall back to interval arithmetic
+        let (out, _) = eval
+            .eval(&tape, [1.0, f32::INFINITY], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.interval(), [0.0, 1.0].into());
+    }
+
+    pub fn test_a_stress() {
+        let (ctx, root) = build_stress_fn(32);
+        for (x, y) in [
+            ([0.0, 0.1], [0.0, 0.1]),
+            ([-0.5, 0.5], [0.25, 0.5]),
+            ([1.0, 1.0], [-1.0, 1.0]),
+        ] {
+            Self::check_bounds(&ctx, root, x.into(), y.into());
+        }
+    }
+
+    pub fn test_unary<C: CanonicalUnaryOp>() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let node = C::build(&mut ctx, x);
+        for a in Self::affine_test_args() {
+            Self::check_bounds(&ctx, node, a, 0.0.into());
+        }
+
+        // Also check an argument which depends on the input, so that the
+        // affine form has more than one term
+        let x2 = ctx.mul(x, 0.5).unwrap();
+        let x2 = ctx.add(x2, x).unwrap();
+        let node = C::build(&mut ctx, x2);
+        for a in Self::affine_test_args().into_iter().step_by(2) {
+            Self::check_bounds(&ctx, node, a, 0.0.into());
+        }
+    }
+
+    pub fn test_binary<C: CanonicalBinaryOp>() {
+        let args = Self::affine_test_args();
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+
+        let node = C::build(&mut ctx, x, y);
+        for &a in &args {
+            for &b in args.iter().step_by(3) {
+                Self::check_bounds(&ctx, node, a, b);
+            }
+        }
+
+        // Correlated arguments
+        let x2 = ctx.add(x, 0.5).unwrap();
+        let node = C::build(&mut ctx, x, x2);
+        for &a in &args {
+            Self::check_bounds(&ctx, node, a, 0.0.into());
+        }
+
+        for imm in [0.5, -1.5, 2.0] {
+            let c = ctx.constant(imm);
+            let reg_imm = C::build(&mut ctx, x, c);
+            let imm_reg = C::build(&mut ctx, c, x);
+            for &a in &args {
+                Self::check_bounds(&ctx, reg_imm, a, 0.0.into());
+                Self::check_bounds(&ctx, imm_reg, a, 0.0.into());
+            }
+        }
+    }
+
+    pub fn test_a_unary_ops() {
+        use super::canonical::*;
+
+        Self::test_unary::<neg>();
+        Self::test_unary::<recip>();
+        Self::test_unary::<abs>();
+        Self::test_unary::<sin>();
+        Self::test_unary::<cos>();
+        Self::test_unary::<tan>();
+        Self::test_unary::<asin>();
+        Self::test_unary::<acos>();
+        Self::test_unary::<atan>();
+        Self::test_unary::<exp>();
+        Self::test_unary::<ln>();
+        Self::test_unary::<square>();
+        Self::test_unary::<sqrt>();
+        Self::test_unary::<floor>();
+        Self::test_unary::<ceil>();
+        Self::test_unary::<round>();
+        Self::test_unary::<not>();
+    }
+
+    pub fn test_a_binary_ops() {
+        use super::canonical::*;
+
+        Self::test_binary::<add>();
+        Self::test_binary::<sub>();
+        Self::test_binary::<mul>();
+        Self::test_binary::<div>();
+        Self::test_binary::<min>();
+        Self::test_binary::<max>();
+        Self::test_binary::<atan2>();
+        Self::test_binary::<modulo>();
+        Self::test_binary::<compare>();
+        Self::test_binary::<and>();
+        Self::test_binary::<or>();
+    }
+
+    pub fn test_a_input() {
+        // Inputs are converted to intervals and given separate noise symbols,
+        // so passing an `Affine` doesn't correlate inputs with each other
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let root = ctx.sub(x, y).unwrap();
+        let shape = S::new(&ctx, root).unwrap();
+        let tape = shape.ez_affine_tape();
+        let mut eval = S::new_affine_eval();
+        let v = Affine::input([0.0, 1.0].into(), 0);
+        let (out, _) = eval.eval(&tape, v, v, 0.0.into(), &[]).unwrap();
+        assert_eq!(out.interval(), [-1.0, 1.0].into());
+    }
+}
+
+#[macro_export]
+macro_rules! affine_test {
+    ($i:ident, $t:ty) => {
+        #[test]
+        fn $i() {
+            $crate::eval::test::affine::TestAffine::<$t>::$i()
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! affine_tests {
+    ($t:ty) => {
+        $crate::affine_test!(test_a_dependency, $t);
+        $crate::affine_test!(test_a_distance, $t);
+        $crate::affine_test!(test_a_choice, $t);
+        $crate::affine_test!(test_a_nan, $t);
+        $crate::affine_test!(test_a_stress, $t);
+        $crate::affine_test!(test_a_input, $t);
+        $crate::affine_test!(test_a_unary_ops, $t);
+        $crate::affine_test!(test_a_binary_ops, $t);
+    };
+}
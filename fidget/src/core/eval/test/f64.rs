@@ -0,0 +1,555 @@
+//! Test suite for double-precision evaluators
+//!
+//! If the `eval-tests` feature is set, then this exposes a standard test suite
+//! for the `f64` point, interval, and float slice evaluators; otherwise, the
+//! module has no public exports.
+use super::{build_stress_fn, test_args, CanonicalBinaryOp, CanonicalUnaryOp};
+use crate::{
+    context::Context,
+    eval::{
+        types::Interval64, BulkEvaluator, EzShape, MathShape, Shape, ShapeVars,
+        TracingEvaluator,
+    },
+    vm::Choice,
+};
+
+/// Helper struct to put constrains on our `Shape` object
+pub struct TestF64<S>(std::marker::PhantomData<*const S>);
+impl<S> TestF64<S>
+where
+    S: Shape + MathShape + ShapeVars,
+    <S as Shape>::Trace: AsRef<[Choice]>,
+{
+    /// Pick a bunch of arguments, some of which are spicy
+    fn test_args64() -> Vec<f64> {
+        let mut args: Vec<f64> =
+            test_args().into_iter().map(|a| a as f64).collect();
+        args.push(std::f64::consts::PI);
+        args.push(1e-12);
+        args.push(1.0 + 1e-12);
+        args
+    }
+
+    pub fn test_p64_constant() {
+        let mut ctx = Context::new();
+        let p = ctx.constant(0.1);
+        let shape = S::new(&ctx, p).unwrap();
+        let tape = shape.ez_point_tape64();
+        let mut eval = S::new_point_eval64();
+        assert_eq!(eval.eval(&tape, 0.0, 0.0, 0.0, &[]).unwrap().0, 0.1);
+    }
+
+    pub fn test_p64_precision() {
+        // (x + 1e-9) - x is zero in single-precision, but not in double
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let sum = ctx.add(x, 1e-9).unwrap();
+        let out = ctx.sub(sum, x).unwrap();
+        let shape = S::new(&ctx, out).unwrap();
+
+        let tape = shape.ez_point_tape();
+        let mut eval = S::new_point_eval();
+        assert_eq!(eval.eval(&tape, 1.0, 0.0, 0.0, &[]).unwrap().0, 0.0);
+
+        let tape = shape.ez_point_tape64();
+        let mut eval = S::new_point_eval64();
+        let v = eval.eval(&tape, 1.0, 0.0, 0.0, &[]).unwrap().0;
+        assert_eq!(v, (1.0 + 1e-9) - 1.0);
+
+        let tape = shape.ez_float_slice_tape64();
+        let mut eval = S::new_float_slice_eval64();
+        let out = eval
+            .eval(&tape, &[1.0, 2.0, 3.0], &[0.0; 3], &[0.0; 3], &[])
+            .unwrap()
+            .output(0);
+        assert_eq!(
+            out,
+            [(1.0 + 1e-9) - 1.0, (2.0 + 1e-9) - 2.0, (3.0 + 1e-9) - 3.0]
+        );
+
+        let tape = shape.ez_interval_tape64();
+        let mut eval = S::new_interval_eval64();
+        let (v, _) = eval
+            .eval(
+                &tape,
+                Interval64::new(1.0, 1.0),
+                0.0.into(),
+                0.0.into(),
+                &[],
+            )
+            .unwrap();
+        assert_eq!(v.lower(), (1.0 + 1e-9) - 1.0);
+        assert_eq!(v.upper(), (1.0 + 1e-9) - 1.0);
+    }
+
+    pub fn test_p64_min() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let min = ctx.min(x, y).unwrap();
+
+        let shape = S::new(&ctx, min).unwrap();
+        let tape = shape.ez_point_tape64();
+        let mut eval = S::new_point_eval64();
+        let (r, trace) = eval.eval(&tape, 0.0, 0.0, 0.0, &[]).unwrap();
+        assert_eq!(r, 0.0);
+        assert!(trace.is_none());
+
+        let (r, trace) = eval.eval(&tape, 0.0, 1e-12, 0.0, &[]).unwrap();
+        assert_eq!(r, 0.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Left]);
+
+        let (r, trace) = eval.eval(&tape, 1e-12, 0.0, 0.0, &[]).unwrap();
+        assert_eq!(r, 0.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Right]);
+
+        let (r, trace) = eval.eval(&tape, f64::NAN, 0.0, 0.0, &[]).unwrap();
+        assert!(r.is_nan());
+        assert!(trace.is_none());
+    }
+
+    pub fn test_i64_min() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let min = ctx.min(x, y).unwrap();
+
+        let shape = S::new(&ctx, min).unwrap();
+        let tape = shape.ez_interval_tape64();
+        let mut eval = S::new_interval_eval64();
+        let (r, trace) = eval
+            .eval(&tape, [0.0, 1.0], [0.5, 1.5], [0.0, 0.0], &[])
+            .unwrap();
+        assert_eq!(r, [0.0, 1.0].into());
+        assert!(trace.is_none());
+
+        let (r, trace) = eval
+            .eval(&tape, [0.0, 1.0], [1.0 + 1e-12, 2.0], [0.0, 0.0], &[])
+            .unwrap();
+        assert_eq!(r, [0.0, 1.0].into());
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Left]);
+
+        let (r, trace) = eval
+            .eval(&tape, [2.0, 3.0], [1.0, 2.0 - 1e-12], [0.0, 0.0], &[])
+            .unwrap();
+        assert_eq!(r, [1.0, 2.0 - 1e-12].into());
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Right]);
+    }
+
+    pub fn test_i64_sin_cos() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let sin_x = ctx.sin(x).unwrap();
+        let cos_x = ctx.cos(x).unwrap();
+
+        let mut eval = S::new_interval_eval64();
+        for (root, f) in
+            [(sin_x, f64::sin as fn(f64) -> f64), (cos_x, f64::cos)]
+        {
+            let shape = S::new(&ctx, root).unwrap();
+            let tape = shape.ez_interval_tape64();
+            for k in -8..=8 {
+                let lower = k as f64 * std::f64::consts::FRAC_PI_4 + 1e-12;
+                for width in [0.0, 1e-9, 0.5, 2.0, 4.0, 6.0] {
+                    let upper = lower + width;
+                    let (r, _) = eval
+                        .eval(&tape, [lower, upper], [0.0; 2], [0.0; 2], &[])
+                        .unwrap();
+                    let mut min = f64::INFINITY;
+                    let mut max = f64::NEG_INFINITY;
+                    for i in 0..=256 {
+                        let x = lower + width * (i as f64 / 256.0);
+                        let out = f(x.min(upper));
+                        assert!(r.contains(out), "{out} is not in {r:?}");
+                        min = min.min(out);
+                        max = max.max(out);
+                    }
+                    let tol = (width / 256.0).powi(2) + 1e-12;
+                    assert!(
+                        r.lower() >= min - tol && r.upper() <= max + tol,
+                        "{r:?} is too wide for [{min}, {max}]"
+                    );
+                }
+            }
+
+            let (r, _) = eval
+                .eval(&tape, [0.0, 7.0], [0.0; 2], [0.0; 2], &[])
+                .unwrap();
+            assert_eq!(r, [-1.0, 1.0].into());
+        }
+    }
+
+    pub fn test_i64_and_or() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let and = ctx.and(x, y).unwrap();
+        let or = ctx.or(and, z).unwrap();
+
+        let shape = S::new(&ctx, or).unwrap();
+        let tape = shape.ez_interval_tape64();
+        let mut eval = S::new_interval_eval64();
+        let (r, trace) = eval
+            .eval(&tape, [0.0, 0.0], [5.0, 6.0], [3.0, 4.0], &[])
+            .unwrap();
+        assert_eq!(r, [3.0, 4.0].into());
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Left, Choice::Right]);
+
+        let (r, trace) = eval
+            .eval(&tape, [1.0, 2.0], [5.0, 6.0], [3.0, 4.0], &[])
+            .unwrap();
+        assert_eq!(r, [5.0, 6.0].into());
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Right, Choice::Left]);
+
+        let (r, trace) = eval
+            .eval(&tape, [-1.0, 1.0], [5.0, 6.0], [3.0, 4.0], &[])
+            .unwrap();
+        assert_eq!(r, [0.0, 6.0].into());
+        assert!(trace.is_none());
+    }
+
+    pub fn test_f64_var() {
+        let mut ctx = Context::new();
+        let a = ctx.var("a").unwrap();
+        let b = ctx.var("b").unwrap();
+        let x = ctx.x();
+        let sum = ctx.add(a, x).unwrap();
+        let out = ctx.mul(sum, b).unwrap();
+
+        let shape = S::new(&ctx, out).unwrap();
+        let mut vars = vec![0.0; shape.vars().len()];
+        vars[shape.vars()["a"] as usize] = 1e-10;
+        vars[shape.vars()["b"] as usize] = 3.0;
+
+        let tape = shape.ez_point_tape64();
+        let mut eval = S::new_point_eval64();
+        let (r, _) = eval.eval(&tape, 1.0, 0.0, 0.0, &vars).unwrap();
+        assert_eq!(r, (1.0 + 1e-10) * 3.0);
+
+        let tape = shape.ez_interval_tape64();
+        let mut eval = S::new_interval_eval64();
+        let (r, _) = eval
+            .eval(&tape, [1.0, 2.0], [0.0, 0.0], [0.0, 0.0], &vars)
+            .unwrap();
+        assert_eq!(r, [(1.0 + 1e-10) * 3.0, (2.0 + 1e-10) * 3.0].into());
+
+        let tape = shape.ez_float_slice_tape64();
+        let mut eval = S::new_float_slice_eval64();
+        let out = eval
+            .eval(&tape, &[1.0, 2.0, 3.0], &[0.0; 3], &[0.0; 3], &vars)
+            .unwrap()
+            .output(0);
+        assert_eq!(
+            out,
+            [
+                (1.0 + 1e-10) * 3.0,
+                (2.0 + 1e-10) * 3.0,
+                (3.0 + 1e-10) * 3.0
+            ]
+        );
+    }
+
+    pub fn test_f64_stress_n(depth: usize) {
+        let (ctx, node) = build_stress_fn(depth);
+
+        // Pick an input slice that's guaranteed to be > 1 SIMD register
+        let x = (0..32).map(|i| i as f64 / 32.0).collect::<Vec<f64>>();
+        let y: Vec<f64> = x[1..].iter().chain(&x[0..1]).cloned().collect();
+        let z: Vec<f64> = x[2..].iter().chain(&x[0..2]).cloned().collect();
+
+        let shape = S::new(&ctx, node).unwrap();
+        let tape = shape.ez_float_slice_tape64();
+        let mut eval = S::new_float_slice_eval64();
+        let out = eval
+            .eval(&tape, &x, &y, &z, &[])
+            .unwrap()
+            .output(0)
+            .to_vec();
+
+        let tape = shape.ez_point_tape64();
+        let mut point_eval = S::new_point_eval64();
+        let tape_i = shape.ez_interval_tape64();
+        let mut interval_eval = S::new_interval_eval64();
+        for (i, v) in out.iter().cloned().enumerate() {
+            let q = ctx.eval_xyz(node, x[i], y[i], z[i]).unwrap();
+            let err = (v - q).abs();
+            assert!(
+                err < 1e-9,
+                "mismatch at index {i} ({}, {}, {}): {v} != {q} [{err}], {}",
+                x[i],
+                y[i],
+                z[i],
+                depth,
+            );
+
+            let (p, _) = point_eval.eval(&tape, x[i], y[i], z[i], &[]).unwrap();
+            assert_eq!(p, v, "point / slice mismatch at index {i}, {depth}");
+
+            let (r, _) =
+                interval_eval.eval(&tape_i, x[i], y[i], z[i], &[]).unwrap();
+            assert!(
+                r.contains(q) || (r.upper() - r.lower()) < 1e-9,
+                "interval mismatch at index {i}: {q} not in {r}, {depth}"
+            );
+        }
+    }
+
+    pub fn test_f64_stress() {
+        for n in [1, 2, 4, 8, 12, 16, 32] {
+            Self::test_f64_stress_n(n);
+        }
+    }
+
+    fn compare_results(v: f64, o: f64, name: &str, args: std::fmt::Arguments) {
+        let err = (v - o).abs();
+        assert!(
+            (o == v) || err < 1e-12 || (v.is_nan() && o.is_nan()),
+            "mismatch in '{name}' at {args}: {v} != {o} ({err})",
+        )
+    }
+
+    pub fn test_unary<C: CanonicalUnaryOp>() {
+        let args = Self::test_args64();
+        let zero = vec![0.0; args.len()];
+
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let node = C::build(&mut ctx, x);
+        let shape = S::new(&ctx, node).unwrap();
+
+        let tape = shape.ez_point_tape64();
+        let mut eval = S::new_point_eval64();
+        for &a in args.iter() {
+            let (o, trace) = eval.eval(&tape, a, 0.0, 0.0, &[]).unwrap();
+            assert!(trace.is_none());
+            Self::compare_results(
+                C::eval_f64(a),
+                o,
+                C::NAME,
+                format_args!("{a}"),
+            );
+        }
+
+        let tape = shape.ez_float_slice_tape64();
+        let mut eval = S::new_float_slice_eval64();
+        let out = eval
+            .eval(&tape, &args, &zero, &zero, &[])
+            .unwrap()
+            .output(0);
+        for (&a, &o) in args.iter().zip(out.iter()) {
+            Self::compare_results(
+                C::eval_f64(a),
+                o,
+                C::NAME,
+                format_args!("{a}"),
+            );
+        }
+
+        let tape = shape.ez_interval_tape64();
+        let mut eval = S::new_interval_eval64();
+        for &a in args.iter() {
+            for &b in args.iter() {
+                if a.is_nan() || b.is_nan() || a > b {
+                    continue;
+                }
+                let i = Interval64::new(a, b);
+                let (o, _) =
+                    eval.eval(&tape, i, 0.0.into(), 0.0.into(), &[]).unwrap();
+                for k in 0..8 {
+                    let pos = k as f64 / 7.0;
+                    let inside = (a * pos + b * (1.0 - pos)).min(b).max(a);
+                    let v = C::eval_f64(inside);
+                    assert!(
+                        v.is_nan() || o.has_nan() || o.contains(v),
+                        "interval failure in '{}': {inside} in {i} => \
+                         {v} not in {o}",
+                        C::NAME,
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn test_binary<C: CanonicalBinaryOp>() {
+        let args = Self::test_args64();
+
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let node = C::build(&mut ctx, x, y);
+        let shape = S::new(&ctx, node).unwrap();
+
+        let name = format!("{}(reg, reg)", C::NAME);
+        let tape = shape.ez_point_tape64();
+        let mut eval = S::new_point_eval64();
+        for &lhs in args.iter() {
+            for &rhs in args.iter() {
+                let (o, _) = eval.eval(&tape, lhs, rhs, 0.0, &[]).unwrap();
+                let v = C::eval_reg_reg_f64(lhs, rhs);
+                Self::compare_results(
+                    v,
+                    o,
+                    &name,
+                    format_args!("({lhs}, {rhs})"),
+                );
+            }
+        }
+
+        let tape = shape.ez_float_slice_tape64();
+        let mut eval = S::new_float_slice_eval64();
+        let zero = vec![0.0; args.len()];
+        for rot in 0..args.len() {
+            let mut rgsa = args.clone();
+            rgsa.rotate_left(rot);
+            let out = eval
+                .eval(&tape, &args, &rgsa, &zero, &[])
+                .unwrap()
+                .output(0);
+            for ((&lhs, &rhs), &o) in args.iter().zip(&rgsa).zip(out) {
+                let v = C::eval_reg_reg_f64(lhs, rhs);
+                Self::compare_results(
+                    v,
+                    o,
+                    &name,
+                    format_args!("({lhs}, {rhs})"),
+                );
+            }
+        }
+
+        let tape = shape.ez_interval_tape64();
+        let mut eval = S::new_interval_eval64();
+        let intervals = args
+            .iter()
+            .flat_map(|&a| args.iter().map(move |&b| (a, b)))
+            .filter(|(a, b)| a <= b && (b - a) < 0.5)
+            .map(|(a, b)| Interval64::new(a, b))
+            .collect::<Vec<_>>();
+        for &lhs in intervals.iter().step_by(7) {
+            for &rhs in intervals.iter().step_by(5) {
+                let (o, _) =
+                    eval.eval(&tape, lhs, rhs, 0.0.into(), &[]).unwrap();
+                for i in 0..4 {
+                    for j in 0..4 {
+                        let i = i as f64 / 3.0;
+                        let j = j as f64 / 3.0;
+                        let a = (lhs.lower() * i + lhs.upper() * (1.0 - i))
+                            .min(lhs.upper())
+                            .max(lhs.lower());
+                        let b = (rhs.lower() * j + rhs.upper() * (1.0 - j))
+                            .min(rhs.upper())
+                            .max(rhs.lower());
+                        let v = C::eval_reg_reg_f64(a, b);
+                        assert!(
+                            v.is_nan() || o.has_nan() || o.contains(v),
+                            "interval failure in '{name}': ({a}, {b}) in \
+                             ({lhs}, {rhs}) => {v} not in {o}"
+                        );
+                    }
+                }
+            }
+        }
+
+        let name = format!("{}(reg, imm)", C::NAME);
+        for &rhs in args.iter() {
+            let c = ctx.constant(rhs);
+            let node = C::build(&mut ctx, x, c);
+            let shape = S::new(&ctx, node).unwrap();
+            let tape = shape.ez_point_tape64();
+            let mut eval = S::new_point_eval64();
+            for &lhs in args.iter() {
+                let (o, _) = eval.eval(&tape, lhs, 0.0, 0.0, &[]).unwrap();
+                let v = C::eval_reg_imm_f64(lhs, rhs);
+                Self::compare_results(
+                    v,
+                    o,
+                    &name,
+                    format_args!("({lhs}, {rhs})"),
+                );
+            }
+        }
+
+        let name = format!("{}(imm, reg)", C::NAME);
+        for &lhs in args.iter() {
+            let c = ctx.constant(lhs);
+            let node = C::build(&mut ctx, c, x);
+            let shape = S::new(&ctx, node).unwrap();
+            let tape = shape.ez_point_tape64();
+            let mut eval = S::new_point_eval64();
+            for &rhs in args.iter() {
+                let (o, _) = eval.eval(&tape, rhs, 0.0, 0.0, &[]).unwrap();
+                let v = C::eval_imm_reg_f64(lhs, rhs);
+                Self::compare_results(
+                    v,
+                    o,
+                    &name,
+                    format_args!("({lhs}, {rhs})"),
+                );
+            }
+        }
+    }
+
+    pub fn test_f64_unary_ops() {
+        use super::canonical::*;
+
+        Self::test_unary::<neg>();
+        Self::test_unary::<recip>();
+        Self::test_unary::<abs>();
+        Self::test_unary::<sin>();
+        Self::test_unary::<cos>();
+        Self::test_unary::<tan>();
+        Self::test_unary::<asin>();
+        Self::test_unary::<acos>();
+        Self::test_unary::<atan>();
+        Self::test_unary::<exp>();
+        Self::test_unary::<ln>();
+        Self::test_unary::<square>();
+        Self::test_unary::<sqrt>();
+        Self::test_unary::<floor>();
+        Self::test_unary::<ceil>();
+        Self::test_unary::<round>();
+        Self::test_unary::<not>();
+    }
+
+    pub fn test_f64_binary_ops() {
+        use super::canonical::*;
+
+        Self::test_binary::<add>();
+        Self::test_binary::<sub>();
+        Self::test_binary::<mul>();
+        Self::test_binary::<div>();
+        Self::test_binary::<min>();
+        Self::test_binary::<max>();
+        Self::test_binary::<atan2>();
+        Self::test_binary::<modulo>();
+        Self::test_binary::<compare>();
+        Self::test_binary::<and>();
+        Self::test_binary::<or>();
+    }
+}
+
+#[macro_export]
+macro_rules! f64_test {
+    ($i:ident, $t:ty) => {
+        #[test]
+        fn $i() {
+            $crate::eval::test::f64::TestF64::<$t>::$i()
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! f64_tests {
+    ($t:ty) => {
+        $crate::f64_test!(test_p64_constant, $t);
+        $crate::f64_test!(test_p64_precision, $t);
+        $crate::f64_test!(test_p64_min, $t);
+        $crate::f64_test!(test_i64_min, $t);
+        $crate::f64_test!(test_i64_and_or, $t);
+        $crate::f64_test!(test_i64_sin_cos, $t);
+        $crate::f64_test!(test_f64_var, $t);
+        $crate::f64_test!(test_f64_stress, $t);
+        $crate::f64_test!(test_f64_unary_ops, $t);
+        $crate::f64_test!(test_f64_binary_ops, $t);
+    };
+}
@@ -36,7 +36,8 @@ where
                     &[0.0, 0.0, 0.0, 100.0],
                     &[],
                 )
-                .unwrap();
+                .unwrap()
+                .output(0);
             assert_eq!(out, [0.0, 1.0, 2.0, 3.0]);
 
             // TODO: reuse tape data here
@@ -50,7 +51,8 @@ where
                     &[0.0, 0.0, 0.0, 100.0],
                     &[],
                 )
-                .unwrap();
+                .unwrap()
+                .output(0);
             assert_eq!(out, [3.0, 2.0, 1.0, 0.0]);
         }
     }
@@ -71,7 +73,8 @@ where
                 &[0.0, 0.0, 0.0, 100.0],
                 &[],
             )
-            .unwrap();
+            .unwrap()
+            .output(0);
         assert_eq!(out, [0.0, 1.0, 2.0, 3.0]);
 
         let out = eval
@@ -82,7 +85,8 @@ where
                 &[0.0, 0.0, 0.0, 100.0, 0.0, 0.0, 0.0, 100.0],
                 &[],
             )
-            .unwrap();
+            .unwrap()
+            .output(0);
         assert_eq!(out, [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
 
         let out = eval
@@ -93,7 +97,8 @@ where
                 &[0.0, 0.0, 0.0, 100.0, 0.0, 0.0, 0.0, 100.0, 200.0],
                 &[],
             )
-            .unwrap();
+            .unwrap()
+            .output(0);
         assert_eq!(out, [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
 
         let mul = ctx.mul(y, 2.0).unwrap();
@@ -107,7 +112,8 @@ where
                 &[0.0, 0.0, 0.0, 100.0],
                 &[],
             )
-            .unwrap();
+            .unwrap()
+            .output(0);
         assert_eq!(out, [6.0, 4.0, 2.0, 0.0]);
 
         let out = eval
@@ -118,7 +124,8 @@ where
                 &[0.0, 0.0, 0.0],
                 &[],
             )
-            .unwrap();
+            .unwrap()
+            .output(0);
         assert_eq!(&out[0..3], &[2.0, 8.0, 16.0]);
 
         let out = eval
@@ -129,7 +136,8 @@ where
                 &[0.0; 7],
                 &[],
             )
-            .unwrap();
+            .unwrap()
+            .output(0);
         assert_eq!(out, [2.0, 8.0, 8.0, -2.0, -4.0, -6.0, 0.0]);
     }
 
@@ -153,7 +161,8 @@ where
                 &[0.0],
                 vars.bind([("a", 5.0), ("b", 3.0)].into_iter())
             )
-            .unwrap()[0],
+            .unwrap()
+            .output(0)[0],
             2.0
         );
         assert_eq!(
@@ -164,7 +173,8 @@ where
                 &[0.0],
                 vars.bind([("a", 3.0), ("b", 2.0)].into_iter())
             )
-            .unwrap()[0],
+            .unwrap()
+            .output(0)[0],
             2.0
         );
         assert_eq!(
@@ -175,7 +185,8 @@ where
                 &[0.0],
                 vars.bind([("a", 0.0), ("b", 2.0)].into_iter())
             )
-            .unwrap()[0],
+            .unwrap()
+            .output(0)[0],
             0.5,
         );
     }
@@ -199,7 +210,8 @@ where
                 &[0.0; 4],
                 vars.bind([("a", 5.0), ("b", 3.0)].into_iter())
             )
-            .unwrap(),
+            .unwrap()
+            .output(0),
             args.map(f32::sin),
         );
     }
@@ -219,7 +231,7 @@ where
         let mut eval = S::new_float_slice_eval();
         let tape = shape.ez_float_slice_tape();
 
-        let out = eval.eval(&tape, &x, &y, &z, &[]).unwrap();
+        let out = eval.eval(&tape, &x, &y, &z, &[]).unwrap().output(0);
 
         for (i, v) in out.iter().cloned().enumerate() {
             let q = ctx
@@ -244,7 +256,7 @@ where
         let mut eval = VmShape::new_float_slice_eval();
         let tape = shape.ez_float_slice_tape();
 
-        let cmp = eval.eval(&tape, &x, &y, &z, &[]).unwrap();
+        let cmp = eval.eval(&tape, &x, &y, &z, &[]).unwrap().output(0);
         for (i, (a, b)) in out.iter().zip(cmp.iter()).enumerate() {
             let err = (a - b).abs();
             assert!(
@@ -282,7 +294,8 @@ where
                 2 => eval.eval(&tape, &zero, &zero, &args, &[]),
                 _ => unreachable!(),
             }
-            .unwrap();
+            .unwrap()
+            .output(0);
             for (a, &o) in args.iter().zip(out.iter()) {
                 let v = C::eval_f32(*a);
                 let err = (v - o).abs();
@@ -342,7 +355,8 @@ where
                         (2, 2) => eval.eval(&tape, &zero, &zero, &args, &[]),
                         _ => unreachable!(),
                     }
-                    .unwrap();
+                    .unwrap()
+                    .output(0);
 
                     let rhs = if i == j { &args } else { &rgsa };
                     Self::compare_float_results(
@@ -383,7 +397,8 @@ where
                         2 => eval.eval(&tape, &zero, &zero, &args, &[]),
                         _ => unreachable!(),
                     }
-                    .unwrap();
+                    .unwrap()
+                    .output(0);
 
                     let rhs = vec![*rhs; out.len()];
                     Self::compare_float_results(
@@ -424,7 +439,8 @@ where
                         2 => eval.eval(&tape, &zero, &zero, &args, &[]),
                         _ => unreachable!(),
                     }
-                    .unwrap();
+                    .unwrap()
+                    .output(0);
 
                     let lhs = vec![*lhs; out.len()];
                     Self::compare_float_results(
@@ -455,6 +471,10 @@ where
         Self::test_unary::<ln>();
         Self::test_unary::<square>();
         Self::test_unary::<sqrt>();
+        Self::test_unary::<floor>();
+        Self::test_unary::<ceil>();
+        Self::test_unary::<round>();
+        Self::test_unary::<not>();
     }
 
     pub fn test_binary<C: CanonicalBinaryOp>() {
@@ -472,6 +492,11 @@ where
         Self::test_binary::<div>();
         Self::test_binary::<min>();
         Self::test_binary::<max>();
+        Self::test_binary::<atan2>();
+        Self::test_binary::<modulo>();
+        Self::test_binary::<compare>();
+        Self::test_binary::<and>();
+        Self::test_binary::<or>();
     }
 }
 
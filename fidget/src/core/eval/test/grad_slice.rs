@@ -25,7 +25,9 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[2.0], &[3.0], &[4.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[2.0], &[3.0], &[4.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(2.0, 1.0, 0.0, 0.0)
         );
     }
@@ -38,7 +40,9 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[2.0], &[3.0], &[4.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[2.0], &[3.0], &[4.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(3.0, 0.0, 1.0, 0.0)
         );
     }
@@ -51,7 +55,9 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[2.0], &[3.0], &[4.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[2.0], &[3.0], &[4.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(4.0, 0.0, 0.0, 1.0)
         );
     }
@@ -65,19 +71,27 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[0.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[0.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(0.0, 0.0, 0.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[1.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[1.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(1.0, 2.0, 0.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[2.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[2.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(4.0, 4.0, 0.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[3.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[3.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(9.0, 6.0, 0.0, 0.0)
         );
     }
@@ -91,11 +105,15 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[2.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[2.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(2.0, 1.0, 0.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[-2.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[-2.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(2.0, -1.0, 0.0, 0.0)
         );
     }
@@ -109,11 +127,15 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[1.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[1.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(1.0, 0.5, 0.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[4.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[4.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(2.0, 0.25, 0.0, 0.0)
         );
     }
@@ -128,7 +150,8 @@ where
         let mut eval = S::new_grad_slice_eval();
         let v = eval
             .eval(&tape, &[1.0, 2.0, 3.0], &[0.0; 3], &[0.0; 3], &[])
-            .unwrap();
+            .unwrap()
+            .output(0);
         v[0].compare_eq(Grad::new(1f32.sin(), 1f32.cos(), 0.0, 0.0));
         v[1].compare_eq(Grad::new(2f32.sin(), 2f32.cos(), 0.0, 0.0));
         v[2].compare_eq(Grad::new(3f32.sin(), 3f32.cos(), 0.0, 0.0));
@@ -140,7 +163,8 @@ where
         let tape = shape.ez_grad_slice_tape();
         let v = eval
             .eval(&tape, &[0.0; 3], &[1.0, 2.0, 3.0], &[0.0; 3], &[])
-            .unwrap();
+            .unwrap()
+            .output(0);
         v[0].compare_eq(Grad::new(2f32.sin(), 0.0, 2.0 * 2f32.cos(), 0.0));
         v[1].compare_eq(Grad::new(4f32.sin(), 0.0, 2.0 * 4f32.cos(), 0.0));
         v[2].compare_eq(Grad::new(6f32.sin(), 0.0, 2.0 * 6f32.cos(), 0.0));
@@ -156,19 +180,27 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[1.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[1.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(0.0, 0.0, 1.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[0.0], &[1.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[0.0], &[1.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(0.0, 1.0, 0.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[4.0], &[1.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[4.0], &[1.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(4.0, 1.0, 4.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[4.0], &[2.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[4.0], &[2.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(8.0, 2.0, 4.0, 0.0)
         );
     }
@@ -182,7 +214,9 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[1.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[1.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(0.5, 0.5, 0.0, 0.0)
         );
     }
@@ -196,11 +230,15 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[1.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[1.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(1.0, -1.0, 0.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[2.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[2.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(0.5, -0.25, 0.0, 0.0)
         );
     }
@@ -215,11 +253,15 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[2.0], &[3.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[2.0], &[3.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(2.0, 1.0, 0.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[4.0], &[3.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[4.0], &[3.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(3.0, 0.0, 1.0, 0.0)
         );
     }
@@ -236,19 +278,85 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[2.0], &[3.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[2.0], &[3.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(2.0, 1.0, 0.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[4.0], &[3.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[4.0], &[3.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(3.0, 0.0, 1.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[4.0], &[3.0], &[5.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[4.0], &[3.0], &[5.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(5.0, 0.0, 0.0, 1.0)
         );
     }
 
+    pub fn test_g_logic() {
+        // These operations are discontinuous, so they're tested here instead
+        // of in `test_g_unary_ops` / `test_g_binary_ops` (which check
+        // gradients against finite differences)
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+
+        let not = ctx.not(x).unwrap();
+        let shape = S::new(&ctx, not).unwrap();
+        let mut eval = S::new_grad_slice_eval();
+        let tape = shape.ez_grad_slice_tape();
+        assert_eq!(
+            eval.eval(&tape, &[0.0, 2.0], &[0.0; 2], &[0.0; 2], &[])
+                .unwrap()
+                .output(0),
+            &[Grad::new(1.0, 0.0, 0.0, 0.0), Grad::new(0.0, 0.0, 0.0, 0.0)]
+        );
+
+        let compare = ctx.compare(x, y).unwrap();
+        let shape = S::new(&ctx, compare).unwrap();
+        let tape = shape.ez_grad_slice_tape();
+        assert_eq!(
+            eval.eval(
+                &tape,
+                &[1.0, 2.0, 2.0],
+                &[2.0, 1.0, 2.0],
+                &[0.0; 3],
+                &[]
+            )
+            .unwrap()
+            .output(0),
+            &[
+                Grad::new(-1.0, 0.0, 0.0, 0.0),
+                Grad::new(1.0, 0.0, 0.0, 0.0),
+                Grad::new(0.0, 0.0, 0.0, 0.0)
+            ]
+        );
+
+        let and = ctx.and(x, y).unwrap();
+        let shape = S::new(&ctx, and).unwrap();
+        let tape = shape.ez_grad_slice_tape();
+        assert_eq!(
+            eval.eval(&tape, &[0.0, 2.0], &[3.0; 2], &[0.0; 2], &[])
+                .unwrap()
+                .output(0),
+            &[Grad::new(0.0, 1.0, 0.0, 0.0), Grad::new(3.0, 0.0, 1.0, 0.0)]
+        );
+
+        let or = ctx.or(x, y).unwrap();
+        let shape = S::new(&ctx, or).unwrap();
+        let tape = shape.ez_grad_slice_tape();
+        assert_eq!(
+            eval.eval(&tape, &[0.0, 2.0], &[3.0; 2], &[0.0; 2], &[])
+                .unwrap()
+                .output(0),
+            &[Grad::new(3.0, 0.0, 1.0, 0.0), Grad::new(2.0, 1.0, 0.0, 0.0)]
+        );
+    }
+
     pub fn test_g_max() {
         let mut ctx = Context::new();
         let x = ctx.x();
@@ -259,11 +367,15 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[2.0], &[3.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[2.0], &[3.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(3.0, 0.0, 1.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[4.0], &[3.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[4.0], &[3.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(4.0, 1.0, 0.0, 0.0)
         );
     }
@@ -283,19 +395,27 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[1.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[1.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(0.5, 1.0, 0.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[0.0], &[1.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[0.0], &[1.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(0.5, 0.0, 1.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[2.0], &[0.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[2.0], &[0.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(1.5, 1.0, 0.0, 0.0)
         );
         assert_eq!(
-            eval.eval(&tape, &[0.0], &[2.0], &[0.0], &[]).unwrap()[0],
+            eval.eval(&tape, &[0.0], &[2.0], &[0.0], &[])
+                .unwrap()
+                .output(0)[0],
             Grad::new(1.5, 0.0, 1.0, 0.0)
         );
     }
@@ -307,11 +427,15 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[0.0], &[0.0], &[0.0], &[1.0]).unwrap()[0],
+            eval.eval(&tape, &[0.0], &[0.0], &[0.0], &[1.0])
+                .unwrap()
+                .output(0)[0],
             1.0.into()
         );
         assert_eq!(
-            eval.eval(&tape, &[0.0], &[0.0], &[0.0], &[2.0]).unwrap()[0],
+            eval.eval(&tape, &[0.0], &[0.0], &[0.0], &[2.0])
+                .unwrap()
+                .output(0)[0],
             2.0.into()
         );
 
@@ -324,11 +448,15 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
         assert_eq!(
-            eval.eval(&tape, &[0.0], &[0.0], &[0.0], &[1.0]).unwrap()[0],
+            eval.eval(&tape, &[0.0], &[0.0], &[0.0], &[1.0])
+                .unwrap()
+                .output(0)[0],
             1.0.into()
         );
         assert_eq!(
-            eval.eval(&tape, &[0.0], &[0.0], &[0.0], &[2.0]).unwrap()[0],
+            eval.eval(&tape, &[0.0], &[0.0], &[0.0], &[2.0])
+                .unwrap()
+                .output(0)[0],
             1.5.into()
         );
 
@@ -350,7 +478,8 @@ where
                 &[0.0],
                 vars.bind([("a", 5.0), ("b", 3.0)].into_iter())
             )
-            .unwrap()[0],
+            .unwrap()
+            .output(0)[0],
             2.0.into()
         );
         assert_eq!(
@@ -361,7 +490,8 @@ where
                 &[0.0],
                 vars.bind([("a", 3.0), ("b", 2.0)].into_iter())
             )
-            .unwrap()[0],
+            .unwrap()
+            .output(0)[0],
             2.0.into()
         );
         assert_eq!(
@@ -372,7 +502,8 @@ where
                 &[0.0],
                 vars.bind([("a", 0.0), ("b", 2.0)].into_iter())
             )
-            .unwrap()[0],
+            .unwrap()
+            .output(0)[0],
             0.5.into(),
         );
     }
@@ -392,7 +523,7 @@ where
         let mut eval = S::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
 
-        let out = eval.eval(&tape, &x, &y, &z, &[]).unwrap();
+        let out = eval.eval(&tape, &x, &y, &z, &[]).unwrap().output(0);
 
         // Compare values (the `.v` term) with the context's evaluator
         for (i, v) in out.iter().cloned().enumerate() {
@@ -418,7 +549,7 @@ where
         let mut eval = VmShape::new_grad_slice_eval();
         let tape = shape.ez_grad_slice_tape();
 
-        let cmp = eval.eval(&tape, &x, &y, &z, &[]).unwrap();
+        let cmp = eval.eval(&tape, &x, &y, &z, &[]).unwrap().output(0);
         for (a, b) in out.iter().zip(cmp.iter()) {
             a.compare_eq(*b)
         }
@@ -448,7 +579,8 @@ where
                 2 => eval.eval(&tape, &zero, &zero, &args, &[]),
                 _ => unreachable!(),
             }
-            .unwrap();
+            .unwrap()
+            .output(0);
             for (a, &o) in args.iter().zip(out.iter()) {
                 let v = C::eval_f64(*a as f64);
                 let err = (v as f32 - o.v).abs();
@@ -466,7 +598,15 @@ where
                 if !v.is_nan() && grad < 1e9 && !grad.is_infinite() {
                     let d = C::eval_f64(*a as f64 + 1e-8);
                     let estimated_gradient = (d - v) / 1e-8;
-                    let err = (estimated_gradient as f32 - grad).abs();
+                    let mut err = (estimated_gradient as f32 - grad).abs();
+
+                    // Discontinuous operations are only checked from the
+                    // side where the value doesn't jump
+                    if C::DISCONTINUOUS {
+                        let d = C::eval_f64(*a as f64 - 1e-8);
+                        let estimated_gradient = (v - d) / 1e-8;
+                        err = err.min((estimated_gradient as f32 - grad).abs());
+                    }
                     assert!(
                         err < 1e-3,
                         "gradient estimate mismatch in '{}' at {a}:
@@ -596,7 +736,8 @@ where
                         (2, 2) => eval.eval(&tape, &zero, &zero, &args, &[]),
                         _ => unreachable!(),
                     }
-                    .unwrap();
+                    .unwrap()
+                    .output(0);
 
                     let rhs = if i == j { &args } else { &rgsa };
                     Self::compare_grad_results(
@@ -639,7 +780,8 @@ where
                         2 => eval.eval(&tape, &zero, &zero, &args, &[]),
                         _ => unreachable!(),
                     }
-                    .unwrap();
+                    .unwrap()
+                    .output(0);
 
                     let rhs = vec![*rhs; out.len()];
                     Self::compare_grad_results(
@@ -682,7 +824,8 @@ where
                         2 => eval.eval(&tape, &zero, &zero, &args, &[]),
                         _ => unreachable!(),
                     }
-                    .unwrap();
+                    .unwrap()
+                    .output(0);
 
                     let lhs = vec![*lhs; out.len()];
                     Self::compare_grad_results(
@@ -715,6 +858,9 @@ where
         Self::test_unary::<ln>();
         Self::test_unary::<square>();
         Self::test_unary::<sqrt>();
+        Self::test_unary::<floor>();
+        Self::test_unary::<ceil>();
+        Self::test_unary::<round>();
     }
 
     pub fn test_binary<C: CanonicalBinaryOp>() {
@@ -732,6 +878,8 @@ where
         Self::test_binary::<div>();
         Self::test_binary::<min>();
         Self::test_binary::<max>();
+        Self::test_binary::<atan2>();
+        Self::test_binary::<modulo>();
     }
 }
 
@@ -760,6 +908,7 @@ macro_rules! grad_slice_tests {
         $crate::grad_test!(test_g_min, $t);
         $crate::grad_test!(test_g_max, $t);
         $crate::grad_test!(test_g_min_max, $t);
+        $crate::grad_test!(test_g_logic, $t);
         $crate::grad_test!(test_g_div, $t);
         $crate::grad_test!(test_g_recip, $t);
         $crate::grad_test!(test_g_var, $t);
@@ -0,0 +1,468 @@
+//! Test suite for second derivative (Hessian) evaluation
+//!
+//! If the `eval-tests` feature is set, then this exposes a standard test suite
+//! for Hessian evaluators; otherwise, the module has no public exports.
+use super::{build_stress_fn, test_args, CanonicalBinaryOp, CanonicalUnaryOp};
+use crate::{
+    context::Context,
+    eval::{
+        types::Hessian, BulkEvaluator, EzShape, MathShape, Shape, ShapeVars,
+    },
+};
+
+/// Step size used when estimating derivatives with finite differences
+const EPSILON: f64 = 1e-4;
+
+/// Helper struct to put constrains on our `Shape` object
+pub struct TestHessianSlice<S>(std::marker::PhantomData<*const S>);
+
+impl<S> TestHessianSlice<S>
+where
+    S: Shape + MathShape + ShapeVars,
+{
+    fn eval_xyz(ctx: &Context, node: crate::context::Node) -> Vec<Hessian> {
+        let shape = S::new(ctx, node).unwrap();
+        let mut eval = S::new_hessian_slice_eval();
+        let tape = shape.ez_hessian_slice_tape();
+        eval.eval(&tape, &[2.0], &[3.0], &[4.0], &[])
+            .unwrap()
+            .output(0)
+            .to_owned()
+    }
+
+    pub fn test_h_inputs() {
+        let mut ctx = Context::new();
+        let zero = [[0.0; 3]; 3];
+        for (i, node) in [ctx.x(), ctx.y(), ctx.z()].into_iter().enumerate() {
+            let out = Self::eval_xyz(&ctx, node);
+            let mut d = [0.0; 3];
+            d[i] = 1.0;
+            assert_eq!(out[0], Hessian::new(i as f32 + 2.0, d, zero));
+        }
+    }
+
+    pub fn test_h_square() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let s = ctx.square(x).unwrap();
+        let out = Self::eval_xyz(&ctx, s);
+        assert_eq!(
+            out[0],
+            Hessian::new(
+                4.0,
+                [4.0, 0.0, 0.0],
+                [[2.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0]]
+            )
+        );
+    }
+
+    pub fn test_h_mul() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let xy = ctx.mul(x, y).unwrap();
+        let xyz = ctx.mul(xy, z).unwrap();
+        let out = Self::eval_xyz(&ctx, xyz);
+        assert_eq!(
+            out[0],
+            Hessian::new(
+                24.0,
+                [12.0, 8.0, 6.0],
+                [[0.0, 4.0, 3.0], [4.0, 0.0, 2.0], [3.0, 2.0, 0.0]]
+            )
+        );
+    }
+
+    pub fn test_h_var() {
+        let mut ctx = Context::new();
+        let a = ctx.var("a").unwrap();
+        let x = ctx.x();
+        let ax = ctx.mul(a, x).unwrap();
+        let s = ctx.mul(ax, x).unwrap();
+        let shape = S::new(&ctx, s).unwrap();
+        let mut eval = S::new_hessian_slice_eval();
+        let tape = shape.ez_hessian_slice_tape();
+        let out = eval
+            .eval(&tape, &[2.0], &[0.0], &[0.0], &[3.0])
+            .unwrap()
+            .output(0);
+        assert_eq!(
+            out[0],
+            Hessian::new(
+                12.0,
+                [12.0, 0.0, 0.0],
+                [[6.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0]]
+            )
+        );
+    }
+
+    pub fn test_h_sphere() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let x2 = ctx.square(x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let z2 = ctx.square(z).unwrap();
+        let r = ctx.add(x2, y2).unwrap();
+        let r = ctx.add(r, z2).unwrap();
+        let r = ctx.sqrt(r).unwrap();
+        let sphere = ctx.sub(r, 1.0).unwrap();
+        let shape = S::new(&ctx, sphere).unwrap();
+        let mut eval = S::new_hessian_slice_eval();
+        let tape = shape.ez_hessian_slice_tape();
+
+        let xs = [1.0, 0.0, 0.5, 2.0];
+        let ys = [0.0, 0.5, 0.5, -1.0];
+        let zs = [0.0, 0.0, -1.0, 3.0];
+        let out = eval.eval(&tape, &xs, &ys, &zs, &[]).unwrap().output(0);
+        for (i, o) in out.iter().enumerate() {
+            let r = (xs[i].powi(2) + ys[i].powi(2) + zs[i].powi(2)).sqrt();
+            assert!((o.v - (r - 1.0)).abs() < 1e-6);
+            let h = o.mean_curvature();
+            assert!((h - 1.0 / r).abs() < 1e-5, "bad mean curvature {h}");
+            let k = o.gaussian_curvature();
+            assert!((k - 1.0 / r.powi(2)).abs() < 1e-5, "bad curvature {k}");
+        }
+    }
+
+    pub fn test_h_cylinder() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let x2 = ctx.square(x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let r = ctx.add(x2, y2).unwrap();
+        let r = ctx.sqrt(r).unwrap();
+        let cyl = ctx.sub(r, 0.5).unwrap();
+
+        let shape = S::new(&ctx, cyl).unwrap();
+        let mut eval = S::new_hessian_slice_eval();
+        let tape = shape.ez_hessian_slice_tape();
+        let out = eval
+            .eval(
+                &tape,
+                &[0.5, 0.0, 0.3],
+                &[0.0, 2.0, 0.4],
+                &[1.0, 0.0, -5.0],
+                &[],
+            )
+            .unwrap()
+            .output(0);
+        for (o, r) in out.iter().zip([0.5, 2.0, 0.5]) {
+            let h = o.mean_curvature();
+            assert!((h - 0.5 / r).abs() < 1e-5, "bad mean curvature {h}");
+            let k = o.gaussian_curvature();
+            assert!(k.abs() < 1e-5, "bad Gaussian curvature {k}");
+        }
+
+        // A saddle has negative Gaussian curvature
+        let xy = ctx.mul(x, y).unwrap();
+        let z = ctx.z();
+        let saddle = ctx.sub(z, xy).unwrap();
+        let out = Self::eval_xyz(&ctx, saddle);
+        assert!(out[0].gaussian_curvature() < 0.0);
+    }
+
+    pub fn test_h_stress_n(depth: usize) {
+        let (ctx, node) = build_stress_fn(depth);
+
+        // Pick an input slice that's guaranteed to be > 1 SIMD registers
+        let args = (0..32).map(|i| i as f32 / 32f32).collect::<Vec<f32>>();
+        let x = args.clone();
+        let y: Vec<f32> =
+            args[1..].iter().chain(&args[0..1]).cloned().collect();
+        let z: Vec<f32> =
+            args[2..].iter().chain(&args[0..2]).cloned().collect();
+
+        let shape = S::new(&ctx, node).unwrap();
+        let mut eval = S::new_hessian_slice_eval();
+        let tape = shape.ez_hessian_slice_tape();
+        let out = eval.eval(&tape, &x, &y, &z, &[]).unwrap().output(0);
+
+        // The value and first derivatives should match the gradient evaluator
+        let mut eval = S::new_grad_slice_eval();
+        let tape = shape.ez_grad_slice_tape();
+        let cmp = eval.eval(&tape, &x, &y, &z, &[]).unwrap().output(0);
+        for (a, b) in out.iter().zip(cmp.iter()) {
+            a.grad().compare_eq(*b);
+        }
+
+        // Everything in the stress function is linear, except for a single
+        // `sin` node, so the Hessian is `-sin(s) * ds * dsᵀ`.
+        for (i, o) in out.iter().enumerate() {
+            let mut ds = [0.0; 3];
+            for j in 1..=depth {
+                ds[j % 3] += j as f32;
+            }
+            let s = (1..=depth)
+                .map(|j| j as f32 * [x[i], y[i], z[i]][j % 3])
+                .sum::<f32>();
+            for r in 0..3 {
+                for c in 0..3 {
+                    let scale = ds[r] * ds[c];
+                    let expected = -s.sin() * scale;
+                    let err = (o.h[r][c] - expected).abs();
+                    assert!(
+                        err < 1e-3 * scale.max(1.0),
+                        "Hessian mismatch at index {i} [{r}, {c}]: \
+                         {} != {expected}",
+                        o.h[r][c]
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn test_h_stress() {
+        for n in [1, 2, 4, 8, 12, 16, 32] {
+            Self::test_h_stress_n(n);
+        }
+    }
+
+    /// Estimates a first or second derivative with finite differences
+    ///
+    /// First derivatives are estimated with central, forward, and backward
+    /// differences, because they may differ at C1 discontinuities.  Estimates
+    /// are discarded if the function doesn't appear to be smooth at `p` (i.e.
+    /// the estimate changes when the step size is halved).
+    fn estimates(
+        f: &impl Fn([f64; 3]) -> f64,
+        p: [f64; 3],
+        i: usize,
+        j: Option<usize>,
+    ) -> Vec<f64> {
+        let at = |di: f64, dj: f64| {
+            let mut p = p;
+            p[i] += di;
+            if let Some(j) = j {
+                p[j] += dj;
+            }
+            f(p)
+        };
+        let est = |e: f64, dir: i32| match (j, dir) {
+            (None, 0) => (at(e, 0.0) - at(-e, 0.0)) / (2.0 * e),
+            (None, 1) => (at(e, 0.0) - f(p)) / e,
+            (None, _) => (f(p) - at(-e, 0.0)) / e,
+            (Some(j), _) if j == i => {
+                (at(e, 0.0) - 2.0 * f(p) + at(-e, 0.0)) / e.powi(2)
+            }
+            (Some(_), _) => {
+                (at(e, e) - at(e, -e) - at(-e, e) + at(-e, -e))
+                    / (4.0 * e.powi(2))
+            }
+        };
+        let dirs = if j.is_none() {
+            &[0, 1, -1][..]
+        } else {
+            &[0][..]
+        };
+        dirs.iter()
+            .filter_map(|&dir| {
+                let a = est(EPSILON, dir);
+                let b = est(EPSILON / 2.0, dir);
+                (a.is_finite() && (a - b).abs() < 1e-3 * a.abs().max(1.0))
+                    .then_some(a)
+            })
+            .collect()
+    }
+
+    /// Checks results against finite-difference estimates
+    ///
+    /// `f` is the reference function, which is evaluated at `(xs[i], ys[i],
+    /// 0.0)` for each point.
+    fn compare_hessian_results(
+        name: &str,
+        xs: &[f32],
+        ys: &[f32],
+        out: &[Hessian],
+        f: impl Fn([f64; 3]) -> f64,
+    ) {
+        for ((x, y), o) in xs.iter().zip(ys).zip(out) {
+            let p = [*x as f64, *y as f64, 0.0];
+            let v = f(p);
+            let err = (v as f32 - o.v).abs();
+            let err_frac = err / (v.abs() as f32).max(o.v.abs());
+            assert!(
+                o.v == v as f32
+                    || err < 1e-6
+                    || err_frac < 1e-6
+                    || (v.is_nan() && o.v.is_nan()),
+                "value mismatch in '{name}' at ({x}, {y}): {v} != {o:?}"
+            );
+            if !v.is_finite() {
+                continue;
+            }
+
+            for i in 0..3 {
+                for j in [None, Some(0), Some(1), Some(2)] {
+                    let actual = match j {
+                        None => o.d[i],
+                        Some(j) => o.h[i][j],
+                    };
+                    // Skip singularities, like the gradient tests
+                    if actual.is_nan() || actual.abs() >= 1e9 {
+                        continue;
+                    }
+                    let est = Self::estimates(&f, p, i, j);
+                    assert!(
+                        est.is_empty()
+                            || est.iter().any(|e| (*e as f32 - actual).abs()
+                                < 1e-3 * (e.abs() as f32).max(1.0)),
+                        "derivative mismatch in '{name}' at ({x}, {y}) \
+                         [{i}, {j:?}]: {est:?} != {actual}"
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn test_unary<C: CanonicalUnaryOp>() {
+        let args = test_args();
+        let zero = vec![0.0; args.len()];
+
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let node = C::build(&mut ctx, x);
+
+        let shape = S::new(&ctx, node).unwrap();
+        let mut eval = S::new_hessian_slice_eval();
+        let tape = shape.ez_hessian_slice_tape();
+        let out = eval
+            .eval(&tape, &args, &zero, &zero, &[])
+            .unwrap()
+            .output(0);
+        Self::compare_hessian_results(C::NAME, &args, &zero, out, |p| {
+            C::eval_f64(p[0])
+        });
+    }
+
+    pub fn test_binary<C: CanonicalBinaryOp>() {
+        let args = test_args();
+        let zero = vec![0.0; args.len()];
+
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let shape_for = |ctx: &Context, node| {
+            let shape = S::new(ctx, node).unwrap();
+            let tape = shape.ez_hessian_slice_tape();
+            (shape, tape)
+        };
+        let mut eval = S::new_hessian_slice_eval();
+
+        // Same register on both sides
+        let node = C::build(&mut ctx, x, x);
+        let (_shape, tape) = shape_for(&ctx, node);
+        let out = eval
+            .eval(&tape, &args, &zero, &zero, &[])
+            .unwrap()
+            .output(0);
+        let name = format!("{}(reg, reg)", C::NAME);
+        Self::compare_hessian_results(&name, &args, &zero, out, |p| {
+            C::eval_reg_reg_f64(p[0], p[0])
+        });
+
+        // Different registers, with every combination of arguments
+        let node = C::build(&mut ctx, x, y);
+        let (_shape, tape) = shape_for(&ctx, node);
+        for rot in 0..args.len() {
+            let mut rgsa = args.clone();
+            rgsa.rotate_left(rot);
+            let out = eval
+                .eval(&tape, &args, &rgsa, &zero, &[])
+                .unwrap()
+                .output(0);
+            Self::compare_hessian_results(&name, &args, &rgsa, out, |p| {
+                C::eval_reg_reg_f64(p[0], p[1])
+            });
+        }
+
+        // Immediates on either side
+        for &c in &args {
+            let imm = ctx.constant(c as f64);
+
+            let node = C::build(&mut ctx, x, imm);
+            let (_shape, tape) = shape_for(&ctx, node);
+            let out = eval
+                .eval(&tape, &args, &zero, &zero, &[])
+                .unwrap()
+                .output(0);
+            let name = format!("{}(reg, imm)", C::NAME);
+            Self::compare_hessian_results(&name, &args, &zero, out, |p| {
+                C::eval_reg_imm_f64(p[0], c as f64)
+            });
+
+            let node = C::build(&mut ctx, imm, x);
+            let (_shape, tape) = shape_for(&ctx, node);
+            let out = eval
+                .eval(&tape, &args, &zero, &zero, &[])
+                .unwrap()
+                .output(0);
+            let name = format!("{}(imm, reg)", C::NAME);
+            Self::compare_hessian_results(&name, &args, &zero, out, |p| {
+                C::eval_imm_reg_f64(c as f64, p[0])
+            });
+        }
+    }
+
+    pub fn test_h_unary_ops() {
+        use super::canonical::*;
+
+        Self::test_unary::<neg>();
+        Self::test_unary::<recip>();
+        Self::test_unary::<abs>();
+        Self::test_unary::<sin>();
+        Self::test_unary::<cos>();
+        Self::test_unary::<tan>();
+        Self::test_unary::<asin>();
+        Self::test_unary::<acos>();
+        Self::test_unary::<atan>();
+        Self::test_unary::<exp>();
+        Self::test_unary::<ln>();
+        Self::test_unary::<square>();
+        Self::test_unary::<sqrt>();
+        Self::test_unary::<floor>();
+        Self::test_unary::<ceil>();
+        Self::test_unary::<round>();
+    }
+
+    pub fn test_h_binary_ops() {
+        use super::canonical::*;
+
+        Self::test_binary::<add>();
+        Self::test_binary::<sub>();
+        Self::test_binary::<mul>();
+        Self::test_binary::<div>();
+        Self::test_binary::<min>();
+        Self::test_binary::<max>();
+        Self::test_binary::<atan2>();
+        Self::test_binary::<modulo>();
+    }
+}
+
+#[macro_export]
+macro_rules! hessian_test {
+    ($i:ident, $t:ty) => {
+        #[test]
+        fn $i() {
+            $crate::eval::test::hessian_slice::TestHessianSlice::<$t>::$i()
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! hessian_slice_tests {
+    ($t:ty) => {
+        $crate::hessian_test!(test_h_inputs, $t);
+        $crate::hessian_test!(test_h_square, $t);
+        $crate::hessian_test!(test_h_mul, $t);
+        $crate::hessian_test!(test_h_var, $t);
+        $crate::hessian_test!(test_h_sphere, $t);
+        $crate::hessian_test!(test_h_cylinder, $t);
+        $crate::hessian_test!(test_h_stress, $t);
+        $crate::hessian_test!(test_h_unary_ops, $t);
+        $crate::hessian_test!(test_h_binary_ops, $t);
+    };
+}
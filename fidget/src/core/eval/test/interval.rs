@@ -140,7 +140,26 @@ where
         let tape = shape.ez_interval_tape();
 
         let mut eval = S::new_interval_eval();
-        assert_eq!(eval.eval_x(&tape, [0.0, 1.0]), [-1.0, 1.0].into());
+        let v = eval.eval_x(&tape, [0.0, 1.0]);
+        assert!(v.lower() <= 0.0 && v.lower() > -1e-6, "{v:?}");
+        assert!(v.upper() >= 1f32.sin() && v.upper() < 0.85, "{v:?}");
+
+        let v = eval.eval_x(&tape, [1.0, 2.0]);
+        assert!(v.lower() <= 1f32.sin() && v.lower() > 0.84, "{v:?}");
+        assert_eq!(v.upper(), 1.0);
+
+        let v = eval.eval_x(&tape, [-2.0, 4.0]);
+        assert_eq!(v, [-1.0, 1.0].into());
+        let v = eval.eval_x(&tape, [0.0, 7.0]);
+        assert_eq!(v, [-1.0, 1.0].into());
+        let v = eval.eval_x(&tape, [f32::NEG_INFINITY, 0.0]);
+        assert_eq!(v, [-1.0, 1.0].into());
+
+        let (v, _) = eval
+            .eval(&tape, [f32::NAN; 2], [0.0, 1.0], [0.0; 2], &[])
+            .unwrap();
+        assert!(v.lower().is_nan());
+        assert!(v.upper().is_nan());
 
         let y = ctx.y();
         let y = ctx.mul(y, 2.0).unwrap();
@@ -149,7 +168,123 @@ where
         let shape = S::new(&ctx, s).unwrap();
         let tape = shape.ez_interval_tape();
 
-        assert_eq!(eval.eval_x(&tape, [0.0, 3.0]), [-1.0, 4.0].into());
+        let v = eval.eval_x(&tape, [0.0, 3.0]);
+        assert!(v.lower() <= 0.0 && v.lower() > -1e-6, "{v:?}");
+        assert!(v.upper() >= 3.0 && v.upper() < 3.000001, "{v:?}");
+        let v = eval.eval_xy(&tape, [0.0, 3.0], [0.0, 1.0]);
+        assert!(v.lower() <= 0.0 && v.lower() > -1e-6, "{v:?}");
+        assert_eq!(v.upper(), 4.0);
+    }
+
+    pub fn test_i_cos() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let c = ctx.cos(x).unwrap();
+        let shape = S::new(&ctx, c).unwrap();
+        let tape = shape.ez_interval_tape();
+
+        let mut eval = S::new_interval_eval();
+        let v = eval.eval_x(&tape, [0.0, 1.0]);
+        assert!(v.lower() <= 1f32.cos() && v.lower() > 0.54, "{v:?}");
+        assert_eq!(v.upper(), 1.0);
+
+        let v = eval.eval_x(&tape, [1.0, 2.0]);
+        assert!(v.lower() <= 2f32.cos() && v.lower() > -0.42, "{v:?}");
+        assert!(v.upper() >= 1f32.cos() && v.upper() < 0.55, "{v:?}");
+
+        let v = eval.eval_x(&tape, [3.0, 4.0]);
+        assert_eq!(v.lower(), -1.0);
+        assert!(v.upper() >= 4f32.cos() && v.upper() < -0.65, "{v:?}");
+
+        let v = eval.eval_x(&tape, [-1.0, 6.0]);
+        assert_eq!(v, [-1.0, 1.0].into());
+        let v = eval.eval_x(&tape, [0.0, f32::INFINITY]);
+        assert_eq!(v, [-1.0, 1.0].into());
+
+        let (v, _) = eval
+            .eval(&tape, [f32::NAN; 2], [0.0, 1.0], [0.0; 2], &[])
+            .unwrap();
+        assert!(v.lower().is_nan());
+        assert!(v.upper().is_nan());
+    }
+
+    /// Checks that `sin` and `cos` are conservative and tight over many
+    /// intervals, including ones with endpoints near their extrema
+    pub fn test_i_sin_cos_bounds() {
+        use ieee754::Ieee754;
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let sin_x = ctx.sin(x).unwrap();
+        let cos_x = ctx.cos(x).unwrap();
+
+        let mut starts = vec![];
+        for k in -16..=16 {
+            let v = k as f32 * std::f32::consts::FRAC_PI_4;
+            starts.extend([v, v - 1e-3, v + 1e-3]);
+            starts.extend([v.prev(), v.next()]);
+        }
+        for v in [1e3, 1e5, -1e5, 1e7] {
+            starts.extend([v, v + 0.5]);
+        }
+        let widths = [
+            0.0,
+            1e-4,
+            0.1,
+            1.0,
+            std::f32::consts::FRAC_PI_2,
+            std::f32::consts::PI,
+            3.0,
+            4.5,
+            6.0,
+            std::f32::consts::TAU,
+            7.0,
+        ];
+
+        const N: usize = 128;
+        let mut eval = S::new_interval_eval();
+        for (root, f) in
+            [(sin_x, f32::sin as fn(f32) -> f32), (cos_x, f32::cos)]
+        {
+            let shape = S::new(&ctx, root).unwrap();
+            let tape = shape.ez_interval_tape();
+            for &lower in &starts {
+                for &width in &widths {
+                    let upper = lower + width;
+                    let v = eval.eval_x(&tape, [lower, upper]);
+                    assert!(v.lower() >= -1.0 && v.upper() <= 1.0, "{v:?}");
+
+                    let mut min = f32::INFINITY;
+                    let mut max = f32::NEG_INFINITY;
+                    for i in 0..=N {
+                        let x = if i == N {
+                            upper
+                        } else {
+                            lower + (upper - lower) * (i as f32 / N as f32)
+                        };
+                        let out = f(x);
+                        assert!(
+                            v.contains(out),
+                            "f({x}) = {out} is not in {v:?} \
+                             (input [{lower}, {upper}])"
+                        );
+                        min = min.min(out);
+                        max = max.max(out);
+                    }
+
+                    // Sampling may miss the true extrema, but only by a
+                    // distance that's quadratic in the sample spacing (which
+                    // can't be finer than the spacing of f32 values)
+                    let h = ((upper - lower) / N as f32)
+                        .max(lower.abs().max(upper.abs()) * f32::EPSILON);
+                    let tol = h * h + 1e-5;
+                    assert!(
+                        v.lower() >= min - tol && v.upper() <= max + tol,
+                        "{v:?} is too wide for [{min}, {max}] \
+                         (input [{lower}, {upper}])"
+                    );
+                }
+            }
+        }
     }
 
     pub fn test_i_neg() {
@@ -488,6 +623,134 @@ where
         assert_eq!(data.unwrap().as_ref(), &[Choice::Left, Choice::Left]);
     }
 
+    pub fn test_i_and() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let and = ctx.and(x, y).unwrap();
+
+        let shape = S::new(&ctx, and).unwrap();
+        let tape = shape.ez_interval_tape();
+        let mut eval = S::new_interval_eval();
+        let (r, data) = eval
+            .eval(&tape, [0.0, 0.0], [1.0, 2.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(r, [0.0, 0.0].into());
+        assert_eq!(data.unwrap().as_ref(), &[Choice::Left]);
+
+        let (r, data) = eval
+            .eval(&tape, [1.0, 2.0], [3.0, 4.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(r, [3.0, 4.0].into());
+        assert_eq!(data.unwrap().as_ref(), &[Choice::Right]);
+
+        let (r, data) = eval
+            .eval(&tape, [-2.0, -1.0], [3.0, 4.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(r, [3.0, 4.0].into());
+        assert_eq!(data.unwrap().as_ref(), &[Choice::Right]);
+
+        let (r, data) = eval
+            .eval(&tape, [-1.0, 1.0], [3.0, 4.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(r, [0.0, 4.0].into());
+        assert!(data.is_none());
+
+        let (r, data) = eval
+            .eval(&tape, [0.0, 1.0], [-3.0, -2.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(r, [-3.0, 0.0].into());
+        assert!(data.is_none());
+
+        let (r, data) = eval
+            .eval(&tape, [f32::NAN; 2], [3.0, 4.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(r, [0.0, 4.0].into());
+        assert!(data.is_none());
+
+        let (v, data) = eval
+            .eval(&tape, [-1.0, 1.0], [f32::NAN; 2], [0.0; 2], &[])
+            .unwrap();
+        assert!(v.lower().is_nan());
+        assert!(v.upper().is_nan());
+        assert!(data.is_none());
+    }
+
+    pub fn test_i_or() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let or = ctx.or(x, y).unwrap();
+
+        let shape = S::new(&ctx, or).unwrap();
+        let tape = shape.ez_interval_tape();
+        let mut eval = S::new_interval_eval();
+        let (r, data) = eval
+            .eval(&tape, [0.0, 0.0], [1.0, 2.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(r, [1.0, 2.0].into());
+        assert_eq!(data.unwrap().as_ref(), &[Choice::Right]);
+
+        let (r, data) = eval
+            .eval(&tape, [1.0, 2.0], [3.0, 4.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(r, [1.0, 2.0].into());
+        assert_eq!(data.unwrap().as_ref(), &[Choice::Left]);
+
+        let (r, data) = eval
+            .eval(&tape, [-1.0, 1.0], [3.0, 4.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(r, [-1.0, 4.0].into());
+        assert!(data.is_none());
+
+        let (v, data) = eval
+            .eval(&tape, [f32::NAN; 2], [3.0, 4.0], [0.0; 2], &[])
+            .unwrap();
+        assert!(v.lower().is_nan());
+        assert!(v.upper().is_nan());
+        assert!(data.is_none());
+    }
+
+    pub fn test_i_if_nonzero_else() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let out = ctx.if_nonzero_else(x, y, z).unwrap();
+
+        let shape = S::new(&ctx, out).unwrap();
+        let tape = shape.ez_interval_tape();
+        let mut eval = S::new_interval_eval();
+        let (r, data) = eval
+            .eval(&tape, [1.0, 2.0], [3.0, 4.0], [5.0, 6.0], &[])
+            .unwrap();
+        assert_eq!(r, [3.0, 4.0].into());
+        let next = shape.ez_simplify(data.unwrap()).unwrap();
+        assert!(next.size() < shape.size());
+
+        let tape = next.ez_interval_tape();
+        let (r, data) = eval
+            .eval(&tape, [1.0, 2.0], [3.0, 4.0], [5.0, 6.0], &[])
+            .unwrap();
+        assert_eq!(r, [3.0, 4.0].into());
+        assert!(data.is_none());
+
+        let tape = shape.ez_interval_tape();
+        let (r, data) = eval
+            .eval(&tape, [0.0, 0.0], [3.0, 4.0], [5.0, 6.0], &[])
+            .unwrap();
+        assert_eq!(r, [5.0, 6.0].into());
+        let next = shape.ez_simplify(data.unwrap()).unwrap();
+        assert!(next.size() < shape.size());
+
+        let tape = next.ez_interval_tape();
+        let (r, data) = eval
+            .eval(&tape, [0.0, 0.0], [3.0, 4.0], [5.0, 6.0], &[])
+            .unwrap();
+        assert_eq!(r, [5.0, 6.0].into());
+        assert!(data.is_none());
+    }
+
     pub fn test_i_simplify() {
         let mut ctx = Context::new();
         let x = ctx.x();
@@ -905,6 +1168,10 @@ where
         Self::test_unary::<ln>();
         Self::test_unary::<square>();
         Self::test_unary::<sqrt>();
+        Self::test_unary::<floor>();
+        Self::test_unary::<ceil>();
+        Self::test_unary::<round>();
+        Self::test_unary::<not>();
     }
 
     pub fn test_binary<C: CanonicalBinaryOp>() {
@@ -922,6 +1189,11 @@ where
         Self::test_binary::<div>();
         Self::test_binary::<min>();
         Self::test_binary::<max>();
+        Self::test_binary::<atan2>();
+        Self::test_binary::<modulo>();
+        Self::test_binary::<compare>();
+        Self::test_binary::<and>();
+        Self::test_binary::<or>();
     }
 }
 
@@ -943,6 +1215,8 @@ macro_rules! interval_tests {
         $crate::interval_test!(test_i_sqrt, $t);
         $crate::interval_test!(test_i_square, $t);
         $crate::interval_test!(test_i_sin, $t);
+        $crate::interval_test!(test_i_cos, $t);
+        $crate::interval_test!(test_i_sin_cos_bounds, $t);
         $crate::interval_test!(test_i_neg, $t);
         $crate::interval_test!(test_i_mul, $t);
         $crate::interval_test!(test_i_mul_imm, $t);
@@ -954,6 +1228,9 @@ macro_rules! interval_tests {
         $crate::interval_test!(test_i_min_imm, $t);
         $crate::interval_test!(test_i_max, $t);
         $crate::interval_test!(test_i_max_imm, $t);
+        $crate::interval_test!(test_i_and, $t);
+        $crate::interval_test!(test_i_or, $t);
+        $crate::interval_test!(test_i_if_nonzero_else, $t);
         $crate::interval_test!(test_i_simplify, $t);
         $crate::interval_test!(test_i_var, $t);
         $crate::interval_test!(test_i_stress, $t);
@@ -0,0 +1,378 @@
+//! Tests for interval evaluators with partial derivatives
+//!
+//! If the `eval-tests` feature is set, then this exposes a standard test suite
+//! for interval gradient evaluators; otherwise, the module has no public
+//! exports.
+//!
+//! Results are checked against the gradient slice evaluator: values and
+//! partial derivatives at sample points must be within the returned bounds.
+use super::{
+    build_stress_fn, test_args_n, CanonicalBinaryOp, CanonicalUnaryOp,
+};
+use crate::{
+    context::{Context, Node},
+    eval::{
+        types::{Interval, IntervalGrad},
+        BulkEvaluator, EzShape, MathShape, Shape, TracingEvaluator,
+    },
+    vm::Choice,
+};
+
+/// Helper struct to put constrains on our `Shape` object
+pub struct TestIntervalGrad<S>(std::marker::PhantomData<*const S>);
+
+impl<S> TestIntervalGrad<S>
+where
+    S: Shape + MathShape,
+    <S as Shape>::Trace: AsRef<[Choice]>,
+{
+    /// Intervals used as inputs
+    fn interval_grad_test_args() -> Vec<Interval> {
+        let mut args = test_args_n(4);
+        args.retain(|a| !a.is_nan());
+        let mut out = vec![];
+        for &lower in &args {
+            out.push(Interval::from(lower));
+            for size in [0.1, 1.0, 2.5] {
+                out.push(Interval::new(lower, lower + size));
+            }
+        }
+        out
+    }
+
+    /// Returns a set of sample points within the interval
+    fn samples(i: Interval) -> Vec<f32> {
+        let mut out = vec![i.lower(), i.upper()];
+        for j in 1..8 {
+            let pos = j as f32 / 8.0;
+            out.push(i.lerp(pos).clamp(i.lower(), i.upper()));
+        }
+        out
+    }
+
+    /// Checks that `v` is within `i`, with a small tolerance for rounding
+    ///
+    /// `NaN` bounds and non-finite values are skipped.
+    fn check_within(v: f32, i: Interval) -> bool {
+        let eps = 1e-4 * v.abs().max(1.0);
+        i.has_nan()
+            || !v.is_finite()
+            || (v >= i.lower() - eps && v <= i.upper() + eps)
+    }
+
+    /// Evaluates `root` over the given region, checking that the value and
+    /// derivatives at sample points are within the returned bounds
+    fn check_bounds(ctx: &Context, root: Node, x: Interval, y: Interval) {
+        let shape = S::new(ctx, root).unwrap();
+        let tape = shape.ez_interval_grad_tape();
+        let mut eval = S::new_interval_grad_eval();
+        let (out, _trace) = eval.eval(&tape, x, y, 0.0.into(), &[]).unwrap();
+
+        let mut xs = vec![];
+        let mut ys = vec![];
+        for &px in &Self::samples(x) {
+            for &py in &Self::samples(y) {
+                xs.push(px);
+                ys.push(py);
+            }
+        }
+        let zs = vec![0.0; xs.len()];
+        let tape = shape.ez_grad_slice_tape();
+        let mut eval = S::new_grad_slice_eval();
+        let grads = eval.eval(&tape, &xs, &ys, &zs, &[]).unwrap().output(0);
+        for ((px, py), g) in xs.iter().zip(&ys).zip(grads) {
+            if g.v.is_nan() {
+                continue;
+            }
+            for (v, i) in
+                [(g.v, out.v), (g.dx, out.dx), (g.dy, out.dy), (g.dz, out.dz)]
+            {
+                assert!(
+                    Self::check_within(v, i),
+                    "f({px}, {py}) = {g} is not in {out} (inputs {x}, {y})"
+                );
+            }
+        }
+    }
+
+    pub fn test_ig_inputs() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let mut eval = S::new_interval_grad_eval();
+        for (node, d) in [
+            (x, [1.0, 0.0, 0.0]),
+            (y, [0.0, 1.0, 0.0]),
+            (z, [0.0, 0.0, 1.0]),
+        ] {
+            let shape = S::new(&ctx, node).unwrap();
+            let tape = shape.ez_interval_grad_tape();
+            let (out, trace) = eval
+                .eval(&tape, [0.0, 1.0], [2.0, 3.0], [4.0, 5.0], &[])
+                .unwrap();
+            assert!(trace.is_none());
+            for (i, d) in d.into_iter().enumerate() {
+                assert_eq!(out.d(i), d.into());
+            }
+            assert_eq!(out.grad_norm(), 1.0.into());
+        }
+
+        // Constants have zero derivatives
+        let c = ctx.constant(1.5);
+        let shape = S::new(&ctx, c).unwrap();
+        let tape = shape.ez_interval_grad_tape();
+        let (out, _) = eval
+            .eval(&tape, [0.0, 1.0], [0.0, 1.0], [0.0, 1.0], &[])
+            .unwrap();
+        assert_eq!(out, IntervalGrad::from(1.5));
+    }
+
+    pub fn test_ig_arithmetic() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let mut eval = S::new_interval_grad_eval();
+
+        // d/dx (x * y) = y, d/dy (x * y) = x
+        let mul = ctx.mul(x, y).unwrap();
+        let shape = S::new(&ctx, mul).unwrap();
+        let tape = shape.ez_interval_grad_tape();
+        let (out, _) = eval
+            .eval(&tape, [1.0, 2.0], [3.0, 5.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.v, [3.0, 10.0].into());
+        assert_eq!(out.dx, [3.0, 5.0].into());
+        assert_eq!(out.dy, [1.0, 2.0].into());
+        assert_eq!(out.dz, 0.0.into());
+
+        // d/dx x^2 = 2x
+        let sq = ctx.square(x).unwrap();
+        let shape = S::new(&ctx, sq).unwrap();
+        let tape = shape.ez_interval_grad_tape();
+        let (out, _) = eval
+            .eval(&tape, [-1.0, 2.0], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.v, [0.0, 4.0].into());
+        assert_eq!(out.dx, [-2.0, 4.0].into());
+
+        // The derivative of sqrt is unbounded at zero
+        let sqrt = ctx.sqrt(x).unwrap();
+        let shape = S::new(&ctx, sqrt).unwrap();
+        let tape = shape.ez_interval_grad_tape();
+        let (out, _) = eval
+            .eval(&tape, [0.0, 4.0], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.v, [0.0, 2.0].into());
+        assert!(out.dx.has_nan());
+        let (out, _) = eval
+            .eval(&tape, [1.0, 4.0], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.dx, [0.25, 0.5].into());
+    }
+
+    pub fn test_ig_choice() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let min = ctx.min(x, y).unwrap();
+        let shape = S::new(&ctx, min).unwrap();
+        let tape = shape.ez_interval_grad_tape();
+        let mut eval = S::new_interval_grad_eval();
+
+        // If one side is always picked, we take its derivatives
+        let (out, trace) = eval
+            .eval(&tape, [0.0, 1.0], [2.0, 3.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.v, [0.0, 1.0].into());
+        assert_eq!(out.dx, 1.0.into());
+        assert_eq!(out.dy, 0.0.into());
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Left]);
+
+        let (out, trace) = eval
+            .eval(&tape, [2.0, 3.0], [0.0, 1.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.dx, 0.0.into());
+        assert_eq!(out.dy, 1.0.into());
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Right]);
+
+        // Otherwise, the derivatives could come from either side
+        let (out, trace) = eval
+            .eval(&tape, [0.0, 2.0], [1.0, 3.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.v, [0.0, 2.0].into());
+        assert_eq!(out.dx, [0.0, 1.0].into());
+        assert_eq!(out.dy, [0.0, 1.0].into());
+        assert!(trace.is_none());
+    }
+
+    pub fn test_ig_step() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let floor = ctx.floor(x).unwrap();
+        let shape = S::new(&ctx, floor).unwrap();
+        let tape = shape.ez_interval_grad_tape();
+        let mut eval = S::new_interval_grad_eval();
+
+        // Within a single step, the function is constant
+        let (out, _) = eval
+            .eval(&tape, [0.25, 0.75], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out, IntervalGrad::from(0.0));
+
+        // Across a step, there's no bound on the derivative
+        let (out, _) = eval
+            .eval(&tape, [0.5, 1.5], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out.v, [0.0, 1.0].into());
+        assert!(out.dx.has_nan());
+        assert!(out.grad_norm().has_nan());
+    }
+
+    pub fn test_ig_sdf() {
+        // Distance to a circle is 1-Lipschitz, so the gradient's magnitude
+        // should be bounded near 1
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let x2 = ctx.square(x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let r2 = ctx.add(x2, y2).unwrap();
+        let r = ctx.sqrt(r2).unwrap();
+        let circle = ctx.sub(r, 0.5).unwrap();
+
+        // Scaling it by 2 makes it overestimate distances
+        let scaled = ctx.mul(circle, 2.0).unwrap();
+
+        let mut eval = S::new_interval_grad_eval();
+        for (node, scale) in [(circle, 1.0), (scaled, 2.0)] {
+            let shape = S::new(&ctx, node).unwrap();
+            let tape = shape.ez_interval_grad_tape();
+            let (out, _) = eval
+                .eval(&tape, [1.0, 1.1], [0.0, 0.1], [0.0; 2], &[])
+                .unwrap();
+            let n = out.grad_norm();
+            assert!(n.lower() <= scale && n.upper() >= scale, "{n}");
+            assert!(n.width() < 0.25 * scale, "{n} is too wide");
+        }
+    }
+
+    pub fn test_ig_stress() {
+        let (ctx, root) = build_stress_fn(16);
+        for (x, y) in [
+            ([0.0, 0.1], [0.0, 0.1]),
+            ([-0.5, 0.5], [0.25, 0.5]),
+            ([1.0, 1.0], [-1.0, 1.0]),
+        ] {
+            Self::check_bounds(&ctx, root, x.into(), y.into());
+        }
+    }
+
+    pub fn test_unary<C: CanonicalUnaryOp>() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let node = C::build(&mut ctx, x);
+        for a in Self::interval_grad_test_args() {
+            Self::check_bounds(&ctx, node, a, 0.0.into());
+        }
+
+        // Also check an argument which depends on both inputs, so that the
+        // chain rule is applied to multiple partial derivatives
+        let y = ctx.y();
+        let y2 = ctx.mul(y, 0.5).unwrap();
+        let xy = ctx.sub(x, y2).unwrap();
+        let node = C::build(&mut ctx, xy);
+        let args = Self::interval_grad_test_args();
+        for &a in args.iter().step_by(2) {
+            for &b in args.iter().step_by(7) {
+                Self::check_bounds(&ctx, node, a, b);
+            }
+        }
+    }
+
+    pub fn test_binary<C: CanonicalBinaryOp>() {
+        let args = Self::interval_grad_test_args();
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+
+        let node = C::build(&mut ctx, x, y);
+        for &a in &args {
+            for &b in args.iter().step_by(3) {
+                Self::check_bounds(&ctx, node, a, b);
+            }
+        }
+
+        for imm in [0.5, -1.5, 2.0] {
+            let c = ctx.constant(imm);
+            let reg_imm = C::build(&mut ctx, x, c);
+            let imm_reg = C::build(&mut ctx, c, x);
+            for &a in &args {
+                Self::check_bounds(&ctx, reg_imm, a, 0.0.into());
+                Self::check_bounds(&ctx, imm_reg, a, 0.0.into());
+            }
+        }
+    }
+
+    pub fn test_ig_unary_ops() {
+        use super::canonical::*;
+
+        Self::test_unary::<neg>();
+        Self::test_unary::<recip>();
+        Self::test_unary::<abs>();
+        Self::test_unary::<sin>();
+        Self::test_unary::<cos>();
+        Self::test_unary::<tan>();
+        Self::test_unary::<asin>();
+        Self::test_unary::<acos>();
+        Self::test_unary::<atan>();
+        Self::test_unary::<exp>();
+        Self::test_unary::<ln>();
+        Self::test_unary::<square>();
+        Self::test_unary::<sqrt>();
+        Self::test_unary::<floor>();
+        Self::test_unary::<ceil>();
+        Self::test_unary::<round>();
+        Self::test_unary::<not>();
+    }
+
+    pub fn test_ig_binary_ops() {
+        use super::canonical::*;
+
+        Self::test_binary::<add>();
+        Self::test_binary::<sub>();
+        Self::test_binary::<mul>();
+        Self::test_binary::<div>();
+        Self::test_binary::<min>();
+        Self::test_binary::<max>();
+        Self::test_binary::<atan2>();
+        Self::test_binary::<modulo>();
+        Self::test_binary::<compare>();
+        Self::test_binary::<and>();
+        Self::test_binary::<or>();
+    }
+}
+
+#[macro_export]
+macro_rules! interval_grad_test {
+    ($i:ident, $t:ty) => {
+        #[test]
+        fn $i() {
+            $crate::eval::test::interval_grad::TestIntervalGrad::<$t>::$i()
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! interval_grad_tests {
+    ($t:ty) => {
+        $crate::interval_grad_test!(test_ig_inputs, $t);
+        $crate::interval_grad_test!(test_ig_arithmetic, $t);
+        $crate::interval_grad_test!(test_ig_choice, $t);
+        $crate::interval_grad_test!(test_ig_step, $t);
+        $crate::interval_grad_test!(test_ig_sdf, $t);
+        $crate::interval_grad_test!(test_ig_stress, $t);
+        $crate::interval_grad_test!(test_ig_unary_ops, $t);
+        $crate::interval_grad_test!(test_ig_binary_ops, $t);
+    };
+}
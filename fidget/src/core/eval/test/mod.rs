@@ -1,8 +1,14 @@
 //! Test suites for each evaluator type
+pub mod affine;
+pub mod f64;
 pub mod float_slice;
 pub mod grad_slice;
+pub mod hessian_slice;
 pub mod interval;
+pub mod interval_grad;
+pub mod multi_grad_slice;
 pub mod point;
+pub mod robust_interval;
 
 use crate::context::{Context, Node};
 
@@ -47,13 +53,16 @@ fn test_args_n(n: i64) -> Vec<f32> {
     args
 }
 
-fn test_args() -> Vec<f32> {
+pub(crate) fn test_args() -> Vec<f32> {
     test_args_n(32)
 }
 
 /// Trait for canonical evaluation testing of unary operations
 pub trait CanonicalUnaryOp {
     const NAME: &'static str;
+    /// Whether the operation has jump discontinuities, which means that
+    /// finite-difference gradients are only valid from one side
+    const DISCONTINUOUS: bool = false;
     fn build(ctx: &mut Context, arg: Node) -> Node;
     fn eval_f32(arg: f32) -> f32;
     fn eval_f64(arg: f64) -> f64;
@@ -72,10 +81,11 @@ pub trait CanonicalBinaryOp {
 }
 
 macro_rules! declare_canonical_unary {
-    (Context::$i:ident, |$a:ident| $t:expr) => {
+    (Context::$i:ident, |$a:ident| $t:expr $(, $disc:literal)?) => {
         pub struct $i;
         impl CanonicalUnaryOp for $i {
             const NAME: &'static str = stringify!($i);
+            $(const DISCONTINUOUS: bool = $disc;)?
             fn build(ctx: &mut Context, arg: Node) -> Node {
                 Context::$i(ctx, arg).unwrap()
             }
@@ -154,7 +164,7 @@ macro_rules! declare_canonical_binary_full {
 }
 
 #[allow(non_camel_case_types)]
-mod canonical {
+pub(crate) mod canonical {
     use super::*;
 
     declare_canonical_unary!(Context::neg, |a| -a);
@@ -170,6 +180,14 @@ mod canonical {
     declare_canonical_unary!(Context::ln, |a| a.ln());
     declare_canonical_unary!(Context::square, |a| a * a);
     declare_canonical_unary!(Context::sqrt, |a| a.sqrt());
+    declare_canonical_unary!(Context::floor, |a| a.floor(), true);
+    declare_canonical_unary!(Context::ceil, |a| a.ceil(), true);
+    declare_canonical_unary!(Context::round, |a| a.round(), true);
+    declare_canonical_unary!(Context::not, |a| if a == 0.0 {
+        1.0
+    } else {
+        0.0
+    });
 
     declare_canonical_binary!(Context::add, |a, b| a + b);
     declare_canonical_binary!(Context::sub, |a, b| a - b);
@@ -201,4 +219,23 @@ mod canonical {
             a.max(b)
         }
     );
+    declare_canonical_binary!(Context::atan2, |y, x| y.atan2(x));
+    declare_canonical_binary!(Context::modulo, |a, b| a.rem_euclid(b));
+    declare_canonical_binary!(Context::compare, |a, b| if a.is_nan()
+        || b.is_nan()
+    {
+        a * b // get a NAN
+    } else if a < b {
+        -1.0
+    } else if a > b {
+        1.0
+    } else {
+        0.0
+    });
+    declare_canonical_binary!(Context::and, |a, b| if a == 0.0 {
+        a
+    } else {
+        b
+    });
+    declare_canonical_binary!(Context::or, |a, b| if a != 0.0 { a } else { b });
 }
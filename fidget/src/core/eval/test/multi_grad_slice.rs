@@ -0,0 +1,353 @@
+//! Test suite for multi-target partial derivative evaluation
+//!
+//! If the `eval-tests` feature is set, then this exposes a standard test suite
+//! for multi-target gradient evaluators; otherwise, the module has no public
+//! exports.
+use super::{build_stress_fn, test_args, CanonicalBinaryOp, CanonicalUnaryOp};
+use crate::{
+    context::{Context, Node},
+    eval::{
+        types::{Grad, MultiGrad},
+        BulkEvaluator, EzShape, GradTarget, MathShape, Shape, ShapeVars, Vars,
+    },
+    Error,
+};
+
+/// Helper struct to put constrains on our `Shape` object
+pub struct TestMultiGradSlice<S>(std::marker::PhantomData<*const S>);
+
+const XYZ: [GradTarget; 3] = [GradTarget::X, GradTarget::Y, GradTarget::Z];
+
+impl<S> TestMultiGradSlice<S>
+where
+    S: Shape + MathShape + ShapeVars,
+{
+    /// Checks that a `MultiGrad` is roughly equal to a `Grad`
+    fn check_grad(name: &str, i: usize, g: Grad, m: MultiGrad) {
+        for (a, b) in
+            [(g.v, m.v), (g.dx, m.d[0]), (g.dy, m.d[1]), (g.dz, m.d[2])]
+        {
+            let err = (a - b).abs() / a.abs().max(1.0);
+            assert!(
+                (a.is_nan() && b.is_nan()) || a == b || err < 1e-5,
+                "mismatch in '{name}' at {i}: {g} != {m}"
+            );
+        }
+        assert!(
+            m.d[3..].iter().all(|d| *d == 0.0 || d.is_nan()),
+            "unused derivatives in '{name}' at {i} are not zero: {m}"
+        );
+    }
+
+    /// Evaluates a node with [`XYZ`] targets and compares it against the
+    /// standard gradient evaluator
+    fn compare_with_grad(
+        ctx: &Context,
+        node: Node,
+        xs: &[f32],
+        ys: &[f32],
+        zs: &[f32],
+        name: &str,
+    ) {
+        let shape = S::new(ctx, node).unwrap();
+
+        let tape = shape.ez_grad_slice_tape();
+        let mut eval = S::new_grad_slice_eval();
+        let expected = eval.eval(&tape, xs, ys, zs, &[]).unwrap().output(0);
+
+        let tape = shape.ez_multi_grad_slice_tape(&XYZ).unwrap();
+        let mut eval = S::new_multi_grad_slice_eval();
+        let actual = eval.eval(&tape, xs, ys, zs, &[]).unwrap().output(0);
+
+        assert_eq!(expected.len(), actual.len());
+        for (i, (g, m)) in expected.iter().zip(actual).enumerate() {
+            Self::check_grad(name, i, *g, *m);
+        }
+    }
+
+    pub fn test_mg_xyz() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let shape = S::new(&ctx, x).unwrap();
+        let tape = shape.ez_multi_grad_slice_tape(&XYZ).unwrap();
+        let mut eval = S::new_multi_grad_slice_eval();
+        let out = eval
+            .eval(&tape, &[2.0], &[3.0], &[4.0], &[])
+            .unwrap()
+            .output(0);
+        assert_eq!(
+            out[0],
+            MultiGrad::new(2.0, [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0])
+        );
+
+        // Targets may be in any order, and may be repeated
+        let targets = [GradTarget::Z, GradTarget::X, GradTarget::Z];
+        let tape = shape.ez_multi_grad_slice_tape(&targets).unwrap();
+        let out = eval
+            .eval(&tape, &[2.0], &[3.0], &[4.0], &[])
+            .unwrap()
+            .output(0);
+        assert_eq!(
+            out[0],
+            MultiGrad::new(2.0, [0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0])
+        );
+
+        let sum = ctx.add(y, z).unwrap();
+        let shape = S::new(&ctx, sum).unwrap();
+        let tape = shape.ez_multi_grad_slice_tape(&targets).unwrap();
+        let out = eval
+            .eval(&tape, &[2.0], &[3.0], &[4.0], &[])
+            .unwrap()
+            .output(0);
+        assert_eq!(
+            out[0],
+            MultiGrad::new(7.0, [1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0])
+        );
+
+        // With no targets, we only get the value
+        let tape = shape.ez_multi_grad_slice_tape(&[]).unwrap();
+        let out = eval
+            .eval(&tape, &[2.0], &[3.0], &[4.0], &[])
+            .unwrap()
+            .output(0);
+        assert_eq!(out[0], MultiGrad::from(7.0));
+    }
+
+    pub fn test_mg_var() {
+        // Sphere with a variable radius
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let r = ctx.var("r").unwrap();
+        let x2 = ctx.square(x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let z2 = ctx.square(z).unwrap();
+        let sum = ctx.add(x2, y2).unwrap();
+        let sum = ctx.add(sum, z2).unwrap();
+        let dist = ctx.sqrt(sum).unwrap();
+        let sphere = ctx.sub(dist, r).unwrap();
+
+        let shape = S::new(&ctx, sphere).unwrap();
+        let targets = [GradTarget::Var("r".to_owned()), GradTarget::X];
+        let tape = shape.ez_multi_grad_slice_tape(&targets).unwrap();
+        let mut eval = S::new_multi_grad_slice_eval();
+
+        let mut vars = Vars::new(shape.vars());
+        let out = eval
+            .eval(
+                &tape,
+                &[3.0, 0.0],
+                &[4.0, 0.0],
+                &[0.0, 2.0],
+                vars.bind([("r", 1.5)].into_iter()),
+            )
+            .unwrap()
+            .output(0);
+        assert_eq!(
+            out[0],
+            MultiGrad::new(3.5, [-1.0, 0.6, 0.0, 0.0, 0.0, 0.0, 0.0])
+        );
+        assert_eq!(
+            out[1],
+            MultiGrad::new(0.5, [-1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0])
+        );
+    }
+
+    pub fn test_mg_vars() {
+        // a * x + b * y^2 + sin(c), with derivatives for every input and var
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let a = ctx.var("a").unwrap();
+        let b = ctx.var("b").unwrap();
+        let c = ctx.var("c").unwrap();
+        let ax = ctx.mul(a, x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let by2 = ctx.mul(b, y2).unwrap();
+        let sc = ctx.sin(c).unwrap();
+        let sum = ctx.add(ax, by2).unwrap();
+        let sum = ctx.add(sum, sc).unwrap();
+
+        let shape = S::new(&ctx, sum).unwrap();
+        let targets = [
+            GradTarget::Var("c".to_owned()),
+            GradTarget::X,
+            GradTarget::Var("a".to_owned()),
+            GradTarget::Y,
+            GradTarget::Var("b".to_owned()),
+            GradTarget::Z,
+            GradTarget::Var("a".to_owned()),
+        ];
+        let tape = shape.ez_multi_grad_slice_tape(&targets).unwrap();
+        let mut eval = S::new_multi_grad_slice_eval();
+
+        let mut vars = Vars::new(shape.vars());
+        let (va, vb, vc) = (2.0f32, -3.0f32, 0.5f32);
+        let vars = vars.bind([("a", va), ("b", vb), ("c", vc)].into_iter());
+        let xs = [1.0, -2.0, 0.5];
+        let ys = [0.0, 1.5, -0.25];
+        let out = eval
+            .eval(&tape, &xs, &ys, &[0.0; 3], vars)
+            .unwrap()
+            .output(0);
+        for (i, o) in out.iter().enumerate() {
+            let (x, y) = (xs[i], ys[i]);
+            let expected = MultiGrad::new(
+                va * x + vb * y * y + vc.sin(),
+                [vc.cos(), va, x, 2.0 * vb * y, y * y, 0.0, x],
+            );
+            assert!((o.v - expected.v).abs() < 1e-6, "{o} != {expected}");
+            for (a, b) in o.d.iter().zip(expected.d) {
+                assert!((a - b).abs() < 1e-6, "{o} != {expected}");
+            }
+        }
+    }
+
+    pub fn test_mg_errors() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let a = ctx.var("a").unwrap();
+        let sum = ctx.add(x, a).unwrap();
+        let shape = S::new(&ctx, sum).unwrap();
+
+        let targets = vec![GradTarget::X; MultiGrad::N + 1];
+        assert!(matches!(
+            shape.ez_multi_grad_slice_tape(&targets),
+            Err(Error::TooManyGradTargets(8, 7))
+        ));
+
+        let targets = [GradTarget::Var("b".to_owned())];
+        assert!(matches!(
+            shape.ez_multi_grad_slice_tape(&targets),
+            Err(Error::UnknownVariable(..))
+        ));
+
+        let targets = [GradTarget::Var("a".to_owned())];
+        let tape = shape.ez_multi_grad_slice_tape(&targets).unwrap();
+        let mut eval = S::new_multi_grad_slice_eval();
+        assert!(matches!(
+            eval.eval(&tape, &[1.0], &[2.0], &[3.0], &[]),
+            Err(Error::BadVarSlice(0, 1))
+        ));
+        assert!(matches!(
+            eval.eval(&tape, &[1.0], &[2.0], &[], &[1.0]),
+            Err(Error::MismatchedSlices)
+        ));
+    }
+
+    pub fn test_mg_stress() {
+        let args = test_args();
+        let zs = args.iter().rev().cloned().collect::<Vec<_>>();
+        for n in [4, 8, 12, 16, 32] {
+            let (ctx, node) = build_stress_fn(n);
+            Self::compare_with_grad(
+                &ctx,
+                node,
+                &args,
+                &args,
+                &zs,
+                &format!("stress({n})"),
+            );
+        }
+    }
+
+    fn test_unary<C: CanonicalUnaryOp>() {
+        let args = test_args();
+        let zero = vec![0.0; args.len()];
+
+        let mut ctx = Context::new();
+        let y = ctx.y();
+        let node = C::build(&mut ctx, y);
+        Self::compare_with_grad(&ctx, node, &zero, &args, &zero, C::NAME);
+    }
+
+    fn test_binary<C: CanonicalBinaryOp>() {
+        let args = test_args();
+        let zero = vec![0.0; args.len()];
+
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let z = ctx.z();
+        for rot in 0..args.len() {
+            let mut rgsa = args.clone();
+            rgsa.rotate_left(rot);
+
+            let node = C::build(&mut ctx, x, z);
+            let name = format!("{}(reg, reg)", C::NAME);
+            Self::compare_with_grad(&ctx, node, &args, &zero, &rgsa, &name);
+
+            let c = ctx.constant(args[rot] as f64);
+            let node = C::build(&mut ctx, z, c);
+            let name = format!("{}(reg, imm)", C::NAME);
+            Self::compare_with_grad(&ctx, node, &zero, &zero, &rgsa, &name);
+
+            let node = C::build(&mut ctx, c, x);
+            let name = format!("{}(imm, reg)", C::NAME);
+            Self::compare_with_grad(&ctx, node, &rgsa, &zero, &zero, &name);
+        }
+    }
+
+    pub fn test_mg_unary_ops() {
+        use super::canonical::*;
+
+        Self::test_unary::<neg>();
+        Self::test_unary::<recip>();
+        Self::test_unary::<abs>();
+        Self::test_unary::<sin>();
+        Self::test_unary::<cos>();
+        Self::test_unary::<tan>();
+        Self::test_unary::<asin>();
+        Self::test_unary::<acos>();
+        Self::test_unary::<atan>();
+        Self::test_unary::<exp>();
+        Self::test_unary::<ln>();
+        Self::test_unary::<square>();
+        Self::test_unary::<sqrt>();
+        Self::test_unary::<floor>();
+        Self::test_unary::<ceil>();
+        Self::test_unary::<round>();
+        Self::test_unary::<not>();
+    }
+
+    pub fn test_mg_binary_ops() {
+        use super::canonical::*;
+
+        Self::test_binary::<add>();
+        Self::test_binary::<sub>();
+        Self::test_binary::<mul>();
+        Self::test_binary::<div>();
+        Self::test_binary::<min>();
+        Self::test_binary::<max>();
+        Self::test_binary::<atan2>();
+        Self::test_binary::<modulo>();
+        Self::test_binary::<compare>();
+        Self::test_binary::<and>();
+        Self::test_binary::<or>();
+    }
+}
+
+#[macro_export]
+macro_rules! multi_grad_test {
+    ($i:ident, $t:ty) => {
+        #[test]
+        fn $i() {
+            $crate::eval::test::multi_grad_slice::TestMultiGradSlice::<$t>::$i()
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! multi_grad_slice_tests {
+    ($t:ty) => {
+        $crate::multi_grad_test!(test_mg_xyz, $t);
+        $crate::multi_grad_test!(test_mg_var, $t);
+        $crate::multi_grad_test!(test_mg_vars, $t);
+        $crate::multi_grad_test!(test_mg_errors, $t);
+        $crate::multi_grad_test!(test_mg_stress, $t);
+        $crate::multi_grad_test!(test_mg_unary_ops, $t);
+        $crate::multi_grad_test!(test_mg_binary_ops, $t);
+    };
+}
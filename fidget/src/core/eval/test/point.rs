@@ -128,6 +128,37 @@ where
         assert!(trace.is_none());
     }
 
+    pub fn test_p_and_or()
+    where
+        <S as Shape>::Trace: AsRef<[Choice]>,
+    {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let and = ctx.and(x, y).unwrap();
+        let or = ctx.or(and, z).unwrap();
+
+        let shape = S::new(&ctx, or).unwrap();
+        let tape = shape.ez_point_tape();
+        let mut eval = S::new_point_eval();
+        let (r, trace) = eval.eval(&tape, 0.0, 5.0, 3.0, &[]).unwrap();
+        assert_eq!(r, 3.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Left, Choice::Right]);
+
+        let (r, trace) = eval.eval(&tape, 2.0, 5.0, 3.0, &[]).unwrap();
+        assert_eq!(r, 5.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Right, Choice::Left]);
+
+        let (r, trace) = eval.eval(&tape, 2.0, 0.0, 3.0, &[]).unwrap();
+        assert_eq!(r, 3.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Right, Choice::Right]);
+
+        let (r, trace) = eval.eval(&tape, f32::NAN, 5.0, 3.0, &[]).unwrap();
+        assert_eq!(r, 5.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Right, Choice::Left]);
+    }
+
     pub fn test_p_sin()
     where
         <S as Shape>::Trace: AsRef<[Choice]>,
@@ -566,6 +597,11 @@ where
         Self::test_binary::<div>();
         Self::test_binary::<min>();
         Self::test_binary::<max>();
+        Self::test_binary::<atan2>();
+        Self::test_binary::<modulo>();
+        Self::test_binary::<compare>();
+        Self::test_binary::<and>();
+        Self::test_binary::<or>();
     }
 
     pub fn test_p_unary_ops() {
@@ -584,6 +620,10 @@ where
         Self::test_unary::<ln>();
         Self::test_unary::<square>();
         Self::test_unary::<sqrt>();
+        Self::test_unary::<floor>();
+        Self::test_unary::<ceil>();
+        Self::test_unary::<round>();
+        Self::test_unary::<not>();
     }
 }
 
@@ -605,6 +645,7 @@ macro_rules! point_tests {
         $crate::point_test!(test_circle, $t);
         $crate::point_test!(test_p_max, $t);
         $crate::point_test!(test_p_min, $t);
+        $crate::point_test!(test_p_and_or, $t);
         $crate::point_test!(test_p_sin, $t);
         $crate::point_test!(basic_interpreter, $t);
         $crate::point_test!(test_push, $t);
@@ -0,0 +1,266 @@
+//! Tests for interval evaluators with outward rounding
+//!
+//! If the `eval-tests` feature is set, then this exposes a standard test suite
+//! for robust interval evaluators; otherwise, the module has no public exports.
+//!
+//! Results are checked against [`Context::eval`], which evaluates the
+//! expression in double precision.
+use super::{test_args_n, CanonicalBinaryOp, CanonicalUnaryOp};
+use crate::{
+    context::{Context, Node},
+    eval::{types::Interval, MathShape, Shape, TracingEvaluator},
+};
+
+/// Helper struct to put constrains on our `Shape` object
+pub struct TestRobustInterval<S>(std::marker::PhantomData<*const S>);
+
+impl<S> TestRobustInterval<S>
+where
+    S: Shape + MathShape,
+{
+    /// Intervals used as inputs, including bounds which aren't representable
+    /// as `f32` values (so constants in the tape will be rounded)
+    fn robust_test_args() -> Vec<Interval> {
+        let mut args = test_args_n(4);
+        args.retain(|a| !a.is_nan());
+        args.extend([0.1, 1.0 / 3.0, 1e-3, 7.3, -2.9]);
+        let mut out = vec![];
+        for &lower in &args {
+            out.push(Interval::from(lower));
+            for size in [0.1, 1.0, 2.5] {
+                out.push(Interval::new(lower, lower + size));
+            }
+        }
+        out
+    }
+
+    /// Returns a set of sample points within the interval
+    fn samples(i: Interval) -> Vec<f32> {
+        let mut out = vec![i.lower(), i.upper()];
+        for j in 1..16 {
+            let pos = j as f32 / 16.0;
+            out.push(i.lerp(pos).clamp(i.lower(), i.upper()));
+        }
+        out
+    }
+
+    /// Evaluates `root` at sample points, checking that each (double-precision)
+    /// result is contained within the robust interval result
+    fn check_enclosure(ctx: &Context, root: Node, x: Interval, y: Interval) {
+        let shape = S::new(ctx, root).unwrap();
+        let tape = shape.robust_interval_tape(Default::default());
+        let mut eval = S::new_robust_interval_eval();
+        let (out, _trace) = eval.eval(&tape, x, y, 0.0.into(), &[]).unwrap();
+        if out.has_nan() {
+            return;
+        }
+        for &px in &Self::samples(x) {
+            for &py in &Self::samples(y) {
+                let v = ctx.eval_xyz(root, px as f64, py as f64, 0.0).unwrap();
+                assert!(
+                    v.is_nan()
+                        || (v >= out.lower() as f64 && v <= out.upper() as f64),
+                    "enclosure failure: f({px}, {py}) = {v} not in {out} \
+                     (inputs {x}, {y})"
+                );
+            }
+        }
+    }
+
+    pub fn test_r_widen() {
+        use ieee754::Ieee754;
+
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let sum = ctx.add(x, y).unwrap();
+        let min = ctx.min(x, y).unwrap();
+
+        let shape = S::new(&ctx, sum).unwrap();
+        let tape = shape.robust_interval_tape(Default::default());
+        let mut eval = S::new_robust_interval_eval();
+
+        // Adding zero doesn't round, but the result is still widened
+        for (x, expected) in [
+            ([1.0, 2.0], [1f32.prev(), 2f32.next()]),
+            ([-1.0, -0.5], [-(1f32.next()), -(0.5f32.prev())]),
+            ([0.0, 0.0], [0f32.prev(), 0f32.next()]),
+            ([-0.0, -0.0], [0f32.prev(), 0f32.next()]),
+            ([f32::NEG_INFINITY, 0.0], [f32::NEG_INFINITY, 0f32.next()]),
+            ([f32::INFINITY; 2], [f32::MAX, f32::INFINITY]),
+            ([f32::NEG_INFINITY; 2], [f32::NEG_INFINITY, f32::MIN]),
+        ] {
+            let (out, _) =
+                eval.eval(&tape, x, [0.0; 2], [0.0; 2], &[]).unwrap();
+            assert_eq!(out, expected.into(), "widening {x:?}");
+            assert_eq!(out, Interval::from(x).widen());
+        }
+
+        let (out, _) = eval
+            .eval(&tape, [f32::NAN; 2], [0.0; 2], [0.0; 2], &[])
+            .unwrap();
+        assert!(out.lower().is_nan());
+        assert!(out.upper().is_nan());
+
+        // Operations which don't round are not widened
+        let shape = S::new(&ctx, min).unwrap();
+        let tape = shape.robust_interval_tape(Default::default());
+        let (out, _) = eval
+            .eval(&tape, [1.0, 3.0], [2.0, 4.0], [0.0; 2], &[])
+            .unwrap();
+        assert_eq!(out, [1.0, 3.0].into());
+    }
+
+    pub fn test_r_constants() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+
+        // 0.1 isn't representable as an `f32`, so a constant is an interval
+        let c = ctx.constant(0.1);
+        let shape = S::new(&ctx, c).unwrap();
+        let tape = shape.robust_interval_tape(Default::default());
+        let mut eval = S::new_robust_interval_eval();
+        let (out, _) =
+            eval.eval(&tape, [0.0; 2], [0.0; 2], [0.0; 2], &[]).unwrap();
+        assert_eq!(out, Interval::enclosing(0.1));
+        assert!(out.lower() < out.upper());
+
+        // A representable constant remains a single value
+        let c = ctx.constant(0.5);
+        let shape = S::new(&ctx, c).unwrap();
+        let tape = shape.robust_interval_tape(Default::default());
+        let (out, _) =
+            eval.eval(&tape, [0.0; 2], [0.0; 2], [0.0; 2], &[]).unwrap();
+        assert_eq!(out, 0.5.into());
+
+        // With plain f32 arithmetic, 1.0 * 0.1 rounds up and excludes the
+        // double-precision result
+        let mul = ctx.mul(x, 0.1).unwrap();
+        let third = ctx.div(x, 3.0).unwrap();
+        let cmp = ctx.compare(x, 0.1).unwrap();
+        for root in [mul, third, cmp] {
+            for v in [0.1f32, 1.0, 3.0] {
+                Self::check_enclosure(&ctx, root, v.into(), 0.0.into());
+            }
+        }
+    }
+
+    pub fn test_r_accumulated() {
+        // Repeatedly compute t = t * 1.1 + x, accumulating rounding error
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let mut t = x;
+        for _ in 0..64 {
+            t = ctx.mul(t, 1.1).unwrap();
+            t = ctx.add(t, x).unwrap();
+        }
+        for v in [0.1f32, 0.3, 1.0 / 3.0, 7.7] {
+            Self::check_enclosure(&ctx, t, v.into(), 0.0.into());
+            Self::check_enclosure(&ctx, t, [v, v + 0.5].into(), 0.0.into());
+        }
+
+        // Sum 0.1 many times, then subtract the (double-precision) total
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let mut t = ctx.constant(0.0);
+        for _ in 0..100 {
+            t = ctx.add(t, 0.1).unwrap();
+            t = ctx.add(t, x).unwrap();
+        }
+        let t = ctx.sub(t, 10.0).unwrap();
+        Self::check_enclosure(&ctx, t, 0.0.into(), 0.0.into());
+        Self::check_enclosure(&ctx, t, [-1e-3, 1e-3].into(), 0.0.into());
+    }
+
+    pub fn test_unary<C: CanonicalUnaryOp>() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let node = C::build(&mut ctx, x);
+        for a in Self::robust_test_args() {
+            Self::check_enclosure(&ctx, node, a, 0.0.into());
+        }
+    }
+
+    pub fn test_binary<C: CanonicalBinaryOp>() {
+        let args = Self::robust_test_args();
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+
+        let node = C::build(&mut ctx, x, y);
+        for &a in &args {
+            for &b in args.iter().step_by(3) {
+                Self::check_enclosure(&ctx, node, a, b);
+            }
+        }
+
+        for imm in [0.1, -1.0 / 3.0, std::f64::consts::PI, 2.0] {
+            let c = ctx.constant(imm);
+            let reg_imm = C::build(&mut ctx, x, c);
+            let imm_reg = C::build(&mut ctx, c, x);
+            for &a in &args {
+                Self::check_enclosure(&ctx, reg_imm, a, 0.0.into());
+                Self::check_enclosure(&ctx, imm_reg, a, 0.0.into());
+            }
+        }
+    }
+
+    pub fn test_r_unary_ops() {
+        use super::canonical::*;
+
+        Self::test_unary::<neg>();
+        Self::test_unary::<recip>();
+        Self::test_unary::<abs>();
+        Self::test_unary::<sin>();
+        Self::test_unary::<cos>();
+        Self::test_unary::<tan>();
+        Self::test_unary::<asin>();
+        Self::test_unary::<acos>();
+        Self::test_unary::<atan>();
+        Self::test_unary::<exp>();
+        Self::test_unary::<ln>();
+        Self::test_unary::<square>();
+        Self::test_unary::<sqrt>();
+        Self::test_unary::<floor>();
+        Self::test_unary::<ceil>();
+        Self::test_unary::<round>();
+        Self::test_unary::<not>();
+    }
+
+    pub fn test_r_binary_ops() {
+        use super::canonical::*;
+
+        Self::test_binary::<add>();
+        Self::test_binary::<sub>();
+        Self::test_binary::<mul>();
+        Self::test_binary::<div>();
+        Self::test_binary::<min>();
+        Self::test_binary::<max>();
+        Self::test_binary::<atan2>();
+        Self::test_binary::<modulo>();
+        Self::test_binary::<compare>();
+        Self::test_binary::<and>();
+        Self::test_binary::<or>();
+    }
+}
+
+#[macro_export]
+macro_rules! robust_interval_test {
+    ($i:ident, $t:ty) => {
+        #[test]
+        fn $i() {
+            $crate::eval::test::robust_interval::TestRobustInterval::<$t>::$i()
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! robust_interval_tests {
+    ($t:ty) => {
+        $crate::robust_interval_test!(test_r_widen, $t);
+        $crate::robust_interval_test!(test_r_constants, $t);
+        $crate::robust_interval_test!(test_r_accumulated, $t);
+        $crate::robust_interval_test!(test_r_unary_ops, $t);
+        $crate::robust_interval_test!(test_r_binary_ops, $t);
+    };
+}
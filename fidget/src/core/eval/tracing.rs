@@ -14,7 +14,10 @@ use crate::{eval::Tape, Error};
 ///
 /// The trace can later be used to simplify the [`Shape`](crate::eval::Shape)
 /// using [`Shape::simplify`](crate::eval::Shape::simplify).
-pub trait TracingEvaluator: Default {
+///
+/// Variables are passed as a slice of the scalar type `V`, which is `f32` for
+/// all but the double-precision evaluators.
+pub trait TracingEvaluator<V = f32>: Default {
     /// Data type used during evaluation
     type Data: From<f32> + Copy + Clone;
 
@@ -39,7 +42,7 @@ pub trait TracingEvaluator: Default {
         x: F,
         y: F,
         z: F,
-        vars: &[f32],
+        vars: &[V],
     ) -> Result<(Self::Data, Option<&Self::Trace>), Error>;
 
     /// Build a new empty evaluator
@@ -50,7 +53,7 @@ pub trait TracingEvaluator: Default {
     /// Helper function to check input arguments
     fn check_arguments(
         &self,
-        vars: &[f32],
+        vars: &[V],
         var_count: usize,
     ) -> Result<(), Error> {
         if vars.len() != var_count {
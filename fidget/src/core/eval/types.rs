@@ -171,6 +171,56 @@ impl Grad {
         }
     }
 
+    /// Rounds down to the nearest integer
+    ///
+    /// The derivatives are zero, except at discontinuities (where they're
+    /// undefined and we pick zero anyways)
+    pub fn floor(self) -> Self {
+        Grad::new(self.v.floor(), 0.0, 0.0, 0.0)
+    }
+
+    /// Rounds up to the nearest integer
+    ///
+    /// The derivatives are zero, except at discontinuities (where they're
+    /// undefined and we pick zero anyways)
+    pub fn ceil(self) -> Self {
+        Grad::new(self.v.ceil(), 0.0, 0.0, 0.0)
+    }
+
+    /// Rounds to the nearest integer, with half-way cases rounded away from 0
+    ///
+    /// The derivatives are zero, except at discontinuities (where they're
+    /// undefined and we pick zero anyways)
+    pub fn round(self) -> Self {
+        Grad::new(self.v.round(), 0.0, 0.0, 0.0)
+    }
+
+    /// Two-argument arctangent, treating `self` as `y` and `x` as `x`
+    pub fn atan2(self, x: Self) -> Self {
+        let y = self;
+        let d = x.v.powi(2) + y.v.powi(2);
+        Grad {
+            v: y.v.atan2(x.v),
+            dx: (x.v * y.dx - y.v * x.dx) / d,
+            dy: (x.v * y.dy - y.v * x.dy) / d,
+            dz: (x.v * y.dz - y.v * x.dz) / d,
+        }
+    }
+
+    /// Least non-negative remainder of `self / rhs`
+    ///
+    /// This is [`f32::rem_euclid`], i.e. `self - rhs * q` where `q` is the
+    /// (locally constant) Euclidean quotient.
+    pub fn rem_euclid(self, rhs: Self) -> Self {
+        let q = self.v.div_euclid(rhs.v);
+        Grad {
+            v: self.v.rem_euclid(rhs.v),
+            dx: self.dx - q * rhs.dx,
+            dy: self.dy - q * rhs.dy,
+            dz: self.dz - q * rhs.dz,
+        }
+    }
+
     /// Minimum of two values
     pub fn min(self, rhs: Self) -> Self {
         if self.v < rhs.v {
@@ -189,6 +239,35 @@ impl Grad {
         }
     }
 
+    /// Compares two values, returning -1, 0, or 1 (or `NaN`)
+    ///
+    /// The derivatives are zero, except at discontinuities
+    pub fn compare(self, rhs: Self) -> Self {
+        let v = match self.v.partial_cmp(&rhs.v) {
+            Some(c) => c as i8 as f32,
+            None => f32::NAN,
+        };
+        Grad::new(v, 0.0, 0.0, 0.0)
+    }
+
+    /// Logical and: returns `self` if its value is zero, and `rhs` otherwise
+    pub fn and(self, rhs: Self) -> Self {
+        if self.v == 0.0 {
+            self
+        } else {
+            rhs
+        }
+    }
+
+    /// Logical or: returns `self` if its value is nonzero, and `rhs` otherwise
+    pub fn or(self, rhs: Self) -> Self {
+        if self.v != 0.0 {
+            self
+        } else {
+            rhs
+        }
+    }
+
     /// Checks that the two values are roughly equal, panicking otherwise
     #[cfg(test)]
     pub(crate) fn compare_eq(&self, other: Self) {
@@ -203,6 +282,16 @@ impl Grad {
     }
 }
 
+/// Logical not: returns 1 if the value is zero, and 0 otherwise
+///
+/// The derivatives are always zero.
+impl std::ops::Not for Grad {
+    type Output = Self;
+    fn not(self) -> Self {
+        Grad::new((self.v == 0.0).into(), 0.0, 0.0, 0.0)
+    }
+}
+
 impl From<f32> for Grad {
     fn from(v: f32) -> Self {
         Grad {
@@ -283,196 +372,1877 @@ impl std::ops::Neg for Grad {
 
 ////////////////////////////////////////////////////////////////////////////////
 
-/// Stores a range, with conservative calculations to guarantee that it always
-/// contains the actual value.
+/// A point in space with first and second partial derivatives
 ///
-/// # Warning
-/// This implementation does not set rounding modes, so it may not be _perfect_.
-#[derive(Copy, Clone, PartialEq)]
-#[repr(C)]
-pub struct Interval {
-    lower: f32,
-    upper: f32,
+/// This is a second-order extension of [`Grad`] (similar to hyper-dual
+/// numbers), which tracks the Hessian matrix alongside the gradient.  Because
+/// mixed partials commute, `h` is always symmetric.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Hessian {
+    /// Value of the distance field at this point
+    pub v: f32,
+    /// Partial derivatives with respect to `x`, `y`, and `z`
+    pub d: [f32; 3],
+    /// Second partial derivatives, where `h[i][j]` is `∂²f / ∂i∂j`
+    pub h: [[f32; 3]; 3],
 }
 
-impl std::fmt::Debug for Interval {
-    fn fmt(
-        &self,
-        f: &mut std::fmt::Formatter<'_>,
-    ) -> Result<(), std::fmt::Error> {
-        f.debug_tuple("")
-            .field(&self.lower)
-            .field(&self.upper)
-            .finish()
+impl Hessian {
+    /// Constructs a new value from its gradient and Hessian matrix
+    pub fn new(v: f32, d: [f32; 3], h: [[f32; 3]; 3]) -> Self {
+        Self { v, d, h }
     }
-}
 
-impl Interval {
-    /// Builds a new interval
-    ///
-    /// There are two kinds of valid interval:
-    /// - `[lower, upper]` where `lower <= upper`
-    /// - `[NaN, NaN]`
-    ///
-    /// # Panics
-    /// Panics if the resulting interval would be invalid
-    #[inline]
-    pub fn new(lower: f32, upper: f32) -> Self {
-        assert!(
-            upper >= lower || (lower.is_nan() && upper.is_nan()),
-            "invalid interval [{lower}, {upper}]"
-        );
-        Self { lower, upper }
+    /// Returns the value and first derivatives as a [`Grad`]
+    pub fn grad(&self) -> Grad {
+        Grad::new(self.v, self.d[0], self.d[1], self.d[2])
     }
-    /// Returns the lower bound of the interval
-    #[inline]
-    pub fn lower(&self) -> f32 {
-        self.lower
+
+    /// Mean curvature of the isosurface passing through this point
+    ///
+    /// The result is positive for convex regions (e.g. `1 / r` on the surface
+    /// of a sphere with radius `r`) and negative for concave regions.  It is
+    /// `NaN` if the gradient is zero.
+    pub fn mean_curvature(&self) -> f32 {
+        let g = self.d;
+        let n2 = g.iter().map(|d| d * d).sum::<f32>();
+        let trace = (0..3).map(|i| self.h[i][i]).sum::<f32>();
+        let mut ghg = 0.0;
+        for i in 0..3 {
+            for j in 0..3 {
+                ghg += g[i] * self.h[i][j] * g[j];
+            }
+        }
+        (n2 * trace - ghg) / (2.0 * n2.powf(1.5))
     }
-    /// Returns the upper bound of the interval
-    #[inline]
-    pub fn upper(&self) -> f32 {
-        self.upper
+
+    /// Gaussian curvature of the isosurface passing through this point
+    ///
+    /// The result is positive for elliptic points (e.g. `1 / r²` on the
+    /// surface of a sphere with radius `r`), zero for flat or cylindrical
+    /// regions, and negative at saddle points.  It is `NaN` if the gradient is
+    /// zero.
+    pub fn gaussian_curvature(&self) -> f32 {
+        let g = self.d;
+        let h = self.h;
+        let n2 = g.iter().map(|d| d * d).sum::<f32>();
+
+        // Adjugate of the (symmetric) Hessian matrix
+        let mut adj = [[0.0; 3]; 3];
+        for (i, row) in adj.iter_mut().enumerate() {
+            for (j, a) in row.iter_mut().enumerate() {
+                let (i1, i2) = ((i + 1) % 3, (i + 2) % 3);
+                let (j1, j2) = ((j + 1) % 3, (j + 2) % 3);
+                *a = h[j1][i1] * h[j2][i2] - h[j1][i2] * h[j2][i1];
+            }
+        }
+        let mut gag = 0.0;
+        for i in 0..3 {
+            for j in 0..3 {
+                gag += g[i] * adj[i][j] * g[j];
+            }
+        }
+        gag / n2.powi(2)
     }
-    /// Checks whether the given value is (strictly) contained in the interval
-    #[inline]
-    pub fn contains(&self, v: f32) -> bool {
-        v >= self.lower && v <= self.upper
+
+    /// Applies the chain rule for `f(self)`, given `f`, `f'`, and `f''`
+    fn chain(self, v: f32, d1: f32, d2: f32) -> Self {
+        let mut out = Self {
+            v,
+            d: self.d.map(|d| d * d1),
+            h: [[0.0; 3]; 3],
+        };
+        for i in 0..3 {
+            for j in 0..3 {
+                out.h[i][j] = d1 * self.h[i][j] + d2 * self.d[i] * self.d[j];
+            }
+        }
+        out
     }
-    /// Returns `true` if either bound of the interval is `NaN`
-    pub fn has_nan(&self) -> bool {
-        self.lower.is_nan() || self.upper.is_nan()
+
+    /// Applies the chain rule for a binary function `f(self, rhs)`
+    ///
+    /// `fa` and `fb` are the partial derivatives of `f` with respect to each
+    /// argument; `faa`, `fab`, and `fbb` are its second partial derivatives.
+    #[allow(clippy::too_many_arguments)]
+    fn chain2(
+        self,
+        rhs: Self,
+        v: f32,
+        fa: f32,
+        fb: f32,
+        faa: f32,
+        fab: f32,
+        fbb: f32,
+    ) -> Self {
+        let (a, b) = (self, rhs);
+        let mut out = Self {
+            v,
+            d: [0.0; 3],
+            h: [[0.0; 3]; 3],
+        };
+        for i in 0..3 {
+            out.d[i] = fa * a.d[i] + fb * b.d[i];
+            for j in 0..3 {
+                out.h[i][j] = fa * a.h[i][j]
+                    + fb * b.h[i][j]
+                    + faa * a.d[i] * a.d[j]
+                    + fbb * b.d[i] * b.d[j]
+                    + fab * (a.d[i] * b.d[j] + b.d[i] * a.d[j]);
+            }
+        }
+        out
     }
-    /// Calculates the absolute value of the interval
+
+    /// Absolute value
     pub fn abs(self) -> Self {
-        if self.lower < 0.0 {
-            if self.upper > 0.0 {
-                Interval::new(0.0, self.upper.max(-self.lower))
-            } else {
-                Interval::new(-self.upper, -self.lower)
-            }
+        if self.v < 0.0 {
+            -self
         } else {
             self
         }
     }
-    /// Squares the interval
-    ///
-    /// Note that this has tighter bounds than multiplication, because we know
-    /// that both sides of the multiplication are the same value.
-    pub fn square(self) -> Self {
-        if self.upper < 0.0 {
-            Interval::new(self.upper.powi(2), self.lower.powi(2))
-        } else if self.lower > 0.0 {
-            Interval::new(self.lower.powi(2), self.upper.powi(2))
-        } else if self.has_nan() {
-            f32::NAN.into()
-        } else {
-            Interval::new(0.0, self.lower.abs().max(self.upper.abs()).powi(2))
-        }
+
+    /// Square root
+    pub fn sqrt(self) -> Self {
+        let v = self.v.sqrt();
+        self.chain(v, 1.0 / (2.0 * v), -1.0 / (4.0 * v.powi(3)))
     }
-    /// Computes the sine of the interval
-    ///
-    /// Right now, this always returns the maximum range of `[-1, 1]`
+
+    /// Sine
     pub fn sin(self) -> Self {
-        // TODO: make this smarter
-        Interval::new(-1.0, 1.0)
+        let (s, c) = self.v.sin_cos();
+        self.chain(s, c, -s)
     }
-    /// Computes the cosine of the interval
-    ///
-    /// Right now, this always returns the maximum range of `[-1, 1]`
+    /// Cosine
     pub fn cos(self) -> Self {
-        // TODO: make this smarter
-        Interval::new(-1.0, 1.0)
+        let (s, c) = self.v.sin_cos();
+        self.chain(c, -s, -c)
     }
-    /// Computes the tangent of the interval
-    ///
-    /// Returns the `NAN` interval if the result contains a undefined point
+    /// Tangent
     pub fn tan(self) -> Self {
-        let size = self.upper - self.lower;
-        if size >= std::f32::consts::PI {
-            f32::NAN.into()
-        } else {
-            let lower = self.lower.tan();
-            let upper = self.upper.tan();
-            if upper >= lower {
-                Interval::new(lower, upper)
-            } else {
-                f32::NAN.into()
-            }
-        }
+        let t = self.v.tan();
+        let d1 = 1.0 + t * t;
+        self.chain(t, d1, 2.0 * t * d1)
     }
-    /// Computes the arcsine of the interval
-    ///
-    /// Returns the `NAN` interval if the input is invalid
+    /// Arcsin
     pub fn asin(self) -> Self {
-        if self.lower < -1.0 || self.upper > 1.0 {
-            f32::NAN.into()
-        } else {
-            Interval::new(self.lower.asin(), self.upper.asin())
-        }
+        let r = 1.0 - self.v.powi(2);
+        self.chain(self.v.asin(), 1.0 / r.sqrt(), self.v / r.powf(1.5))
     }
-    /// Computes the arccosine of the interval
-    ///
-    /// Returns the `NAN` interval if the input is invalid
+    /// Arccos
     pub fn acos(self) -> Self {
-        if self.lower < -1.0 || self.upper > 1.0 {
-            f32::NAN.into()
-        } else {
-            Interval::new(self.upper.acos(), self.lower.acos())
-        }
+        let r = 1.0 - self.v.powi(2);
+        self.chain(self.v.acos(), -1.0 / r.sqrt(), -self.v / r.powf(1.5))
     }
-    /// Computes the arctangent of the interval
+    /// Arctangent
     pub fn atan(self) -> Self {
-        Interval::new(self.lower.atan(), self.upper.atan())
+        let r = self.v.powi(2) + 1.0;
+        self.chain(self.v.atan(), 1.0 / r, -2.0 * self.v / r.powi(2))
     }
-    /// Computes the exponent function applied to the interval
+    /// Exponential function
     pub fn exp(self) -> Self {
-        Interval::new(self.lower.exp(), self.upper.exp())
+        let v = self.v.exp();
+        self.chain(v, v, v)
     }
-    /// Computes the natural log of the input interval
-    ///
-    /// Returns the `NAN` interval if the input contains zero
+    /// Natural log
     pub fn ln(self) -> Self {
-        if self.lower <= 0.0 {
-            f32::NAN.into()
+        self.chain(self.v.ln(), 1.0 / self.v, -1.0 / self.v.powi(2))
+    }
+
+    /// Reciprocal
+    pub fn recip(self) -> Self {
+        let r = 1.0 / self.v;
+        self.chain(r, -r * r, 2.0 * r * r * r)
+    }
+
+    /// Rounds down to the nearest integer
+    ///
+    /// The derivatives are zero, except at discontinuities (where they're
+    /// undefined and we pick zero anyways)
+    pub fn floor(self) -> Self {
+        self.v.floor().into()
+    }
+
+    /// Rounds up to the nearest integer
+    ///
+    /// The derivatives are zero, except at discontinuities (where they're
+    /// undefined and we pick zero anyways)
+    pub fn ceil(self) -> Self {
+        self.v.ceil().into()
+    }
+
+    /// Rounds to the nearest integer, with half-way cases rounded away from 0
+    ///
+    /// The derivatives are zero, except at discontinuities (where they're
+    /// undefined and we pick zero anyways)
+    pub fn round(self) -> Self {
+        self.v.round().into()
+    }
+
+    /// Two-argument arctangent, treating `self` as `y` and `x` as `x`
+    pub fn atan2(self, x: Self) -> Self {
+        let (yv, xv) = (self.v, x.v);
+        let r2 = xv.powi(2) + yv.powi(2);
+        let r4 = r2.powi(2);
+        self.chain2(
+            x,
+            yv.atan2(xv),
+            xv / r2,
+            -yv / r2,
+            -2.0 * xv * yv / r4,
+            (yv.powi(2) - xv.powi(2)) / r4,
+            2.0 * xv * yv / r4,
+        )
+    }
+
+    /// Least non-negative remainder of `self / rhs`
+    ///
+    /// This is [`f32::rem_euclid`], i.e. `self - rhs * q` where `q` is the
+    /// (locally constant) Euclidean quotient.
+    pub fn rem_euclid(self, rhs: Self) -> Self {
+        let q = self.v.div_euclid(rhs.v);
+        let mut out = self - rhs * q.into();
+        out.v = self.v.rem_euclid(rhs.v);
+        out
+    }
+
+    /// Minimum of two values
+    pub fn min(self, rhs: Self) -> Self {
+        if self.v < rhs.v {
+            self
         } else {
-            Interval::new(self.lower.ln(), self.upper.ln())
+            rhs
         }
     }
-    /// Calculates the square root of the interval
-    ///
-    /// If the entire interval is below 0, returns a `NAN` interval; otherwise,
-    /// returns the valid (positive) interval.
-    pub fn sqrt(self) -> Self {
-        if self.lower < 0.0 {
-            if self.upper > 0.0 {
-                Interval::new(0.0, self.upper.sqrt())
-            } else {
-                f32::NAN.into()
-            }
+
+    /// Maximum of two values
+    pub fn max(self, rhs: Self) -> Self {
+        if self.v > rhs.v {
+            self
         } else {
-            Interval::new(self.lower.sqrt(), self.upper.sqrt())
+            rhs
         }
     }
-    /// Calculates the reciprocal of the interval
+
+    /// Compares two values, returning -1, 0, or 1 (or `NaN`)
     ///
-    /// If the interval includes 0, returns the `NAN` interval
-    pub fn recip(self) -> Self {
-        if self.lower > 0.0 || self.upper < 0.0 {
-            Interval::new(1.0 / self.upper, 1.0 / self.lower)
+    /// The derivatives are zero, except at discontinuities
+    pub fn compare(self, rhs: Self) -> Self {
+        match self.v.partial_cmp(&rhs.v) {
+            Some(c) => (c as i8 as f32).into(),
+            None => f32::NAN.into(),
+        }
+    }
+
+    /// Logical and: returns `self` if its value is zero, and `rhs` otherwise
+    pub fn and(self, rhs: Self) -> Self {
+        if self.v == 0.0 {
+            self
         } else {
-            f32::NAN.into()
+            rhs
         }
     }
-    /// Calculates the minimum of two intervals
-    ///
+
+    /// Logical or: returns `self` if its value is nonzero, and `rhs` otherwise
+    pub fn or(self, rhs: Self) -> Self {
+        if self.v != 0.0 {
+            self
+        } else {
+            rhs
+        }
+    }
+}
+
+/// Logical not: returns 1 if the value is zero, and 0 otherwise
+///
+/// The derivatives are always zero.
+impl std::ops::Not for Hessian {
+    type Output = Self;
+    fn not(self) -> Self {
+        f32::from(self.v == 0.0).into()
+    }
+}
+
+impl From<f32> for Hessian {
+    fn from(v: f32) -> Self {
+        Hessian {
+            v,
+            d: [0.0; 3],
+            h: [[0.0; 3]; 3],
+        }
+    }
+}
+
+impl std::ops::Add<Hessian> for Hessian {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        self.chain2(rhs, self.v + rhs.v, 1.0, 1.0, 0.0, 0.0, 0.0)
+    }
+}
+
+impl std::ops::Mul<Hessian> for Hessian {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        self.chain2(rhs, self.v * rhs.v, rhs.v, self.v, 0.0, 1.0, 0.0)
+    }
+}
+
+impl std::ops::Div<Hessian> for Hessian {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        let r = 1.0 / rhs.v;
+        let v = self.v * r;
+        self.chain2(
+            rhs,
+            self.v / rhs.v,
+            r,
+            -v * r,
+            0.0,
+            -r * r,
+            2.0 * v * r * r,
+        )
+    }
+}
+
+impl std::ops::Sub<Hessian> for Hessian {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        self.chain2(rhs, self.v - rhs.v, 1.0, -1.0, 0.0, 0.0, 0.0)
+    }
+}
+
+impl std::ops::Neg for Hessian {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self {
+            v: -self.v,
+            d: self.d.map(|d| -d),
+            h: self.h.map(|row| row.map(|h| -h)),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A point in space with partial derivatives against configurable targets
+///
+/// Unlike [`Grad`], which always tracks derivatives with respect to `x`, `y`,
+/// and `z`, the derivatives in a `MultiGrad` may be taken with respect to any
+/// of the inputs or variables in a shape (see
+/// [`GradTarget`](crate::eval::GradTarget)).  Up to [`MultiGrad::N`] targets
+/// are supported; unused derivative slots are always zero.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct MultiGrad {
+    /// Value of the distance field at this point
+    pub v: f32,
+    /// Partial derivatives with respect to each target, in order
+    pub d: [f32; MultiGrad::N],
+}
+
+impl std::fmt::Display for MultiGrad {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}", self.v)?;
+        for d in &self.d {
+            write!(f, ", {d}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl MultiGrad {
+    /// Maximum number of differentiation targets
+    ///
+    /// This is chosen so that the value and its derivatives fill a 256-bit
+    /// SIMD register.
+    pub const N: usize = 7;
+
+    /// Constructs a new gradient
+    pub fn new(v: f32, d: [f32; Self::N]) -> Self {
+        Self { v, d }
+    }
+
+    /// Looks up a partial derivative by target index
+    ///
+    /// # Panics
+    /// If the index is not below [`MultiGrad::N`]
+    pub fn d(&self, i: usize) -> f32 {
+        self.d[i]
+    }
+
+    /// Builds a value with the given derivatives scaled by `s`
+    ///
+    /// This is the chain rule for a unary function with derivative `s`
+    fn chain(self, v: f32, s: f32) -> Self {
+        Self {
+            v,
+            d: self.d.map(|d| d * s),
+        }
+    }
+
+    /// Builds a value by combining pairs of derivatives from `self` and `rhs`
+    fn zip<F: Fn(f32, f32) -> f32>(self, rhs: Self, v: f32, f: F) -> Self {
+        let mut d = [0.0; Self::N];
+        for (i, d) in d.iter_mut().enumerate() {
+            *d = f(self.d[i], rhs.d[i]);
+        }
+        Self { v, d }
+    }
+
+    /// Absolute value
+    pub fn abs(self) -> Self {
+        if self.v < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+
+    /// Square root
+    pub fn sqrt(self) -> Self {
+        let v = self.v.sqrt();
+        self.chain(v, 1.0 / (2.0 * v))
+    }
+
+    /// Sine
+    pub fn sin(self) -> Self {
+        self.chain(self.v.sin(), self.v.cos())
+    }
+    /// Cosine
+    pub fn cos(self) -> Self {
+        self.chain(self.v.cos(), -self.v.sin())
+    }
+    /// Tangent
+    pub fn tan(self) -> Self {
+        self.chain(self.v.tan(), 1.0 / self.v.cos().powi(2))
+    }
+    /// Arcsin
+    pub fn asin(self) -> Self {
+        let r = (1.0 - self.v.powi(2)).sqrt();
+        self.chain(self.v.asin(), 1.0 / r)
+    }
+    /// Arccos
+    pub fn acos(self) -> Self {
+        let r = (1.0 - self.v.powi(2)).sqrt();
+        self.chain(self.v.acos(), -1.0 / r)
+    }
+    /// Arctangent
+    pub fn atan(self) -> Self {
+        self.chain(self.v.atan(), 1.0 / (self.v.powi(2) + 1.0))
+    }
+    /// Exponential function
+    pub fn exp(self) -> Self {
+        let v = self.v.exp();
+        self.chain(v, v)
+    }
+    /// Natural log
+    pub fn ln(self) -> Self {
+        self.chain(self.v.ln(), 1.0 / self.v)
+    }
+
+    /// Reciprocal
+    pub fn recip(self) -> Self {
+        self.chain(1.0 / self.v, -1.0 / self.v.powi(2))
+    }
+
+    /// Rounds down to the nearest integer
+    ///
+    /// The derivatives are zero, except at discontinuities (where they're
+    /// undefined and we pick zero anyways)
+    pub fn floor(self) -> Self {
+        self.v.floor().into()
+    }
+
+    /// Rounds up to the nearest integer
+    ///
+    /// The derivatives are zero, except at discontinuities (where they're
+    /// undefined and we pick zero anyways)
+    pub fn ceil(self) -> Self {
+        self.v.ceil().into()
+    }
+
+    /// Rounds to the nearest integer, with half-way cases rounded away from 0
+    ///
+    /// The derivatives are zero, except at discontinuities (where they're
+    /// undefined and we pick zero anyways)
+    pub fn round(self) -> Self {
+        self.v.round().into()
+    }
+
+    /// Two-argument arctangent, treating `self` as `y` and `x` as `x`
+    pub fn atan2(self, x: Self) -> Self {
+        let y = self;
+        let d = x.v.powi(2) + y.v.powi(2);
+        y.zip(x, y.v.atan2(x.v), |dy, dx| (x.v * dy - y.v * dx) / d)
+    }
+
+    /// Least non-negative remainder of `self / rhs`
+    ///
+    /// This is [`f32::rem_euclid`], i.e. `self - rhs * q` where `q` is the
+    /// (locally constant) Euclidean quotient.
+    pub fn rem_euclid(self, rhs: Self) -> Self {
+        let q = self.v.div_euclid(rhs.v);
+        self.zip(rhs, self.v.rem_euclid(rhs.v), |a, b| a - q * b)
+    }
+
+    /// Minimum of two values
+    pub fn min(self, rhs: Self) -> Self {
+        if self.v < rhs.v {
+            self
+        } else {
+            rhs
+        }
+    }
+
+    /// Maximum of two values
+    pub fn max(self, rhs: Self) -> Self {
+        if self.v > rhs.v {
+            self
+        } else {
+            rhs
+        }
+    }
+
+    /// Compares two values, returning -1, 0, or 1 (or `NaN`)
+    ///
+    /// The derivatives are zero, except at discontinuities
+    pub fn compare(self, rhs: Self) -> Self {
+        match self.v.partial_cmp(&rhs.v) {
+            Some(c) => (c as i8 as f32).into(),
+            None => f32::NAN.into(),
+        }
+    }
+
+    /// Logical and: returns `self` if its value is zero, and `rhs` otherwise
+    pub fn and(self, rhs: Self) -> Self {
+        if self.v == 0.0 {
+            self
+        } else {
+            rhs
+        }
+    }
+
+    /// Logical or: returns `self` if its value is nonzero, and `rhs` otherwise
+    pub fn or(self, rhs: Self) -> Self {
+        if self.v != 0.0 {
+            self
+        } else {
+            rhs
+        }
+    }
+}
+
+/// Logical not: returns 1 if the value is zero, and 0 otherwise
+///
+/// The derivatives are always zero.
+impl std::ops::Not for MultiGrad {
+    type Output = Self;
+    fn not(self) -> Self {
+        f32::from(self.v == 0.0).into()
+    }
+}
+
+impl From<f32> for MultiGrad {
+    fn from(v: f32) -> Self {
+        MultiGrad {
+            v,
+            d: [0.0; Self::N],
+        }
+    }
+}
+
+impl std::ops::Add<MultiGrad> for MultiGrad {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        self.zip(rhs, self.v + rhs.v, |a, b| a + b)
+    }
+}
+
+impl std::ops::Mul<MultiGrad> for MultiGrad {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        let mut out = Self::from(self.v * rhs.v);
+        for i in 0..Self::N {
+            out.d[i] = self.v * rhs.d[i] + rhs.v * self.d[i];
+        }
+        out
+    }
+}
+
+impl std::ops::Div<MultiGrad> for MultiGrad {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        let d = rhs.v.powi(2);
+        let mut out = Self::from(self.v / rhs.v);
+        for i in 0..Self::N {
+            out.d[i] = (rhs.v * self.d[i] - self.v * rhs.d[i]) / d;
+        }
+        out
+    }
+}
+
+impl std::ops::Sub<MultiGrad> for MultiGrad {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        self.zip(rhs, self.v - rhs.v, |a, b| a - b)
+    }
+}
+
+impl std::ops::Neg for MultiGrad {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self {
+            v: -self.v,
+            d: self.d.map(|d| -d),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Stores a range, with conservative calculations to guarantee that it always
+/// contains the actual value.
+///
+/// # Warning
+/// This implementation does not set rounding modes, so it may not be _perfect_.
+#[derive(Copy, Clone, PartialEq)]
+#[repr(C)]
+pub struct Interval {
+    lower: f32,
+    upper: f32,
+}
+
+impl std::fmt::Debug for Interval {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> Result<(), std::fmt::Error> {
+        f.debug_tuple("")
+            .field(&self.lower)
+            .field(&self.upper)
+            .finish()
+    }
+}
+
+impl Interval {
+    /// Builds a new interval
+    ///
+    /// There are two kinds of valid interval:
+    /// - `[lower, upper]` where `lower <= upper`
+    /// - `[NaN, NaN]`
+    ///
+    /// # Panics
+    /// Panics if the resulting interval would be invalid
+    #[inline]
+    pub fn new(lower: f32, upper: f32) -> Self {
+        assert!(
+            upper >= lower || (lower.is_nan() && upper.is_nan()),
+            "invalid interval [{lower}, {upper}]"
+        );
+        Self { lower, upper }
+    }
+    /// Returns the lower bound of the interval
+    #[inline]
+    pub fn lower(&self) -> f32 {
+        self.lower
+    }
+    /// Returns the upper bound of the interval
+    #[inline]
+    pub fn upper(&self) -> f32 {
+        self.upper
+    }
+    /// Checks whether the given value is (strictly) contained in the interval
+    #[inline]
+    pub fn contains(&self, v: f32) -> bool {
+        v >= self.lower && v <= self.upper
+    }
+    /// Returns `true` if either bound of the interval is `NaN`
+    pub fn has_nan(&self) -> bool {
+        self.lower.is_nan() || self.upper.is_nan()
+    }
+    /// Returns the tightest interval which contains the given `f64` value
+    ///
+    /// ```
+    /// # use fidget::eval::types::Interval;
+    /// assert_eq!(Interval::enclosing(0.5), Interval::new(0.5, 0.5));
+    /// let i = Interval::enclosing(0.1);
+    /// assert!(i.lower() < i.upper());
+    /// assert!((i.lower() as f64) < 0.1 && (i.upper() as f64) > 0.1);
+    /// ```
+    pub fn enclosing(v: f64) -> Self {
+        use ieee754::Ieee754;
+        let f = v as f32;
+        if v.is_nan() || f as f64 == v {
+            f.into()
+        } else if (f as f64) < v {
+            Interval::new(f, f.next())
+        } else {
+            Interval::new(f.prev(), f)
+        }
+    }
+    /// Widens the interval by one ulp in each direction
+    ///
+    /// If the bounds were each computed with a single round-to-nearest
+    /// operation, the widened interval contains the exact result.  Infinite
+    /// bounds and `NaN` are left unchanged.
+    ///
+    /// ```
+    /// # use fidget::eval::types::Interval;
+    /// let i = Interval::new(1.0, 2.0).widen();
+    /// assert!(i.lower() < 1.0 && i.upper() > 2.0);
+    /// let i = Interval::new(f32::NEG_INFINITY, 0.0).widen();
+    /// assert_eq!(i.lower(), f32::NEG_INFINITY);
+    /// assert!(i.upper() > 0.0);
+    /// ```
+    pub fn widen(self) -> Self {
+        use ieee754::Ieee754;
+        if self.has_nan() {
+            return self;
+        }
+        let lower = if self.lower == f32::NEG_INFINITY {
+            self.lower
+        } else {
+            self.lower.prev()
+        };
+        let upper = if self.upper == f32::INFINITY {
+            self.upper
+        } else {
+            self.upper.next()
+        };
+        Interval::new(lower, upper)
+    }
+    /// Calculates the absolute value of the interval
+    pub fn abs(self) -> Self {
+        if self.lower < 0.0 {
+            if self.upper > 0.0 {
+                Interval::new(0.0, self.upper.max(-self.lower))
+            } else {
+                Interval::new(-self.upper, -self.lower)
+            }
+        } else {
+            self
+        }
+    }
+    /// Squares the interval
+    ///
+    /// Note that this has tighter bounds than multiplication, because we know
+    /// that both sides of the multiplication are the same value.
+    pub fn square(self) -> Self {
+        if self.upper < 0.0 {
+            Interval::new(self.upper.powi(2), self.lower.powi(2))
+        } else if self.lower > 0.0 {
+            Interval::new(self.lower.powi(2), self.upper.powi(2))
+        } else if self.has_nan() {
+            f32::NAN.into()
+        } else {
+            Interval::new(0.0, self.lower.abs().max(self.upper.abs()).powi(2))
+        }
+    }
+    /// Computes the sine of the interval
+    ///
+    /// The result is tight (to within an ulp), using range reduction in
+    /// double precision to find the extrema within the interval.
+    pub fn sin(self) -> Self {
+        self.periodic(f64::sin, std::f64::consts::FRAC_PI_2)
+    }
+    /// Computes the cosine of the interval
+    ///
+    /// The result is tight (to within an ulp), using range reduction in
+    /// double precision to find the extrema within the interval.
+    pub fn cos(self) -> Self {
+        self.periodic(f64::cos, 0.0)
+    }
+    /// Helper function to compute the range of `sin` or `cos`
+    fn periodic(self, f: fn(f64) -> f64, phase: f64) -> Self {
+        use ieee754::Ieee754;
+        if self.has_nan() {
+            return f32::NAN.into();
+        }
+        let (lower, upper) =
+            periodic_range(self.lower as f64, self.upper as f64, f, phase);
+        // Round outwards, since the conversion to f32 may round inwards
+        Interval::new(
+            (lower as f32).prev().max(-1.0),
+            (upper as f32).next().min(1.0),
+        )
+    }
+    /// Computes the tangent of the interval
+    ///
+    /// Returns the `NAN` interval if the result contains a undefined point
+    pub fn tan(self) -> Self {
+        let size = self.upper - self.lower;
+        if size >= std::f32::consts::PI {
+            f32::NAN.into()
+        } else {
+            let lower = self.lower.tan();
+            let upper = self.upper.tan();
+            if upper >= lower {
+                Interval::new(lower, upper)
+            } else {
+                f32::NAN.into()
+            }
+        }
+    }
+    /// Computes the arcsine of the interval
+    ///
+    /// Returns the `NAN` interval if the input is invalid
+    pub fn asin(self) -> Self {
+        if self.lower < -1.0 || self.upper > 1.0 {
+            f32::NAN.into()
+        } else {
+            Interval::new(self.lower.asin(), self.upper.asin())
+        }
+    }
+    /// Computes the arccosine of the interval
+    ///
+    /// Returns the `NAN` interval if the input is invalid
+    pub fn acos(self) -> Self {
+        if self.lower < -1.0 || self.upper > 1.0 {
+            f32::NAN.into()
+        } else {
+            Interval::new(self.upper.acos(), self.lower.acos())
+        }
+    }
+    /// Computes the arctangent of the interval
+    pub fn atan(self) -> Self {
+        Interval::new(self.lower.atan(), self.upper.atan())
+    }
+    /// Computes the exponent function applied to the interval
+    pub fn exp(self) -> Self {
+        Interval::new(self.lower.exp(), self.upper.exp())
+    }
+    /// Computes the natural log of the input interval
+    ///
+    /// Returns the `NAN` interval if the input contains zero
+    pub fn ln(self) -> Self {
+        if self.lower <= 0.0 {
+            f32::NAN.into()
+        } else {
+            Interval::new(self.lower.ln(), self.upper.ln())
+        }
+    }
+    /// Calculates the square root of the interval
+    ///
+    /// If the entire interval is below 0, returns a `NAN` interval; otherwise,
+    /// returns the valid (positive) interval.
+    pub fn sqrt(self) -> Self {
+        if self.lower < 0.0 {
+            if self.upper > 0.0 {
+                Interval::new(0.0, self.upper.sqrt())
+            } else {
+                f32::NAN.into()
+            }
+        } else {
+            Interval::new(self.lower.sqrt(), self.upper.sqrt())
+        }
+    }
+    /// Calculates the reciprocal of the interval
+    ///
+    /// If the interval includes 0, returns the `NAN` interval
+    pub fn recip(self) -> Self {
+        if self.lower > 0.0 || self.upper < 0.0 {
+            Interval::new(1.0 / self.upper, 1.0 / self.lower)
+        } else {
+            f32::NAN.into()
+        }
+    }
+    /// Rounds both bounds of the interval down to the nearest integer
+    pub fn floor(self) -> Self {
+        Interval::new(self.lower.floor(), self.upper.floor())
+    }
+    /// Rounds both bounds of the interval up to the nearest integer
+    pub fn ceil(self) -> Self {
+        Interval::new(self.lower.ceil(), self.upper.ceil())
+    }
+    /// Rounds both bounds of the interval to the nearest integer
+    pub fn round(self) -> Self {
+        Interval::new(self.lower.round(), self.upper.round())
+    }
+    /// Computes the two-argument arctangent, treating `self` as `y`
+    ///
+    /// If the input region could cross the branch cut (along the negative X
+    /// axis), returns the full range of `[-π, π]`.
+    pub fn atan2(self, x: Self) -> Self {
+        let y = self;
+        if y.has_nan() || x.has_nan() {
+            f32::NAN.into()
+        } else if x.lower > 0.0 || y.lower > 0.0 || y.upper < 0.0 {
+            // The region is on one side of the branch cut, so extrema are
+            // found at its corners.
+            let mut lower = f32::INFINITY;
+            let mut upper = -f32::INFINITY;
+            for yi in [y.lower, y.upper] {
+                for xi in [x.lower, x.upper] {
+                    let v = yi.atan2(xi);
+                    lower = lower.min(v);
+                    upper = upper.max(v);
+                }
+            }
+            Interval::new(lower, upper)
+        } else {
+            Interval::new(-std::f32::consts::PI, std::f32::consts::PI)
+        }
+    }
+    /// Computes the least non-negative remainder of `self / rhs`
+    ///
+    /// The result is tight if `rhs` is a single value and `self` doesn't wrap
+    /// around a multiple of it; otherwise, it's `[0, max(|rhs|)]`.  If `rhs`
+    /// contains zero, returns the `NAN` interval.
+    pub fn rem_euclid(self, rhs: Self) -> Self {
+        if self.has_nan() || rhs.has_nan() {
+            return f32::NAN.into();
+        }
+        if !(rhs.lower > 0.0 || rhs.upper < 0.0) {
+            return f32::NAN.into();
+        }
+        if rhs.lower == rhs.upper {
+            let b = rhs.lower;
+            if self.lower == self.upper {
+                return self.lower.rem_euclid(b).into();
+            }
+            // If the interval is narrower than the period, it can wrap at
+            // most once, in which case the remainders would be out of order.
+            let width = self.upper as f64 - self.lower as f64;
+            if width < b.abs() as f64 {
+                let lower = self.lower.rem_euclid(b);
+                let upper = self.upper.rem_euclid(b);
+                if lower < upper {
+                    return Interval::new(lower, upper);
+                }
+            }
+        }
+        Interval::new(0.0, rhs.lower.abs().max(rhs.upper.abs()))
+    }
+    /// Calculates the minimum of two intervals
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always less than the other.
+    ///
+    /// If either side is `NAN`, returns the `NAN` interval and `Choice::Both`.
+    pub fn min_choice(self, rhs: Self) -> (Self, Choice) {
+        if self.has_nan() || rhs.has_nan() {
+            return (f32::NAN.into(), Choice::Both);
+        }
+        let choice = if self.upper < rhs.lower {
+            Choice::Left
+        } else if rhs.upper < self.lower {
+            Choice::Right
+        } else {
+            Choice::Both
+        };
+        (
+            Interval::new(self.lower.min(rhs.lower), self.upper.min(rhs.upper)),
+            choice,
+        )
+    }
+    /// Calculates the maximum of two intervals
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always greater than the other.
+    ///
+    /// If either side is `NAN`, returns the `NAN` interval and `Choice::Both`.
+    pub fn max_choice(self, rhs: Self) -> (Self, Choice) {
+        if self.has_nan() || rhs.has_nan() {
+            return (f32::NAN.into(), Choice::Both);
+        }
+        let choice = if self.lower > rhs.upper {
+            Choice::Left
+        } else if rhs.lower > self.upper {
+            Choice::Right
+        } else {
+            Choice::Both
+        };
+        (
+            Interval::new(self.lower.max(rhs.lower), self.upper.max(rhs.upper)),
+            choice,
+        )
+    }
+
+    /// Compares two intervals, returning -1, 0, or 1 (or a range thereof)
+    ///
+    /// If either side is `NAN`, returns the `NAN` interval.
+    pub fn compare(self, rhs: Self) -> Self {
+        if self.has_nan() || rhs.has_nan() {
+            f32::NAN.into()
+        } else if self.upper < rhs.lower {
+            (-1.0).into()
+        } else if self.lower > rhs.upper {
+            1.0.into()
+        } else if self.lower == self.upper && rhs.lower == rhs.upper {
+            0.0.into()
+        } else if self.upper <= rhs.lower {
+            Interval::new(-1.0, 0.0)
+        } else if self.lower >= rhs.upper {
+            Interval::new(0.0, 1.0)
+        } else {
+            Interval::new(-1.0, 1.0)
+        }
+    }
+    /// Calculates the logical `and` of two intervals
+    ///
+    /// The result is `self` if it's zero and `rhs` otherwise.  Returns both
+    /// the result and a [`Choice`] indicating whether one side is always
+    /// picked.
+    pub fn and_choice(self, rhs: Self) -> (Self, Choice) {
+        if self.has_nan() {
+            // NAN is truthy, but the interval could also contain zero
+            let out = if rhs.has_nan() {
+                f32::NAN.into()
+            } else {
+                Interval::new(rhs.lower.min(0.0), rhs.upper.max(0.0))
+            };
+            (out, Choice::Both)
+        } else if self.lower == 0.0 && self.upper == 0.0 {
+            (self, Choice::Left)
+        } else if !self.contains(0.0) {
+            (rhs, Choice::Right)
+        } else if rhs.has_nan() {
+            (f32::NAN.into(), Choice::Both)
+        } else {
+            (
+                Interval::new(rhs.lower.min(0.0), rhs.upper.max(0.0)),
+                Choice::Both,
+            )
+        }
+    }
+    /// Calculates the logical `or` of two intervals
+    ///
+    /// The result is `self` if it's nonzero and `rhs` otherwise.  Returns both
+    /// the result and a [`Choice`] indicating whether one side is always
+    /// picked.
+    pub fn or_choice(self, rhs: Self) -> (Self, Choice) {
+        if self.has_nan() {
+            (f32::NAN.into(), Choice::Both)
+        } else if self.lower == 0.0 && self.upper == 0.0 {
+            (rhs, Choice::Right)
+        } else if !self.contains(0.0) {
+            (self, Choice::Left)
+        } else if rhs.has_nan() {
+            (f32::NAN.into(), Choice::Both)
+        } else {
+            (
+                Interval::new(
+                    self.lower.min(rhs.lower),
+                    self.upper.max(rhs.upper),
+                ),
+                Choice::Both,
+            )
+        }
+    }
+
+    /// Returns the midpoint of the interval
+    pub fn midpoint(self) -> f32 {
+        (self.lower + self.upper) / 2.0
+    }
+
+    /// Splits the interval at the midpoint
+    ///
+    /// ```
+    /// # use fidget::eval::types::Interval;
+    /// let a = Interval::new(0.0, 1.0);
+    /// let (lo, hi) = a.split();
+    /// assert_eq!(lo, Interval::new(0.0, 0.5));
+    /// assert_eq!(hi, Interval::new(0.5, 1.0));
+    /// ```
+    pub fn split(self) -> (Self, Self) {
+        let mid = self.midpoint();
+        (
+            Interval::new(self.lower, mid),
+            Interval::new(mid, self.upper),
+        )
+    }
+
+    /// Linear interpolation from `lower` to `upper`
+    ///
+    /// ```
+    /// # use fidget::eval::types::Interval;
+    /// let a = Interval::new(0.0, 2.0);
+    /// assert_eq!(a.lerp(0.5), 1.0);
+    /// assert_eq!(a.lerp(0.75), 1.5);
+    /// assert_eq!(a.lerp(2.0), 4.0);
+    /// ```
+    pub fn lerp(self, frac: f32) -> f32 {
+        self.lower * (1.0 - frac) + self.upper * frac
+    }
+
+    /// Calculates the width of the interval
+    ///
+    /// ```
+    /// # use fidget::eval::types::Interval;
+    /// let a = Interval::new(2.0, 3.0);
+    /// assert_eq!(a.width(), 1.0);
+    /// let b = Interval::new(2.0, 5.0);
+    /// assert_eq!(b.width(), 3.0);
+    /// ```
+    pub fn width(self) -> f32 {
+        self.upper - self.lower
+    }
+
+    /// Checks that the two values are roughly equal, panicking otherwise
+    #[cfg(test)]
+    pub(crate) fn compare_eq(&self, other: Self) {
+        let d = (self.lower - other.lower)
+            .abs()
+            .max((self.upper - other.upper).abs());
+        if d >= 1e-6 {
+            panic!("lhs != rhs ({self:?} != {other:?})");
+        }
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.lower, self.upper)
+    }
+}
+
+impl From<[f32; 2]> for Interval {
+    fn from(i: [f32; 2]) -> Interval {
+        Interval::new(i[0], i[1])
+    }
+}
+
+impl From<f32> for Interval {
+    fn from(f: f32) -> Self {
+        Interval::new(f, f)
+    }
+}
+
+impl std::ops::Add<Interval> for Interval {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Interval::new(self.lower + rhs.lower, self.upper + rhs.upper)
+    }
+}
+
+impl std::ops::Mul<Interval> for Interval {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        if self.has_nan() || rhs.has_nan() {
+            return f32::NAN.into();
+        }
+        let mut out = [0.0; 4];
+        let mut k = 0;
+        for i in [self.lower, self.upper] {
+            for j in [rhs.lower, rhs.upper] {
+                out[k] = i * j;
+                k += 1;
+            }
+        }
+        let mut lower = out[0];
+        let mut upper = out[0];
+        for &v in &out[1..] {
+            lower = lower.min(v);
+            upper = upper.max(v);
+        }
+        Interval::new(lower, upper)
+    }
+}
+
+impl std::ops::Div<Interval> for Interval {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        if self.has_nan() {
+            return f32::NAN.into();
+        }
+        if rhs.lower > 0.0 || rhs.upper < 0.0 {
+            let mut out = [0.0; 4];
+            let mut k = 0;
+            for i in [self.lower, self.upper] {
+                for j in [rhs.lower, rhs.upper] {
+                    out[k] = i / j;
+                    k += 1;
+                }
+            }
+            let mut lower = out[0];
+            let mut upper = out[0];
+            for &v in &out[1..] {
+                lower = lower.min(v);
+                upper = upper.max(v);
+            }
+            Interval::new(lower, upper)
+        } else {
+            f32::NAN.into()
+        }
+    }
+}
+
+impl std::ops::Sub<Interval> for Interval {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Interval::new(self.lower - rhs.upper, self.upper - rhs.lower)
+    }
+}
+
+/// Logical not: the result is 1 where the input is zero, and 0 elsewhere
+impl std::ops::Not for Interval {
+    type Output = Self;
+    fn not(self) -> Self {
+        if self.has_nan() {
+            Interval::new(0.0, 1.0)
+        } else if self.lower == 0.0 && self.upper == 0.0 {
+            1.0.into()
+        } else if !self.contains(0.0) {
+            0.0.into()
+        } else {
+            Interval::new(0.0, 1.0)
+        }
+    }
+}
+
+impl std::ops::Neg for Interval {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Interval::new(-self.upper, -self.lower)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A range with bounds on its partial derivatives
+///
+/// This is the interval equivalent of [`Grad`]: `v` bounds the value of a
+/// function over a region, and `dx`, `dy`, `dz` bound its partial derivatives
+/// over that same region.
+///
+/// Derivatives are `NaN` where no bound is known, e.g. if the function is
+/// discontinuous within the region (from `floor`, `compare`, etc) or its
+/// derivative is unbounded (e.g. `sqrt` of a range which includes zero).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct IntervalGrad {
+    /// Bounds on the value of the distance field
+    pub v: Interval,
+    /// Bounds on the partial derivative with respect to `x`
+    pub dx: Interval,
+    /// Bounds on the partial derivative with respect to `y`
+    pub dy: Interval,
+    /// Bounds on the partial derivative with respect to `z`
+    pub dz: Interval,
+}
+
+impl std::fmt::Display for IntervalGrad {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {}, {}, {})", self.v, self.dx, self.dy, self.dz)
+    }
+}
+
+impl IntervalGrad {
+    /// Constructs a new value with bounded derivatives
+    pub fn new(v: Interval, dx: Interval, dy: Interval, dz: Interval) -> Self {
+        Self { v, dx, dy, dz }
+    }
+
+    /// Looks up a partial derivative by index (0 = x, 1 = y, 2 = z)
+    ///
+    /// # Panics
+    /// If the index is not in the 0-2 range
+    pub fn d(&self, i: usize) -> Interval {
+        match i {
+            0 => self.dx,
+            1 => self.dy,
+            2 => self.dz,
+            _ => panic!("invalid index {i}"),
+        }
+    }
+
+    /// Returns bounds on the magnitude of the gradient
+    ///
+    /// If the upper bound is at most 1, then the function is 1-Lipschitz over
+    /// the region, i.e. it never overestimates the distance to its surface.
+    ///
+    /// ```
+    /// # use fidget::eval::types::{Interval, IntervalGrad};
+    /// let x = IntervalGrad::new(
+    ///     Interval::new(0.0, 1.0),
+    ///     1.0.into(),
+    ///     0.0.into(),
+    ///     0.0.into(),
+    /// );
+    /// assert_eq!(x.grad_norm(), 1.0.into());
+    /// assert_eq!((x * 2.0.into()).grad_norm(), 2.0.into());
+    /// ```
+    pub fn grad_norm(&self) -> Interval {
+        (self.dx.square() + self.dy.square() + self.dz.square()).sqrt()
+    }
+
+    /// Applies the chain rule, given bounds on the value and derivative of
+    /// the outer function
+    fn chain(self, v: Interval, s: Interval) -> Self {
+        Self {
+            v,
+            dx: self.dx * s,
+            dy: self.dy * s,
+            dz: self.dz * s,
+        }
+    }
+
+    /// Builds a value which is locally constant, or has unbounded derivatives
+    ///
+    /// If `v` is a single value, then the function is constant over the
+    /// region and the derivatives are zero; otherwise, it's discontinuous
+    /// (e.g. for `floor` or `compare`), so the derivatives are `NaN`.
+    fn step(v: Interval) -> Self {
+        let d = if v.lower() == v.upper() {
+            0.0
+        } else {
+            f32::NAN
+        };
+        Self::new(v, d.into(), d.into(), d.into())
+    }
+
+    /// Absolute value
+    pub fn abs(self) -> Self {
+        if self.v.lower() >= 0.0 {
+            self
+        } else if self.v.upper() < 0.0 {
+            -self
+        } else {
+            // The derivative may be either sign (at zero, we could pick either
+            // branch of the absolute value)
+            let f = |d: Interval| {
+                let m = d.abs().upper();
+                Interval::new(-m, m)
+            };
+            Self::new(self.v.abs(), f(self.dx), f(self.dy), f(self.dz))
+        }
+    }
+    /// Square
+    pub fn square(self) -> Self {
+        self.chain(self.v.square(), self.v * 2.0.into())
+    }
+    /// Square root
+    pub fn sqrt(self) -> Self {
+        let v = self.v.sqrt();
+        self.chain(v, (v * 2.0.into()).recip())
+    }
+    /// Sine
+    pub fn sin(self) -> Self {
+        self.chain(self.v.sin(), self.v.cos())
+    }
+    /// Cosine
+    pub fn cos(self) -> Self {
+        self.chain(self.v.cos(), -self.v.sin())
+    }
+    /// Tangent
+    pub fn tan(self) -> Self {
+        self.chain(self.v.tan(), self.v.cos().square().recip())
+    }
+    /// Arcsin
+    pub fn asin(self) -> Self {
+        let r = (Interval::from(1.0) - self.v.square()).sqrt();
+        self.chain(self.v.asin(), r.recip())
+    }
+    /// Arccos
+    pub fn acos(self) -> Self {
+        let r = (Interval::from(1.0) - self.v.square()).sqrt();
+        self.chain(self.v.acos(), -r.recip())
+    }
+    /// Arctangent
+    pub fn atan(self) -> Self {
+        let r = self.v.square() + 1.0.into();
+        self.chain(self.v.atan(), r.recip())
+    }
+    /// Exponential function
+    pub fn exp(self) -> Self {
+        let v = self.v.exp();
+        self.chain(v, v)
+    }
+    /// Natural log
+    pub fn ln(self) -> Self {
+        self.chain(self.v.ln(), self.v.recip())
+    }
+    /// Reciprocal
+    ///
+    /// If the range includes 0, returns `NAN`
+    pub fn recip(self) -> Self {
+        self.chain(self.v.recip(), -self.v.square().recip())
+    }
+    /// Rounds down to the nearest integer
+    pub fn floor(self) -> Self {
+        Self::step(self.v.floor())
+    }
+    /// Rounds up to the nearest integer
+    pub fn ceil(self) -> Self {
+        Self::step(self.v.ceil())
+    }
+    /// Rounds to the nearest integer
+    pub fn round(self) -> Self {
+        Self::step(self.v.round())
+    }
+    /// Two-argument arctangent, treating `self` as `y` and `x` as `x`
+    pub fn atan2(self, x: Self) -> Self {
+        let y = self;
+        let d = x.v.square() + y.v.square();
+        Self {
+            v: y.v.atan2(x.v),
+            dx: (x.v * y.dx - y.v * x.dx) / d,
+            dy: (x.v * y.dy - y.v * x.dy) / d,
+            dz: (x.v * y.dz - y.v * x.dz) / d,
+        }
+    }
+    /// Least non-negative remainder of `self / rhs`
+    ///
+    /// Derivatives are only bounded if the Euclidean quotient is constant
+    /// over the region.
+    pub fn rem_euclid(self, rhs: Self) -> Self {
+        // The Euclidean quotient rounds towards -∞ for positive divisors and
+        // towards +∞ for negative divisors (so that the remainder is positive)
+        let q = self.v / rhs.v;
+        let q = if rhs.v.upper() < 0.0 {
+            q.ceil()
+        } else {
+            q.floor()
+        };
+        let v = self.v.rem_euclid(rhs.v);
+        if q.lower() == q.upper() {
+            Self {
+                v,
+                dx: self.dx - q * rhs.dx,
+                dy: self.dy - q * rhs.dy,
+                dz: self.dz - q * rhs.dz,
+            }
+        } else {
+            Self::new(v, f32::NAN.into(), f32::NAN.into(), f32::NAN.into())
+        }
+    }
+    /// Calculates the minimum of two values
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always less than the other.  If both sides are possible, the
+    /// derivatives are bounded by the union of both sides' derivatives.
+    pub fn min_choice(self, rhs: Self) -> (Self, Choice) {
+        let (v, choice) = self.v.min_choice(rhs.v);
+        (self.pick(rhs, v, choice), choice)
+    }
+    /// Calculates the maximum of two values
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always greater than the other.  If both sides are possible, the
+    /// derivatives are bounded by the union of both sides' derivatives.
+    pub fn max_choice(self, rhs: Self) -> (Self, Choice) {
+        let (v, choice) = self.v.max_choice(rhs.v);
+        (self.pick(rhs, v, choice), choice)
+    }
+    /// Selects one side of a `min` or `max` operation
+    fn pick(self, rhs: Self, v: Interval, choice: Choice) -> Self {
+        let hull = |a: Interval, b: Interval| {
+            if a.has_nan() || b.has_nan() {
+                f32::NAN.into()
+            } else {
+                Interval::new(
+                    a.lower().min(b.lower()),
+                    a.upper().max(b.upper()),
+                )
+            }
+        };
+        match choice {
+            Choice::Left => self,
+            Choice::Right => rhs,
+            _ => Self {
+                v,
+                dx: hull(self.dx, rhs.dx),
+                dy: hull(self.dy, rhs.dy),
+                dz: hull(self.dz, rhs.dz),
+            },
+        }
+    }
+    /// Compares two values, returning -1, 0, or 1 (or a range thereof)
+    pub fn compare(self, rhs: Self) -> Self {
+        Self::step(self.v.compare(rhs.v))
+    }
+    /// Calculates the logical `and` of two values
+    ///
+    /// The result is `self` if it's zero and `rhs` otherwise.  Returns both
+    /// the result and a [`Choice`] indicating whether one side is always
+    /// picked.
+    pub fn and_choice(self, rhs: Self) -> (Self, Choice) {
+        let (v, choice) = self.v.and_choice(rhs.v);
+        match choice {
+            Choice::Left => (self, choice),
+            Choice::Right => (rhs, choice),
+            _ => (Self::step(v), choice),
+        }
+    }
+    /// Calculates the logical `or` of two values
+    ///
+    /// The result is `self` if it's nonzero and `rhs` otherwise.  Returns both
+    /// the result and a [`Choice`] indicating whether one side is always
+    /// picked.
+    pub fn or_choice(self, rhs: Self) -> (Self, Choice) {
+        let (v, choice) = self.v.or_choice(rhs.v);
+        match choice {
+            Choice::Left => (self, choice),
+            Choice::Right => (rhs, choice),
+            _ => (Self::step(v), choice),
+        }
+    }
+}
+
+/// Logical not: the result is 1 where the input is zero, and 0 elsewhere
+impl std::ops::Not for IntervalGrad {
+    type Output = Self;
+    fn not(self) -> Self {
+        Self::step(!self.v)
+    }
+}
+
+impl From<Interval> for IntervalGrad {
+    fn from(v: Interval) -> Self {
+        Self::new(v, 0.0.into(), 0.0.into(), 0.0.into())
+    }
+}
+
+impl From<[f32; 2]> for IntervalGrad {
+    fn from(i: [f32; 2]) -> Self {
+        Interval::from(i).into()
+    }
+}
+
+impl From<f32> for IntervalGrad {
+    fn from(f: f32) -> Self {
+        Interval::from(f).into()
+    }
+}
+
+impl std::ops::Add<IntervalGrad> for IntervalGrad {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            v: self.v + rhs.v,
+            dx: self.dx + rhs.dx,
+            dy: self.dy + rhs.dy,
+            dz: self.dz + rhs.dz,
+        }
+    }
+}
+
+impl std::ops::Mul<IntervalGrad> for IntervalGrad {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            v: self.v * rhs.v,
+            dx: self.v * rhs.dx + rhs.v * self.dx,
+            dy: self.v * rhs.dy + rhs.v * self.dy,
+            dz: self.v * rhs.dz + rhs.v * self.dz,
+        }
+    }
+}
+
+impl std::ops::Div<IntervalGrad> for IntervalGrad {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        let d = rhs.v.square();
+        Self {
+            v: self.v / rhs.v,
+            dx: (rhs.v * self.dx - self.v * rhs.dx) / d,
+            dy: (rhs.v * self.dy - self.v * rhs.dy) / d,
+            dz: (rhs.v * self.dz - self.v * rhs.dz) / d,
+        }
+    }
+}
+
+impl std::ops::Sub<IntervalGrad> for IntervalGrad {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            v: self.v - rhs.v,
+            dx: self.dx - rhs.dx,
+            dy: self.dy - rhs.dy,
+            dz: self.dz - rhs.dz,
+        }
+    }
+}
+
+impl std::ops::Neg for IntervalGrad {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self {
+            v: -self.v,
+            dx: -self.dx,
+            dy: -self.dy,
+            dz: -self.dz,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Stores a double-precision range
+///
+/// This is the `f64` equivalent of [`Interval`], used by double-precision
+/// interval evaluators.
+///
+/// # Warning
+/// This implementation does not set rounding modes, so it may not be _perfect_.
+#[derive(Copy, Clone, PartialEq)]
+#[repr(C)]
+pub struct Interval64 {
+    lower: f64,
+    upper: f64,
+}
+
+impl std::fmt::Debug for Interval64 {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> Result<(), std::fmt::Error> {
+        f.debug_tuple("")
+            .field(&self.lower)
+            .field(&self.upper)
+            .finish()
+    }
+}
+
+impl Interval64 {
+    /// Builds a new interval
+    ///
+    /// There are two kinds of valid interval:
+    /// - `[lower, upper]` where `lower <= upper`
+    /// - `[NaN, NaN]`
+    ///
+    /// # Panics
+    /// Panics if the resulting interval would be invalid
+    #[inline]
+    pub fn new(lower: f64, upper: f64) -> Self {
+        assert!(
+            upper >= lower || (lower.is_nan() && upper.is_nan()),
+            "invalid interval [{lower}, {upper}]"
+        );
+        Self { lower, upper }
+    }
+    /// Returns the lower bound of the interval
+    #[inline]
+    pub fn lower(&self) -> f64 {
+        self.lower
+    }
+    /// Returns the upper bound of the interval
+    #[inline]
+    pub fn upper(&self) -> f64 {
+        self.upper
+    }
+    /// Checks whether the given value is (strictly) contained in the interval
+    #[inline]
+    pub fn contains(&self, v: f64) -> bool {
+        v >= self.lower && v <= self.upper
+    }
+    /// Returns `true` if either bound of the interval is `NaN`
+    pub fn has_nan(&self) -> bool {
+        self.lower.is_nan() || self.upper.is_nan()
+    }
+    /// Converts to a single-precision interval which contains this interval
+    ///
+    /// Each bound is rounded to the nearest `f32`, then moved outward by one
+    /// ulp; this also absorbs any small error in the double-precision bounds.
+    pub fn round_outward(self) -> Interval {
+        Interval::new(self.lower as f32, self.upper as f32).widen()
+    }
+    /// Calculates the absolute value of the interval
+    pub fn abs(self) -> Self {
+        if self.lower < 0.0 {
+            if self.upper > 0.0 {
+                Interval64::new(0.0, self.upper.max(-self.lower))
+            } else {
+                Interval64::new(-self.upper, -self.lower)
+            }
+        } else {
+            self
+        }
+    }
+    /// Squares the interval
+    ///
+    /// Note that this has tighter bounds than multiplication, because we know
+    /// that both sides of the multiplication are the same value.
+    pub fn square(self) -> Self {
+        if self.upper < 0.0 {
+            Interval64::new(self.upper.powi(2), self.lower.powi(2))
+        } else if self.lower > 0.0 {
+            Interval64::new(self.lower.powi(2), self.upper.powi(2))
+        } else if self.has_nan() {
+            f64::NAN.into()
+        } else {
+            Interval64::new(0.0, self.lower.abs().max(self.upper.abs()).powi(2))
+        }
+    }
+    /// Computes the sine of the interval
+    ///
+    /// The result is tight to within an ulp, which is added to account for
+    /// rounding during range reduction.
+    pub fn sin(self) -> Self {
+        self.periodic(f64::sin, std::f64::consts::FRAC_PI_2)
+    }
+    /// Computes the cosine of the interval
+    ///
+    /// The result is tight to within an ulp, which is added to account for
+    /// rounding during range reduction.
+    pub fn cos(self) -> Self {
+        self.periodic(f64::cos, 0.0)
+    }
+    /// Helper function to compute the range of `sin` or `cos`
+    fn periodic(self, f: fn(f64) -> f64, phase: f64) -> Self {
+        use ieee754::Ieee754;
+        if self.has_nan() {
+            return f64::NAN.into();
+        }
+        let (lower, upper) = periodic_range(self.lower, self.upper, f, phase);
+        Interval64::new(lower.prev().max(-1.0), upper.next().min(1.0))
+    }
+    /// Computes the tangent of the interval
+    ///
+    /// Returns the `NAN` interval if the result contains a undefined point
+    pub fn tan(self) -> Self {
+        let size = self.upper - self.lower;
+        if size >= std::f64::consts::PI {
+            f64::NAN.into()
+        } else {
+            let lower = self.lower.tan();
+            let upper = self.upper.tan();
+            if upper >= lower {
+                Interval64::new(lower, upper)
+            } else {
+                f64::NAN.into()
+            }
+        }
+    }
+    /// Computes the arcsine of the interval
+    ///
+    /// Returns the `NAN` interval if the input is invalid
+    pub fn asin(self) -> Self {
+        if self.lower < -1.0 || self.upper > 1.0 {
+            f64::NAN.into()
+        } else {
+            Interval64::new(self.lower.asin(), self.upper.asin())
+        }
+    }
+    /// Computes the arccosine of the interval
+    ///
+    /// Returns the `NAN` interval if the input is invalid
+    pub fn acos(self) -> Self {
+        if self.lower < -1.0 || self.upper > 1.0 {
+            f64::NAN.into()
+        } else {
+            Interval64::new(self.upper.acos(), self.lower.acos())
+        }
+    }
+    /// Computes the arctangent of the interval
+    pub fn atan(self) -> Self {
+        Interval64::new(self.lower.atan(), self.upper.atan())
+    }
+    /// Computes the exponent function applied to the interval
+    pub fn exp(self) -> Self {
+        Interval64::new(self.lower.exp(), self.upper.exp())
+    }
+    /// Computes the natural log of the input interval
+    ///
+    /// Returns the `NAN` interval if the input contains zero
+    pub fn ln(self) -> Self {
+        if self.lower <= 0.0 {
+            f64::NAN.into()
+        } else {
+            Interval64::new(self.lower.ln(), self.upper.ln())
+        }
+    }
+    /// Calculates the square root of the interval
+    ///
+    /// If the entire interval is below 0, returns a `NAN` interval; otherwise,
+    /// returns the valid (positive) interval.
+    pub fn sqrt(self) -> Self {
+        if self.lower < 0.0 {
+            if self.upper > 0.0 {
+                Interval64::new(0.0, self.upper.sqrt())
+            } else {
+                f64::NAN.into()
+            }
+        } else {
+            Interval64::new(self.lower.sqrt(), self.upper.sqrt())
+        }
+    }
+    /// Calculates the reciprocal of the interval
+    ///
+    /// If the interval includes 0, returns the `NAN` interval
+    pub fn recip(self) -> Self {
+        if self.lower > 0.0 || self.upper < 0.0 {
+            Interval64::new(1.0 / self.upper, 1.0 / self.lower)
+        } else {
+            f64::NAN.into()
+        }
+    }
+    /// Rounds both bounds of the interval down to the nearest integer
+    pub fn floor(self) -> Self {
+        Interval64::new(self.lower.floor(), self.upper.floor())
+    }
+    /// Rounds both bounds of the interval up to the nearest integer
+    pub fn ceil(self) -> Self {
+        Interval64::new(self.lower.ceil(), self.upper.ceil())
+    }
+    /// Rounds both bounds of the interval to the nearest integer
+    pub fn round(self) -> Self {
+        Interval64::new(self.lower.round(), self.upper.round())
+    }
+    /// Computes the two-argument arctangent, treating `self` as `y`
+    ///
+    /// If the input region could cross the branch cut (along the negative X
+    /// axis), returns the full range of `[-π, π]`.
+    pub fn atan2(self, x: Self) -> Self {
+        let y = self;
+        if y.has_nan() || x.has_nan() {
+            f64::NAN.into()
+        } else if x.lower > 0.0 || y.lower > 0.0 || y.upper < 0.0 {
+            // The region is on one side of the branch cut, so extrema are
+            // found at its corners.
+            let mut lower = f64::INFINITY;
+            let mut upper = -f64::INFINITY;
+            for yi in [y.lower, y.upper] {
+                for xi in [x.lower, x.upper] {
+                    let v = yi.atan2(xi);
+                    lower = lower.min(v);
+                    upper = upper.max(v);
+                }
+            }
+            Interval64::new(lower, upper)
+        } else {
+            Interval64::new(-std::f64::consts::PI, std::f64::consts::PI)
+        }
+    }
+    /// Computes the least non-negative remainder of `self / rhs`
+    ///
+    /// The result is tight if `rhs` is a single value and `self` doesn't wrap
+    /// around a multiple of it; otherwise, it's `[0, max(|rhs|)]`.  If `rhs`
+    /// contains zero, returns the `NAN` interval.
+    pub fn rem_euclid(self, rhs: Self) -> Self {
+        if self.has_nan() || rhs.has_nan() {
+            return f64::NAN.into();
+        }
+        if !(rhs.lower > 0.0 || rhs.upper < 0.0) {
+            return f64::NAN.into();
+        }
+        if rhs.lower == rhs.upper {
+            let b = rhs.lower;
+            if self.lower == self.upper {
+                return self.lower.rem_euclid(b).into();
+            }
+            // If the interval is narrower than the period, it can wrap at
+            // most once, in which case the remainders would be out of order.
+            let width = self.upper - self.lower;
+            if width < b.abs() {
+                let lower = self.lower.rem_euclid(b);
+                let upper = self.upper.rem_euclid(b);
+                if lower < upper {
+                    return Interval64::new(lower, upper);
+                }
+            }
+        }
+        Interval64::new(0.0, rhs.lower.abs().max(rhs.upper.abs()))
+    }
+    /// Calculates the minimum of two intervals
+    ///
     /// Returns both the result and a [`Choice`] indicating whether one side is
     /// always less than the other.
     ///
     /// If either side is `NAN`, returns the `NAN` interval and `Choice::Both`.
     pub fn min_choice(self, rhs: Self) -> (Self, Choice) {
         if self.has_nan() || rhs.has_nan() {
-            return (f32::NAN.into(), Choice::Both);
+            return (f64::NAN.into(), Choice::Both);
         }
         let choice = if self.upper < rhs.lower {
             Choice::Left
@@ -482,7 +2252,10 @@ impl Interval {
             Choice::Both
         };
         (
-            Interval::new(self.lower.min(rhs.lower), self.upper.min(rhs.upper)),
+            Interval64::new(
+                self.lower.min(rhs.lower),
+                self.upper.min(rhs.upper),
+            ),
             choice,
         )
     }
@@ -494,7 +2267,7 @@ impl Interval {
     /// If either side is `NAN`, returns the `NAN` interval and `Choice::Both`.
     pub fn max_choice(self, rhs: Self) -> (Self, Choice) {
         if self.has_nan() || rhs.has_nan() {
-            return (f32::NAN.into(), Choice::Both);
+            return (f64::NAN.into(), Choice::Both);
         }
         let choice = if self.lower > rhs.upper {
             Choice::Left
@@ -504,101 +2277,177 @@ impl Interval {
             Choice::Both
         };
         (
-            Interval::new(self.lower.max(rhs.lower), self.upper.max(rhs.upper)),
+            Interval64::new(
+                self.lower.max(rhs.lower),
+                self.upper.max(rhs.upper),
+            ),
             choice,
         )
     }
 
+    /// Compares two intervals, returning -1, 0, or 1 (or a range thereof)
+    ///
+    /// If either side is `NAN`, returns the `NAN` interval.
+    pub fn compare(self, rhs: Self) -> Self {
+        if self.has_nan() || rhs.has_nan() {
+            f64::NAN.into()
+        } else if self.upper < rhs.lower {
+            (-1.0).into()
+        } else if self.lower > rhs.upper {
+            1.0.into()
+        } else if self.lower == self.upper && rhs.lower == rhs.upper {
+            0.0.into()
+        } else if self.upper <= rhs.lower {
+            Interval64::new(-1.0, 0.0)
+        } else if self.lower >= rhs.upper {
+            Interval64::new(0.0, 1.0)
+        } else {
+            Interval64::new(-1.0, 1.0)
+        }
+    }
+    /// Calculates the logical `and` of two intervals
+    ///
+    /// The result is `self` if it's zero and `rhs` otherwise.  Returns both
+    /// the result and a [`Choice`] indicating whether one side is always
+    /// picked.
+    pub fn and_choice(self, rhs: Self) -> (Self, Choice) {
+        if self.has_nan() {
+            // NAN is truthy, but the interval could also contain zero
+            let out = if rhs.has_nan() {
+                f64::NAN.into()
+            } else {
+                Interval64::new(rhs.lower.min(0.0), rhs.upper.max(0.0))
+            };
+            (out, Choice::Both)
+        } else if self.lower == 0.0 && self.upper == 0.0 {
+            (self, Choice::Left)
+        } else if !self.contains(0.0) {
+            (rhs, Choice::Right)
+        } else if rhs.has_nan() {
+            (f64::NAN.into(), Choice::Both)
+        } else {
+            (
+                Interval64::new(rhs.lower.min(0.0), rhs.upper.max(0.0)),
+                Choice::Both,
+            )
+        }
+    }
+    /// Calculates the logical `or` of two intervals
+    ///
+    /// The result is `self` if it's nonzero and `rhs` otherwise.  Returns both
+    /// the result and a [`Choice`] indicating whether one side is always
+    /// picked.
+    pub fn or_choice(self, rhs: Self) -> (Self, Choice) {
+        if self.has_nan() {
+            (f64::NAN.into(), Choice::Both)
+        } else if self.lower == 0.0 && self.upper == 0.0 {
+            (rhs, Choice::Right)
+        } else if !self.contains(0.0) {
+            (self, Choice::Left)
+        } else if rhs.has_nan() {
+            (f64::NAN.into(), Choice::Both)
+        } else {
+            (
+                Interval64::new(
+                    self.lower.min(rhs.lower),
+                    self.upper.max(rhs.upper),
+                ),
+                Choice::Both,
+            )
+        }
+    }
+
     /// Returns the midpoint of the interval
-    pub fn midpoint(self) -> f32 {
+    pub fn midpoint(self) -> f64 {
         (self.lower + self.upper) / 2.0
     }
 
     /// Splits the interval at the midpoint
     ///
     /// ```
-    /// # use fidget::eval::types::Interval;
-    /// let a = Interval::new(0.0, 1.0);
+    /// # use fidget::eval::types::Interval64;
+    /// let a = Interval64::new(0.0, 1.0);
     /// let (lo, hi) = a.split();
-    /// assert_eq!(lo, Interval::new(0.0, 0.5));
-    /// assert_eq!(hi, Interval::new(0.5, 1.0));
+    /// assert_eq!(lo, Interval64::new(0.0, 0.5));
+    /// assert_eq!(hi, Interval64::new(0.5, 1.0));
     /// ```
     pub fn split(self) -> (Self, Self) {
         let mid = self.midpoint();
         (
-            Interval::new(self.lower, mid),
-            Interval::new(mid, self.upper),
+            Interval64::new(self.lower, mid),
+            Interval64::new(mid, self.upper),
         )
     }
 
     /// Linear interpolation from `lower` to `upper`
     ///
     /// ```
-    /// # use fidget::eval::types::Interval;
-    /// let a = Interval::new(0.0, 2.0);
+    /// # use fidget::eval::types::Interval64;
+    /// let a = Interval64::new(0.0, 2.0);
     /// assert_eq!(a.lerp(0.5), 1.0);
     /// assert_eq!(a.lerp(0.75), 1.5);
     /// assert_eq!(a.lerp(2.0), 4.0);
     /// ```
-    pub fn lerp(self, frac: f32) -> f32 {
+    pub fn lerp(self, frac: f64) -> f64 {
         self.lower * (1.0 - frac) + self.upper * frac
     }
 
     /// Calculates the width of the interval
     ///
     /// ```
-    /// # use fidget::eval::types::Interval;
-    /// let a = Interval::new(2.0, 3.0);
+    /// # use fidget::eval::types::Interval64;
+    /// let a = Interval64::new(2.0, 3.0);
     /// assert_eq!(a.width(), 1.0);
-    /// let b = Interval::new(2.0, 5.0);
+    /// let b = Interval64::new(2.0, 5.0);
     /// assert_eq!(b.width(), 3.0);
     /// ```
-    pub fn width(self) -> f32 {
+    pub fn width(self) -> f64 {
         self.upper - self.lower
     }
-
-    /// Checks that the two values are roughly equal, panicking otherwise
-    #[cfg(test)]
-    pub(crate) fn compare_eq(&self, other: Self) {
-        let d = (self.lower - other.lower)
-            .abs()
-            .max((self.upper - other.upper).abs());
-        if d >= 1e-6 {
-            panic!("lhs != rhs ({self:?} != {other:?})");
-        }
-    }
 }
 
-impl std::fmt::Display for Interval {
+impl std::fmt::Display for Interval64 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "({}, {})", self.lower, self.upper)
     }
 }
 
-impl From<[f32; 2]> for Interval {
-    fn from(i: [f32; 2]) -> Interval {
-        Interval::new(i[0], i[1])
+impl From<[f64; 2]> for Interval64 {
+    fn from(i: [f64; 2]) -> Interval64 {
+        Interval64::new(i[0], i[1])
     }
 }
 
-impl From<f32> for Interval {
+impl From<f64> for Interval64 {
+    fn from(f: f64) -> Self {
+        Interval64::new(f, f)
+    }
+}
+
+impl From<f32> for Interval64 {
     fn from(f: f32) -> Self {
-        Interval::new(f, f)
+        Interval64::from(f as f64)
     }
 }
 
-impl std::ops::Add<Interval> for Interval {
+impl From<Interval> for Interval64 {
+    fn from(i: Interval) -> Self {
+        Interval64::new(i.lower as f64, i.upper as f64)
+    }
+}
+
+impl std::ops::Add<Interval64> for Interval64 {
     type Output = Self;
     fn add(self, rhs: Self) -> Self {
-        Interval::new(self.lower + rhs.lower, self.upper + rhs.upper)
+        Interval64::new(self.lower + rhs.lower, self.upper + rhs.upper)
     }
 }
 
-impl std::ops::Mul<Interval> for Interval {
+impl std::ops::Mul<Interval64> for Interval64 {
     type Output = Self;
     fn mul(self, rhs: Self) -> Self {
         if self.has_nan() || rhs.has_nan() {
-            return f32::NAN.into();
+            return f64::NAN.into();
         }
         let mut out = [0.0; 4];
         let mut k = 0;
@@ -614,15 +2463,15 @@ impl std::ops::Mul<Interval> for Interval {
             lower = lower.min(v);
             upper = upper.max(v);
         }
-        Interval::new(lower, upper)
+        Interval64::new(lower, upper)
     }
 }
 
-impl std::ops::Div<Interval> for Interval {
+impl std::ops::Div<Interval64> for Interval64 {
     type Output = Self;
     fn div(self, rhs: Self) -> Self {
         if self.has_nan() {
-            return f32::NAN.into();
+            return f64::NAN.into();
         }
         if rhs.lower > 0.0 || rhs.upper < 0.0 {
             let mut out = [0.0; 4];
@@ -639,25 +2488,543 @@ impl std::ops::Div<Interval> for Interval {
                 lower = lower.min(v);
                 upper = upper.max(v);
             }
+            Interval64::new(lower, upper)
+        } else {
+            f64::NAN.into()
+        }
+    }
+}
+
+impl std::ops::Sub<Interval64> for Interval64 {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Interval64::new(self.lower - rhs.upper, self.upper - rhs.lower)
+    }
+}
+
+/// Logical not: the result is 1 where the input is zero, and 0 elsewhere
+impl std::ops::Not for Interval64 {
+    type Output = Self;
+    fn not(self) -> Self {
+        if self.has_nan() {
+            Interval64::new(0.0, 1.0)
+        } else if self.lower == 0.0 && self.upper == 0.0 {
+            1.0.into()
+        } else if !self.contains(0.0) {
+            0.0.into()
+        } else {
+            Interval64::new(0.0, 1.0)
+        }
+    }
+}
+
+impl std::ops::Neg for Interval64 {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Interval64::new(-self.upper, -self.lower)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Stores an affine form, which tracks linear dependencies on the inputs
+///
+/// An affine form represents the value `c + x·εx + y·εy + z·εz ± e`, where each
+/// noise symbol `ε` is an unknown value in `[-1, 1]` belonging to one of the
+/// X, Y, Z inputs.  Because noise symbols are shared by every value in an
+/// expression, correlated terms cancel out: `x - x` is exactly zero, where
+/// interval arithmetic would return `[-w, w]` (for an input of width `w`).
+///
+/// Nonlinear operations are linearized (using a Chebyshev approximation where
+/// the function is convex or concave), with the approximation error folded
+/// into `e`; other operations fall back to interval arithmetic.  An
+/// [`Interval`] is also tracked alongside the affine form and intersected with
+/// it, so the resulting bounds are never looser than interval arithmetic.
+///
+/// # Warning
+/// This implementation does not set rounding modes, so it may not be _perfect_.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Affine {
+    /// Central value
+    center: f32,
+    /// Coefficients of the noise symbols for X, Y, Z
+    terms: [f32; 3],
+    /// Magnitude of the independent error term (always non-negative)
+    err: f32,
+    /// Bounds computed with interval arithmetic
+    bounds: Interval,
+}
+
+impl Affine {
+    /// Builds an affine form for an input, with its own noise symbol
+    ///
+    /// `axis` selects the noise symbol (0, 1, 2 for X, Y, Z).
+    ///
+    /// ```
+    /// # use fidget::eval::types::{Affine, Interval};
+    /// let x = Affine::input(Interval::new(0.0, 2.0), 0);
+    /// assert_eq!((x - x).interval(), Interval::new(0.0, 0.0));
+    ///
+    /// let y = Affine::from(Interval::new(0.0, 2.0));
+    /// assert_eq!((x - y).interval(), Interval::new(-2.0, 2.0));
+    /// ```
+    ///
+    /// # Panics
+    /// If `axis` is not in the range 0-2
+    pub fn input(i: Interval, axis: usize) -> Self {
+        assert!(axis < 3, "invalid axis {axis}");
+        let mut out = Self::from(i);
+        if out.err.is_finite() {
+            out.terms[axis] = out.err;
+            out.err = 0.0;
+        }
+        out
+    }
+    /// Returns the range of this value
+    ///
+    /// This is the range of the affine form, intersected with bounds from
+    /// interval arithmetic.
+    pub fn interval(&self) -> Interval {
+        if self.bounds.has_nan() {
+            return self.bounds;
+        }
+        let r = self.radius();
+        let lower = (self.center - r).max(self.bounds.lower());
+        let upper = (self.center + r).min(self.bounds.upper());
+        if lower <= upper {
             Interval::new(lower, upper)
         } else {
-            f32::NAN.into()
+            self.bounds
+        }
+    }
+    /// Returns the lower bound of the range
+    pub fn lower(&self) -> f32 {
+        self.interval().lower()
+    }
+    /// Returns the upper bound of the range
+    pub fn upper(&self) -> f32 {
+        self.interval().upper()
+    }
+    /// Returns `true` if the range contains `NaN`
+    pub fn has_nan(&self) -> bool {
+        self.bounds.has_nan()
+    }
+    /// Returns the total deviation of the affine form from its center
+    fn radius(&self) -> f32 {
+        self.terms.iter().map(|t| t.abs()).sum::<f32>() + self.err
+    }
+    /// Builds an affine form, falling back to `bounds` if it isn't finite
+    ///
+    /// The error term is padded to account for rounding in the `f32`
+    /// operations which produced `center` and `terms`.
+    fn build(center: f32, terms: [f32; 3], err: f32, bounds: Interval) -> Self {
+        let r = terms.iter().map(|t| t.abs()).sum::<f32>() + err;
+        let err = err + (center.abs() + r) * 4.0 * f32::EPSILON;
+        if center.is_finite()
+            && terms.iter().all(|t| t.is_finite())
+            && err.is_finite()
+        {
+            Self {
+                center,
+                terms,
+                err,
+                bounds,
+            }
+        } else {
+            Self::unbounded(bounds)
+        }
+    }
+    /// Builds an affine form with no useful range, relying on `bounds` alone
+    fn unbounded(bounds: Interval) -> Self {
+        Self {
+            center: 0.0,
+            terms: [0.0; 3],
+            err: f32::INFINITY,
+            bounds,
+        }
+    }
+    /// Computes `self * alpha + zeta ± delta`
+    ///
+    /// This is evaluated in double precision before being rounded to `f32`.
+    fn linear(
+        self,
+        alpha: f64,
+        zeta: f64,
+        delta: f64,
+        bounds: Interval,
+    ) -> Self {
+        let center = self.center as f64 * alpha + zeta;
+        let err = self.err as f64 * alpha.abs() + delta;
+        Self::build(
+            center as f32,
+            self.terms.map(|t| (t as f64 * alpha) as f32),
+            err as f32,
+            bounds,
+        )
+    }
+    /// Applies a function which is convex or concave over the input range
+    ///
+    /// The function is approximated by the line halfway between its secant
+    /// and the parallel tangent line (a Chebyshev approximation), and the
+    /// distance between the two lines becomes the error term.
+    ///
+    /// `bounds` is the result from interval arithmetic, and `tangent` returns
+    /// the point at which the derivative of `f` equals a given slope.
+    fn chebyshev(
+        self,
+        bounds: Interval,
+        f: impl Fn(f64) -> f64,
+        tangent: impl Fn(f64) -> f64,
+    ) -> Self {
+        let i = self.interval();
+        let (lower, upper) = (i.lower() as f64, i.upper() as f64);
+        if bounds.has_nan()
+            || !lower.is_finite()
+            || !upper.is_finite()
+            || lower >= upper
+        {
+            return bounds.into();
+        }
+        let alpha = (f(upper) - f(lower)) / (upper - lower);
+        let t = tangent(alpha).clamp(lower, upper);
+        let secant = f(lower) - alpha * lower;
+        let tangent = f(t) - alpha * t;
+        self.linear(
+            alpha,
+            (secant + tangent) / 2.0,
+            (secant - tangent).abs() / 2.0,
+            bounds,
+        )
+    }
+    /// Returns the difference `self - rhs` as an interval
+    ///
+    /// This is used for comparisons, which can be decided even if the ranges
+    /// overlap (e.g. `x < x + 1`).
+    fn difference(self, rhs: Self) -> Interval {
+        (self - rhs).interval()
+    }
+
+    /// Calculates the absolute value
+    pub fn abs(self) -> Self {
+        let i = self.interval();
+        if i.lower() >= 0.0 {
+            self
+        } else if i.upper() <= 0.0 {
+            -self
+        } else {
+            self.chebyshev(i.abs(), f64::abs, |_| 0.0)
+        }
+    }
+    /// Squares the value
+    ///
+    /// Like [`Interval::square`], this is tighter than multiplication.
+    pub fn square(self) -> Self {
+        let r = self.radius();
+        let half = r * r / 2.0;
+        Self::build(
+            self.center * self.center + half,
+            self.terms.map(|t| 2.0 * self.center * t),
+            2.0 * self.center.abs() * self.err + half,
+            self.interval().square(),
+        )
+    }
+    /// Calculates the square root
+    pub fn sqrt(self) -> Self {
+        let i = self.interval();
+        if i.lower() < 0.0 {
+            i.sqrt().into()
+        } else {
+            self.chebyshev(i.sqrt(), f64::sqrt, |a| 1.0 / (4.0 * a * a))
+        }
+    }
+    /// Calculates the reciprocal
+    ///
+    /// If the range includes 0, returns `NAN`
+    pub fn recip(self) -> Self {
+        let i = self.interval();
+        let sign = if i.lower() > 0.0 { 1.0 } else { -1.0 };
+        self.chebyshev(i.recip(), |x| 1.0 / x, |a| sign * (-1.0 / a).sqrt())
+    }
+    /// Computes the exponent function
+    pub fn exp(self) -> Self {
+        self.chebyshev(self.interval().exp(), f64::exp, f64::ln)
+    }
+    /// Computes the natural log
+    ///
+    /// If the range includes values `<= 0`, returns `NAN`
+    pub fn ln(self) -> Self {
+        self.chebyshev(self.interval().ln(), f64::ln, |a| 1.0 / a)
+    }
+    /// Computes the sine, using interval arithmetic
+    pub fn sin(self) -> Self {
+        self.interval().sin().into()
+    }
+    /// Computes the cosine, using interval arithmetic
+    pub fn cos(self) -> Self {
+        self.interval().cos().into()
+    }
+    /// Computes the tangent, using interval arithmetic
+    pub fn tan(self) -> Self {
+        self.interval().tan().into()
+    }
+    /// Computes the arcsine, using interval arithmetic
+    pub fn asin(self) -> Self {
+        self.interval().asin().into()
+    }
+    /// Computes the arccosine, using interval arithmetic
+    pub fn acos(self) -> Self {
+        self.interval().acos().into()
+    }
+    /// Computes the arctangent, using interval arithmetic
+    pub fn atan(self) -> Self {
+        self.interval().atan().into()
+    }
+    /// Rounds down to the nearest integer, using interval arithmetic
+    pub fn floor(self) -> Self {
+        self.interval().floor().into()
+    }
+    /// Rounds up to the nearest integer, using interval arithmetic
+    pub fn ceil(self) -> Self {
+        self.interval().ceil().into()
+    }
+    /// Rounds to the nearest integer, using interval arithmetic
+    pub fn round(self) -> Self {
+        self.interval().round().into()
+    }
+    /// Computes the two-argument arctangent (treating `self` as `y`), using
+    /// interval arithmetic
+    pub fn atan2(self, x: Self) -> Self {
+        self.interval().atan2(x.interval()).into()
+    }
+    /// Computes the least non-negative remainder of `self / rhs`, using
+    /// interval arithmetic
+    pub fn rem_euclid(self, rhs: Self) -> Self {
+        self.interval().rem_euclid(rhs.interval()).into()
+    }
+    /// Calculates the minimum of two values
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always less than the other.  Unlike [`Interval::min_choice`], this can
+    /// pick a side even if the two ranges overlap, based on their difference.
+    ///
+    /// ```
+    /// # use fidget::{eval::types::{Affine, Interval}, vm::Choice};
+    /// let x = Affine::input(Interval::new(0.0, 2.0), 0);
+    /// let (v, c) = x.min_choice(x + 1.0.into());
+    /// assert_eq!(c, Choice::Left);
+    /// assert_eq!(v.interval(), Interval::new(0.0, 2.0));
+    /// ```
+    pub fn min_choice(self, rhs: Self) -> (Self, Choice) {
+        let (value, choice) = self.interval().min_choice(rhs.interval());
+        let d = self.difference(rhs);
+        if choice == Choice::Left || d.upper() < 0.0 {
+            (self, Choice::Left)
+        } else if choice == Choice::Right || d.lower() > 0.0 {
+            (rhs, Choice::Right)
+        } else {
+            (value.into(), Choice::Both)
+        }
+    }
+    /// Calculates the maximum of two values
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always greater than the other.  Unlike [`Interval::max_choice`], this
+    /// can pick a side even if the two ranges overlap.
+    pub fn max_choice(self, rhs: Self) -> (Self, Choice) {
+        let (value, choice) = self.interval().max_choice(rhs.interval());
+        let d = self.difference(rhs);
+        if choice == Choice::Left || d.lower() > 0.0 {
+            (self, Choice::Left)
+        } else if choice == Choice::Right || d.upper() < 0.0 {
+            (rhs, Choice::Right)
+        } else {
+            (value.into(), Choice::Both)
+        }
+    }
+    /// Compares two values, returning -1, 0, or 1 (or a range thereof)
+    pub fn compare(self, rhs: Self) -> Self {
+        let d = self.difference(rhs);
+        if d.upper() < 0.0 {
+            (-1.0).into()
+        } else if d.lower() > 0.0 {
+            1.0.into()
+        } else {
+            self.interval().compare(rhs.interval()).into()
+        }
+    }
+    /// Calculates the logical `and` of two values
+    ///
+    /// The result is `self` if it's zero and `rhs` otherwise.  Returns both
+    /// the result and a [`Choice`] indicating whether one side is always
+    /// picked.
+    pub fn and_choice(self, rhs: Self) -> (Self, Choice) {
+        let (value, choice) = self.interval().and_choice(rhs.interval());
+        match choice {
+            Choice::Left => (self, choice),
+            Choice::Right => (rhs, choice),
+            _ => (value.into(), choice),
+        }
+    }
+    /// Calculates the logical `or` of two values
+    ///
+    /// The result is `self` if it's nonzero and `rhs` otherwise.  Returns both
+    /// the result and a [`Choice`] indicating whether one side is always
+    /// picked.
+    pub fn or_choice(self, rhs: Self) -> (Self, Choice) {
+        let (value, choice) = self.interval().or_choice(rhs.interval());
+        match choice {
+            Choice::Left => (self, choice),
+            Choice::Right => (rhs, choice),
+            _ => (value.into(), choice),
         }
     }
 }
 
-impl std::ops::Sub<Interval> for Interval {
+impl From<Interval> for Affine {
+    fn from(i: Interval) -> Self {
+        if i.has_nan() {
+            return Self::unbounded(i);
+        }
+        let center = i.midpoint();
+        let err = (i.upper() - center).max(center - i.lower());
+        Self::build(center, [0.0; 3], err, i)
+    }
+}
+
+impl From<[f32; 2]> for Affine {
+    fn from(i: [f32; 2]) -> Self {
+        Interval::from(i).into()
+    }
+}
+
+impl From<f32> for Affine {
+    fn from(f: f32) -> Self {
+        Interval::from(f).into()
+    }
+}
+
+impl From<Affine> for Interval {
+    fn from(a: Affine) -> Self {
+        a.interval()
+    }
+}
+
+impl std::fmt::Display for Affine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.interval())
+    }
+}
+
+impl std::ops::Add<Affine> for Affine {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::build(
+            self.center + rhs.center,
+            std::array::from_fn(|i| self.terms[i] + rhs.terms[i]),
+            self.err + rhs.err,
+            self.interval() + rhs.interval(),
+        )
+    }
+}
+
+impl std::ops::Sub<Affine> for Affine {
     type Output = Self;
     fn sub(self, rhs: Self) -> Self {
-        Interval::new(self.lower - rhs.upper, self.upper - rhs.lower)
+        Self::build(
+            self.center - rhs.center,
+            std::array::from_fn(|i| self.terms[i] - rhs.terms[i]),
+            self.err + rhs.err,
+            self.interval() - rhs.interval(),
+        )
     }
 }
 
-impl std::ops::Neg for Interval {
+impl std::ops::Mul<Affine> for Affine {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self::build(
+            self.center * rhs.center,
+            std::array::from_fn(|i| {
+                self.center * rhs.terms[i] + rhs.center * self.terms[i]
+            }),
+            self.center.abs() * rhs.err
+                + rhs.center.abs() * self.err
+                + self.radius() * rhs.radius(),
+            self.interval() * rhs.interval(),
+        )
+    }
+}
+
+impl std::ops::Div<Affine> for Affine {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        let out = self * rhs.recip();
+        Self::build(
+            out.center,
+            out.terms,
+            out.err,
+            self.interval() / rhs.interval(),
+        )
+    }
+}
+
+/// Logical not, using interval arithmetic
+impl std::ops::Not for Affine {
+    type Output = Self;
+    fn not(self) -> Self {
+        (!self.interval()).into()
+    }
+}
+
+impl std::ops::Neg for Affine {
     type Output = Self;
     fn neg(self) -> Self {
-        Interval::new(-self.upper, -self.lower)
+        Self {
+            center: -self.center,
+            terms: self.terms.map(|t| -t),
+            err: self.err,
+            bounds: -self.interval(),
+        }
+    }
+}
+
+/// Computes the range of `f` (either `sin` or `cos`) over `[lower, upper]`
+///
+/// `f` must have a maximum of 1 at `phase + 2πn` and a minimum of -1 at
+/// `phase + π + 2πn`; between those extrema, it's monotonic, so we only need to
+/// check the endpoints and any extrema within the interval.
+fn periodic_range(
+    lower: f64,
+    upper: f64,
+    f: fn(f64) -> f64,
+    phase: f64,
+) -> (f64, f64) {
+    use std::f64::consts::PI;
+    // Infinite bounds produce a width of either inf or NaN (inf - inf)
+    let width = upper - lower;
+    if width.is_nan() || width >= 2.0 * PI {
+        return (-1.0, 1.0);
+    }
+    let a = f(lower);
+    let b = f(upper);
+    let mut out = (a.min(b), a.max(b));
+
+    // Find the extrema (indexed by multiples of π) within the interval
+    let start = ((lower - phase) / PI).ceil();
+    let end = ((upper - phase) / PI).floor();
+    // (there are at most two, but we check three because the bounds may be
+    // off by one due to rounding)
+    for i in 0..3 {
+        let n = start + i as f64;
+        if n > end {
+            break;
+        } else if n.rem_euclid(2.0) == 0.0 {
+            out.1 = 1.0;
+        } else {
+            out.0 = -1.0;
+        }
     }
+    out
 }
 
 #[cfg(test)]
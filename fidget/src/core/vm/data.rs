@@ -58,7 +58,39 @@ pub struct VmData<const N: usize = { u8::MAX as usize }> {
 impl<const N: usize> VmData<N> {
     /// Builds a new tape for the given node
     pub fn new(context: &Context, node: Node) -> Result<Self, Error> {
-        let ssa = SsaTape::new(context, node)?;
+        Self::new_multi(context, &[node])
+    }
+
+    /// Builds a new tape with one output for each of the given nodes
+    ///
+    /// Output `i` is stored in register `i` at the end of evaluation, so there
+    /// may be at most `N` outputs; otherwise, this returns
+    /// `Error::TooManyOutputs`.
+    pub fn new_multi(context: &Context, nodes: &[Node]) -> Result<Self, Error> {
+        if nodes.len() > N {
+            return Err(Error::TooManyOutputs(nodes.len(), N));
+        }
+        let ssa = SsaTape::new_multi(context, nodes)?;
+        let asm = RegTape::new::<N>(&ssa);
+        Ok(Self { ssa, asm })
+    }
+
+    /// Builds a new tape with one output for each of the given nodes,
+    /// recording the originating [`Node`] of every operation
+    ///
+    /// The source map is preserved through [`VmData::simplify`]; it can be
+    /// read back with [`VmData::iter_asm_source`], and is used by
+    /// [`VmDebugEval`](crate::vm::VmDebugEval) to report where non-finite
+    /// values come from.  Otherwise, this is identical to
+    /// [`VmData::new_multi`].
+    pub fn new_with_source_map(
+        context: &Context,
+        nodes: &[Node],
+    ) -> Result<Self, Error> {
+        if nodes.len() > N {
+            return Err(Error::TooManyOutputs(nodes.len(), N));
+        }
+        let ssa = SsaTape::new_with_source_map(context, nodes)?;
         let asm = RegTape::new::<N>(&ssa);
         Ok(Self { ssa, asm })
     }
@@ -96,8 +128,16 @@ impl<const N: usize> VmData<N> {
         self.ssa.vars.len()
     }
 
+    /// Returns the number of outputs in this tape
+    pub fn output_count(&self) -> usize {
+        self.ssa.output_count
+    }
+
     /// Simplifies both inner tapes, using the provided choice array
     ///
+    /// Every output is preserved (in its original slot), so an expression
+    /// remains in the tape as long as any output depends on it.
+    ///
     /// To minimize allocations, this function takes a [`VmWorkspace`] and
     /// spare [`VmData`]; it will reuse those allocations.
     pub fn simplify(
@@ -113,23 +153,28 @@ impl<const N: usize> VmData<N> {
             ));
         }
         tape.ssa.reset();
+        let source = self.ssa.source_map.as_deref();
+        let mut source_out = tape.ssa.source_map.take().unwrap_or_default();
 
         // Steal `tape.asm` and hand it to the workspace for use in allocator
-        workspace.reset(self.ssa.tape.len(), tape.asm);
+        let output_count = self.output_count();
+        workspace.reset(self.ssa.tape.len(), output_count, tape.asm);
 
         let mut choice_count = 0;
 
-        // The tape is constructed so that the output slot is first
-        assert_eq!(self.ssa.tape[0].output(), 0);
-        workspace.set_active(self.ssa.tape[0].output(), 0);
-        workspace.count += 1;
+        // The tape is constructed so that outputs are in the first slots, and
+        // they are kept in those slots in the simplified tape.
+        for i in 0..output_count as u32 {
+            workspace.set_active(i, i);
+        }
+        workspace.count += output_count as u32;
 
         // Other iterators to consume various arrays in order
         let mut choice_iter = choices.iter().rev();
 
         let mut ops_out = tape.ssa.tape;
 
-        for mut op in self.ssa.tape.iter().cloned() {
+        for (i, mut op) in self.ssa.tape.iter().cloned().enumerate() {
             let index = op.output();
 
             if workspace.active(index).is_none() {
@@ -155,6 +200,10 @@ impl<const N: usize> VmData<N> {
                 | SsaOp::RecipReg(index, arg)
                 | SsaOp::SqrtReg(index, arg)
                 | SsaOp::SquareReg(index, arg)
+                | SsaOp::FloorReg(index, arg)
+                | SsaOp::CeilReg(index, arg)
+                | SsaOp::RoundReg(index, arg)
+                | SsaOp::NotReg(index, arg)
                 | SsaOp::SinReg(index, arg)
                 | SsaOp::CosReg(index, arg)
                 | SsaOp::TanReg(index, arg)
@@ -184,7 +233,9 @@ impl<const N: usize> VmData<N> {
                     }
                 }
                 SsaOp::MinRegImm(index, arg, imm)
-                | SsaOp::MaxRegImm(index, arg, imm) => {
+                | SsaOp::MaxRegImm(index, arg, imm)
+                | SsaOp::AndRegImm(index, arg, imm)
+                | SsaOp::OrRegImm(index, arg, imm) => {
                     match choice_iter.next().unwrap() {
                         Choice::Left => match workspace.active(*arg) {
                             Some(new_arg) => {
@@ -207,7 +258,9 @@ impl<const N: usize> VmData<N> {
                     }
                 }
                 SsaOp::MinRegReg(index, lhs, rhs)
-                | SsaOp::MaxRegReg(index, lhs, rhs) => {
+                | SsaOp::MaxRegReg(index, lhs, rhs)
+                | SsaOp::AndRegReg(index, lhs, rhs)
+                | SsaOp::OrRegReg(index, lhs, rhs) => {
                     match choice_iter.next().unwrap() {
                         Choice::Left => match workspace.active(*lhs) {
                             Some(new_lhs) => {
@@ -239,7 +292,10 @@ impl<const N: usize> VmData<N> {
                 SsaOp::AddRegReg(index, lhs, rhs)
                 | SsaOp::MulRegReg(index, lhs, rhs)
                 | SsaOp::SubRegReg(index, lhs, rhs)
-                | SsaOp::DivRegReg(index, lhs, rhs) => {
+                | SsaOp::DivRegReg(index, lhs, rhs)
+                | SsaOp::AtanRegReg(index, lhs, rhs)
+                | SsaOp::ModRegReg(index, lhs, rhs)
+                | SsaOp::CompareRegReg(index, lhs, rhs) => {
                     *index = new_index;
                     *lhs = workspace.get_or_insert_active(*lhs);
                     *rhs = workspace.get_or_insert_active(*rhs);
@@ -249,12 +305,24 @@ impl<const N: usize> VmData<N> {
                 | SsaOp::SubRegImm(index, arg, _imm)
                 | SsaOp::SubImmReg(index, arg, _imm)
                 | SsaOp::DivRegImm(index, arg, _imm)
-                | SsaOp::DivImmReg(index, arg, _imm) => {
+                | SsaOp::DivImmReg(index, arg, _imm)
+                | SsaOp::AtanRegImm(index, arg, _imm)
+                | SsaOp::AtanImmReg(index, arg, _imm)
+                | SsaOp::ModRegImm(index, arg, _imm)
+                | SsaOp::ModImmReg(index, arg, _imm)
+                | SsaOp::CompareRegImm(index, arg, _imm)
+                | SsaOp::CompareImmReg(index, arg, _imm) => {
                     *index = new_index;
                     *arg = workspace.get_or_insert_active(*arg);
                 }
             }
-            workspace.alloc.op(op);
+            match source {
+                Some(source) => {
+                    workspace.alloc.op_with_source(op, source[i]);
+                    source_out.push(source[i]);
+                }
+                None => workspace.alloc.op(op),
+            }
             ops_out.push(op);
         }
 
@@ -265,7 +333,9 @@ impl<const N: usize> VmData<N> {
             ssa: SsaTape {
                 tape: ops_out,
                 choice_count,
+                output_count,
                 vars: self.ssa.vars.clone(),
+                source_map: source.map(|_| source_out),
             },
             asm: asm_tape,
         })
@@ -276,6 +346,15 @@ impl<const N: usize> VmData<N> {
         self.asm.iter().cloned().rev()
     }
 
+    /// Produces an iterator over the originating [`Node`] of each [`RegOp`],
+    /// in the same order as [`VmData::iter_asm`]
+    ///
+    /// Returns `None` if this tape was built without a source map (see
+    /// [`VmData::new_with_source_map`]).
+    pub fn iter_asm_source(&self) -> Option<impl Iterator<Item = Node> + '_> {
+        self.asm.source_map().map(|s| s.iter().cloned().rev())
+    }
+
     /// Pretty-prints the inner SSA tape
     pub fn pretty_print(&self) {
         self.ssa.pretty_print();
@@ -337,8 +416,15 @@ impl<const N: usize> VmWorkspace<N> {
 
     /// Resets the workspace, preserving allocations and claiming the given
     /// [`RegTape`].
-    pub fn reset(&mut self, tape_len: usize, tape: RegTape) {
-        self.alloc.reset(tape_len, tape);
+    ///
+    /// The first `output_count` SSA slots are bound to the tape's outputs.
+    pub fn reset(
+        &mut self,
+        tape_len: usize,
+        output_count: usize,
+        tape: RegTape,
+    ) {
+        self.alloc.reset(tape_len, output_count, tape);
         self.bind.fill(u32::MAX);
         self.bind.resize(tape_len, u32::MAX);
         self.count = 0;
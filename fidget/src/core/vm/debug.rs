@@ -0,0 +1,389 @@
+//! Debugging evaluator, which finds the source of non-finite values
+use super::{tracing_op, SlotArray, VmData};
+use crate::{compiler::RegOp, context::Node, Error};
+
+/// Description of the first non-finite value found by [`VmDebugEval`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct NonFinite {
+    /// Index of the operation, in the order of [`VmData::iter_asm`]
+    pub index: usize,
+    /// Operation which produced the value
+    pub op: RegOp,
+    /// Value produced by the operation (`NaN` or an infinity)
+    pub value: f32,
+    /// [`Node`] which generated the operation
+    ///
+    /// This is only available if the tape was built with a source map (e.g.
+    /// by [`VmData::new_with_source_map`]).
+    pub node: Option<Node>,
+    /// Name of the input (`"X"`, `"Y"`, or `"Z"`) or variable read by the
+    /// operation, if it's an `Input` or `Var` operation
+    pub var: Option<String>,
+}
+
+/// Single-point evaluator which reports the first operation to produce a
+/// non-finite value (`NaN` or an infinity)
+///
+/// This uses the same single-precision arithmetic as
+/// [`VmPointEval`](crate::vm::VmPointEval), so it finds the same values that a
+/// [`VmShape`](crate::vm::VmShape) (or a JIT-compiled shape) would produce,
+/// but it is slower and does not record a trace.
+///
+/// ```
+/// use fidget::{context::Context, vm::{VmData, VmDebugEval}};
+///
+/// let mut ctx = Context::new();
+/// let x = ctx.x();
+/// let r = ctx.var("r").unwrap();
+/// let d = ctx.sub(x, r).unwrap();
+/// let s = ctx.sqrt(d).unwrap();
+/// let root = ctx.add(s, 1.0).unwrap();
+///
+/// let data = VmData::<255>::new_with_source_map(&ctx, &[root])?;
+/// let mut eval = VmDebugEval::new();
+/// let (v, bad) = eval.eval(&data, 3.0, 0.0, 0.0, &[2.0])?;
+/// assert_eq!(v, 2.0);
+/// assert!(bad.is_none());
+///
+/// let (v, bad) = eval.eval(&data, 1.0, 0.0, 0.0, &[2.0])?;
+/// assert!(v.is_nan());
+/// let bad = bad.unwrap();
+/// assert_eq!(bad.node, Some(s)); // sqrt of a negative number
+///
+/// let (_v, bad) = eval.eval(&data, 1.0, 0.0, 0.0, &[f32::NAN])?;
+/// let bad = bad.unwrap();
+/// assert_eq!(bad.node, Some(r));
+/// assert_eq!(bad.var.as_deref(), Some("r"));
+/// # Ok::<(), fidget::Error>(())
+/// ```
+#[derive(Default)]
+pub struct VmDebugEval {
+    slots: Vec<f32>,
+}
+
+impl VmDebugEval {
+    /// Builds a new evaluator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Evaluates the tape at the given position
+    ///
+    /// Returns the value of the first output and, if any operation produced a
+    /// non-finite value, a description of the first such operation.
+    pub fn eval<const N: usize>(
+        &mut self,
+        tape: &VmData<N>,
+        x: f32,
+        y: f32,
+        z: f32,
+        vars: &[f32],
+    ) -> Result<(f32, Option<NonFinite>), Error> {
+        if vars.len() != tape.var_count() {
+            return Err(Error::BadVarSlice(vars.len(), tape.var_count()));
+        }
+        self.slots.fill(f32::NAN);
+        self.slots.resize(tape.slot_count(), f32::NAN);
+
+        let mut first = None;
+        let mut source = tape.iter_asm_source();
+        let mut v = SlotArray(&mut self.slots);
+        for (index, op) in tape.iter_asm().enumerate() {
+            let node = source.as_mut().map(|s| s.next().unwrap());
+            tracing_op::<f32, _>(op, &mut v, [x, y, z], vars);
+
+            // Loads and stores only move values around, so any non-finite
+            // value was already reported when it was produced
+            let out = match op {
+                RegOp::Load(..) | RegOp::Store(..) => continue,
+                op => op.output().unwrap(),
+            };
+            let value = v[out];
+            if first.is_none() && !value.is_finite() {
+                let var = match op {
+                    RegOp::Input(_, i) => Some(["X", "Y", "Z"][i as usize]),
+                    RegOp::Var(_, i) => tape
+                        .vars()
+                        .iter()
+                        .find(|(_, v)| **v == i)
+                        .map(|(name, _)| name.as_str()),
+                    _ => None,
+                };
+                first = Some(NonFinite {
+                    index,
+                    op,
+                    value,
+                    node,
+                    var: var.map(String::from),
+                });
+            }
+        }
+        Ok((self.slots[0], first))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::Context,
+        eval::{
+            test::{
+                build_stress_fn, canonical::*, test_args, CanonicalBinaryOp,
+                CanonicalUnaryOp,
+            },
+            EzShape, MathShape, Shape, TracingEvaluator,
+        },
+        vm::{GenericVmShape, VmShape},
+    };
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_debug_eval_matches_point_eval() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let a = ctx.var("a").unwrap();
+        let x2 = ctx.square(x).unwrap();
+        let ya = ctx.mul(y, a).unwrap();
+        let m = ctx.min(x2, ya).unwrap();
+        let l = ctx.ln(m).unwrap();
+        let c = ctx.compare(x, y).unwrap();
+        let root = ctx.if_nonzero_else(c, l, m).unwrap();
+
+        let shape = VmShape::new(&ctx, root).unwrap();
+        let data = VmData::<255>::new_with_source_map(&ctx, &[root]).unwrap();
+        let mut point = VmShape::new_point_eval();
+        let mut debug = VmDebugEval::new();
+        let tape = shape.ez_point_tape();
+        for &x in &[-2.0, -0.5, 0.0, 0.5, 1.0, 3.0] {
+            for &y in &[-1.0, 0.0, 0.25, 2.0] {
+                for &a in &[-1.0, 0.0, 1.5, f32::NAN] {
+                    let (p, _) = point.eval(&tape, x, y, 0.0, &[a]).unwrap();
+                    let (d, bad) = debug.eval(&data, x, y, 0.0, &[a]).unwrap();
+                    assert_eq!(p.to_bits(), d.to_bits(), "{x} {y} {a}");
+                    // Non-finite intermediate values may be discarded (e.g.
+                    // by `and`), so we can only check in one direction
+                    if !d.is_finite() || a.is_nan() {
+                        assert!(bad.is_some(), "{x} {y} {a}");
+                    }
+                }
+            }
+        }
+
+        let (_, bad) = debug.eval(&data, 1.0, 1.0, 0.0, &[-1.0]).unwrap();
+        let bad = bad.unwrap();
+        assert_eq!(bad.node, Some(l)); // ln(-1) is NaN
+        assert!(bad.value.is_nan());
+        assert_eq!(bad.var, None);
+        assert!(matches!(bad.op, RegOp::LnReg(..)));
+        assert_eq!(data.iter_asm().nth(bad.index), Some(bad.op));
+
+        let (_, bad) =
+            debug.eval(&data, 1.0, f32::INFINITY, 0.0, &[1.0]).unwrap();
+        let bad = bad.unwrap();
+        assert_eq!(bad.node, Some(y));
+        assert_eq!(bad.var.as_deref(), Some("Y"));
+        assert_eq!(bad.value, f32::INFINITY);
+
+        assert!(matches!(
+            debug.eval(&data, 0.0, 0.0, 0.0, &[]),
+            Err(Error::BadVarSlice(0, 1))
+        ));
+    }
+
+    /// Evaluates the shape with both [`VmDebugEval`] and
+    /// [`VmPointEval`](crate::vm::VmPointEval), checking that they agree
+    fn check_against_point<const N: usize>(
+        shape: &GenericVmShape<N>,
+        seen: &mut HashSet<std::mem::Discriminant<RegOp>>,
+    ) {
+        let data = shape.data();
+        seen.extend(data.iter_asm().map(|op| std::mem::discriminant(&op)));
+
+        let mut point = GenericVmShape::<N>::new_point_eval();
+        let mut debug = VmDebugEval::new();
+        let tape = shape.ez_point_tape();
+        for x in test_args() {
+            for y in [-1.0, 0.0, 0.5, 2.0, f32::NAN] {
+                let vars = vec![y; data.var_count()];
+                let (p, _) = point.eval(&tape, x, y, 0.0, &vars).unwrap();
+                let (d, bad) = debug.eval(data, x, y, 0.0, &vars).unwrap();
+                let ops: Vec<_> = data.iter_asm().collect();
+                assert_eq!(p.to_bits(), d.to_bits(), "{ops:?} at {x} {y}");
+                if !d.is_finite() {
+                    assert!(bad.is_some(), "{ops:?} at {x} {y}");
+                }
+            }
+        }
+    }
+
+    fn unary<C: CanonicalUnaryOp>(ctx: &mut Context) -> Vec<Node> {
+        let x = ctx.x();
+        vec![C::build(ctx, x)]
+    }
+
+    fn binary<C: CanonicalBinaryOp>(ctx: &mut Context) -> Vec<Node> {
+        let x = ctx.x();
+        let y = ctx.y();
+        let mut out = vec![C::build(ctx, x, y)];
+        for imm in test_args() {
+            let c = ctx.constant(imm as f64);
+            out.push(C::build(ctx, x, c));
+            out.push(C::build(ctx, c, x));
+        }
+        out
+    }
+
+    #[test]
+    fn test_debug_eval_all_ops() {
+        let mut ctx = Context::new();
+        let mut nodes = vec![];
+        nodes.extend(unary::<neg>(&mut ctx));
+        nodes.extend(unary::<recip>(&mut ctx));
+        nodes.extend(unary::<abs>(&mut ctx));
+        nodes.extend(unary::<sin>(&mut ctx));
+        nodes.extend(unary::<cos>(&mut ctx));
+        nodes.extend(unary::<tan>(&mut ctx));
+        nodes.extend(unary::<asin>(&mut ctx));
+        nodes.extend(unary::<acos>(&mut ctx));
+        nodes.extend(unary::<atan>(&mut ctx));
+        nodes.extend(unary::<exp>(&mut ctx));
+        nodes.extend(unary::<ln>(&mut ctx));
+        nodes.extend(unary::<square>(&mut ctx));
+        nodes.extend(unary::<sqrt>(&mut ctx));
+        nodes.extend(unary::<floor>(&mut ctx));
+        nodes.extend(unary::<ceil>(&mut ctx));
+        nodes.extend(unary::<round>(&mut ctx));
+        nodes.extend(unary::<not>(&mut ctx));
+        nodes.extend(binary::<add>(&mut ctx));
+        nodes.extend(binary::<sub>(&mut ctx));
+        nodes.extend(binary::<mul>(&mut ctx));
+        nodes.extend(binary::<div>(&mut ctx));
+        nodes.extend(binary::<min>(&mut ctx));
+        nodes.extend(binary::<max>(&mut ctx));
+        nodes.extend(binary::<atan2>(&mut ctx));
+        nodes.extend(binary::<modulo>(&mut ctx));
+        nodes.extend(binary::<compare>(&mut ctx));
+        nodes.extend(binary::<and>(&mut ctx));
+        nodes.extend(binary::<or>(&mut ctx));
+        let a = ctx.var("a").unwrap();
+        nodes.push(a);
+        nodes.push(ctx.constant(1.5));
+
+        let mut seen = HashSet::new();
+        for n in nodes {
+            let shape = VmShape::new(&ctx, n).unwrap();
+            check_against_point(&shape, &mut seen);
+        }
+
+        // Simplifying a `min` to a branch which is used elsewhere produces a
+        // copy
+        let x = ctx.x();
+        let y = ctx.y();
+        let m = ctx.min(x, y).unwrap();
+        let root = ctx.add(m, x).unwrap();
+        let shape = VmShape::new(&ctx, root).unwrap();
+        let mut point = VmShape::new_point_eval();
+        let (_, trace) = point
+            .eval(&shape.ez_point_tape(), -1.0, 1.0, 0.0, &[])
+            .unwrap();
+        let shape = shape.ez_simplify(trace.unwrap()).unwrap();
+        check_against_point(&shape, &mut seen);
+
+        // Running out of registers produces loads and stores
+        let (ctx, root) = build_stress_fn(8);
+        let shape = GenericVmShape::<4>::new(&ctx, root).unwrap();
+        check_against_point(&shape, &mut seen);
+
+        // Make sure that we've tested every `RegOp` variant
+        assert_eq!(seen.len(), 50, "not every opcode was tested");
+    }
+
+    #[test]
+    fn test_debug_eval_no_source_map() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let root = ctx.recip(x).unwrap();
+        let data = VmData::<255>::new(&ctx, root).unwrap();
+        assert!(data.iter_asm_source().is_none());
+
+        let mut debug = VmDebugEval::new();
+        let (v, bad) = debug.eval(&data, 0.0, 0.0, 0.0, &[]).unwrap();
+        assert_eq!(v, f32::INFINITY);
+        let bad = bad.unwrap();
+        assert_eq!(bad.node, None);
+        assert!(matches!(bad.op, RegOp::RecipReg(..)));
+    }
+
+    #[test]
+    fn test_source_map() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let xy = ctx.min(x, y).unwrap();
+        let s = ctx.sqrt(xy).unwrap();
+        let root = ctx.max(s, z).unwrap();
+
+        let shape = VmShape::new_with_source_map(&ctx, &[root, x]).unwrap();
+        let data = shape.data();
+        let nodes: Vec<_> = data.iter_asm_source().unwrap().collect();
+        assert_eq!(nodes.len(), data.len());
+        for (op, node) in data.iter_asm().zip(&nodes) {
+            let expected = match op {
+                RegOp::Input(_, 0) | RegOp::CopyReg(..) => x,
+                RegOp::Input(_, 1) => y,
+                RegOp::Input(_, 2) => z,
+                RegOp::MinRegReg(..) => xy,
+                RegOp::SqrtReg(..) => s,
+                RegOp::MaxRegReg(..) => root,
+                op => panic!("unexpected op {op:?}"),
+            };
+            assert_eq!(*node, expected, "{op:?}");
+        }
+
+        // The SSA tape's map is preserved when building the register tape
+        let ssa =
+            crate::compiler::SsaTape::new_with_source_map(&ctx, &[root, x])
+                .unwrap();
+        assert_eq!(ssa.source_map.as_ref().unwrap().len(), ssa.len());
+        assert!(crate::compiler::SsaTape::new(&ctx, root)
+            .unwrap()
+            .source_map
+            .is_none());
+
+        // Simplifying picks the `y` branch of `min(x, y)`, and the source map
+        // follows along; the `min` becomes a copy (or disappears entirely).
+        let mut point = VmShape::new_point_eval();
+        let tape = shape.ez_point_tape();
+        let (_, trace) = point.eval(&tape, 2.0, 1.0, 0.0, &[]).unwrap();
+        let next = shape.ez_simplify(trace.unwrap()).unwrap();
+        let data = next.data();
+        let nodes: Vec<_> = data.iter_asm_source().unwrap().collect();
+        assert_eq!(nodes.len(), data.len());
+        assert!(!data.iter_asm().any(|op| matches!(op, RegOp::MinRegReg(..))));
+        for (op, node) in data.iter_asm().zip(&nodes) {
+            match op {
+                RegOp::SqrtReg(..) => assert_eq!(*node, s),
+                RegOp::Input(_, 1) => assert_eq!(*node, y),
+                RegOp::MaxRegReg(..) => assert_eq!(*node, root),
+                _ => (),
+            }
+        }
+
+        let mut debug = VmDebugEval::new();
+        let (_, bad) = debug.eval(data, 2.0, -1.0, 0.0, &[]).unwrap();
+        assert_eq!(bad.unwrap().node, Some(s));
+
+        // Simplifying a tape without a source map doesn't create one
+        let shape = VmShape::new(&ctx, root).unwrap();
+        let (_, trace) = point
+            .eval(&shape.ez_point_tape(), 2.0, 1.0, 0.0, &[])
+            .unwrap();
+        let next = shape.ez_simplify(trace.unwrap()).unwrap();
+        assert!(next.data().iter_asm_source().is_none());
+    }
+}
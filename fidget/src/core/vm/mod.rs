@@ -1,11 +1,13 @@
 //! Simple virtual machine for shape evaluation
 use crate::{
-    compiler::RegOp,
     context::Node,
     eval::{
-        types::{Grad, Interval},
-        BulkEvaluator, MathShape, Shape, ShapeVars, Tape, Trace,
-        TracingEvaluator,
+        types::{
+            Affine, Grad, Hessian, Interval, Interval64, IntervalGrad,
+            MultiGrad,
+        },
+        BulkEvaluator, BulkOutput, GradSeeds, GradTarget, MathShape, Shape,
+        ShapeVars, Tape, Trace, TracingEvaluator,
     },
     Context, Error,
 };
@@ -13,16 +15,22 @@ use std::{collections::HashMap, sync::Arc};
 
 mod choice;
 mod data;
+mod debug;
+mod ops;
 
 pub use choice::Choice;
 pub use data::{VmData, VmWorkspace};
+pub use debug::{NonFinite, VmDebugEval};
+
+use ops::{bulk_op, tracing_op, BulkOps, RobustInterval, TracingOps};
 
 ////////////////////////////////////////////////////////////////////////////////
 
 /// Shape that use a VM backend for evaluation
 ///
 /// Internally, the [`VmShape`] stores an [`Arc<VmData>`](VmData), and
-/// iterates over a [`Vec<RegOp>`](RegOp) to perform evaluation.
+/// iterates over a [`Vec<RegOp>`](crate::compiler::RegOp) to perform
+/// evaluation.
 ///
 /// All of the associated [`Tape`] types simply clone the internal `Arc`;
 /// there's no separate planning required to generate a tape.
@@ -94,6 +102,34 @@ impl AsRef<[Choice]> for VmTrace {
 pub struct GenericVmShape<const N: usize>(Arc<VmData<N>>);
 
 impl<const N: usize> GenericVmShape<N> {
+    /// Builds a new shape with one output for each of the given nodes
+    ///
+    /// Shared subexpressions are only evaluated once.  Bulk evaluators return
+    /// one slice per output (in the order given here), while tracing
+    /// evaluators return the first output; traces include choices from every
+    /// output, so simplification keeps any branch that's used by at least one
+    /// output.
+    ///
+    /// There may be at most `N` outputs.  Multi-output tapes are only
+    /// supported by the VM; `JitShape` always has a single output.
+    pub fn new_multi(ctx: &Context, nodes: &[Node]) -> Result<Self, Error> {
+        let d = VmData::new_multi(ctx, nodes)?;
+        Ok(Self(Arc::new(d)))
+    }
+
+    /// Builds a new shape which records the originating [`Node`] of every
+    /// operation, for use with [`VmDebugEval`]
+    ///
+    /// The source map is preserved when the shape is simplified; see
+    /// [`VmData::new_with_source_map`] for details.
+    pub fn new_with_source_map(
+        ctx: &Context,
+        nodes: &[Node],
+    ) -> Result<Self, Error> {
+        let d = VmData::new_with_source_map(ctx, nodes)?;
+        Ok(Self(Arc::new(d)))
+    }
+
     pub(crate) fn simplify_inner(
         &self,
         choices: &[Choice],
@@ -127,6 +163,11 @@ impl<const N: usize> GenericVmShape<N> {
     pub fn choice_count(&self) -> usize {
         self.0.choice_count()
     }
+
+    /// Returns the number of outputs in the tape
+    pub fn output_count(&self) -> usize {
+        self.0.output_count()
+    }
 }
 
 impl<const N: usize> Shape for GenericVmShape<N> {
@@ -143,6 +184,18 @@ impl<const N: usize> Shape for GenericVmShape<N> {
     fn grad_slice_tape(&self, _storage: ()) -> Self {
         self.clone()
     }
+    type HessianSliceEval = VmHessianSliceEval<N>;
+    fn hessian_slice_tape(&self, _storage: ()) -> Self {
+        self.clone()
+    }
+    type MultiGradSliceEval = VmMultiGradSliceEval<N>;
+    fn multi_grad_slice_tape(
+        &self,
+        targets: &[GradTarget],
+        _storage: (),
+    ) -> Result<VmMultiGradTape<N>, Error> {
+        self.multi_grad_tape(targets)
+    }
     type PointEval = VmPointEval<N>;
     fn point_tape(&self, _storage: ()) -> Self {
         self.clone()
@@ -151,6 +204,30 @@ impl<const N: usize> Shape for GenericVmShape<N> {
     fn interval_tape(&self, _storage: ()) -> Self {
         self.clone()
     }
+    type RobustIntervalEval = VmRobustIntervalEval<N>;
+    fn robust_interval_tape(&self, _storage: ()) -> Self {
+        self.clone()
+    }
+    type AffineEval = VmAffineEval<N>;
+    fn affine_tape(&self, _storage: ()) -> Self {
+        self.clone()
+    }
+    type IntervalGradEval = VmIntervalGradEval<N>;
+    fn interval_grad_tape(&self, _storage: ()) -> Self {
+        self.clone()
+    }
+    type PointEval64 = VmPointEval64<N>;
+    fn point_tape64(&self, _storage: ()) -> Self {
+        self.clone()
+    }
+    type IntervalEval64 = VmIntervalEval64<N>;
+    fn interval_tape64(&self, _storage: ()) -> Self {
+        self.clone()
+    }
+    type FloatSliceEval64 = VmFloatSliceEval64<N>;
+    fn float_slice_tape64(&self, _storage: ()) -> Self {
+        self.clone()
+    }
     type Trace = VmTrace;
     fn simplify(
         &self,
@@ -169,6 +246,10 @@ impl<const N: usize> Shape for GenericVmShape<N> {
         GenericVmShape::size(self)
     }
 
+    fn var_count(&self) -> usize {
+        GenericVmShape::var_count(self)
+    }
+
     fn tile_sizes_3d() -> &'static [usize] {
         &[256, 128, 64, 32, 16, 8]
     }
@@ -193,6 +274,22 @@ impl<const N: usize> ShapeVars for GenericVmShape<N> {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// Compares two values, returning -1, 0, or 1 (or `NAN` if either is `NAN`)
+pub(crate) fn compare_f32(a: f32, b: f32) -> f32 {
+    match a.partial_cmp(&b) {
+        Some(c) => c as i8 as f32,
+        None => f32::NAN,
+    }
+}
+
+/// Compares two values, returning -1, 0, or 1 (or `NAN` if either is `NAN`)
+pub(crate) fn compare_f64(a: f64, b: f64) -> f64 {
+    match a.partial_cmp(&b) {
+        Some(c) => c as i8 as f64,
+        None => f64::NAN,
+    }
+}
+
 /// Helper struct to reduce boilerplate conversions
 struct SlotArray<'a, T>(&'a mut [T]);
 impl<T> std::ops::Index<u8> for SlotArray<'_, T> {
@@ -235,12 +332,39 @@ impl<T> Default for TracingVmEval<T> {
     }
 }
 
-impl<T: From<f32> + Clone> TracingVmEval<T> {
+impl<T: From<f32> + Copy> TracingVmEval<T> {
     fn resize_slots<const N: usize>(&mut self, tape: &VmData<N>) {
         self.slots.resize(tape.slot_count(), f32::NAN.into());
         self.choices.resize(tape.choice_count(), Choice::Unknown);
         self.choices.fill(Choice::Unknown);
     }
+
+    /// Evaluates the tape, using `O` for arithmetic
+    ///
+    /// Returns the first output and the trace, if any choice could simplify
+    /// the tape.
+    fn run<O: TracingOps<Data = T>, V: Copy + Into<T>, const N: usize>(
+        &mut self,
+        tape: &VmData<N>,
+        inputs: [T; 3],
+        vars: &[V],
+    ) -> (T, Option<&VmTrace>) {
+        self.resize_slots(tape);
+
+        let mut simplify = false;
+        let mut v = SlotArray(&mut self.slots);
+        let mut choices = self.choices.as_mut_slice().iter_mut();
+        for op in tape.iter_asm() {
+            if let Some(choice) = tracing_op::<O, V>(op, &mut v, inputs, vars) {
+                *choices.next().unwrap() |= choice;
+                simplify |= choice != Choice::Both;
+            }
+        }
+        (
+            self.slots[0],
+            if simplify { Some(&self.choices) } else { None },
+        )
+    }
 }
 
 /// VM-based tracing evaluator for intervals
@@ -265,131 +389,125 @@ impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
         let z = z.into();
         let tape = tape.0.as_ref();
         self.check_arguments(vars, tape.var_count())?;
-        self.0.resize_slots(tape);
-        assert_eq!(vars.len(), tape.var_count());
+        Ok(self.0.run::<Interval, _, N>(tape, [x, y, z], vars))
+    }
+}
 
-        let mut simplify = false;
-        let mut v = SlotArray(&mut self.0.slots);
-        let mut choices = self.0.choices.as_mut_slice().iter_mut();
-        for op in tape.iter_asm() {
-            match op {
-                RegOp::Input(out, i) => {
-                    v[out] = match i {
-                        0 => x,
-                        1 => y,
-                        2 => z,
-                        _ => panic!("Invalid input: {}", i),
-                    }
-                }
-                RegOp::Var(out, i) => {
-                    v[out] = vars[i as usize].into();
-                }
-                RegOp::NegReg(out, arg) => {
-                    v[out] = -v[arg];
-                }
-                RegOp::AbsReg(out, arg) => {
-                    v[out] = v[arg].abs();
-                }
-                RegOp::RecipReg(out, arg) => {
-                    v[out] = v[arg].recip();
-                }
-                RegOp::SqrtReg(out, arg) => {
-                    v[out] = v[arg].sqrt();
-                }
-                RegOp::SquareReg(out, arg) => {
-                    v[out] = v[arg].square();
-                }
-                RegOp::SinReg(out, arg) => {
-                    v[out] = v[arg].sin();
-                }
-                RegOp::CosReg(out, arg) => {
-                    v[out] = v[arg].cos();
-                }
-                RegOp::TanReg(out, arg) => {
-                    v[out] = v[arg].tan();
-                }
-                RegOp::AsinReg(out, arg) => {
-                    v[out] = v[arg].asin();
-                }
-                RegOp::AcosReg(out, arg) => {
-                    v[out] = v[arg].acos();
-                }
-                RegOp::AtanReg(out, arg) => {
-                    v[out] = v[arg].atan();
-                }
-                RegOp::ExpReg(out, arg) => {
-                    v[out] = v[arg].exp();
-                }
-                RegOp::LnReg(out, arg) => {
-                    v[out] = v[arg].ln();
-                }
-                RegOp::CopyReg(out, arg) => v[out] = v[arg],
-                RegOp::AddRegImm(out, arg, imm) => {
-                    v[out] = v[arg] + imm.into();
-                }
-                RegOp::MulRegImm(out, arg, imm) => {
-                    v[out] = v[arg] * imm.into();
-                }
-                RegOp::DivRegImm(out, arg, imm) => {
-                    v[out] = v[arg] / imm.into();
-                }
-                RegOp::DivImmReg(out, arg, imm) => {
-                    let imm: Interval = imm.into();
-                    v[out] = imm / v[arg];
-                }
-                RegOp::SubImmReg(out, arg, imm) => {
-                    v[out] = Interval::from(imm) - v[arg];
-                }
-                RegOp::SubRegImm(out, arg, imm) => {
-                    v[out] = v[arg] - imm.into();
-                }
-                RegOp::MinRegImm(out, arg, imm) => {
-                    let (value, choice) = v[arg].min_choice(imm.into());
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::MaxRegImm(out, arg, imm) => {
-                    let (value, choice) = v[arg].max_choice(imm.into());
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::AddRegReg(out, lhs, rhs) => v[out] = v[lhs] + v[rhs],
-                RegOp::MulRegReg(out, lhs, rhs) => v[out] = v[lhs] * v[rhs],
-                RegOp::DivRegReg(out, lhs, rhs) => v[out] = v[lhs] / v[rhs],
-                RegOp::SubRegReg(out, lhs, rhs) => v[out] = v[lhs] - v[rhs],
-                RegOp::MinRegReg(out, lhs, rhs) => {
-                    let (value, choice) = v[lhs].min_choice(v[rhs]);
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::MaxRegReg(out, lhs, rhs) => {
-                    let (value, choice) = v[lhs].max_choice(v[rhs]);
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::CopyImm(out, imm) => {
-                    v[out] = imm.into();
-                }
-                RegOp::Load(out, mem) => {
-                    v[out] = v[mem];
-                }
-                RegOp::Store(out, mem) => {
-                    v[mem] = v[out];
-                }
-            }
-        }
-        Ok((
-            self.0.slots[0],
-            if simplify {
-                Some(&self.0.choices)
-            } else {
-                None
-            },
-        ))
+/// VM-based tracing evaluator using affine arithmetic
+///
+/// Each input is converted to an interval and assigned its own noise symbol,
+/// so that correlated terms cancel out; see [`Affine`] for details.  This is
+/// slower than [`VmIntervalEval`], but produces tighter bounds.
+#[derive(Default)]
+pub struct VmAffineEval<const N: usize>(TracingVmEval<Affine>);
+impl<const N: usize> TracingEvaluator for VmAffineEval<N> {
+    type Data = Affine;
+    type Tape = GenericVmShape<N>;
+    type Trace = VmTrace;
+    type TapeStorage = ();
+
+    fn eval<F: Into<Affine>>(
+        &mut self,
+        tape: &Self::Tape,
+        x: F,
+        y: F,
+        z: F,
+        vars: &[f32],
+    ) -> Result<(Affine, Option<&VmTrace>), Error> {
+        let x = Affine::input(x.into().interval(), 0);
+        let y = Affine::input(y.into().interval(), 1);
+        let z = Affine::input(z.into().interval(), 2);
+        let tape = tape.0.as_ref();
+        self.check_arguments(vars, tape.var_count())?;
+        Ok(self.0.run::<Affine, _, N>(tape, [x, y, z], vars))
+    }
+}
+
+/// VM-based tracing evaluator for intervals with partial derivatives
+///
+/// Each input is converted to an interval with a unit derivative along its own
+/// axis; see [`IntervalGrad`] for details.
+#[derive(Default)]
+pub struct VmIntervalGradEval<const N: usize>(TracingVmEval<IntervalGrad>);
+impl<const N: usize> TracingEvaluator for VmIntervalGradEval<N> {
+    type Data = IntervalGrad;
+    type Tape = GenericVmShape<N>;
+    type Trace = VmTrace;
+    type TapeStorage = ();
+
+    fn eval<F: Into<IntervalGrad>>(
+        &mut self,
+        tape: &Self::Tape,
+        x: F,
+        y: F,
+        z: F,
+        vars: &[f32],
+    ) -> Result<(IntervalGrad, Option<&VmTrace>), Error> {
+        let (zero, one) = (Interval::from(0.0), Interval::from(1.0));
+        let x = IntervalGrad::new(x.into().v, one, zero, zero);
+        let y = IntervalGrad::new(y.into().v, zero, one, zero);
+        let z = IntervalGrad::new(z.into().v, zero, zero, one);
+        let tape = tape.0.as_ref();
+        self.check_arguments(vars, tape.var_count())?;
+        Ok(self.0.run::<IntervalGrad, _, N>(tape, [x, y, z], vars))
+    }
+}
+
+/// VM-based tracing evaluator for intervals, with outward rounding
+///
+/// Operations which round are widened by one ulp in each direction,
+/// immediates are converted to the tightest enclosing interval, and
+/// transcendental functions are evaluated in double precision before being
+/// rounded outward.  The result is guaranteed to contain the exact value of
+/// the expression over the input intervals.
+#[derive(Default)]
+pub struct VmRobustIntervalEval<const N: usize>(TracingVmEval<Interval>);
+impl<const N: usize> TracingEvaluator for VmRobustIntervalEval<N> {
+    type Data = Interval;
+    type Tape = GenericVmShape<N>;
+    type Trace = VmTrace;
+    type TapeStorage = ();
+
+    fn eval<F: Into<Interval>>(
+        &mut self,
+        tape: &Self::Tape,
+        x: F,
+        y: F,
+        z: F,
+        vars: &[f32],
+    ) -> Result<(Interval, Option<&VmTrace>), Error> {
+        let x = x.into();
+        let y = y.into();
+        let z = z.into();
+        let tape = tape.0.as_ref();
+        self.check_arguments(vars, tape.var_count())?;
+        Ok(self.0.run::<RobustInterval, _, N>(tape, [x, y, z], vars))
+    }
+}
+
+/// VM-based tracing evaluator for double-precision intervals
+#[derive(Default)]
+pub struct VmIntervalEval64<const N: usize>(TracingVmEval<Interval64>);
+impl<const N: usize> TracingEvaluator<f64> for VmIntervalEval64<N> {
+    type Data = Interval64;
+    type Tape = GenericVmShape<N>;
+    type Trace = VmTrace;
+    type TapeStorage = ();
+
+    fn eval<F: Into<Interval64>>(
+        &mut self,
+        tape: &Self::Tape,
+        x: F,
+        y: F,
+        z: F,
+        vars: &[f64],
+    ) -> Result<(Interval64, Option<&VmTrace>), Error> {
+        let x = x.into();
+        let y = y.into();
+        let z = z.into();
+        let tape = tape.0.as_ref();
+        self.check_arguments(vars, tape.var_count())?;
+        Ok(self.0.run::<Interval64, _, N>(tape, [x, y, z], vars))
     }
 }
 
@@ -415,196 +533,33 @@ impl<const N: usize> TracingEvaluator for VmPointEval<N> {
         let z = z.into();
         let tape = tape.0.as_ref();
         self.check_arguments(vars, tape.var_count())?;
-        self.0.resize_slots(tape);
+        Ok(self.0.run::<f32, _, N>(tape, [x, y, z], vars))
+    }
+}
 
-        let mut choices = self.0.choices.as_mut_slice().iter_mut();
-        let mut simplify = false;
-        let mut v = SlotArray(&mut self.0.slots);
-        for op in tape.iter_asm() {
-            match op {
-                RegOp::Input(out, i) => {
-                    v[out] = match i {
-                        0 => x,
-                        1 => y,
-                        2 => z,
-                        _ => panic!("Invalid input: {}", i),
-                    }
-                }
-                RegOp::Var(out, i) => v[out] = vars[i as usize],
-                RegOp::NegReg(out, arg) => {
-                    v[out] = -v[arg];
-                }
-                RegOp::AbsReg(out, arg) => {
-                    v[out] = v[arg].abs();
-                }
-                RegOp::RecipReg(out, arg) => {
-                    v[out] = 1.0 / v[arg];
-                }
-                RegOp::SqrtReg(out, arg) => {
-                    v[out] = v[arg].sqrt();
-                }
-                RegOp::SquareReg(out, arg) => {
-                    let s = v[arg];
-                    v[out] = s * s;
-                }
-                RegOp::SinReg(out, arg) => {
-                    v[out] = v[arg].sin();
-                }
-                RegOp::CosReg(out, arg) => {
-                    v[out] = v[arg].cos();
-                }
-                RegOp::TanReg(out, arg) => {
-                    v[out] = v[arg].tan();
-                }
-                RegOp::AsinReg(out, arg) => {
-                    v[out] = v[arg].asin();
-                }
-                RegOp::AcosReg(out, arg) => {
-                    v[out] = v[arg].acos();
-                }
-                RegOp::AtanReg(out, arg) => {
-                    v[out] = v[arg].atan();
-                }
-                RegOp::ExpReg(out, arg) => {
-                    v[out] = v[arg].exp();
-                }
-                RegOp::LnReg(out, arg) => {
-                    v[out] = v[arg].ln();
-                }
-                RegOp::CopyReg(out, arg) => {
-                    v[out] = v[arg];
-                }
-                RegOp::AddRegImm(out, arg, imm) => {
-                    v[out] = v[arg] + imm;
-                }
-                RegOp::MulRegImm(out, arg, imm) => {
-                    v[out] = v[arg] * imm;
-                }
-                RegOp::DivRegImm(out, arg, imm) => {
-                    v[out] = v[arg] / imm;
-                }
-                RegOp::DivImmReg(out, arg, imm) => {
-                    v[out] = imm / v[arg];
-                }
-                RegOp::SubImmReg(out, arg, imm) => {
-                    v[out] = imm - v[arg];
-                }
-                RegOp::SubRegImm(out, arg, imm) => {
-                    v[out] = v[arg] - imm;
-                }
-                RegOp::MinRegImm(out, arg, imm) => {
-                    let a = v[arg];
-                    let (choice, value) = if a < imm {
-                        (Choice::Left, a)
-                    } else if imm < a {
-                        (Choice::Right, imm)
-                    } else {
-                        (
-                            Choice::Both,
-                            if a.is_nan() || imm.is_nan() {
-                                f32::NAN
-                            } else {
-                                imm
-                            },
-                        )
-                    };
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::MaxRegImm(out, arg, imm) => {
-                    let a = v[arg];
-                    let (choice, value) = if a > imm {
-                        (Choice::Left, a)
-                    } else if imm > a {
-                        (Choice::Right, imm)
-                    } else {
-                        (
-                            Choice::Both,
-                            if a.is_nan() || imm.is_nan() {
-                                f32::NAN
-                            } else {
-                                imm
-                            },
-                        )
-                    };
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::AddRegReg(out, lhs, rhs) => {
-                    v[out] = v[lhs] + v[rhs];
-                }
-                RegOp::MulRegReg(out, lhs, rhs) => {
-                    v[out] = v[lhs] * v[rhs];
-                }
-                RegOp::DivRegReg(out, lhs, rhs) => {
-                    v[out] = v[lhs] / v[rhs];
-                }
-                RegOp::SubRegReg(out, lhs, rhs) => {
-                    v[out] = v[lhs] - v[rhs];
-                }
-                RegOp::MinRegReg(out, lhs, rhs) => {
-                    let a = v[lhs];
-                    let b = v[rhs];
-                    let (choice, value) = if a < b {
-                        (Choice::Left, a)
-                    } else if b < a {
-                        (Choice::Right, b)
-                    } else {
-                        (
-                            Choice::Both,
-                            if a.is_nan() || b.is_nan() {
-                                f32::NAN
-                            } else {
-                                b
-                            },
-                        )
-                    };
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::MaxRegReg(out, lhs, rhs) => {
-                    let a = v[lhs];
-                    let b = v[rhs];
-                    let (choice, value) = if a > b {
-                        (Choice::Left, a)
-                    } else if b > a {
-                        (Choice::Right, b)
-                    } else {
-                        (
-                            Choice::Both,
-                            if a.is_nan() || b.is_nan() {
-                                f32::NAN
-                            } else {
-                                b
-                            },
-                        )
-                    };
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::CopyImm(out, imm) => {
-                    v[out] = imm;
-                }
-                RegOp::Load(out, mem) => {
-                    v[out] = v[mem];
-                }
-                RegOp::Store(out, mem) => {
-                    v[mem] = v[out];
-                }
-            }
-        }
-        Ok((
-            self.0.slots[0],
-            if simplify {
-                Some(&self.0.choices)
-            } else {
-                None
-            },
-        ))
+/// VM-based tracing evaluator for double-precision single points
+#[derive(Default)]
+pub struct VmPointEval64<const N: usize>(TracingVmEval<f64>);
+impl<const N: usize> TracingEvaluator<f64> for VmPointEval64<N> {
+    type Data = f64;
+    type Tape = GenericVmShape<N>;
+    type Trace = VmTrace;
+    type TapeStorage = ();
+
+    fn eval<F: Into<f64>>(
+        &mut self,
+        tape: &Self::Tape,
+        x: F,
+        y: F,
+        z: F,
+        vars: &[f64],
+    ) -> Result<(f64, Option<&VmTrace>), Error> {
+        let x = x.into();
+        let y = y.into();
+        let z = z.into();
+        let tape = tape.0.as_ref();
+        self.check_arguments(vars, tape.var_count())?;
+        Ok(self.0.run::<f64, _, N>(tape, [x, y, z], vars))
     }
 }
 
@@ -617,7 +572,7 @@ struct BulkVmEval<T> {
     slots: Vec<Vec<T>>,
 }
 
-impl<T: From<f32> + Clone> BulkVmEval<T> {
+impl<T: From<f32> + Copy> BulkVmEval<T> {
     /// Reserves slots for the given tape and slice size
     fn resize_slots<const N: usize>(&mut self, tape: &VmData<N>, size: usize) {
         self.slots
@@ -626,6 +581,26 @@ impl<T: From<f32> + Clone> BulkVmEval<T> {
             s.resize(size, f32::NAN.into());
         }
     }
+
+    /// Evaluates the tape on `size` points, using `O` for arithmetic
+    ///
+    /// `input(i, out)` fills `out` with values for input `i` (X, Y, or Z), and
+    /// `var(i)` returns the value of variable `i`.
+    fn run<O: BulkOps<Data = T>, const N: usize>(
+        &mut self,
+        tape: &VmData<N>,
+        size: usize,
+        input: impl Fn(u8, &mut [T]),
+        var: impl Fn(u32) -> T,
+    ) -> BulkOutput<'_, T> {
+        self.resize_slots(tape, size);
+
+        let mut v = SlotArray(&mut self.slots);
+        for op in tape.iter_asm() {
+            bulk_op::<O>(op, &mut v, size, &input, &var);
+        }
+        BulkOutput::new(&self.slots[0..tape.output_count()], size)
+    }
 }
 
 /// VM-based bulk evaluator for arrays of points, yielding point values
@@ -643,205 +618,51 @@ impl<const N: usize> BulkEvaluator for VmFloatSliceEval<N> {
         ys: &[f32],
         zs: &[f32],
         vars: &[f32],
-    ) -> Result<&[f32], Error> {
+    ) -> Result<BulkOutput<'_, f32>, Error> {
         let tape = tape.0.as_ref();
         self.check_arguments(xs, ys, zs, vars, tape.var_count())?;
-        self.0.resize_slots(tape, xs.len());
-        assert_eq!(xs.len(), ys.len());
-        assert_eq!(ys.len(), zs.len());
-        assert_eq!(vars.len(), tape.var_count());
+        let input = |i, out: &mut [_]| {
+            out.copy_from_slice(match i {
+                0 => xs,
+                1 => ys,
+                2 => zs,
+                _ => panic!("Invalid input: {}", i),
+            })
+        };
+        let var = |i: u32| vars[i as usize];
+        Ok(self.0.run::<f32, N>(tape, xs.len(), input, var))
+    }
+}
 
-        let size = xs.len();
+/// VM-based bulk evaluator for arrays of points, yielding double-precision
+/// point values
+#[derive(Default)]
+pub struct VmFloatSliceEval64<const N: usize>(BulkVmEval<f64>);
+impl<const N: usize> BulkEvaluator<f64> for VmFloatSliceEval64<N> {
+    type Data = f64;
+    type Tape = GenericVmShape<N>;
+    type TapeStorage = ();
 
-        let mut v = SlotArray(&mut self.0.slots);
-        for op in tape.iter_asm() {
-            match op {
-                RegOp::Input(out, i) => {
-                    v[out][0..size].copy_from_slice(match i {
-                        0 => xs,
-                        1 => ys,
-                        2 => zs,
-                        _ => panic!("Invalid input: {}", i),
-                    })
-                }
-                RegOp::Var(out, i) => v[out][0..size].fill(vars[i as usize]),
-                RegOp::NegReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = -v[arg][i];
-                    }
-                }
-                RegOp::AbsReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].abs();
-                    }
-                }
-                RegOp::RecipReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = 1.0 / v[arg][i];
-                    }
-                }
-                RegOp::SqrtReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].sqrt();
-                    }
-                }
-                RegOp::SquareReg(out, arg) => {
-                    for i in 0..size {
-                        let s = v[arg][i];
-                        v[out][i] = s * s;
-                    }
-                }
-                RegOp::SinReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].sin();
-                    }
-                }
-                RegOp::CosReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].cos();
-                    }
-                }
-                RegOp::TanReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].tan();
-                    }
-                }
-                RegOp::AsinReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].asin();
-                    }
-                }
-                RegOp::AcosReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].acos();
-                    }
-                }
-                RegOp::AtanReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].atan();
-                    }
-                }
-                RegOp::ExpReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].exp();
-                    }
-                }
-                RegOp::LnReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].ln();
-                    }
-                }
-                RegOp::CopyReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i];
-                    }
-                }
-                RegOp::AddRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i] + imm;
-                    }
-                }
-                RegOp::MulRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i] * imm;
-                    }
-                }
-                RegOp::DivRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i] / imm;
-                    }
-                }
-                RegOp::DivImmReg(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = imm / v[arg][i];
-                    }
-                }
-                RegOp::SubImmReg(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = imm - v[arg][i];
-                    }
-                }
-                RegOp::SubRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i] - imm;
-                    }
-                }
-                RegOp::MinRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = if v[arg][i].is_nan() || imm.is_nan() {
-                            f32::NAN
-                        } else {
-                            v[arg][i].min(imm)
-                        };
-                    }
-                }
-                RegOp::MaxRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = if v[arg][i].is_nan() || imm.is_nan() {
-                            f32::NAN
-                        } else {
-                            v[arg][i].max(imm)
-                        };
-                    }
-                }
-                RegOp::AddRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i] + v[rhs][i];
-                    }
-                }
-                RegOp::MulRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i] * v[rhs][i];
-                    }
-                }
-                RegOp::DivRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i] / v[rhs][i];
-                    }
-                }
-                RegOp::SubRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i] - v[rhs][i];
-                    }
-                }
-                RegOp::MinRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = if v[lhs][i].is_nan() || v[rhs][i].is_nan()
-                        {
-                            f32::NAN
-                        } else {
-                            v[lhs][i].min(v[rhs][i])
-                        };
-                    }
-                }
-                RegOp::MaxRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = if v[lhs][i].is_nan() || v[rhs][i].is_nan()
-                        {
-                            f32::NAN
-                        } else {
-                            v[lhs][i].max(v[rhs][i])
-                        };
-                    }
-                }
-                RegOp::CopyImm(out, imm) => {
-                    for i in 0..size {
-                        v[out][i] = imm;
-                    }
-                }
-                RegOp::Load(out, mem) => {
-                    for i in 0..size {
-                        v[out][i] = v[mem][i];
-                    }
-                }
-                RegOp::Store(out, mem) => {
-                    for i in 0..size {
-                        v[mem][i] = v[out][i];
-                    }
-                }
-            }
-        }
-        Ok(&self.0.slots[0])
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        xs: &[f64],
+        ys: &[f64],
+        zs: &[f64],
+        vars: &[f64],
+    ) -> Result<BulkOutput<'_, f64>, Error> {
+        let tape = tape.0.as_ref();
+        self.check_arguments(xs, ys, zs, vars, tape.var_count())?;
+        let input = |i, out: &mut [_]| {
+            out.copy_from_slice(match i {
+                0 => xs,
+                1 => ys,
+                2 => zs,
+                _ => panic!("Invalid input: {}", i),
+            })
+        };
+        let var = |i: u32| vars[i as usize];
+        Ok(self.0.run::<f64, N>(tape, xs.len(), input, var))
     }
 }
 
@@ -860,229 +681,294 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
         ys: &[f32],
         zs: &[f32],
         vars: &[f32],
-    ) -> Result<&[Grad], Error> {
+    ) -> Result<BulkOutput<'_, Grad>, Error> {
+        let tape = tape.0.as_ref();
+        self.check_arguments(xs, ys, zs, vars, tape.var_count())?;
+        let input = |i, out: &mut [_]| {
+            let vs = match i {
+                0 => xs,
+                1 => ys,
+                2 => zs,
+                _ => panic!("Invalid input: {}", i),
+            };
+            let mut d = [0.0; 3];
+            d[i as usize] = 1.0;
+            for (o, v) in out.iter_mut().zip(vs) {
+                *o = Grad::new(*v, d[0], d[1], d[2]);
+            }
+        };
+        let var = |i: u32| Grad::new(vars[i as usize], 0.0, 0.0, 0.0);
+        Ok(self.0.run::<Grad, N>(tape, xs.len(), input, var))
+    }
+}
+
+/// VM-based bulk evaluator for arrays of points, yielding values with first
+/// and second derivatives
+#[derive(Default)]
+pub struct VmHessianSliceEval<const N: usize>(BulkVmEval<Hessian>);
+impl<const N: usize> BulkEvaluator for VmHessianSliceEval<N> {
+    type Data = Hessian;
+    type Tape = GenericVmShape<N>;
+    type TapeStorage = ();
+
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        xs: &[f32],
+        ys: &[f32],
+        zs: &[f32],
+        vars: &[f32],
+    ) -> Result<BulkOutput<'_, Hessian>, Error> {
         let tape = tape.0.as_ref();
         self.check_arguments(xs, ys, zs, vars, tape.var_count())?;
-        self.0.resize_slots(tape, xs.len());
-        assert_eq!(xs.len(), ys.len());
-        assert_eq!(ys.len(), zs.len());
-        assert_eq!(vars.len(), tape.var_count());
+        let input = |i, out: &mut [_]| {
+            let vs = match i {
+                0 => xs,
+                1 => ys,
+                2 => zs,
+                _ => panic!("Invalid input: {}", i),
+            };
+            let mut d = [0.0; 3];
+            d[i as usize] = 1.0;
+            for (o, v) in out.iter_mut().zip(vs) {
+                *o = Hessian::new(*v, d, [[0.0; 3]; 3]);
+            }
+        };
+        let var = |i: u32| vars[i as usize].into();
+        Ok(self.0.run::<Hessian, N>(tape, xs.len(), input, var))
+    }
+}
 
-        let size = xs.len();
-        let mut v = SlotArray(&mut self.0.slots);
-        for op in tape.iter_asm() {
-            match op {
-                RegOp::Input(out, j) => {
-                    for i in 0..size {
-                        v[out][i] = match j {
-                            0 => Grad::new(xs[i], 1.0, 0.0, 0.0),
-                            1 => Grad::new(ys[i], 0.0, 1.0, 0.0),
-                            2 => Grad::new(zs[i], 0.0, 0.0, 1.0),
-                            _ => panic!("Invalid input: {}", i),
-                        }
-                    }
-                }
-                RegOp::Var(out, j) => {
-                    // TODO: error handling?
-                    v[out][0..size].fill(Grad::new(
-                        vars[j as usize],
-                        0.0,
-                        0.0,
-                        0.0,
-                    ));
-                }
-                RegOp::NegReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = -v[arg][i];
-                    }
-                }
-                RegOp::AbsReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].abs();
-                    }
-                }
-                RegOp::RecipReg(out, arg) => {
-                    let one: Grad = 1.0.into();
-                    for i in 0..size {
-                        v[out][i] = one / v[arg][i];
-                    }
-                }
-                RegOp::SqrtReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].sqrt();
-                    }
-                }
-                RegOp::SquareReg(out, arg) => {
-                    for i in 0..size {
-                        let s = v[arg][i];
-                        v[out][i] = s * s;
-                    }
-                }
-                RegOp::SinReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].sin();
-                    }
-                }
-                RegOp::CosReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].cos();
-                    }
-                }
-                RegOp::TanReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].tan();
-                    }
-                }
-                RegOp::AsinReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].asin();
-                    }
-                }
-                RegOp::AcosReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].acos();
-                    }
-                }
-                RegOp::AtanReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].atan();
-                    }
-                }
-                RegOp::ExpReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].exp();
-                    }
-                }
-                RegOp::LnReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].ln();
-                    }
-                }
-                RegOp::CopyReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i];
-                    }
-                }
-                RegOp::AddRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i] + imm.into();
-                    }
-                }
-                RegOp::MulRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i] * imm.into();
-                    }
-                }
-                RegOp::DivRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i] / imm.into();
-                    }
-                }
-                RegOp::DivImmReg(out, arg, imm) => {
-                    let imm: Grad = imm.into();
-                    for i in 0..size {
-                        v[out][i] = imm / v[arg][i];
-                    }
-                }
-                RegOp::SubImmReg(out, arg, imm) => {
-                    let imm: Grad = imm.into();
-                    for i in 0..size {
-                        v[out][i] = imm - v[arg][i];
-                    }
-                }
-                RegOp::SubRegImm(out, arg, imm) => {
-                    let imm: Grad = imm.into();
-                    for i in 0..size {
-                        v[out][i] = v[arg][i] - imm;
-                    }
-                }
-                RegOp::MinRegImm(out, arg, imm) => {
-                    let imm: Grad = imm.into();
-                    for i in 0..size {
-                        v[out][i] = if v[arg][i].v.is_nan() || imm.v.is_nan() {
-                            f32::NAN.into()
-                        } else {
-                            v[arg][i].min(imm)
-                        };
-                    }
-                }
-                RegOp::MaxRegImm(out, arg, imm) => {
-                    let imm: Grad = imm.into();
-                    for i in 0..size {
-                        v[out][i] = if v[arg][i].v.is_nan() || imm.v.is_nan() {
-                            f32::NAN.into()
-                        } else {
-                            v[arg][i].max(imm)
-                        };
-                    }
-                }
-                RegOp::AddRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i] + v[rhs][i];
-                    }
-                }
-                RegOp::MulRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i] * v[rhs][i];
-                    }
-                }
-                RegOp::DivRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i] / v[rhs][i];
-                    }
-                }
-                RegOp::SubRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i] - v[rhs][i];
-                    }
-                }
-                RegOp::MinRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] =
-                            if v[lhs][i].v.is_nan() || v[rhs][i].v.is_nan() {
-                                f32::NAN.into()
-                            } else {
-                                v[lhs][i].min(v[rhs][i])
-                            };
-                    }
-                }
-                RegOp::MaxRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] =
-                            if v[lhs][i].v.is_nan() || v[rhs][i].v.is_nan() {
-                                f32::NAN.into()
-                            } else {
-                                v[lhs][i].max(v[rhs][i])
-                            };
-                    }
-                }
-                RegOp::CopyImm(out, imm) => {
-                    let imm: Grad = imm.into();
-                    for i in 0..size {
-                        v[out][i] = imm;
-                    }
-                }
-                RegOp::Load(out, mem) => {
-                    for i in 0..size {
-                        v[out][i] = v[mem][i];
-                    }
-                }
-                RegOp::Store(out, mem) => {
-                    for i in 0..size {
-                        v[mem][i] = v[out][i];
-                    }
-                }
+/// Tape for multi-target gradient evaluation with the VM
+///
+/// This is a [`GenericVmShape`] bundled with the derivative seeds for each
+/// input and variable.
+#[derive(Clone)]
+pub struct VmMultiGradTape<const N: usize> {
+    shape: GenericVmShape<N>,
+    seeds: GradSeeds,
+}
+
+impl<const N: usize> Tape for VmMultiGradTape<N> {
+    type Storage = ();
+    fn recycle(self) -> Self::Storage {
+        // nothing to do here
+    }
+}
+
+impl<const N: usize> GenericVmShape<N> {
+    pub(crate) fn multi_grad_tape(
+        &self,
+        targets: &[GradTarget],
+    ) -> Result<VmMultiGradTape<N>, Error> {
+        let seeds = GradSeeds::new(targets, self.0.vars())?;
+        Ok(VmMultiGradTape {
+            shape: self.clone(),
+            seeds,
+        })
+    }
+}
+
+/// VM-based bulk evaluator for arrays of points, yielding gradient values with
+/// respect to configurable targets
+#[derive(Default)]
+pub struct VmMultiGradSliceEval<const N: usize>(BulkVmEval<MultiGrad>);
+impl<const N: usize> BulkEvaluator for VmMultiGradSliceEval<N> {
+    type Data = MultiGrad;
+    type Tape = VmMultiGradTape<N>;
+    type TapeStorage = ();
+
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        xs: &[f32],
+        ys: &[f32],
+        zs: &[f32],
+        vars: &[f32],
+    ) -> Result<BulkOutput<'_, MultiGrad>, Error> {
+        let seeds = &tape.seeds;
+        let tape = tape.shape.0.as_ref();
+        self.check_arguments(xs, ys, zs, vars, tape.var_count())?;
+        let input = |i, out: &mut [_]| {
+            let vs = match i {
+                0 => xs,
+                1 => ys,
+                2 => zs,
+                _ => panic!("Invalid input: {}", i),
+            };
+            let d = *seeds.input(i);
+            for (o, v) in out.iter_mut().zip(vs) {
+                *o = MultiGrad::new(*v, d);
             }
-        }
-        Ok(&self.0.slots[0])
+        };
+        let var = |i: u32| MultiGrad::new(vars[i as usize], *seeds.var(i));
+        Ok(self.0.run::<MultiGrad, N>(tape, xs.len(), input, var))
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::eval::EzShape;
+
+    #[test]
+    fn test_multi_output() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let sum = ctx.add(x, y).unwrap();
+        let scaled = ctx.mul(sum, 2.0).unwrap();
+        let one = ctx.constant(1.0);
+
+        let shape =
+            VmShape::new_multi(&ctx, &[sum, scaled, x, one, x]).unwrap();
+        assert_eq!(shape.output_count(), 5);
+
+        let mut eval = VmShape::new_float_slice_eval();
+        let tape = shape.ez_float_slice_tape();
+        let out = eval
+            .eval(&tape, &[1.0, 2.0], &[3.0, 5.0], &[0.0, 0.0], &[])
+            .unwrap();
+        assert_eq!(out.len(), 5);
+        assert_eq!(out[0], [4.0, 7.0]);
+        assert_eq!(out[1], [8.0, 14.0]);
+        assert_eq!(out[2], [1.0, 2.0]);
+        assert_eq!(out[3], [1.0, 1.0]);
+        assert_eq!(out[4], [1.0, 2.0]);
+
+        let mut eval = VmShape::new_grad_slice_eval();
+        let tape = shape.ez_grad_slice_tape();
+        let out = eval.eval(&tape, &[1.0], &[3.0], &[0.0], &[]).unwrap();
+        assert_eq!(out[0], [Grad::new(4.0, 1.0, 1.0, 0.0)]);
+        assert_eq!(out[1], [Grad::new(8.0, 2.0, 2.0, 0.0)]);
+        assert_eq!(out[2], [Grad::new(1.0, 1.0, 0.0, 0.0)]);
+        assert_eq!(out[3], [Grad::new(1.0, 0.0, 0.0, 0.0)]);
+
+        // Tracing evaluators return the first output
+        let mut eval = VmShape::new_point_eval();
+        let tape = shape.ez_point_tape();
+        let (v, _) = eval.eval(&tape, 1.0, 3.0, 0.0, &[]).unwrap();
+        assert_eq!(v, 4.0);
+    }
+
+    #[test]
+    fn test_multi_output_simplify() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let y2 = ctx.square(y).unwrap();
+        let a = ctx.add(y2, 2.0).unwrap();
+        let min = ctx.min(x, a).unwrap();
+        let b = ctx.sub(a, x).unwrap();
+
+        // The right branch of the `min` is pruned, but `a` is still used by
+        // the second output, so it must remain in the tape.
+        let shape = VmShape::new_multi(&ctx, &[min, b]).unwrap();
+        let mut eval = VmShape::new_interval_eval();
+        let tape = shape.ez_interval_tape();
+        let (i, trace) = eval
+            .eval(&tape, [0.0, 1.0], [-1.0, 1.0], [0.0, 0.0], &[])
+            .unwrap();
+        assert_eq!(i, [0.0, 1.0].into());
+        let trace = trace.unwrap();
+        assert_eq!(trace.as_slice(), [Choice::Left]);
+
+        let next = shape.ez_simplify(trace).unwrap();
+        assert_eq!(next.output_count(), 2);
+        assert_eq!(next.choice_count(), 0);
+
+        let mut eval = VmShape::new_float_slice_eval();
+        let tape = next.ez_float_slice_tape();
+        let out = eval
+            .eval(&tape, &[0.5, 1.0], &[1.0, -0.5], &[0.0, 0.0], &[])
+            .unwrap();
+        assert_eq!(out[0], [0.5, 1.0]);
+        assert_eq!(out[1], [2.5, 1.25]);
+
+        // Pruning both branches of a choice, one per output
+        let max = ctx.max(x, a).unwrap();
+        let shape = VmShape::new_multi(&ctx, &[min, max]).unwrap();
+        let mut eval = VmShape::new_interval_eval();
+        let tape = shape.ez_interval_tape();
+        let (_, trace) = eval
+            .eval(&tape, [0.0, 1.0], [-1.0, 1.0], [0.0, 0.0], &[])
+            .unwrap();
+        let next = shape.ez_simplify(trace.unwrap()).unwrap();
+        assert_eq!(next.choice_count(), 0);
+
+        let mut eval = VmShape::new_float_slice_eval();
+        let tape = next.ez_float_slice_tape();
+        let out = eval.eval(&tape, &[0.5], &[1.0], &[0.0], &[]).unwrap();
+        assert_eq!(out[0], [0.5]);
+        assert_eq!(out[1], [3.0]);
+    }
+
+    #[test]
+    fn test_multi_output_spill() {
+        // Use a tiny register count, so that outputs are spilled to memory
+        // while the rest of the tape is evaluated
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let mut nodes = vec![];
+        let mut acc = x;
+        for i in 0..8 {
+            let v = [x, y, z][i % 3];
+            let t = ctx.mul(v, (i + 1) as f64).unwrap();
+            acc = ctx.add(acc, t).unwrap();
+            let s = ctx.sin(acc).unwrap();
+            acc = ctx.add(acc, s).unwrap();
+            if i % 3 == 0 {
+                nodes.push(acc);
+            }
+        }
+        nodes.reverse();
+
+        let xs = [0.1, 0.2, -0.3, 1.5];
+        let ys = [0.5, -1.0, 0.25, 2.0];
+        let zs = [1.0, 0.0, -0.75, -2.0];
+
+        let mut eval = VmShape::new_float_slice_eval();
+        let shape = VmShape::new_multi(&ctx, &nodes).unwrap();
+        let tape = shape.ez_float_slice_tape();
+        let expected: Vec<Vec<f32>> = eval
+            .eval(&tape, &xs, &ys, &zs, &[])
+            .unwrap()
+            .iter()
+            .map(|o| o.to_vec())
+            .collect();
+        assert_eq!(expected.len(), 3);
+        for (i, e) in expected.iter().enumerate() {
+            for (j, v) in e.iter().enumerate() {
+                let (x, y, z) = (xs[j] as f64, ys[j] as f64, zs[j] as f64);
+                let q = ctx.eval_xyz(nodes[i], x, y, z).unwrap();
+                assert!((*v as f64 - q).abs() < 1e-5);
+            }
+        }
+
+        let mut eval = GenericVmShape::<3>::new_float_slice_eval();
+        let shape = GenericVmShape::<3>::new_multi(&ctx, &nodes).unwrap();
+        let tape = shape.ez_float_slice_tape();
+        let out = eval.eval(&tape, &xs, &ys, &zs, &[]).unwrap();
+        for (i, e) in expected.iter().enumerate() {
+            assert_eq!(out[i], *e);
+        }
+
+        assert!(matches!(
+            GenericVmShape::<2>::new_multi(&ctx, &nodes),
+            Err(Error::TooManyOutputs(3, 2))
+        ));
+    }
+
     crate::grad_slice_tests!(VmShape);
+    crate::hessian_slice_tests!(VmShape);
+    crate::multi_grad_slice_tests!(VmShape);
     crate::interval_tests!(VmShape);
+    crate::robust_interval_tests!(VmShape);
+    crate::affine_tests!(VmShape);
+    crate::interval_grad_tests!(VmShape);
     crate::float_slice_tests!(VmShape);
     crate::point_tests!(VmShape);
+    crate::f64_tests!(VmShape);
 }
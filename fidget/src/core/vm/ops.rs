@@ -0,0 +1,826 @@
+//! Per-operation arithmetic shared by the VM evaluators
+//!
+//! Each evaluator describes how its data type implements the individual
+//! operations (through [`VmOps`] and either [`TracingOps`] or [`BulkOps`]);
+//! [`tracing_op`] and [`bulk_op`] then dispatch every [`RegOp`] to those
+//! functions, so there's a single `match` over the opcodes for each style of
+//! evaluation.
+use super::{compare_f32, compare_f64, Choice, SlotArray};
+use crate::{
+    compiler::RegOp,
+    eval::types::{
+        Affine, Grad, Hessian, Interval, Interval64, IntervalGrad, MultiGrad,
+    },
+};
+
+/// Arithmetic on the values stored in VM registers
+pub(crate) trait VmOps {
+    /// Type stored in each register
+    type Data: Copy;
+
+    /// Converts an immediate from the tape
+    fn imm(imm: f64) -> Self::Data;
+
+    fn neg(a: Self::Data) -> Self::Data;
+    fn abs(a: Self::Data) -> Self::Data;
+    fn recip(a: Self::Data) -> Self::Data;
+    fn sqrt(a: Self::Data) -> Self::Data;
+    fn square(a: Self::Data) -> Self::Data;
+    fn floor(a: Self::Data) -> Self::Data;
+    fn ceil(a: Self::Data) -> Self::Data;
+    fn round(a: Self::Data) -> Self::Data;
+    fn not(a: Self::Data) -> Self::Data;
+    fn sin(a: Self::Data) -> Self::Data;
+    fn cos(a: Self::Data) -> Self::Data;
+    fn tan(a: Self::Data) -> Self::Data;
+    fn asin(a: Self::Data) -> Self::Data;
+    fn acos(a: Self::Data) -> Self::Data;
+    fn atan(a: Self::Data) -> Self::Data;
+    fn exp(a: Self::Data) -> Self::Data;
+    fn ln(a: Self::Data) -> Self::Data;
+
+    fn add(a: Self::Data, b: Self::Data) -> Self::Data;
+    fn sub(a: Self::Data, b: Self::Data) -> Self::Data;
+    fn mul(a: Self::Data, b: Self::Data) -> Self::Data;
+    fn div(a: Self::Data, b: Self::Data) -> Self::Data;
+    fn atan2(a: Self::Data, b: Self::Data) -> Self::Data;
+    fn rem_euclid(a: Self::Data, b: Self::Data) -> Self::Data;
+    fn compare(a: Self::Data, b: Self::Data) -> Self::Data;
+
+    /// Computes `atan2(a, imm)`
+    ///
+    /// By default, this converts the immediate with [`VmOps::imm`]; it may be
+    /// overloaded by types which evaluate `atan2` at a different precision.
+    fn atan2_imm(a: Self::Data, imm: f64) -> Self::Data {
+        Self::atan2(a, Self::imm(imm))
+    }
+    /// Computes `atan2(imm, b)`
+    fn imm_atan2(imm: f64, b: Self::Data) -> Self::Data {
+        Self::atan2(Self::imm(imm), b)
+    }
+    /// Computes `a.rem_euclid(imm)`
+    fn rem_euclid_imm(a: Self::Data, imm: f64) -> Self::Data {
+        Self::rem_euclid(a, Self::imm(imm))
+    }
+    /// Computes `imm.rem_euclid(b)`
+    fn imm_rem_euclid(imm: f64, b: Self::Data) -> Self::Data {
+        Self::rem_euclid(Self::imm(imm), b)
+    }
+}
+
+/// Selection operations which record a [`Choice`] for tracing evaluation
+pub(crate) trait TracingOps: VmOps {
+    fn min_choice(a: Self::Data, b: Self::Data) -> (Self::Data, Choice);
+    fn max_choice(a: Self::Data, b: Self::Data) -> (Self::Data, Choice);
+    fn and_choice(a: Self::Data, b: Self::Data) -> (Self::Data, Choice);
+    fn or_choice(a: Self::Data, b: Self::Data) -> (Self::Data, Choice);
+}
+
+/// Selection operations for bulk evaluation, which doesn't record choices
+pub(crate) trait BulkOps: VmOps {
+    fn min(a: Self::Data, b: Self::Data) -> Self::Data;
+    fn max(a: Self::Data, b: Self::Data) -> Self::Data;
+    fn and(a: Self::Data, b: Self::Data) -> Self::Data;
+    fn or(a: Self::Data, b: Self::Data) -> Self::Data;
+}
+
+/// Evaluates a single operation for a tracing evaluator
+///
+/// Returns the choice made by `min`, `max`, `and`, and `or` operations, or
+/// `None` for every other operation.
+#[inline(always)]
+pub(crate) fn tracing_op<O: TracingOps, V: Copy + Into<O::Data>>(
+    op: RegOp,
+    v: &mut SlotArray<'_, O::Data>,
+    [x, y, z]: [O::Data; 3],
+    vars: &[V],
+) -> Option<Choice> {
+    let mut choice = None;
+    match op {
+        RegOp::Input(out, i) => {
+            v[out] = match i {
+                0 => x,
+                1 => y,
+                2 => z,
+                _ => panic!("Invalid input: {}", i),
+            }
+        }
+        RegOp::Var(out, i) => v[out] = vars[i as usize].into(),
+        RegOp::NegReg(out, arg) => v[out] = O::neg(v[arg]),
+        RegOp::AbsReg(out, arg) => v[out] = O::abs(v[arg]),
+        RegOp::RecipReg(out, arg) => v[out] = O::recip(v[arg]),
+        RegOp::SqrtReg(out, arg) => v[out] = O::sqrt(v[arg]),
+        RegOp::SquareReg(out, arg) => v[out] = O::square(v[arg]),
+        RegOp::FloorReg(out, arg) => v[out] = O::floor(v[arg]),
+        RegOp::CeilReg(out, arg) => v[out] = O::ceil(v[arg]),
+        RegOp::RoundReg(out, arg) => v[out] = O::round(v[arg]),
+        RegOp::NotReg(out, arg) => v[out] = O::not(v[arg]),
+        RegOp::SinReg(out, arg) => v[out] = O::sin(v[arg]),
+        RegOp::CosReg(out, arg) => v[out] = O::cos(v[arg]),
+        RegOp::TanReg(out, arg) => v[out] = O::tan(v[arg]),
+        RegOp::AsinReg(out, arg) => v[out] = O::asin(v[arg]),
+        RegOp::AcosReg(out, arg) => v[out] = O::acos(v[arg]),
+        RegOp::AtanReg(out, arg) => v[out] = O::atan(v[arg]),
+        RegOp::ExpReg(out, arg) => v[out] = O::exp(v[arg]),
+        RegOp::LnReg(out, arg) => v[out] = O::ln(v[arg]),
+        RegOp::CopyReg(out, arg) => v[out] = v[arg],
+        RegOp::AddRegImm(out, arg, imm) => {
+            v[out] = O::add(v[arg], O::imm(imm));
+        }
+        RegOp::MulRegImm(out, arg, imm) => {
+            v[out] = O::mul(v[arg], O::imm(imm));
+        }
+        RegOp::DivRegImm(out, arg, imm) => {
+            v[out] = O::div(v[arg], O::imm(imm));
+        }
+        RegOp::DivImmReg(out, arg, imm) => {
+            v[out] = O::div(O::imm(imm), v[arg]);
+        }
+        RegOp::SubImmReg(out, arg, imm) => {
+            v[out] = O::sub(O::imm(imm), v[arg]);
+        }
+        RegOp::SubRegImm(out, arg, imm) => {
+            v[out] = O::sub(v[arg], O::imm(imm));
+        }
+        RegOp::AtanRegImm(out, arg, imm) => v[out] = O::atan2_imm(v[arg], imm),
+        RegOp::AtanImmReg(out, arg, imm) => v[out] = O::imm_atan2(imm, v[arg]),
+        RegOp::ModRegImm(out, arg, imm) => {
+            v[out] = O::rem_euclid_imm(v[arg], imm);
+        }
+        RegOp::ModImmReg(out, arg, imm) => {
+            v[out] = O::imm_rem_euclid(imm, v[arg]);
+        }
+        RegOp::CompareRegImm(out, arg, imm) => {
+            v[out] = O::compare(v[arg], O::imm(imm));
+        }
+        RegOp::CompareImmReg(out, arg, imm) => {
+            v[out] = O::compare(O::imm(imm), v[arg]);
+        }
+        RegOp::MinRegImm(out, arg, imm) => {
+            let (value, c) = O::min_choice(v[arg], O::imm(imm));
+            v[out] = value;
+            choice = Some(c);
+        }
+        RegOp::MaxRegImm(out, arg, imm) => {
+            let (value, c) = O::max_choice(v[arg], O::imm(imm));
+            v[out] = value;
+            choice = Some(c);
+        }
+        RegOp::AndRegImm(out, arg, imm) => {
+            let (value, c) = O::and_choice(v[arg], O::imm(imm));
+            v[out] = value;
+            choice = Some(c);
+        }
+        RegOp::OrRegImm(out, arg, imm) => {
+            let (value, c) = O::or_choice(v[arg], O::imm(imm));
+            v[out] = value;
+            choice = Some(c);
+        }
+        RegOp::AddRegReg(out, lhs, rhs) => v[out] = O::add(v[lhs], v[rhs]),
+        RegOp::MulRegReg(out, lhs, rhs) => v[out] = O::mul(v[lhs], v[rhs]),
+        RegOp::DivRegReg(out, lhs, rhs) => v[out] = O::div(v[lhs], v[rhs]),
+        RegOp::SubRegReg(out, lhs, rhs) => v[out] = O::sub(v[lhs], v[rhs]),
+        RegOp::AtanRegReg(out, lhs, rhs) => v[out] = O::atan2(v[lhs], v[rhs]),
+        RegOp::ModRegReg(out, lhs, rhs) => {
+            v[out] = O::rem_euclid(v[lhs], v[rhs]);
+        }
+        RegOp::CompareRegReg(out, lhs, rhs) => {
+            v[out] = O::compare(v[lhs], v[rhs]);
+        }
+        RegOp::MinRegReg(out, lhs, rhs) => {
+            let (value, c) = O::min_choice(v[lhs], v[rhs]);
+            v[out] = value;
+            choice = Some(c);
+        }
+        RegOp::MaxRegReg(out, lhs, rhs) => {
+            let (value, c) = O::max_choice(v[lhs], v[rhs]);
+            v[out] = value;
+            choice = Some(c);
+        }
+        RegOp::AndRegReg(out, lhs, rhs) => {
+            let (value, c) = O::and_choice(v[lhs], v[rhs]);
+            v[out] = value;
+            choice = Some(c);
+        }
+        RegOp::OrRegReg(out, lhs, rhs) => {
+            let (value, c) = O::or_choice(v[lhs], v[rhs]);
+            v[out] = value;
+            choice = Some(c);
+        }
+        RegOp::CopyImm(out, imm) => v[out] = O::imm(imm),
+        RegOp::Load(out, mem) => v[out] = v[mem],
+        RegOp::Store(out, mem) => v[mem] = v[out],
+    }
+    choice
+}
+
+/// Evaluates a single operation for a bulk evaluator
+///
+/// Inputs and variables are written by the `input` and `var` callbacks,
+/// since their representation depends on the evaluator (e.g. derivative
+/// evaluators seed the partial derivatives of each input).
+#[inline(always)]
+pub(crate) fn bulk_op<O: BulkOps>(
+    op: RegOp,
+    v: &mut SlotArray<'_, Vec<O::Data>>,
+    size: usize,
+    input: &impl Fn(u8, &mut [O::Data]),
+    var: &impl Fn(u32) -> O::Data,
+) {
+    match op {
+        RegOp::Input(out, i) => input(i, &mut v[out][0..size]),
+        RegOp::Var(out, i) => v[out][0..size].fill(var(i)),
+        RegOp::NegReg(out, arg) => unary(v, out, arg, size, O::neg),
+        RegOp::AbsReg(out, arg) => unary(v, out, arg, size, O::abs),
+        RegOp::RecipReg(out, arg) => unary(v, out, arg, size, O::recip),
+        RegOp::SqrtReg(out, arg) => unary(v, out, arg, size, O::sqrt),
+        RegOp::SquareReg(out, arg) => unary(v, out, arg, size, O::square),
+        RegOp::FloorReg(out, arg) => unary(v, out, arg, size, O::floor),
+        RegOp::CeilReg(out, arg) => unary(v, out, arg, size, O::ceil),
+        RegOp::RoundReg(out, arg) => unary(v, out, arg, size, O::round),
+        RegOp::NotReg(out, arg) => unary(v, out, arg, size, O::not),
+        RegOp::SinReg(out, arg) => unary(v, out, arg, size, O::sin),
+        RegOp::CosReg(out, arg) => unary(v, out, arg, size, O::cos),
+        RegOp::TanReg(out, arg) => unary(v, out, arg, size, O::tan),
+        RegOp::AsinReg(out, arg) => unary(v, out, arg, size, O::asin),
+        RegOp::AcosReg(out, arg) => unary(v, out, arg, size, O::acos),
+        RegOp::AtanReg(out, arg) => unary(v, out, arg, size, O::atan),
+        RegOp::ExpReg(out, arg) => unary(v, out, arg, size, O::exp),
+        RegOp::LnReg(out, arg) => unary(v, out, arg, size, O::ln),
+        RegOp::CopyReg(out, arg) => unary(v, out, arg, size, |a| a),
+        RegOp::AddRegImm(out, arg, imm) => {
+            let imm = O::imm(imm);
+            unary(v, out, arg, size, |a| O::add(a, imm));
+        }
+        RegOp::MulRegImm(out, arg, imm) => {
+            let imm = O::imm(imm);
+            unary(v, out, arg, size, |a| O::mul(a, imm));
+        }
+        RegOp::DivRegImm(out, arg, imm) => {
+            let imm = O::imm(imm);
+            unary(v, out, arg, size, |a| O::div(a, imm));
+        }
+        RegOp::DivImmReg(out, arg, imm) => {
+            let imm = O::imm(imm);
+            unary(v, out, arg, size, |a| O::div(imm, a));
+        }
+        RegOp::SubImmReg(out, arg, imm) => {
+            let imm = O::imm(imm);
+            unary(v, out, arg, size, |a| O::sub(imm, a));
+        }
+        RegOp::SubRegImm(out, arg, imm) => {
+            let imm = O::imm(imm);
+            unary(v, out, arg, size, |a| O::sub(a, imm));
+        }
+        RegOp::AtanRegImm(out, arg, imm) => {
+            unary(v, out, arg, size, |a| O::atan2_imm(a, imm));
+        }
+        RegOp::AtanImmReg(out, arg, imm) => {
+            unary(v, out, arg, size, |a| O::imm_atan2(imm, a));
+        }
+        RegOp::ModRegImm(out, arg, imm) => {
+            unary(v, out, arg, size, |a| O::rem_euclid_imm(a, imm));
+        }
+        RegOp::ModImmReg(out, arg, imm) => {
+            unary(v, out, arg, size, |a| O::imm_rem_euclid(imm, a));
+        }
+        RegOp::CompareRegImm(out, arg, imm) => {
+            let imm = O::imm(imm);
+            unary(v, out, arg, size, |a| O::compare(a, imm));
+        }
+        RegOp::CompareImmReg(out, arg, imm) => {
+            let imm = O::imm(imm);
+            unary(v, out, arg, size, |a| O::compare(imm, a));
+        }
+        RegOp::MinRegImm(out, arg, imm) => {
+            let imm = O::imm(imm);
+            unary(v, out, arg, size, |a| O::min(a, imm));
+        }
+        RegOp::MaxRegImm(out, arg, imm) => {
+            let imm = O::imm(imm);
+            unary(v, out, arg, size, |a| O::max(a, imm));
+        }
+        RegOp::AndRegImm(out, arg, imm) => {
+            let imm = O::imm(imm);
+            unary(v, out, arg, size, |a| O::and(a, imm));
+        }
+        RegOp::OrRegImm(out, arg, imm) => {
+            let imm = O::imm(imm);
+            unary(v, out, arg, size, |a| O::or(a, imm));
+        }
+        RegOp::AddRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, O::add);
+        }
+        RegOp::MulRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, O::mul);
+        }
+        RegOp::DivRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, O::div);
+        }
+        RegOp::SubRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, O::sub);
+        }
+        RegOp::AtanRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, O::atan2);
+        }
+        RegOp::ModRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, O::rem_euclid);
+        }
+        RegOp::CompareRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, O::compare);
+        }
+        RegOp::MinRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, O::min);
+        }
+        RegOp::MaxRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, O::max);
+        }
+        RegOp::AndRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, O::and);
+        }
+        RegOp::OrRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, O::or);
+        }
+        RegOp::CopyImm(out, imm) => v[out][0..size].fill(O::imm(imm)),
+        RegOp::Load(out, mem) => {
+            for i in 0..size {
+                v[out][i] = v[mem][i];
+            }
+        }
+        RegOp::Store(out, mem) => {
+            for i in 0..size {
+                v[mem][i] = v[out][i];
+            }
+        }
+    }
+}
+
+/// Applies `f` to each element of `v[arg]`, writing the result to `v[out]`
+#[inline(always)]
+fn unary<T: Copy>(
+    v: &mut SlotArray<'_, Vec<T>>,
+    out: u8,
+    arg: u8,
+    size: usize,
+    f: impl Fn(T) -> T,
+) {
+    for i in 0..size {
+        v[out][i] = f(v[arg][i]);
+    }
+}
+
+/// Applies `f` to each pair of elements from `v[lhs]` and `v[rhs]`, writing
+/// the result to `v[out]`
+#[inline(always)]
+fn binary<T: Copy>(
+    v: &mut SlotArray<'_, Vec<T>>,
+    out: u8,
+    lhs: u8,
+    rhs: u8,
+    size: usize,
+    f: impl Fn(T, T) -> T,
+) {
+    for i in 0..size {
+        v[out][i] = f(v[lhs][i], v[rhs][i]);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+macro_rules! float_ops {
+    ($t:ty, $compare:ident) => {
+        impl VmOps for $t {
+            type Data = $t;
+
+            fn imm(imm: f64) -> $t {
+                imm as $t
+            }
+            fn neg(a: $t) -> $t {
+                -a
+            }
+            fn abs(a: $t) -> $t {
+                a.abs()
+            }
+            fn recip(a: $t) -> $t {
+                1.0 / a
+            }
+            fn sqrt(a: $t) -> $t {
+                a.sqrt()
+            }
+            fn square(a: $t) -> $t {
+                a * a
+            }
+            fn floor(a: $t) -> $t {
+                a.floor()
+            }
+            fn ceil(a: $t) -> $t {
+                a.ceil()
+            }
+            fn round(a: $t) -> $t {
+                a.round()
+            }
+            fn not(a: $t) -> $t {
+                (a == 0.0).into()
+            }
+            fn sin(a: $t) -> $t {
+                a.sin()
+            }
+            fn cos(a: $t) -> $t {
+                a.cos()
+            }
+            fn tan(a: $t) -> $t {
+                a.tan()
+            }
+            fn asin(a: $t) -> $t {
+                a.asin()
+            }
+            fn acos(a: $t) -> $t {
+                a.acos()
+            }
+            fn atan(a: $t) -> $t {
+                a.atan()
+            }
+            fn exp(a: $t) -> $t {
+                a.exp()
+            }
+            fn ln(a: $t) -> $t {
+                a.ln()
+            }
+            fn add(a: $t, b: $t) -> $t {
+                a + b
+            }
+            fn sub(a: $t, b: $t) -> $t {
+                a - b
+            }
+            fn mul(a: $t, b: $t) -> $t {
+                a * b
+            }
+            fn div(a: $t, b: $t) -> $t {
+                a / b
+            }
+            fn atan2(a: $t, b: $t) -> $t {
+                a.atan2(b)
+            }
+            fn rem_euclid(a: $t, b: $t) -> $t {
+                a.rem_euclid(b)
+            }
+            fn compare(a: $t, b: $t) -> $t {
+                $compare(a, b)
+            }
+        }
+
+        impl TracingOps for $t {
+            fn min_choice(a: $t, b: $t) -> ($t, Choice) {
+                if a < b {
+                    (a, Choice::Left)
+                } else if b < a {
+                    (b, Choice::Right)
+                } else {
+                    let v = if a.is_nan() || b.is_nan() {
+                        <$t>::NAN
+                    } else {
+                        b
+                    };
+                    (v, Choice::Both)
+                }
+            }
+            fn max_choice(a: $t, b: $t) -> ($t, Choice) {
+                if a > b {
+                    (a, Choice::Left)
+                } else if b > a {
+                    (b, Choice::Right)
+                } else {
+                    let v = if a.is_nan() || b.is_nan() {
+                        <$t>::NAN
+                    } else {
+                        b
+                    };
+                    (v, Choice::Both)
+                }
+            }
+            fn and_choice(a: $t, b: $t) -> ($t, Choice) {
+                if a == 0.0 {
+                    (a, Choice::Left)
+                } else {
+                    (b, Choice::Right)
+                }
+            }
+            fn or_choice(a: $t, b: $t) -> ($t, Choice) {
+                if a != 0.0 {
+                    (a, Choice::Left)
+                } else {
+                    (b, Choice::Right)
+                }
+            }
+        }
+
+        impl BulkOps for $t {
+            fn min(a: $t, b: $t) -> $t {
+                if a.is_nan() || b.is_nan() {
+                    <$t>::NAN
+                } else {
+                    a.min(b)
+                }
+            }
+            fn max(a: $t, b: $t) -> $t {
+                if a.is_nan() || b.is_nan() {
+                    <$t>::NAN
+                } else {
+                    a.max(b)
+                }
+            }
+            fn and(a: $t, b: $t) -> $t {
+                if a == 0.0 {
+                    a
+                } else {
+                    b
+                }
+            }
+            fn or(a: $t, b: $t) -> $t {
+                if a != 0.0 {
+                    a
+                } else {
+                    b
+                }
+            }
+        }
+    };
+}
+
+float_ops!(f32, compare_f32);
+float_ops!(f64, compare_f64);
+
+/// Implements [`VmOps`] by forwarding to methods on the data type
+///
+/// `$imm` is the scalar type from which immediates are converted, and `$recip`
+/// and `$square` compute the reciprocal and square of `a`.
+macro_rules! forward_ops {
+    ($t:ty, $imm:ty, |$a:ident| $recip:expr, $square:expr) => {
+        impl VmOps for $t {
+            type Data = $t;
+
+            fn imm(imm: f64) -> $t {
+                (imm as $imm).into()
+            }
+            fn neg(a: $t) -> $t {
+                -a
+            }
+            fn abs(a: $t) -> $t {
+                a.abs()
+            }
+            fn recip($a: $t) -> $t {
+                $recip
+            }
+            fn sqrt(a: $t) -> $t {
+                a.sqrt()
+            }
+            fn square($a: $t) -> $t {
+                $square
+            }
+            fn floor(a: $t) -> $t {
+                a.floor()
+            }
+            fn ceil(a: $t) -> $t {
+                a.ceil()
+            }
+            fn round(a: $t) -> $t {
+                a.round()
+            }
+            fn not(a: $t) -> $t {
+                !a
+            }
+            fn sin(a: $t) -> $t {
+                a.sin()
+            }
+            fn cos(a: $t) -> $t {
+                a.cos()
+            }
+            fn tan(a: $t) -> $t {
+                a.tan()
+            }
+            fn asin(a: $t) -> $t {
+                a.asin()
+            }
+            fn acos(a: $t) -> $t {
+                a.acos()
+            }
+            fn atan(a: $t) -> $t {
+                a.atan()
+            }
+            fn exp(a: $t) -> $t {
+                a.exp()
+            }
+            fn ln(a: $t) -> $t {
+                a.ln()
+            }
+            fn add(a: $t, b: $t) -> $t {
+                a + b
+            }
+            fn sub(a: $t, b: $t) -> $t {
+                a - b
+            }
+            fn mul(a: $t, b: $t) -> $t {
+                a * b
+            }
+            fn div(a: $t, b: $t) -> $t {
+                a / b
+            }
+            fn atan2(a: $t, b: $t) -> $t {
+                a.atan2(b)
+            }
+            fn rem_euclid(a: $t, b: $t) -> $t {
+                a.rem_euclid(b)
+            }
+            fn compare(a: $t, b: $t) -> $t {
+                a.compare(b)
+            }
+        }
+    };
+}
+
+/// Implements [`TracingOps`] for interval-like types, which provide their own
+/// `*_choice` functions
+macro_rules! interval_ops {
+    ($t:ty, $imm:ty) => {
+        forward_ops!($t, $imm, |a| a.recip(), a.square());
+
+        impl TracingOps for $t {
+            fn min_choice(a: $t, b: $t) -> ($t, Choice) {
+                a.min_choice(b)
+            }
+            fn max_choice(a: $t, b: $t) -> ($t, Choice) {
+                a.max_choice(b)
+            }
+            fn and_choice(a: $t, b: $t) -> ($t, Choice) {
+                a.and_choice(b)
+            }
+            fn or_choice(a: $t, b: $t) -> ($t, Choice) {
+                a.or_choice(b)
+            }
+        }
+    };
+}
+
+interval_ops!(Interval, f32);
+interval_ops!(Interval64, f64);
+interval_ops!(Affine, f32);
+interval_ops!(IntervalGrad, f32);
+
+/// Implements [`BulkOps`] for automatic differentiation types, which pick
+/// `NaN` if either value of a `min` or `max` is `NaN`
+macro_rules! grad_ops {
+    ($t:ty) => {
+        forward_ops!($t, f32, |a| <$t>::from(1.0) / a, a * a);
+
+        impl BulkOps for $t {
+            fn min(a: $t, b: $t) -> $t {
+                if a.v.is_nan() || b.v.is_nan() {
+                    f32::NAN.into()
+                } else {
+                    a.min(b)
+                }
+            }
+            fn max(a: $t, b: $t) -> $t {
+                if a.v.is_nan() || b.v.is_nan() {
+                    f32::NAN.into()
+                } else {
+                    a.max(b)
+                }
+            }
+            fn and(a: $t, b: $t) -> $t {
+                a.and(b)
+            }
+            fn or(a: $t, b: $t) -> $t {
+                a.or(b)
+            }
+        }
+    };
+}
+
+grad_ops!(Grad);
+grad_ops!(Hessian);
+grad_ops!(MultiGrad);
+
+/// Arithmetic for [`VmRobustIntervalEval`](super::VmRobustIntervalEval)
+///
+/// Operations which round are widened by one ulp in each direction, and
+/// transcendental functions are evaluated in double precision before being
+/// rounded outward.
+pub(crate) struct RobustInterval;
+
+impl RobustInterval {
+    /// Evaluates a function in double precision
+    fn f64_unary(a: Interval, f: fn(Interval64) -> Interval64) -> Interval {
+        f(a.into()).round_outward()
+    }
+}
+
+impl VmOps for RobustInterval {
+    type Data = Interval;
+
+    fn imm(imm: f64) -> Interval {
+        Interval::enclosing(imm)
+    }
+    fn neg(a: Interval) -> Interval {
+        -a
+    }
+    fn abs(a: Interval) -> Interval {
+        a.abs()
+    }
+    fn recip(a: Interval) -> Interval {
+        a.recip().widen()
+    }
+    fn sqrt(a: Interval) -> Interval {
+        a.sqrt().widen()
+    }
+    fn square(a: Interval) -> Interval {
+        a.square().widen()
+    }
+    fn floor(a: Interval) -> Interval {
+        a.floor()
+    }
+    fn ceil(a: Interval) -> Interval {
+        a.ceil()
+    }
+    fn round(a: Interval) -> Interval {
+        a.round()
+    }
+    fn not(a: Interval) -> Interval {
+        !a
+    }
+    fn sin(a: Interval) -> Interval {
+        Self::f64_unary(a, Interval64::sin)
+    }
+    fn cos(a: Interval) -> Interval {
+        Self::f64_unary(a, Interval64::cos)
+    }
+    fn tan(a: Interval) -> Interval {
+        Self::f64_unary(a, Interval64::tan)
+    }
+    fn asin(a: Interval) -> Interval {
+        Self::f64_unary(a, Interval64::asin)
+    }
+    fn acos(a: Interval) -> Interval {
+        Self::f64_unary(a, Interval64::acos)
+    }
+    fn atan(a: Interval) -> Interval {
+        Self::f64_unary(a, Interval64::atan)
+    }
+    fn exp(a: Interval) -> Interval {
+        Self::f64_unary(a, Interval64::exp)
+    }
+    fn ln(a: Interval) -> Interval {
+        Self::f64_unary(a, Interval64::ln)
+    }
+    fn add(a: Interval, b: Interval) -> Interval {
+        (a + b).widen()
+    }
+    fn sub(a: Interval, b: Interval) -> Interval {
+        (a - b).widen()
+    }
+    fn mul(a: Interval, b: Interval) -> Interval {
+        (a * b).widen()
+    }
+    fn div(a: Interval, b: Interval) -> Interval {
+        (a / b).widen()
+    }
+    fn atan2(a: Interval, b: Interval) -> Interval {
+        Interval64::from(a).atan2(b.into()).round_outward()
+    }
+    fn rem_euclid(a: Interval, b: Interval) -> Interval {
+        Interval64::from(a).rem_euclid(b.into()).round_outward()
+    }
+    fn compare(a: Interval, b: Interval) -> Interval {
+        a.compare(b)
+    }
+
+    // Immediates are used exactly (rather than through their enclosing
+    // interval) by functions which are evaluated in double precision
+    fn atan2_imm(a: Interval, imm: f64) -> Interval {
+        Interval64::from(a).atan2(imm.into()).round_outward()
+    }
+    fn imm_atan2(imm: f64, b: Interval) -> Interval {
+        Interval64::from(imm).atan2(b.into()).round_outward()
+    }
+    fn rem_euclid_imm(a: Interval, imm: f64) -> Interval {
+        Interval64::from(a).rem_euclid(imm.into()).round_outward()
+    }
+    fn imm_rem_euclid(imm: f64, b: Interval) -> Interval {
+        Interval64::from(imm).rem_euclid(b.into()).round_outward()
+    }
+}
+
+impl TracingOps for RobustInterval {
+    fn min_choice(a: Interval, b: Interval) -> (Interval, Choice) {
+        a.min_choice(b)
+    }
+    fn max_choice(a: Interval, b: Interval) -> (Interval, Choice) {
+        a.max_choice(b)
+    }
+    fn and_choice(a: Interval, b: Interval) -> (Interval, Choice) {
+        a.and_choice(b)
+    }
+    fn or_choice(a: Interval, b: Interval) -> (Interval, Choice) {
+        a.or_choice(b)
+    }
+}
@@ -10,6 +10,9 @@ pub enum Error {
     /// Variable is not present in this `Context`
     #[error("variable is not present in this `Context`")]
     BadVar,
+    /// Node is not an input or variable
+    #[error("node is not an input or variable")]
+    NotAVariable,
 
     /// `Context` is empty
     #[error("`Context` is empty")]
@@ -18,9 +21,6 @@ pub enum Error {
     #[error("`IndexMap` is empty")]
     EmptyMap,
 
-    /// Unknown opcode {0}
-    #[error("unknown opcode {0}")]
-    UnknownOpcode(String),
     /// Unknown variable {0}
     #[error("unknown variable {0}")]
     UnknownVariable(String),
@@ -29,6 +29,44 @@ pub enum Error {
     #[error("empty file")]
     EmptyFile,
 
+    /// Line {0} is missing a field
+    #[error("line {0} is missing a field")]
+    MissingField(usize),
+    /// Line {0} has an invalid constant
+    #[error("line {0}: invalid constant `{1}`")]
+    BadConstant(usize, String),
+    /// Line {0} refers to an undefined node
+    #[error("line {0}: undefined node `{1}`")]
+    UndefinedNode(usize, String),
+    /// Line {0} has extra fields after the last argument
+    #[error("line {0} has extra fields")]
+    ExtraField(usize),
+    /// Line {0} has an unknown opcode
+    #[error("line {0}: unknown opcode `{1}`")]
+    UnknownOpcode(usize, String),
+    /// Line {0} uses a variable name reserved for 3D coordinates
+    #[error("line {0}: variable name `{1}` is reserved for 3D coordinates")]
+    ReservedVarName(usize, String),
+
+    /// Serialized data has a missing or malformed header
+    #[error("missing or malformed header")]
+    BadHeader,
+    /// Serialized data uses an unsupported format version
+    #[error("unsupported format version {0}")]
+    UnsupportedVersion(u32),
+    /// Serialized data ended unexpectedly
+    #[error("unexpected end of data")]
+    UnexpectedEof,
+    /// Serialized data continues after the last node
+    #[error("trailing data after the last node")]
+    TrailingData,
+    /// Serialized node is malformed
+    #[error("malformed node: {0}")]
+    MalformedNode(String),
+    /// Serialized node refers to a node that has not yet been defined
+    #[error("node {0} refers to undefined node {1}")]
+    BadReference(usize, usize),
+
     /// Choice slice length does not match choice count
     #[error("choice slice length ({0}) does not match choice count ({1})")]
     BadChoiceSlice(usize, usize),
@@ -41,6 +79,22 @@ pub enum Error {
     #[error("var slice length ({0}) does not match var count ({1})")]
     BadVarSlice(usize, usize),
 
+    /// Too many gradient targets
+    #[error("too many gradient targets ({0}); the maximum is {1}")]
+    TooManyGradTargets(usize, usize),
+
+    /// Tape has no outputs
+    #[error("tape must have at least one output")]
+    NoOutputs,
+
+    /// Too many outputs
+    #[error("too many outputs ({0}); the maximum is {1}")]
+    TooManyOutputs(usize, usize),
+
+    /// Name is not a valid identifier in generated code
+    #[error("`{0}` is not a valid identifier")]
+    BadIdentifier(String),
+
     /// This name is reserved for 3D coordinates
     #[error("this name is reserved for 3D coordinates")]
     ReservedName,
@@ -49,6 +103,18 @@ pub enum Error {
     #[error("this name has already been used")]
     DuplicateName,
 
+    /// Meshing bounds are not finite or have `lower >= upper`
+    #[error("bounds must be finite, with `lower < upper` on every axis")]
+    BadBounds,
+
+    /// Attribute name is empty or contains whitespace
+    #[error("invalid attribute name `{0}`")]
+    BadAttributeName(String),
+
+    /// Attribute name collides with a vertex position or normal property
+    #[error("attribute `{0}` is reserved for vertex positions or normals")]
+    ReservedAttributeName(String),
+
     /// io error; see inner code for details
     #[error("io error: {0}")]
     IoError(#[from] std::io::Error),
@@ -237,6 +237,25 @@ impl Assembler for FloatSliceAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; frintm V(reg(out_reg)).s4, V(reg(lhs_reg)).s4)
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; frintp V(reg(out_reg)).s4, V(reg(lhs_reg)).s4)
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; frinta V(reg(out_reg)).s4, V(reg(lhs_reg)).s4)
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Build a mask of zeros (which is false for NaN), then use it to
+            // select 1.0
+            ; fcmeq v4.s4, V(reg(lhs_reg)).s4, 0.0
+            ; fmov s5, 1.0
+            ; dup v5.s4, v5.s[0]
+            ; and V(reg(out_reg)).b16, v4.b16, v5.b16
+        )
+    }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops ; mov V(reg(out_reg)).b16, V(reg(lhs_reg)).b16)
     }
@@ -281,6 +300,40 @@ impl Assembler for FloatSliceAssembler {
             ; fdiv V(reg(out_reg)).s4, V(reg(lhs_reg)).s4, V(reg(rhs_reg)).s4
         )
     }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn float_atan2(y: f32, x: f32) -> f32 {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn float_mod(lhs: f32, rhs: f32) -> f32 {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn float_compare(lhs: f32, rhs: f32) -> f32 {
+            crate::vm::compare_f32(lhs, rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Build a mask of zeros in lhs, then pick lhs where it's set
+            ; fcmeq v4.s4, V(reg(lhs_reg)).s4, 0.0
+            ; bsl v4.b16, V(reg(lhs_reg)).b16, V(reg(rhs_reg)).b16
+            ; mov V(reg(out_reg)).b16, v4.b16
+        )
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Build a mask of zeros in lhs, then pick rhs where it's set
+            ; fcmeq v4.s4, V(reg(lhs_reg)).s4, 0.0
+            ; bsl v4.b16, V(reg(rhs_reg)).b16, V(reg(lhs_reg)).b16
+            ; mov V(reg(out_reg)).b16, v4.b16
+        )
+    }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             ; fmax V(reg(out_reg)).s4, V(reg(lhs_reg)).s4, V(reg(rhs_reg)).s4
@@ -293,8 +346,8 @@ impl Assembler for FloatSliceAssembler {
     }
 
     /// Loads an immediate into register V4, using W9 as an intermediary
-    fn load_imm(&mut self, imm: f32) -> u8 {
-        let imm_u32 = imm.to_bits();
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        let imm_u32 = (imm as f32).to_bits();
         dynasm!(self.0.ops
             ; movz w9, #(imm_u32 >> 16), lsl 16
             ; movk w9, #(imm_u32)
@@ -352,7 +405,79 @@ impl FloatSliceAssembler {
         arg_reg: u8,
         f: extern "C" fn(f32) -> f32,
     ) {
-        let addr = f as usize;
+        self.call_fn_prelude(f as usize);
+        dynasm!(self.0.ops
+            // We're going to back up our argument into d8/d9 (since the callee
+            // only saves the bottom 64 bits).  Note that d8/d9 may be our input
+            // argument, so we'll move it to v0 first.
+            ; mov v0.b16, V(reg(arg_reg)).b16
+            ; mov d8, v0.d[0]
+            ; mov d9, v0.d[1]
+
+            ; mov s0, v8.s[0]
+            ; blr x26
+            ; mov v8.s[0], v0.s[0]
+
+            ; mov s0, v8.s[1]
+            ; blr x26
+            ; mov v8.s[1], v0.s[0]
+
+            ; mov s0, v9.s[0]
+            ; blr x26
+            ; mov v9.s[0], v0.s[0]
+
+            ; mov s0, v9.s[1]
+            ; blr x26
+            ; mov v9.s[1], v0.s[0]
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "C" fn(f32, f32) -> f32,
+    ) {
+        self.call_fn_prelude(f as usize);
+        dynasm!(self.0.ops
+            // Back up our arguments into d8/d9 (LHS) and d10/d11 (RHS), since
+            // the callee only saves the bottom 64 bits.  As above, those may
+            // be our input arguments, so we'll move them to v0/v1 first.
+            ; mov v0.b16, V(reg(lhs_reg)).b16
+            ; mov v1.b16, V(reg(rhs_reg)).b16
+            ; mov d8, v0.d[0]
+            ; mov d9, v0.d[1]
+            ; mov d10, v1.d[0]
+            ; mov d11, v1.d[1]
+
+            ; mov s0, v8.s[0]
+            ; mov s1, v10.s[0]
+            ; blr x26
+            ; mov v8.s[0], v0.s[0]
+
+            ; mov s0, v8.s[1]
+            ; mov s1, v10.s[1]
+            ; blr x26
+            ; mov v8.s[1], v0.s[0]
+
+            ; mov s0, v9.s[0]
+            ; mov s1, v11.s[0]
+            ; blr x26
+            ; mov v9.s[0], v0.s[0]
+
+            ; mov s0, v9.s[1]
+            ; mov s1, v11.s[1]
+            ; blr x26
+            ; mov v9.s[1], v0.s[0]
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves register state before a function call, loading the function
+    /// address into `x26`
+    fn call_fn_prelude(&mut self, addr: usize) {
         dynasm!(self.0.ops
             // Back up our current state
             ; mov x20, x0
@@ -388,30 +513,13 @@ impl FloatSliceAssembler {
             ; movk x26, #((addr >> 32) as u32), lsl 32
             ; movk x26, #((addr >> 16) as u32), lsl 16
             ; movk x26, #(addr as u32)
+        );
+    }
 
-            // We're going to back up our argument into d8/d9 (since the callee
-            // only saves the bottom 64 bits).  Note that d8/d9 may be our input
-            // argument, so we'll move it to v0 first.
-            ; mov v0.b16, V(reg(arg_reg)).b16
-            ; mov d8, v0.d[0]
-            ; mov d9, v0.d[1]
-
-            ; mov s0, v8.s[0]
-            ; blr x26
-            ; mov v8.s[0], v0.s[0]
-
-            ; mov s0, v8.s[1]
-            ; blr x26
-            ; mov v8.s[1], v0.s[0]
-
-            ; mov s0, v9.s[0]
-            ; blr x26
-            ; mov v9.s[0], v0.s[0]
-
-            ; mov s0, v9.s[1]
-            ; blr x26
-            ; mov v9.s[1], v0.s[0]
-
+    /// Restores register state after a function call, with the result stored
+    /// in `v8` and `v9`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
             // Copy into v0, because we're about to restore v8
             ; mov v0.d[0], v8.d[0]
             ; mov v0.d[1], v9.d[0]
@@ -242,6 +242,34 @@ impl Assembler for GradSliceAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Scalar operations clear the upper lanes, which sets our derivatives
+        // to zero (as expected for a rounded value)
+        dynasm!(self.0.ops
+            ; frintm s6, S(reg(lhs_reg))
+            ; mov V(reg(out_reg)).b16, v6.b16
+        )
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; frintp s6, S(reg(lhs_reg))
+            ; mov V(reg(out_reg)).b16, v6.b16
+        )
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; frinta s6, S(reg(lhs_reg))
+            ; mov V(reg(out_reg)).b16, v6.b16
+        )
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; fcmp S(reg(lhs_reg)), 0.0
+            ; cset w15, eq // NaN is unordered, so this is false
+            ; ucvtf s6, w15 // writing s6 clears the partial derivatives
+            ; mov V(reg(out_reg)).b16, v6.b16
+        )
+    }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops ; mov V(reg(out_reg)).b16, V(reg(lhs_reg)).b16)
     }
@@ -362,6 +390,52 @@ impl Assembler for GradSliceAssembler {
             ; mov V(reg(out_reg)).s[0], v6.s[0]
         )
     }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn grad_atan2(y: Grad, x: Grad) -> Grad {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn grad_mod(lhs: Grad, rhs: Grad) -> Grad {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn grad_compare(lhs: Grad, rhs: Grad) -> Grad {
+            lhs.compare(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; fcmp S(reg(lhs_reg)), 0.0
+            ; b.ne 12 // -> rhs (NaN is unordered, so it's also nonzero)
+
+            // lhs is zero
+            ; mov V(reg(out_reg)).b16, V(reg(lhs_reg)).b16
+            ; b 8 // -> end
+
+            // rhs:
+            ; mov V(reg(out_reg)).b16, V(reg(rhs_reg)).b16
+            // end:
+        )
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; fcmp S(reg(lhs_reg)), 0.0
+            ; b.ne 12 // -> lhs (NaN is unordered, so it's also nonzero)
+
+            // lhs is zero
+            ; mov V(reg(out_reg)).b16, V(reg(rhs_reg)).b16
+            ; b 8 // -> end
+
+            // lhs:
+            ; mov V(reg(out_reg)).b16, V(reg(lhs_reg)).b16
+            // end:
+        )
+    }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         let nan_u32 = f32::NAN.to_bits();
         dynasm!(self.0.ops
@@ -408,8 +482,8 @@ impl Assembler for GradSliceAssembler {
     }
 
     /// Loads an immediate into register S4, using W9 as an intermediary
-    fn load_imm(&mut self, imm: f32) -> u8 {
-        let imm_u32 = imm.to_bits();
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        let imm_u32 = (imm as f32).to_bits();
         dynasm!(self.0.ops
             ; movz w9, #(imm_u32 >> 16), lsl 16
             ; movk w9, #(imm_u32)
@@ -466,7 +540,48 @@ impl GradSliceAssembler {
         arg_reg: u8,
         f: extern "C" fn(Grad) -> Grad,
     ) {
-        let addr = f as usize;
+        self.call_fn_prelude(f as usize);
+        dynasm!(self.0.ops
+            // Prepare to call our stuff!
+            ; mov s0, V(reg(arg_reg)).s[0]
+            ; mov s1, V(reg(arg_reg)).s[1]
+            ; mov s2, V(reg(arg_reg)).s[2]
+            ; mov s3, V(reg(arg_reg)).s[3]
+
+            ; blr x26
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "C" fn(Grad, Grad) -> Grad,
+    ) {
+        self.call_fn_prelude(f as usize);
+        dynasm!(self.0.ops
+            // Prepare to call our stuff!  The RHS is unpacked into s4-7 first,
+            // because unpacking the LHS overwrites v3 (which may be the RHS
+            // immediate register)
+            ; mov s4, V(reg(rhs_reg)).s[0]
+            ; mov s5, V(reg(rhs_reg)).s[1]
+            ; mov s6, V(reg(rhs_reg)).s[2]
+            ; mov s7, V(reg(rhs_reg)).s[3]
+            ; mov s0, V(reg(lhs_reg)).s[0]
+            ; mov s1, V(reg(lhs_reg)).s[1]
+            ; mov s2, V(reg(lhs_reg)).s[2]
+            ; mov s3, V(reg(lhs_reg)).s[3]
+
+            ; blr x26
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves register state before a function call, loading the function
+    /// address into `x26`
+    fn call_fn_prelude(&mut self, addr: usize) {
         dynasm!(self.0.ops
             // Back up our current state
             ; mov x20, x0
@@ -502,15 +617,13 @@ impl GradSliceAssembler {
             ; movk x26, #((addr >> 32) as u32), lsl 32
             ; movk x26, #((addr >> 16) as u32), lsl 16
             ; movk x26, #(addr as u32)
+        );
+    }
 
-            // Prepare to call our stuff!
-            ; mov s0, V(reg(arg_reg)).s[0]
-            ; mov s1, V(reg(arg_reg)).s[1]
-            ; mov s2, V(reg(arg_reg)).s[2]
-            ; mov s3, V(reg(arg_reg)).s[3]
-
-            ; blr x26
-
+    /// Restores register state after a function call, packing the result
+    /// (in `s0-3`) into `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
             // Restore register state (lol)
             ; ldp q8, q9, [sp, 0x50]
             ; ldp q10, q11, [sp, 0x70]
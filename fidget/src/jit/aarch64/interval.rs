@@ -195,6 +195,22 @@ impl Assembler for IntervalAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Rounding is monotonic, so we can round both bounds in place
+        dynasm!(self.0.ops ; frintm V(reg(out_reg)).s2, V(reg(lhs_reg)).s2)
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; frintp V(reg(out_reg)).s2, V(reg(lhs_reg)).s2)
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; frinta V(reg(out_reg)).s2, V(reg(lhs_reg)).s2)
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_not(v: Interval) -> Interval {
+            !v
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_not);
+    }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops ; fmov D(reg(out_reg)), D(reg(lhs_reg)))
     }
@@ -337,7 +353,7 @@ impl Assembler for IntervalAssembler {
             ; fsub V(reg(out_reg)).s2, V(reg(lhs_reg)).s2, v4.s2
         )
     }
-    fn build_sub_reg_imm(&mut self, out_reg: u8, arg: u8, imm: f32) {
+    fn build_sub_reg_imm(&mut self, out_reg: u8, arg: u8, imm: f64) {
         let imm = self.load_imm(imm);
         dynasm!(self.0.ops
             ; fsub V(reg(out_reg)).s2, V(reg(arg)).s2, V(reg(imm)).s2
@@ -364,7 +380,7 @@ impl Assembler for IntervalAssembler {
         )
     }
 
-    fn build_mul_imm(&mut self, out_reg: u8, lhs_reg: u8, imm: f32) {
+    fn build_mul_imm(&mut self, out_reg: u8, lhs_reg: u8, imm: f64) {
         let rhs_reg = self.load_imm(imm);
         dynasm!(self.0.ops
             ; fmul V(reg(out_reg)).s2, V(reg(lhs_reg)).s2, V(reg(rhs_reg)).s2
@@ -414,6 +430,124 @@ impl Assembler for IntervalAssembler {
             // >end
         )
     }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn interval_atan2(y: Interval, x: Interval) -> Interval {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn interval_mod(lhs: Interval, rhs: Interval) -> Interval {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn interval_compare(
+            lhs: Interval,
+            rhs: Interval,
+        ) -> Interval {
+            lhs.compare(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            //  if lhs == [0, 0]
+            //      *choices++ |= CHOICE_LEFT
+            //      out = lhs
+            //  elif !lhs.contains(0)
+            //      *choices++ |= CHOICE_RIGHT
+            //      out = rhs
+            //  else
+            //      *choices++ |= CHOICE_BOTH
+            //      out = [min(rhs.lower, 0), max(rhs.upper, 0)]
+            ; ldrb w14, [x1]
+            ; mov s4, V(reg(lhs_reg)).s[1]
+
+            ; fcmp S(reg(lhs_reg)), 0.0
+            ; b.gt 44 // -> nonzero
+            ; fcmp s4, 0.0
+            ; b.mi 36 // -> nonzero
+
+            // NaN is unordered, so it's caught by these b.ne checks
+            ; fcmp S(reg(lhs_reg)), 0.0
+            ; b.ne 44 // -> both
+            ; fcmp s4, 0.0
+            ; b.ne 36 // -> both
+
+            // Fallthrough: lhs is exactly zero
+            ; fmov D(reg(out_reg)), D(reg(lhs_reg))
+            ; orr w14, w14, #CHOICE_LEFT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 44 // -> end
+
+            // <- nonzero
+            ; fmov D(reg(out_reg)), D(reg(rhs_reg))
+            ; orr w14, w14, #CHOICE_RIGHT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 28 // -> end
+
+            // <- both
+            ; movi d4, 0
+            ; fmin v5.s2, V(reg(rhs_reg)).s2, v4.s2
+            ; fmax v4.s2, V(reg(rhs_reg)).s2, v4.s2
+            ; mov v5.s[1], v4.s[1]
+            ; fmov D(reg(out_reg)), d5
+            ; orr w14, w14, #CHOICE_BOTH
+
+            // <- end
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            //  if lhs == [0, 0]
+            //      *choices++ |= CHOICE_RIGHT
+            //      out = rhs
+            //  elif !lhs.contains(0)
+            //      *choices++ |= CHOICE_LEFT
+            //      out = lhs
+            //  else
+            //      *choices++ |= CHOICE_BOTH
+            //      out = [min(lhs.lower, rhs.lower), max(lhs.upper, rhs.upper)]
+            ; ldrb w14, [x1]
+            ; mov s4, V(reg(lhs_reg)).s[1]
+
+            ; fcmp S(reg(lhs_reg)), 0.0
+            ; b.gt 44 // -> nonzero
+            ; fcmp s4, 0.0
+            ; b.mi 36 // -> nonzero
+
+            // NaN is unordered, so it's caught by these b.ne checks
+            ; fcmp S(reg(lhs_reg)), 0.0
+            ; b.ne 44 // -> both
+            ; fcmp s4, 0.0
+            ; b.ne 36 // -> both
+
+            // Fallthrough: lhs is exactly zero
+            ; fmov D(reg(out_reg)), D(reg(rhs_reg))
+            ; orr w14, w14, #CHOICE_RIGHT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 40 // -> end
+
+            // <- nonzero
+            ; fmov D(reg(out_reg)), D(reg(lhs_reg))
+            ; orr w14, w14, #CHOICE_LEFT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 24 // -> end
+
+            // <- both (fmin and fmax propagate NaN)
+            ; fmin v4.s2, V(reg(lhs_reg)).s2, V(reg(rhs_reg)).s2
+            ; fmax v5.s2, V(reg(lhs_reg)).s2, V(reg(rhs_reg)).s2
+            ; mov v4.s[1], v5.s[1]
+            ; fmov D(reg(out_reg)), d4
+            ; orr w14, w14, #CHOICE_BOTH
+
+            // <- end
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             // Basically the same as MinRegReg
@@ -500,8 +634,8 @@ impl Assembler for IntervalAssembler {
     }
 
     /// Loads an immediate into register S4, using W9 as an intermediary
-    fn load_imm(&mut self, imm: f32) -> u8 {
-        let imm_u32 = imm.to_bits();
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        let imm_u32 = (imm as f32).to_bits();
         dynasm!(self.0.ops
             ; movz w15, #(imm_u32 >> 16), lsl 16
             ; movk w15, #(imm_u32)
@@ -544,12 +678,47 @@ impl Assembler for IntervalAssembler {
 }
 
 impl IntervalAssembler {
-    fn call_fn_unary(
+    pub(super) fn call_fn_unary(
         &mut self,
         out_reg: u8,
         arg_reg: u8,
         f: extern "C" fn(Interval) -> Interval,
     ) {
+        self.call_fn_prelude(f as usize);
+        dynasm!(self.0.ops
+            // Prepare to call our stuff!
+            ; mov s0, V(reg(arg_reg)).s[0]
+            ; mov s1, V(reg(arg_reg)).s[1]
+
+            ; blr x0
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    pub(super) fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "C" fn(Interval, Interval) -> Interval,
+    ) {
+        self.call_fn_prelude(f as usize);
+        dynasm!(self.0.ops
+            // Prepare to call our stuff!  The LHS is unpacked first, because
+            // the RHS writes to v3 (which may be the LHS immediate register)
+            ; mov s0, V(reg(lhs_reg)).s[0]
+            ; mov s1, V(reg(lhs_reg)).s[1]
+            ; mov s2, V(reg(rhs_reg)).s[0]
+            ; mov s3, V(reg(rhs_reg)).s[1]
+
+            ; blr x0
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves register state before a function call, loading the function
+    /// address into `x0`
+    fn call_fn_prelude(&mut self, addr: usize) {
         if !self.0.saved_callee_regs {
             dynasm!(self.0.ops
                 // Back up a few callee-saved registers that we're about to use
@@ -559,7 +728,6 @@ impl IntervalAssembler {
             self.0.saved_callee_regs = true;
         }
 
-        let addr = f as usize;
         dynasm!(self.0.ops
             // Back up our current state to callee-saved registers
             ; mov x20, x0
@@ -584,13 +752,13 @@ impl IntervalAssembler {
             ; movk x0, #((addr >> 32) as u32), lsl 32
             ; movk x0, #((addr >> 16) as u32), lsl 16
             ; movk x0, #(addr as u32)
+        );
+    }
 
-            // Prepare to call our stuff!
-            ; mov s0, V(reg(arg_reg)).s[0]
-            ; mov s1, V(reg(arg_reg)).s[1]
-
-            ; blr x0
-
+    /// Restores register state after a function call, unpacking the result
+    /// into `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
             // Restore floating-point state
             ; ldp d16, d17, [sp, 0x50]
             ; ldp d18, d19, [sp, 0x60]
@@ -0,0 +1,696 @@
+use crate::{
+    eval::types::Interval64,
+    jit::{
+        interval64::Interval64Assembler, mmap::Mmap, reg, Assembler,
+        AssemblerData, CHOICE_BOTH, CHOICE_LEFT, CHOICE_RIGHT, IMM_REG, OFFSET,
+        REGISTER_LIMIT,
+    },
+    Error,
+};
+use dynasmrt::{dynasm, DynasmApi};
+
+/// Implementation for the double-precision interval assembler on `aarch64`
+///
+/// Registers as pased in as follows:
+///
+/// | Variable   | Register   | Type                    |
+/// |------------|------------|-------------------------|
+/// | X          | `(d0, d1)` | `(f64, f64)`            |
+/// | Y          | `(d2, d3)` | `(f64, f64)`            |
+/// | Z          | `(d4, d5)` | `(f64, f64)`            |
+/// | `vars`     | `x0`       | `*const f64` (array)    |
+/// | `choices`  | `x1`       | `*mut u8` (array)       |
+/// | `simplify` | `x2`       | `*mut u8` (single)      |
+///
+/// During evaluation, `x0`, `x1`, and `x2` maintain their meaning, while X, Y,
+/// and Z are stored in `V0-2.D2`.  Intervals use the full width of a SIMD
+/// register `Vx`: `d[0]` is the lower bound of the interval and `d[1]` is the
+/// upper bound; for example, `V0.D0` represents the lower bound for X.
+///
+/// Here is the full table of registers used during evaluation:
+///
+/// | Register | Description                                          |
+/// |----------|------------------------------------------------------|
+/// | `v0.d2`  | X interval                                           |
+/// | `v1.d2`  | Y interval                                           |
+/// | `v2.d2`  | Z interval                                           |
+/// | `v3.d2`  | Immediate value (`IMM_REG`)                          |
+/// | `v4-7`   | Scratch registers                                    |
+/// | `v8-15`  | Tape values (callee-saved, lower 64 bits only)       |
+/// | `v16-31` | Tape values (caller-saved)                           |
+/// | `x0`     | Function pointer for calls                           |
+/// | `w14`    | Scratch space used for choice value                  |
+/// | `x15`    | Miscellaneous scratch space                          |
+///
+/// The stack is configured as follows:
+///
+/// ```text
+/// | Position | Value        | Notes                                       |
+/// |----------|------------------------------------------------------------|
+/// | 0x220    | ...          | Register spills live up here                |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x210    | `x22`        | During functions calls, we use these        |
+/// | 0x208    | `x21`        | as temporary storage so must preserve their |
+/// | 0x200    | `x20`        | previous values on the stack                |
+/// |----------|------------------------------------------------------------|
+/// | 0x1f0    | `q2`         | During functions calls, X/Y/Z are saved on  |
+/// | 0x1e0    | `q1`         | the stack                                   |
+/// | 0x1d0    | `q0`         |                                             |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x1c0    | `q31`        | During functions calls, caller-saved tape   |
+/// | 0x1b0    | `q30`        | registers are saved on the stack            |
+/// | 0x1a0    | `q29`        |                                             |
+/// | 0x190    | `q28`        |                                             |
+/// | 0x180    | `q27`        |                                             |
+/// | 0x170    | `q26`        |                                             |
+/// | 0x160    | `q25`        |                                             |
+/// | 0x150    | `q24`        |                                             |
+/// | 0x140    | `q23`        |                                             |
+/// | 0x130    | `q22`        |                                             |
+/// | 0x120    | `q21`        |                                             |
+/// | 0x110    | `q20`        |                                             |
+/// | 0x100    | `q19`        |                                             |
+/// | 0xf0     | `q18`        |                                             |
+/// | 0xe0     | `q17`        |                                             |
+/// | 0xd0     | `q16`        |                                             |
+/// | 0xc0     | `q15`        | We also have to save callee-saved registers |
+/// | 0xb0     | `q14`        | because the callee only saves the lower 64  |
+/// | 0xa0     | `q13`        | bits, and we're using all 128               |
+/// | 0x90     | `q12`        |                                             |
+/// | 0x80     | `q11`        |                                             |
+/// | 0x70     | `q10`        |                                             |
+/// | 0x60     | `q9`         |                                             |
+/// | 0x50     | `q8`         |                                             |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x48     | `d15`        | Callee-saved registers                      |
+/// | 0x40     | `d14`        |                                             |
+/// | 0x38     | `d13`        |                                             |
+/// | 0x30     | `d12`        |                                             |
+/// | 0x28     | `d11`        |                                             |
+/// | 0x20     | `d10`        |                                             |
+/// | 0x18     | `d9`         |                                             |
+/// | 0x10     | `d8`         |                                             |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x8      | `sp` (`x30`) | Stack frame                                 |
+/// | 0x0      | `fp` (`x29`) | [current value for sp]                      |
+/// ```
+const STACK_SIZE: u32 = 0x220;
+
+impl Assembler for Interval64Assembler {
+    type Data = Interval64;
+
+    fn init(mmap: Mmap, slot_count: usize) -> Self {
+        let mut out = AssemblerData::new(mmap);
+        out.prepare_stack(slot_count, STACK_SIZE as usize);
+        dynasm!(out.ops
+            // Preserve frame and link register, and set up the frame pointer
+            ; stp   x29, x30, [sp, 0x0]
+            ; mov   x29, sp
+
+            // Preserve callee-saved floating-point registers
+            ; stp   d8, d9, [sp, 0x10]
+            ; stp   d10, d11, [sp, 0x20]
+            ; stp   d12, d13, [sp, 0x30]
+            ; stp   d14, d15, [sp, 0x40]
+
+            // Arguments are passed in D0-5; collect them into V0-2
+            ; mov v0.d[1], v1.d[0]
+            ; mov v1.d[0], v2.d[0]
+            ; mov v1.d[1], v3.d[0]
+            ; mov v2.d[0], v4.d[0]
+            ; mov v2.d[1], v5.d[0]
+        );
+        Self(out)
+    }
+
+    fn bytes_per_clause() -> usize {
+        40
+    }
+
+    /// Reads from `src_mem` to `dst_reg`
+    fn build_load(&mut self, dst_reg: u8, src_mem: u32) {
+        assert!((dst_reg as usize) < REGISTER_LIMIT);
+        let sp_offset = self.0.stack_pos(src_mem) + STACK_SIZE;
+        assert!(sp_offset < 65536);
+        dynasm!(self.0.ops ; ldr Q(reg(dst_reg)), [sp, #(sp_offset)])
+    }
+    /// Writes from `src_reg` to `dst_mem`
+    fn build_store(&mut self, dst_mem: u32, src_reg: u8) {
+        assert!((src_reg as usize) < REGISTER_LIMIT);
+        let sp_offset = self.0.stack_pos(dst_mem) + STACK_SIZE;
+        assert!(sp_offset < 65536);
+        dynasm!(self.0.ops ; str Q(reg(src_reg)), [sp, #(sp_offset)])
+    }
+    /// Copies the given input to `out_reg`
+    fn build_input(&mut self, out_reg: u8, src_arg: u8) {
+        dynasm!(self.0.ops ; mov V(reg(out_reg)).b16, V(src_arg as u32).b16);
+    }
+    fn build_var(&mut self, out_reg: u8, src_arg: u32) {
+        assert!(src_arg * 8 < 32768);
+        dynasm!(self.0.ops
+            ; ldr x15, [x0, #(src_arg * 8)]
+            ; dup V(reg(out_reg)).d2, x15
+        );
+    }
+    fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_sin(v: Interval64) -> Interval64 {
+            v.sin()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_sin);
+    }
+    fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_cos(v: Interval64) -> Interval64 {
+            v.cos()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_cos);
+    }
+    fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_tan(v: Interval64) -> Interval64 {
+            v.tan()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_tan);
+    }
+    fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_asin(v: Interval64) -> Interval64 {
+            v.asin()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_asin);
+    }
+    fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_acos(v: Interval64) -> Interval64 {
+            v.acos()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_acos);
+    }
+    fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_atan(v: Interval64) -> Interval64 {
+            v.atan()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_atan);
+    }
+    fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_exp(v: Interval64) -> Interval64 {
+            v.exp()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_exp);
+    }
+    fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_ln(v: Interval64) -> Interval64 {
+            v.ln()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_ln);
+    }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Rounding is monotonic, so we can round both bounds in place
+        dynasm!(self.0.ops ; frintm V(reg(out_reg)).d2, V(reg(lhs_reg)).d2)
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; frintp V(reg(out_reg)).d2, V(reg(lhs_reg)).d2)
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; frinta V(reg(out_reg)).d2, V(reg(lhs_reg)).d2)
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_not(v: Interval64) -> Interval64 {
+            !v
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_not);
+    }
+    fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; mov V(reg(out_reg)).b16, V(reg(lhs_reg)).b16)
+    }
+    fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; fneg v4.d2, V(reg(lhs_reg)).d2
+            ; mov V(reg(out_reg)).d[0], v4.d[1]
+            ; mov V(reg(out_reg)).d[1], v4.d[0]
+        )
+    }
+    fn build_abs(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_abs(v: Interval64) -> Interval64 {
+            v.abs()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_abs);
+    }
+    fn build_recip(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_recip(v: Interval64) -> Interval64 {
+            v.recip()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_recip);
+    }
+    fn build_sqrt(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_sqrt(v: Interval64) -> Interval64 {
+            v.sqrt()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_sqrt);
+    }
+    fn build_square(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_square(v: Interval64) -> Interval64 {
+            v.square()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_square);
+    }
+    fn build_add(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; fadd V(reg(out_reg)).d2, V(reg(lhs_reg)).d2, V(reg(rhs_reg)).d2
+        )
+    }
+    fn build_sub(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Swap the bounds of rhs, then subtract
+            ; mov v4.d[0], V(reg(rhs_reg)).d[1]
+            ; mov v4.d[1], V(reg(rhs_reg)).d[0]
+            ; fsub V(reg(out_reg)).d2, V(reg(lhs_reg)).d2, v4.d2
+        )
+    }
+    fn build_sub_reg_imm(&mut self, out_reg: u8, arg: u8, imm: f64) {
+        let imm = self.load_imm(imm);
+        dynasm!(self.0.ops
+            ; fsub V(reg(out_reg)).d2, V(reg(arg)).d2, V(reg(imm)).d2
+        )
+    }
+    fn build_mul(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Set up v4 to contain
+            //  [lhs.lower * rhs.lower, lhs.lower * rhs.upper]
+            // and v5 to contain
+            //  [lhs.upper * rhs.lower, lhs.upper * rhs.upper]
+            //
+            // This hits all four possible combinations; then we extract the
+            // min and max with pairwise and vector-reducing operations
+            ; dup v4.d2, V(reg(lhs_reg)).d[0]
+            ; fmul v4.d2, v4.d2, V(reg(rhs_reg)).d2
+            ; dup v5.d2, V(reg(lhs_reg)).d[1]
+            ; fmul v5.d2, v5.d2, V(reg(rhs_reg)).d2
+
+            ; fminnm v6.d2, v4.d2, v5.d2
+            ; fmaxnm v7.d2, v4.d2, v5.d2
+            ; fminnmp d6, v6.d2
+            ; fmaxnmp d7, v7.d2
+            ; mov V(reg(out_reg)).d[0], v6.d[0]
+            ; mov V(reg(out_reg)).d[1], v7.d[0]
+        )
+    }
+
+    fn build_mul_imm(&mut self, out_reg: u8, lhs_reg: u8, imm: f64) {
+        let rhs_reg = self.load_imm(imm);
+        dynasm!(self.0.ops
+            ; fmul V(reg(out_reg)).d2, V(reg(lhs_reg)).d2, V(reg(rhs_reg)).d2
+        );
+        if imm < 0.0 {
+            dynasm!(self.0.ops
+                ; mov v4.b16, V(reg(out_reg)).b16
+                ; mov V(reg(out_reg)).d[0], v4.d[1]
+                ; mov V(reg(out_reg)).d[1], v4.d[0]
+            );
+        }
+    }
+    fn build_div(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn interval_div(
+            lhs: Interval64,
+            rhs: Interval64,
+        ) -> Interval64 {
+            lhs / rhs
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_div);
+    }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn interval_atan2(
+            y: Interval64,
+            x: Interval64,
+        ) -> Interval64 {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn interval_mod(
+            lhs: Interval64,
+            rhs: Interval64,
+        ) -> Interval64 {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn interval_compare(
+            lhs: Interval64,
+            rhs: Interval64,
+        ) -> Interval64 {
+            lhs.compare(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            //  if lhs == [0, 0]
+            //      *choices++ |= CHOICE_LEFT
+            //      out = lhs
+            //  elif !lhs.contains(0)
+            //      *choices++ |= CHOICE_RIGHT
+            //      out = rhs
+            //  else
+            //      *choices++ |= CHOICE_BOTH
+            //      out = [min(rhs.lower, 0), max(rhs.upper, 0)]
+            ; ldrb w14, [x1]
+            ; mov d4, V(reg(lhs_reg)).d[1]
+
+            ; fcmp D(reg(lhs_reg)), 0.0
+            ; b.gt 44 // -> nonzero
+            ; fcmp d4, 0.0
+            ; b.mi 36 // -> nonzero
+
+            // NaN is unordered, so it's caught by these b.ne checks
+            ; fcmp D(reg(lhs_reg)), 0.0
+            ; b.ne 44 // -> both
+            ; fcmp d4, 0.0
+            ; b.ne 36 // -> both
+
+            // Fallthrough: lhs is exactly zero
+            ; mov V(reg(out_reg)).b16, V(reg(lhs_reg)).b16
+            ; orr w14, w14, #CHOICE_LEFT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 44 // -> end
+
+            // <- nonzero
+            ; mov V(reg(out_reg)).b16, V(reg(rhs_reg)).b16
+            ; orr w14, w14, #CHOICE_RIGHT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 28 // -> end
+
+            // <- both
+            ; movi d4, 0
+            ; fmin v5.d2, V(reg(rhs_reg)).d2, v4.d2
+            ; fmax v4.d2, V(reg(rhs_reg)).d2, v4.d2
+            ; mov v5.d[1], v4.d[1]
+            ; mov V(reg(out_reg)).b16, v5.b16
+            ; orr w14, w14, #CHOICE_BOTH
+
+            // <- end
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            //  if lhs == [0, 0]
+            //      *choices++ |= CHOICE_RIGHT
+            //      out = rhs
+            //  elif !lhs.contains(0)
+            //      *choices++ |= CHOICE_LEFT
+            //      out = lhs
+            //  else
+            //      *choices++ |= CHOICE_BOTH
+            //      out = [min(lhs.lower, rhs.lower), max(lhs.upper, rhs.upper)]
+            ; ldrb w14, [x1]
+            ; mov d4, V(reg(lhs_reg)).d[1]
+
+            ; fcmp D(reg(lhs_reg)), 0.0
+            ; b.gt 44 // -> nonzero
+            ; fcmp d4, 0.0
+            ; b.mi 36 // -> nonzero
+
+            // NaN is unordered, so it's caught by these b.ne checks
+            ; fcmp D(reg(lhs_reg)), 0.0
+            ; b.ne 44 // -> both
+            ; fcmp d4, 0.0
+            ; b.ne 36 // -> both
+
+            // Fallthrough: lhs is exactly zero
+            ; mov V(reg(out_reg)).b16, V(reg(rhs_reg)).b16
+            ; orr w14, w14, #CHOICE_RIGHT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 40 // -> end
+
+            // <- nonzero
+            ; mov V(reg(out_reg)).b16, V(reg(lhs_reg)).b16
+            ; orr w14, w14, #CHOICE_LEFT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 24 // -> end
+
+            // <- both (fmin and fmax propagate NaN)
+            ; fmin v4.d2, V(reg(lhs_reg)).d2, V(reg(rhs_reg)).d2
+            ; fmax v5.d2, V(reg(lhs_reg)).d2, V(reg(rhs_reg)).d2
+            ; mov v4.d[1], v5.d[1]
+            ; mov V(reg(out_reg)).b16, v4.b16
+            ; orr w14, w14, #CHOICE_BOTH
+
+            // <- end
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
+    fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Basically the same as MinRegReg
+            ; zip2 v4.d2, V(reg(lhs_reg)).d2, V(reg(rhs_reg)).d2
+            ; zip1 v5.d2, V(reg(rhs_reg)).d2, V(reg(lhs_reg)).d2
+            ; fcmgt v5.d2, v5.d2, v4.d2
+            ; xtn v5.s2, v5.d2
+            ; fmov x15, d5
+            ; ldrb w14, [x1]
+
+            ; tst x15, 0x1_0000_0000
+            ; b.ne 28 // -> lhs
+
+            ; tst x15, 0x1
+            ; b.eq 36 // -> both
+
+            // LHS < RHS
+            ; mov V(reg(out_reg)).b16, V(reg(rhs_reg)).b16
+            ; orr w14, w14, #CHOICE_RIGHT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 28 // -> end
+
+            // <- lhs (when RHS < LHS)
+            ; mov V(reg(out_reg)).b16, V(reg(lhs_reg)).b16
+            ; orr w14, w14, #CHOICE_LEFT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 12 // -> end
+
+            // <- both
+            ; fmax V(reg(out_reg)).d2, V(reg(lhs_reg)).d2, V(reg(rhs_reg)).d2
+            ; orr w14, w14, #CHOICE_BOTH
+
+            // <- end
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
+    fn build_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            //  if lhs.upper < rhs.lower
+            //      *choices++ |= CHOICE_LEFT
+            //      out = lhs
+            //  elif rhs.upper < lhs.lower
+            //      *choices++ |= CHOICE_RIGHT
+            //      out = rhs
+            //  else
+            //      *choices++ |= CHOICE_BOTH
+            //      out = fmin(lhs, rhs)
+
+            // v4 = [lhs.upper, rhs.upper]
+            // v5 = [rhs.lower, lhs.lower]
+            // This lets us do two comparisons simultaneously
+            ; zip2 v4.d2, V(reg(lhs_reg)).d2, V(reg(rhs_reg)).d2
+            ; zip1 v5.d2, V(reg(rhs_reg)).d2, V(reg(lhs_reg)).d2
+
+            // v5 = [rhs.lower > lhs.upper, lhs.lower > rhs.upper], narrowed
+            // to 32-bit lanes so that both results fit into x15
+            ; fcmgt v5.d2, v5.d2, v4.d2
+            ; xtn v5.s2, v5.d2
+            ; fmov x15, d5
+            ; ldrb w14, [x1]
+
+            ; tst x15, 0x1_0000_0000
+            ; b.ne 28 // -> rhs
+
+            ; tst x15, 0x1
+            ; b.eq 36 // -> both
+
+            // Fallthrough: LHS < RHS
+            ; mov V(reg(out_reg)).b16, V(reg(lhs_reg)).b16
+            ; orr w14, w14, #CHOICE_LEFT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 28 // -> end
+
+            // <- rhs (for when RHS < LHS)
+            ; mov V(reg(out_reg)).b16, V(reg(rhs_reg)).b16
+            ; orr w14, w14, #CHOICE_RIGHT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 12
+
+            // <- both
+            ; fmin V(reg(out_reg)).d2, V(reg(lhs_reg)).d2, V(reg(rhs_reg)).d2
+            ; orr w14, w14, #CHOICE_BOTH
+
+            // <- end
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
+
+    /// Loads an immediate into register V3, using X15 as an intermediary
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        let imm_u64 = imm.to_bits();
+        dynasm!(self.0.ops
+            ; movz x15, #((imm_u64 >> 48) as u32), lsl 48
+            ; movk x15, #((imm_u64 >> 32) as u32), lsl 32
+            ; movk x15, #((imm_u64 >> 16) as u32), lsl 16
+            ; movk x15, #(imm_u64 as u32)
+            ; dup V(IMM_REG as u32).d2, x15
+        );
+        IMM_REG.wrapping_sub(OFFSET)
+    }
+
+    fn finalize(mut self, out_reg: u8) -> Result<Mmap, Error> {
+        assert!(self.0.mem_offset < 4096);
+        if self.0.saved_callee_regs {
+            dynasm!(self.0.ops
+                // Restore callee-saved registers (using `ldr` because we're
+                // outside the range for `ldp`)
+                ; ldr x20, [sp, 0x200]
+                ; ldr x21, [sp, 0x208]
+                ; ldr x22, [sp, 0x210]
+            )
+        }
+        dynasm!(self.0.ops
+            // Prepare our return value
+            ; mov  d0, V(reg(out_reg)).d[0]
+            ; mov  d1, V(reg(out_reg)).d[1]
+
+            // Restore frame and link register
+            ; ldp   x29, x30, [sp, 0x0]
+
+            // Restore callee-saved floating-point registers
+            ; ldp   d8, d9, [sp, 0x10]
+            ; ldp   d10, d11, [sp, 0x20]
+            ; ldp   d12, d13, [sp, 0x30]
+            ; ldp   d14, d15, [sp, 0x40]
+
+            // Fix up the stack
+            ; add sp, sp, #(self.0.mem_offset as u32)
+
+            ; ret
+        );
+
+        self.0.ops.finalize()
+    }
+}
+
+impl Interval64Assembler {
+    fn call_fn_unary(
+        &mut self,
+        out_reg: u8,
+        arg_reg: u8,
+        f: extern "C" fn(Interval64) -> Interval64,
+    ) {
+        self.call_fn_prelude(f as usize);
+        dynasm!(self.0.ops
+            // Prepare to call our stuff!
+            ; mov d0, V(reg(arg_reg)).d[0]
+            ; mov d1, V(reg(arg_reg)).d[1]
+
+            ; blr x0
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "C" fn(Interval64, Interval64) -> Interval64,
+    ) {
+        self.call_fn_prelude(f as usize);
+        dynasm!(self.0.ops
+            // Prepare to call our stuff!  The LHS is unpacked first, because
+            // the RHS writes to v3 (which may be the LHS immediate register)
+            ; mov d0, V(reg(lhs_reg)).d[0]
+            ; mov d1, V(reg(lhs_reg)).d[1]
+            ; mov d2, V(reg(rhs_reg)).d[0]
+            ; mov d3, V(reg(rhs_reg)).d[1]
+
+            ; blr x0
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves register state before a function call, loading the function
+    /// address into `x0`
+    fn call_fn_prelude(&mut self, addr: usize) {
+        if !self.0.saved_callee_regs {
+            dynasm!(self.0.ops
+                // Back up a few callee-saved registers that we're about to use
+                // (using `str` because we're outside the range for `stp`)
+                ; str x20, [sp, 0x200]
+                ; str x21, [sp, 0x208]
+                ; str x22, [sp, 0x210]
+            );
+            self.0.saved_callee_regs = true;
+        }
+
+        dynasm!(self.0.ops
+            // Back up our current state to callee-saved registers
+            ; mov x20, x0
+            ; mov x21, x1
+            ; mov x22, x2
+
+            // Back up X/Y/Z values
+            ; stp q0, q1, [sp, 0x1d0]
+            ; str q2, [sp, 0x1f0]
+
+            // We use registers v8-v15 (callee saved, but only lower 64 bytes)
+            // and v16-v31 (caller saved)
+            ; stp q8, q9, [sp, 0x50]
+            ; stp q10, q11, [sp, 0x70]
+            ; stp q12, q13, [sp, 0x90]
+            ; stp q14, q15, [sp, 0xb0]
+            ; stp q16, q17, [sp, 0xd0]
+            ; stp q18, q19, [sp, 0xf0]
+            ; stp q20, q21, [sp, 0x110]
+            ; stp q22, q23, [sp, 0x130]
+            ; stp q24, q25, [sp, 0x150]
+            ; stp q26, q27, [sp, 0x170]
+            ; stp q28, q29, [sp, 0x190]
+            ; stp q30, q31, [sp, 0x1b0]
+
+            // Load the function address, awkwardly, into a caller-saved
+            // register (so we only need to do this once)
+            ; movz x0, #((addr >> 48) as u32), lsl 48
+            ; movk x0, #((addr >> 32) as u32), lsl 32
+            ; movk x0, #((addr >> 16) as u32), lsl 16
+            ; movk x0, #(addr as u32)
+        );
+    }
+
+    /// Restores register state after a function call, unpacking the result
+    /// into `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
+            // Restore floating-point state
+            ; ldp q8, q9, [sp, 0x50]
+            ; ldp q10, q11, [sp, 0x70]
+            ; ldp q12, q13, [sp, 0x90]
+            ; ldp q14, q15, [sp, 0xb0]
+            ; ldp q16, q17, [sp, 0xd0]
+            ; ldp q18, q19, [sp, 0xf0]
+            ; ldp q20, q21, [sp, 0x110]
+            ; ldp q22, q23, [sp, 0x130]
+            ; ldp q24, q25, [sp, 0x150]
+            ; ldp q26, q27, [sp, 0x170]
+            ; ldp q28, q29, [sp, 0x190]
+            ; ldp q30, q31, [sp, 0x1b0]
+
+            // Set our output value
+            ; mov V(reg(out_reg)).d[0], v0.d[0]
+            ; mov V(reg(out_reg)).d[1], v1.d[0]
+
+            // Restore X/Y/Z values
+            ; ldp q0, q1, [sp, 0x1d0]
+            ; ldr q2, [sp, 0x1f0]
+
+            // Restore registers
+            ; mov x0, x20
+            ; mov x1, x21
+            ; mov x2, x22
+        );
+    }
+}
@@ -26,6 +26,10 @@ pub const IMM_REG: u8 = 3;
 pub const OFFSET: u8 = 8;
 
 pub mod float_slice;
+pub mod float_slice64;
 pub mod grad_slice;
 pub mod interval;
+pub mod interval64;
 pub mod point;
+pub mod point64;
+pub mod robust_interval;
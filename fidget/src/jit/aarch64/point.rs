@@ -182,6 +182,23 @@ impl Assembler for PointAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; frintm S(reg(out_reg)), S(reg(lhs_reg)))
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; frintp S(reg(out_reg)), S(reg(lhs_reg)))
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Round half-way cases away from zero, matching `f32::round`
+        dynasm!(self.0.ops ; frinta S(reg(out_reg)), S(reg(lhs_reg)))
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; fcmp S(reg(lhs_reg)), 0.0
+            ; cset w9, eq // NaN is unordered, so this is false
+            ; ucvtf S(reg(out_reg)), w9
+        )
+    }
     fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops ; fneg S(reg(out_reg)), S(reg(lhs_reg)))
     }
@@ -220,6 +237,64 @@ impl Assembler for PointAssembler {
             ; fdiv S(reg(out_reg)), S(reg(lhs_reg)), S(reg(rhs_reg))
         )
     }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn float_atan2(y: f32, x: f32) -> f32 {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn float_mod(lhs: f32, rhs: f32) -> f32 {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn float_compare(lhs: f32, rhs: f32) -> f32 {
+            crate::vm::compare_f32(lhs, rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; ldrb w14, [x1]
+            ; fcmp S(reg(lhs_reg)), 0.0
+            ; b.ne 16 // -> RHS (NaN is unordered, so it's also nonzero)
+
+            // LHS is zero
+            ; fmov S(reg(out_reg)), S(reg(lhs_reg))
+            ; orr w14, w14, #CHOICE_LEFT
+            ; b 12 // -> end
+
+            // RHS
+            ; fmov S(reg(out_reg)), S(reg(rhs_reg))
+            ; orr w14, w14, #CHOICE_RIGHT
+
+            // <- end
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; ldrb w14, [x1]
+            ; fcmp S(reg(lhs_reg)), 0.0
+            ; b.ne 16 // -> LHS (NaN is unordered, so it's also nonzero)
+
+            // LHS is zero, so pick RHS
+            ; fmov S(reg(out_reg)), S(reg(rhs_reg))
+            ; orr w14, w14, #CHOICE_RIGHT
+            ; b 12 // -> end
+
+            // LHS
+            ; fmov S(reg(out_reg)), S(reg(lhs_reg))
+            ; orr w14, w14, #CHOICE_LEFT
+
+            // <- end
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             ; ldrb w14, [x1]
@@ -278,8 +353,8 @@ impl Assembler for PointAssembler {
     }
 
     /// Loads an immediate into register S4, using W9 as an intermediary
-    fn load_imm(&mut self, imm: f32) -> u8 {
-        let imm_u32 = imm.to_bits();
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        let imm_u32 = (imm as f32).to_bits();
         dynasm!(self.0.ops
             ; movz w9, #(imm_u32 >> 16), lsl 16
             ; movk w9, #(imm_u32)
@@ -326,6 +401,33 @@ impl PointAssembler {
         arg_reg: u8,
         f: extern "C" fn(f32) -> f32,
     ) {
+        self.call_fn_prelude(f as usize);
+        dynasm!(self.0.ops
+            ; fmov s0, S(reg(arg_reg))
+            ; blr x0
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "C" fn(f32, f32) -> f32,
+    ) {
+        self.call_fn_prelude(f as usize);
+        dynasm!(self.0.ops
+            ; fmov s1, S(reg(rhs_reg))
+            ; fmov s0, S(reg(lhs_reg))
+            ; blr x0
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves register state before a function call, loading the function
+    /// address into `x0`
+    fn call_fn_prelude(&mut self, addr: usize) {
         if !self.0.saved_callee_regs {
             dynasm!(self.0.ops
                 // Back up a few callee-saved registers that we're about to use
@@ -335,7 +437,6 @@ impl PointAssembler {
             self.0.saved_callee_regs = true;
         }
 
-        let addr = f as usize;
         dynasm!(self.0.ops
             // Back up our current state to callee-saved registers
             ; mov x20, x0
@@ -360,10 +461,13 @@ impl PointAssembler {
             ; movk x0, #((addr >> 32) as u32), lsl 32
             ; movk x0, #((addr >> 16) as u32), lsl 16
             ; movk x0, #(addr as u32)
+        );
+    }
 
-            ; fmov s0, S(reg(arg_reg))
-            ; blr x0
-
+    /// Restores register state after a function call, writing the result to
+    /// `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
             // Restore floating-point state
             ; ldp s16, s17, [sp, 0x50]
             ; ldp s18, s19, [sp, 0x58]
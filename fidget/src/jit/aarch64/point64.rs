@@ -0,0 +1,494 @@
+use crate::{
+    jit::{
+        mmap::Mmap, point64::Point64Assembler, reg, Assembler, AssemblerData,
+        CHOICE_BOTH, CHOICE_LEFT, CHOICE_RIGHT, IMM_REG, OFFSET,
+        REGISTER_LIMIT,
+    },
+    Error,
+};
+use dynasmrt::{dynasm, DynasmApi};
+
+/// Implementation for the double-precision single-point assembler on `aarch64`
+///
+/// Registers as pased in as follows:
+///
+/// | Variable   | Register | Type                  |
+/// |------------|----------|-----------------------|
+/// | X          | `d0`     | `f64`                 |
+/// | Y          | `d1`     | `f64`                 |
+/// | Z          | `d2`     | `f64`                 |
+/// | `vars`     | `x0`     | `*const f64` (array)  |
+/// | `out`      | `x1`     | `*mut u8` (array)     |
+/// | `count`    | `x2`     | `*mut u8` (single)    |
+///
+/// During evaluation, registers are identical.  In addition, we use the
+/// following registers during evaluation:
+///
+/// | Register | Description                                          |
+/// |----------|------------------------------------------------------|
+/// | `d3`     | Immediate value (`IMM_REG`)                          |
+/// | `d7`     | Immediate value for `recip` (1.0)                    |
+/// | `d8-15`  | Tape values (callee-saved)                           |
+/// | `d16-31` | Tape values (caller-saved)                           |
+/// | `x0`     | Function pointer for calls                           |
+/// | `x9`     | Staging for loading immediate                        |
+/// | `w14`    | Choice byte (limited scope)                          |
+/// | `x20`    | Backup for `x0` during function calls (callee-saved) |
+/// | `x21`    | Backup for `x1` during function calls (callee-saved) |
+/// | `x22`    | Backup for `x2` during function calls (callee-saved) |
+///
+/// The stack is configured as follows
+///
+/// ```text
+/// | Position | Value        | Notes                                       |
+/// |----------|--------------|---------------------------------------------|
+/// | 0xf8     | `x22`        | During functions calls, we use these        |
+/// | 0xf0     | `x21`        | as temporary storage so must preserve their |
+/// | 0xe8     | `x20`        | previous values on the stack                |
+/// |----------|--------------|---------------------------------------------|
+/// | 0xe0     | `d2`         | During functions calls, X/Y/Z are saved on  |
+/// | 0xd8     | `d1`         | the stack                                   |
+/// | 0xd0     | `d0`         |                                             |
+/// |----------|--------------|---------------------------------------------|
+/// | 0xc8     | `d31`        | During functions calls, caller-saved tape   |
+/// | 0xc0     | `d30`        | registers are saved on the stack            |
+/// | 0xb8     | `d29`        |                                             |
+/// | 0xb0     | `d28`        |                                             |
+/// | 0xa8     | `d27`        |                                             |
+/// | 0xa0     | `d26`        |                                             |
+/// | 0x98     | `d25`        |                                             |
+/// | 0x90     | `d24`        |                                             |
+/// | 0x88     | `d23`        |                                             |
+/// | 0x80     | `d22`        |                                             |
+/// | 0x78     | `d21`        |                                             |
+/// | 0x70     | `d20`        |                                             |
+/// | 0x68     | `d19`        |                                             |
+/// | 0x60     | `d18`        |                                             |
+/// | 0x58     | `d17`        |                                             |
+/// | 0x50     | `d16`        |                                             |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x48     | `d15`        | Callee-saved registers                      |
+/// | 0x40     | `d14`        |                                             |
+/// | 0x38     | `d13`        |                                             |
+/// | 0x30     | `d12`        |                                             |
+/// | 0x28     | `d11`        |                                             |
+/// | 0x20     | `d10`        |                                             |
+/// | 0x18     | `d9`         |                                             |
+/// | 0x10     | `d8`         |                                             |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x8      | `sp` (`x30`) | Stack frame                                 |
+/// | 0x0      | `fp` (`x29`) | [current value for sp]                      |
+/// ```
+const STACK_SIZE: u32 = 0x100;
+impl Assembler for Point64Assembler {
+    type Data = f64;
+
+    fn init(mmap: Mmap, slot_count: usize) -> Self {
+        let mut out = AssemblerData::new(mmap);
+        out.prepare_stack(slot_count, STACK_SIZE as usize);
+        dynasm!(out.ops
+            // Preserve frame and link register, and set up the frame pointer
+            ; stp   x29, x30, [sp, 0x0]
+            ; mov   x29, sp
+
+            // Preserve callee-saved floating-point registers
+            ; stp   d8, d9, [sp, 0x10]
+            ; stp   d10, d11, [sp, 0x20]
+            ; stp   d12, d13, [sp, 0x30]
+            ; stp   d14, d15, [sp, 0x40]
+        );
+
+        Self(out)
+    }
+
+    fn bytes_per_clause() -> usize {
+        10
+    }
+
+    /// Reads from `src_mem` to `dst_reg`
+    fn build_load(&mut self, dst_reg: u8, src_mem: u32) {
+        assert!((dst_reg as usize) < REGISTER_LIMIT);
+        let sp_offset = self.0.stack_pos(src_mem) + STACK_SIZE;
+        assert!(sp_offset <= 16384);
+        dynasm!(self.0.ops ; ldr D(reg(dst_reg)), [sp, #(sp_offset)])
+    }
+    /// Writes from `src_reg` to `dst_mem`
+    fn build_store(&mut self, dst_mem: u32, src_reg: u8) {
+        assert!((src_reg as usize) < REGISTER_LIMIT);
+        let sp_offset = self.0.stack_pos(dst_mem) + STACK_SIZE;
+        assert!(sp_offset <= 16384);
+        dynasm!(self.0.ops ; str D(reg(src_reg)), [sp, #(sp_offset)])
+    }
+    /// Copies the given input to `out_reg`
+    fn build_input(&mut self, out_reg: u8, src_arg: u8) {
+        dynasm!(self.0.ops ; fmov D(reg(out_reg)), D(src_arg as u32));
+    }
+    fn build_var(&mut self, out_reg: u8, src_arg: u32) {
+        assert!(src_arg * 8 < 32768);
+        dynasm!(self.0.ops
+            ; ldr D(reg(out_reg)), [x0, #(src_arg * 8)]
+        );
+    }
+    fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; fmov D(reg(out_reg)), D(reg(lhs_reg)))
+    }
+    fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn double_sin(f: f64) -> f64 {
+            f.sin()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_sin);
+    }
+    fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn double_cos(f: f64) -> f64 {
+            f.cos()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_cos);
+    }
+    fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn double_tan(f: f64) -> f64 {
+            f.tan()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_tan);
+    }
+    fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn double_asin(f: f64) -> f64 {
+            f.asin()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_asin);
+    }
+    fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn double_acos(f: f64) -> f64 {
+            f.acos()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_acos);
+    }
+    fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn double_atan(f: f64) -> f64 {
+            f.atan()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_atan);
+    }
+    fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn double_exp(f: f64) -> f64 {
+            f.exp()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_exp);
+    }
+    fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn double_ln(f: f64) -> f64 {
+            f.ln()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_ln);
+    }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; frintm D(reg(out_reg)), D(reg(lhs_reg)))
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; frintp D(reg(out_reg)), D(reg(lhs_reg)))
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Round half-way cases away from zero, matching `f64::round`
+        dynasm!(self.0.ops ; frinta D(reg(out_reg)), D(reg(lhs_reg)))
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; fcmp D(reg(lhs_reg)), 0.0
+            ; cset w9, eq // NaN is unordered, so this is false
+            ; ucvtf D(reg(out_reg)), w9
+        )
+    }
+    fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; fneg D(reg(out_reg)), D(reg(lhs_reg)))
+    }
+    fn build_abs(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; fabs D(reg(out_reg)), D(reg(lhs_reg)))
+    }
+    fn build_recip(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; fmov d7, 1.0
+            ; fdiv D(reg(out_reg)), d7, D(reg(lhs_reg))
+        )
+    }
+    fn build_sqrt(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; fsqrt D(reg(out_reg)), D(reg(lhs_reg)))
+    }
+    fn build_square(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops ; fmul D(reg(out_reg)), D(reg(lhs_reg)), D(reg(lhs_reg)))
+    }
+    fn build_add(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; fadd D(reg(out_reg)), D(reg(lhs_reg)), D(reg(rhs_reg))
+        )
+    }
+    fn build_sub(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; fsub D(reg(out_reg)), D(reg(lhs_reg)), D(reg(rhs_reg))
+        )
+    }
+    fn build_mul(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; fmul D(reg(out_reg)), D(reg(lhs_reg)), D(reg(rhs_reg))
+        )
+    }
+    fn build_div(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; fdiv D(reg(out_reg)), D(reg(lhs_reg)), D(reg(rhs_reg))
+        )
+    }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn double_atan2(y: f64, x: f64) -> f64 {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, double_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn double_mod(lhs: f64, rhs: f64) -> f64 {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, double_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn double_compare(lhs: f64, rhs: f64) -> f64 {
+            crate::vm::compare_f64(lhs, rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, double_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; ldrb w14, [x1]
+            ; fcmp D(reg(lhs_reg)), 0.0
+            ; b.ne 16 // -> RHS (NaN is unordered, so it's also nonzero)
+
+            // LHS is zero
+            ; fmov D(reg(out_reg)), D(reg(lhs_reg))
+            ; orr w14, w14, #CHOICE_LEFT
+            ; b 12 // -> end
+
+            // RHS
+            ; fmov D(reg(out_reg)), D(reg(rhs_reg))
+            ; orr w14, w14, #CHOICE_RIGHT
+
+            // <- end
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; ldrb w14, [x1]
+            ; fcmp D(reg(lhs_reg)), 0.0
+            ; b.ne 16 // -> LHS (NaN is unordered, so it's also nonzero)
+
+            // LHS is zero, so pick RHS
+            ; fmov D(reg(out_reg)), D(reg(rhs_reg))
+            ; orr w14, w14, #CHOICE_RIGHT
+            ; b 12 // -> end
+
+            // LHS
+            ; fmov D(reg(out_reg)), D(reg(lhs_reg))
+            ; orr w14, w14, #CHOICE_LEFT
+
+            // <- end
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
+    fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; ldrb w14, [x1]
+            ; fcmp D(reg(lhs_reg)), D(reg(rhs_reg))
+            ; b.mi 20 // -> RHS
+            ; b.gt 32 // -> LHS
+
+            // Equal or NaN; do the comparison to collapse NaNs
+            ; fmax D(reg(out_reg)), D(reg(lhs_reg)), D(reg(rhs_reg))
+            ; orr w14, w14, #CHOICE_BOTH
+            ; b 32 // -> end
+
+            // RHS
+            ; fmov D(reg(out_reg)), D(reg(rhs_reg))
+            ; orr w14, w14, #CHOICE_RIGHT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 16
+
+            // LHS
+            ; fmov D(reg(out_reg)), D(reg(lhs_reg))
+            ; orr w14, w14, #CHOICE_LEFT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            // fall-through to end
+
+            // <- end
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
+    fn build_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; ldrb w14, [x1]
+            ; fcmp D(reg(lhs_reg)), D(reg(rhs_reg))
+            ; b.mi 20
+            ; b.gt 32
+
+            // Equal or NaN; do the comparison to collapse NaNs
+            ; fmin D(reg(out_reg)), D(reg(lhs_reg)), D(reg(rhs_reg))
+            ; orr w14, w14, #CHOICE_BOTH
+            ; b 32 // -> end
+
+            // LHS
+            ; fmov D(reg(out_reg)), D(reg(lhs_reg))
+            ; orr w14, w14, #CHOICE_LEFT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 16
+
+            // RHS
+            ; fmov D(reg(out_reg)), D(reg(rhs_reg))
+            ; orr w14, w14, #CHOICE_RIGHT
+            ; strb w14, [x2, 0]
+            // fall-through to end
+
+            // <- end
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
+
+    /// Loads an immediate into register D3, using X9 as an intermediary
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        let imm_u64 = imm.to_bits();
+        dynasm!(self.0.ops
+            ; movz x9, #((imm_u64 >> 48) as u32), lsl 48
+            ; movk x9, #((imm_u64 >> 32) as u32), lsl 32
+            ; movk x9, #((imm_u64 >> 16) as u32), lsl 16
+            ; movk x9, #(imm_u64 as u32)
+            ; fmov D(IMM_REG as u32), x9
+        );
+        IMM_REG.wrapping_sub(OFFSET)
+    }
+
+    fn finalize(mut self, out_reg: u8) -> Result<Mmap, Error> {
+        if self.0.saved_callee_regs {
+            dynasm!(self.0.ops
+                // Restore callee-saved registers
+                ; ldp x20, x21, [sp, 0xe8]
+                ; ldr x22, [sp, 0xf8]
+            )
+        }
+        dynasm!(self.0.ops
+            // Prepare our return value
+            ; fmov  d0, D(reg(out_reg))
+
+            // Restore frame and link register
+            ; ldp   x29, x30, [sp, 0x0]
+
+            // Restore callee-saved floating-point registers
+            ; ldp   d8, d9, [sp, 0x10]
+            ; ldp   d10, d11, [sp, 0x20]
+            ; ldp   d12, d13, [sp, 0x30]
+            ; ldp   d14, d15, [sp, 0x40]
+
+            // Fix up the stack
+            ; add sp, sp, #(self.0.mem_offset as u32)
+
+            ; ret
+        );
+
+        self.0.ops.finalize()
+    }
+}
+
+impl Point64Assembler {
+    fn call_fn_unary(
+        &mut self,
+        out_reg: u8,
+        arg_reg: u8,
+        f: extern "C" fn(f64) -> f64,
+    ) {
+        self.call_fn_prelude(f as usize);
+        dynasm!(self.0.ops
+            ; fmov d0, D(reg(arg_reg))
+            ; blr x0
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "C" fn(f64, f64) -> f64,
+    ) {
+        self.call_fn_prelude(f as usize);
+        dynasm!(self.0.ops
+            ; fmov d1, D(reg(rhs_reg))
+            ; fmov d0, D(reg(lhs_reg))
+            ; blr x0
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves register state before a function call, loading the function
+    /// address into `x0`
+    fn call_fn_prelude(&mut self, addr: usize) {
+        if !self.0.saved_callee_regs {
+            dynasm!(self.0.ops
+                // Back up a few callee-saved registers that we're about to use
+                ; stp x20, x21, [sp, 0xe8]
+                ; str x22, [sp, 0xf8]
+            );
+            self.0.saved_callee_regs = true;
+        }
+
+        dynasm!(self.0.ops
+            // Back up our current state to callee-saved registers
+            ; mov x20, x0
+            ; mov x21, x1
+            ; mov x22, x2
+
+            // Back up our state
+            ; stp d16, d17, [sp, 0x50]
+            ; stp d18, d19, [sp, 0x60]
+            ; stp d20, d21, [sp, 0x70]
+            ; stp d22, d23, [sp, 0x80]
+            ; stp d24, d25, [sp, 0x90]
+            ; stp d26, d27, [sp, 0xa0]
+            ; stp d28, d29, [sp, 0xb0]
+            ; stp d30, d31, [sp, 0xc0]
+            ; stp d0, d1, [sp, 0xd0]
+            ; str d2, [sp, 0xe0]
+
+            // Load the function address, awkwardly, into x0
+            // (since it doesn't matter if it gets trashed)
+            ; movz x0, #((addr >> 48) as u32), lsl 48
+            ; movk x0, #((addr >> 32) as u32), lsl 32
+            ; movk x0, #((addr >> 16) as u32), lsl 16
+            ; movk x0, #(addr as u32)
+        );
+    }
+
+    /// Restores register state after a function call, writing the result to
+    /// `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
+            // Restore floating-point state
+            ; ldp d16, d17, [sp, 0x50]
+            ; ldp d18, d19, [sp, 0x60]
+            ; ldp d20, d21, [sp, 0x70]
+            ; ldp d22, d23, [sp, 0x80]
+            ; ldp d24, d25, [sp, 0x90]
+            ; ldp d26, d27, [sp, 0xa0]
+            ; ldp d28, d29, [sp, 0xb0]
+            ; ldp d30, d31, [sp, 0xc0]
+
+            // Set our output value
+            ; fmov D(reg(out_reg)), d0
+
+            // Restore X/Y/Z values
+            ; ldp d0, d1, [sp, 0xd0]
+            ; ldr d2, [sp, 0xe0]
+
+            // Restore registers
+            ; mov x0, x20
+            ; mov x1, x21
+            ; mov x2, x22
+        );
+    }
+}
@@ -0,0 +1,7 @@
+use crate::jit::{arch::float_slice64::SIMD_WIDTH, AssemblerData, SimdSize};
+
+pub struct FloatSlice64Assembler(pub(crate) AssemblerData<[f64; SIMD_WIDTH]>);
+
+impl SimdSize for f64 {
+    const SIMD_SIZE: usize = SIMD_WIDTH;
+}
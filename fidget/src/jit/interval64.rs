@@ -0,0 +1,3 @@
+use crate::jit::AssemblerData;
+
+pub struct Interval64Assembler(pub(crate) AssemblerData<[f64; 2]>);
@@ -21,16 +21,26 @@
 //! assert_eq!(r, 0.1 + 0.3);
 //! # Ok::<(), fidget::Error>(())
 //! ```
+//!
+//! Most evaluators are compiled for both `x86_64` and `aarch64`.  The exception
+//! is `JitMultiGradSliceEval`, which needs 256-bit AVX registers: it only
+//! exists on `x86_64`, and [`JitShape`] falls back to the VM evaluator (through
+//! [`VmFallback`]) on `aarch64`.  Affine, interval gradient, and Hessian
+//! evaluation use the VM on both architectures.
 
 use crate::{
     compiler::RegOp,
     context::{Context, Node},
     eval::{
-        types::{Grad, Interval},
-        BulkEvaluator, MathShape, Shape, ShapeVars, Tape, TracingEvaluator,
+        types::{Grad, Interval, Interval64},
+        BulkEvaluator, BulkOutput, GradTarget, MathShape, Shape, ShapeVars,
+        Tape, TracingEvaluator,
     },
     jit::mmap::Mmap,
-    vm::{Choice, GenericVmShape, VmData, VmTrace, VmWorkspace},
+    vm::{
+        Choice, GenericVmShape, VmAffineEval, VmData, VmHessianSliceEval,
+        VmIntervalGradEval, VmTrace, VmWorkspace,
+    },
     Error,
 };
 use dynasmrt::{
@@ -39,13 +49,24 @@ use dynasmrt::{
 };
 use std::collections::HashMap;
 
+#[cfg(target_arch = "x86_64")]
+use crate::eval::{types::MultiGrad, GradSeeds};
+#[cfg(target_arch = "aarch64")]
+use crate::vm::{VmMultiGradSliceEval, VmMultiGradTape};
+
 mod mmap;
 
 // Evaluators
 mod float_slice;
+mod float_slice64;
 mod grad_slice;
 mod interval;
+mod interval64;
+#[cfg(target_arch = "x86_64")]
+mod multi_grad_slice;
 mod point;
+mod point64;
+mod robust_interval;
 
 #[cfg(not(any(target_os = "linux", target_os = "macos")))]
 compile_error!(
@@ -171,6 +192,18 @@ trait Assembler {
     /// Natural log
     fn build_ln(&mut self, out_reg: u8, lhs_reg: u8);
 
+    /// Round down to the nearest integer
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8);
+
+    /// Round up to the nearest integer
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8);
+
+    /// Round to the nearest integer, with half-way cases rounded away from 0
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8);
+
+    /// Logical not, returning 1 if the input is zero and 0 otherwise
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8);
+
     /// Square
     ///
     /// This has a default implementation, but can be overloaded for efficiency;
@@ -192,6 +225,12 @@ trait Assembler {
     /// Division
     fn build_div(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
 
+    /// Two-argument arctangent, treating `lhs_reg` as `y` and `rhs_reg` as `x`
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
+
+    /// Least non-negative remainder of `lhs_reg / rhs_reg`
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
+
     /// Maximum of two values
     ///
     /// In a tracing evaluator, this function must also write to the `choices`
@@ -204,6 +243,22 @@ trait Assembler {
     /// array and may set `simplify` if one branch is always taken.
     fn build_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
 
+    /// Compares two values, returning -1, 0, or 1 (or `NaN` if either is
+    /// `NaN`)
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
+
+    /// Logical and, returning `lhs_reg` if it is zero and `rhs_reg` otherwise
+    ///
+    /// In a tracing evaluator, this function must also write to the `choices`
+    /// array and may set `simplify` if one branch is always taken.
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
+
+    /// Logical or, returning `lhs_reg` if it is nonzero and `rhs_reg` otherwise
+    ///
+    /// In a tracing evaluator, this function must also write to the `choices`
+    /// array and may set `simplify` if one branch is always taken.
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
+
     // Special-case functions for immediates.  In some cases, you can be more
     // efficient if you know that an argument is an immediate (for example, both
     // values in the interval will be the same, and it wlll have no gradients).
@@ -211,34 +266,34 @@ trait Assembler {
     /// Builds a addition (immediate + register)
     ///
     /// This has a default implementation, but can be overloaded for efficiency
-    fn build_add_imm(&mut self, out_reg: u8, lhs_reg: u8, imm: f32) {
+    fn build_add_imm(&mut self, out_reg: u8, lhs_reg: u8, imm: f64) {
         let imm = self.load_imm(imm);
         self.build_add(out_reg, lhs_reg, imm);
     }
     /// Builds a subtraction (immediate − register)
     ///
     /// This has a default implementation, but can be overloaded for efficiency
-    fn build_sub_imm_reg(&mut self, out_reg: u8, arg: u8, imm: f32) {
+    fn build_sub_imm_reg(&mut self, out_reg: u8, arg: u8, imm: f64) {
         let imm = self.load_imm(imm);
         self.build_sub(out_reg, imm, arg);
     }
     /// Builds a subtraction (register − immediate)
     ///
     /// This has a default implementation, but can be overloaded for efficiency
-    fn build_sub_reg_imm(&mut self, out_reg: u8, arg: u8, imm: f32) {
+    fn build_sub_reg_imm(&mut self, out_reg: u8, arg: u8, imm: f64) {
         let imm = self.load_imm(imm);
         self.build_sub(out_reg, arg, imm);
     }
     /// Builds a multiplication (register × immediate)
     ///
     /// This has a default implementation, but can be overloaded for efficiency
-    fn build_mul_imm(&mut self, out_reg: u8, lhs_reg: u8, imm: f32) {
+    fn build_mul_imm(&mut self, out_reg: u8, lhs_reg: u8, imm: f64) {
         let imm = self.load_imm(imm);
         self.build_mul(out_reg, lhs_reg, imm);
     }
 
     /// Loads an immediate into a register, returning that register
-    fn load_imm(&mut self, imm: f32) -> u8;
+    fn load_imm(&mut self, imm: f64) -> u8;
 
     /// Finalize the assembly code, returning a memory-mapped region
     fn finalize(self, out_reg: u8) -> Result<Mmap, Error>;
@@ -328,7 +383,7 @@ struct MmapAssembler {
     local_labels: [Option<AssemblyOffset>; 26],
 
     global_relocs: arrayvec::ArrayVec<(PatchLoc<Relocation>, u8), 2>,
-    local_relocs: arrayvec::ArrayVec<(PatchLoc<Relocation>, u8), 8>,
+    local_relocs: arrayvec::ArrayVec<(PatchLoc<Relocation>, u8), 16>,
 }
 
 impl Extend<u8> for MmapAssembler {
@@ -396,9 +451,9 @@ impl DynasmApi for MmapAssembler {
 ///
 /// - Labels must be a single character
 /// - Local labels must be committed before they're reused, using `commit_local`
-/// - Only 8 local jumps are available at any given time; this is reset when
+/// - Only 16 local jumps are available at any given time; this is reset when
 ///   `commit_local` is called.  (if this becomes problematic, it can be
-///   increased by tweaking the size of `local_relocs: ArrayVec<..., 8>`.
+///   increased by tweaking the size of `local_relocs: ArrayVec<..., 16>`.
 ///
 /// In exchange for these limitations, it allocates no memory at runtime, and all
 /// label lookups are done in constant time.
@@ -684,6 +739,18 @@ fn build_asm_fn_with_storage<A: Assembler>(
             RegOp::SquareReg(out, arg) => {
                 asm.build_square(out, arg);
             }
+            RegOp::FloorReg(out, arg) => {
+                asm.build_floor(out, arg);
+            }
+            RegOp::CeilReg(out, arg) => {
+                asm.build_ceil(out, arg);
+            }
+            RegOp::RoundReg(out, arg) => {
+                asm.build_round(out, arg);
+            }
+            RegOp::NotReg(out, arg) => {
+                asm.build_not(out, arg);
+            }
             RegOp::AddRegReg(out, lhs, rhs) => {
                 asm.build_add(out, lhs, rhs);
             }
@@ -702,6 +769,21 @@ fn build_asm_fn_with_storage<A: Assembler>(
             RegOp::MaxRegReg(out, lhs, rhs) => {
                 asm.build_max(out, lhs, rhs);
             }
+            RegOp::AtanRegReg(out, lhs, rhs) => {
+                asm.build_atan2(out, lhs, rhs);
+            }
+            RegOp::ModRegReg(out, lhs, rhs) => {
+                asm.build_mod(out, lhs, rhs);
+            }
+            RegOp::CompareRegReg(out, lhs, rhs) => {
+                asm.build_compare(out, lhs, rhs);
+            }
+            RegOp::AndRegReg(out, lhs, rhs) => {
+                asm.build_and(out, lhs, rhs);
+            }
+            RegOp::OrRegReg(out, lhs, rhs) => {
+                asm.build_or(out, lhs, rhs);
+            }
             RegOp::AddRegImm(out, arg, imm) => {
                 asm.build_add_imm(out, arg, imm);
             }
@@ -730,6 +812,38 @@ fn build_asm_fn_with_storage<A: Assembler>(
                 let reg = asm.load_imm(imm);
                 asm.build_max(out, arg, reg);
             }
+            RegOp::AtanRegImm(out, arg, imm) => {
+                let reg = asm.load_imm(imm);
+                asm.build_atan2(out, arg, reg);
+            }
+            RegOp::AtanImmReg(out, arg, imm) => {
+                let reg = asm.load_imm(imm);
+                asm.build_atan2(out, reg, arg);
+            }
+            RegOp::ModRegImm(out, arg, imm) => {
+                let reg = asm.load_imm(imm);
+                asm.build_mod(out, arg, reg);
+            }
+            RegOp::ModImmReg(out, arg, imm) => {
+                let reg = asm.load_imm(imm);
+                asm.build_mod(out, reg, arg);
+            }
+            RegOp::CompareRegImm(out, arg, imm) => {
+                let reg = asm.load_imm(imm);
+                asm.build_compare(out, arg, reg);
+            }
+            RegOp::CompareImmReg(out, arg, imm) => {
+                let reg = asm.load_imm(imm);
+                asm.build_compare(out, reg, arg);
+            }
+            RegOp::AndRegImm(out, arg, imm) => {
+                let reg = asm.load_imm(imm);
+                asm.build_and(out, arg, reg);
+            }
+            RegOp::OrRegImm(out, arg, imm) => {
+                let reg = asm.load_imm(imm);
+                asm.build_or(out, arg, reg);
+            }
             RegOp::CopyImm(out, imm) => {
                 let reg = asm.load_imm(imm);
                 asm.build_copy(out, reg);
@@ -742,14 +856,38 @@ fn build_asm_fn_with_storage<A: Assembler>(
 }
 
 /// Shape for use with a JIT evaluator
+///
+/// JIT shapes always have a single output, so their bulk evaluators return a
+/// [`BulkOutput`] with exactly one slice.  Multi-output tapes (from
+/// [`VmShape::new_multi`](crate::vm::GenericVmShape::new_multi)) are only
+/// supported by the VM evaluators.
 #[derive(Clone)]
 pub struct JitShape(GenericVmShape<REGISTER_LIMIT>);
 
 impl JitShape {
-    fn tracing_tape<A: Assembler>(
+    /// Builds a new shape which records the originating [`Node`] of every
+    /// operation
+    ///
+    /// JIT functions are compiled from the same register-allocated tape as the
+    /// VM, so the inner [`data`](JitShape::data) can be passed to
+    /// [`VmDebugEval`](crate::vm::VmDebugEval) to find the source of
+    /// non-finite values.
+    pub fn new_with_source_map(
+        ctx: &Context,
+        node: Node,
+    ) -> Result<Self, Error> {
+        GenericVmShape::new_with_source_map(ctx, &[node]).map(JitShape)
+    }
+
+    /// Borrows the inner [`VmData`], from which functions are compiled
+    pub fn data(&self) -> &VmData<REGISTER_LIMIT> {
+        self.0.data()
+    }
+
+    fn tracing_tape<A: Assembler, F>(
         &self,
         storage: Mmap,
-    ) -> JitTracingFn<A::Data> {
+    ) -> JitTracingFn<A::Data, F> {
         let f = build_asm_fn_with_storage::<A>(self.0.data(), storage);
         let ptr = f.as_ptr();
         JitTracingFn {
@@ -759,7 +897,10 @@ impl JitShape {
             fn_trace: unsafe { std::mem::transmute(ptr) },
         }
     }
-    fn bulk_tape<A: Assembler>(&self, storage: Mmap) -> JitBulkFn<A::Data> {
+    fn bulk_tape<A: Assembler, F>(
+        &self,
+        storage: Mmap,
+    ) -> JitBulkFn<A::Data, F> {
         let f = build_asm_fn_with_storage::<A>(self.0.data(), storage);
         let ptr = f.as_ptr();
         JitBulkFn {
@@ -778,24 +919,98 @@ impl Shape for JitShape {
     type TapeStorage = Mmap;
 
     type IntervalEval = JitIntervalEval;
+    type RobustIntervalEval = JitRobustIntervalEval;
+    type AffineEval = VmFallback<VmAffineEval<REGISTER_LIMIT>>;
+    type IntervalGradEval = VmFallback<VmIntervalGradEval<REGISTER_LIMIT>>;
     type PointEval = JitPointEval;
     type FloatSliceEval = JitFloatSliceEval;
     type GradSliceEval = JitGradSliceEval;
+    type HessianSliceEval = VmFallback<VmHessianSliceEval<REGISTER_LIMIT>>;
+    #[cfg(target_arch = "x86_64")]
+    type MultiGradSliceEval = JitMultiGradSliceEval;
+    #[cfg(target_arch = "aarch64")]
+    type MultiGradSliceEval = VmFallback<VmMultiGradSliceEval<REGISTER_LIMIT>>;
+    type PointEval64 = JitPointEval64;
+    type IntervalEval64 = JitIntervalEval64;
+    type FloatSliceEval64 = JitFloatSliceEval64;
 
     fn point_tape(&self, storage: Mmap) -> JitTracingFn<f32> {
-        self.tracing_tape::<point::PointAssembler>(storage)
+        self.tracing_tape::<point::PointAssembler, _>(storage)
     }
 
     fn interval_tape(&self, storage: Mmap) -> JitTracingFn<Interval> {
-        self.tracing_tape::<interval::IntervalAssembler>(storage)
+        self.tracing_tape::<interval::IntervalAssembler, _>(storage)
+    }
+
+    fn robust_interval_tape(&self, storage: Mmap) -> JitRobustIntervalFn {
+        JitRobustIntervalFn(
+            self.tracing_tape::<robust_interval::RobustIntervalAssembler, _>(
+                storage,
+            ),
+        )
+    }
+
+    fn affine_tape(
+        &self,
+        storage: Mmap,
+    ) -> VmFallbackTape<GenericVmShape<REGISTER_LIMIT>> {
+        VmFallbackTape::new(self.0.clone(), storage)
+    }
+
+    fn interval_grad_tape(
+        &self,
+        storage: Mmap,
+    ) -> VmFallbackTape<GenericVmShape<REGISTER_LIMIT>> {
+        VmFallbackTape::new(self.0.clone(), storage)
     }
 
     fn float_slice_tape(&self, storage: Mmap) -> JitBulkFn<f32> {
-        self.bulk_tape::<float_slice::FloatSliceAssembler>(storage)
+        self.bulk_tape::<float_slice::FloatSliceAssembler, _>(storage)
     }
 
     fn grad_slice_tape(&self, storage: Mmap) -> JitBulkFn<Grad> {
-        self.bulk_tape::<grad_slice::GradSliceAssembler>(storage)
+        self.bulk_tape::<grad_slice::GradSliceAssembler, _>(storage)
+    }
+
+    fn hessian_slice_tape(
+        &self,
+        storage: Mmap,
+    ) -> VmFallbackTape<GenericVmShape<REGISTER_LIMIT>> {
+        VmFallbackTape::new(self.0.clone(), storage)
+    }
+
+    fn point_tape64(&self, storage: Mmap) -> JitTracingFn<f64, f64> {
+        self.tracing_tape::<point64::Point64Assembler, _>(storage)
+    }
+
+    fn interval_tape64(&self, storage: Mmap) -> JitTracingFn<Interval64, f64> {
+        self.tracing_tape::<interval64::Interval64Assembler, _>(storage)
+    }
+
+    fn float_slice_tape64(&self, storage: Mmap) -> JitBulkFn<f64, f64> {
+        self.bulk_tape::<float_slice64::FloatSlice64Assembler, _>(storage)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    fn multi_grad_slice_tape(
+        &self,
+        targets: &[GradTarget],
+        storage: Mmap,
+    ) -> Result<JitMultiGradFn, Error> {
+        let seeds = GradSeeds::new(targets, self.0.vars())?;
+        let f = self
+            .bulk_tape::<multi_grad_slice::MultiGradSliceAssembler, _>(storage);
+        Ok(JitMultiGradFn { f, seeds })
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    fn multi_grad_slice_tape(
+        &self,
+        targets: &[GradTarget],
+        storage: Mmap,
+    ) -> Result<VmFallbackTape<VmMultiGradTape<REGISTER_LIMIT>>, Error> {
+        let tape = self.0.multi_grad_tape(targets)?;
+        Ok(VmFallbackTape::new(tape, storage))
     }
 
     fn simplify(
@@ -829,6 +1044,10 @@ impl Shape for JitShape {
     fn size(&self) -> usize {
         self.0.size()
     }
+
+    fn var_count(&self) -> usize {
+        self.0.var_count()
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -844,6 +1063,9 @@ macro_rules! jit_fn {
     (unsafe fn($($args:tt)*) -> $($out:tt)*) => {
         unsafe extern "sysv64" fn($($args)*) -> $($out)*
     };
+    (unsafe fn($($args:tt)*)) => {
+        unsafe extern "sysv64" fn($($args)*)
+    };
 }
 
 /// Macro to build a function type with the `extern "C"` calling convention
@@ -854,6 +1076,9 @@ macro_rules! jit_fn {
     (unsafe fn($($args:tt)*) -> $($out:tt)*) => {
         unsafe extern "C" fn($($args)*) -> $($out)*
     };
+    (unsafe fn($($args:tt)*)) => {
+        unsafe extern "C" fn($($args)*)
+    };
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -868,24 +1093,26 @@ struct JitTracingEval {
 }
 
 /// Handle to an owned function pointer for tracing evaluation
-pub struct JitTracingFn<T> {
+///
+/// `F` is the scalar type used for variable values (`f32` or `f64`).
+pub struct JitTracingFn<T, F = f32> {
     #[allow(unused)]
     mmap: Mmap,
     var_count: usize,
     choice_count: usize,
     fn_trace: jit_fn!(
         unsafe fn(
-            T,          // X
-            T,          // Y
-            T,          // Z
-            *const f32, // vars
-            *mut u8,    // choices
-            *mut u8,    // simplify (single boolean)
+            T,        // X
+            T,        // Y
+            T,        // Z
+            *const F, // vars
+            *mut u8,  // choices
+            *mut u8,  // simplify (single boolean)
         ) -> T
     ),
 }
 
-impl<T> Tape for JitTracingFn<T> {
+impl<T, F> Tape for JitTracingFn<T, F> {
     type Storage = Mmap;
     fn recycle(self) -> Self::Storage {
         self.mmap
@@ -894,18 +1121,18 @@ impl<T> Tape for JitTracingFn<T> {
 
 // SAFETY: there is no mutable state in a `JitTracingFn`, and the pointer
 // inside of it points to its own `Mmap`, which is owned by an `Arc`
-unsafe impl<T> Send for JitTracingFn<T> {}
-unsafe impl<T> Sync for JitTracingFn<T> {}
+unsafe impl<T, F> Send for JitTracingFn<T, F> {}
+unsafe impl<T, F> Sync for JitTracingFn<T, F> {}
 
 impl JitTracingEval {
     /// Evaluates a single point, capturing an evaluation trace
-    fn eval<T: From<f32>, F: Into<T>>(
+    fn eval<T, F: Into<T>, V>(
         &mut self,
-        tape: &JitTracingFn<T>,
+        tape: &JitTracingFn<T, V>,
         x: F,
         y: F,
         z: F,
-        vars: &[f32],
+        vars: &[V],
     ) -> (T, Option<&VmTrace>) {
         let x = x.into();
         let y = y.into();
@@ -956,6 +1183,114 @@ impl TracingEvaluator for JitIntervalEval {
     }
 }
 
+/// Handle to an owned function pointer for interval evaluation with outward
+/// rounding
+///
+/// This is a separate type from [`JitTracingFn<Interval>`](JitTracingFn), so
+/// that tapes built for [`JitIntervalEval`] can't be passed to
+/// [`JitRobustIntervalEval`].
+pub struct JitRobustIntervalFn(JitTracingFn<Interval>);
+
+impl Tape for JitRobustIntervalFn {
+    type Storage = Mmap;
+    fn recycle(self) -> Self::Storage {
+        self.0.recycle()
+    }
+}
+
+/// JIT-based tracing evaluator for interval values, with outward rounding
+///
+/// See [`VmRobustIntervalEval`](crate::vm::VmRobustIntervalEval) for details
+/// on how rounding is handled.
+#[derive(Default)]
+pub struct JitRobustIntervalEval(JitTracingEval);
+impl TracingEvaluator for JitRobustIntervalEval {
+    type Data = Interval;
+    type Tape = JitRobustIntervalFn;
+    type Trace = VmTrace;
+    type TapeStorage = Mmap;
+
+    fn eval<F: Into<Self::Data>>(
+        &mut self,
+        tape: &Self::Tape,
+        x: F,
+        y: F,
+        z: F,
+        vars: &[f32],
+    ) -> Result<(Self::Data, Option<&Self::Trace>), Error> {
+        self.check_arguments(vars, tape.0.var_count)?;
+        Ok(self.0.eval(&tape.0, x, y, z, vars))
+    }
+}
+
+/// Handle to a VM tape, for evaluators without a native JIT implementation
+///
+/// The unused `Mmap` is kept so that it can be recycled; it's wrapped in a
+/// `Mutex` (which is never locked) so that the tape is `Sync`.
+pub struct VmFallbackTape<T> {
+    mmap: std::sync::Mutex<Mmap>,
+    tape: T,
+}
+
+impl<T> VmFallbackTape<T> {
+    fn new(tape: T, mmap: Mmap) -> Self {
+        Self {
+            mmap: std::sync::Mutex::new(mmap),
+            tape,
+        }
+    }
+}
+
+impl<T> Tape for VmFallbackTape<T> {
+    type Storage = Mmap;
+    fn recycle(self) -> Self::Storage {
+        // The mutex is never locked, so it can't be poisoned
+        self.mmap.into_inner().unwrap()
+    }
+}
+
+/// Evaluator which falls back to the VM evaluator `E`
+///
+/// This is used for evaluation modes without a native JIT implementation, e.g.
+/// [`VmAffineEval`] or [`VmHessianSliceEval`].
+#[derive(Default)]
+pub struct VmFallback<E>(E);
+
+impl<E: TracingEvaluator<V>, V> TracingEvaluator<V> for VmFallback<E> {
+    type Data = E::Data;
+    type Tape = VmFallbackTape<E::Tape>;
+    type Trace = E::Trace;
+    type TapeStorage = Mmap;
+
+    fn eval<F: Into<Self::Data>>(
+        &mut self,
+        tape: &Self::Tape,
+        x: F,
+        y: F,
+        z: F,
+        vars: &[V],
+    ) -> Result<(Self::Data, Option<&Self::Trace>), Error> {
+        self.0.eval(&tape.tape, x, y, z, vars)
+    }
+}
+
+impl<E: BulkEvaluator<V>, V> BulkEvaluator<V> for VmFallback<E> {
+    type Data = E::Data;
+    type Tape = VmFallbackTape<E::Tape>;
+    type TapeStorage = Mmap;
+
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        xs: &[V],
+        ys: &[V],
+        zs: &[V],
+        vars: &[V],
+    ) -> Result<BulkOutput<'_, Self::Data>, Error> {
+        self.0.eval(&tape.tape, xs, ys, zs, vars)
+    }
+}
+
 /// JIT-based tracing evaluator for point values
 #[derive(Default)]
 pub struct JitPointEval(JitTracingEval);
@@ -978,26 +1313,73 @@ impl TracingEvaluator for JitPointEval {
     }
 }
 
+/// JIT-based tracing evaluator for double-precision interval values
+#[derive(Default)]
+pub struct JitIntervalEval64(JitTracingEval);
+impl TracingEvaluator<f64> for JitIntervalEval64 {
+    type Data = Interval64;
+    type Tape = JitTracingFn<Interval64, f64>;
+    type Trace = VmTrace;
+    type TapeStorage = Mmap;
+
+    fn eval<F: Into<Self::Data>>(
+        &mut self,
+        tape: &Self::Tape,
+        x: F,
+        y: F,
+        z: F,
+        vars: &[f64],
+    ) -> Result<(Self::Data, Option<&Self::Trace>), Error> {
+        self.check_arguments(vars, tape.var_count)?;
+        Ok(self.0.eval(tape, x, y, z, vars))
+    }
+}
+
+/// JIT-based tracing evaluator for double-precision point values
+#[derive(Default)]
+pub struct JitPointEval64(JitTracingEval);
+impl TracingEvaluator<f64> for JitPointEval64 {
+    type Data = f64;
+    type Tape = JitTracingFn<f64, f64>;
+    type Trace = VmTrace;
+    type TapeStorage = Mmap;
+
+    fn eval<F: Into<Self::Data>>(
+        &mut self,
+        tape: &Self::Tape,
+        x: F,
+        y: F,
+        z: F,
+        vars: &[f64],
+    ) -> Result<(Self::Data, Option<&Self::Trace>), Error> {
+        self.check_arguments(vars, tape.var_count)?;
+        Ok(self.0.eval(tape, x, y, z, vars))
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 /// Handle to an owned function pointer for bulk evaluation
-pub struct JitBulkFn<T> {
+///
+/// `F` is the scalar type used for inputs and variable values (`f32` or
+/// `f64`).
+pub struct JitBulkFn<T, F = f32> {
     #[allow(unused)]
     mmap: Mmap,
     var_count: usize,
     fn_bulk: jit_fn!(
         unsafe fn(
-            *const f32, // X
-            *const f32, // Y
-            *const f32, // Z
-            *const f32, // vars
-            *mut T,     // out
-            u64,        // size
-        ) -> T
+            *const F, // X
+            *const F, // Y
+            *const F, // Z
+            *const F, // vars
+            *mut T,   // out
+            u64,      // size
+        )
     ),
 }
 
-impl<T> Tape for JitBulkFn<T> {
+impl<T, F> Tape for JitBulkFn<T, F> {
     type Storage = Mmap;
     fn recycle(self) -> Self::Storage {
         self.mmap
@@ -1018,19 +1400,19 @@ impl<T> Default for JitBulkEval<T> {
 
 // SAFETY: there is no mutable state in a `JitBulkFn`, and the pointer
 // inside of it points to its own `Mmap`, which is owned by an `Arc`
-unsafe impl<T> Send for JitBulkFn<T> {}
-unsafe impl<T> Sync for JitBulkFn<T> {}
+unsafe impl<T, F> Send for JitBulkFn<T, F> {}
+unsafe impl<T, F> Sync for JitBulkFn<T, F> {}
 
 impl<T: From<f32> + Copy + SimdSize> JitBulkEval<T> {
     /// Evaluate multiple points
-    fn eval(
+    fn eval<F: Copy + Default>(
         &mut self,
-        tape: &JitBulkFn<T>,
-        xs: &[f32],
-        ys: &[f32],
-        zs: &[f32],
-        vars: &[f32],
-    ) -> &[T] {
+        tape: &JitBulkFn<T, F>,
+        xs: &[F],
+        ys: &[F],
+        zs: &[F],
+        vars: &[F],
+    ) -> BulkOutput<'_, T> {
         let n = xs.len();
         self.out.resize(n, f32::NAN.into());
         self.out.fill(f32::NAN.into());
@@ -1044,9 +1426,9 @@ impl<T: From<f32> + Copy + SimdSize> JitBulkEval<T> {
             // that should be optimized out; we can't use a constant assertion
             // here due to the same compiler limitations.
             const MAX_SIMD_WIDTH: usize = 8;
-            let mut x = [0.0; MAX_SIMD_WIDTH];
-            let mut y = [0.0; MAX_SIMD_WIDTH];
-            let mut z = [0.0; MAX_SIMD_WIDTH];
+            let mut x = [F::default(); MAX_SIMD_WIDTH];
+            let mut y = [F::default(); MAX_SIMD_WIDTH];
+            let mut z = [F::default(); MAX_SIMD_WIDTH];
             assert!(T::SIMD_SIZE <= MAX_SIMD_WIDTH);
 
             x[0..n].copy_from_slice(xs);
@@ -1097,7 +1479,7 @@ impl<T: From<f32> + Copy + SimdSize> JitBulkEval<T> {
                 }
             }
         }
-        &self.out
+        BulkOutput::new(std::slice::from_ref(&self.out), n)
     }
 }
 
@@ -1116,7 +1498,29 @@ impl BulkEvaluator for JitFloatSliceEval {
         ys: &[f32],
         zs: &[f32],
         vars: &[f32],
-    ) -> Result<&[Self::Data], Error> {
+    ) -> Result<BulkOutput<'_, Self::Data>, Error> {
+        self.check_arguments(xs, ys, zs, vars, tape.var_count)?;
+        Ok(self.0.eval(tape, xs, ys, zs, vars))
+    }
+}
+
+/// JIT-based bulk evaluator for arrays of points, yielding double-precision
+/// point values
+#[derive(Default)]
+pub struct JitFloatSliceEval64(JitBulkEval<f64>);
+impl BulkEvaluator<f64> for JitFloatSliceEval64 {
+    type Data = f64;
+    type Tape = JitBulkFn<Self::Data, f64>;
+    type TapeStorage = Mmap;
+
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        xs: &[f64],
+        ys: &[f64],
+        zs: &[f64],
+        vars: &[f64],
+    ) -> Result<BulkOutput<'_, Self::Data>, Error> {
         self.check_arguments(xs, ys, zs, vars, tape.var_count)?;
         Ok(self.0.eval(tape, xs, ys, zs, vars))
     }
@@ -1137,12 +1541,84 @@ impl BulkEvaluator for JitGradSliceEval {
         ys: &[f32],
         zs: &[f32],
         vars: &[f32],
-    ) -> Result<&[Self::Data], Error> {
+    ) -> Result<BulkOutput<'_, Self::Data>, Error> {
         self.check_arguments(xs, ys, zs, vars, tape.var_count)?;
         Ok(self.0.eval(tape, xs, ys, zs, vars))
     }
 }
 
+/// Handle to an owned function pointer for multi-target gradient evaluation
+///
+/// The function itself is independent of the differentiation targets, which
+/// are passed in as derivative seeds alongside variable values.
+#[cfg(target_arch = "x86_64")]
+pub struct JitMultiGradFn {
+    f: JitBulkFn<MultiGrad>,
+    seeds: GradSeeds,
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Tape for JitMultiGradFn {
+    type Storage = Mmap;
+    fn recycle(self) -> Self::Storage {
+        self.f.recycle()
+    }
+}
+
+/// JIT-based bulk evaluator for arrays of points, yielding gradient values
+/// with respect to configurable targets
+///
+/// Each gradient is evaluated in a single 256-bit AVX register, so this is
+/// only available on `x86_64`.  On `aarch64`, [`JitShape`] uses
+/// [`VmMultiGradSliceEval`](crate::vm::VmMultiGradSliceEval) instead.
+#[cfg(target_arch = "x86_64")]
+#[derive(Default)]
+pub struct JitMultiGradSliceEval {
+    eval: JitBulkEval<MultiGrad>,
+
+    /// Seeds for inputs and variables, passed into the JIT function
+    seeds: Vec<MultiGrad>,
+}
+
+#[cfg(target_arch = "x86_64")]
+impl BulkEvaluator for JitMultiGradSliceEval {
+    type Data = MultiGrad;
+    type Tape = JitMultiGradFn;
+    type TapeStorage = Mmap;
+
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        xs: &[f32],
+        ys: &[f32],
+        zs: &[f32],
+        vars: &[f32],
+    ) -> Result<BulkOutput<'_, Self::Data>, Error> {
+        self.check_arguments(xs, ys, zs, vars, tape.f.var_count)?;
+
+        // Build the seed array: three input seeds, then one per variable
+        self.seeds.clear();
+        self.seeds
+            .extend((0..3).map(|i| MultiGrad::new(0.0, *tape.seeds.input(i))));
+        self.seeds.extend(
+            vars.iter()
+                .enumerate()
+                .map(|(i, v)| MultiGrad::new(*v, *tape.seeds.var(i as u32))),
+        );
+
+        // SAFETY: `MultiGrad` is `repr(C)` and made entirely of `f32` values,
+        // so it can be reinterpreted as a flat slice (which is passed into the
+        // JIT function as its `vars` argument).
+        let seeds = unsafe {
+            std::slice::from_raw_parts(
+                self.seeds.as_ptr() as *const f32,
+                self.seeds.len() * std::mem::size_of::<MultiGrad>() / 4,
+            )
+        };
+        Ok(self.eval.eval(&tape.f, xs, ys, zs, seeds))
+    }
+}
+
 impl MathShape for JitShape {
     fn new(ctx: &Context, node: Node) -> Result<Self, Error> {
         GenericVmShape::new(ctx, node).map(JitShape)
@@ -1161,7 +1637,40 @@ impl ShapeVars for JitShape {
 mod test {
     use super::*;
     crate::grad_slice_tests!(JitShape);
+    crate::hessian_slice_tests!(JitShape);
+    crate::multi_grad_slice_tests!(JitShape);
     crate::interval_tests!(JitShape);
+    crate::robust_interval_tests!(JitShape);
+    crate::affine_tests!(JitShape);
+    crate::interval_grad_tests!(JitShape);
     crate::float_slice_tests!(JitShape);
     crate::point_tests!(JitShape);
+    crate::f64_tests!(JitShape);
+
+    #[test]
+    fn test_debug_source_map() {
+        use crate::{
+            eval::{EzShape, TracingEvaluator},
+            vm::VmDebugEval,
+        };
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let d = ctx.div(x, y).unwrap();
+        let s = ctx.asin(d).unwrap();
+        let root = ctx.max(s, y).unwrap();
+
+        let shape = JitShape::new_with_source_map(&ctx, root).unwrap();
+        let mut eval = JitShape::new_point_eval();
+        let tape = shape.ez_point_tape();
+        let mut debug = VmDebugEval::new();
+        for (x, y, node) in
+            [(0.5, 1.0, None), (2.0, 1.0, Some(s)), (1.0, 0.0, Some(d))]
+        {
+            let (v, _) = eval.eval(&tape, x, y, 0.0, &[]).unwrap();
+            let (dv, bad) = debug.eval(shape.data(), x, y, 0.0, &[]).unwrap();
+            assert_eq!(v.to_bits(), dv.to_bits());
+            assert_eq!(bad.and_then(|b| b.node), node);
+        }
+    }
 }
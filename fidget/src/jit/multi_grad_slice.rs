@@ -0,0 +1,15 @@
+use crate::{
+    eval::types::MultiGrad,
+    jit::{AssemblerData, SimdSize},
+};
+
+/// Assembler for multi-target gradient evaluation
+///
+/// This is only implemented on `x86_64`, where a [`MultiGrad`] fits into a
+/// single 256-bit AVX register.
+pub struct MultiGradSliceAssembler(pub(crate) AssemblerData<MultiGrad>);
+
+// We process 1 gradient per (256-bit) register
+impl SimdSize for MultiGrad {
+    const SIMD_SIZE: usize = 1;
+}
@@ -0,0 +1,3 @@
+use crate::jit::AssemblerData;
+
+pub struct Point64Assembler(pub(crate) AssemblerData<f64>);
@@ -0,0 +1,7 @@
+use crate::jit::interval::IntervalAssembler;
+
+/// Interval assembler with outward rounding
+///
+/// This wraps an [`IntervalAssembler`], widening the result of every operation
+/// which may round.
+pub struct RobustIntervalAssembler(pub(crate) IntervalAssembler);
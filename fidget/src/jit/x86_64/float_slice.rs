@@ -48,6 +48,7 @@ pub const SIMD_WIDTH: usize = 8;
 /// |----------|--------------|---------------------------------------------|
 /// | ...      | ...          | Register spills live up here                |
 /// |----------|--------------|---------------------------------------------|
+/// | 0x1a0    | function in  | Second input for binary function calls      |
 /// | 0x180    | function i/o | Inputs and outputs for function calls       |
 /// |----------|--------------|---------------------------------------------|
 /// | 0x160    | ymm15        | Caller-saved registers during functions     |
@@ -180,6 +181,45 @@ impl Assembler for FloatSliceAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vroundps Ry(reg(out_reg)), Ry(reg(lhs_reg)), 1
+        );
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vroundps Ry(reg(out_reg)), Ry(reg(lhs_reg)), 2
+        );
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Add copysign(0.49999997, lhs) then truncate, which rounds half-way
+        // cases away from zero (matching `f32::round`)
+        dynasm!(self.0.ops
+            ; mov eax, 0x80000000u32 as i32
+            ; vmovd xmm1, eax
+            ; vbroadcastss ymm1, xmm1
+            ; vandps ymm1, ymm1, Ry(reg(lhs_reg))
+            ; mov eax, 0x3effffffu32 as i32
+            ; vmovd xmm2, eax
+            ; vbroadcastss ymm2, xmm2
+            ; vorps ymm1, ymm1, ymm2
+            ; vaddps ymm1, ymm1, Ry(reg(lhs_reg))
+            ; vroundps Ry(reg(out_reg)), ymm1, 3
+        );
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Build a mask of zeros (which is false for NaN)
+            ; vxorps ymm1, ymm1, ymm1
+            ; vcmpps ymm1, ymm1, Ry(reg(lhs_reg)), 0
+
+            // Build [1.0 x 8] in ymm2, then mask it
+            ; vpcmpeqw ymm2, ymm2, ymm2
+            ; vpslld ymm2, ymm2, 25
+            ; vpsrld ymm2, ymm2, 2
+            ; vandps Ry(reg(out_reg)), ymm1, ymm2
+        );
+    }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops
             ; vmovups Ry(reg(out_reg)), Ry(reg(lhs_reg))
@@ -238,6 +278,40 @@ impl Assembler for FloatSliceAssembler {
             ; vdivps Ry(reg(out_reg)), Ry(reg(lhs_reg)), Ry(reg(rhs_reg))
         );
     }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn float_atan2(y: f32, x: f32) -> f32 {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn float_mod(lhs: f32, rhs: f32) -> f32 {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn float_compare(lhs: f32, rhs: f32) -> f32 {
+            crate::vm::compare_f32(lhs, rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Build a mask of zeros in lhs, then pick lhs where it's set
+            ; vxorps ymm1, ymm1, ymm1
+            ; vcmpps ymm1, ymm1, Ry(reg(lhs_reg)), 0
+            ; vblendvps Ry(reg(out_reg)), Ry(reg(rhs_reg)), Ry(reg(lhs_reg)), ymm1
+        );
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Build a mask of zeros in lhs, then pick rhs where it's set
+            ; vxorps ymm1, ymm1, ymm1
+            ; vcmpps ymm1, ymm1, Ry(reg(lhs_reg)), 0
+            ; vblendvps Ry(reg(out_reg)), Ry(reg(lhs_reg)), Ry(reg(rhs_reg)), ymm1
+        );
+    }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             // Build a mask of NANs; conveniently, all 1s is a NAN
@@ -268,9 +342,9 @@ impl Assembler for FloatSliceAssembler {
             ; vorps Ry(reg(out_reg)), Ry(reg(out_reg)), ymm1
         );
     }
-    fn load_imm(&mut self, imm: f32) -> u8 {
+    fn load_imm(&mut self, imm: f64) -> u8 {
         dynasm!(self.0.ops
-            ; mov eax, imm.to_bits() as i32
+            ; mov eax, (imm as f32).to_bits() as i32
             ; vmovd Rx(IMM_REG), eax
             ; vbroadcastss Ry(IMM_REG), Rx(IMM_REG)
         );
@@ -306,58 +380,129 @@ impl FloatSliceAssembler {
     ) {
         let addr = f as usize;
         dynasm!(self.0.ops
-            // Back up X/Y/Z pointers to the stack
-            ; mov [rbp - 0x8], rdi
-            ; mov [rbp - 0x10], rsi
-            ; mov [rbp - 0x18], rdx
-            ; mov [rbp - 0x20], rcx
-            ; mov [rbp - 0x28], r8
-            ; mov [rbp - 0x30], r9
-            ; mov [rbp - 0x38], r15
+            // Store the argument to the stack before doing anything else
+            ; vmovups [rsp + 0x180], Ry(reg(arg_reg))
+        );
+        self.call_fn_prelude();
+        dynasm!(self.0.ops
+            // Put the function pointer into a caller-saved register
+            ; mov r15, QWORD addr as _
 
-            // Back up register values to the stack, saving all 128 bits
-            ; vmovups [rsp], ymm4
-            ; vmovups [rsp + 0x20], ymm5
-            ; vmovups [rsp + 0x40], ymm6
-            ; vmovups [rsp + 0x60], ymm7
-            ; vmovups [rsp + 0x80], ymm8
-            ; vmovups [rsp + 0xa0], ymm9
-            ; vmovups [rsp + 0xc0], ymm10
-            ; vmovups [rsp + 0xe0], ymm11
-            ; vmovups [rsp + 0x100], ymm12
-            ; vmovups [rsp + 0x120], ymm13
-            ; vmovups [rsp + 0x140], ymm14
-            ; vmovups [rsp + 0x160], ymm15
+            ; movd xmm0, [rsp + 0x180]
+            ; call r15
+            ; movd [rsp + 0x180], xmm0
+            ; movd xmm0, [rsp + 0x184]
+            ; call r15
+            ; movd [rsp + 0x184], xmm0
+            ; movd xmm0, [rsp + 0x188]
+            ; call r15
+            ; movd [rsp + 0x188], xmm0
+            ; movd xmm0, [rsp + 0x18c]
+            ; call r15
+            ; movd [rsp + 0x18c], xmm0
+            ; movd xmm0, [rsp + 0x190]
+            ; call r15
+            ; movd [rsp + 0x190], xmm0
+            ; movd xmm0, [rsp + 0x194]
+            ; call r15
+            ; movd [rsp + 0x194], xmm0
+            ; movd xmm0, [rsp + 0x198]
+            ; call r15
+            ; movd [rsp + 0x198], xmm0
+            ; movd xmm0, [rsp + 0x19c]
+            ; call r15
+            ; movd [rsp + 0x19c], xmm0
+        );
+        self.call_fn_postlude(out_reg);
+    }
 
+    fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "sysv64" fn(f32, f32) -> f32,
+    ) {
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // Store the arguments to the stack before doing anything else,
+            // since either one may be the immediate register (ymm0)
+            ; vmovups [rsp + 0x180], Ry(reg(lhs_reg))
+            ; vmovups [rsp + 0x1a0], Ry(reg(rhs_reg))
+        );
+        self.call_fn_prelude();
+        dynasm!(self.0.ops
             // Put the function pointer into a caller-saved register
             ; mov r15, QWORD addr as _
-            ; vmovups [rsp + 0x180], Ry(reg(arg_reg))
 
             ; movd xmm0, [rsp + 0x180]
+            ; movd xmm1, [rsp + 0x1a0]
             ; call r15
             ; movd [rsp + 0x180], xmm0
             ; movd xmm0, [rsp + 0x184]
+            ; movd xmm1, [rsp + 0x1a4]
             ; call r15
             ; movd [rsp + 0x184], xmm0
             ; movd xmm0, [rsp + 0x188]
+            ; movd xmm1, [rsp + 0x1a8]
             ; call r15
             ; movd [rsp + 0x188], xmm0
             ; movd xmm0, [rsp + 0x18c]
+            ; movd xmm1, [rsp + 0x1ac]
             ; call r15
             ; movd [rsp + 0x18c], xmm0
             ; movd xmm0, [rsp + 0x190]
+            ; movd xmm1, [rsp + 0x1b0]
             ; call r15
             ; movd [rsp + 0x190], xmm0
             ; movd xmm0, [rsp + 0x194]
+            ; movd xmm1, [rsp + 0x1b4]
             ; call r15
             ; movd [rsp + 0x194], xmm0
             ; movd xmm0, [rsp + 0x198]
+            ; movd xmm1, [rsp + 0x1b8]
             ; call r15
             ; movd [rsp + 0x198], xmm0
             ; movd xmm0, [rsp + 0x19c]
+            ; movd xmm1, [rsp + 0x1bc]
             ; call r15
             ; movd [rsp + 0x19c], xmm0
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves pointers and register state before a function call
+    fn call_fn_prelude(&mut self) {
+        dynasm!(self.0.ops
+            // Back up X/Y/Z pointers to the stack
+            ; mov [rbp - 0x8], rdi
+            ; mov [rbp - 0x10], rsi
+            ; mov [rbp - 0x18], rdx
+            ; mov [rbp - 0x20], rcx
+            ; mov [rbp - 0x28], r8
+            ; mov [rbp - 0x30], r9
+            ; mov [rbp - 0x38], r15
+
+            // Back up register values to the stack, saving all 128 bits
+            ; vmovups [rsp], ymm4
+            ; vmovups [rsp + 0x20], ymm5
+            ; vmovups [rsp + 0x40], ymm6
+            ; vmovups [rsp + 0x60], ymm7
+            ; vmovups [rsp + 0x80], ymm8
+            ; vmovups [rsp + 0xa0], ymm9
+            ; vmovups [rsp + 0xc0], ymm10
+            ; vmovups [rsp + 0xe0], ymm11
+            ; vmovups [rsp + 0x100], ymm12
+            ; vmovups [rsp + 0x120], ymm13
+            ; vmovups [rsp + 0x140], ymm14
+            ; vmovups [rsp + 0x160], ymm15
+        );
+    }
 
+    /// Restores pointers and register state after a function call, loading
+    /// the result (stored at `rsp + 0x180`) into `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
             // Restore float registers
             ; vmovups ymm4, [rsp]
             ; vmovups ymm5, [rsp + 0x20]
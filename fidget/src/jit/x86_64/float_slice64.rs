@@ -0,0 +1,504 @@
+use crate::jit::{
+    float_slice64::FloatSlice64Assembler, mmap::Mmap, reg, Assembler,
+    AssemblerData, Error, IMM_REG, OFFSET, REGISTER_LIMIT,
+};
+use dynasmrt::{dynasm, DynasmApi, DynasmLabelApi};
+
+pub const SIMD_WIDTH: usize = 4;
+
+/// Assembler for double-precision SIMD point-wise evaluation on `x86_64`
+///
+/// Arguments are passed as follows:
+///
+/// | Argument | Register | Type                |
+/// | ---------|----------|---------------------|
+/// | X        | `rdi`    | `*const [f64; 4]`   |
+/// | Y        | `rsi`    | `*const [f64; 4]`   |
+/// | Z        | `rdx`    | `*const [f64; 4]`   |
+/// | vars     | `rcx`    | `*const f64`        |
+/// | out      | `r8`     | `*mut [f64; 4]`     |
+/// | size     | `r9`     | `u64`               |
+///
+/// The arrays (other than `vars`) must be an even multiple of 4 doubles, since
+/// we're using AVX2 and 256-bit wide operations for everything.  The `vars`
+/// array contains single `f64` values, which are broadcast into SIMD registers
+/// when they are used.
+///
+/// During evaluation, X, Y, and Z values are stored on the stack to keep
+/// registers unoccupied.
+///
+/// The stack is configured as follows
+///
+/// ```text
+/// | Position | Value        | Notes                                       |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x00     | `rbp`        | Previous value for base pointer             |
+/// |----------|--------------|---------------------------------------------|
+/// | -0x08    | `rdi`        | During functions calls, we use these        |
+/// | -0x10    | `rsi`        | as temporary storage so must preserve their |
+/// | -0x18    | `rdx`        | previous values on the stack                |
+/// | -0x20    | `rcx`        |                                             |
+/// | -0x28    | `r8`         |                                             |
+/// | -0x30    | `r9`         |                                             |
+/// | -0x38    | `r15`        |                                             |
+/// |----------|--------------|---------------------------------------------|
+/// | -0x40    | Z            | Inputs (as 4x doubles)                       |
+/// | -0x60    | Y            |                                             |
+/// | -0x80    | X            |                                             |
+/// |----------|--------------|---------------------------------------------|
+/// | ...      | ...          | Register spills live up here                |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x1a0    | function in  | Second input for binary function calls      |
+/// | 0x180    | function i/o | Inputs and outputs for function calls       |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x160    | ymm15        | Caller-saved registers during functions     |
+/// | 0x140    | ymm14        | calls are placed here, then restored        |
+/// | 0x120    | ymm13        |                                             |
+/// | 0x100    | ymm12        |                                             |
+/// | 0xe0     | ymm11        |                                             |
+/// | 0xc0     | ymm10        |                                             |
+/// | 0xa0     | ymm9         |                                             |
+/// | 0x80     | ymm8         |                                             |
+/// | 0x60     | ymm7         |                                             |
+/// | 0x40     | ymm6         |                                             |
+/// | 0x20     | ymm5         |                                             |
+/// | 0x00     | ymm4         |                                             |
+/// ```
+const STACK_SIZE_UPPER: usize = 0x80; // Positions relative to `rbp`
+const STACK_SIZE_LOWER: usize = 0x200; // Positions relative to `rsp`
+
+impl Assembler for FloatSlice64Assembler {
+    type Data = f64;
+
+    fn init(mmap: Mmap, slot_count: usize) -> Self {
+        let mut out = AssemblerData::new(mmap);
+        dynasm!(out.ops
+            ; push rbp
+            ; mov rbp, rsp
+        );
+        out.prepare_stack(slot_count, STACK_SIZE_UPPER + STACK_SIZE_LOWER);
+        dynasm!(out.ops
+            // TODO should there be a `vzeroupper` in here?
+
+            // The loop returns here, and we check whether to keep looping
+            ; ->L:
+
+            ; test r9, r9
+            ; jz ->X // jump to the exit if we're done, otherwise fallthrough
+
+            // Copy from the input pointers into the stack
+            ; vmovupd ymm0, [rdi]
+            ; vmovupd [rbp - (STACK_SIZE_UPPER as i32)], ymm0
+            ; add rdi, 32
+
+            ; vmovupd ymm0, [rsi]
+            ; vmovupd [rbp - (STACK_SIZE_UPPER as i32 - 32)], ymm0
+            ; add rsi, 32
+
+            ; vmovupd ymm0, [rdx]
+            ; vmovupd [rbp - (STACK_SIZE_UPPER as i32 - 32 * 2)], ymm0
+            ; add rdx, 32
+        );
+        Self(out)
+    }
+    fn build_load(&mut self, dst_reg: u8, src_mem: u32) {
+        assert!((dst_reg as usize) < REGISTER_LIMIT);
+        let sp_offset: i32 = (self.0.stack_pos(src_mem)
+            + STACK_SIZE_LOWER as u32)
+            .try_into()
+            .unwrap();
+        dynasm!(self.0.ops
+            ; vmovupd Ry(reg(dst_reg)), [rsp + sp_offset]
+        );
+    }
+    fn build_store(&mut self, dst_mem: u32, src_reg: u8) {
+        assert!((src_reg as usize) < REGISTER_LIMIT);
+        let sp_offset: i32 = (self.0.stack_pos(dst_mem)
+            + STACK_SIZE_LOWER as u32)
+            .try_into()
+            .unwrap();
+        dynasm!(self.0.ops
+            ; vmovupd [rsp + sp_offset], Ry(reg(src_reg))
+        );
+    }
+    fn build_input(&mut self, out_reg: u8, src_arg: u8) {
+        let pos = STACK_SIZE_UPPER as i32 - 32 * (src_arg as i32);
+        dynasm!(self.0.ops
+            ; vmovupd Ry(reg(out_reg)), [rbp - pos]
+        );
+    }
+    fn build_var(&mut self, out_reg: u8, src_arg: u32) {
+        dynasm!(self.0.ops
+            ; vbroadcastsd Ry(reg(out_reg)), [rcx + 8 * (src_arg as i32)]
+        );
+    }
+    fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_sin(f: f64) -> f64 {
+            f.sin()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_sin);
+    }
+    fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_cos(f: f64) -> f64 {
+            f.cos()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_cos);
+    }
+    fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_tan(f: f64) -> f64 {
+            f.tan()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_tan);
+    }
+    fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_asin(f: f64) -> f64 {
+            f.asin()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_asin);
+    }
+    fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_acos(f: f64) -> f64 {
+            f.acos()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_acos);
+    }
+    fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_atan(f: f64) -> f64 {
+            f.atan()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_atan);
+    }
+    fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_exp(f: f64) -> f64 {
+            f.exp()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_exp);
+    }
+    fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_ln(f: f64) -> f64 {
+            f.ln()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_ln);
+    }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vroundpd Ry(reg(out_reg)), Ry(reg(lhs_reg)), 1
+        );
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vroundpd Ry(reg(out_reg)), Ry(reg(lhs_reg)), 2
+        );
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Add copysign(0.49999999999999994, lhs) then truncate, which rounds
+        // half-way cases away from zero (matching `f64::round`)
+        dynasm!(self.0.ops
+            ; mov rax, QWORD 0x8000000000000000u64 as i64
+            ; vmovq xmm1, rax
+            ; vbroadcastsd ymm1, xmm1
+            ; vandpd ymm1, ymm1, Ry(reg(lhs_reg))
+            ; mov rax, QWORD 0x3fdfffffffffffffu64 as i64
+            ; vmovq xmm2, rax
+            ; vbroadcastsd ymm2, xmm2
+            ; vorpd ymm1, ymm1, ymm2
+            ; vaddpd ymm1, ymm1, Ry(reg(lhs_reg))
+            ; vroundpd Ry(reg(out_reg)), ymm1, 3
+        );
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Build a mask of zeros (which is false for NaN)
+            ; vxorpd ymm1, ymm1, ymm1
+            ; vcmppd ymm1, ymm1, Ry(reg(lhs_reg)), 0
+
+            // Build [1.0 x 4] in ymm2, then mask it
+            ; mov rax, QWORD 1f64.to_bits() as i64
+            ; vmovq xmm2, rax
+            ; vbroadcastsd ymm2, xmm2
+            ; vandpd Ry(reg(out_reg)), ymm1, ymm2
+        );
+    }
+    fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vmovupd Ry(reg(out_reg)), Ry(reg(lhs_reg))
+        );
+    }
+    fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vpcmpeqw ymm0, ymm0, ymm0
+            ; vpsllq ymm0, ymm0, 63 // set the sign bit
+            ; vpxor Ry(reg(out_reg)), ymm0, Ry(reg(lhs_reg))
+        );
+    }
+    fn build_abs(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vpcmpeqw ymm0, ymm0, ymm0
+            ; vpsllq ymm0, ymm0, 63 // set the sign bit
+            ; vandnpd Ry(reg(out_reg)), ymm0, Ry(reg(lhs_reg))
+        );
+    }
+    fn build_recip(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Build [1.0 x 4] in ymm0
+            ; mov rax, QWORD 1f64.to_bits() as i64
+            ; vmovq xmm0, rax
+            ; vbroadcastsd ymm0, xmm0
+            ; vdivpd Ry(reg(out_reg)), ymm0, Ry(reg(lhs_reg))
+        );
+    }
+    fn build_sqrt(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vsqrtpd Ry(reg(out_reg)), Ry(reg(lhs_reg))
+        );
+    }
+    fn build_square(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vmulpd Ry(reg(out_reg)), Ry(reg(lhs_reg)), Ry(reg(lhs_reg))
+        );
+    }
+    fn build_add(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vaddpd Ry(reg(out_reg)), Ry(reg(lhs_reg)), Ry(reg(rhs_reg))
+        );
+    }
+    fn build_sub(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vsubpd Ry(reg(out_reg)), Ry(reg(lhs_reg)), Ry(reg(rhs_reg))
+        );
+    }
+    fn build_mul(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vmulpd Ry(reg(out_reg)), Ry(reg(lhs_reg)), Ry(reg(rhs_reg))
+        );
+    }
+    fn build_div(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vdivpd Ry(reg(out_reg)), Ry(reg(lhs_reg)), Ry(reg(rhs_reg))
+        );
+    }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn double_atan2(y: f64, x: f64) -> f64 {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, double_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn double_mod(lhs: f64, rhs: f64) -> f64 {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, double_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn double_compare(lhs: f64, rhs: f64) -> f64 {
+            crate::vm::compare_f64(lhs, rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, double_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Build a mask of zeros in lhs, then pick lhs where it's set
+            ; vxorpd ymm1, ymm1, ymm1
+            ; vcmppd ymm1, ymm1, Ry(reg(lhs_reg)), 0
+            ; vblendvpd Ry(reg(out_reg)), Ry(reg(rhs_reg)), Ry(reg(lhs_reg)), ymm1
+        );
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Build a mask of zeros in lhs, then pick rhs where it's set
+            ; vxorpd ymm1, ymm1, ymm1
+            ; vcmppd ymm1, ymm1, Ry(reg(lhs_reg)), 0
+            ; vblendvpd Ry(reg(out_reg)), Ry(reg(lhs_reg)), Ry(reg(rhs_reg)), ymm1
+        );
+    }
+    fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Build a mask of NANs; conveniently, all 1s is a NAN
+            ; vcmppd ymm1, Ry(reg(lhs_reg)), Ry(reg(lhs_reg)), 3
+            ; vcmppd ymm2, Ry(reg(rhs_reg)), Ry(reg(rhs_reg)), 3
+            ; vorpd ymm1, ymm2, ymm1
+
+            // Calculate the max, which ignores NANs
+            ; vmaxpd Ry(reg(out_reg)), Ry(reg(lhs_reg)), Ry(reg(rhs_reg))
+
+            // Set the NAN bits
+            ; vorpd Ry(reg(out_reg)), Ry(reg(out_reg)), ymm1
+        );
+    }
+    fn build_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Build a mask of NANs; conveniently, all 1s is a NAN
+            ; vcmppd ymm1, Ry(reg(lhs_reg)), Ry(reg(lhs_reg)), 3
+            ; vcmppd ymm2, Ry(reg(rhs_reg)), Ry(reg(rhs_reg)), 3
+            ; vorpd ymm1, ymm2, ymm1
+
+            // Calculate the min, which ignores NANs
+            ; vminpd Ry(reg(out_reg)), Ry(reg(lhs_reg)), Ry(reg(rhs_reg))
+
+            // Set the NAN bits
+            // (note that we leave other bits unchanged, because it doesn't
+            // matter here!)
+            ; vorpd Ry(reg(out_reg)), Ry(reg(out_reg)), ymm1
+        );
+    }
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        dynasm!(self.0.ops
+            ; mov rax, QWORD imm.to_bits() as i64
+            ; vmovq Rx(IMM_REG), rax
+            ; vbroadcastsd Ry(IMM_REG), Rx(IMM_REG)
+        );
+        IMM_REG.wrapping_sub(OFFSET)
+    }
+    fn finalize(mut self, out_reg: u8) -> Result<Mmap, Error> {
+        dynasm!(self.0.ops
+            // Copy data from out_reg into the out array, then adjust it
+            ; vmovupd [r8], Ry(reg(out_reg))
+            ; add r8, 32
+            ; sub r9, 4
+            ; jmp ->L
+
+            // Finalization code, which happens after all evaluation is complete
+            ; ->X:
+            ; add rsp, self.0.mem_offset as i32
+            ; pop rbp
+            ; emms
+            ; vzeroall
+            ; ret
+        );
+
+        self.0.ops.finalize()
+    }
+}
+
+impl FloatSlice64Assembler {
+    fn call_fn_unary(
+        &mut self,
+        out_reg: u8,
+        arg_reg: u8,
+        f: extern "sysv64" fn(f64) -> f64,
+    ) {
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // Store the argument to the stack before doing anything else
+            ; vmovupd [rsp + 0x180], Ry(reg(arg_reg))
+        );
+        self.call_fn_prelude();
+        dynasm!(self.0.ops
+            // Put the function pointer into a caller-saved register
+            ; mov r15, QWORD addr as _
+
+            ; movq xmm0, [rsp + 0x180]
+            ; call r15
+            ; movq [rsp + 0x180], xmm0
+            ; movq xmm0, [rsp + 0x188]
+            ; call r15
+            ; movq [rsp + 0x188], xmm0
+            ; movq xmm0, [rsp + 0x190]
+            ; call r15
+            ; movq [rsp + 0x190], xmm0
+            ; movq xmm0, [rsp + 0x198]
+            ; call r15
+            ; movq [rsp + 0x198], xmm0
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "sysv64" fn(f64, f64) -> f64,
+    ) {
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // Store the arguments to the stack before doing anything else,
+            // since either one may be the immediate register (ymm0)
+            ; vmovupd [rsp + 0x180], Ry(reg(lhs_reg))
+            ; vmovupd [rsp + 0x1a0], Ry(reg(rhs_reg))
+        );
+        self.call_fn_prelude();
+        dynasm!(self.0.ops
+            // Put the function pointer into a caller-saved register
+            ; mov r15, QWORD addr as _
+
+            ; movq xmm0, [rsp + 0x180]
+            ; movq xmm1, [rsp + 0x1a0]
+            ; call r15
+            ; movq [rsp + 0x180], xmm0
+            ; movq xmm0, [rsp + 0x188]
+            ; movq xmm1, [rsp + 0x1a8]
+            ; call r15
+            ; movq [rsp + 0x188], xmm0
+            ; movq xmm0, [rsp + 0x190]
+            ; movq xmm1, [rsp + 0x1b0]
+            ; call r15
+            ; movq [rsp + 0x190], xmm0
+            ; movq xmm0, [rsp + 0x198]
+            ; movq xmm1, [rsp + 0x1b8]
+            ; call r15
+            ; movq [rsp + 0x198], xmm0
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves pointers and register state before a function call
+    fn call_fn_prelude(&mut self) {
+        dynasm!(self.0.ops
+            // Back up X/Y/Z pointers to the stack
+            ; mov [rbp - 0x8], rdi
+            ; mov [rbp - 0x10], rsi
+            ; mov [rbp - 0x18], rdx
+            ; mov [rbp - 0x20], rcx
+            ; mov [rbp - 0x28], r8
+            ; mov [rbp - 0x30], r9
+            ; mov [rbp - 0x38], r15
+
+            // Back up register values to the stack, saving all 256 bits
+            ; vmovupd [rsp], ymm4
+            ; vmovupd [rsp + 0x20], ymm5
+            ; vmovupd [rsp + 0x40], ymm6
+            ; vmovupd [rsp + 0x60], ymm7
+            ; vmovupd [rsp + 0x80], ymm8
+            ; vmovupd [rsp + 0xa0], ymm9
+            ; vmovupd [rsp + 0xc0], ymm10
+            ; vmovupd [rsp + 0xe0], ymm11
+            ; vmovupd [rsp + 0x100], ymm12
+            ; vmovupd [rsp + 0x120], ymm13
+            ; vmovupd [rsp + 0x140], ymm14
+            ; vmovupd [rsp + 0x160], ymm15
+        );
+    }
+
+    /// Restores pointers and register state after a function call, loading
+    /// the result (stored at `rsp + 0x180`) into `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
+            // Restore float registers
+            ; vmovupd ymm4, [rsp]
+            ; vmovupd ymm5, [rsp + 0x20]
+            ; vmovupd ymm6, [rsp + 0x40]
+            ; vmovupd ymm7, [rsp + 0x60]
+            ; vmovupd ymm8, [rsp + 0x80]
+            ; vmovupd ymm9, [rsp + 0xa0]
+            ; vmovupd ymm10, [rsp + 0xc0]
+            ; vmovupd ymm11, [rsp + 0xe0]
+            ; vmovupd ymm12, [rsp + 0x100]
+            ; vmovupd ymm13, [rsp + 0x120]
+            ; vmovupd ymm14, [rsp + 0x140]
+            ; vmovupd ymm15, [rsp + 0x160]
+
+            // Get the output value from the stack
+            ; vmovupd Ry(reg(out_reg)), [rsp + 0x180]
+
+            // Restore X/Y/Z pointers
+            ; mov rdi, [rbp - 0x8]
+            ; mov rsi, [rbp - 0x10]
+            ; mov rdx, [rbp - 0x18]
+            ; mov rcx, [rbp - 0x20]
+            ; mov r8, [rbp - 0x28]
+            ; mov r9, [rbp - 0x30]
+            ; mov r15, [rbp - 0x38]
+        );
+    }
+}
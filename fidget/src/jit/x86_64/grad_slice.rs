@@ -187,6 +187,44 @@ impl Assembler for GradSliceAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        // The derivatives of a rounded value are zero (almost everywhere)
+        dynasm!(self.0.ops
+            ; vpxor xmm1, xmm1, xmm1
+            ; vroundss Rx(reg(out_reg)), xmm1, Rx(reg(lhs_reg)), 1
+        );
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vpxor xmm1, xmm1, xmm1
+            ; vroundss Rx(reg(out_reg)), xmm1, Rx(reg(lhs_reg)), 2
+        );
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Add copysign(0.49999997, lhs) then truncate, which rounds half-way
+        // cases away from zero (matching `f32::round`)
+        dynasm!(self.0.ops
+            ; mov eax, 0x80000000u32 as i32
+            ; vmovd xmm2, eax
+            ; vandps xmm2, xmm2, Rx(reg(lhs_reg))
+            ; mov eax, 0x3effffffu32 as i32
+            ; vmovd xmm3, eax
+            ; vorps xmm2, xmm2, xmm3
+            ; vaddss xmm2, xmm2, Rx(reg(lhs_reg))
+            ; vpxor xmm1, xmm1, xmm1
+            ; vroundss Rx(reg(out_reg)), xmm1, xmm2, 3
+        );
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            // xmm1 = [(lhs.v == 0) ? 0xFFFFFFFF : 0, 0, 0, 0]
+            ; vpxor xmm1, xmm1, xmm1
+            ; vcmpeqss xmm1, xmm1, Rx(reg(lhs_reg))
+            ; mov eax, 1.0f32.to_bits() as i32
+            ; vmovd xmm2, eax
+            ; vandps Rx(reg(out_reg)), xmm1, xmm2
+        );
+    }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops
             ; vmovups Rx(reg(out_reg)), Rx(reg(lhs_reg))
@@ -341,6 +379,62 @@ impl Assembler for GradSliceAssembler {
             ; vmovss Rx(reg(out_reg)), Rx(reg(out_reg)), xmm2
         );
     }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn grad_atan2(y: Grad, x: Grad) -> Grad {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn grad_mod(lhs: Grad, rhs: Grad) -> Grad {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn grad_compare(lhs: Grad, rhs: Grad) -> Grad {
+            lhs.compare(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vpxor xmm1, xmm1, xmm1
+            ; vcomiss Rx(reg(lhs_reg)), xmm1
+            ; jp >R // NaN is nonzero
+            ; jne >R
+
+            // Fallthrough: lhs is zero
+            ; vmovups Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; jmp >E
+
+            ; R:
+            ; vmovups Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            // Fallthrough
+
+            ; E:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vpxor xmm1, xmm1, xmm1
+            ; vcomiss Rx(reg(lhs_reg)), xmm1
+            ; jp >L // NaN is nonzero
+            ; jne >L
+
+            // Fallthrough: lhs is zero
+            ; vmovups Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; jmp >E
+
+            ; L:
+            ; vmovups Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            // Fallthrough
+
+            ; E:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             ; vcomiss Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
@@ -387,8 +481,8 @@ impl Assembler for GradSliceAssembler {
         );
         self.0.ops.commit_local().unwrap();
     }
-    fn load_imm(&mut self, imm: f32) -> u8 {
-        let imm_u32 = imm.to_bits();
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        let imm_u32 = (imm as f32).to_bits();
         dynasm!(self.0.ops
             ; vpxor Rx(IMM_REG), Rx(IMM_REG), Rx(IMM_REG)
             ; mov eax, imm_u32 as i32
@@ -423,7 +517,43 @@ impl GradSliceAssembler {
         arg_reg: u8,
         f: extern "sysv64" fn(Grad) -> Grad,
     ) {
+        self.call_fn_prelude();
         let addr = f as usize;
+        dynasm!(self.0.ops
+            // call the function, packing the gradient into xmm0 + xmm1
+            ; movsd xmm0, Rx(reg(arg_reg))
+            ; vpshufd xmm1, Rx(reg(arg_reg)), 0b1110
+            ; mov rdx, QWORD addr as _
+            ; call rdx
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "sysv64" fn(Grad, Grad) -> Grad,
+    ) {
+        self.call_fn_prelude();
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // call the function, packing the gradients into xmm0-3.  We set up
+            // the RHS first, because either argument may be the immediate
+            // register (xmm0).
+            ; movsd xmm2, Rx(reg(rhs_reg))
+            ; vpshufd xmm3, Rx(reg(rhs_reg)), 0b1110
+            ; movsd xmm0, Rx(reg(lhs_reg))
+            ; vpshufd xmm1, Rx(reg(lhs_reg)), 0b1110
+            ; mov rdx, QWORD addr as _
+            ; call rdx
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves pointers and register state before a function call
+    fn call_fn_prelude(&mut self) {
         dynasm!(self.0.ops
             // Back up X/Y/Z pointers to the stack
             ; mov [rbp - 0x8], rdi
@@ -446,13 +576,13 @@ impl GradSliceAssembler {
             ; vmovups [rsp + 0x90], xmm13
             ; vmovups [rsp + 0xa0], xmm14
             ; vmovups [rsp + 0xb0], xmm15
+        );
+    }
 
-            // call the function, packing the gradient into xmm0 + xmm1
-            ; movsd xmm0, Rx(reg(arg_reg))
-            ; vpshufd xmm1, Rx(reg(arg_reg)), 0b1110
-            ; mov rdx, QWORD addr as _
-            ; call rdx
-
+    /// Restores pointers and register state after a function call, packing
+    /// the result (in `xmm0` and `xmm1`) into `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
             // Restore gradient registers
             ; vmovups xmm4, [rsp]
             ; vmovups xmm5, [rsp + 0x10]
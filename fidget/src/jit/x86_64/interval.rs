@@ -159,6 +159,39 @@ impl Assembler for IntervalAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Rounding is monotonic, so we can round both bounds in place
+        dynasm!(self.0.ops
+            ; vroundps Rx(reg(out_reg)), Rx(reg(lhs_reg)), 1
+        );
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vroundps Rx(reg(out_reg)), Rx(reg(lhs_reg)), 2
+        );
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Add copysign(0.49999997, lhs) then truncate, which rounds half-way
+        // cases away from zero (matching `f32::round`)
+        dynasm!(self.0.ops
+            ; mov eax, 0x80000000u32 as i32
+            ; vmovd xmm1, eax
+            ; vbroadcastss xmm1, xmm1
+            ; vandps xmm1, xmm1, Rx(reg(lhs_reg))
+            ; mov eax, 0x3effffffu32 as i32
+            ; vmovd xmm2, eax
+            ; vbroadcastss xmm2, xmm2
+            ; vorps xmm1, xmm1, xmm2
+            ; vaddps xmm1, xmm1, Rx(reg(lhs_reg))
+            ; vroundps Rx(reg(out_reg)), xmm1, 3
+        );
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_not(v: Interval) -> Interval {
+            !v
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_not);
+    }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops
             ; vmovq Rx(reg(out_reg)), Rx(reg(lhs_reg))
@@ -222,8 +255,11 @@ impl Assembler for IntervalAssembler {
             ; vpshufd Rx(reg(out_reg)), Rx(reg(out_reg)), 0b11110011u8 as i8
 
             // Clear the lowest value of the interval, leaving us with [0, ...]
+            // (the upper lanes may not be zero, so we blend in an explicit
+            // zero; `dynasm` takes the lowest lane from the first source)
             ; C:
-            ; vpshufd Rx(reg(out_reg)), Rx(reg(out_reg)), 0b11110111u8 as i8
+            ; vpxor xmm0, xmm0, xmm0
+            ; vblendps Rx(reg(out_reg)), xmm0, Rx(reg(out_reg)), 1
             // fallthrough to end
 
             ; E:
@@ -393,6 +429,156 @@ impl Assembler for IntervalAssembler {
         );
         self.0.ops.commit_local().unwrap();
     }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn interval_atan2(
+            y: Interval,
+            x: Interval,
+        ) -> Interval {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn interval_mod(
+            lhs: Interval,
+            rhs: Interval,
+        ) -> Interval {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn interval_compare(
+            lhs: Interval,
+            rhs: Interval,
+        ) -> Interval {
+            lhs.compare(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            //  if lhs == [0, 0]
+            //      *choices++ |= CHOICE_LEFT
+            //      out = lhs
+            //  elif !lhs.contains(0)
+            //      *choices++ |= CHOICE_RIGHT
+            //      out = rhs
+            //  else
+            //      *choices++ |= CHOICE_BOTH
+            //      out = [min(rhs.lower, 0), max(rhs.upper, 0)]
+            ; mov ax, [rsi]
+
+            // xmm1 = lhs.upper, xmm2 = 0
+            ; vpshufd xmm1, Rx(reg(lhs_reg)), 0b11111101u8 as i8
+            ; vxorps xmm2, xmm2, xmm2
+            ; vcomiss Rx(reg(lhs_reg)), xmm2 // compare lhs.lower and 0
+            ; jp >B
+            ; ja >R
+            ; vcomiss xmm1, xmm2 // compare lhs.upper and 0
+            ; jp >B
+            ; jb >R
+            ; jne >B
+            ; vcomiss Rx(reg(lhs_reg)), xmm2
+            ; jne >B
+
+            // Fallthrough: lhs is exactly zero
+            ; vmovq Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or ax, CHOICE_LEFT as i16
+            ; mov cx, 1
+            ; mov [rdx], cx
+            ; jmp >E
+
+            // lhs doesn't contain zero
+            ; R:
+            ; vmovq Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or ax, CHOICE_RIGHT as i16
+            ; mov cx, 1
+            ; mov [rdx], cx
+            ; jmp >E
+
+            // Ambiguous case: extend rhs to include zero.  vminps / vmaxps
+            // return their second argument if either is NaN, which propagates
+            // NaN from rhs.
+            ; B:
+            ; vminps xmm1, xmm2, Rx(reg(rhs_reg))
+            ; vmaxps xmm2, xmm2, Rx(reg(rhs_reg))
+            ; vmovss xmm2, xmm2, xmm1 // xmm2 = [xmm1.lower, xmm2.upper]
+            ; vmovq Rx(reg(out_reg)), xmm2
+            ; or ax, CHOICE_BOTH as i16
+            // Fallthrough
+
+            ; E:
+            ; mov [rsi], ax
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            //  if lhs == [0, 0]
+            //      *choices++ |= CHOICE_RIGHT
+            //      out = rhs
+            //  elif !lhs.contains(0)
+            //      *choices++ |= CHOICE_LEFT
+            //      out = lhs
+            //  else
+            //      *choices++ |= CHOICE_BOTH
+            //      out = [min(lhs.lower, rhs.lower), max(lhs.upper, rhs.upper)]
+            ; mov ax, [rsi]
+
+            // xmm1 = lhs.upper, xmm2 = 0
+            ; vpshufd xmm1, Rx(reg(lhs_reg)), 0b11111101u8 as i8
+            ; vxorps xmm2, xmm2, xmm2
+            ; vcomiss Rx(reg(lhs_reg)), xmm2 // compare lhs.lower and 0
+            ; jp >N
+            ; ja >L
+            ; vcomiss xmm1, xmm2 // compare lhs.upper and 0
+            ; jp >N
+            ; jb >L
+            ; jne >B
+            ; vcomiss Rx(reg(lhs_reg)), xmm2
+            ; jne >B
+
+            // Fallthrough: lhs is exactly zero
+            ; vmovq Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or ax, CHOICE_RIGHT as i16
+            ; mov cx, 1
+            ; mov [rdx], cx
+            ; jmp >E
+
+            // lhs doesn't contain zero
+            ; L:
+            ; vmovq Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or ax, CHOICE_LEFT as i16
+            ; mov cx, 1
+            ; mov [rdx], cx
+            ; jmp >E
+
+            ; N:
+            ; or ax, CHOICE_BOTH as i16
+            // Load NaN into out_reg
+            ; vpcmpeqw Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vpslld Rx(reg(out_reg)), Rx(reg(out_reg)), 23
+            ; vpsrld Rx(reg(out_reg)), Rx(reg(out_reg)), 1
+            ; jmp >E
+
+            // Ambiguous case: take the union of both intervals, propagating
+            // NaN from rhs (the second argument to vminps / vmaxps)
+            ; B:
+            ; vminps xmm1, Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; vmaxps xmm2, Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; vmovss xmm2, xmm2, xmm1 // xmm2 = [xmm1.lower, xmm2.upper]
+            ; vmovq Rx(reg(out_reg)), xmm2
+            ; or ax, CHOICE_BOTH as i16
+            // Fallthrough
+
+            ; E:
+            ; mov [rsi], ax
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap();
+    }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             ; mov ax, [rsi]
@@ -508,8 +694,8 @@ impl Assembler for IntervalAssembler {
         );
         self.0.ops.commit_local().unwrap();
     }
-    fn load_imm(&mut self, imm: f32) -> u8 {
-        let imm_u32 = imm.to_bits();
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        let imm_u32 = (imm as f32).to_bits();
         dynasm!(self.0.ops
             ; mov eax, imm_u32 as i32
             ; vmovd Rx(IMM_REG), eax
@@ -537,12 +723,45 @@ impl Assembler for IntervalAssembler {
 }
 
 impl IntervalAssembler {
-    fn call_fn_unary(
+    pub(super) fn call_fn_unary(
         &mut self,
         out_reg: u8,
         arg_reg: u8,
         f: extern "sysv64" fn(Interval) -> Interval,
     ) {
+        self.call_fn_prelude();
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // copy arg to xmm0
+            ; vmovq xmm0, Rx(reg(arg_reg))
+            ; mov rdx, QWORD addr as _
+            ; call rdx
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    pub(super) fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "sysv64" fn(Interval, Interval) -> Interval,
+    ) {
+        self.call_fn_prelude();
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // copy args to xmm0 and xmm1, writing xmm1 first (because either
+            // argument may be the immediate register, i.e. xmm0)
+            ; vmovq xmm1, Rx(reg(rhs_reg))
+            ; vmovq xmm0, Rx(reg(lhs_reg))
+            ; mov rdx, QWORD addr as _
+            ; call rdx
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves register state before a function call
+    fn call_fn_prelude(&mut self) {
         // Back up a few callee-saved registers that we're about to use
         if !self.0.saved_callee_regs {
             dynasm!(self.0.ops
@@ -552,7 +771,6 @@ impl IntervalAssembler {
             );
             self.0.saved_callee_regs = true
         }
-        let addr = f as usize;
         dynasm!(self.0.ops
             // Back up vars/choice/simplify pointers to registers
             ; mov r12, rdi
@@ -575,12 +793,13 @@ impl IntervalAssembler {
             ; vmovsd [rsp + 0x48], xmm13
             ; vmovsd [rsp + 0x50], xmm14
             ; vmovsd [rsp + 0x58], xmm15
+        );
+    }
 
-            // copy arg to xmm0
-            ; vmovq xmm0, Rx(reg(arg_reg))
-            ; mov rdx, QWORD addr as _
-            ; call rdx
-
+    /// Restores register state after a function call, unpacking the result
+    /// into `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
             // Restore float registers
             ; vmovsd xmm4, [rsp]
             ; vmovsd xmm5, [rsp + 0x08]
@@ -0,0 +1,808 @@
+use crate::{
+    eval::types::Interval64,
+    jit::{
+        interval64::Interval64Assembler, mmap::Mmap, reg, Assembler,
+        AssemblerData, CHOICE_BOTH, CHOICE_LEFT, CHOICE_RIGHT, IMM_REG, OFFSET,
+        REGISTER_LIMIT,
+    },
+    Error,
+};
+use dynasmrt::{dynasm, DynasmApi, DynasmLabelApi};
+
+/// Implementation of the double-precision interval assembler on `x86_64`
+///
+/// Each interval is stored in a single `xmm` register as `[lower, upper]`.
+///
+/// Registers are passed in as follows:
+///
+/// | Variable   | Register      | Type                  |
+/// |------------|---------------|-----------------------|
+/// | X          | `xmm0`/`xmm1` | `[f64; 2]`            |
+/// | Y          | `xmm2`/`xmm3` | `[f64; 2]`            |
+/// | Z          | `xmm4`/`xmm5` | `[f64; 2]`            |
+/// | `vars`     | `rdi`         | `*const f64` (array)  |
+/// | `choices`  | `rsi`         | `*mut u8` (array)     |
+/// | `simplify` | `rdx`         | `*mut u8` (single)    |
+///
+/// (the calling convention splits each 16-byte interval across two registers;
+/// the return value is split between `xmm0` and `xmm1` in the same way)
+///
+/// The stack is configured as follows
+///
+/// ```text
+/// | Position | Value        | Notes                                       |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x00     | `rbp`        | Previous value for base pointer             |
+/// |----------|--------------|---------------------------------------------|
+/// | -0x08    | `r12`        | During functions calls, we use these        |
+/// | -0x10    | `r13`        | as temporary storage so must preserve their |
+/// | -0x18    | `r14`        | previous values on the stack                |
+/// |----------|--------------|---------------------------------------------|
+/// | -0x30    | Z            | Inputs (as 2x doubles)                      |
+/// | -0x40    | Y            |                                             |
+/// | -0x50    | X            |                                             |
+/// |----------|--------------|---------------------------------------------|
+/// | ...      | ...          | Register spills live up here                |
+/// |----------|--------------|---------------------------------------------|
+/// | 0xb0     | xmm15        | Caller-saved registers during functions     |
+/// | 0xa0     | xmm14        | calls are placed here, then restored        |
+/// | 0x90     | xmm13        |                                             |
+/// | 0x80     | xmm12        |                                             |
+/// | 0x70     | xmm11        |                                             |
+/// | 0x60     | xmm10        |                                             |
+/// | 0x50     | xmm9         |                                             |
+/// | 0x40     | xmm8         |                                             |
+/// | 0x30     | xmm7         |                                             |
+/// | 0x20     | xmm6         |                                             |
+/// | 0x10     | xmm5         |                                             |
+/// | 0x00     | xmm4         |                                             |
+/// ```
+const STACK_SIZE_UPPER: usize = 0x50; // Positions relative to `rbp`
+const STACK_SIZE_LOWER: usize = 0xc0; // Positions relative to `rsp`
+
+impl Assembler for Interval64Assembler {
+    type Data = Interval64;
+
+    fn init(mmap: Mmap, slot_count: usize) -> Self {
+        let mut out = AssemblerData::new(mmap);
+        dynasm!(out.ops
+            ; push rbp
+            ; mov rbp, rsp
+        );
+        out.prepare_stack(slot_count, STACK_SIZE_UPPER + STACK_SIZE_LOWER);
+        dynasm!(out.ops
+            ; vzeroupper
+
+            // Pack X/Y/Z into single registers and put them on the stack, so
+            // we can use those registers
+            ; vunpcklpd xmm0, xmm0, xmm1
+            ; vmovupd [rbp - 0x50], xmm0
+            ; vunpcklpd xmm2, xmm2, xmm3
+            ; vmovupd [rbp - 0x40], xmm2
+            ; vunpcklpd xmm4, xmm4, xmm5
+            ; vmovupd [rbp - 0x30], xmm4
+        );
+        Self(out)
+    }
+    fn build_load(&mut self, dst_reg: u8, src_mem: u32) {
+        assert!((dst_reg as usize) < REGISTER_LIMIT);
+        let sp_offset: i32 = (self.0.stack_pos(src_mem)
+            + STACK_SIZE_LOWER as u32)
+            .try_into()
+            .unwrap();
+        dynasm!(self.0.ops
+            ; vmovupd Rx(reg(dst_reg)), [rsp + sp_offset]
+        );
+    }
+    fn build_store(&mut self, dst_mem: u32, src_reg: u8) {
+        assert!((src_reg as usize) < REGISTER_LIMIT);
+        let sp_offset: i32 = (self.0.stack_pos(dst_mem)
+            + STACK_SIZE_LOWER as u32)
+            .try_into()
+            .unwrap();
+        dynasm!(self.0.ops
+            ; vmovupd [rsp + sp_offset], Rx(reg(src_reg))
+        );
+    }
+    fn build_input(&mut self, out_reg: u8, src_arg: u8) {
+        let pos = STACK_SIZE_UPPER as i32
+            - (std::mem::size_of::<Self::Data>() as i32) * (src_arg as i32);
+        dynasm!(self.0.ops
+            ; vmovupd Rx(reg(out_reg)), [rbp - pos]
+        );
+    }
+    fn build_var(&mut self, out_reg: u8, src_arg: u32) {
+        dynasm!(self.0.ops
+            ; vmovddup Rx(reg(out_reg)), [rdi + 8 * (src_arg as i32)]
+        );
+    }
+    fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_sin(v: Interval64) -> Interval64 {
+            v.sin()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_sin);
+    }
+    fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_cos(v: Interval64) -> Interval64 {
+            v.cos()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_cos);
+    }
+    fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_tan(v: Interval64) -> Interval64 {
+            v.tan()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_tan);
+    }
+    fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_asin(v: Interval64) -> Interval64 {
+            v.asin()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_asin);
+    }
+    fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_acos(v: Interval64) -> Interval64 {
+            v.acos()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_acos);
+    }
+    fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_atan(v: Interval64) -> Interval64 {
+            v.atan()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_atan);
+    }
+    fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_exp(v: Interval64) -> Interval64 {
+            v.exp()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_exp);
+    }
+    fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_ln(v: Interval64) -> Interval64 {
+            v.ln()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_ln);
+    }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Rounding is monotonic, so we can round both bounds in place
+        dynasm!(self.0.ops
+            ; vroundpd Rx(reg(out_reg)), Rx(reg(lhs_reg)), 1
+        );
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vroundpd Rx(reg(out_reg)), Rx(reg(lhs_reg)), 2
+        );
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Add copysign(0.49999999999999994, lhs) then truncate, which rounds
+        // half-way cases away from zero (matching `f64::round`)
+        dynasm!(self.0.ops
+            ; mov rax, QWORD 0x8000000000000000u64 as i64
+            ; vmovq xmm1, rax
+            ; vmovddup xmm1, xmm1
+            ; vandpd xmm1, xmm1, Rx(reg(lhs_reg))
+            ; mov rax, QWORD 0x3fdfffffffffffffu64 as i64
+            ; vmovq xmm2, rax
+            ; vmovddup xmm2, xmm2
+            ; vorpd xmm1, xmm1, xmm2
+            ; vaddpd xmm1, xmm1, Rx(reg(lhs_reg))
+            ; vroundpd Rx(reg(out_reg)), xmm1, 3
+        );
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_not(v: Interval64) -> Interval64 {
+            !v
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_not);
+    }
+    fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vmovapd Rx(reg(out_reg)), Rx(reg(lhs_reg))
+        );
+    }
+    fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vpermilpd Rx(reg(out_reg)), Rx(reg(lhs_reg)), 1 // swap lo and hi
+            ; vpcmpeqd xmm1, xmm1, xmm1 // set xmm1 to all 1s
+            ; vpsllq xmm1, xmm1, 63     // shift, leaving the sign bits
+            ; vxorpd Rx(reg(out_reg)), Rx(reg(out_reg)), xmm1
+        );
+    }
+    fn build_abs(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Store 0.0 to xmm1, for comparisons
+            ; vxorpd xmm1, xmm1, xmm1
+
+            // Pull the upper value into xmm2
+            ; vpermilpd xmm2, Rx(reg(lhs_reg)), 1
+
+            // Check whether lhs.upper < 0
+            ; vcomisd xmm1, xmm2
+            ; ja >N // negative
+
+            // Check whether lhs.lower < 0
+            ; vcomisd xmm1, Rx(reg(lhs_reg))
+            ; ja >S // straddling 0
+
+            // Fallthrough: the whole interval is above zero, so we just copy it
+            // over and return.
+            ; vmovapd Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; jmp >E
+
+            // The interval is less than zero, so we need to calculate
+            // [-upper, -lower]
+            ; N:
+            ; vpcmpeqd xmm1, xmm1, xmm1 // set xmm1 to all 1s
+            ; vpsllq xmm1, xmm1, 63     // shift, leaving the sign bits
+            ; vxorpd xmm1, xmm1, Rx(reg(lhs_reg)) // xor to swap sign bits
+            ; vpermilpd Rx(reg(out_reg)), xmm1, 1 // swap lo and hi
+            ; jmp >E
+
+            // The interval straddles 0, so we need to calculate
+            // [0.0, max(abs(lower, upper))]
+            ; S:
+            ; vpcmpeqd xmm1, xmm1, xmm1 // set xmm1 to all 1s
+            ; vpsrlq xmm1, xmm1, 1      // shift, clearing the sign bits
+            ; vandpd xmm1, xmm1, Rx(reg(lhs_reg)) // [abs(lo), abs(hi)]
+            ; vpermilpd xmm2, xmm1, 1 // [abs(hi), abs(lo)]
+            ; vmaxsd xmm1, xmm1, xmm2
+            ; vxorpd xmm2, xmm2, xmm2
+            ; vunpcklpd Rx(reg(out_reg)), xmm2, xmm1
+            // fallthrough to end
+
+            ; E:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_recip(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vxorpd xmm1, xmm1, xmm1 // xmm1 = 0.0
+            ; vcomisd Rx(reg(lhs_reg)), xmm1
+            ; ja >O // low element is > 0
+            ; vpermilpd xmm2, Rx(reg(lhs_reg)), 1 // extract high element
+            ; vcomisd xmm1, xmm2
+            ; ja >O // high element is < 0
+
+            // Bad case: the division spans 0, so return NaN
+            ; vpcmpeqd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vpsllq Rx(reg(out_reg)), Rx(reg(out_reg)), 52
+            ; vpsrlq Rx(reg(out_reg)), Rx(reg(out_reg)), 1
+            ; jmp >E
+
+            ; O: // We're okay!
+            ; mov rax, QWORD 1.0f64.to_bits() as i64
+            ; vmovq xmm1, rax
+            ; vmovddup xmm1, xmm1
+            ; vdivpd xmm1, xmm1, Rx(reg(lhs_reg))
+            ; vpermilpd Rx(reg(out_reg)), xmm1, 1
+            // Fallthrough to end
+
+            ; E:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_sqrt(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vxorpd xmm1, xmm1, xmm1 // xmm1 = 0.0
+            ; vcomisd xmm1, Rx(reg(lhs_reg))
+            ; ja >L // lower_lz
+
+            // Happy path
+            ; vsqrtpd Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; jmp >E
+
+            // lower < 0, upper > 0 => [0, sqrt(upper)]
+            ; L:
+            ; vpermilpd xmm2, Rx(reg(lhs_reg)), 1
+            ; vcomisd xmm2, xmm1
+            ; jbe >U // upper_lz (or NaN)
+            ; vsqrtsd xmm2, xmm2, xmm2
+            ; vunpcklpd Rx(reg(out_reg)), xmm1, xmm2
+            ; jmp >E
+
+            // upper <= 0 => [NaN, NaN]
+            ; U:
+            ; vpcmpeqd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vpsllq Rx(reg(out_reg)), Rx(reg(out_reg)), 52
+            ; vpsrlq Rx(reg(out_reg)), Rx(reg(out_reg)), 1
+
+            ; E:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_square(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Put component-wise multiplication in xmm3
+            ; vmulpd xmm3, Rx(reg(lhs_reg)), Rx(reg(lhs_reg))
+            ; vxorpd xmm1, xmm1, xmm1 // xmm1 = 0.0
+            ; vpermilpd xmm2, Rx(reg(lhs_reg)), 1
+            ; vcomisd xmm1, xmm2
+            ; ja >N // negative
+            ; vcomisd xmm1, Rx(reg(lhs_reg))
+            ; ja >S // straddling 0
+
+            // Fallthrough: lower > 0, so our previous result is fine
+            ; vmovapd Rx(reg(out_reg)), xmm3
+            ; jmp >E
+
+            // upper < 0, so we square then swap
+            ; N:
+            ; vpermilpd Rx(reg(out_reg)), xmm3, 1
+            ; jmp >E
+
+            // lower < 0, upper > 0 => pick the bigger result
+            ; S:
+            ; vpermilpd xmm2, xmm3, 1
+            ; vmaxsd xmm2, xmm2, xmm3
+            ; vunpcklpd Rx(reg(out_reg)), xmm1, xmm2
+
+            ; E:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_add(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vaddpd Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+        );
+    }
+    fn build_sub(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vpermilpd xmm1, Rx(reg(rhs_reg)), 1
+            ; vsubpd Rx(reg(out_reg)), Rx(reg(lhs_reg)), xmm1
+        );
+    }
+    fn build_mul(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vmovddup xmm1, Rx(reg(lhs_reg)) // xmm1 = [lhs.lo, lhs.lo]
+            ; vpermilpd xmm2, Rx(reg(lhs_reg)), 3 // xmm2 = [lhs.hi, lhs.hi]
+            ; vmulpd xmm1, xmm1, Rx(reg(rhs_reg))
+            ; vmulpd xmm2, xmm2, Rx(reg(rhs_reg)) // xmm1-2 contain all 4 results
+
+            // Pairwise minimum and maximum
+            ; vminpd xmm3, xmm1, xmm2
+            ; vmaxpd xmm1, xmm1, xmm2
+
+            // Horizontal minimum into xmm3[0] and maximum into xmm1[0]
+            ; vpermilpd xmm2, xmm3, 1
+            ; vminsd xmm3, xmm3, xmm2
+            ; vpermilpd xmm2, xmm1, 1
+            ; vmaxsd xmm1, xmm1, xmm2
+
+            // Splice the two together
+            ; vunpcklpd Rx(reg(out_reg)), xmm3, xmm1
+        );
+    }
+    fn build_div(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vxorpd xmm1, xmm1, xmm1 // xmm1 = 0.0
+            ; vcomisd Rx(reg(rhs_reg)), xmm1
+            ; ja >O // okay
+            ; vpermilpd xmm2, Rx(reg(rhs_reg)), 1
+            ; vcomisd xmm1, xmm2
+            ; ja >O // okay
+
+            // Fallthrough: an input is NaN or rhs_reg spans 0; return NaN
+            // by manually building it in the XMM register
+            ; vpcmpeqd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vpsllq Rx(reg(out_reg)), Rx(reg(out_reg)), 52
+            ; vpsrlq Rx(reg(out_reg)), Rx(reg(out_reg)), 1
+            ; jmp >E
+
+            ; O:
+            ; vmovddup xmm1, Rx(reg(lhs_reg)) // xmm1 = [lhs.lo, lhs.lo]
+            ; vpermilpd xmm2, Rx(reg(lhs_reg)), 3 // xmm2 = [lhs.hi, lhs.hi]
+            ; vdivpd xmm1, xmm1, Rx(reg(rhs_reg))
+            ; vdivpd xmm2, xmm2, Rx(reg(rhs_reg)) // xmm1-2 contain all 4 results
+
+            // Pairwise minimum and maximum
+            ; vminpd xmm3, xmm1, xmm2
+            ; vmaxpd xmm1, xmm1, xmm2
+
+            // Horizontal minimum into xmm3[0] and maximum into xmm1[0]
+            ; vpermilpd xmm2, xmm3, 1
+            ; vminsd xmm3, xmm3, xmm2
+            ; vpermilpd xmm2, xmm1, 1
+            ; vmaxsd xmm1, xmm1, xmm2
+
+            // Splice the two together
+            ; vunpcklpd Rx(reg(out_reg)), xmm3, xmm1
+
+            ; E:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn interval_atan2(
+            y: Interval64,
+            x: Interval64,
+        ) -> Interval64 {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn interval_mod(
+            lhs: Interval64,
+            rhs: Interval64,
+        ) -> Interval64 {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn interval_compare(
+            lhs: Interval64,
+            rhs: Interval64,
+        ) -> Interval64 {
+            lhs.compare(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            //  if lhs == [0, 0]
+            //      *choices++ |= CHOICE_LEFT
+            //      out = lhs
+            //  elif !lhs.contains(0)
+            //      *choices++ |= CHOICE_RIGHT
+            //      out = rhs
+            //  else
+            //      *choices++ |= CHOICE_BOTH
+            //      out = [min(rhs.lower, 0), max(rhs.upper, 0)]
+            ; mov ax, [rsi]
+
+            // xmm1 = lhs.upper, xmm2 = 0
+            ; vpermilpd xmm1, Rx(reg(lhs_reg)), 1
+            ; vxorpd xmm2, xmm2, xmm2
+            ; vcomisd Rx(reg(lhs_reg)), xmm2 // compare lhs.lower and 0
+            ; jp >B
+            ; ja >R
+            ; vcomisd xmm1, xmm2 // compare lhs.upper and 0
+            ; jp >B
+            ; jb >R
+            ; jne >B
+            ; vcomisd Rx(reg(lhs_reg)), xmm2
+            ; jne >B
+
+            // Fallthrough: lhs is exactly zero
+            ; vmovapd Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or ax, CHOICE_LEFT as i16
+            ; mov cx, 1
+            ; mov [rdx], cx
+            ; jmp >E
+
+            // lhs doesn't contain zero
+            ; R:
+            ; vmovapd Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or ax, CHOICE_RIGHT as i16
+            ; mov cx, 1
+            ; mov [rdx], cx
+            ; jmp >E
+
+            // Ambiguous case: extend rhs to include zero.  vminpd / vmaxpd
+            // return their second argument if either is NaN, which propagates
+            // NaN from rhs.
+            ; B:
+            ; vminpd xmm1, xmm2, Rx(reg(rhs_reg))
+            ; vmaxpd xmm2, xmm2, Rx(reg(rhs_reg))
+            ; vmovsd xmm2, xmm2, xmm1 // xmm2 = [xmm1.lower, xmm2.upper]
+            ; vmovapd Rx(reg(out_reg)), xmm2
+            ; or ax, CHOICE_BOTH as i16
+            // Fallthrough
+
+            ; E:
+            ; mov [rsi], ax
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            //  if lhs == [0, 0]
+            //      *choices++ |= CHOICE_RIGHT
+            //      out = rhs
+            //  elif !lhs.contains(0)
+            //      *choices++ |= CHOICE_LEFT
+            //      out = lhs
+            //  else
+            //      *choices++ |= CHOICE_BOTH
+            //      out = [min(lhs.lower, rhs.lower), max(lhs.upper, rhs.upper)]
+            ; mov ax, [rsi]
+
+            // xmm1 = lhs.upper, xmm2 = 0
+            ; vpermilpd xmm1, Rx(reg(lhs_reg)), 1
+            ; vxorpd xmm2, xmm2, xmm2
+            ; vcomisd Rx(reg(lhs_reg)), xmm2 // compare lhs.lower and 0
+            ; jp >N
+            ; ja >L
+            ; vcomisd xmm1, xmm2 // compare lhs.upper and 0
+            ; jp >N
+            ; jb >L
+            ; jne >B
+            ; vcomisd Rx(reg(lhs_reg)), xmm2
+            ; jne >B
+
+            // Fallthrough: lhs is exactly zero
+            ; vmovapd Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or ax, CHOICE_RIGHT as i16
+            ; mov cx, 1
+            ; mov [rdx], cx
+            ; jmp >E
+
+            // lhs doesn't contain zero
+            ; L:
+            ; vmovapd Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or ax, CHOICE_LEFT as i16
+            ; mov cx, 1
+            ; mov [rdx], cx
+            ; jmp >E
+
+            ; N:
+            ; or ax, CHOICE_BOTH as i16
+            // Load NaN into out_reg
+            ; vpcmpeqd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vpsllq Rx(reg(out_reg)), Rx(reg(out_reg)), 52
+            ; vpsrlq Rx(reg(out_reg)), Rx(reg(out_reg)), 1
+            ; jmp >E
+
+            // Ambiguous case: take the union of both intervals, propagating
+            // NaN from rhs (the second argument to vminpd / vmaxpd)
+            ; B:
+            ; vminpd xmm1, Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; vmaxpd xmm2, Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; vmovsd xmm2, xmm2, xmm1 // xmm2 = [xmm1.lower, xmm2.upper]
+            ; vmovapd Rx(reg(out_reg)), xmm2
+            ; or ax, CHOICE_BOTH as i16
+            // Fallthrough
+
+            ; E:
+            ; mov [rsi], ax
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; mov ax, [rsi]
+
+            // xmm1 = lhs.upper
+            ; vpermilpd xmm1, Rx(reg(lhs_reg)), 1
+            ; vcomisd xmm1, Rx(reg(rhs_reg)) // compare lhs.upper and rhs.lower
+            ; jp >N // NaN
+            ; jb >R // rhs
+
+            // xmm1 = rhs.upper
+            ; vpermilpd xmm1, Rx(reg(rhs_reg)), 1
+            ; vcomisd xmm1, Rx(reg(lhs_reg))
+            ; jp >N
+            ; jb >L
+
+            // Fallthrough: ambiguous case
+            ; vmaxpd Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; or ax, CHOICE_BOTH as i16
+            ; jmp >E
+
+            ; N:
+            ; or ax, CHOICE_BOTH as i16
+            // Load NaN into out_reg
+            ; vpcmpeqd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vpsllq Rx(reg(out_reg)), Rx(reg(out_reg)), 52
+            ; vpsrlq Rx(reg(out_reg)), Rx(reg(out_reg)), 1
+            ; jmp >E
+
+            // rhs.upper < lhs.lower
+            ; L:
+            ; vmovapd Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or ax, CHOICE_LEFT as i16
+            ; mov cx, 1
+            ; mov [rdx], cx
+            ; jmp >E
+
+            // lhs.upper < rhs.lower
+            ; R:
+            ; vmovapd Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or ax, CHOICE_RIGHT as i16
+            ; mov cx, 1
+            ; mov [rdx], cx
+            // Fallthrough
+
+            ; E:
+            ; mov [rsi], ax
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            //  if lhs.upper < rhs.lower
+            //      *choices++ |= CHOICE_LEFT
+            //      out = lhs
+            //  elif rhs.upper < lhs.lower
+            //      *choices++ |= CHOICE_RIGHT
+            //      out = rhs
+            //  else
+            //      *choices++ |= CHOICE_BOTH
+            //      out = fmin(lhs, rhs)
+
+            ; mov ax, [rsi]
+
+            // xmm1 = lhs.upper
+            ; vpermilpd xmm1, Rx(reg(lhs_reg)), 1
+            ; vcomisd xmm1, Rx(reg(rhs_reg)) // compare lhs.upper and rhs.lower
+            ; jp >N
+            ; jb >L
+
+            // xmm1 = rhs.upper
+            ; vpermilpd xmm1, Rx(reg(rhs_reg)), 1
+            ; vcomisd xmm1, Rx(reg(lhs_reg))
+            ; jp >N
+            ; jb >R
+
+            // Fallthrough: ambiguous case
+            ; vminpd Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; or ax, CHOICE_BOTH as i16
+            ; jmp >E
+
+            ; N:
+            ; or ax, CHOICE_BOTH as i16
+            // Load NAN into out_reg
+            ; vpcmpeqd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vpsllq Rx(reg(out_reg)), Rx(reg(out_reg)), 52
+            ; vpsrlq Rx(reg(out_reg)), Rx(reg(out_reg)), 1
+            ; jmp >E
+
+            // lhs.upper < rhs.lower
+            ; L:
+            ; vmovapd Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or ax, CHOICE_LEFT as i16
+            ; mov cx, 1
+            ; mov [rdx], cx
+            ; jmp >E
+
+            // rhs.upper < lhs.lower
+            ; R:
+            ; vmovapd Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or ax, CHOICE_RIGHT as i16
+            ; mov cx, 1
+            ; mov [rdx], cx
+            // Fallthrough
+
+            ; E:
+            ; mov [rsi], ax
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        dynasm!(self.0.ops
+            ; mov rax, QWORD imm.to_bits() as i64
+            ; vmovq Rx(IMM_REG), rax
+            ; vmovddup Rx(IMM_REG), Rx(IMM_REG)
+        );
+        IMM_REG.wrapping_sub(OFFSET)
+    }
+    fn finalize(mut self, out_reg: u8) -> Result<Mmap, Error> {
+        if self.0.saved_callee_regs {
+            dynasm!(self.0.ops
+                ; mov r12, [rbp - 0x8]
+                ; mov r13, [rbp - 0x10]
+                ; mov r14, [rbp - 0x18]
+            );
+        }
+        dynasm!(self.0.ops
+            // Split the result into xmm0 (lower) and xmm1 (upper)
+            ; vmovapd xmm0, Rx(reg(out_reg))
+            ; vpermilpd xmm1, xmm0, 1
+            ; add rsp, self.0.mem_offset as i32
+            ; pop rbp
+            ; emms
+            ; ret
+        );
+        self.0.ops.finalize()
+    }
+}
+
+impl Interval64Assembler {
+    fn call_fn_unary(
+        &mut self,
+        out_reg: u8,
+        arg_reg: u8,
+        f: extern "sysv64" fn(Interval64) -> Interval64,
+    ) {
+        self.call_fn_prelude();
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // split arg into xmm0 and xmm1
+            ; vpermilpd xmm1, Rx(reg(arg_reg)), 1
+            ; vmovapd xmm0, Rx(reg(arg_reg))
+            ; mov rdx, QWORD addr as _
+            ; call rdx
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "sysv64" fn(Interval64, Interval64) -> Interval64,
+    ) {
+        self.call_fn_prelude();
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // split args into xmm0-1 and xmm2-3, writing xmm2-3 first (because
+            // either argument may be the immediate register, i.e. xmm0)
+            ; vpermilpd xmm3, Rx(reg(rhs_reg)), 1
+            ; vmovapd xmm2, Rx(reg(rhs_reg))
+            ; vpermilpd xmm1, Rx(reg(lhs_reg)), 1
+            ; vmovapd xmm0, Rx(reg(lhs_reg))
+            ; mov rdx, QWORD addr as _
+            ; call rdx
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves register state before a function call
+    fn call_fn_prelude(&mut self) {
+        // Back up a few callee-saved registers that we're about to use
+        if !self.0.saved_callee_regs {
+            dynasm!(self.0.ops
+                ; mov [rbp - 0x8], r12
+                ; mov [rbp - 0x10], r13
+                ; mov [rbp - 0x18], r14
+            );
+            self.0.saved_callee_regs = true
+        }
+        dynasm!(self.0.ops
+            // Back up vars/choice/simplify pointers to registers
+            ; mov r12, rdi
+            ; mov r13, rsi
+            ; mov r14, rdx
+
+            // Back up all 128 bits of each register to the stack
+            ; vmovupd [rsp], xmm4
+            ; vmovupd [rsp + 0x10], xmm5
+            ; vmovupd [rsp + 0x20], xmm6
+            ; vmovupd [rsp + 0x30], xmm7
+            ; vmovupd [rsp + 0x40], xmm8
+            ; vmovupd [rsp + 0x50], xmm9
+            ; vmovupd [rsp + 0x60], xmm10
+            ; vmovupd [rsp + 0x70], xmm11
+            ; vmovupd [rsp + 0x80], xmm12
+            ; vmovupd [rsp + 0x90], xmm13
+            ; vmovupd [rsp + 0xa0], xmm14
+            ; vmovupd [rsp + 0xb0], xmm15
+        );
+    }
+
+    /// Restores register state after a function call, packing the result
+    /// into `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
+            // Restore float registers
+            ; vmovupd xmm4, [rsp]
+            ; vmovupd xmm5, [rsp + 0x10]
+            ; vmovupd xmm6, [rsp + 0x20]
+            ; vmovupd xmm7, [rsp + 0x30]
+            ; vmovupd xmm8, [rsp + 0x40]
+            ; vmovupd xmm9, [rsp + 0x50]
+            ; vmovupd xmm10, [rsp + 0x60]
+            ; vmovupd xmm11, [rsp + 0x70]
+            ; vmovupd xmm12, [rsp + 0x80]
+            ; vmovupd xmm13, [rsp + 0x90]
+            ; vmovupd xmm14, [rsp + 0xa0]
+            ; vmovupd xmm15, [rsp + 0xb0]
+
+            // Restore vars/choice/simplify pointers
+            ; mov rdi, r12
+            ; mov rsi, r13
+            ; mov rdx, r14
+
+            // Pack the interval result
+            ; vunpcklpd Rx(reg(out_reg)), xmm0, xmm1
+        );
+    }
+}
@@ -19,6 +19,11 @@ pub const IMM_REG: u8 = 0;
 pub const OFFSET: u8 = 4;
 
 pub mod float_slice;
+pub mod float_slice64;
 pub mod grad_slice;
 pub mod interval;
+pub mod interval64;
+pub mod multi_grad_slice;
 pub mod point;
+pub mod point64;
+pub mod robust_interval;
@@ -0,0 +1,620 @@
+use crate::{
+    eval::types::MultiGrad,
+    jit::{
+        mmap::Mmap, multi_grad_slice::MultiGradSliceAssembler, reg, Assembler,
+        AssemblerData, IMM_REG, OFFSET, REGISTER_LIMIT,
+    },
+    Error,
+};
+use dynasmrt::{dynasm, DynasmApi, DynasmLabelApi};
+
+/// Implementation for the multi-target gradient assembler on `x86_64`
+///
+/// Each [`MultiGrad`] is stored in a single `ymm` register, with the value in
+/// the lowest lane and derivatives in the remaining seven lanes.
+///
+/// Registers as pased in as follows:
+///
+/// | Variable   | Register | Type                   |
+/// |------------|----------|------------------------|
+/// | X          | `rdi`    | `*const f32`           |
+/// | Y          | `rsi`    | `*const f32`           |
+/// | Z          | `rdx`    | `*const f32`           |
+/// | `seeds`    | `rcx`    | `*const MultiGrad`     |
+/// | `out`      | `r8`     | `*const MultiGrad`     |
+/// | `count`    | `r9`     | `u64`                  |
+///
+/// Instead of a plain `vars` array, the function expects an array of
+/// `MultiGrad` seeds: the first three are derivative seeds for X, Y, and Z (the
+/// value is ignored), followed by one item per variable (with both value and
+/// derivatives populated).
+///
+/// Values are patched into the lowest lane with `vblendps`.  Note that
+/// `dynasm` swaps the two source operands of `vblendps` relative to Intel
+/// syntax, so `vblendps out, a, b, 1` takes the lowest lane from `a` and the
+/// rest from `b`.
+///
+/// During evaluation, X, Y, and Z values are stored on the stack to keep
+/// registers unoccupied.
+///
+/// The stack is configured as follows
+///
+/// ```text
+/// | Position | Value        | Notes                                       |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x00     | `rbp`        | Previous value for base pointer             |
+/// |----------|--------------|---------------------------------------------|
+/// | -0x08    | `rdi`        | During functions calls, we use these        |
+/// | -0x10    | `rsi`        | as temporary storage so must preserve their |
+/// | -0x18    | `rdx`        | previous values on the stack                |
+/// | -0x20    | `rcx`        |                                             |
+/// | -0x28    | `r8`         |                                             |
+/// | -0x30    | `r9`         |                                             |
+/// |----------|--------------|---------------------------------------------|
+/// | -0x60    | Z            | Inputs (as 8x floats)                       |
+/// | -0x80    | Y            |                                             |
+/// | -0xa0    | X            |                                             |
+/// |----------|--------------|---------------------------------------------|
+/// | ...      | ...          | Register spills live up here                |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x1a0    | function in  | Second input for binary function calls      |
+/// | 0x180    | function i/o | Inputs and outputs for function calls       |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x160    | ymm15        | Caller-saved registers during functions     |
+/// | 0x140    | ymm14        | calls are placed here, then restored        |
+/// | 0x120    | ymm13        |                                             |
+/// | 0x100    | ymm12        |                                             |
+/// | 0xe0     | ymm11        |                                             |
+/// | 0xc0     | ymm10        |                                             |
+/// | 0xa0     | ymm9         |                                             |
+/// | 0x80     | ymm8         |                                             |
+/// | 0x60     | ymm7         |                                             |
+/// | 0x40     | ymm6         |                                             |
+/// | 0x20     | ymm5         |                                             |
+/// | 0x00     | ymm4         |                                             |
+/// ```
+const STACK_SIZE_UPPER: usize = 0xa0; // Positions relative to `rbp`
+const STACK_SIZE_LOWER: usize = 0x1c0; // Positions relative to `rsp`
+
+impl Assembler for MultiGradSliceAssembler {
+    type Data = MultiGrad;
+
+    fn init(mmap: Mmap, slot_count: usize) -> Self {
+        let mut out = AssemblerData::new(mmap);
+        dynasm!(out.ops
+            ; push rbp
+            ; mov rbp, rsp
+        );
+        out.prepare_stack(slot_count, STACK_SIZE_UPPER + STACK_SIZE_LOWER);
+        let input_pos = STACK_SIZE_UPPER as i32;
+        dynasm!(out.ops
+            // Preload derivative seeds for X, Y, and Z
+            ; vmovups ymm1, [rcx]
+            ; vmovups [rbp - input_pos], ymm1
+            ; vmovups ymm1, [rcx + 0x20]
+            ; vmovups [rbp - (input_pos - 0x20)], ymm1
+            ; vmovups ymm1, [rcx + 0x40]
+            ; vmovups [rbp - (input_pos - 0x40)], ymm1
+
+            // The loop returns here, and we check whether to keep looping
+            ; ->L:
+
+            ; test r9, r9
+            ; jz ->X // jump to the exit if we're done, otherwise fallthrough
+
+            // Copy from the input pointers into the stack right below rbp
+            ; mov eax, [rdi]
+            ; mov [rbp - input_pos], eax  // X
+            ; add rdi, 4
+
+            ; mov eax, [rsi]
+            ; mov [rbp - (input_pos - 0x20)], eax // Y
+            ; add rsi, 4
+
+            ; mov eax, [rdx]
+            ; mov [rbp - (input_pos - 0x40)], eax // Z
+            ; add rdx, 4
+        );
+        Self(out)
+    }
+    fn bytes_per_clause() -> usize {
+        20
+    }
+    fn build_load(&mut self, dst_reg: u8, src_mem: u32) {
+        assert!((dst_reg as usize) < REGISTER_LIMIT);
+        let sp_offset: i32 = (self.0.stack_pos(src_mem)
+            + STACK_SIZE_LOWER as u32)
+            .try_into()
+            .unwrap();
+        dynasm!(self.0.ops
+            ; vmovups Ry(reg(dst_reg)), [rsp + sp_offset]
+        );
+    }
+    fn build_store(&mut self, dst_mem: u32, src_reg: u8) {
+        assert!((src_reg as usize) < REGISTER_LIMIT);
+        let sp_offset: i32 = (self.0.stack_pos(dst_mem)
+            + STACK_SIZE_LOWER as u32)
+            .try_into()
+            .unwrap();
+        dynasm!(self.0.ops
+            ; vmovups [rsp + sp_offset], Ry(reg(src_reg))
+        );
+    }
+    fn build_input(&mut self, out_reg: u8, src_arg: u8) {
+        let pos = STACK_SIZE_UPPER as i32 - 32 * (src_arg as i32);
+        dynasm!(self.0.ops
+            ; vmovups Ry(reg(out_reg)), [rbp - pos]
+        );
+    }
+    fn build_var(&mut self, out_reg: u8, src_arg: u32) {
+        // Variables (with their seeds) come after the three input seeds
+        let pos = 32 * (src_arg as i32 + 3);
+        dynasm!(self.0.ops
+            ; vmovups Ry(reg(out_reg)), [rcx + pos]
+        );
+    }
+    fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn multi_grad_sin(v: &mut MultiGrad) {
+            *v = v.sin()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, multi_grad_sin);
+    }
+    fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn multi_grad_cos(v: &mut MultiGrad) {
+            *v = v.cos()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, multi_grad_cos);
+    }
+    fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn multi_grad_tan(v: &mut MultiGrad) {
+            *v = v.tan()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, multi_grad_tan);
+    }
+    fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn multi_grad_asin(v: &mut MultiGrad) {
+            *v = v.asin()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, multi_grad_asin);
+    }
+    fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn multi_grad_acos(v: &mut MultiGrad) {
+            *v = v.acos()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, multi_grad_acos);
+    }
+    fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn multi_grad_atan(v: &mut MultiGrad) {
+            *v = v.atan()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, multi_grad_atan);
+    }
+    fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn multi_grad_exp(v: &mut MultiGrad) {
+            *v = v.exp()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, multi_grad_exp);
+    }
+    fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn multi_grad_ln(v: &mut MultiGrad) {
+            *v = v.ln()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, multi_grad_ln);
+    }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        // The derivatives of a rounded value are zero (almost everywhere).  The
+        // VEX-encoded `vroundss` clears the upper lanes of the output register.
+        dynasm!(self.0.ops
+            ; vpxor xmm1, xmm1, xmm1
+            ; vroundss Rx(reg(out_reg)), xmm1, Rx(reg(lhs_reg)), 1
+        );
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vpxor xmm1, xmm1, xmm1
+            ; vroundss Rx(reg(out_reg)), xmm1, Rx(reg(lhs_reg)), 2
+        );
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Add copysign(0.49999997, lhs) then truncate, which rounds half-way
+        // cases away from zero (matching `f32::round`)
+        dynasm!(self.0.ops
+            ; mov eax, 0x80000000u32 as i32
+            ; vmovd xmm2, eax
+            ; vandps xmm2, xmm2, Rx(reg(lhs_reg))
+            ; mov eax, 0x3effffffu32 as i32
+            ; vmovd xmm3, eax
+            ; vorps xmm2, xmm2, xmm3
+            ; vaddss xmm2, xmm2, Rx(reg(lhs_reg))
+            ; vpxor xmm1, xmm1, xmm1
+            ; vroundss Rx(reg(out_reg)), xmm1, xmm2, 3
+        );
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            // xmm1 = [(lhs.v == 0) ? 0xFFFFFFFF : 0, 0, 0, 0]
+            ; vpxor xmm1, xmm1, xmm1
+            ; vcmpeqss xmm1, xmm1, Rx(reg(lhs_reg))
+            ; mov eax, 1.0f32.to_bits() as i32
+            ; vmovd xmm2, eax
+            ; vandps Rx(reg(out_reg)), xmm1, xmm2
+        );
+    }
+    fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vmovups Ry(reg(out_reg)), Ry(reg(lhs_reg))
+        );
+    }
+    fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vpcmpeqw ymm1, ymm1, ymm1
+            ; vpslld ymm1, ymm1, 31 // set the sign bit
+            ; vpxor Ry(reg(out_reg)), ymm1, Ry(reg(lhs_reg))
+        );
+    }
+    fn build_abs(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            // Store 0.0 to xmm1, for comparisons
+            ; vpxor xmm1, xmm1, xmm1
+
+            ; vcomiss Rx(reg(lhs_reg)), xmm1
+            ; jb >N
+
+            // Fallthrough: non-negative (or NaN) input
+            ; vmovups Ry(reg(out_reg)), Ry(reg(lhs_reg))
+            ; jmp >E
+
+            ; N: // negative
+            ; vpcmpeqw ymm1, ymm1, ymm1
+            ; vpslld ymm1, ymm1, 31 // set the sign bit
+            ; vpxor Ry(reg(out_reg)), ymm1, Ry(reg(lhs_reg))
+            // Fallthrough to end
+
+            ; E:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_recip(&mut self, out_reg: u8, lhs_reg: u8) {
+        // d/dx 1/f(x) = -f'(x) / f(x)**2
+        dynasm!(self.0.ops
+            // Set every element in ymm1 to -f(x)**2
+            ; vmulss xmm1, Rx(reg(lhs_reg)), Rx(reg(lhs_reg))
+            ; vbroadcastss ymm1, xmm1
+            ; vpcmpeqw ymm2, ymm2, ymm2
+            ; vpslld ymm2, ymm2, 31 // set the sign bit
+            ; vxorps ymm1, ymm1, ymm2
+
+            // Set every element in ymm1 to -f'(x) / f(x)**2
+            ; vdivps ymm1, Ry(reg(lhs_reg)), ymm1
+
+            // Compute the actual reciprocal into xmm2
+            ; mov eax, 1.0f32.to_bits() as i32
+            ; vmovd xmm2, eax
+            ; vdivss xmm2, xmm2, Rx(reg(lhs_reg))
+
+            // Patch the value into the lowest lane
+            ; vblendps Ry(reg(out_reg)), ymm2, ymm1, 1
+        );
+    }
+    fn build_sqrt(&mut self, out_reg: u8, lhs_reg: u8) {
+        // d/dx sqrt(f(x)) = f'(x) / (2 * sqrt(f(x)))
+        dynasm!(self.0.ops
+            // Compute the actual square root into xmm1
+            ; vsqrtss xmm1, xmm1, Rx(reg(lhs_reg))
+
+            // Set every element in ymm2 to 2 * sqrt(f(x))
+            ; vaddss xmm2, xmm1, xmm1
+            ; vbroadcastss ymm2, xmm2
+
+            // Set every element in ymm2 to f'(x) / (2 * sqrt(f(x)))
+            ; vdivps ymm2, Ry(reg(lhs_reg)), ymm2
+
+            // Patch the value into the lowest lane
+            ; vblendps Ry(reg(out_reg)), ymm1, ymm2, 1
+        );
+    }
+    fn build_square(&mut self, out_reg: u8, lhs_reg: u8) {
+        // d/dx f(x)**2 = 2 * f(x) * f'(x)
+        dynasm!(self.0.ops
+            ; vbroadcastss ymm1, Rx(reg(lhs_reg))
+            ; vaddps ymm1, ymm1, ymm1
+            ; vmulps ymm1, ymm1, Ry(reg(lhs_reg))
+
+            ; vmulss xmm2, Rx(reg(lhs_reg)), Rx(reg(lhs_reg))
+            ; vblendps Ry(reg(out_reg)), ymm2, ymm1, 1
+        );
+    }
+    fn build_add(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vaddps Ry(reg(out_reg)), Ry(reg(lhs_reg)), Ry(reg(rhs_reg))
+        );
+    }
+    fn build_sub(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vsubps Ry(reg(out_reg)), Ry(reg(lhs_reg)), Ry(reg(rhs_reg))
+        );
+    }
+    fn build_mul(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        // d/dx f(x) * g(x) = f'(x)*g(x) + f(x)*g'(x)
+        dynasm!(self.0.ops
+            ; vbroadcastss ymm1, Rx(reg(lhs_reg))
+            ; vmulps ymm1, ymm1, Ry(reg(rhs_reg))
+            ; vbroadcastss ymm2, Rx(reg(rhs_reg))
+            ; vmulps ymm2, ymm2, Ry(reg(lhs_reg))
+            ; vaddps ymm1, ymm1, ymm2
+
+            ; vmulss xmm2, Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; vblendps Ry(reg(out_reg)), ymm2, ymm1, 1
+        );
+    }
+    fn build_div(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        // d/dx f(x) / g(x) = (f'(x)*g(x) - f(x)*g'(x)) / g(x)**2
+        dynasm!(self.0.ops
+            // f(x) * g'(x)
+            ; vbroadcastss ymm1, Rx(reg(lhs_reg))
+            ; vmulps ymm1, ymm1, Ry(reg(rhs_reg))
+
+            // g(x) * f'(x)
+            ; vbroadcastss ymm2, Rx(reg(rhs_reg))
+            ; vmulps ymm2, ymm2, Ry(reg(lhs_reg))
+
+            // f'(x)*g(x) - f(x)*g'(x)
+            ; vsubps ymm1, ymm2, ymm1
+
+            // g(x)**2
+            ; vmulss xmm2, Rx(reg(rhs_reg)), Rx(reg(rhs_reg))
+            ; vbroadcastss ymm2, xmm2
+
+            // Do the division
+            ; vdivps ymm1, ymm1, ymm2
+
+            // Patch in the actual division result
+            ; vdivss xmm2, Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; vblendps Ry(reg(out_reg)), ymm2, ymm1, 1
+        );
+    }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn multi_grad_atan2(y: &mut MultiGrad, x: &MultiGrad) {
+            *y = y.atan2(*x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, multi_grad_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn multi_grad_mod(
+            lhs: &mut MultiGrad,
+            rhs: &MultiGrad,
+        ) {
+            *lhs = lhs.rem_euclid(*rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, multi_grad_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn multi_grad_compare(
+            lhs: &mut MultiGrad,
+            rhs: &MultiGrad,
+        ) {
+            *lhs = lhs.compare(*rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, multi_grad_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vpxor xmm1, xmm1, xmm1
+            ; vcomiss Rx(reg(lhs_reg)), xmm1
+            ; jp >R // NaN is nonzero
+            ; jne >R
+
+            // Fallthrough: lhs is zero
+            ; vmovups Ry(reg(out_reg)), Ry(reg(lhs_reg))
+            ; jmp >E
+
+            ; R:
+            ; vmovups Ry(reg(out_reg)), Ry(reg(rhs_reg))
+            // Fallthrough
+
+            ; E:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vpxor xmm1, xmm1, xmm1
+            ; vcomiss Rx(reg(lhs_reg)), xmm1
+            ; jp >L // NaN is nonzero
+            ; jne >L
+
+            // Fallthrough: lhs is zero
+            ; vmovups Ry(reg(out_reg)), Ry(reg(rhs_reg))
+            ; jmp >E
+
+            ; L:
+            ; vmovups Ry(reg(out_reg)), Ry(reg(lhs_reg))
+            // Fallthrough
+
+            ; E:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vcomiss Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; jp >N // Parity flag is set if result is NAN
+            ; ja >L
+
+            // Fallthrough
+            ; vmovups Ry(reg(out_reg)), Ry(reg(rhs_reg))
+            ; jmp >E
+
+            ; N:
+            ; vpxor Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vcmpeqss Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; jmp >E
+
+            ; L:
+            ; vmovups Ry(reg(out_reg)), Ry(reg(lhs_reg))
+            // Fallthrough
+
+            ; E:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vcomiss Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; jp >N // Parity flag is set if result is NAN
+            ; ja >R
+
+            // Fallthrough
+            ; vmovups Ry(reg(out_reg)), Ry(reg(lhs_reg))
+            ; jmp >O
+
+            ; N:
+            ; vpxor Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vcmpeqss Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; jmp >O
+
+            ; R:
+            ; vmovups Ry(reg(out_reg)), Ry(reg(rhs_reg))
+            // Fallthrough
+
+            ; O:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        // The VEX-encoded `vmovd` clears the rest of the register
+        let imm_u32 = (imm as f32).to_bits();
+        dynasm!(self.0.ops
+            ; mov eax, imm_u32 as i32
+            ; vmovd Rx(IMM_REG), eax
+        );
+        IMM_REG.wrapping_sub(OFFSET)
+    }
+    fn finalize(mut self, out_reg: u8) -> Result<Mmap, Error> {
+        dynasm!(self.0.ops
+            // Copy data from out_reg into the out array, then adjust it
+            ; vmovups [r8], Ry(reg(out_reg))
+            ; add r8, 32 // 8x float
+            ; sub r9, 1
+            ; jmp ->L
+
+            // Finalization code, which happens after all evaluation is complete
+            ; -> X:
+            ; add rsp, self.0.mem_offset as i32
+            ; pop rbp
+            ; vzeroupper
+            ; ret
+        );
+
+        self.0.ops.finalize()
+    }
+}
+
+impl MultiGradSliceAssembler {
+    fn call_fn_unary(
+        &mut self,
+        out_reg: u8,
+        arg_reg: u8,
+        f: extern "sysv64" fn(&mut MultiGrad),
+    ) {
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // Store the argument to the stack before doing anything else
+            ; vmovups [rsp + 0x180], Ry(reg(arg_reg))
+        );
+        self.call_fn_prelude();
+        dynasm!(self.0.ops
+            // The function modifies its argument in-place
+            ; lea rdi, [rsp + 0x180]
+            ; mov rax, QWORD addr as _
+            ; call rax
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "sysv64" fn(&mut MultiGrad, &MultiGrad),
+    ) {
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // Store the arguments to the stack before doing anything else,
+            // since either one may be the immediate register (ymm0)
+            ; vmovups [rsp + 0x180], Ry(reg(lhs_reg))
+            ; vmovups [rsp + 0x1a0], Ry(reg(rhs_reg))
+        );
+        self.call_fn_prelude();
+        dynasm!(self.0.ops
+            // The function writes its result into the first argument
+            ; lea rdi, [rsp + 0x180]
+            ; lea rsi, [rsp + 0x1a0]
+            ; mov rax, QWORD addr as _
+            ; call rax
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves pointers and register state before a function call
+    fn call_fn_prelude(&mut self) {
+        dynasm!(self.0.ops
+            // Back up X/Y/Z pointers to the stack
+            ; mov [rbp - 0x8], rdi
+            ; mov [rbp - 0x10], rsi
+            ; mov [rbp - 0x18], rdx
+            ; mov [rbp - 0x20], rcx
+            ; mov [rbp - 0x28], r8
+            ; mov [rbp - 0x30], r9
+
+            // Back up register values to the stack, saving all 256 bits
+            ; vmovups [rsp], ymm4
+            ; vmovups [rsp + 0x20], ymm5
+            ; vmovups [rsp + 0x40], ymm6
+            ; vmovups [rsp + 0x60], ymm7
+            ; vmovups [rsp + 0x80], ymm8
+            ; vmovups [rsp + 0xa0], ymm9
+            ; vmovups [rsp + 0xc0], ymm10
+            ; vmovups [rsp + 0xe0], ymm11
+            ; vmovups [rsp + 0x100], ymm12
+            ; vmovups [rsp + 0x120], ymm13
+            ; vmovups [rsp + 0x140], ymm14
+            ; vmovups [rsp + 0x160], ymm15
+
+            // Avoid AVX-SSE transition penalties in the called function
+            ; vzeroupper
+        );
+    }
+
+    /// Restores pointers and register state after a function call, loading
+    /// the result (stored at `rsp + 0x180`) into `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
+            // Restore gradient registers
+            ; vmovups ymm4, [rsp]
+            ; vmovups ymm5, [rsp + 0x20]
+            ; vmovups ymm6, [rsp + 0x40]
+            ; vmovups ymm7, [rsp + 0x60]
+            ; vmovups ymm8, [rsp + 0x80]
+            ; vmovups ymm9, [rsp + 0xa0]
+            ; vmovups ymm10, [rsp + 0xc0]
+            ; vmovups ymm11, [rsp + 0xe0]
+            ; vmovups ymm12, [rsp + 0x100]
+            ; vmovups ymm13, [rsp + 0x120]
+            ; vmovups ymm14, [rsp + 0x140]
+            ; vmovups ymm15, [rsp + 0x160]
+
+            // Get the output value from the stack
+            ; vmovups Ry(reg(out_reg)), [rsp + 0x180]
+
+            // Restore X/Y/Z pointers
+            ; mov rdi, [rbp - 0x8]
+            ; mov rsi, [rbp - 0x10]
+            ; mov rdx, [rbp - 0x18]
+            ; mov rcx, [rbp - 0x20]
+            ; mov r8, [rbp - 0x28]
+            ; mov r9, [rbp - 0x30]
+        );
+    }
+}
@@ -162,6 +162,40 @@ impl Assembler for PointAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vroundss Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(lhs_reg)), 1
+        );
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vroundss Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(lhs_reg)), 2
+        );
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Add copysign(0.49999997, lhs) then truncate, which rounds half-way
+        // cases away from zero (matching `f32::round`)
+        dynasm!(self.0.ops
+            ; mov eax, 0x80000000u32 as i32
+            ; vmovd xmm1, eax
+            ; vandps xmm1, xmm1, Rx(reg(lhs_reg))
+            ; mov eax, 0x3effffffu32 as i32
+            ; vmovd xmm2, eax
+            ; vorps xmm1, xmm1, xmm2
+            ; vaddss xmm1, xmm1, Rx(reg(lhs_reg))
+            ; vroundss Rx(reg(out_reg)), xmm1, xmm1, 3
+        );
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            // xmm1 = (lhs == 0) ? 0xFFFFFFFF : 0, which is false for NaN
+            ; vxorps xmm1, xmm1, xmm1
+            ; vcmpss xmm1, xmm1, Rx(reg(lhs_reg)), 0
+            ; mov eax, 1.0f32.to_bits() as i32
+            ; vmovd xmm2, eax
+            ; vandps Rx(reg(out_reg)), xmm1, xmm2
+        );
+    }
     fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
         // Flip the sign bit in the float
         dynasm!(self.0.ops
@@ -216,6 +250,70 @@ impl Assembler for PointAssembler {
             ; vdivss Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
         );
     }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn float_atan2(y: f32, x: f32) -> f32 {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn float_mod(lhs: f32, rhs: f32) -> f32 {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn float_compare(lhs: f32, rhs: f32) -> f32 {
+            crate::vm::compare_f32(lhs, rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vxorps xmm1, xmm1, xmm1
+            ; vcomiss Rx(reg(lhs_reg)), xmm1
+            ; jp >R // NaN is nonzero
+            ; jne >R
+
+            // lhs == 0, so return it
+            ; vmovss Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or [rsi], CHOICE_LEFT as i8
+            ; jmp >O
+
+            ; R:
+            ; vmovss Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or [rsi], CHOICE_RIGHT as i8
+            // fallthrough to out
+
+            ; O:
+            ; or [rdx], 1
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap()
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vxorps xmm1, xmm1, xmm1
+            ; vcomiss Rx(reg(lhs_reg)), xmm1
+            ; jp >L // NaN is nonzero
+            ; jne >L
+
+            // lhs == 0, so return rhs
+            ; vmovss Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or [rsi], CHOICE_RIGHT as i8
+            ; jmp >O
+
+            ; L:
+            ; vmovss Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or [rsi], CHOICE_LEFT as i8
+            // fallthrough to out
+
+            ; O:
+            ; or [rdx], 1
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap()
+    }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             ; vcomiss Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
@@ -249,6 +347,7 @@ impl Assembler for PointAssembler {
             // fallthrough to out
 
             ; O:
+            ; add rsi, 1
         );
         self.0.ops.commit_local().unwrap()
     }
@@ -283,11 +382,12 @@ impl Assembler for PointAssembler {
             // fallthrough to out
 
             ; O:
+            ; add rsi, 1
         );
         self.0.ops.commit_local().unwrap()
     }
-    fn load_imm(&mut self, imm: f32) -> u8 {
-        let imm_u32 = imm.to_bits();
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        let imm_u32 = (imm as f32).to_bits();
         dynasm!(self.0.ops
             ; mov eax, imm_u32 as i32
             ; vmovd Rx(IMM_REG), eax
@@ -321,6 +421,39 @@ impl PointAssembler {
         arg_reg: u8,
         f: extern "sysv64" fn(f32) -> f32,
     ) {
+        self.call_fn_prelude();
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // call the function
+            ; movss xmm0, Rx(reg(arg_reg))
+            ; mov rdx, QWORD addr as _
+            ; call rdx
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "sysv64" fn(f32, f32) -> f32,
+    ) {
+        self.call_fn_prelude();
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // call the function, loading the RHS first (since the LHS may be
+            // stored in xmm0, i.e. the immediate register)
+            ; movss xmm1, Rx(reg(rhs_reg))
+            ; movss xmm0, Rx(reg(lhs_reg))
+            ; mov rdx, QWORD addr as _
+            ; call rdx
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves register state before a function call
+    fn call_fn_prelude(&mut self) {
         // Back up a few callee-saved registers that we're about to use
         if !self.0.saved_callee_regs {
             dynasm!(self.0.ops
@@ -330,7 +463,6 @@ impl PointAssembler {
             );
             self.0.saved_callee_regs = true
         }
-        let addr = f as usize;
         dynasm!(self.0.ops
             // Back up X/Y/Z pointers to caller-saved registers
             ; mov r12, rdi
@@ -350,12 +482,13 @@ impl PointAssembler {
             ; movss [rsp + 0x24], xmm13
             ; movss [rsp + 0x28], xmm14
             ; movss [rsp + 0x2c], xmm15
+        );
+    }
 
-            // call the function
-            ; movss xmm0, Rx(reg(arg_reg))
-            ; mov rdx, QWORD addr as _
-            ; call rdx
-
+    /// Restores register state after a function call, writing the result to
+    /// `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
             // Restore float registers
             ; movss xmm4, [rsp]
             ; movss xmm5, [rsp + 0x4]
@@ -0,0 +1,510 @@
+use crate::{
+    jit::{
+        mmap::Mmap, point64::Point64Assembler, reg, Assembler, AssemblerData,
+        CHOICE_BOTH, CHOICE_LEFT, CHOICE_RIGHT, IMM_REG, OFFSET,
+        REGISTER_LIMIT,
+    },
+    Error,
+};
+use dynasmrt::{dynasm, DynasmApi, DynasmLabelApi};
+
+/// Implementation of the double-precision single-point assembler on `x86_64`
+///
+/// Registers are passed in as follows:
+///
+/// | Variable   | Register | Type                  |
+/// |------------|----------|-----------------------|
+/// | X          | `xmm0`   | `f64`                 |
+/// | Y          | `xmm1`   | `f64`                 |
+/// | Z          | `xmm2`   | `f64`                 |
+/// | `vars`     | `rdi`    | `*const f64` (array)  |
+/// | `choices`  | `rsi`    | `*mut u8` (array)     |
+/// | `simplify` | `rdx`    | `*mut u8` (single)    |
+///
+/// X, Y, and Z are stored on the stack during code execution, to free up those
+/// registers as scratch values.
+///
+/// The stack is configured as follows
+///
+/// ```text
+/// | Position | Value        | Notes                                       |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x00     | `rbp`        | Previous value for base pointer             |
+/// |----------|--------------|---------------------------------------------|
+/// | -0x08    | `r12`        | During functions calls, we use these        |
+/// | -0x10    | `r13`        | as temporary storage so must preserve their |
+/// | -0x18    | `r14`        | previous values on the stack                |
+/// |----------|--------------|---------------------------------------------|
+/// | -0x20    | Z            | Inputs                                      |
+/// | -0x28    | Y            |                                             |
+/// | -0x30    | X            |                                             |
+/// |----------|--------------|---------------------------------------------|
+/// | ...      | ...          | Register spills live up here                |
+/// |----------|--------------|---------------------------------------------|
+/// | 0x58     | xmm15        | Caller-saved registers during functions     |
+/// | 0x50     | xmm14        | calls are placed here, then restored        |
+/// | 0x48     | xmm13        |                                             |
+/// | 0x40     | xmm12        |                                             |
+/// | 0x38     | xmm11        |                                             |
+/// | 0x30     | xmm10        |                                             |
+/// | 0x28     | xmm9         |                                             |
+/// | 0x20     | xmm8         |                                             |
+/// | 0x18     | xmm7         |                                             |
+/// | 0x10     | xmm6         |                                             |
+/// | 0x08     | xmm5         |                                             |
+/// | 0x00     | xmm4         |                                             |
+/// ```
+const STACK_SIZE_UPPER: usize = 0x30; // Positions relative to `rbp`
+const STACK_SIZE_LOWER: usize = 0x60; // Positions relative to `rsp`
+
+impl Assembler for Point64Assembler {
+    type Data = f64;
+
+    fn init(mmap: Mmap, slot_count: usize) -> Self {
+        let mut out = AssemblerData::new(mmap);
+        dynasm!(out.ops
+            ; push rbp
+            ; mov rbp, rsp
+        );
+        out.prepare_stack(slot_count, STACK_SIZE_UPPER + STACK_SIZE_LOWER);
+        dynasm!(out.ops
+            ; vzeroupper
+            // Put X/Y/Z on the stack to free up those registers
+            ; vmovsd [rbp - 0x20], xmm2
+            ; vmovsd [rbp - 0x28], xmm1
+            ; vmovsd [rbp - 0x30], xmm0
+        );
+        Self(out)
+    }
+
+    fn build_load(&mut self, dst_reg: u8, src_mem: u32) {
+        assert!((dst_reg as usize) < REGISTER_LIMIT);
+        let sp_offset: i32 = (self.0.stack_pos(src_mem)
+            + STACK_SIZE_LOWER as u32)
+            .try_into()
+            .unwrap();
+        dynasm!(self.0.ops
+            ; vmovsd Rx(reg(dst_reg)), [rsp + sp_offset]
+        );
+    }
+    fn build_store(&mut self, dst_mem: u32, src_reg: u8) {
+        assert!((src_reg as usize) < REGISTER_LIMIT);
+        let sp_offset: i32 = (self.0.stack_pos(dst_mem)
+            + STACK_SIZE_LOWER as u32)
+            .try_into()
+            .unwrap();
+        dynasm!(self.0.ops
+            ; vmovsd [rsp + sp_offset], Rx(reg(src_reg))
+        );
+    }
+    fn build_input(&mut self, out_reg: u8, src_arg: u8) {
+        let pos = STACK_SIZE_UPPER as i32 - 8 * (src_arg as i32);
+        dynasm!(self.0.ops
+            // Pull X/Y/Z from the stack, where they've been placed by init()
+            ; vmovsd Rx(reg(out_reg)), [rbp - pos]
+        );
+    }
+    fn build_var(&mut self, out_reg: u8, src_arg: u32) {
+        dynasm!(self.0.ops
+            ; vmovsd Rx(reg(out_reg)), [rdi + 8 * (src_arg as i32)]
+        );
+    }
+    fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vmovsd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(lhs_reg))
+        );
+    }
+    fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_sin(f: f64) -> f64 {
+            f.sin()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_sin);
+    }
+    fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_cos(f: f64) -> f64 {
+            f.cos()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_cos);
+    }
+    fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_tan(f: f64) -> f64 {
+            f.tan()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_tan);
+    }
+    fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_asin(f: f64) -> f64 {
+            f.asin()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_asin);
+    }
+    fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_acos(f: f64) -> f64 {
+            f.acos()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_acos);
+    }
+    fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_atan(f: f64) -> f64 {
+            f.atan()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_atan);
+    }
+    fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_exp(f: f64) -> f64 {
+            f.exp()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_exp);
+    }
+    fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn double_ln(f: f64) -> f64 {
+            f.ln()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, double_ln);
+    }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vroundsd Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(lhs_reg)), 1
+        );
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vroundsd Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(lhs_reg)), 2
+        );
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Add copysign(0.49999999999999994, lhs) then truncate, which rounds
+        // half-way cases away from zero (matching `f64::round`)
+        dynasm!(self.0.ops
+            ; mov rax, QWORD 0x8000000000000000u64 as i64
+            ; vmovq xmm1, rax
+            ; vandpd xmm1, xmm1, Rx(reg(lhs_reg))
+            ; mov rax, QWORD 0x3fdfffffffffffffu64 as i64
+            ; vmovq xmm2, rax
+            ; vorpd xmm1, xmm1, xmm2
+            ; vaddsd xmm1, xmm1, Rx(reg(lhs_reg))
+            ; vroundsd Rx(reg(out_reg)), xmm1, xmm1, 3
+        );
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            // xmm1 = (lhs == 0) ? 0xFFFFFFFFFFFFFFFF : 0, which is false for NaN
+            ; vxorpd xmm1, xmm1, xmm1
+            ; vcmpsd xmm1, xmm1, Rx(reg(lhs_reg)), 0
+            ; mov rax, QWORD 1.0f64.to_bits() as i64
+            ; vmovq xmm2, rax
+            ; vandpd Rx(reg(out_reg)), xmm1, xmm2
+        );
+    }
+    fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Flip the sign bit in the double
+        dynasm!(self.0.ops
+            ; mov rax, QWORD 0x8000000000000000u64 as i64
+            ; vmovq xmm1, rax
+            ; vxorpd Rx(reg(out_reg)), xmm1, Rx(reg(lhs_reg))
+        );
+    }
+    fn build_abs(&mut self, out_reg: u8, lhs_reg: u8) {
+        // Clear the sign bit in the double
+        dynasm!(self.0.ops
+            ; mov rax, QWORD 0x7fffffffffffffffu64 as i64
+            ; vmovq xmm1, rax
+            ; vandpd Rx(reg(out_reg)), xmm1, Rx(reg(lhs_reg))
+        )
+    }
+    fn build_recip(&mut self, out_reg: u8, lhs_reg: u8) {
+        let imm = self.load_imm(1.0);
+        dynasm!(self.0.ops
+            ; divsd Rx(reg(imm)), Rx(reg(lhs_reg))
+            ; movsd Rx(reg(out_reg)), Rx(reg(imm))
+        );
+    }
+    fn build_sqrt(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; sqrtsd Rx(reg(out_reg)), Rx(reg(lhs_reg))
+        );
+    }
+    fn build_square(&mut self, out_reg: u8, lhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vmulsd Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(lhs_reg))
+        );
+    }
+    fn build_add(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vaddsd Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+        )
+    }
+    fn build_sub(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vsubsd Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+        );
+    }
+    fn build_mul(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vmulsd Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+        );
+    }
+    fn build_div(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vdivsd Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+        );
+    }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn double_atan2(y: f64, x: f64) -> f64 {
+            y.atan2(x)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, double_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn double_mod(lhs: f64, rhs: f64) -> f64 {
+            lhs.rem_euclid(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, double_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn double_compare(lhs: f64, rhs: f64) -> f64 {
+            crate::vm::compare_f64(lhs, rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, double_compare);
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vxorpd xmm1, xmm1, xmm1
+            ; vcomisd Rx(reg(lhs_reg)), xmm1
+            ; jp >R // NaN is nonzero
+            ; jne >R
+
+            // lhs == 0, so return it
+            ; vmovsd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or [rsi], CHOICE_LEFT as i8
+            ; jmp >O
+
+            ; R:
+            ; vmovsd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or [rsi], CHOICE_RIGHT as i8
+            // fallthrough to out
+
+            ; O:
+            ; or [rdx], 1
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap()
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vxorpd xmm1, xmm1, xmm1
+            ; vcomisd Rx(reg(lhs_reg)), xmm1
+            ; jp >L // NaN is nonzero
+            ; jne >L
+
+            // lhs == 0, so return rhs
+            ; vmovsd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or [rsi], CHOICE_RIGHT as i8
+            ; jmp >O
+
+            ; L:
+            ; vmovsd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or [rsi], CHOICE_LEFT as i8
+            // fallthrough to out
+
+            ; O:
+            ; or [rdx], 1
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap()
+    }
+    fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vcomisd Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; jp >N
+            ; ja >L
+            ; jb >R
+
+            // Fallthrough for equal, so just copy to the output register
+            ; or [rsi], CHOICE_BOTH as i8
+            ; vmovsd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; jmp >O
+
+            // Fallthrough for NaN, which are !=; do a float addition to
+            // propagate it to the output register.
+            ; N:
+            ; or [rsi], CHOICE_BOTH as i8
+            ; vaddsd Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; jmp >O
+
+            ; L:
+            ; vmovsd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or [rsi], CHOICE_LEFT as i8
+            ; or [rdx], 1
+            ; jmp >O
+
+            ; R:
+            ; vmovsd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or [rsi], CHOICE_RIGHT as i8
+            ; or [rdx], 1
+            // fallthrough to out
+
+            ; O:
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap()
+    }
+    fn build_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            ; vcomisd Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; jp >N
+            ; ja >R
+            ; jb >L
+
+            // Fallthrough for equal, so just copy to the output register
+            ; or [rsi], CHOICE_BOTH as i8
+            ; vmovsd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; jmp >O
+
+            ; N:
+            ; or [rsi], CHOICE_BOTH as i8
+            ; vaddsd Rx(reg(out_reg)), Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; jmp >O
+
+            ; L:
+            ; vmovsd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or [rsi], CHOICE_LEFT as i8
+            ; or [rdx], 1
+            ; jmp >O
+
+            ; R:
+            ; vmovsd Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or [rsi], CHOICE_RIGHT as i8
+            ; or [rdx], 1
+            // fallthrough to out
+
+            ; O:
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap()
+    }
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        dynasm!(self.0.ops
+            ; mov rax, QWORD imm.to_bits() as i64
+            ; vmovq Rx(IMM_REG), rax
+        );
+        IMM_REG.wrapping_sub(OFFSET)
+    }
+    fn finalize(mut self, out_reg: u8) -> Result<Mmap, Error> {
+        if self.0.saved_callee_regs {
+            dynasm!(self.0.ops
+                ; mov r12, [rbp - 0x8]
+                ; mov r13, [rbp - 0x10]
+                ; mov r14, [rbp - 0x18]
+            );
+        }
+        dynasm!(self.0.ops
+            // Prepare our return value
+            ; vmovsd xmm0, xmm0, Rx(reg(out_reg))
+            ; add rsp, self.0.mem_offset as i32
+            ; pop rbp
+            ; emms
+            ; ret
+        );
+        self.0.ops.finalize()
+    }
+}
+
+impl Point64Assembler {
+    fn call_fn_unary(
+        &mut self,
+        out_reg: u8,
+        arg_reg: u8,
+        f: extern "sysv64" fn(f64) -> f64,
+    ) {
+        self.call_fn_prelude();
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // call the function
+            ; movsd xmm0, Rx(reg(arg_reg))
+            ; mov rdx, QWORD addr as _
+            ; call rdx
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    fn call_fn_binary(
+        &mut self,
+        out_reg: u8,
+        lhs_reg: u8,
+        rhs_reg: u8,
+        f: extern "sysv64" fn(f64, f64) -> f64,
+    ) {
+        self.call_fn_prelude();
+        let addr = f as usize;
+        dynasm!(self.0.ops
+            // call the function, loading the RHS first (since the LHS may be
+            // stored in xmm0, i.e. the immediate register)
+            ; movsd xmm1, Rx(reg(rhs_reg))
+            ; movsd xmm0, Rx(reg(lhs_reg))
+            ; mov rdx, QWORD addr as _
+            ; call rdx
+        );
+        self.call_fn_postlude(out_reg);
+    }
+
+    /// Saves register state before a function call
+    fn call_fn_prelude(&mut self) {
+        // Back up a few callee-saved registers that we're about to use
+        if !self.0.saved_callee_regs {
+            dynasm!(self.0.ops
+                ; mov [rbp - 0x8], r12
+                ; mov [rbp - 0x10], r13
+                ; mov [rbp - 0x18], r14
+            );
+            self.0.saved_callee_regs = true
+        }
+        dynasm!(self.0.ops
+            // Back up vars/choice/simplify pointers to callee-saved registers
+            ; mov r12, rdi
+            ; mov r13, rsi
+            ; mov r14, rdx
+
+            // Back up all register values to the stack
+            ; movsd [rsp], xmm4
+            ; movsd [rsp + 0x08], xmm5
+            ; movsd [rsp + 0x10], xmm6
+            ; movsd [rsp + 0x18], xmm7
+            ; movsd [rsp + 0x20], xmm8
+            ; movsd [rsp + 0x28], xmm9
+            ; movsd [rsp + 0x30], xmm10
+            ; movsd [rsp + 0x38], xmm11
+            ; movsd [rsp + 0x40], xmm12
+            ; movsd [rsp + 0x48], xmm13
+            ; movsd [rsp + 0x50], xmm14
+            ; movsd [rsp + 0x58], xmm15
+        );
+    }
+
+    /// Restores register state after a function call, writing the result to
+    /// `out_reg`
+    fn call_fn_postlude(&mut self, out_reg: u8) {
+        dynasm!(self.0.ops
+            // Restore float registers
+            ; movsd xmm4, [rsp]
+            ; movsd xmm5, [rsp + 0x08]
+            ; movsd xmm6, [rsp + 0x10]
+            ; movsd xmm7, [rsp + 0x18]
+            ; movsd xmm8, [rsp + 0x20]
+            ; movsd xmm9, [rsp + 0x28]
+            ; movsd xmm10, [rsp + 0x30]
+            ; movsd xmm11, [rsp + 0x38]
+            ; movsd xmm12, [rsp + 0x40]
+            ; movsd xmm13, [rsp + 0x48]
+            ; movsd xmm14, [rsp + 0x50]
+            ; movsd xmm15, [rsp + 0x58]
+
+            // Restore vars/choice/simplify pointers
+            ; mov rdi, r12
+            ; mov rsi, r13
+            ; mov rdx, r14
+
+            ; movsd Rx(reg(out_reg)), xmm0
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{
+    eval::types::{Interval, Interval64},
+    jit::{
+        interval::IntervalAssembler, mmap::Mmap, reg,
+        robust_interval::RobustIntervalAssembler, Assembler, IMM_REG, OFFSET,
+    },
+    Error,
+};
+use dynasmrt::{dynasm, DynasmApi};
+
+/// Implementation of the robust interval assembler on `x86_64`
+///
+/// Most operations are delegated to the [`IntervalAssembler`]; operations
+/// which round are followed by a widening step, and transcendental functions
+/// are evaluated in double precision by calling into Rust.
+impl Assembler for RobustIntervalAssembler {
+    type Data = Interval;
+
+    fn init(mmap: Mmap, slot_count: usize) -> Self {
+        Self(IntervalAssembler::init(mmap, slot_count))
+    }
+    fn bytes_per_clause() -> usize {
+        IntervalAssembler::bytes_per_clause() + 64
+    }
+    fn build_load(&mut self, dst_reg: u8, src_mem: u32) {
+        self.0.build_load(dst_reg, src_mem)
+    }
+    fn build_store(&mut self, dst_mem: u32, src_reg: u8) {
+        self.0.build_store(dst_mem, src_reg)
+    }
+    fn build_input(&mut self, out_reg: u8, src_arg: u8) {
+        self.0.build_input(out_reg, src_arg)
+    }
+    fn build_var(&mut self, out_reg: u8, src_arg: u32) {
+        self.0.build_var(out_reg, src_arg)
+    }
+    fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_sin(v: Interval) -> Interval {
+            Interval64::from(v).sin().round_outward()
+        }
+        self.0.call_fn_unary(out_reg, lhs_reg, interval_sin);
+    }
+    fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_cos(v: Interval) -> Interval {
+            Interval64::from(v).cos().round_outward()
+        }
+        self.0.call_fn_unary(out_reg, lhs_reg, interval_cos);
+    }
+    fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_tan(v: Interval) -> Interval {
+            Interval64::from(v).tan().round_outward()
+        }
+        self.0.call_fn_unary(out_reg, lhs_reg, interval_tan);
+    }
+    fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_asin(v: Interval) -> Interval {
+            Interval64::from(v).asin().round_outward()
+        }
+        self.0.call_fn_unary(out_reg, lhs_reg, interval_asin);
+    }
+    fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_acos(v: Interval) -> Interval {
+            Interval64::from(v).acos().round_outward()
+        }
+        self.0.call_fn_unary(out_reg, lhs_reg, interval_acos);
+    }
+    fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_atan(v: Interval) -> Interval {
+            Interval64::from(v).atan().round_outward()
+        }
+        self.0.call_fn_unary(out_reg, lhs_reg, interval_atan);
+    }
+    fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_exp(v: Interval) -> Interval {
+            Interval64::from(v).exp().round_outward()
+        }
+        self.0.call_fn_unary(out_reg, lhs_reg, interval_exp);
+    }
+    fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_ln(v: Interval) -> Interval {
+            Interval64::from(v).ln().round_outward()
+        }
+        self.0.call_fn_unary(out_reg, lhs_reg, interval_ln);
+    }
+    fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
+        self.0.build_floor(out_reg, lhs_reg)
+    }
+    fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
+        self.0.build_ceil(out_reg, lhs_reg)
+    }
+    fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
+        self.0.build_round(out_reg, lhs_reg)
+    }
+    fn build_not(&mut self, out_reg: u8, lhs_reg: u8) {
+        self.0.build_not(out_reg, lhs_reg)
+    }
+    fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
+        self.0.build_copy(out_reg, lhs_reg)
+    }
+    fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
+        self.0.build_neg(out_reg, lhs_reg)
+    }
+    fn build_abs(&mut self, out_reg: u8, lhs_reg: u8) {
+        self.0.build_abs(out_reg, lhs_reg)
+    }
+    fn build_recip(&mut self, out_reg: u8, lhs_reg: u8) {
+        self.0.build_recip(out_reg, lhs_reg);
+        self.build_widen(out_reg);
+    }
+    fn build_sqrt(&mut self, out_reg: u8, lhs_reg: u8) {
+        self.0.build_sqrt(out_reg, lhs_reg);
+        self.build_widen(out_reg);
+    }
+    fn build_square(&mut self, out_reg: u8, lhs_reg: u8) {
+        self.0.build_square(out_reg, lhs_reg);
+        self.build_widen(out_reg);
+    }
+    fn build_add(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        self.0.build_add(out_reg, lhs_reg, rhs_reg);
+        self.build_widen(out_reg);
+    }
+    fn build_sub(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        self.0.build_sub(out_reg, lhs_reg, rhs_reg);
+        self.build_widen(out_reg);
+    }
+    fn build_mul(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        self.0.build_mul(out_reg, lhs_reg, rhs_reg);
+        self.build_widen(out_reg);
+    }
+    fn build_div(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        self.0.build_div(out_reg, lhs_reg, rhs_reg);
+        self.build_widen(out_reg);
+    }
+    fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn interval_atan2(
+            y: Interval,
+            x: Interval,
+        ) -> Interval {
+            Interval64::from(y).atan2(x.into()).round_outward()
+        }
+        self.0
+            .call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_atan2);
+    }
+    fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn interval_mod(
+            lhs: Interval,
+            rhs: Interval,
+        ) -> Interval {
+            Interval64::from(lhs).rem_euclid(rhs.into()).round_outward()
+        }
+        self.0
+            .call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_mod);
+    }
+    fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        self.0.build_compare(out_reg, lhs_reg, rhs_reg)
+    }
+    fn build_and(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        self.0.build_and(out_reg, lhs_reg, rhs_reg)
+    }
+    fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        self.0.build_or(out_reg, lhs_reg, rhs_reg)
+    }
+    fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        self.0.build_max(out_reg, lhs_reg, rhs_reg)
+    }
+    fn build_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        self.0.build_min(out_reg, lhs_reg, rhs_reg)
+    }
+    /// Loads the tightest interval containing `imm` into `IMM_REG`
+    fn load_imm(&mut self, imm: f64) -> u8 {
+        let imm = Interval::enclosing(imm);
+        let lower_u32 = imm.lower().to_bits();
+        let upper_u32 = imm.upper().to_bits();
+        dynasm!(self.0 .0.ops
+            ; mov eax, lower_u32 as i32
+            ; vmovd Rx(IMM_REG), eax
+            ; mov eax, upper_u32 as i32
+            ; vpinsrd Rx(IMM_REG), Rx(IMM_REG), eax, 1
+        );
+        IMM_REG.wrapping_sub(OFFSET)
+    }
+    fn finalize(self, out_reg: u8) -> Result<Mmap, Error> {
+        self.0.finalize(out_reg)
+    }
+}
+
+impl RobustIntervalAssembler {
+    /// Widens the interval in `out_reg` by one ulp in each direction
+    ///
+    /// This matches [`Interval::widen`]: infinite bounds and `NaN` are left
+    /// unchanged.
+    fn build_widen(&mut self, out_reg: u8) {
+        dynasm!(self.0 .0.ops
+            // Negate the lower bound, so that both bounds are moved upwards
+            ; mov eax, 0x80000000u32 as i32
+            ; vmovd xmm1, eax // xmm1 = [0x80000000, 0, 0, 0]
+            ; vxorps Rx(reg(out_reg)), Rx(reg(out_reg)), xmm1
+
+            // Adding zero converts -0.0 to +0.0, leaving other values alone
+            ; vxorps xmm2, xmm2, xmm2
+            ; vaddps Rx(reg(out_reg)), Rx(reg(out_reg)), xmm2
+
+            // Build a step of +1 (for positive values) or -1 (for negative
+            // values), which moves the bit pattern towards +inf
+            ; vpsrad xmm2, Rx(reg(out_reg)), 31
+            ; vpcmpeqd xmm3, xmm3, xmm3
+            ; vpsrld xmm3, xmm3, 31
+            ; vpor xmm2, xmm2, xmm3
+
+            // Clear the step for +inf and NaN, which are left unchanged
+            ; mov eax, f32::INFINITY.to_bits() as i32
+            ; vmovd xmm3, eax
+            ; vbroadcastss xmm3, xmm3
+            ; vcmpps xmm3, Rx(reg(out_reg)), xmm3, 0xc // not equal (ordered)
+            ; vpand xmm2, xmm2, xmm3
+            ; vpaddd Rx(reg(out_reg)), Rx(reg(out_reg)), xmm2
+
+            // Restore the sign of the lower bound
+            ; vxorps Rx(reg(out_reg)), Rx(reg(out_reg)), xmm1
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Per-vertex data computed after meshing
+//!
+//! These are opt-in passes which evaluate a shape at the final vertices of a
+//! [`Mesh`], e.g. to populate normals for smooth shading.
+use super::Mesh;
+use crate::{
+    eval::{BulkEvaluator, Shape},
+    Error,
+};
+
+/// A named scalar value stored at each vertex of a [`Mesh`]
+#[derive(Clone, Debug, Default)]
+pub struct VertexAttribute {
+    /// Name of the attribute, used when writing it to a file
+    pub name: String,
+    /// Attribute values, with one per vertex
+    pub values: Vec<f32>,
+}
+
+impl Mesh {
+    /// Populates [`self.normals`](Self::normals) from the shape's gradient
+    ///
+    /// The shape and variable values should be the same as those used to build
+    /// the mesh.  Normals are normalized; vertices where the gradient is zero
+    /// get a zero normal.
+    pub fn compute_normals<S: Shape>(
+        &mut self,
+        shape: &S,
+        vars: &[f32],
+    ) -> Result<(), Error> {
+        let mut eval = S::new_grad_slice_eval();
+        let tape = shape.grad_slice_tape(Default::default());
+        let [xs, ys, zs] = self.vertex_slices();
+        let out = eval.eval(&tape, &xs, &ys, &zs, vars)?;
+        self.normals = out[0]
+            .iter()
+            .map(|g| {
+                nalgebra::Vector3::new(g.dx, g.dy, g.dz)
+                    .try_normalize(0.0)
+                    .unwrap_or_else(nalgebra::Vector3::zeros)
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Evaluates the given shape at every vertex, storing the result as a
+    /// named attribute
+    ///
+    /// `vars` are the values for the shape's variables, in the same order as
+    /// [`Settings::vars`](super::Settings::vars).
+    ///
+    /// If an attribute with this name already exists, it is replaced.
+    ///
+    /// Returns `Error::BadAttributeName` if the name is empty or contains
+    /// whitespace, or `Error::ReservedAttributeName` if it collides with the
+    /// position or normal properties written by [`Mesh::write_ply`] (`x`, `y`,
+    /// `z`, `nx`, `ny`, `nz`).
+    pub fn compute_attribute<S: Shape>(
+        &mut self,
+        name: &str,
+        shape: &S,
+        vars: &[f32],
+    ) -> Result<(), Error> {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(Error::BadAttributeName(name.to_owned()));
+        } else if matches!(name, "x" | "y" | "z" | "nx" | "ny" | "nz") {
+            return Err(Error::ReservedAttributeName(name.to_owned()));
+        }
+
+        let mut eval = S::new_float_slice_eval();
+        let tape = shape.float_slice_tape(Default::default());
+        let [xs, ys, zs] = self.vertex_slices();
+        let values = eval.eval(&tape, &xs, &ys, &zs, vars)?[0].to_vec();
+
+        if let Some(a) = self.attributes.iter_mut().find(|a| a.name == name) {
+            a.values = values;
+        } else {
+            self.attributes.push(VertexAttribute {
+                name: name.to_owned(),
+                values,
+            });
+        }
+        Ok(())
+    }
+
+    /// Looks up an attribute by name
+    pub fn attribute(&self, name: &str) -> Option<&[f32]> {
+        self.attributes
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| a.values.as_slice())
+    }
+
+    /// Splits vertex positions into X, Y, Z slices for bulk evaluation
+    fn vertex_slices(&self) -> [Vec<f32>; 3] {
+        [0, 1, 2].map(|i| self.vertices.iter().map(|v| v[i]).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        eval::{MathShape, ShapeVars, Vars},
+        mesh::output::test::sphere_mesh,
+        vm::VmShape,
+    };
+
+    #[test]
+    fn test_normals() {
+        let (shape, mut mesh) = sphere_mesh();
+        mesh.compute_normals(&shape, &[]).unwrap();
+        assert_eq!(mesh.normals.len(), mesh.vertices.len());
+        for (v, n) in mesh.vertices.iter().zip(&mesh.normals) {
+            assert!((n.norm() - 1.0).abs() < 1e-6);
+            assert!((v.normalize() - n).norm() < 1e-3, "{v} {n}");
+        }
+    }
+
+    #[test]
+    fn test_attributes() {
+        let (_shape, mut mesh) = sphere_mesh();
+        let (ctx, root) = {
+            let mut ctx = crate::Context::new();
+            let x = ctx.x();
+            let z = ctx.z();
+            let root = ctx.mul(x, z).unwrap();
+            (ctx, root)
+        };
+        let xz = VmShape::new(&ctx, root).unwrap();
+        mesh.compute_attribute("xz", &xz, &[]).unwrap();
+        let values = mesh.attribute("xz").unwrap();
+        assert_eq!(values.len(), mesh.vertices.len());
+        for (v, a) in mesh.vertices.iter().zip(values) {
+            assert_eq!(*a, v.x * v.z);
+        }
+
+        // Replacing an attribute keeps a single copy
+        mesh.compute_attribute("xz", &xz, &[]).unwrap();
+        assert_eq!(mesh.attributes.len(), 1);
+        assert!(mesh.attribute("yz").is_none());
+
+        assert!(matches!(
+            mesh.compute_attribute("", &xz, &[]),
+            Err(Error::BadAttributeName(..))
+        ));
+        assert!(matches!(
+            mesh.compute_attribute("a b", &xz, &[]),
+            Err(Error::BadAttributeName(..))
+        ));
+        for name in ["x", "nx", "nz"] {
+            assert!(matches!(
+                mesh.compute_attribute(name, &xz, &[]),
+                Err(Error::ReservedAttributeName(n)) if n == name
+            ));
+        }
+    }
+
+    #[test]
+    fn test_vars() {
+        let mut ctx = crate::Context::new();
+        let (x, y, z) = (ctx.x(), ctx.y(), ctx.z());
+        let r = ctx.var("r").unwrap();
+        let x2 = ctx.square(x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let z2 = ctx.square(z).unwrap();
+        let sum = ctx.add(x2, y2).unwrap();
+        let sum = ctx.add(sum, z2).unwrap();
+        let dist = ctx.sqrt(sum).unwrap();
+        let root = ctx.sub(dist, r).unwrap();
+        let shape = VmShape::new(&ctx, root).unwrap();
+        let mut vars = Vars::new(shape.vars());
+        let vars = vars.bind([("r", 0.6)].into_iter());
+
+        let (_shape, mut mesh) = sphere_mesh();
+        mesh.compute_normals(&shape, vars).unwrap();
+        for (v, n) in mesh.vertices.iter().zip(&mesh.normals) {
+            assert!((v.normalize() - n).norm() < 1e-3, "{v} {n}");
+        }
+        mesh.compute_attribute("d", &shape, vars).unwrap();
+        for (v, d) in mesh.vertices.iter().zip(mesh.attribute("d").unwrap()) {
+            assert!((d - (v.norm() - 0.6)).abs() < 1e-6);
+        }
+
+        assert!(matches!(
+            mesh.compute_normals(&shape, &[]),
+            Err(Error::BadVarSlice(0, 1))
+        ));
+        assert!(matches!(
+            mesh.compute_attribute("d", &shape, &[]),
+            Err(Error::BadVarSlice(0, 1))
+        ));
+    }
+}
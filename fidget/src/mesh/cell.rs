@@ -4,6 +4,7 @@ use crate::eval::types::Interval;
 use super::{
     gen::CELL_TO_EDGE_TO_VERT,
     types::{Axis, Corner, Edge, Intersection, X, Y, Z},
+    Bounds,
 };
 
 /// Raw cell data
@@ -158,6 +159,16 @@ impl Default for CellIndex {
     }
 }
 
+impl From<Bounds> for CellIndex {
+    fn from(bounds: Bounds) -> Self {
+        CellIndex {
+            index: 0,
+            bounds: bounds.into(),
+            depth: 0,
+        }
+    }
+}
+
 impl CellIndex {
     pub fn new() -> Self {
         CellIndex {
@@ -231,6 +242,15 @@ impl Default for CellBounds {
     }
 }
 
+impl From<Bounds> for CellBounds {
+    fn from(b: Bounds) -> Self {
+        let x = Interval::new(b.lower.x, b.upper.x);
+        let y = Interval::new(b.lower.y, b.upper.y);
+        let z = Interval::new(b.lower.z, b.upper.z);
+        Self { x, y, z }
+    }
+}
+
 impl CellBounds {
     pub fn new() -> Self {
         let x = Interval::new(-1.0, 1.0);
@@ -13,21 +13,30 @@
 //! However, they may contain self-intersections, and are not guaranteed to
 //! catch thin features (below the sampling grid resolution).
 //!
-//! The resulting [`Mesh`] objects can be written out as STL files.
+//! The resulting [`Mesh`] objects can be written out as STL, OBJ, or PLY
+//! files.  Per-vertex normals and attributes can be added afterwards with
+//! [`Mesh::compute_normals`] and [`Mesh::compute_attribute`].
 //!
 //! Here's a full example:
 //!
 //! ```
 //! use fidget::{
 //!     eval::MathShape,
-//!     mesh::{Octree, Settings},
+//!     mesh::{Bounds, Octree, Settings},
 //!     vm::VmShape
 //! };
 //!
 //! let (node, ctx) = fidget::rhai::eval("sphere(0, 0, 0, 0.6).call(x, y, z)")?;
 //! let shape = VmShape::new(&ctx, node)?;
-//! let settings = Settings { threads: 8, min_depth: 4, max_depth: 4 };
-//! let o = Octree::build(&shape, settings);
+//! let settings = Settings {
+//!     threads: 8,
+//!     min_depth: 4,
+//!     max_depth: 4,
+//!     bounds: Bounds::default(),
+//!     interval_mode: Default::default(),
+//!     vars: &[],
+//! };
+//! let o = Octree::build(&shape, settings)?;
 //! let mesh = o.walk_dual(settings);
 //!
 //! // Open a file to write, e.g.
@@ -37,6 +46,7 @@
 //! # Ok::<(), fidget::Error>(())
 //! ```
 
+mod attributes;
 mod builder;
 mod cell;
 mod dc;
@@ -54,6 +64,10 @@ pub mod types;
 // Re-export the main Octree type as public
 pub use octree::Octree;
 
+pub use attributes::VertexAttribute;
+
+use crate::eval::IntervalMode;
+
 ////////////////////////////////////////////////////////////////////////////////
 
 /// An indexed 3D mesh
@@ -63,6 +77,17 @@ pub struct Mesh {
     pub triangles: Vec<nalgebra::Vector3<usize>>,
     /// Vertex positions
     pub vertices: Vec<nalgebra::Vector3<f32>>,
+    /// Per-vertex normals
+    ///
+    /// This is empty unless populated by [`Mesh::compute_normals`]; if it is
+    /// not empty, then it has the same length as
+    /// [`self.vertices`](Self::vertices).
+    pub normals: Vec<nalgebra::Vector3<f32>>,
+    /// Per-vertex scalar attributes
+    ///
+    /// These are populated by [`Mesh::compute_attribute`]; each attribute has
+    /// one value per vertex.
+    pub attributes: Vec<VertexAttribute>,
 }
 
 impl Mesh {
@@ -72,9 +97,67 @@ impl Mesh {
     }
 }
 
+/// Axis-aligned region in which to build an octree
+///
+/// The region does not need to be cubic; if it isn't, then octree cells are
+/// stretched to match its aspect ratio.
+///
+/// Both corners must be finite, and `lower` must be less than `upper` on every
+/// axis; [`Octree::build`] returns [`Error::BadBounds`](crate::Error::BadBounds)
+/// otherwise.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Bounds {
+    /// Lower corner of the region
+    pub lower: nalgebra::Vector3<f32>,
+    /// Upper corner of the region
+    pub upper: nalgebra::Vector3<f32>,
+}
+
+impl Default for Bounds {
+    /// Returns the region `[-1, 1]` on all axes
+    fn default() -> Self {
+        Self {
+            lower: nalgebra::Vector3::repeat(-1.0),
+            upper: nalgebra::Vector3::repeat(1.0),
+        }
+    }
+}
+
+impl Bounds {
+    /// Builds a new region from its lower and upper corners
+    ///
+    /// `lower` must be less than `upper` on every axis; this is checked when
+    /// the region is used to build an [`Octree`].
+    pub const fn new(
+        lower: nalgebra::Vector3<f32>,
+        upper: nalgebra::Vector3<f32>,
+    ) -> Self {
+        Self { lower, upper }
+    }
+
+    /// Builds a cubic region with the given center and half-width
+    ///
+    /// `half_width` must be positive.
+    pub fn cube(center: nalgebra::Vector3<f32>, half_width: f32) -> Self {
+        let d = nalgebra::Vector3::repeat(half_width);
+        Self {
+            lower: center - d,
+            upper: center + d,
+        }
+    }
+
+    /// Checks whether both corners are finite, with `lower < upper`
+    pub fn is_valid(&self) -> bool {
+        self.lower
+            .iter()
+            .zip(&self.upper)
+            .all(|(lo, hi)| lo.is_finite() && hi.is_finite() && lo < hi)
+    }
+}
+
 /// Settings when building an octree and mesh
 #[derive(Copy, Clone, Debug)]
-pub struct Settings {
+pub struct Settings<'a> {
     /// Number of threads to use
     ///
     /// 0 indicates to use the single-threaded evaluator; other values will
@@ -92,4 +175,20 @@ pub struct Settings {
     ///
     /// This is **much slower**.
     pub max_depth: u8,
+
+    /// Region in which to evaluate the shape
+    ///
+    /// Mesh vertices are returned in the same coordinate system as this
+    /// region, so there's no need to rescale the model beforehand.
+    pub bounds: Bounds,
+
+    /// Evaluator used to find bounds over each octree cell
+    pub interval_mode: IntervalMode,
+
+    /// Values for variables in the shape, passed to every evaluator
+    ///
+    /// These must be in the shape's variable order (e.g. as returned by
+    /// [`Vars::bind`](crate::eval::Vars::bind)), and should be empty if the
+    /// shape doesn't have any variables.
+    pub vars: &'a [f32],
 }
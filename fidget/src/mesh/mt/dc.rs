@@ -73,7 +73,9 @@ impl<'a> DcWorker<'a> {
                 verts: vec![],
             })
             .collect::<Vec<_>>();
-        workers[0].queue.push(Task::Cell(CellIndex::default()));
+        workers[0]
+            .queue
+            .push(Task::Cell(CellIndex::from(octree.bounds)));
 
         let pool = &ThreadPool::new(threads as usize);
         let out: Vec<_> = std::thread::scope(|s| {
@@ -102,6 +104,8 @@ impl<'a> DcWorker<'a> {
                 *vert_offsets.last().unwrap()
             ],
             triangles: vec![nalgebra::Vector3::zeros(); tri_count],
+            normals: vec![],
+            attributes: vec![],
         };
 
         let mut slice = mesh.vertices.as_mut_slice();
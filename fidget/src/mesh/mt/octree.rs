@@ -31,12 +31,12 @@ impl<S: Shape> std::ops::Deref for Task<S> {
 impl<S: Shape> Task<S> {
     /// Builds a new root task
     ///
-    /// The root task is from worker 0 with the default cell index
-    fn new(eval: Arc<EvalGroup<S>>) -> Self {
+    /// The root task is from worker 0 with the given root cell index
+    fn new(eval: Arc<EvalGroup<S>>, root: CellIndex) -> Self {
         Self {
             data: Arc::new(TaskData {
                 eval,
-                target_cell: CellIndex::default(),
+                target_cell: root,
                 assigned_by: 0,
                 parent: None,
             }),
@@ -132,9 +132,9 @@ impl<S: Shape> OctreeWorker<S> {
             .map(|(thread_index, (queue, done))| OctreeWorker {
                 thread_index,
                 octree: if thread_index == 0 {
-                    OctreeBuilder::new()
+                    OctreeBuilder::new(settings.bounds)
                 } else {
-                    OctreeBuilder::empty()
+                    OctreeBuilder::empty(settings.bounds)
                 },
                 queue,
                 done,
@@ -142,13 +142,13 @@ impl<S: Shape> OctreeWorker<S> {
             })
             .collect::<Vec<_>>();
 
-        let root = CellIndex::default();
+        let root = CellIndex::from(settings.bounds);
         let r = workers[0].octree.eval_cell(&eval, root, settings);
         let c = match r {
             CellResult::Done(cell) => Some(cell),
             CellResult::Recurse(eval) => {
                 // Inject the recursive task into worker[0]'s queue
-                workers[0].queue.push(Task::new(eval));
+                workers[0].queue.push(Task::new(eval, root));
                 None
             }
         };
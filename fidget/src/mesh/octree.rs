@@ -10,9 +10,12 @@ use super::{
     mt::{DcWorker, OctreeWorker},
     qef::QuadraticErrorSolver,
     types::{Axis, Corner, Edge, EdgeMask, Face, FaceMask},
-    Mesh, Settings,
+    Bounds, Mesh, Settings,
+};
+use crate::{
+    eval::{BulkEvaluator, IntervalMode, Shape, Tape, TracingEvaluator},
+    Error,
 };
-use crate::eval::{BulkEvaluator, Shape, Tape, TracingEvaluator};
 use std::{num::NonZeroUsize, sync::Arc, sync::OnceLock};
 
 /// Helper struct to contain a set of matched evaluators
@@ -25,6 +28,7 @@ pub struct EvalGroup<S: Shape> {
     // indirection (since the tapes also contain `Arc`); could we flatten
     // them out?  (same with the shape, which is usually an `Arc`)
     pub interval: OnceLock<<S::IntervalEval as TracingEvaluator>::Tape>,
+    pub affine: OnceLock<<S::AffineEval as TracingEvaluator>::Tape>,
     pub float_slice: OnceLock<<S::FloatSliceEval as BulkEvaluator>::Tape>,
     pub grad_slice: OnceLock<<S::GradSliceEval as BulkEvaluator>::Tape>,
 }
@@ -34,6 +38,7 @@ impl<S: Shape> EvalGroup<S> {
         Self {
             shape,
             interval: OnceLock::new(),
+            affine: OnceLock::new(),
             float_slice: OnceLock::new(),
             grad_slice: OnceLock::new(),
         }
@@ -46,6 +51,14 @@ impl<S: Shape> EvalGroup<S> {
             self.shape.interval_tape(storage.pop().unwrap_or_default())
         })
     }
+    fn affine_tape(
+        &self,
+        storage: &mut Vec<S::TapeStorage>,
+    ) -> &<S::AffineEval as TracingEvaluator>::Tape {
+        self.affine.get_or_init(|| {
+            self.shape.affine_tape(storage.pop().unwrap_or_default())
+        })
+    }
     fn float_slice_tape(
         &self,
         storage: &mut Vec<S::TapeStorage>,
@@ -80,6 +93,9 @@ pub struct Octree {
     /// This is indexed by cell leaf index; the exact shape depends heavily on
     /// the number of intersections and vertices within each leaf.
     pub(crate) verts: Vec<CellVertex>,
+
+    /// Region in which the octree was built
+    pub(crate) bounds: Bounds,
 }
 
 impl Octree {
@@ -102,6 +118,7 @@ impl Octree {
         let mut out = Octree {
             cells: Vec::with_capacity(*cell_offsets.last().unwrap()),
             verts: Vec::with_capacity(*vert_offsets.last().unwrap()),
+            bounds: os[0].bounds,
         };
 
         for (t, o) in os.iter().enumerate() {
@@ -126,13 +143,33 @@ impl Octree {
 
     /// Builds an octree to the given depth
     ///
-    /// The shape is evaluated on the region `[-1, 1]` on all axes
-    pub fn build<S: Shape + Clone>(shape: &S, settings: Settings) -> Self {
+    /// The shape is evaluated on the region given by
+    /// [`settings.bounds`](Settings::bounds).
+    ///
+    /// Returns an error if [`settings.bounds`](Settings::bounds) is invalid
+    /// (see [`Bounds::is_valid`]), or if [`settings.vars`](Settings::vars)
+    /// doesn't match the shape's variable count.
+    pub fn build<S: Shape + Clone>(
+        shape: &S,
+        settings: Settings,
+    ) -> Result<Self, Error> {
+        if !settings.bounds.is_valid() {
+            return Err(Error::BadBounds);
+        }
+        // Check variables up front, because evaluation errors in the worker
+        // threads would otherwise cause a panic.
+        if settings.vars.len() != shape.var_count() {
+            return Err(Error::BadVarSlice(
+                settings.vars.len(),
+                shape.var_count(),
+            ));
+        }
         let eval = Arc::new(EvalGroup::new(shape.clone()));
+        let root = CellIndex::from(settings.bounds);
 
         let mut octree = if settings.threads == 0 {
-            let mut out = OctreeBuilder::new();
-            out.recurse(&eval, CellIndex::default(), settings);
+            let mut out = OctreeBuilder::new(settings.bounds);
+            out.recurse(&eval, root, settings);
             out.into()
         } else {
             OctreeWorker::scheduler(eval.clone(), settings)
@@ -140,12 +177,12 @@ impl Octree {
 
         // If we can't refine any further, then return right away
         if settings.min_depth == settings.max_depth {
-            return octree;
+            return Ok(octree);
         }
 
         loop {
             let mut fixup = DcFixup::new(octree.cells.len(), &settings);
-            fixup.cell(&octree, CellIndex::default());
+            fixup.cell(&octree, root);
             let num_fix = fixup.needs_fixing.iter().filter(|i| **i).count();
             if num_fix == 0 {
                 break;
@@ -175,6 +212,7 @@ impl Octree {
                 o: Octree {
                     cells,
                     verts: octree.verts,
+                    bounds: octree.bounds,
                 },
                 leafs,
                 hermite: vec![LeafHermiteData::default()],
@@ -182,25 +220,30 @@ impl Octree {
                 eval_float_slice: S::new_float_slice_eval(),
                 eval_grad_slice: S::new_grad_slice_eval(),
                 eval_interval: S::new_interval_eval(),
+                eval_affine: S::new_affine_eval(),
                 tape_storage: vec![],
                 shape_storage: vec![],
                 workspace: Default::default(),
             };
-            b.refine(&eval, CellIndex::default(), &fixup.needs_fixing);
+            b.refine(&eval, root, &fixup.needs_fixing, settings.vars);
             octree = b.into();
         }
-        octree
+        Ok(octree)
     }
 
     /// Recursively walks the dual of the octree, building a mesh
+    ///
+    /// Only [`Settings::threads`] is used here, because the octree stores the
+    /// region from the settings which were used to build it.
     pub fn walk_dual(&self, settings: Settings) -> Mesh {
+        let threads = settings.threads;
         let mut mesh = MeshBuilder::default();
 
-        if settings.threads == 0 {
-            mesh.cell(self, CellIndex::default());
+        if threads == 0 {
+            mesh.cell(self, CellIndex::from(self.bounds));
             mesh.take()
         } else {
-            DcWorker::scheduler(self, settings.threads)
+            DcWorker::scheduler(self, threads)
         }
     }
 
@@ -384,6 +427,7 @@ pub(crate) struct OctreeBuilder<S: Shape> {
 
     eval_float_slice: S::FloatSliceEval,
     eval_interval: S::IntervalEval,
+    eval_affine: S::AffineEval,
     eval_grad_slice: S::GradSliceEval,
 
     pub tape_storage: Vec<S::TapeStorage>,
@@ -393,7 +437,7 @@ pub(crate) struct OctreeBuilder<S: Shape> {
 
 impl<S: Shape> Default for OctreeBuilder<S> {
     fn default() -> Self {
-        Self::new()
+        Self::new(Bounds::default())
     }
 }
 
@@ -419,17 +463,19 @@ impl<S: Shape> From<OctreeBuilder<S>> for Octree {
         Self {
             cells,
             verts: o.o.verts,
+            bounds: o.o.bounds,
         }
     }
 }
 
 impl<S: Shape> OctreeBuilder<S> {
     /// Builds a new octree, which allocates data for 8 root cells
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(bounds: Bounds) -> Self {
         Self {
             o: Octree {
                 cells: vec![Cell::Invalid.into(); 8],
                 verts: vec![],
+                bounds,
             },
             leafs: vec![],
             hermite: vec![LeafHermiteData::default()],
@@ -437,6 +483,7 @@ impl<S: Shape> OctreeBuilder<S> {
             eval_float_slice: S::new_float_slice_eval(),
             eval_grad_slice: S::new_grad_slice_eval(),
             eval_interval: S::new_interval_eval(),
+            eval_affine: S::new_affine_eval(),
             tape_storage: vec![],
             shape_storage: vec![],
             workspace: Default::default(),
@@ -446,11 +493,12 @@ impl<S: Shape> OctreeBuilder<S> {
     /// Builds a new empty octree
     ///
     /// This still allocates data to reserve the lowest slot in `hermite`
-    pub(crate) fn empty() -> Self {
+    pub(crate) fn empty(bounds: Bounds) -> Self {
         Self {
             o: Octree {
                 cells: vec![],
                 verts: vec![],
+                bounds,
             },
             leafs: vec![],
             hermite: vec![LeafHermiteData::default()],
@@ -459,6 +507,7 @@ impl<S: Shape> OctreeBuilder<S> {
             eval_float_slice: S::new_float_slice_eval(),
             eval_grad_slice: S::new_grad_slice_eval(),
             eval_interval: S::new_interval_eval(),
+            eval_affine: S::new_affine_eval(),
 
             tape_storage: vec![],
             shape_storage: vec![],
@@ -506,16 +555,32 @@ impl<S: Shape> OctreeBuilder<S> {
         cell: CellIndex,
         settings: Settings,
     ) -> CellResult<S> {
-        let (i, r) = self
-            .eval_interval
-            .eval(
-                eval.interval_tape(&mut self.tape_storage),
-                cell.bounds.x,
-                cell.bounds.y,
-                cell.bounds.z,
-                &[],
-            )
-            .unwrap();
+        let (x, y, z) = (cell.bounds.x, cell.bounds.y, cell.bounds.z);
+        let (i, r) = match settings.interval_mode {
+            IntervalMode::Interval => self
+                .eval_interval
+                .eval(
+                    eval.interval_tape(&mut self.tape_storage),
+                    x,
+                    y,
+                    z,
+                    settings.vars,
+                )
+                .unwrap(),
+            IntervalMode::Affine => {
+                let (a, r) = self
+                    .eval_affine
+                    .eval(
+                        eval.affine_tape(&mut self.tape_storage),
+                        x,
+                        y,
+                        z,
+                        settings.vars,
+                    )
+                    .unwrap();
+                (a.interval(), r)
+            }
+        };
         if i.upper() < 0.0 {
             CellResult::Done(Cell::Full)
         } else if i.lower() > 0.0 {
@@ -533,7 +598,8 @@ impl<S: Shape> OctreeBuilder<S> {
             };
             if cell.depth == settings.min_depth as usize {
                 let eval = sub_tape.unwrap_or_else(|| eval.clone());
-                let out = CellResult::Done(self.leaf(&eval, cell));
+                let out =
+                    CellResult::Done(self.leaf(&eval, cell, settings.vars));
                 if let Ok(t) = Arc::try_unwrap(eval) {
                     self.reclaim(t);
                 }
@@ -624,7 +690,12 @@ impl<S: Shape> OctreeBuilder<S> {
     /// Writes the leaf vertex to `self.o.verts`, hermite data to
     /// `self.hermite`, and the leaf data to `self.leafs`.  Does **not** write
     /// anything to `self.o.cells`; the cell is returned instead.
-    fn leaf(&mut self, eval: &EvalGroup<S>, cell: CellIndex) -> Cell {
+    fn leaf(
+        &mut self,
+        eval: &EvalGroup<S>,
+        cell: CellIndex,
+        vars: &[f32],
+    ) -> Cell {
         let mut xs = [0.0; 8];
         let mut ys = [0.0; 8];
         let mut zs = [0.0; 8];
@@ -642,14 +713,14 @@ impl<S: Shape> OctreeBuilder<S> {
                 &xs,
                 &ys,
                 &zs,
-                &[],
+                vars,
             )
             .unwrap();
-        debug_assert_eq!(out.len(), 8);
+        debug_assert_eq!(out[0].len(), 8);
 
         // Build a mask of active corners, which determines cell
         // topology / vertex count / active edges / etc.
-        let mask = out
+        let mask = out[0]
             .iter()
             .enumerate()
             .filter(|(_i, &v)| v < 0.0)
@@ -749,7 +820,7 @@ impl<S: Shape> OctreeBuilder<S> {
                     xs,
                     ys,
                     zs,
-                    &[],
+                    vars,
                 )
                 .unwrap();
 
@@ -757,7 +828,7 @@ impl<S: Shape> OctreeBuilder<S> {
             for ((start, end), search) in start
                 .iter_mut()
                 .zip(end.iter_mut())
-                .zip(out.chunks(EDGE_SEARCH_SIZE))
+                .zip(out[0].chunks(EDGE_SEARCH_SIZE))
             {
                 // The search must be inside-to-outside
                 debug_assert!(search[0] < 0.0);
@@ -814,7 +885,7 @@ impl<S: Shape> OctreeBuilder<S> {
                 xs,
                 ys,
                 zs,
-                &[],
+                vars,
             )
             .unwrap();
 
@@ -826,7 +897,7 @@ impl<S: Shape> OctreeBuilder<S> {
             let mut qef = QuadraticErrorSolver::new();
             for e in vs.iter() {
                 let pos = nalgebra::Vector3::new(xs[i], ys[i], zs[i]);
-                let grad: nalgebra::Vector4<f32> = grads[i].into();
+                let grad: nalgebra::Vector4<f32> = grads[0][i].into();
 
                 qef.add_intersection(pos, grad);
 
@@ -1068,6 +1139,7 @@ impl<S: Shape> OctreeBuilder<S> {
         eval: &Arc<EvalGroup<S>>,
         cell: CellIndex,
         needs_fixing: &[bool],
+        vars: &[f32],
     ) {
         match self.o[cell].into() {
             Cell::Empty | Cell::Full | Cell::Leaf(..)
@@ -1080,7 +1152,7 @@ impl<S: Shape> OctreeBuilder<S> {
                 // Evaluate all 8 leafs
                 for i in Corner::iter() {
                     let subcell = cell.child(index, i);
-                    let leaf = self.leaf(eval, subcell);
+                    let leaf = self.leaf(eval, subcell, vars);
                     match leaf {
                         Cell::Leaf(Leaf { index, .. }) => {
                             // Discard hermite data immediately, because we
@@ -1103,7 +1175,7 @@ impl<S: Shape> OctreeBuilder<S> {
             Cell::Branch { index, .. } => {
                 assert!(!needs_fixing[cell.index]);
                 for i in Corner::iter() {
-                    self.refine(eval, cell.child(index, i), needs_fixing)
+                    self.refine(eval, cell.child(index, i), needs_fixing, vars)
                 }
             }
             Cell::Invalid => panic!(),
@@ -1117,6 +1189,9 @@ impl<S: Shape> OctreeBuilder<S> {
         if let Some(i_tape) = e.interval.take() {
             self.tape_storage.push(i_tape.recycle());
         }
+        if let Some(a_tape) = e.affine.take() {
+            self.tape_storage.push(a_tape.recycle());
+        }
         if let Some(f_tape) = e.float_slice.take() {
             self.tape_storage.push(f_tape.recycle());
         }
@@ -1344,29 +1419,38 @@ impl LeafHermiteData {
 mod test {
     use super::*;
     use crate::{
-        context::bound::{self, BoundContext, BoundNode},
-        eval::{EzShape, MathShape},
-        mesh::types::{Edge, X, Y, Z},
+        context::{BoundContext, BoundNode},
+        eval::{EzShape, MathShape, ShapeVars, Vars},
+        mesh::{
+            types::{Edge, X, Y, Z},
+            Bounds,
+        },
         vm::VmShape,
     };
     use std::collections::BTreeMap;
 
+    const BOUNDS: Bounds = Bounds::new(
+        nalgebra::Vector3::new(-1.0, -1.0, -1.0),
+        nalgebra::Vector3::new(1.0, 1.0, 1.0),
+    );
     const DEPTH0_SINGLE_THREAD: Settings = Settings {
         min_depth: 0,
         max_depth: 0,
         threads: 0,
+        bounds: BOUNDS,
+        interval_mode: IntervalMode::Interval,
+        vars: &[],
     };
     const DEPTH1_SINGLE_THREAD: Settings = Settings {
         min_depth: 1,
         max_depth: 1,
         threads: 0,
+        bounds: BOUNDS,
+        interval_mode: IntervalMode::Interval,
+        vars: &[],
     };
 
-    fn sphere(
-        ctx: &BoundContext,
-        center: [f32; 3],
-        radius: f32,
-    ) -> bound::BoundNode {
+    fn sphere(ctx: &BoundContext, center: [f32; 3], radius: f32) -> BoundNode {
         let (x, y, z) = ctx.axes();
         ((x - center[0]).square()
             + (y - center[1]).square()
@@ -1377,9 +1461,9 @@ mod test {
 
     fn cube(
         ctx: &BoundContext,
-        bx: [f32; 2],
-        by: [f32; 2],
-        bz: [f32; 2],
+        bx: [f64; 2],
+        by: [f64; 2],
+        bz: [f64; 2],
     ) -> BoundNode {
         let (x, y, z) = ctx.axes();
         let x_bounds = (bx[0] - x.clone()).max(x - bx[1]);
@@ -1396,8 +1480,8 @@ mod test {
         let cube = cube(&ctx, [-f, f], [-f, 0.3], [-f, 0.6]);
         // This should be a cube with a single edge running through the root
         // node of the octree, with an edge vertex at [0, 0.3, 0.6]
-        let shape: VmShape = cube.convert();
-        let octree = Octree::build(&shape, DEPTH0_SINGLE_THREAD);
+        let shape: VmShape = cube.convert().unwrap();
+        let octree = Octree::build(&shape, DEPTH0_SINGLE_THREAD).unwrap();
         assert_eq!(octree.verts.len(), 5);
         let v = octree.verts[0].pos;
         let expected = nalgebra::Vector3::new(0.0, 0.3, 0.6);
@@ -1439,18 +1523,18 @@ mod test {
             + offset.z.clone().square())
         .sqrt();
 
-        b - radius * (1.0 - a / length)
+        b - (1.0 - a / length) * radius
     }
 
     #[test]
     fn test_mesh_basic() {
         let ctx = BoundContext::new();
         let shape = sphere(&ctx, [0.0; 3], 0.2);
-        let shape: VmShape = shape.convert();
+        let shape: VmShape = shape.convert().unwrap();
 
         // If we only build a depth-0 octree, then it's a leaf without any
         // vertices (since all the corners are empty)
-        let octree = Octree::build(&shape, DEPTH0_SINGLE_THREAD);
+        let octree = Octree::build(&shape, DEPTH0_SINGLE_THREAD).unwrap();
         assert_eq!(octree.cells.len(), 8); // we always build at least 8 cells
         assert_eq!(Cell::Empty, octree.cells[0].into(),);
         assert_eq!(octree.verts.len(), 0);
@@ -1460,7 +1544,7 @@ mod test {
         assert!(empty_mesh.triangles.is_empty());
 
         // Now, at depth-1, each cell should be a Leaf with one vertex
-        let octree = Octree::build(&shape, DEPTH1_SINGLE_THREAD);
+        let octree = Octree::build(&shape, DEPTH1_SINGLE_THREAD).unwrap();
         assert_eq!(octree.cells.len(), 16); // we always build at least 8 cells
         assert_eq!(
             Cell::Branch {
@@ -1492,8 +1576,8 @@ mod test {
         let ctx = BoundContext::new();
         let shape = sphere(&ctx, [0.0; 3], 0.2);
 
-        let shape: VmShape = shape.convert();
-        let octree = Octree::build(&shape, DEPTH1_SINGLE_THREAD);
+        let shape: VmShape = shape.convert().unwrap();
+        let octree = Octree::build(&shape, DEPTH1_SINGLE_THREAD).unwrap();
         let sphere_mesh = octree.walk_dual(DEPTH1_SINGLE_THREAD);
 
         let mut edge_count = 0;
@@ -1529,15 +1613,18 @@ mod test {
     fn test_sphere_manifold() {
         let ctx = BoundContext::new();
         let shape = sphere(&ctx, [0.0; 3], 0.85);
-        let shape: VmShape = shape.convert();
+        let shape: VmShape = shape.convert().unwrap();
 
         for threads in [0, 8] {
             let settings = Settings {
                 min_depth: 5,
                 max_depth: 5,
                 threads,
+                bounds: BOUNDS,
+                interval_mode: IntervalMode::Interval,
+                vars: &[],
             };
-            let octree = Octree::build(&shape, settings);
+            let octree = Octree::build(&shape, settings).unwrap();
             let sphere_mesh = octree.walk_dual(settings);
             sphere_mesh
                 .write_stl(
@@ -1555,13 +1642,186 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_sphere_affine() {
+        let ctx = BoundContext::new();
+        let shape = sphere(&ctx, [0.1, 0.2, -0.1], 0.57);
+        let shape: VmShape = shape.convert().unwrap();
+
+        for threads in [0, 8] {
+            let meshes =
+                [IntervalMode::Interval, IntervalMode::Affine].map(|mode| {
+                    let settings = Settings {
+                        min_depth: 5,
+                        max_depth: 5,
+                        threads,
+                        bounds: BOUNDS,
+                        interval_mode: mode,
+                        vars: &[],
+                    };
+                    Octree::build(&shape, settings).unwrap().walk_dual(settings)
+                });
+            let [interval_mesh, affine_mesh] = meshes;
+            if let Err(e) = check_for_vertex_dupes(&affine_mesh) {
+                panic!("{e} (with {threads} threads)");
+            }
+            if let Err(e) = check_for_edge_matching(&affine_mesh) {
+                panic!("{e} (with {threads} threads)");
+            }
+            if threads == 0 {
+                assert_eq!(interval_mesh.vertices, affine_mesh.vertices);
+                assert_eq!(interval_mesh.triangles, affine_mesh.triangles);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sphere_vars() {
+        let mut ctx = crate::Context::new();
+        let (x, y, z) = (ctx.x(), ctx.y(), ctx.z());
+        let r = ctx.var("r").unwrap();
+        let x2 = ctx.square(x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let z2 = ctx.square(z).unwrap();
+        let sum = ctx.add(x2, y2).unwrap();
+        let sum = ctx.add(sum, z2).unwrap();
+        let root = ctx.sqrt(sum).unwrap();
+        let root = ctx.sub(root, r).unwrap();
+        let shape = VmShape::new(&ctx, root).unwrap();
+        let mut vars = Vars::new(shape.vars());
+
+        for radius in [0.57, 0.83] {
+            let vars = vars.bind([("r", radius)].into_iter());
+            let expected: VmShape =
+                sphere(&BoundContext::new(), [0.0; 3], radius)
+                    .convert()
+                    .unwrap();
+            for interval_mode in [IntervalMode::Interval, IntervalMode::Affine]
+            {
+                for (threads, max_depth) in [(0, 5), (8, 5), (0, 6)] {
+                    let settings = Settings {
+                        min_depth: 5,
+                        max_depth,
+                        threads,
+                        bounds: BOUNDS,
+                        interval_mode,
+                        vars,
+                    };
+                    let mesh = Octree::build(&shape, settings)
+                        .unwrap()
+                        .walk_dual(settings);
+                    for v in &mesh.vertices {
+                        let r = v.norm();
+                        assert!(
+                            (r - radius).abs() < 0.05,
+                            "bad vertex {v:?} (radius {r})"
+                        );
+                    }
+                    if threads == 0 && max_depth == 5 {
+                        let settings = Settings {
+                            vars: &[],
+                            ..settings
+                        };
+                        let cmp = Octree::build(&expected, settings)
+                            .unwrap()
+                            .walk_dual(settings);
+                        assert_eq!(mesh.vertices, cmp.vertices);
+                        assert_eq!(mesh.triangles, cmp.triangles);
+                    }
+                }
+            }
+        }
+
+        // A mismatched variable slice is an error, rather than a panic
+        for (v, n) in [(&[][..], 0), (&[0.5, 0.5][..], 2)] {
+            for threads in [0, 8] {
+                let settings = Settings {
+                    min_depth: 5,
+                    max_depth: 5,
+                    threads,
+                    bounds: BOUNDS,
+                    interval_mode: IntervalMode::Interval,
+                    vars: v,
+                };
+                assert!(matches!(
+                    Octree::build(&shape, settings),
+                    Err(Error::BadVarSlice(a, 1)) if a == n
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sphere_bounds() {
+        let ctx = BoundContext::new();
+        let center = [10.0, -3.0, 5.0];
+        let shape = sphere(&ctx, center, 2.5);
+        let shape: VmShape = shape.convert().unwrap();
+
+        let center = nalgebra::Vector3::from(center);
+        for bounds in [
+            Bounds::cube(center, 3.0),
+            Bounds::new(
+                center - nalgebra::Vector3::new(3.0, 4.0, 5.0),
+                center + nalgebra::Vector3::new(3.0, 6.0, 2.8),
+            ),
+        ] {
+            for threads in [0, 8] {
+                let settings = Settings {
+                    min_depth: 5,
+                    max_depth: 5,
+                    threads,
+                    bounds,
+                    interval_mode: IntervalMode::Interval,
+                    vars: &[],
+                };
+                let octree = Octree::build(&shape, settings).unwrap();
+                let mesh = octree.walk_dual(settings);
+                assert!(!mesh.triangles.is_empty());
+                for v in &mesh.vertices {
+                    let r = (v - center).norm();
+                    assert!(
+                        (r - 2.5).abs() < 0.05,
+                        "bad vertex {v:?} (radius {r}, bounds {bounds:?}, threads {threads})"
+                    );
+                }
+                if let Err(e) = check_for_vertex_dupes(&mesh) {
+                    panic!("{e} (with {threads} threads)");
+                }
+                if let Err(e) = check_for_edge_matching(&mesh) {
+                    panic!("{e} (with {threads} threads)");
+                }
+            }
+        }
+
+        // Empty, inverted, and non-finite regions are rejected
+        let v = nalgebra::Vector3::new;
+        for bounds in [
+            Bounds::cube(center, 0.0),
+            Bounds::cube(center, -1.0),
+            Bounds::new(v(0.0, 0.0, 0.0), v(1.0, -1.0, 1.0)),
+            Bounds::new(v(0.0, f32::NAN, 0.0), v(1.0, 1.0, 1.0)),
+            Bounds::new(v(0.0, 0.0, 0.0), v(1.0, 1.0, f32::INFINITY)),
+        ] {
+            assert!(!bounds.is_valid());
+            let settings = Settings {
+                bounds,
+                ..DEPTH1_SINGLE_THREAD
+            };
+            assert!(matches!(
+                Octree::build(&shape, settings),
+                Err(Error::BadBounds)
+            ));
+        }
+    }
+
     #[test]
     fn test_cube_verts() {
         let ctx = BoundContext::new();
         let shape = cube(&ctx, [-0.1, 0.6], [-0.2, 0.75], [-0.3, 0.4]);
 
-        let shape: VmShape = shape.convert();
-        let octree = Octree::build(&shape, DEPTH1_SINGLE_THREAD);
+        let shape: VmShape = shape.convert().unwrap();
+        let octree = Octree::build(&shape, DEPTH1_SINGLE_THREAD).unwrap();
         let mesh = octree.walk_dual(DEPTH1_SINGLE_THREAD);
         const EPSILON: f32 = 2.0 / u16::MAX as f32;
         assert!(!mesh.vertices.is_empty());
@@ -1610,8 +1870,9 @@ mod test {
                 for offset in [0.0, -0.2, 0.2] {
                     let (x, y, z) = ctx.axes();
                     let f = x * dx + y * dy + z + offset;
-                    let shape: VmShape = f.convert();
-                    let octree = Octree::build(&shape, DEPTH0_SINGLE_THREAD);
+                    let shape: VmShape = f.convert().unwrap();
+                    let octree =
+                        Octree::build(&shape, DEPTH0_SINGLE_THREAD).unwrap();
 
                     assert_eq!(octree.cells.len(), 8);
                     let pos = octree.verts[0].pos;
@@ -1648,7 +1909,7 @@ mod test {
             let ctx = BoundContext::new();
             let corner = nalgebra::Vector3::new(-1.0, -1.0, -1.0);
             let shape = cone(&ctx, corner, tip, 0.1);
-            let shape: VmShape = shape.convert();
+            let shape: VmShape = shape.convert().unwrap();
 
             let mut eval = VmShape::new_point_eval();
             let tape = shape.ez_point_tape();
@@ -1658,7 +1919,7 @@ mod test {
                 eval.eval(&tape, corner.x, corner.y, corner.z, &[]).unwrap();
             assert!(v < 0.0, "bad corner value: {v}");
 
-            let octree = Octree::build(&shape, DEPTH0_SINGLE_THREAD);
+            let octree = Octree::build(&shape, DEPTH0_SINGLE_THREAD).unwrap();
             assert_eq!(octree.cells.len(), 8);
             assert_eq!(octree.verts.len(), 4);
 
@@ -1694,13 +1955,16 @@ mod test {
 
                 // Now, we have our shape, which is 0-8 spheres placed at the
                 // corners of the cell spanning [0, 0.25]
-                let shape: VmShape = shape.convert();
+                let shape: VmShape = shape.convert().unwrap();
                 let settings = Settings {
                     min_depth: 2,
                     max_depth: 2,
                     threads,
+                    bounds: BOUNDS,
+                    interval_mode: IntervalMode::Interval,
+                    vars: &[],
                 };
-                let octree = Octree::build(&shape, settings);
+                let octree = Octree::build(&shape, settings).unwrap();
 
                 let mesh = octree.walk_dual(settings);
                 if i != 0 && i != 255 {
@@ -1726,9 +1990,9 @@ mod test {
             shape: BoundNode,
             settings: Settings,
         ) -> OctreeBuilder<VmShape> {
-            let shape: VmShape = shape.convert();
+            let shape: VmShape = shape.convert().unwrap();
             let eval = Arc::new(EvalGroup::new(shape));
-            let mut out = OctreeBuilder::new();
+            let mut out = OctreeBuilder::new(settings.bounds);
             out.recurse(&eval, CellIndex::default(), settings);
             out
         }
@@ -1757,14 +2021,17 @@ mod test {
         // Make a very smol sphere that won't be sampled
         let ctx = BoundContext::new();
         let shape = sphere(&ctx, [0.1; 3], 0.05);
-        let tape: VmShape = shape.convert();
+        let tape: VmShape = shape.convert().unwrap();
         for threads in [0, 4] {
             let settings = Settings {
                 min_depth: 1,
                 max_depth: 1,
                 threads,
+                bounds: BOUNDS,
+                interval_mode: IntervalMode::Interval,
+                vars: &[],
             };
-            let octree = Octree::build(&tape, settings);
+            let octree = Octree::build(&tape, settings).unwrap();
             assert_eq!(
                 octree.cells[0],
                 Cell::Empty.into(),
@@ -1784,8 +2051,11 @@ mod test {
                 min_depth: 5,
                 max_depth: 5,
                 threads,
+                bounds: BOUNDS,
+                interval_mode: IntervalMode::Interval,
+                vars: &[],
             };
-            let octree = Octree::build(&tape, settings);
+            let octree = Octree::build(&tape, settings).unwrap();
             let mesh = octree.walk_dual(settings);
             // Note: the model has duplicate vertices!
             if let Err(e) = check_for_edge_matching(&mesh) {
@@ -1,5 +1,7 @@
 //! Mesh output implementation
 use super::Mesh;
+use crate::Error;
+use std::collections::HashMap;
 
 impl Mesh {
     /// Writes a binary STL to the given output
@@ -32,4 +34,339 @@ impl Mesh {
         }
         Ok(())
     }
+
+    /// Reads a binary STL from the given input
+    ///
+    /// STL files store each triangle separately, so vertices with identical
+    /// positions are merged to rebuild an indexed mesh.  Facet normals are
+    /// ignored.
+    pub fn read_stl<R: std::io::Read>(input: &mut R) -> Result<Self, Error> {
+        let mut header = [0u8; 80];
+        read_exact(input, &mut header)?;
+        let mut count = [0u8; 4];
+        read_exact(input, &mut count)?;
+        let count = u32::from_le_bytes(count);
+
+        let mut out = Mesh::new();
+        let mut seen = HashMap::new();
+        for _ in 0..count {
+            let mut data = [0u8; 50];
+            read_exact(input, &mut data)?;
+            let f = |i: usize| {
+                let i = i * 4;
+                f32::from_le_bytes(data[i..i + 4].try_into().unwrap())
+            };
+            let mut tri = nalgebra::Vector3::zeros();
+            for (j, t) in tri.iter_mut().enumerate() {
+                // Skip the facet normal, which is the first three floats
+                let v = nalgebra::Vector3::new(
+                    f(3 + j * 3),
+                    f(4 + j * 3),
+                    f(5 + j * 3),
+                );
+                *t = *seen.entry(v.map(f32::to_bits)).or_insert_with(|| {
+                    out.vertices.push(v);
+                    out.vertices.len() - 1
+                });
+            }
+            out.triangles.push(tri);
+        }
+        if input.read(&mut [0u8])? != 0 {
+            return Err(Error::TrailingData);
+        }
+        Ok(out)
+    }
+
+    /// Writes a Wavefront OBJ file to the given output
+    ///
+    /// Vertex normals are included if they have been populated (e.g. by
+    /// [`Mesh::compute_normals`]).
+    pub fn write_obj<F: std::io::Write>(
+        &self,
+        out: &mut F,
+    ) -> Result<(), Error> {
+        writeln!(out, "# Exported by Fidget")?;
+        for v in &self.vertices {
+            writeln!(out, "v {} {} {}", v.x, v.y, v.z)?;
+        }
+        for n in &self.normals {
+            writeln!(out, "vn {} {} {}", n.x, n.y, n.z)?;
+        }
+        // OBJ indices are 1-indexed
+        for t in &self.triangles {
+            let t = t.map(|i| i + 1);
+            if self.normals.is_empty() {
+                writeln!(out, "f {} {} {}", t.x, t.y, t.z)?;
+            } else {
+                writeln!(out, "f {0}//{0} {1}//{1} {2}//{2}", t.x, t.y, t.z)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a binary (little-endian) PLY file to the given output
+    ///
+    /// Vertex normals are included if they have been populated (e.g. by
+    /// [`Mesh::compute_normals`]), followed by any
+    /// [`attributes`](Self::attributes) as `float` properties.
+    pub fn write_ply<F: std::io::Write>(
+        &self,
+        out: &mut F,
+    ) -> Result<(), Error> {
+        self.write_ply_header(out, "binary_little_endian")?;
+        for (i, v) in self.vertices.iter().enumerate() {
+            for p in v {
+                out.write_all(&p.to_le_bytes())?;
+            }
+            if let Some(n) = self.normals.get(i) {
+                for p in n {
+                    out.write_all(&p.to_le_bytes())?;
+                }
+            }
+            for a in &self.attributes {
+                out.write_all(&a.values[i].to_le_bytes())?;
+            }
+        }
+        for t in &self.triangles {
+            out.write_all(&[3u8])?;
+            for i in t {
+                out.write_all(&(*i as u32).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes an ASCII PLY file to the given output
+    ///
+    /// Vertex normals and attributes are included as in [`Mesh::write_ply`].
+    pub fn write_ply_ascii<F: std::io::Write>(
+        &self,
+        out: &mut F,
+    ) -> Result<(), Error> {
+        self.write_ply_header(out, "ascii")?;
+        for (i, v) in self.vertices.iter().enumerate() {
+            write!(out, "{} {} {}", v.x, v.y, v.z)?;
+            if let Some(n) = self.normals.get(i) {
+                write!(out, " {} {} {}", n.x, n.y, n.z)?;
+            }
+            for a in &self.attributes {
+                write!(out, " {}", a.values[i])?;
+            }
+            writeln!(out)?;
+        }
+        for t in &self.triangles {
+            writeln!(out, "3 {} {} {}", t.x, t.y, t.z)?;
+        }
+        Ok(())
+    }
+
+    fn write_ply_header<F: std::io::Write>(
+        &self,
+        out: &mut F,
+        format: &str,
+    ) -> Result<(), Error> {
+        writeln!(out, "ply")?;
+        writeln!(out, "format {format} 1.0")?;
+        writeln!(out, "comment Exported by Fidget")?;
+        writeln!(out, "element vertex {}", self.vertices.len())?;
+        for axis in ["x", "y", "z"] {
+            writeln!(out, "property float {axis}")?;
+        }
+        if !self.normals.is_empty() {
+            for axis in ["nx", "ny", "nz"] {
+                writeln!(out, "property float {axis}")?;
+            }
+        }
+        for a in &self.attributes {
+            writeln!(out, "property float {}", a.name)?;
+        }
+        writeln!(out, "element face {}", self.triangles.len())?;
+        writeln!(out, "property list uchar uint vertex_indices")?;
+        writeln!(out, "end_header")?;
+        Ok(())
+    }
+}
+
+/// Wrapper around [`Read::read_exact`](std::io::Read::read_exact) which
+/// converts an early EOF into [`Error::UnexpectedEof`]
+fn read_exact<R: std::io::Read>(
+    r: &mut R,
+    buf: &mut [u8],
+) -> Result<(), Error> {
+    r.read_exact(buf).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => Error::UnexpectedEof,
+        _ => e.into(),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::{
+        eval::MathShape,
+        mesh::{Octree, Settings},
+        vm::VmShape,
+        Context,
+    };
+
+    pub fn sphere_mesh() -> (VmShape, Mesh) {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let x2 = ctx.square(x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let z2 = ctx.square(z).unwrap();
+        let r = ctx.add(x2, y2).unwrap();
+        let r = ctx.add(r, z2).unwrap();
+        let r = ctx.sqrt(r).unwrap();
+        let root = ctx.sub(r, 0.6).unwrap();
+        let shape = VmShape::new(&ctx, root).unwrap();
+        let settings = Settings {
+            threads: 0,
+            min_depth: 4,
+            max_depth: 4,
+            bounds: Default::default(),
+            interval_mode: Default::default(),
+            vars: &[],
+        };
+        let octree = Octree::build(&shape, settings).unwrap();
+        let mesh = octree.walk_dual(settings);
+        (shape, mesh)
+    }
+
+    #[test]
+    fn test_stl_round_trip() {
+        let (_shape, mesh) = sphere_mesh();
+        let mut out = vec![];
+        mesh.write_stl(&mut out).unwrap();
+        let new_mesh = Mesh::read_stl(&mut out.as_slice()).unwrap();
+        assert_eq!(new_mesh.vertices.len(), mesh.vertices.len());
+        assert_eq!(new_mesh.triangles.len(), mesh.triangles.len());
+        for (a, b) in mesh.triangles.iter().zip(&new_mesh.triangles) {
+            for (i, j) in a.iter().zip(b) {
+                assert_eq!(mesh.vertices[*i], new_mesh.vertices[*j]);
+            }
+        }
+
+        for i in 0..84 {
+            assert!(matches!(
+                Mesh::read_stl(&mut &out[..i]),
+                Err(Error::UnexpectedEof)
+            ));
+        }
+        assert!(matches!(
+            Mesh::read_stl(&mut &out[..out.len() - 1]),
+            Err(Error::UnexpectedEof)
+        ));
+        out.push(0);
+        assert!(matches!(
+            Mesh::read_stl(&mut out.as_slice()),
+            Err(Error::TrailingData)
+        ));
+    }
+
+    /// Returns every line in `text` which starts with the given prefix
+    fn lines_with<'a>(text: &'a str, prefix: &str) -> Vec<&'a str> {
+        text.lines().filter(|l| l.starts_with(prefix)).collect()
+    }
+
+    #[test]
+    fn test_obj() {
+        let (shape, mut mesh) = sphere_mesh();
+        let mut out = vec![];
+        mesh.write_obj(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(lines_with(&text, "v ").len(), mesh.vertices.len());
+        assert_eq!(lines_with(&text, "vn ").len(), 0);
+        let faces = lines_with(&text, "f ");
+        assert_eq!(faces.len(), mesh.triangles.len());
+        let t = mesh.triangles[0].map(|i| i + 1);
+        assert_eq!(faces[0], format!("f {} {} {}", t.x, t.y, t.z));
+
+        mesh.compute_normals(&shape, &[]).unwrap();
+        let mut out = vec![];
+        mesh.write_obj(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(lines_with(&text, "vn ").len(), mesh.vertices.len());
+        assert_eq!(
+            lines_with(&text, "f ")[0],
+            format!("f {0}//{0} {1}//{1} {2}//{2}", t.x, t.y, t.z)
+        );
+    }
+
+    #[test]
+    fn test_ply() {
+        let (shape, mut mesh) = sphere_mesh();
+        mesh.compute_normals(&shape, &[]).unwrap();
+        let nv = mesh.vertices.len();
+        let nt = mesh.triangles.len();
+
+        let mut out = vec![];
+        mesh.write_ply_ascii(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let (header, body) = text.split_once("end_header\n").unwrap();
+        assert!(header.starts_with("ply\nformat ascii 1.0\n"));
+        assert!(header.contains(&format!("element vertex {nv}\n")));
+        assert!(header.contains("property float nz\n"));
+        assert!(header.contains(&format!("element face {nt}\n")));
+        let lines: Vec<_> = body.lines().collect();
+        assert_eq!(lines.len(), nv + nt);
+        assert_eq!(lines[0].split(' ').count(), 6);
+        let t = mesh.triangles[0];
+        assert_eq!(lines[nv], format!("3 {} {} {}", t.x, t.y, t.z));
+
+        let mut out = vec![];
+        mesh.write_ply(&mut out).unwrap();
+        let end = b"end_header\n";
+        let i = out.windows(end.len()).position(|w| w == end).unwrap();
+        let header = std::str::from_utf8(&out[..i]).unwrap();
+        assert!(header.starts_with("ply\nformat binary_little_endian 1.0\n"));
+        let body = &out[i + end.len()..];
+        assert_eq!(body.len(), nv * 6 * 4 + nt * (1 + 3 * 4));
+        let f =
+            |j: usize| f32::from_le_bytes(body[j..j + 4].try_into().unwrap());
+        assert_eq!(f(0), mesh.vertices[0].x);
+        assert_eq!(f(12), mesh.normals[0].x);
+
+        mesh.compute_attribute("distance", &shape, &[]).unwrap();
+        let mut out = vec![];
+        mesh.write_ply_ascii(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let (header, body) = text.split_once("end_header\n").unwrap();
+        assert!(header.contains("property float nz\nproperty float distance\n"));
+        let d = body.lines().next().unwrap().split(' ').nth(6).unwrap();
+        assert_eq!(d.parse::<f32>().unwrap(), mesh.attributes[0].values[0]);
+
+        let mut out = vec![];
+        mesh.write_ply(&mut out).unwrap();
+        let i = out.windows(end.len()).position(|w| w == end).unwrap();
+        let body = &out[i + end.len()..];
+        assert_eq!(body.len(), nv * 7 * 4 + nt * (1 + 3 * 4));
+    }
+
+    #[test]
+    fn test_stl_normals() {
+        let (shape, mut mesh) = sphere_mesh();
+        let mut before = vec![];
+        mesh.write_stl(&mut before).unwrap();
+
+        // Facet normals always come from the triangle, not vertex normals
+        mesh.compute_normals(&shape, &[]).unwrap();
+        let mut after = vec![];
+        mesh.write_stl(&mut after).unwrap();
+        assert_eq!(before, after);
+
+        let f =
+            |j: usize| f32::from_le_bytes(after[j..j + 4].try_into().unwrap());
+        for (i, t) in mesh.triangles.iter().enumerate() {
+            let offset = 84 + i * 50;
+            let n =
+                nalgebra::Vector3::new(f(offset), f(offset + 4), f(offset + 8));
+            let a = mesh.vertices[t.x];
+            let b = mesh.vertices[t.y];
+            let c = mesh.vertices[t.z];
+            assert_eq!(n, (b - a).cross(&(c - a)));
+        }
+    }
 }
@@ -1,19 +1,22 @@
 use super::cell::CellVertex;
 
 /// Solver for a quadratic error function to position a vertex within a cell
+///
+/// Terms are accumulated in double precision, because the error is computed
+/// by subtracting large values when the model is far from the origin.
 #[derive(Copy, Clone, Debug, Default)]
 pub struct QuadraticErrorSolver {
     /// A^T A term
-    ata: nalgebra::Matrix3<f32>,
+    ata: nalgebra::Matrix3<f64>,
 
     /// A^T B term
-    atb: nalgebra::Vector3<f32>,
+    atb: nalgebra::Vector3<f64>,
 
     /// B^T B term
-    btb: f32,
+    btb: f64,
 
     /// Mass point of intersections is stored as XYZ / W, so that summing works
-    mass_point: nalgebra::Vector4<f32>,
+    mass_point: nalgebra::Vector4<f64>,
 }
 
 impl std::ops::AddAssign for QuadraticErrorSolver {
@@ -37,7 +40,7 @@ impl QuadraticErrorSolver {
 
     #[cfg(test)]
     pub fn mass_point(&self) -> nalgebra::Vector4<f32> {
-        self.mass_point
+        self.mass_point.map(|v| v as f32)
     }
 
     /// Adds a new intersection to the QEF
@@ -51,6 +54,8 @@ impl QuadraticErrorSolver {
         grad: nalgebra::Vector4<f32>,
     ) {
         // TODO: correct for non-zero distance value in grad.w
+        let pos = pos.map(|v| v as f64);
+        let grad = grad.map(|v| v as f64);
         self.mass_point += nalgebra::Vector4::new(pos.x, pos.y, pos.z, 1.0);
         let norm = grad.xyz().normalize();
         self.ata += norm * norm.transpose();
@@ -80,7 +85,7 @@ impl QuadraticErrorSolver {
         let mut prev = None;
         for i in 0..4 {
             let epsilon = if i == 3 {
-                f32::INFINITY
+                f64::INFINITY
             } else {
                 use ieee754::Ieee754;
                 svd.singular_values[2 - i].prev()
@@ -91,7 +96,7 @@ impl QuadraticErrorSolver {
             let err = ((pos.transpose() * self.ata * pos
                 - 2.0 * pos.transpose() * self.atb)[0]
                 + self.btb)
-                .max(1e-6);
+                .max(1e-6) as f32;
 
             // If this epsilon dramatically increases the error, then we'll
             // assume that the previous (possibly out-of-cell) vertex was
@@ -100,6 +105,7 @@ impl QuadraticErrorSolver {
                 return p;
             }
 
+            let pos = pos.map(|v| v as f32);
             prev = Some((CellVertex { pos }, err));
         }
         prev.unwrap()
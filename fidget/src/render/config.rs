@@ -1,4 +1,8 @@
-use crate::{eval::Shape, render::RenderMode, Error};
+use crate::{
+    eval::{IntervalMode, Shape},
+    render::{ColorMode, RenderMode},
+    Error,
+};
 use nalgebra::{
     allocator::Allocator, geometry::Transform, Const, DefaultAllocator,
     DimNameAdd, DimNameSub, DimNameSum, U1,
@@ -6,7 +10,7 @@ use nalgebra::{
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Container to store render configuration (resolution, etc)
-pub struct RenderConfig<const N: usize>
+pub struct RenderConfig<'a, const N: usize>
 where
     nalgebra::Const<N>: nalgebra::DimNameAdd<nalgebra::U1>,
     DefaultAllocator:
@@ -29,9 +33,22 @@ where
     /// By default, we render a cube spanning ±1 on all axes; `mat` allows for
     /// rotation, scaling, transformation, and even perspective.
     pub mat: Transform<f32, nalgebra::TGeneral, N>,
+
+    /// Evaluator used to find bounds over each tile
+    pub interval_mode: IntervalMode,
+
+    /// Coloring of surface pixels (only used in 3D rendering)
+    pub color_mode: ColorMode,
+
+    /// Values for variables in the shape, passed to every evaluator
+    ///
+    /// These must be in the shape's variable order (e.g. as returned by
+    /// [`Vars::bind`](crate::eval::Vars::bind)), and should be empty if the
+    /// shape doesn't have any variables.
+    pub vars: &'a [f32],
 }
 
-impl<const N: usize> Default for RenderConfig<N>
+impl<const N: usize> Default for RenderConfig<'_, N>
 where
     nalgebra::Const<N>: nalgebra::DimNameAdd<nalgebra::U1>,
     DefaultAllocator:
@@ -46,11 +63,14 @@ where
             },
             threads: 8,
             mat: Transform::identity(),
+            interval_mode: IntervalMode::default(),
+            color_mode: ColorMode::default(),
+            vars: &[],
         }
     }
 }
 
-impl<const N: usize> RenderConfig<N>
+impl<const N: usize> RenderConfig<'_, N>
 where
     nalgebra::Const<N>: nalgebra::DimNameAdd<nalgebra::U1>,
     DefaultAllocator:
@@ -111,6 +131,9 @@ where
             tile_sizes,
             threads: self.threads,
             mat,
+            interval_mode: self.interval_mode,
+            color_mode: self.color_mode,
+            vars: self.vars.to_vec(),
         }
     }
 }
@@ -131,6 +154,10 @@ where
     pub threads: usize,
 
     pub mat: NPlusOneMatrix<N>,
+
+    pub interval_mode: IntervalMode,
+    pub color_mode: ColorMode,
+    pub vars: Vec<f32>,
 }
 
 /// Type for a static `f32` matrix of size `N + 1`
@@ -193,35 +220,53 @@ impl<const N: usize> Queue<N> {
     }
 }
 
-impl RenderConfig<2> {
+impl RenderConfig<'_, 2> {
     /// High-level API for rendering shapes in 2D
     ///
     /// Under the hood, this delegates to
     /// [`fidget::render::render2d`](crate::render::render2d())
+    ///
+    /// Returns an error if [`self.vars`](Self::vars) doesn't match the shape's
+    /// variable count.
     pub fn run<S: Shape, M: RenderMode + Sync>(
         &self,
         shape: S,
         mode: &M,
     ) -> Result<Vec<<M as RenderMode>::Output>, Error> {
+        check_vars(self.vars, &shape)?;
         Ok(crate::render::render2d::<S, M>(shape, self, mode))
     }
 }
 
-impl RenderConfig<3> {
+impl RenderConfig<'_, 3> {
     /// High-level API for rendering shapes in 2D
     ///
     /// Under the hood, this delegates to
     /// [`fidget::render::render3d`](crate::render::render3d())
     ///
-    /// Returns a tuple of heightmap, RGB image.
+    /// Returns a tuple of heightmap, RGB image, or an error if
+    /// [`self.vars`](Self::vars) doesn't match the shape's variable count.
     pub fn run<S: Shape>(
         &self,
         shape: S,
     ) -> Result<(Vec<u32>, Vec<[u8; 3]>), Error> {
+        check_vars(self.vars, &shape)?;
         Ok(crate::render::render3d::<S>(shape, self))
     }
 }
 
+/// Checks that the variable slice matches the shape
+///
+/// This is done before spawning worker threads, because evaluation errors
+/// within a worker would otherwise cause a panic.
+fn check_vars<S: Shape>(vars: &[f32], shape: &S) -> Result<(), Error> {
+    if vars.len() != shape.var_count() {
+        Err(Error::BadVarSlice(vars.len(), shape.var_count()))
+    } else {
+        Ok(())
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
@@ -237,6 +282,9 @@ mod test {
             tile_sizes: vec![64, 32],
             threads: 8,
             mat: Transform::identity(),
+            interval_mode: Default::default(),
+            color_mode: Default::default(),
+            vars: &[],
         };
         let aligned = config.align();
         assert_eq!(aligned.image_size, config.image_size);
@@ -260,6 +308,9 @@ mod test {
             tile_sizes: vec![64, 32],
             threads: 8,
             mat: Transform::identity(),
+            interval_mode: Default::default(),
+            color_mode: Default::default(),
+            vars: &[],
         };
         let aligned = config.align();
         assert_eq!(aligned.orig_image_size, 575);
@@ -4,7 +4,9 @@
 //! [`RenderConfig::run`](RenderConfig::run); you can also use the lower-level
 //! functions ([`render2d`](render2d()) and [`render3d`](render3d())) for manual
 //! control over the input tape.
-use crate::eval::{BulkEvaluator, Shape, Tape, Trace, TracingEvaluator};
+use crate::eval::{
+    BulkEvaluator, IntervalMode, Shape, Tape, Trace, TracingEvaluator,
+};
 use std::sync::Arc;
 
 mod config;
@@ -16,13 +18,16 @@ pub use render2d::render as render2d;
 pub use render3d::render as render3d;
 
 pub use render2d::{BitRenderMode, DebugRenderMode, RenderMode, SdfRenderMode};
+pub use render3d::ColorMode;
 
 struct RenderHandle<S: Shape> {
     shape: S,
 
     i_tape: Option<Arc<<S::IntervalEval as TracingEvaluator>::Tape>>,
+    a_tape: Option<Arc<<S::AffineEval as TracingEvaluator>::Tape>>,
     f_tape: Option<<S::FloatSliceEval as BulkEvaluator>::Tape>,
     g_tape: Option<<S::GradSliceEval as BulkEvaluator>::Tape>,
+    h_tape: Option<<S::HessianSliceEval as BulkEvaluator>::Tape>,
 
     next: Option<(S::Trace, Box<Self>)>,
 }
@@ -33,16 +38,41 @@ where
 {
     fn new(
         shape: S,
-        i_tape: Arc<<S::IntervalEval as TracingEvaluator>::Tape>,
+        i_tape: Option<Arc<<S::IntervalEval as TracingEvaluator>::Tape>>,
+        a_tape: Option<Arc<<S::AffineEval as TracingEvaluator>::Tape>>,
     ) -> Self {
         Self {
             shape,
-            i_tape: Some(i_tape),
+            i_tape,
+            a_tape,
             f_tape: None,
             g_tape: None,
+            h_tape: None,
             next: None,
         }
     }
+    /// Returns tapes for the selected interval mode
+    ///
+    /// Only one of the two tapes is built, so that it can be shared between
+    /// threads.
+    #[allow(clippy::type_complexity)]
+    fn root_tapes(
+        shape: &S,
+        mode: IntervalMode,
+    ) -> (
+        Option<Arc<<S::IntervalEval as TracingEvaluator>::Tape>>,
+        Option<Arc<<S::AffineEval as TracingEvaluator>::Tape>>,
+    ) {
+        match mode {
+            IntervalMode::Interval => (
+                Some(Arc::new(shape.interval_tape(Default::default()))),
+                None,
+            ),
+            IntervalMode::Affine => {
+                (None, Some(Arc::new(shape.affine_tape(Default::default()))))
+            }
+        }
+    }
     fn i_tape(
         &mut self,
         storage: &mut Vec<S::TapeStorage>,
@@ -53,6 +83,14 @@ where
             )
         })
     }
+    fn a_tape(
+        &mut self,
+        storage: &mut Vec<S::TapeStorage>,
+    ) -> &<S::AffineEval as TracingEvaluator>::Tape {
+        self.a_tape.get_or_insert_with(|| {
+            Arc::new(self.shape.affine_tape(storage.pop().unwrap_or_default()))
+        })
+    }
     fn f_tape(
         &mut self,
         storage: &mut Vec<S::TapeStorage>,
@@ -71,6 +109,15 @@ where
                 .grad_slice_tape(storage.pop().unwrap_or_default())
         })
     }
+    fn h_tape(
+        &mut self,
+        storage: &mut Vec<S::TapeStorage>,
+    ) -> &<S::HessianSliceEval as BulkEvaluator>::Tape {
+        self.h_tape.get_or_insert_with(|| {
+            self.shape
+                .hessian_slice_tape(storage.pop().unwrap_or_default())
+        })
+    }
     fn simplify(
         &mut self,
         trace: &S::Trace,
@@ -115,8 +162,10 @@ where
                     Box::new(RenderHandle {
                         shape: next,
                         i_tape: None,
+                        a_tape: None,
                         f_tape: None,
                         g_tape: None,
+                        h_tape: None,
                         next: None,
                     }),
                 ));
@@ -142,7 +191,13 @@ where
                 tape_storage.push(i_tape.recycle());
             }
         }
+        if let Some(a_tape) = self.a_tape.take() {
+            if let Ok(a_tape) = Arc::try_unwrap(a_tape) {
+                tape_storage.push(a_tape.recycle());
+            }
+        }
         tape_storage.extend(self.g_tape.map(Tape::recycle));
+        tape_storage.extend(self.h_tape.map(Tape::recycle));
         tape_storage.extend(self.f_tape.map(Tape::recycle));
 
         // Do this step last because the evaluators may borrow the shape
@@ -1,11 +1,12 @@
 //! 2D bitmap rendering / rasterization
 use super::RenderHandle;
 use crate::{
-    eval::{types::Interval, BulkEvaluator, Shape, TracingEvaluator},
+    eval::{
+        types::Interval, BulkEvaluator, IntervalMode, Shape, TracingEvaluator,
+    },
     render::config::{AlignedRenderConfig, Queue, RenderConfig, Tile},
 };
 use nalgebra::{Point2, Vector2};
-use std::sync::Arc;
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -175,6 +176,7 @@ struct Worker<'a, S: Shape, M: RenderMode> {
 
     eval_float_slice: S::FloatSliceEval,
     eval_interval: S::IntervalEval,
+    eval_affine: S::AffineEval,
 
     /// Spare tape storage for reuse
     tape_storage: Vec<S::TapeStorage>,
@@ -220,10 +222,31 @@ impl<S: Shape, M: RenderMode> Worker<'_, S, M> {
         let y = Interval::new(y_min, y_max);
         let z = Interval::new(0.0, 0.0);
 
-        let (i, simplify) = self
-            .eval_interval
-            .eval(shape.i_tape(&mut self.tape_storage), x, y, z, &[])
-            .unwrap();
+        let (i, simplify) = match self.config.interval_mode {
+            IntervalMode::Interval => self
+                .eval_interval
+                .eval(
+                    shape.i_tape(&mut self.tape_storage),
+                    x,
+                    y,
+                    z,
+                    &self.config.vars,
+                )
+                .unwrap(),
+            IntervalMode::Affine => {
+                let (a, simplify) = self
+                    .eval_affine
+                    .eval(
+                        shape.a_tape(&mut self.tape_storage),
+                        x,
+                        y,
+                        z,
+                        &self.config.vars,
+                    )
+                    .unwrap();
+                (a.interval(), simplify)
+            }
+        };
 
         let fill = mode.interval(i, depth);
 
@@ -293,7 +316,7 @@ impl<S: Shape, M: RenderMode> Worker<'_, S, M> {
                 &self.scratch.x,
                 &self.scratch.y,
                 &self.scratch.z,
-                &[],
+                &self.config.vars,
             )
             .unwrap();
 
@@ -301,7 +324,7 @@ impl<S: Shape, M: RenderMode> Worker<'_, S, M> {
         for j in 0..tile_size {
             let o = self.config.tile_to_offset(tile, 0, j);
             for i in 0..tile_size {
-                self.image[o + i] = mode.pixel(out[index]);
+                self.image[o + i] = mode.pixel(out[0][index]);
                 index += 1;
             }
         }
@@ -325,6 +348,7 @@ fn worker<S: Shape, M: RenderMode>(
         config,
         eval_float_slice: S::FloatSliceEval::new(),
         eval_interval: S::IntervalEval::new(),
+        eval_affine: S::AffineEval::new(),
         tape_storage: vec![],
         shape_storage: vec![],
         workspace: Default::default(),
@@ -349,6 +373,10 @@ fn worker<S: Shape, M: RenderMode>(
 /// This function is parameterized by both shape type (which determines how we
 /// perform evaluation) and render mode (which tells us how to color in the
 /// resulting pixels).
+///
+/// # Panics
+/// If `config.vars` doesn't match the shape's variable count; use
+/// [`RenderConfig::run`] to get an error instead.
 pub fn render<S: Shape, M: RenderMode + Sync>(
     shape: S,
     config: &RenderConfig<2>,
@@ -370,12 +398,17 @@ pub fn render<S: Shape, M: RenderMode + Sync>(
         }
     }
 
-    let i_tape = Arc::new(shape.interval_tape(Default::default()));
+    let (i_tape, a_tape) =
+        RenderHandle::root_tapes(&shape, config.interval_mode);
     let queue = Queue::new(tiles);
     let out = std::thread::scope(|s| {
         let mut handles = vec![];
         for _ in 0..config.threads {
-            let shape = RenderHandle::new(shape.clone(), i_tape.clone());
+            let shape = RenderHandle::new(
+                shape.clone(),
+                i_tape.clone(),
+                a_tape.clone(),
+            );
             handles
                 .push(s.spawn(|| worker::<S, M>(shape, &queue, &config, mode)));
         }
@@ -410,7 +443,7 @@ pub fn render<S: Shape, M: RenderMode + Sync>(
 mod test {
     use super::*;
     use crate::{
-        eval::{MathShape, Shape},
+        eval::{MathShape, Shape, ShapeVars, Vars},
         vm::{GenericVmShape, VmShape},
         Context,
     };
@@ -423,8 +456,19 @@ mod test {
     ));
 
     fn render_and_compare<S: Shape>(shape: S, expected: &'static str) {
+        for interval_mode in [IntervalMode::Interval, IntervalMode::Affine] {
+            render_and_compare_with(shape.clone(), interval_mode, expected);
+        }
+    }
+
+    fn render_and_compare_with<S: Shape>(
+        shape: S,
+        interval_mode: IntervalMode,
+        expected: &'static str,
+    ) {
         let cfg = RenderConfig::<2> {
             image_size: 32,
+            interval_mode,
             ..RenderConfig::default()
         };
         let out = cfg.run(shape, &BitRenderMode).unwrap();
@@ -436,7 +480,7 @@ mod test {
             img_str.push(if *b { 'X' } else { '.' });
         }
         if img_str != expected {
-            println!("image mismatch detected!");
+            println!("image mismatch detected ({interval_mode:?})!");
             println!("Expected:\n{expected}\nGot:\n{img_str}");
             println!("Diff:");
             for (a, b) in img_str.chars().zip(expected.chars()) {
@@ -524,6 +568,71 @@ mod test {
         render_and_compare(shape, EXPECTED);
     }
 
+    fn check_circle_vars<S: Shape + MathShape + ShapeVars>() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let r = ctx.var("r").unwrap();
+        let x2 = ctx.square(x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let sum = ctx.add(x2, y2).unwrap();
+        let dist = ctx.sqrt(sum).unwrap();
+        let circle = ctx.sub(dist, r).unwrap();
+        let shape = S::new(&ctx, circle).unwrap();
+        let mut vars = Vars::new(shape.vars());
+
+        for radius in [0.25, 0.5, 0.9] {
+            let mut ctx = Context::new();
+            let x = ctx.x();
+            let y = ctx.y();
+            let x2 = ctx.square(x).unwrap();
+            let y2 = ctx.square(y).unwrap();
+            let sum = ctx.add(x2, y2).unwrap();
+            let dist = ctx.sqrt(sum).unwrap();
+            let circle = ctx.sub(dist, radius).unwrap();
+            let expected = S::new(&ctx, circle).unwrap();
+
+            for interval_mode in [IntervalMode::Interval, IntervalMode::Affine]
+            {
+                let cfg = RenderConfig::<2> {
+                    image_size: 32,
+                    interval_mode,
+                    vars: vars.bind([("r", radius)].into_iter()),
+                    ..RenderConfig::default()
+                };
+                let out = cfg.run(shape.clone(), &BitRenderMode).unwrap();
+                let cfg = RenderConfig { vars: &[], ..cfg };
+                let cmp = cfg.run(expected.clone(), &BitRenderMode).unwrap();
+                assert_eq!(out, cmp, "mismatch at radius {radius}");
+                assert!(out.iter().any(|b| *b));
+            }
+        }
+
+        // A mismatched variable slice is an error, rather than a panic
+        for (v, n) in [(&[][..], 0), (&[0.5, 0.5][..], 2)] {
+            let cfg = RenderConfig::<2> {
+                image_size: 32,
+                vars: v,
+                ..RenderConfig::default()
+            };
+            let r = cfg.run(shape.clone(), &BitRenderMode);
+            assert!(
+                matches!(r, Err(crate::Error::BadVarSlice(a, 1)) if a == n)
+            );
+        }
+    }
+
+    #[test]
+    fn render_circle_vars_vm() {
+        check_circle_vars::<VmShape>();
+    }
+
+    #[cfg(feature = "jit")]
+    #[test]
+    fn render_circle_vars_jit() {
+        check_circle_vars::<crate::jit::JitShape>();
+    }
+
     #[test]
     fn render_hi_vm() {
         check_hi::<VmShape>();
@@ -1,12 +1,52 @@
 //! 3D bitmap rendering / rasterization
 use super::RenderHandle;
 use crate::{
-    eval::{types::Interval, BulkEvaluator, Shape, TracingEvaluator},
+    eval::{
+        types::Interval, BulkEvaluator, IntervalMode, Shape, TracingEvaluator,
+    },
     render::config::{AlignedRenderConfig, Queue, RenderConfig, Tile},
 };
 
 use nalgebra::{Point3, Vector3};
-use std::{collections::HashMap, sync::Arc};
+use std::collections::HashMap;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Selects how surface pixels are colored in a 3D render
+///
+/// Curvatures are computed with [`Shape::HessianSliceEval`] and drawn on a
+/// diverging color ramp: positive values are red, negative values are blue, and
+/// zero is white.  Pixels where the curvature is undefined (e.g. due to a zero
+/// gradient) are green.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Color by the (absolute) surface normal
+    #[default]
+    Normals,
+    /// Color by mean curvature, which is positive in convex regions and
+    /// negative in concave regions (e.g. fillets along inside corners)
+    MeanCurvature,
+    /// Color by Gaussian curvature, which is positive at dome-like points,
+    /// negative at saddle points, and zero on flat or cylindrical regions
+    GaussianCurvature,
+}
+
+/// Maps a curvature value to a color
+///
+/// The ramp saturates as `|k|` grows, reaching half saturation at `|k| = 1`
+/// (in model units).
+fn curvature_color(k: f32) -> [u8; 3] {
+    if k.is_nan() {
+        return [0, 255, 0];
+    }
+    let t = k.atan() * std::f32::consts::FRAC_2_PI;
+    let c = (255.0 * (1.0 - t.abs())) as u8;
+    if t >= 0.0 {
+        [255, c, c]
+    } else {
+        [c, c, 255]
+    }
+}
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -43,7 +83,9 @@ struct Worker<'a, S: Shape> {
 
     eval_float_slice: S::FloatSliceEval,
     eval_grad_slice: S::GradSliceEval,
+    eval_hessian_slice: S::HessianSliceEval,
     eval_interval: S::IntervalEval,
+    eval_affine: S::AffineEval,
 
     tape_storage: Vec<S::TapeStorage>,
     shape_storage: Vec<S::Storage>,
@@ -99,10 +141,31 @@ impl<S: Shape> Worker<'_, S> {
         let y = Interval::new(y_min, y_max);
         let z = Interval::new(z_min, z_max);
 
-        let (i, trace) = self
-            .eval_interval
-            .eval(shape.i_tape(&mut self.tape_storage), x, y, z, &[])
-            .unwrap();
+        let (i, trace) = match self.config.interval_mode {
+            IntervalMode::Interval => self
+                .eval_interval
+                .eval(
+                    shape.i_tape(&mut self.tape_storage),
+                    x,
+                    y,
+                    z,
+                    &self.config.vars,
+                )
+                .unwrap(),
+            IntervalMode::Affine => {
+                let (a, trace) = self
+                    .eval_affine
+                    .eval(
+                        shape.a_tape(&mut self.tape_storage),
+                        x,
+                        y,
+                        z,
+                        &self.config.vars,
+                    )
+                    .unwrap();
+                (a.interval(), trace)
+            }
+        };
 
         // Return early if this tile is completely empty or full, returning
         // `data_interval` to scratch memory for reuse.
@@ -216,7 +279,7 @@ impl<S: Shape> Worker<'_, S> {
                 &self.scratch.x[..index],
                 &self.scratch.y[..index],
                 &self.scratch.z[..index],
-                &[],
+                &self.config.vars,
             )
             .unwrap();
 
@@ -224,7 +287,7 @@ impl<S: Shape> Worker<'_, S> {
         // - col refers to the xy position in the tile
         // - grad refers to points that we must do gradient evaluation on
         let mut grad = 0;
-        let mut depth = out.chunks(tile_size);
+        let mut depth = out[0].chunks(tile_size);
         for col in 0..self.scratch.columns.len() {
             // Find the first set pixel in the column
             let depth = depth.next().unwrap();
@@ -269,20 +332,52 @@ impl<S: Shape> Worker<'_, S> {
             grad += 1;
         }
 
-        if grad > 0 {
-            let out = self
-                .eval_grad_slice
-                .eval(
-                    shape.g_tape(&mut self.tape_storage),
-                    &self.scratch.x[..grad],
-                    &self.scratch.y[..grad],
-                    &self.scratch.z[..grad],
-                    &[],
-                )
-                .unwrap();
-
-            for (index, o) in self.scratch.columns[0..grad].iter().enumerate() {
-                self.color[*o] = out[index].to_rgb().unwrap_or([255, 0, 0]);
+        if grad == 0 {
+            return;
+        }
+        match self.config.color_mode {
+            ColorMode::Normals => {
+                let out = self
+                    .eval_grad_slice
+                    .eval(
+                        shape.g_tape(&mut self.tape_storage),
+                        &self.scratch.x[..grad],
+                        &self.scratch.y[..grad],
+                        &self.scratch.z[..grad],
+                        &self.config.vars,
+                    )
+                    .unwrap();
+
+                for (index, o) in
+                    self.scratch.columns[0..grad].iter().enumerate()
+                {
+                    self.color[*o] =
+                        out[0][index].to_rgb().unwrap_or([255, 0, 0]);
+                }
+            }
+            mode
+            @ (ColorMode::MeanCurvature | ColorMode::GaussianCurvature) => {
+                let out = self
+                    .eval_hessian_slice
+                    .eval(
+                        shape.h_tape(&mut self.tape_storage),
+                        &self.scratch.x[..grad],
+                        &self.scratch.y[..grad],
+                        &self.scratch.z[..grad],
+                        &self.config.vars,
+                    )
+                    .unwrap();
+
+                for (index, o) in
+                    self.scratch.columns[0..grad].iter().enumerate()
+                {
+                    let k = if mode == ColorMode::MeanCurvature {
+                        out[0][index].mean_curvature()
+                    } else {
+                        out[0][index].gaussian_curvature()
+                    };
+                    self.color[*o] = curvature_color(k);
+                }
             }
         }
     }
@@ -326,7 +421,9 @@ fn worker<S: Shape>(
 
         eval_float_slice: S::FloatSliceEval::new(),
         eval_interval: S::IntervalEval::new(),
+        eval_affine: S::AffineEval::new(),
         eval_grad_slice: S::GradSliceEval::new(),
+        eval_hessian_slice: S::HessianSliceEval::new(),
 
         tape_storage: vec![],
         shape_storage: vec![],
@@ -377,6 +474,10 @@ fn worker<S: Shape>(
 ///
 /// This function is parameterized by shape type, which determines how we
 /// perform evaluation.
+///
+/// # Panics
+/// If `config.vars` doesn't match the shape's variable count; use
+/// [`RenderConfig::run`] to get an error instead.
 pub fn render<S: Shape>(
     shape: S,
     config: &RenderConfig<3>,
@@ -405,11 +506,12 @@ pub fn render<S: Shape>(
         tile_queues.push(Queue::new(ts.to_vec()));
     }
 
-    let i_tape = Arc::new(shape.interval_tape(Default::default()));
+    let (i_tape, a_tape) =
+        RenderHandle::root_tapes(&shape, config.interval_mode);
 
     // Special-case for single-threaded operation, to give simpler backtraces
     let out = if config.threads == 1 {
-        let shape = RenderHandle::new(shape, i_tape);
+        let shape = RenderHandle::new(shape, i_tape, a_tape);
         worker::<S>(shape, tile_queues.as_slice(), 0, &config)
             .into_iter()
             .collect()
@@ -418,8 +520,14 @@ pub fn render<S: Shape>(
         std::thread::scope(|s| {
             let mut handles = vec![];
             let queues = tile_queues.as_slice();
-            for i in 0..config.threads {
-                let handle = RenderHandle::new(shape.clone(), i_tape.clone());
+            // Small images may have fewer queues than threads; workers steal
+            // from every queue, so there's no point in spawning extras.
+            for i in 0..config.threads.min(queues.len()) {
+                let handle = RenderHandle::new(
+                    shape.clone(),
+                    i_tape.clone(),
+                    a_tape.clone(),
+                );
                 handles.push(
                     s.spawn(move || worker::<S>(handle, queues, i, config_ref)),
                 );
@@ -455,3 +563,118 @@ pub fn render<S: Shape>(
     }
     (image_depth, image_color)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::Context,
+        eval::{MathShape, ShapeVars, Vars},
+        vm::VmShape,
+    };
+
+    fn sphere(radius: f32) -> VmShape {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let x2 = ctx.square(x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let z2 = ctx.square(z).unwrap();
+        let r = ctx.add(x2, y2).unwrap();
+        let r = ctx.add(r, z2).unwrap();
+        let r = ctx.sqrt(r).unwrap();
+        let out = ctx.sub(r, radius).unwrap();
+        VmShape::new(&ctx, out).unwrap()
+    }
+
+    #[test]
+    fn test_sphere_curvature() {
+        let radius = 0.5;
+        for mode in [ColorMode::MeanCurvature, ColorMode::GaussianCurvature] {
+            let cfg = RenderConfig::<3> {
+                image_size: 64,
+                color_mode: mode,
+                ..RenderConfig::default()
+            };
+            let (depth, color) = render(sphere(radius), &cfg);
+
+            // Surface points are sampled one voxel above the surface, so the
+            // curvature is slightly lower than for the sphere itself.
+            let k = |r: f32| match mode {
+                ColorMode::MeanCurvature => 1.0 / r,
+                _ => 1.0 / r.powi(2),
+            };
+            let lo = curvature_color(k(radius));
+            let hi = curvature_color(k(radius + 0.1));
+            let mut filled = 0;
+            for (d, c) in depth.iter().zip(&color) {
+                if *d == 0 {
+                    continue;
+                }
+                filled += 1;
+                assert_eq!(c[0], 255, "bad color {c:?} in {mode:?}");
+                assert_eq!(c[1], c[2], "bad color {c:?} in {mode:?}");
+                assert!(
+                    c[1] >= lo[1] && c[1] <= hi[1],
+                    "bad color {c:?} in {mode:?} (expected {lo:?} - {hi:?})"
+                );
+            }
+            assert!(filled > 0);
+        }
+    }
+
+    #[test]
+    fn test_sphere_vars() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let r = ctx.var("r").unwrap();
+        let x2 = ctx.square(x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let z2 = ctx.square(z).unwrap();
+        let sum = ctx.add(x2, y2).unwrap();
+        let sum = ctx.add(sum, z2).unwrap();
+        let dist = ctx.sqrt(sum).unwrap();
+        let root = ctx.sub(dist, r).unwrap();
+        let shape = VmShape::new(&ctx, root).unwrap();
+        let mut vars = Vars::new(shape.vars());
+
+        for radius in [0.3, 0.7] {
+            let cfg = RenderConfig::<3> {
+                image_size: 64,
+                vars: vars.bind([("r", radius)].into_iter()),
+                ..RenderConfig::default()
+            };
+            let out = render(shape.clone(), &cfg);
+            let cfg = RenderConfig { vars: &[], ..cfg };
+            let cmp = render(sphere(radius), &cfg);
+            assert_eq!(out, cmp, "mismatch at radius {radius}");
+            assert!(out.0.iter().any(|d| *d > 0));
+        }
+
+        // A mismatched variable slice is an error, rather than a panic
+        for (v, n) in [(&[][..], 0), (&[0.5, 0.5][..], 2)] {
+            let cfg = RenderConfig::<3> {
+                image_size: 64,
+                vars: v,
+                ..RenderConfig::default()
+            };
+            assert!(matches!(
+                cfg.run(shape.clone()),
+                Err(crate::Error::BadVarSlice(a, 1)) if a == n
+            ));
+        }
+    }
+
+    #[test]
+    fn test_curvature_color() {
+        assert_eq!(curvature_color(0.0), [255, 255, 255]);
+        assert_eq!(curvature_color(f32::NAN), [0, 255, 0]);
+        assert_eq!(curvature_color(f32::INFINITY), [255, 0, 0]);
+        assert_eq!(curvature_color(f32::NEG_INFINITY), [0, 0, 255]);
+        let [r, g, b] = curvature_color(-1.0);
+        assert!(r == g && b == 255 && r > 100 && r < 150);
+    }
+}
@@ -165,6 +165,9 @@ fn render<S: fidget::eval::Shape>(
                 threads: 8,
 
                 mat,
+                interval_mode: Default::default(),
+                color_mode: Default::default(),
+                vars: &[],
             };
             match mode {
                 TwoDMode::Color => {
@@ -228,6 +231,9 @@ fn render<S: fidget::eval::Shape>(
                 threads: 8,
 
                 mat,
+                interval_mode: Default::default(),
+                color_mode: Default::default(),
+                vars: &[],
             };
             let (depth, color) = fidget::render::render3d(shape, &config);
             match mode {